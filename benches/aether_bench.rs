@@ -1,5 +1,9 @@
-use aether_link::AetherLinkKernel;
+use aether_link::bank::{KernelBank, MAX_KERNELS};
+use aether_link::stats_hub::StatsHub;
+use aether_link::{wrap_angle, AetherLinkKernel, KernelConfig, ShardedKernelPool};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
 fn bench_core_functions(c: &mut Criterion) {
     let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
@@ -12,6 +16,13 @@ fn bench_core_functions(c: &mut Criterion) {
         b.iter(|| kernel.process_io_cycle(black_box(&lba_stream)))
     });
 
+    // The decision core alone, from the span the full cycle derives.
+    let mut raw_kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
+    let span = (lba_stream[lba_stream.len() - 1] - lba_stream[0]) as f32;
+    group.bench_function("process_io_cycle_raw", |b| {
+        b.iter(|| raw_kernel.process_io_cycle_raw(black_box(span)))
+    });
+
     group.bench_function("extract_telemetry", |b| {
         b.iter(|| kernel.extract_telemetry(black_box(&lba_stream)))
     });
@@ -94,6 +105,276 @@ fn bench_throughput(c: &mut Criterion) {
     group.finish();
 }
 
+/// Identical pre-generated traces for every predictor.
+fn comparison_traces() -> Vec<(&'static str, Vec<u64>)> {
+    let mut rng = 0x9e37_79b9_7f4a_7c15u64;
+    let random = (0..4096)
+        .map(|_| {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            rng % 10_000_000
+        })
+        .collect();
+    // Runs of 16 sequential blocks separated by jumps.
+    let bursty = (0..4096u64).map(|i| (i / 16) * 5000 + i % 16).collect();
+    vec![
+        ("sequential", (0..4096).collect()),
+        ("random", random),
+        ("bursty", bursty),
+    ]
+}
+
+fn bench_prefetchers(c: &mut Criterion) {
+    use aether_link::baseline::{GhbPrefetcher, NextNPrefetcher, StridePrefetcher};
+    use aether_link::Prefetcher;
+
+    for (trace_name, lbas) in comparison_traces() {
+        let mut prefetchers: Vec<Box<dyn Prefetcher>> = vec![
+            Box::new(AetherLinkKernel::new_hft()),
+            Box::new(StridePrefetcher::default()),
+            Box::new(GhbPrefetcher::default()),
+            Box::new(NextNPrefetcher::default()),
+            Box::new(NextNPrefetcher::ramp_up(8)),
+        ];
+
+        let mut group = c.benchmark_group(format!("Prefetcher observe/{trace_name}"));
+        group.throughput(Throughput::Elements(lbas.len() as u64));
+        for p in prefetchers.iter_mut() {
+            let name = p.name().to_owned();
+            group.bench_function(name, |b| {
+                b.iter(|| {
+                    for &lba in &lbas {
+                        black_box(p.observe(black_box(lba)));
+                    }
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+/// What the real telemetry costs over the v0.1.0 path, which filled the
+/// variance, spectrum, history and context dimensions with constants.  The
+/// POVM step after encoding is identical in both, so it is left out.
+fn bench_telemetry_cost(c: &mut Criterion) {
+    let lba_stream: Vec<u64> = (0..16).map(|i| i * 8).collect();
+    let mut kernel = AetherLinkKernel::new_hft();
+
+    let mut group = c.benchmark_group("Telemetry cost");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("real features", |b| {
+        b.iter(|| {
+            let features = kernel.extract_telemetry(black_box(&lba_stream));
+            kernel.prepare_quantum_state(features)
+        })
+    });
+
+    group.bench_function("legacy constants", |b| {
+        b.iter(|| {
+            let s = black_box(&lba_stream);
+            let delta = (s[s.len() - 1] - s[0]) as f32;
+            kernel.prepare_quantum_state([delta, delta * 0.5, 0.1, 0.01, 0.8, 1.0])
+        })
+    });
+
+    group.finish();
+}
+
+/// Four threads deciding on private shards against four threads sharing
+/// one kernel behind a mutex.
+fn bench_sharded(c: &mut Criterion) {
+    const THREADS: u64 = 4;
+    const CYCLES: u64 = 10_000;
+
+    let mut group = c.benchmark_group("Sharded vs mutex");
+    group.throughput(Throughput::Elements(THREADS * CYCLES));
+
+    let pool = ShardedKernelPool::new(THREADS as usize, AetherLinkKernel::new_hft());
+    group.bench_function("sharded", |b| {
+        b.iter(|| {
+            std::thread::scope(|s| {
+                for t in 0..THREADS {
+                    let pool = &pool;
+                    s.spawn(move || {
+                        let mut shard = pool.shard(t as usize).unwrap();
+                        for i in 0..CYCLES {
+                            black_box(shard.push_lba(t << 32 | i));
+                        }
+                    });
+                }
+            })
+        })
+    });
+
+    let shared = Mutex::new(AetherLinkKernel::new_hft());
+    group.bench_function("mutex", |b| {
+        b.iter(|| {
+            std::thread::scope(|s| {
+                for t in 0..THREADS {
+                    let shared = &shared;
+                    s.spawn(move || {
+                        for i in 0..CYCLES {
+                            black_box(shared.lock().unwrap().push_lba(t << 32 | i));
+                        }
+                    });
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+/// One thread pinned to the first node driving a shard placed on its own
+/// node against one placed on another.  Machine-dependent: needs two nodes
+/// with CPUs and the `linux-io` feature, and is skipped otherwise.
+fn bench_numa_shards(c: &mut Criterion) {
+    use aether_link::numa::{pin_current_thread, NumaTopology};
+
+    const CYCLES: u64 = 10_000;
+
+    let Some(topology) = NumaTopology::detect() else {
+        return;
+    };
+    let nodes: Vec<usize> = topology
+        .nodes()
+        .iter()
+        .filter(|n| !n.cpus.is_empty())
+        .map(|n| n.id)
+        .collect();
+    if nodes.len() < 2 || !pin_current_thread(&topology, nodes[0]) {
+        return;
+    }
+
+    let pool = ShardedKernelPool::new(2, AetherLinkKernel::new_hft()).with_topology(topology);
+    let mut group = c.benchmark_group("NUMA shards");
+    group.throughput(Throughput::Elements(CYCLES));
+    for (name, idx) in [("local", 0), ("remote", 1)] {
+        let mut shard = pool.shard(idx).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for i in 0..CYCLES {
+                    black_box(shard.push_lba(i));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Four threads counting cycles into their own hub slots against four
+/// threads hammering one shared counter.
+fn bench_stats_hub(c: &mut Criterion) {
+    const THREADS: u64 = 4;
+    const CYCLES: u64 = 100_000;
+
+    let mut group = c.benchmark_group("Stats hub vs shared counter");
+    group.throughput(Throughput::Elements(THREADS * CYCLES));
+
+    let hub = StatsHub::new();
+    let handles: Vec<_> = (0..THREADS).map(|_| hub.register()).collect();
+    group.bench_function("per-thread slots", |b| {
+        b.iter(|| {
+            std::thread::scope(|s| {
+                for stats in &handles {
+                    s.spawn(move || {
+                        for i in 0..CYCLES {
+                            stats.record_cycle(black_box(i & 1 == 0));
+                        }
+                    });
+                }
+            });
+            black_box(hub.aggregate())
+        })
+    });
+
+    let shared = [AtomicU64::new(0), AtomicU64::new(0)];
+    group.bench_function("shared counter", |b| {
+        b.iter(|| {
+            std::thread::scope(|s| {
+                for _ in 0..THREADS {
+                    let shared = &shared;
+                    s.spawn(move || {
+                        for i in 0..CYCLES {
+                            shared[0].fetch_add(1, Ordering::Relaxed);
+                            if black_box(i & 1 == 0) {
+                                shared[1].fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                    });
+                }
+            });
+            black_box(shared[0].load(Ordering::Relaxed))
+        })
+    });
+
+    group.finish();
+}
+
+/// The φ update alone: the floating-point modulo it used to be against the
+/// add-and-select wrap it is now.
+fn bench_phi_update(c: &mut Criterion) {
+    let steps: Vec<f32> = (0..1024)
+        .map(|i| ((i * 37 % 200) as f32 / 100.0 - 1.0) * 0.2)
+        .collect();
+
+    let mut group = c.benchmark_group("Adaptation step");
+    group.throughput(Throughput::Elements(steps.len() as u64));
+
+    group.bench_function("rem_euclid", |b| {
+        b.iter(|| {
+            let mut phi = 0.1f32;
+            for &step in &steps {
+                phi = (phi + black_box(step)).rem_euclid(2.0 * std::f32::consts::PI);
+            }
+            phi
+        })
+    });
+
+    group.bench_function("wrap_angle", |b| {
+        b.iter(|| {
+            let mut phi = 0.1f32;
+            for &step in &steps {
+                phi = wrap_angle(phi, black_box(step));
+            }
+            phi
+        })
+    });
+
+    group.finish();
+}
+
+/// 64 kernels per tick: one structure-of-arrays bank against a loop over
+/// scalar kernels.  Throughput is per kernel decision.
+fn bench_kernel_bank(c: &mut Criterion) {
+    let configs = vec![KernelConfig::default(); MAX_KERNELS];
+    let spans: Vec<u64> = (0..MAX_KERNELS as u64).map(|k| k * 7 % 64).collect();
+    let spans_f32: Vec<f32> = spans.iter().map(|&s| s as f32).collect();
+
+    let mut group = c.benchmark_group("Kernel bank");
+    group.throughput(Throughput::Elements(MAX_KERNELS as u64));
+
+    let mut bank = KernelBank::new(&configs);
+    group.bench_function("bank", |b| {
+        b.iter(|| bank.process_all(black_box(&spans_f32)))
+    });
+
+    let mut kernels: Vec<AetherLinkKernel> = configs.iter().map(|c| c.build().unwrap()).collect();
+    group.bench_function("scalar loop", |b| {
+        b.iter(|| {
+            let mut mask = 0u64;
+            for (k, kernel) in kernels.iter_mut().enumerate() {
+                mask |= (kernel.process_io_cycle(black_box(&[0, spans[k]])) as u64) << k;
+            }
+            mask
+        })
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_core_functions,
@@ -101,6 +382,13 @@ criterion_group!(
     bench_stream_sizes,
     bench_fast_math,
     bench_throughput,
+    bench_prefetchers,
+    bench_telemetry_cost,
+    bench_sharded,
+    bench_numa_shards,
+    bench_stats_hub,
+    bench_phi_update,
+    bench_kernel_bank,
 );
 
 criterion_main!(benches);
@@ -0,0 +1,290 @@
+//! Instruction-count benchmarks under Cachegrind.
+//!
+//! Wall-clock numbers on shared CI runners move by more than a 2 ns
+//! regression in an 18 ns function.  Instruction counts do not: this
+//! harness re-runs itself under `valgrind --tool=cachegrind` once per
+//! benchmark, with a fixed simulated cache so the L1/LL/RAM split is
+//! reproducible too, and compares the instruction count per call against
+//! `benches/iai_baseline.txt`.
+//!
+//! Each benchmark runs twice, with `ITERS` calls and with none; both runs
+//! do the same setup (kernel construction, process start-up), so the
+//! difference divided by `ITERS` is the cost of one call alone.  Inputs are
+//! constant arrays and the measured loops do not allocate.
+//!
+//! ```bash
+//! cargo bench --features iai --bench iai                       # check
+//! AETHER_IAI_BLESS=1 cargo bench --features iai --bench iai    # re-record
+//! ```
+//!
+//! A count more than `AETHER_IAI_TOLERANCE` percent (default 1) above its
+//! baseline fails the run, and so does a missing baseline file or entry
+//! unless re-recording.  Without valgrind the harness prints a note and
+//! exits successfully.
+
+use aether_link::{fast_atan, fast_exp, fast_sigmoid, wrap_angle, AetherLinkKernel};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::{env, fs};
+
+/// Calls per measured run.
+const ITERS: u64 = 1_000;
+
+/// Same LBA window as the criterion `AETHER Core` group.
+const STREAM: [u64; 7] = [100, 101, 102, 105, 110, 200, 205];
+
+/// Arguments for the fast-math functions, cycled through by the loops.
+const INPUTS: [f32; 8] = [-12.0, -1.5, -0.3, 0.0, 0.25, 0.9, 3.0, 40.0];
+
+/// Cache geometry handed to Cachegrind, so counts do not depend on the host.
+const CACHE_ARGS: [&str; 3] = ["--I1=32768,8,64", "--D1=32768,8,64", "--LL=8388608,16,64"];
+
+const BASELINE: &str = "benches/iai_baseline.txt";
+
+fn process_io_cycle(iters: u64) {
+    let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
+    for _ in 0..iters {
+        black_box(kernel.process_io_cycle(black_box(&STREAM)));
+    }
+}
+
+fn extract_telemetry(iters: u64) {
+    let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
+    for _ in 0..iters {
+        black_box(kernel.extract_telemetry(black_box(&STREAM)));
+    }
+}
+
+fn prepare_quantum_state(iters: u64) {
+    let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
+    let features = kernel.extract_telemetry(&STREAM);
+    for _ in 0..iters {
+        black_box(kernel.prepare_quantum_state(black_box(features)));
+    }
+}
+
+fn fast_math_loop(iters: u64, f: fn(f32) -> f32) {
+    for i in 0..iters {
+        black_box(f(black_box(INPUTS[i as usize % INPUTS.len()])));
+    }
+}
+
+fn bench_fast_atan(iters: u64) {
+    fast_math_loop(iters, fast_atan);
+}
+
+fn bench_fast_exp(iters: u64) {
+    fast_math_loop(iters, fast_exp);
+}
+
+fn bench_fast_sigmoid(iters: u64) {
+    fast_math_loop(iters, fast_sigmoid);
+}
+
+fn bench_wrap_angle(iters: u64) {
+    fast_math_loop(iters, |x| wrap_angle(3.0, x));
+}
+
+/// Name and body of one benchmark; the body makes the given number of calls.
+type Bench = (&'static str, fn(u64));
+
+const BENCHES: [Bench; 7] = [
+    ("process_io_cycle", process_io_cycle),
+    ("extract_telemetry", extract_telemetry),
+    ("prepare_quantum_state", prepare_quantum_state),
+    ("fast_atan", bench_fast_atan),
+    ("fast_exp", bench_fast_exp),
+    ("fast_sigmoid", bench_fast_sigmoid),
+    ("wrap_angle", bench_wrap_angle),
+];
+
+/// Event totals from one Cachegrind run.
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    instructions: u64,
+    l1_hits: u64,
+    ll_hits: u64,
+    ram_hits: u64,
+}
+
+impl Counts {
+    /// Parses the `events:` and `summary:` lines of a Cachegrind output file.
+    fn parse(out: &str) -> Option<Self> {
+        let field = |prefix: &str| {
+            out.lines()
+                .find_map(|l| l.strip_prefix(prefix))
+                .map(|rest| rest.split_whitespace().collect::<Vec<_>>())
+        };
+        let events = field("events:")?;
+        let values = field("summary:")?;
+        let get = |name: &str| -> u64 {
+            events
+                .iter()
+                .position(|&e| e == name)
+                .and_then(|i| values.get(i)?.parse().ok())
+                .unwrap_or(0)
+        };
+        let accesses = get("Ir") + get("Dr") + get("Dw");
+        let l1_misses = get("I1mr") + get("D1mr") + get("D1mw");
+        let ram_hits = get("ILmr") + get("DLmr") + get("DLmw");
+        Some(Self {
+            instructions: get("Ir"),
+            l1_hits: accesses - l1_misses,
+            ll_hits: l1_misses - ram_hits,
+            ram_hits,
+        })
+    }
+
+    fn minus(self, setup: Self) -> Self {
+        Self {
+            instructions: self.instructions.saturating_sub(setup.instructions),
+            l1_hits: self.l1_hits.saturating_sub(setup.l1_hits),
+            ll_hits: self.ll_hits.saturating_sub(setup.ll_hits),
+            ram_hits: self.ram_hits.saturating_sub(setup.ram_hits),
+        }
+    }
+
+    /// Rough cycle estimate with the usual 1/5/35 latency weights.
+    fn estimated_cycles(self) -> u64 {
+        self.l1_hits + 5 * self.ll_hits + 35 * self.ram_hits
+    }
+}
+
+fn per_call(total: u64) -> f64 {
+    total as f64 / ITERS as f64
+}
+
+/// Runs benchmark `index` with `iters` calls under Cachegrind.
+fn cachegrind(exe: &Path, out_dir: &Path, index: usize, iters: u64) -> Result<Counts, String> {
+    let out_file = out_dir.join(format!("cachegrind.out.{index}.{iters}"));
+    let status = Command::new("valgrind")
+        .arg("--tool=cachegrind")
+        .arg("--cache-sim=yes")
+        .args(CACHE_ARGS)
+        .arg(format!("--cachegrind-out-file={}", out_file.display()))
+        .arg(exe)
+        .args(["--iai-run", &index.to_string(), &iters.to_string()])
+        .output()
+        .map_err(|e| format!("failed to run valgrind: {e}"))?;
+    if !status.status.success() {
+        return Err(format!(
+            "cachegrind failed for {}:\n{}",
+            BENCHES[index].0,
+            String::from_utf8_lossy(&status.stderr)
+        ));
+    }
+    let out = fs::read_to_string(&out_file).map_err(|e| format!("{}: {e}", out_file.display()))?;
+    Counts::parse(&out).ok_or_else(|| format!("{}: no summary line", out_file.display()))
+}
+
+fn load_baseline(path: &Path) -> std::io::Result<BTreeMap<String, u64>> {
+    let text = fs::read_to_string(path)?;
+    Ok(text
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let (name, count) = l.split_once(char::is_whitespace)?;
+            Some((name.to_string(), count.trim().parse().ok()?))
+        })
+        .collect())
+}
+
+fn write_baseline(path: &Path, measured: &[(&str, Counts)]) -> std::io::Result<()> {
+    let mut text = format!(
+        "# Instructions for {ITERS} calls, setup subtracted.\n\
+         # Re-record with AETHER_IAI_BLESS=1 cargo bench --features iai --bench iai\n"
+    );
+    for (name, counts) in measured {
+        text += &format!("{name} {}\n", counts.instructions);
+    }
+    fs::write(path, text)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--iai-run") {
+        let index: usize = args[i + 1].parse().expect("benchmark index");
+        let iters: u64 = args[i + 2].parse().expect("iteration count");
+        BENCHES[index].1(iters);
+        return ExitCode::SUCCESS;
+    }
+
+    if Command::new("valgrind").arg("--version").output().is_err() {
+        println!("valgrind not found; skipping instruction-count benchmarks");
+        return ExitCode::SUCCESS;
+    }
+
+    let exe = env::current_exe().expect("benchmark executable");
+    let out_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/iai");
+    fs::create_dir_all(&out_dir).expect("create target/iai");
+    let baseline_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(BASELINE);
+    let bless = env::var_os("AETHER_IAI_BLESS").is_some();
+    let baseline = match load_baseline(&baseline_path) {
+        Ok(baseline) => baseline,
+        Err(_) if bless => BTreeMap::new(),
+        Err(e) => {
+            eprintln!("{BASELINE}: {e}; record it with AETHER_IAI_BLESS=1");
+            return ExitCode::FAILURE;
+        }
+    };
+    let tolerance: f64 = env::var("AETHER_IAI_TOLERANCE")
+        .ok()
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(1.0);
+
+    let mut measured = Vec::new();
+    let mut regressions = Vec::new();
+    for (index, &(name, _)) in BENCHES.iter().enumerate() {
+        let run = |iters| match cachegrind(&exe, &out_dir, index, iters) {
+            Ok(counts) => counts,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        };
+        let counts = run(ITERS).minus(run(0));
+
+        println!("{name}");
+        println!(
+            "  Instructions:     {:>10.1}",
+            per_call(counts.instructions)
+        );
+        println!("  L1 Accesses:      {:>10.1}", per_call(counts.l1_hits));
+        println!("  L2 Accesses:      {:>10.1}", per_call(counts.ll_hits));
+        println!("  RAM Accesses:     {:>10.1}", per_call(counts.ram_hits));
+        println!(
+            "  Estimated Cycles: {:>10.1}",
+            per_call(counts.estimated_cycles())
+        );
+        match baseline.get(name) {
+            Some(&base) => {
+                let change = (counts.instructions as f64 / base as f64 - 1.0) * 100.0;
+                println!("  vs baseline:      {change:>+9.2}%");
+                if change > tolerance {
+                    regressions.push(format!("{name}: {change:+.2}% instructions"));
+                }
+            }
+            None => {
+                println!("  vs baseline:      (none)");
+                regressions.push(format!("{name}: no baseline entry"));
+            }
+        }
+        measured.push((name, counts));
+    }
+
+    if bless {
+        write_baseline(&baseline_path, &measured).expect("write baseline");
+        println!("baseline written to {BASELINE}");
+        return ExitCode::SUCCESS;
+    }
+    if regressions.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    eprintln!("instruction count regressions beyond {tolerance}% or missing baselines:");
+    for r in &regressions {
+        eprintln!("  {r}");
+    }
+    ExitCode::FAILURE
+}
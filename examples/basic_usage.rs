@@ -15,10 +15,7 @@ fn main() {
     // Initialize with default parameters
     let mut kernel = AetherLinkKernel::default();
     println!("📊 Kernel initialized with default parameters:");
-    println!("   ε (epsilon): {:.3}", kernel.epsilon);
-    println!("   φ (phi):     {:.3}", kernel.phi);
-    println!("   λ (lambda):  {:?}", kernel.lambda);
-    println!("   bias:        {:.3}", kernel.bias);
+    println!("{}", kernel.report());
     println!();
 
     // Simulate an LBA stream (Logical Block Addresses)
@@ -67,9 +64,7 @@ fn main() {
         kernel.process_io_cycle(&dynamic_stream);
     }
 
-    println!("   Cycles processed: {}", kernel.cycles);
-    println!("   Prefetches triggered: {}", kernel.prefetches);
-    println!("   Prefetch ratio: {:.1}%", kernel.prefetch_ratio() * 100.0);
+    println!("{}", kernel.report());
     println!();
 
     // Compare presets
@@ -77,13 +72,11 @@ fn main() {
     println!("📋 Preset Comparison:");
     println!();
 
-    let hft = AetherLinkKernel::new_hft();
     println!("   🏦 HFT Preset (Conservative):");
-    println!("      ε = {:.3}, φ = {:.3}", hft.epsilon, hft.phi);
+    println!("      {}", AetherLinkKernel::new_hft());
 
-    let gaming = AetherLinkKernel::new_gaming();
     println!("   🎮 Gaming Preset (Aggressive):");
-    println!("      ε = {:.3}, φ = {:.3}", gaming.epsilon, gaming.phi);
+    println!("      {}", AetherLinkKernel::new_gaming());
     println!();
 
     println!("✅ Demo complete! Run `cargo bench` for performance metrics.");
@@ -0,0 +1,43 @@
+//! Prefetch effectiveness of AETHER presets against the classic baselines.
+//!
+//! Replays one synthetic trace — sequential scans, strided reads and random
+//! lookups interleaved in phases — through every predictor with identical
+//! caches and prints the ranked comparison.
+//!
+//! Run with: cargo run --release --example compare
+
+use aether_link::baseline::{GhbPrefetcher, NextNPrefetcher, StridePrefetcher};
+use aether_link::eval::{self, Metric};
+use aether_link::trace::IoEvent;
+use aether_link::workload::Pattern;
+use aether_link::{AetherLinkKernel, Named, Prefetcher};
+
+fn trace() -> Vec<IoEvent> {
+    let mut events = Vec::new();
+    for phase in 0..30u64 {
+        let (pattern, len) = match phase % 3 {
+            0 => (Pattern::SEQUENTIAL, 500),
+            1 => (Pattern::Sequential { stride: 4 }, 500),
+            _ => (Pattern::Random { span: 10_000_000 }, 200),
+        };
+        let lbas = pattern.workload(phase).starting_at(phase * 1_000_000);
+        events.extend(lbas.take(len).map(IoEvent::read));
+    }
+    events
+}
+
+fn main() {
+    let trace = trace();
+    let lineup: Vec<Box<dyn Prefetcher>> = vec![
+        Box::new(Named::new("aether-hft", AetherLinkKernel::new_hft())),
+        Box::new(Named::new("aether-gaming", AetherLinkKernel::new_gaming())),
+        Box::new(StridePrefetcher::default()),
+        Box::new(GhbPrefetcher::default()),
+        Box::new(NextNPrefetcher::default()),
+        Box::new(NextNPrefetcher::ramp_up(8)),
+    ];
+    println!("{} events\n", trace.len());
+    let report = eval::compare(&trace, lineup, 4096);
+    println!("{report}\n");
+    println!("{}", report.ranked_by(Metric::Accuracy));
+}
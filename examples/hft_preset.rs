@@ -21,13 +21,7 @@ fn main() {
     // HFT preset: aggressive epsilon (lower = more aggressive prefetch)
     let mut kernel = AetherLinkKernel::new_hft();
     println!("📊 HFT Kernel parameters:");
-    println!("   ε (epsilon): {:.4}", kernel.epsilon);
-    println!("   φ (phi):     {:.6}", kernel.phi);
-    println!(
-        "   λ (lambda):  [{:.3}, {:.3}, {:.3}]",
-        kernel.lambda[0], kernel.lambda[1], kernel.lambda[2]
-    );
-    println!("   bias:        {:.4}", kernel.bias);
+    println!("{}", kernel.report());
     println!();
 
     // Simulate HFT market data stream: mostly sequential with occasional jumps
@@ -43,7 +37,6 @@ fn main() {
     println!("📥 Processing {} market data LBAs...", market_stream.len());
     println!();
 
-    let mut prefetches = 0u64;
     let chunk_size = 5;
 
     for (i, chunk) in market_stream.chunks(chunk_size).enumerate() {
@@ -51,7 +44,6 @@ fn main() {
         let should_prefetch = kernel.process_io_cycle(&lba_slice);
 
         if should_prefetch {
-            prefetches += 1;
             println!(
                 "  cycle {:3}: LBAs {:?}: ⚡ PREFETCH",
                 i,
@@ -68,12 +60,7 @@ fn main() {
 
     println!();
     println!("📈 HFT Statistics:");
-    println!("   Total cycles:    {}", market_stream.len() / chunk_size);
-    println!("   Prefetch events: {}", prefetches);
-    println!(
-        "   Prefetch ratio: {:.1}%",
-        100.0 * prefetches as f32 / (market_stream.len() / chunk_size) as f32
-    );
+    println!("{}", kernel.report());
     println!();
 
     // HFT-specific: verify jitter characteristics
@@ -0,0 +1,108 @@
+//! Watch the kernel decide on live block I/O.
+//!
+//! Streams `block:block_rq_issue` events from tracefs, runs one kernel per
+//! device over a sliding window of request sectors, and prints a summary
+//! line per device once a second.  Needs root (or the capabilities tracefs
+//! requires on your kernel).
+//!
+//! Run with: sudo cargo run --features linux-io --example live_capture [-- SECONDS]
+
+#[cfg(target_os = "linux")]
+fn main() {
+    use aether_link::linux::BlockTracer;
+    use aether_link::KernelPool;
+    use std::collections::VecDeque;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const WINDOW: usize = 16;
+
+    let seconds: Option<u64> = std::env::args().nth(1).and_then(|s| s.parse().ok());
+
+    let (tracer, mut reader) = match BlockTracer::open().and_then(|t| {
+        let r = t.events()?;
+        Ok((t, r))
+    }) {
+        Ok(pair) => pair,
+        Err(e) => {
+            eprintln!("error: cannot capture block events: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // `trace_pipe` reads block while the disk is idle, so read on a
+    // separate thread and report from this one on a timer.
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Some(ev)) = reader.next_event() {
+            if tx.send(ev).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut pool: KernelPool<VecDeque<u64>> = KernelPool::default();
+    let mut per_second: Vec<(u64, u64, u64)> = Vec::new();
+    let start = Instant::now();
+    let mut next_report = start + Duration::from_secs(1);
+
+    println!("capturing block_rq_issue — Ctrl-C to stop");
+    loop {
+        let timeout = next_report.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(ev) => {
+                let dev = ev.device();
+                let entry = pool.entry(dev);
+                let window = &mut entry.state;
+                if window.len() == WINDOW {
+                    window.pop_front();
+                }
+                window.push_back(ev.sector);
+                let fired =
+                    window.len() >= 2 && entry.kernel.process_io_cycle(window.make_contiguous());
+                match per_second.iter_mut().find(|(d, _, _)| *d == dev) {
+                    Some((_, events, triggers)) => {
+                        *events += 1;
+                        *triggers += u64::from(fired);
+                    }
+                    None => per_second.push((dev, 1, u64::from(fired))),
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                eprintln!("trace_pipe closed");
+                break;
+            }
+        }
+
+        if Instant::now() >= next_report {
+            let t = start.elapsed().as_secs();
+            if per_second.is_empty() {
+                println!("[{t:>4}s] idle");
+            }
+            per_second.sort_unstable_by_key(|&(d, _, _)| d);
+            for &(dev, events, triggers) in &per_second {
+                let epsilon = pool.get(dev).map_or(0.0, |e| e.kernel.epsilon);
+                println!(
+                    "[{t:>4}s] dev {:>3},{:<3} events {events:>6}  triggers {triggers:>6}  ratio {:>5.1}%  ε {epsilon:.3}",
+                    dev >> 32,
+                    dev & 0xFFFF_FFFF,
+                    100.0 * triggers as f64 / events as f64,
+                );
+            }
+            per_second.clear();
+            next_report += Duration::from_secs(1);
+            if seconds.is_some_and(|s| t >= s) {
+                break;
+            }
+        }
+    }
+    // Restores the tracepoint's previous enable state.
+    drop(tracer);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("live_capture reads Linux tracefs and only runs on Linux");
+}
@@ -0,0 +1,108 @@
+//! Object-storage range prefetching for video playback.
+//!
+//! Several clients stream videos from an S3-compatible store: each plays
+//! linearly in 1 MiB `GET Range` requests and occasionally seeks.  Requests
+//! from all clients are interleaved and fed to a `RangePlanner`; the example
+//! reports how much of what was prefetched was actually used.
+//!
+//! Run with: cargo run --example object_range
+
+use aether_link::adapters::range::{coalesce, RangePlan, RangePlanner};
+use std::collections::HashMap;
+
+const MIB: u64 = 1 << 20;
+const VIDEO_LEN: u64 = 2048 * MIB;
+const CLIENTS: u64 = 6;
+const REQUESTS: usize = 20_000;
+
+struct Client {
+    object: u64,
+    offset: u64,
+    left_in_run: u32,
+}
+
+/// xorshift64 — enough randomness for a synthetic log.
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Bytes covered by both sorted, coalesced range lists.
+fn intersect(a: &[RangePlan], b: &[RangePlan]) -> u64 {
+    let (mut i, mut j, mut total) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        total += a[i].overlap(&b[j]);
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    total
+}
+
+fn main() {
+    let mut rng = 0x9E37_79B9_7F4A_7C15u64;
+    let mut clients: Vec<Client> = (0..CLIENTS)
+        .map(|c| Client {
+            object: 0xC0FFEE ^ c,
+            offset: 0,
+            left_in_run: 0,
+        })
+        .collect();
+
+    let mut planner = RangePlanner::default().with_part_size(8 * MIB);
+    let mut prefetched: HashMap<u64, Vec<RangePlan>> = HashMap::new();
+    let mut requested: HashMap<u64, Vec<RangePlan>> = HashMap::new();
+    let (mut bytes_requested, mut bytes_prefetched, mut covered) = (0u64, 0u64, 0u64);
+
+    for _ in 0..REQUESTS {
+        let client = &mut clients[(next(&mut rng) % CLIENTS) as usize];
+        if client.left_in_run == 0 || client.offset + MIB > VIDEO_LEN {
+            // Seek: jump somewhere in the video and play for a while.
+            client.offset = (next(&mut rng) % (VIDEO_LEN / MIB - 256)) * MIB;
+            client.left_in_run = 30 + (next(&mut rng) % 300) as u32;
+        }
+        let request = RangePlan {
+            start: client.offset,
+            end: client.offset + MIB,
+        };
+        client.offset += MIB;
+        client.left_in_run -= 1;
+
+        let done = prefetched.entry(client.object).or_default();
+        covered += done.iter().map(|p| p.overlap(&request)).sum::<u64>();
+        bytes_requested += request.len();
+        requested.entry(client.object).or_default().push(request);
+
+        if let Some(plan) = planner.observe(client.object, request.start, request.len()) {
+            bytes_prefetched += plan.len();
+            done.push(plan);
+            coalesce(done);
+        }
+    }
+
+    let unique_prefetched: u64 = prefetched.values().flatten().map(RangePlan::len).sum();
+    let used: u64 = prefetched
+        .iter_mut()
+        .map(|(object, plans)| {
+            let reqs = requested.get_mut(object).expect("requested before planned");
+            coalesce(reqs);
+            intersect(plans, reqs)
+        })
+        .sum();
+
+    println!("requests          : {REQUESTS} from {CLIENTS} clients");
+    println!("bytes requested   : {} MiB", bytes_requested / MIB);
+    println!("bytes prefetched  : {} MiB", bytes_prefetched / MIB);
+    println!(
+        "coverage          : {:.1}% of requested bytes were already prefetched",
+        100.0 * covered as f64 / bytes_requested.max(1) as f64
+    );
+    println!(
+        "waste             : {:.1}% of prefetched bytes were never requested",
+        100.0 * (unique_prefetched - used) as f64 / unique_prefetched.max(1) as f64
+    );
+}
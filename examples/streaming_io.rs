@@ -3,83 +3,29 @@
 //! This example simulates real-time I/O processing with different
 //! workload patterns to demonstrate adaptive behavior.
 //!
-//! Run with: cargo run --example streaming_io
+//! Run with: cargo run --release --example streaming_io --features selftime
 
+use aether_link::workload::Pattern;
 use aether_link::AetherLinkKernel;
 use std::time::Instant;
 
-/// Simulates different I/O workload patterns
-enum WorkloadPattern {
-    Sequential,
-    Random,
-    Bursty,
-    HftTick,
-}
-
-impl WorkloadPattern {
-    fn generate(&self, base: u64, count: usize) -> Vec<u64> {
-        match self {
-            WorkloadPattern::Sequential => (base..base + count as u64).collect(),
-            WorkloadPattern::Random => {
-                // Pseudo-random using simple LCG
-                let mut rng = base;
-                (0..count)
-                    .map(|_| {
-                        rng = rng.wrapping_mul(6364136223846793005).wrapping_add(1);
-                        rng % 100000
-                    })
-                    .collect()
-            }
-            WorkloadPattern::Bursty => {
-                // Bursts of sequential with gaps
-                let mut result = Vec::with_capacity(count);
-                let mut pos = base;
-                for i in 0..count {
-                    if i % 5 == 0 {
-                        pos += 1000; // Jump
-                    }
-                    result.push(pos);
-                    pos += 1;
-                }
-                result
-            }
-            WorkloadPattern::HftTick => {
-                // High-frequency tick data pattern
-                // Small sequential bursts with occasional jumps
-                let mut result = Vec::with_capacity(count);
-                let mut pos = base;
-                for i in 0..count {
-                    if i % 10 == 0 {
-                        pos += 64; // Cache line boundary
-                    }
-                    result.push(pos);
-                    pos += 1;
-                }
-                result
-            }
-        }
-    }
-
-    fn name(&self) -> &'static str {
-        match self {
-            WorkloadPattern::Sequential => "Sequential",
-            WorkloadPattern::Random => "Random",
-            WorkloadPattern::Bursty => "Bursty",
-            WorkloadPattern::HftTick => "HFT Tick",
-        }
-    }
-}
-
-fn run_workload(pattern: &WorkloadPattern, cycles: usize) {
+fn run_workload(pattern: Pattern, cycles: usize) {
     let mut kernel = match pattern {
-        WorkloadPattern::HftTick => AetherLinkKernel::new_hft(),
+        Pattern::HftTick { .. } => AetherLinkKernel::new_hft(),
         _ => AetherLinkKernel::default(),
     };
 
+    let mut stream = Vec::with_capacity(20);
     let start = Instant::now();
 
     for i in 0..cycles {
-        let stream = pattern.generate(i as u64 * 100, 20);
+        stream.clear();
+        stream.extend(
+            pattern
+                .workload(i as u64)
+                .starting_at(i as u64 * 100)
+                .take(20),
+        );
         kernel.process_io_cycle(&stream);
     }
 
@@ -115,10 +61,14 @@ fn main() {
     println!("│   Workload   │  Cycles  │ Prefetch │  Ratio  │  Latency    │ Throughput │");
     println!("├──────────────┼──────────┼──────────┼─────────┼─────────────┼────────────┤");
 
-    run_workload(&WorkloadPattern::Sequential, cycles);
-    run_workload(&WorkloadPattern::Random, cycles);
-    run_workload(&WorkloadPattern::Bursty, cycles);
-    run_workload(&WorkloadPattern::HftTick, cycles);
+    for pattern in [
+        Pattern::SEQUENTIAL,
+        Pattern::RANDOM,
+        Pattern::BURSTY,
+        Pattern::HFT_TICK,
+    ] {
+        run_workload(pattern, cycles);
+    }
 
     println!("└──────────────┴──────────┴──────────┴─────────┴─────────────┴────────────┘");
     println!();
@@ -149,26 +99,45 @@ fn main() {
     println!("   Final φ: {:.4} rad", hft_kernel.phi);
     println!();
 
-    // Consistency check
-    println!("📐 Latency Consistency (1000 samples):");
-    let mut latencies = Vec::with_capacity(1000);
-    for _ in 0..1000 {
-        let start = Instant::now();
-        for _ in 0..1000 {
-            std::hint::black_box(hft_kernel.process_io_cycle(&tick_stream));
+    // Consistency check: the fixed tick window, then windows whose
+    // decisions flip unpredictably (sequential runs interleaved with
+    // random jumps), where data-dependent branches would show up as jitter.
+    let mut rng_state = 0x9e37_79b9_7f4a_7c15u64;
+    let mixed: Vec<[u64; 8]> = (0..4096)
+        .map(|_| {
+            rng_state ^= rng_state << 13;
+            rng_state ^= rng_state >> 7;
+            rng_state ^= rng_state << 17;
+            let base = rng_state % (1 << 30);
+            let stride = if rng_state >> 63 == 0 {
+                1
+            } else {
+                rng_state % 4096
+            };
+            core::array::from_fn(|i| base + i as u64 * stride)
+        })
+        .collect();
+    let windows: [(&str, Vec<&[u64]>); 2] = [
+        ("fixed window", vec![&tick_stream[..]]),
+        ("mixed windows", mixed.iter().map(|w| &w[..]).collect()),
+    ];
+    for (label, windows) in windows {
+        println!("📐 Latency Consistency, {label} (1M cycles, every 16th timed):");
+        hft_kernel.enable_self_timing(16);
+        for lbas in windows.iter().cycle().take(1_000_000) {
+            std::hint::black_box(hft_kernel.process_io_cycle(lbas));
         }
-        latencies.push(start.elapsed().as_nanos() as f64 / 1000.0);
-    }
-
-    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    let p50 = latencies[500];
-    let p99 = latencies[990];
-    let p999 = latencies[999];
 
-    println!("   P50:  {:.1} ns", p50);
-    println!("   P99:  {:.1} ns", p99);
-    println!("   P999: {:.1} ns", p999);
-    println!("   Jitter (P99-P50): {:.1} ns", p99 - p50);
+        let report = hft_kernel.latency_percentiles();
+        println!("   Samples: {}", report.samples);
+        println!("   P50:  {:.1} ns", report.p50);
+        println!("   P99:  {:.1} ns", report.p99);
+        println!("   P999: {:.1} ns", report.p999);
+        println!("   Jitter (P99-P50): {:.1} ns", report.p99 - report.p50);
+        println!();
+    }
+    println!("   Percentiles are power-of-two bucket upper bounds and include");
+    println!("   the two counter reads around each timed cycle.");
     println!();
 
     println!("✅ Streaming simulation complete!");
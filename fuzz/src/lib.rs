@@ -0,0 +1,184 @@
+//! Fuzz harness shared by the `process_cycle` and `decision_path` targets
+//! and the crate's
+//! `tests/fuzz_regressions.rs`, which replays the seed corpus and every
+//! saved crasher through the same code.
+//!
+//! Input layout (all little-endian):
+//!
+//! | bytes  | meaning                                        |
+//! |--------|------------------------------------------------|
+//! | 0..24  | ε, φ, λ₀, λ₁, λ₂, bias as `f32`                |
+//! | 24     | slice length per cycle (0 and 1 are allowed)   |
+//! | 25..   | LBA stream as `u64`s; a partial tail is dropped |
+//!
+//! Parameters rejected by [`AetherLinkKernel::try_new`] end the run of
+//! [`process_cycle`]; [`decision_path`] takes them as they are.
+
+use aether_link::anomaly::AnomalyOptions;
+use aether_link::mode::{ModeGains, ModeOptions};
+use aether_link::watchdog::{Recovery, WatchdogOptions};
+use aether_link::window::WindowOptions;
+use aether_link::{AetherLinkKernel, KernelConfig, ThresholdPolicy};
+use std::f32::consts::PI;
+
+/// Cycles run per input.
+pub const CYCLES: usize = 256;
+/// Longest LBA stream decoded from one input.
+pub const MAX_LBAS: usize = 4096;
+
+const HEADER: usize = 25;
+
+/// Decode `data`, run [`CYCLES`] cycles and panic on any broken invariant.
+pub fn process_cycle(data: &[u8]) {
+    let Some((config, slice_len, lbas)) = decode(data) else {
+        return;
+    };
+    let Ok(mut kernel) = config.build() else {
+        return;
+    };
+    for i in 0..CYCLES {
+        let start = if lbas.is_empty() { 0 } else { i % lbas.len() };
+        let end = (start + slice_len).min(lbas.len());
+        let slice = &lbas[start..end];
+
+        // The unchecked reads must land on the two ends of the slice.
+        let telemetry = kernel.clone().extract_telemetry(slice);
+        match slice {
+            [first, .., last] => {
+                let span = last.wrapping_sub(*first) as i64;
+                assert_eq!(telemetry[0], span.unsigned_abs() as f32);
+            }
+            _ => assert_eq!(telemetry, [0.0; 6]),
+        }
+
+        kernel.process_io_cycle(slice);
+        if kernel.cycles > 0 {
+            check(&kernel);
+        }
+    }
+    // Windows of fewer than two LBAs run no cycle.
+    assert_eq!(kernel.cycles + kernel.stats().short_windows, CYCLES as u64);
+}
+
+/// Decode `data` and drive every entry point of the panic-free decision
+/// path (see "Panics" in the crate docs) with it: parameters straight into
+/// [`AetherLinkKernel::new`], NaN and infinities included, every optional
+/// stage on, and the parameters reused as telemetry and threshold shifts.
+/// Asserts nothing; fuzz builds abort on the first panic.
+pub fn decision_path(data: &[u8]) {
+    let Some((config, slice_len, lbas)) = decode(data) else {
+        return;
+    };
+    let mut kernel = AetherLinkKernel::new(config.epsilon, config.phi, config.lambda, config.bias);
+    every_stage_on(&mut kernel, slice_len);
+    let shift = config.bias;
+    let features = [
+        config.epsilon,
+        config.phi,
+        config.lambda[0],
+        config.lambda[1],
+        config.lambda[2],
+        config.bias,
+    ];
+    for i in 0..CYCLES {
+        let start = if lbas.is_empty() { 0 } else { i % lbas.len() };
+        let end = (start + slice_len).min(lbas.len());
+        let slice = &lbas[start..end];
+        kernel.process_io_cycle(slice);
+        kernel.process_io_cycle_qos(slice, shift);
+        kernel.process_micro_batch(slice);
+        let lba = slice.first().copied().unwrap_or(i as u64);
+        kernel.push_lba(lba);
+        kernel.push_lba_qos(lba.wrapping_neg(), shift);
+        let telemetry = kernel.extract_telemetry(slice);
+        kernel.prepare_quantum_state(telemetry);
+        kernel.prepare_quantum_state(features);
+    }
+}
+
+/// Turn on every optional stage of the decision path, sized by `n`.
+pub fn every_stage_on(kernel: &mut AetherLinkKernel, n: usize) {
+    let n = n as u32;
+    kernel.enable_anomaly_detection(AnomalyOptions::default());
+    kernel.enable_watchdog(WatchdogOptions {
+        stuck_cycles: u64::from(n % 8),
+        recovery: Recovery::Recenter { margin: 0.05 },
+    });
+    kernel.set_latency_budget(n % 64);
+    kernel.set_queue_depth(n, n / 2);
+    kernel.set_burst_damping(1.0);
+    kernel.set_horizon(n);
+    kernel.set_target_lead(u64::from(n));
+    kernel.set_window(n as usize);
+    kernel.enable_adaptive_window(WindowOptions {
+        min: n as usize % 8,
+        ..WindowOptions::default()
+    });
+    kernel.enable_p_fetch_histogram();
+    kernel.enable_heatmap(u64::from(n)..u64::MAX, n as usize);
+    kernel.enable_write_coalescing();
+    kernel.enable_spatial_footprints();
+    kernel.enable_delta_correlation();
+    kernel.enable_class_stats(n % 4);
+    kernel.set_address_range(u64::from(n), u64::MAX - u64::from(n));
+    kernel.set_mode_options(ModeOptions {
+        steady_cycles: n % 4,
+        gains: Some(ModeGains::default()),
+        ..ModeOptions::default()
+    });
+    if n % 2 == 0 {
+        kernel.set_threshold_policy(ThresholdPolicy::Feedback(Default::default()));
+    }
+}
+
+/// The invariants `tests/properties.rs` checks, as plain assertions.
+pub fn check(kernel: &AetherLinkKernel) {
+    assert!(
+        (0.1..=0.9).contains(&kernel.epsilon),
+        "epsilon {}",
+        kernel.epsilon
+    );
+    assert!((0.0..2.0 * PI).contains(&kernel.phi), "phi {}", kernel.phi);
+    assert!(kernel.lambda.iter().all(|l| l.is_finite()));
+    assert!(kernel.bias.is_finite());
+    assert!(kernel.prefetches <= kernel.cycles);
+    let ratio = kernel.prefetch_ratio();
+    assert!((0.0..=1.0).contains(&ratio), "ratio {ratio}");
+}
+
+/// Split an input into parameters, slice length and LBAs.
+pub fn decode(data: &[u8]) -> Option<(KernelConfig, usize, Vec<u64>)> {
+    if data.len() < HEADER {
+        return None;
+    }
+    let f = |i: usize| f32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+    let config = KernelConfig {
+        epsilon: f(0),
+        phi: f(1),
+        lambda: [f(2), f(3), f(4)],
+        bias: f(5),
+    };
+    let lbas = data[HEADER..]
+        .chunks_exact(8)
+        .take(MAX_LBAS)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    Some((config, data[24] as usize, lbas))
+}
+
+/// Inverse of [`decode`]; used to build the seed corpus.
+pub fn encode(config: &KernelConfig, slice_len: u8, lbas: &[u64]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER + lbas.len() * 8);
+    for v in [config.epsilon, config.phi]
+        .into_iter()
+        .chain(config.lambda)
+        .chain([config.bias])
+    {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.push(slice_len);
+    for lba in lbas {
+        out.extend_from_slice(&lba.to_le_bytes());
+    }
+    out
+}
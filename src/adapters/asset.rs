@@ -0,0 +1,318 @@
+//! Game asset streaming adapter.
+//!
+//! Asset pipelines request chunks by id, each tagged with a priority
+//! (visible geometry outranks audio banks).  [`AssetStreamAdapter`] treats
+//! the chunk id as the kernel's LBA, turns the priority into a QoS threshold
+//! shift, and keeps a fixed-capacity pool of predicted chunks from which it
+//! emits [`PrefetchBatch`]es ready to be pushed onto a DirectStorage queue.
+//! Nothing here allocates.
+
+use crate::replay::LbaWindow;
+use crate::AetherLinkKernel;
+
+/// Default pool and batch capacity.
+pub const DEFAULT_CAPACITY: usize = 32;
+/// Default number of chunks predicted per trigger.
+pub const DEFAULT_LOOKAHEAD: u32 = 4;
+/// Default threshold shift at priority 255 (urgent traffic fetches more
+/// readily).
+pub const DEFAULT_PRIORITY_SHIFT: f32 = 0.15;
+const WINDOW: usize = 16;
+
+/// A batch of chunks to enqueue, most urgent first.
+///
+/// Ordered by descending priority, then descending confidence, then
+/// ascending chunk id.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PrefetchBatch<const N: usize = DEFAULT_CAPACITY> {
+    /// `(chunk_id, priority)` pairs.
+    pub chunks: heapless::Vec<(u32, u8), N>,
+}
+
+impl<const N: usize> PrefetchBatch<N> {
+    /// Number of chunks in the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// `true` if the batch holds no chunks.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Iterate over `(chunk_id, priority)` in queue order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &(u32, u8)> {
+        self.chunks.iter()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    chunk: u32,
+    priority: u8,
+    confidence: f32,
+}
+
+/// Kernel front-end for `(chunk_id, priority)` observations.
+///
+/// On every trigger the adapter predicts the next `lookahead` chunks along
+/// the current stride, scores each by the cycle's margin divided by its
+/// distance, and merges them into a pool of at most `N` candidates (the
+/// weakest are evicted).  The top `queue_depth` candidates are then taken
+/// out of the pool as a batch.
+#[derive(Debug, Clone)]
+pub struct AssetStreamAdapter<const N: usize = DEFAULT_CAPACITY> {
+    kernel: AetherLinkKernel,
+    window: LbaWindow,
+    pool: heapless::Vec<Candidate, N>,
+    queue_depth: usize,
+    lookahead: u32,
+    priority_shift: f32,
+    last_chunk: Option<u32>,
+}
+
+impl AssetStreamAdapter {
+    /// Adapter over the gaming preset with the default capacity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<const N: usize> Default for AssetStreamAdapter<N> {
+    fn default() -> Self {
+        Self::with_kernel(AetherLinkKernel::new_gaming())
+    }
+}
+
+impl<const N: usize> AssetStreamAdapter<N> {
+    /// Adapter over `kernel`, batching up to `N` chunks.
+    pub fn with_kernel(kernel: AetherLinkKernel) -> Self {
+        Self {
+            kernel,
+            window: LbaWindow::new(WINDOW),
+            pool: heapless::Vec::new(),
+            queue_depth: N,
+            lookahead: DEFAULT_LOOKAHEAD,
+            priority_shift: DEFAULT_PRIORITY_SHIFT,
+            last_chunk: None,
+        }
+    }
+
+    /// Cap every batch at `depth` chunks (clamped to `1..=N`).
+    pub fn with_queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth.clamp(1, N.max(1));
+        self
+    }
+
+    /// Predict `lookahead` chunks per trigger.
+    pub fn with_lookahead(mut self, lookahead: u32) -> Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    /// Threshold shift applied at priority 255; priority `p` lowers the
+    /// kernel's fetch threshold by `shift × p / 255`.
+    pub fn with_priority_shift(mut self, shift: f32) -> Self {
+        self.priority_shift = shift;
+        self
+    }
+
+    /// Record a request for `chunk_id` at `priority` (higher is more
+    /// urgent).
+    ///
+    /// Returns a batch when the kernel triggers and the pool has chunks to
+    /// dispatch.
+    pub fn observe(&mut self, chunk_id: u32, priority: u8) -> Option<PrefetchBatch<N>> {
+        self.window.push(u64::from(chunk_id));
+        let prev = self.last_chunk.replace(chunk_id);
+        self.remove(chunk_id);
+        if self.window.len() < 2 {
+            return None;
+        }
+
+        let shift = -self.priority_shift * f32::from(priority) / 255.0;
+        let outcome = self
+            .kernel
+            .process_io_cycle_qos(self.window.as_slice(), shift);
+        if !outcome.fetch {
+            return None;
+        }
+
+        let stride = match prev {
+            Some(p) if p > chunk_id => -1i64,
+            _ => 1,
+        };
+        let margin = outcome.margin().max(f32::EPSILON);
+        for k in 1..=self.lookahead {
+            let next = i64::from(chunk_id) + stride * i64::from(k);
+            let Ok(chunk) = u32::try_from(next) else {
+                break;
+            };
+            self.offer(Candidate {
+                chunk,
+                priority,
+                confidence: margin / k as f32,
+            });
+        }
+        let batch = self.take_batch();
+        (!batch.is_empty()).then_some(batch)
+    }
+
+    /// Take up to `queue_depth` of the most urgent pooled chunks.
+    pub fn take_batch(&mut self) -> PrefetchBatch<N> {
+        self.pool.sort_unstable_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(b.confidence.total_cmp(&a.confidence))
+                .then(a.chunk.cmp(&b.chunk))
+        });
+        let take = self.queue_depth.min(self.pool.len());
+        let mut batch = PrefetchBatch::default();
+        for c in &self.pool[..take] {
+            // Cannot fail: `take <= N`.
+            let _ = batch.chunks.push((c.chunk, c.priority));
+        }
+        let rest = self.pool.len() - take;
+        self.pool.rotate_left(take);
+        self.pool.truncate(rest);
+        batch
+    }
+
+    /// Chunks still waiting in the pool.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// The wrapped kernel.
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+
+    /// The wrapped kernel, mutably.
+    #[inline]
+    pub fn kernel_mut(&mut self) -> &mut AetherLinkKernel {
+        &mut self.kernel
+    }
+
+    /// Merge `cand` into the pool, keeping the stronger entry for a chunk
+    /// and evicting the weakest candidate when full.
+    fn offer(&mut self, cand: Candidate) {
+        let rank = |c: &Candidate| (c.priority, c.confidence);
+        if let Some(existing) = self.pool.iter_mut().find(|c| c.chunk == cand.chunk) {
+            if rank(&cand) > rank(existing) {
+                *existing = cand;
+            }
+            return;
+        }
+        if let Err(cand) = self.pool.push(cand) {
+            let weakest = self
+                .pool
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.priority
+                        .cmp(&b.priority)
+                        .then(a.confidence.total_cmp(&b.confidence))
+                })
+                .map(|(i, _)| i);
+            if let Some(i) = weakest {
+                if rank(&cand) > rank(&self.pool[i]) {
+                    self.pool[i] = cand;
+                }
+            }
+        }
+    }
+
+    /// Drop `chunk` from the pool — it has just been requested on demand.
+    fn remove(&mut self, chunk: u32) {
+        if let Some(i) = self.pool.iter().position(|c| c.chunk == chunk) {
+            self.pool.swap_remove(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Camera fly-through: chunk ids mostly advance by one, with a teleport
+    /// every 40 requests.  Geometry is requested at high priority, every
+    /// fifth request is an audio bank at low priority.
+    fn fly_through(len: u32) -> impl Iterator<Item = (u32, u8)> {
+        (0..len).map(|i| {
+            let chunk = (i / 40) * 10_000 + i % 40;
+            let priority = if i % 5 == 4 { 16 } else { 200 };
+            (chunk, priority)
+        })
+    }
+
+    fn is_ordered(batch: &PrefetchBatch<8>) -> bool {
+        batch
+            .chunks
+            .windows(2)
+            .all(|w| w[0].1 > w[1].1 || (w[0].1 == w[1].1 && w[0].0 != w[1].0))
+    }
+
+    #[test]
+    fn test_fly_through_batches_ordered_and_capped() {
+        let mut adapter = AssetStreamAdapter::<8>::default()
+            .with_queue_depth(3)
+            .with_lookahead(6);
+        let mut batches = 0;
+        for (chunk, priority) in fly_through(400) {
+            if let Some(batch) = adapter.observe(chunk, priority) {
+                batches += 1;
+                assert!(!batch.is_empty() && batch.len() <= 3);
+                assert!(is_ordered(&batch), "{batch:?}");
+                assert!(batch.iter().all(|&(c, _)| c != chunk));
+            }
+            assert!(adapter.pending() <= 8);
+        }
+        assert!(batches > 0);
+    }
+
+    #[test]
+    fn test_priority_then_confidence_order() {
+        let mut adapter = AssetStreamAdapter::<8>::default();
+        for (chunk, priority, confidence) in
+            [(5, 10, 0.9), (6, 200, 0.1), (7, 200, 0.5), (8, 10, 0.2)]
+        {
+            adapter.offer(Candidate {
+                chunk,
+                priority,
+                confidence,
+            });
+        }
+        let batch = adapter.with_queue_depth(3).take_batch();
+        assert_eq!(batch.chunks.as_slice(), &[(7, 200), (6, 200), (5, 10)]);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_weakest() {
+        let mut adapter = AssetStreamAdapter::<2>::default();
+        for (chunk, priority) in [(1, 50), (2, 10), (3, 90)] {
+            adapter.offer(Candidate {
+                chunk,
+                priority,
+                confidence: 1.0,
+            });
+        }
+        let batch = adapter.take_batch();
+        assert_eq!(batch.chunks.as_slice(), &[(3, 90), (1, 50)]);
+        assert_eq!(adapter.pending(), 0);
+    }
+
+    #[test]
+    fn test_default_is_gaming() {
+        let adapter = AssetStreamAdapter::new();
+        assert_eq!(
+            adapter.kernel().config(),
+            AetherLinkKernel::new_gaming().config()
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Adapters that feed the kernel from address spaces other than raw device
+//! LBAs and turn its decisions into concrete read-ahead work.
+
+pub mod asset;
+pub mod page;
+pub mod range;
+pub mod staging;
+
+pub use asset::{AssetStreamAdapter, PrefetchBatch};
+pub use page::{PageRange, PageStreamAdapter};
+pub use range::{RangePlan, RangePlanner};
+pub use staging::{StagingBatch, StagingCopy, StagingPlanner};
@@ -0,0 +1,273 @@
+//! Database buffer-pool adapter.
+//!
+//! Storage engines address data as page numbers within a file.
+//! [`PageStreamAdapter`] scales those ids into the kernel's block space,
+//! keeps a sliding window of recent pages, and on a trigger recommends the
+//! next run of pages to read ahead.  With a file attached it can also issue
+//! the reads itself as positional, page-sized `pread`s.
+
+use crate::replay::LbaWindow;
+use crate::trace::SECTOR_SIZE;
+use crate::AetherLinkKernel;
+use std::fs::File;
+use std::io;
+
+/// Default read-ahead bound in pages.
+pub const DEFAULT_MAX_PAGES: u32 = 32;
+/// Default number of recent pages the kernel sees per cycle.
+pub const DEFAULT_WINDOW: usize = 16;
+
+/// A run of consecutive pages `[start, start + len)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageRange {
+    /// First page number.
+    pub start: u32,
+    /// Number of pages.
+    pub len: u32,
+}
+
+impl PageRange {
+    /// One past the last page.
+    #[inline]
+    pub fn end(&self) -> u32 {
+        self.start.saturating_add(self.len)
+    }
+
+    /// `true` if `page` lies in the range.
+    #[inline]
+    pub fn contains(&self, page: u32) -> bool {
+        page >= self.start && page < self.end()
+    }
+
+    /// The page numbers in the range, in ascending order.
+    #[inline]
+    pub fn pages(&self) -> core::ops::Range<u32> {
+        self.start..self.end()
+    }
+}
+
+/// Kernel front-end for a stream of 32-bit page numbers.
+///
+/// Page `n` is presented to the kernel as block `n × page_size /
+/// block_size`, so deltas and variances keep their device-level scale.
+/// Consecutive recommendations along the same run are trimmed so a page is
+/// recommended at most once per run.
+#[derive(Debug)]
+pub struct PageStreamAdapter {
+    kernel: AetherLinkKernel,
+    page_size: u32,
+    block_size: u64,
+    max_pages: u32,
+    window: LbaWindow,
+    file: Option<File>,
+    file_pages: Option<u32>,
+    /// Page that triggered the previous recommendation, and the range.
+    last: Option<(u32, PageRange)>,
+    buf: Vec<u8>,
+}
+
+impl PageStreamAdapter {
+    /// Adapter for pages of `page_size` bytes, mapped onto 512-byte
+    /// sectors.  A zero `page_size` is treated as 1.
+    pub fn new(kernel: AetherLinkKernel, page_size: u32) -> Self {
+        Self {
+            kernel,
+            page_size: page_size.max(1),
+            block_size: SECTOR_SIZE,
+            max_pages: DEFAULT_MAX_PAGES,
+            window: LbaWindow::new(DEFAULT_WINDOW),
+            file: None,
+            file_pages: None,
+            last: None,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Express block addresses in units of `block_size` bytes instead of
+    /// sectors.  A zero `block_size` is treated as 1.
+    pub fn with_block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Bound every recommendation to at most `max_pages` pages.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Run cycles over the last `window` pages (at least 2).
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = LbaWindow::new(window);
+        self
+    }
+
+    /// Attach the data file: recommendations are clipped to its length and
+    /// [`prefetch`](Self::prefetch) reads from it.
+    pub fn with_file(mut self, file: File) -> io::Result<Self> {
+        self.file = Some(file);
+        self.refresh_len()?;
+        Ok(self)
+    }
+
+    /// Re-read the attached file's length after it grew or shrank.
+    pub fn refresh_len(&mut self) -> io::Result<()> {
+        if let Some(file) = &self.file {
+            let pages = file.metadata()?.len() / u64::from(self.page_size);
+            self.file_pages = Some(pages.min(u64::from(u32::MAX)) as u32);
+        }
+        Ok(())
+    }
+
+    /// Block address of `page_no` in the kernel's block space.
+    #[inline]
+    pub fn page_to_block(&self, page_no: u32) -> u64 {
+        (u64::from(page_no) * u64::from(self.page_size)) / self.block_size
+    }
+
+    /// Record an access to `page_no` and return the pages worth reading
+    /// ahead, if any.
+    ///
+    /// The range starts after `page_no` (or after the previous
+    /// recommendation when this access continues it), never extends more
+    /// than `max_pages` past `page_no`, and is clipped to the attached
+    /// file.
+    pub fn observe_page(&mut self, page_no: u32) -> Option<PageRange> {
+        self.window.push(self.page_to_block(page_no));
+        if self.window.len() < 2 || !self.kernel.process_io_cycle(self.window.as_slice()) {
+            return None;
+        }
+
+        let next = page_no.saturating_add(1);
+        let mut end = next.saturating_add(self.max_pages);
+        if let Some(pages) = self.file_pages {
+            end = end.min(pages);
+        }
+        let start = match self.last {
+            Some((origin, prev)) if origin <= page_no && page_no < prev.end() => prev.end(),
+            _ => next,
+        };
+        if start >= end {
+            return None;
+        }
+        let range = PageRange {
+            start,
+            len: end - start,
+        };
+        self.last = Some((page_no, range));
+        Some(range)
+    }
+
+    /// Read every page of `range` from the attached file with positional
+    /// reads, returning the number of pages read.
+    ///
+    /// The data is discarded — the point is to pull it into the page cache
+    /// or the device's read buffer.  Stops early at end of file; without a
+    /// file this does nothing and returns 0.
+    pub fn prefetch(&mut self, range: PageRange) -> io::Result<u32> {
+        let Some(file) = &self.file else {
+            return Ok(0);
+        };
+        self.buf.resize(self.page_size as usize, 0);
+        let mut read = 0;
+        for page in range.pages() {
+            let offset = u64::from(page) * u64::from(self.page_size);
+            if read_at(file, &mut self.buf, offset)? == 0 {
+                break;
+            }
+            read += 1;
+        }
+        Ok(read)
+    }
+
+    /// Page size in bytes.
+    #[inline]
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// The wrapped kernel.
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+
+    /// The wrapped kernel, mutably.
+    #[inline]
+    pub fn kernel_mut(&mut self) -> &mut AetherLinkKernel {
+        &mut self.kernel
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_at(_file: &File, _buf: &mut [u8], _offset: u64) -> io::Result<usize> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "positional reads are not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn adapter() -> PageStreamAdapter {
+        PageStreamAdapter::new(AetherLinkKernel::new_hft(), 8192).with_max_pages(8)
+    }
+
+    #[test]
+    fn test_page_to_block_scaling() {
+        let a = adapter();
+        assert_eq!(a.page_to_block(3), 3 * 16);
+        let a = a.with_block_size(4096);
+        assert_eq!(a.page_to_block(3), 6);
+    }
+
+    #[test]
+    fn test_sequential_scan_recommends_bounded_non_overlapping_ranges() {
+        let mut a = adapter();
+        let mut prev: Option<PageRange> = None;
+        let mut fired = 0;
+        for page in 100..400 {
+            if let Some(r) = a.observe_page(page) {
+                fired += 1;
+                assert!(r.start > page && r.end() <= page + 1 + 8);
+                if let Some(p) = prev {
+                    assert!(r.start >= p.end(), "{r:?} overlaps {p:?}");
+                }
+                prev = Some(r);
+            }
+        }
+        assert!(fired > 0);
+    }
+
+    #[test]
+    fn test_clipped_to_file_and_prefetch_reads_pages() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&vec![7u8; 8192 * 256]).unwrap();
+        let mut a = adapter().with_file(file).unwrap();
+        let mut last = None;
+        for page in 0..256 {
+            if let Some(r) = a.observe_page(page) {
+                assert!(r.end() <= 256);
+                last = Some(r);
+            }
+        }
+        assert!(a.observe_page(255).is_none());
+        let r = last.expect("sequential scan should trigger");
+        assert_eq!(a.prefetch(r).unwrap(), r.len);
+        let past = PageRange { start: 254, len: 5 };
+        assert_eq!(a.prefetch(past).unwrap(), 2);
+    }
+}
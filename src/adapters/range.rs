@@ -0,0 +1,295 @@
+//! Object-storage byte-range planner.
+//!
+//! S3-style stores serve `GET` requests with a `Range` header per object.
+//! [`RangePlanner`] keeps one kernel per object (through a [`KernelPool`]),
+//! feeds it the object's request offsets, and on a trigger suggests the next
+//! part-aligned byte ranges to fetch.  Suggestions that continue what was
+//! already planned for the object are trimmed and merged into one
+//! contiguous frontier, so the same part is never suggested twice in a run.
+
+use crate::pool::KernelPool;
+use crate::replay::LbaWindow;
+use crate::AetherLinkKernel;
+
+/// Default part size (8 MiB), the alignment of every plan.
+pub const DEFAULT_PART_SIZE: u64 = 8 << 20;
+/// Default parts planned ahead per trigger.
+pub const DEFAULT_PARTS_AHEAD: u64 = 2;
+/// Recent requests per object the kernel sees.  Together with the kernel
+/// this bounds per-object memory to a few hundred bytes.
+pub const OBJECT_WINDOW: usize = 8;
+/// Offsets are presented to the kernel in 4 KiB units.
+const BLOCK: u64 = 4096;
+
+/// A byte range `[start, end)` of one object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangePlan {
+    /// First byte.
+    pub start: u64,
+    /// One past the last byte.
+    pub end: u64,
+}
+
+impl RangePlan {
+    /// Range length in bytes.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// `true` if the range holds no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// Widen to whole parts: `start` rounds down and `end` rounds up to a
+    /// multiple of `part` (a zero `part` leaves the range unchanged).
+    pub fn align(self, part: u64) -> Self {
+        if part == 0 {
+            return self;
+        }
+        let start = self.start - self.start % part;
+        let end = match self.end % part {
+            0 => self.end,
+            r => self.end.saturating_add(part - r),
+        };
+        Self { start, end }
+    }
+
+    /// The union of `self` and `other` if they overlap or touch.
+    pub fn merge(self, other: Self) -> Option<Self> {
+        if self.start <= other.end && other.start <= self.end {
+            Some(Self {
+                start: self.start.min(other.start),
+                end: self.end.max(other.end),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Bytes shared with `other`.
+    #[inline]
+    pub fn overlap(&self, other: &Self) -> u64 {
+        self.end
+            .min(other.end)
+            .saturating_sub(self.start.max(other.start))
+    }
+}
+
+/// Sort `plans` and merge overlapping or adjacent ranges in place.
+pub fn coalesce(plans: &mut Vec<RangePlan>) {
+    plans.retain(|p| !p.is_empty());
+    plans.sort_unstable();
+    let mut out = 0;
+    for i in 1..plans.len() {
+        match plans[out].merge(plans[i]) {
+            Some(m) => plans[out] = m,
+            None => {
+                out += 1;
+                plans[out] = plans[i];
+            }
+        }
+    }
+    plans.truncate(plans.len().min(out + 1));
+}
+
+/// Per-object state kept next to the object's kernel.
+#[derive(Debug, Clone)]
+struct ObjectState {
+    window: LbaWindow,
+    frontier: Option<RangePlan>,
+}
+
+impl Default for ObjectState {
+    fn default() -> Self {
+        Self {
+            window: LbaWindow::new(OBJECT_WINDOW),
+            frontier: None,
+        }
+    }
+}
+
+/// Per-object range prefetch planner.
+#[derive(Debug, Clone)]
+pub struct RangePlanner {
+    pool: KernelPool<ObjectState>,
+    part_size: u64,
+    parts_ahead: u64,
+}
+
+impl RangePlanner {
+    /// Planner keeping at most `max_objects` objects resident, each with a
+    /// clone of `template` as its kernel.
+    pub fn new(template: AetherLinkKernel, max_objects: usize) -> Self {
+        Self {
+            pool: KernelPool::new(template, max_objects),
+            part_size: DEFAULT_PART_SIZE,
+            parts_ahead: DEFAULT_PARTS_AHEAD,
+        }
+    }
+
+    /// Align plans to `part_size` bytes (at least 1).
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size.max(1);
+        self
+    }
+
+    /// Plan `parts` whole parts past each triggering request.
+    pub fn with_parts_ahead(mut self, parts: u64) -> Self {
+        self.parts_ahead = parts;
+        self
+    }
+
+    /// Forget objects not requested during the last `observations` calls.
+    pub fn with_max_idle(mut self, observations: u64) -> Self {
+        self.pool = self.pool.with_max_idle(observations);
+        self
+    }
+
+    /// Record a `GET` of `len` bytes at `offset` of the object identified by
+    /// `object_key_hash`, returning the range worth fetching next, if any.
+    ///
+    /// The plan covers `parts_ahead` parts after the part holding the
+    /// request's last byte, minus whatever earlier plans for the object
+    /// already cover.
+    pub fn observe(&mut self, object_key_hash: u64, offset: u64, len: u64) -> Option<RangePlan> {
+        let part = self.part_size;
+        let ahead = self.parts_ahead;
+        let entry = self.pool.entry(object_key_hash);
+        let state = &mut entry.state;
+
+        state.window.push(offset / BLOCK);
+        if state.window.len() < 2 || !entry.kernel.process_io_cycle(state.window.as_slice()) {
+            return None;
+        }
+
+        let req_end = offset.saturating_add(len.max(1));
+        let start = RangePlan {
+            start: req_end,
+            end: req_end,
+        }
+        .align(part)
+        .end;
+        let mut plan = RangePlan {
+            start,
+            end: start.saturating_add(ahead.saturating_mul(part)),
+        };
+        let request = RangePlan {
+            start: offset,
+            end: req_end,
+        };
+        // The run so far: the parts being read plus the parts planned.
+        let run = RangePlan {
+            start: request.align(part).start,
+            end: plan.end,
+        };
+
+        state.frontier = match state.frontier {
+            // Still inside (or right after) the current run: extend it and
+            // hand out only the new tail.
+            Some(f) if f.merge(request).is_some() => {
+                plan.start = plan.start.max(f.end);
+                Some(f.merge(run).unwrap_or(run))
+            }
+            _ => Some(run),
+        };
+        (!plan.is_empty()).then_some(plan)
+    }
+
+    /// Objects currently tracked.
+    #[inline]
+    pub fn objects(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Objects dropped for being idle or least recently used.
+    #[inline]
+    pub fn evictions(&self) -> u64 {
+        self.pool.evictions()
+    }
+
+    /// Part size in bytes.
+    #[inline]
+    pub fn part_size(&self) -> u64 {
+        self.part_size
+    }
+}
+
+impl Default for RangePlanner {
+    fn default() -> Self {
+        Self::new(AetherLinkKernel::default(), crate::pool::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1 << 20;
+
+    fn r(start: u64, end: u64) -> RangePlan {
+        RangePlan { start, end }
+    }
+
+    #[test]
+    fn test_alignment() {
+        assert_eq!(r(3 * MIB, 9 * MIB).align(8 * MIB), r(0, 16 * MIB));
+        assert_eq!(r(8 * MIB, 16 * MIB).align(8 * MIB), r(8 * MIB, 16 * MIB));
+        assert_eq!(
+            r(8 * MIB + 1, 8 * MIB + 1).align(8 * MIB),
+            r(8 * MIB, 16 * MIB)
+        );
+        assert_eq!(r(5, 7).align(0), r(5, 7));
+        assert_eq!(r(0, u64::MAX).align(8).end, u64::MAX);
+    }
+
+    #[test]
+    fn test_merge_and_coalesce() {
+        assert_eq!(r(0, 8).merge(r(8, 16)), Some(r(0, 16)), "adjacent");
+        assert_eq!(r(0, 10).merge(r(4, 6)), Some(r(0, 10)), "contained");
+        assert_eq!(r(0, 8).merge(r(9, 16)), None, "gap");
+        assert_eq!(r(0, 10).overlap(&r(5, 20)), 5);
+
+        let mut plans = vec![
+            r(16, 24),
+            r(0, 8),
+            r(30, 30),
+            r(8, 12),
+            r(40, 48),
+            r(44, 50),
+        ];
+        coalesce(&mut plans);
+        assert_eq!(plans, vec![r(0, 12), r(16, 24), r(40, 50)]);
+        let mut empty = Vec::new();
+        coalesce(&mut empty);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_linear_play_plans_are_aligned_and_disjoint() {
+        let mut planner = RangePlanner::default().with_part_size(8 * MIB);
+        let mut plans = Vec::new();
+        for i in 0..200 {
+            if let Some(p) = planner.observe(42, i * MIB, MIB) {
+                assert_eq!(p.start % (8 * MIB), 0);
+                assert!(p.start >= (i + 1) * MIB);
+                plans.push(p);
+            }
+        }
+        assert!(!plans.is_empty());
+        assert!(plans.windows(2).all(|w| w[0].end <= w[1].start));
+    }
+
+    #[test]
+    fn test_objects_bounded_and_idle_evicted() {
+        let mut planner = RangePlanner::new(AetherLinkKernel::default(), 4).with_max_idle(10);
+        for key in 0..10 {
+            planner.observe(key, 0, MIB);
+        }
+        assert_eq!(planner.objects(), 4);
+        assert!(planner.evictions() >= 6);
+    }
+}
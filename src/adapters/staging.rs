@@ -0,0 +1,255 @@
+//! Staging-buffer packing for DirectStorage-style upload paths.
+//!
+//! GPU upload paths read from storage into fixed-size staging buffers and
+//! copy from there.  [`StagingPlanner`] turns a set of [`PrefetchPlan`]
+//! extents into per-buffer copy lists: source extents are merged where they
+//! touch, split where they exceed a buffer, and packed first-fit with every
+//! destination offset aligned.  It is pure computation, so the layout can be
+//! checked without a GPU.
+
+use crate::prefetcher::{self, PrefetchPlan};
+
+/// Default staging buffer size (32 MiB).
+pub const DEFAULT_BUFFER_SIZE: u64 = 32 << 20;
+/// Default destination alignment (4 KiB).
+pub const DEFAULT_ALIGNMENT: u64 = 4096;
+/// Default limit on copies per submission.
+pub const DEFAULT_MAX_COPIES: usize = 128;
+/// Default device block size in bytes.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
+/// One copy from the device into a staging buffer, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StagingCopy {
+    /// Device byte offset to read from.
+    pub src_offset: u64,
+    /// Offset inside the staging buffer.
+    pub dst_offset: u64,
+    /// Bytes to copy.
+    pub len: u64,
+}
+
+/// The copies that fill one staging buffer, i.e. one submission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagingBatch {
+    /// Index of the staging buffer, in creation order.
+    pub buffer_index: usize,
+    /// Copies in source order.
+    pub copies: Vec<StagingCopy>,
+}
+
+impl StagingBatch {
+    /// Bytes of payload in the buffer.
+    pub fn bytes(&self) -> u64 {
+        self.copies.iter().map(|c| c.len).sum()
+    }
+
+    fn tail(&self) -> u64 {
+        self.copies.last().map_or(0, |c| c.dst_offset + c.len)
+    }
+}
+
+/// First-fit packer of prefetch extents into staging buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StagingPlanner {
+    buffer_size: u64,
+    alignment: u64,
+    max_copies: usize,
+    block_size: u64,
+}
+
+impl Default for StagingPlanner {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUFFER_SIZE, DEFAULT_ALIGNMENT)
+    }
+}
+
+impl StagingPlanner {
+    /// Planner for buffers of `buffer_size` bytes with destination offsets
+    /// aligned to `alignment`.
+    ///
+    /// `alignment` is rounded up to a power of two and `buffer_size` down to
+    /// a multiple of it (but never below one alignment unit).
+    pub fn new(buffer_size: u64, alignment: u64) -> Self {
+        let alignment = alignment.max(1).next_power_of_two();
+        let buffer_size = (buffer_size - buffer_size % alignment).max(alignment);
+        Self {
+            buffer_size,
+            alignment,
+            max_copies: DEFAULT_MAX_COPIES,
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    /// Allow at most `max` copies per batch (at least 1).
+    pub fn with_max_copies(mut self, max: usize) -> Self {
+        self.max_copies = max.max(1);
+        self
+    }
+
+    /// Interpret plan LBAs in units of `block_size` bytes (at least 1).
+    pub fn with_block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Staging buffer size in bytes.
+    #[inline]
+    pub fn buffer_size(&self) -> u64 {
+        self.buffer_size
+    }
+
+    /// Destination alignment in bytes.
+    #[inline]
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    /// Pack `plans` into staging batches.
+    ///
+    /// Plans are sorted and overlapping or adjacent ones merged, so every
+    /// source byte is copied exactly once.  Each merged extent longer than
+    /// a buffer is split into buffer-sized pieces; pieces are then placed,
+    /// in source order, into the first buffer with room at the next aligned
+    /// offset and a free copy slot, opening a new buffer when none fits.
+    pub fn plan(&self, plans: &[PrefetchPlan]) -> Vec<StagingBatch> {
+        let mut batches: Vec<StagingBatch> = Vec::new();
+        for (src, len) in self.merged_extents(plans) {
+            let mut done = 0;
+            while done < len {
+                let piece = (len - done).min(self.buffer_size);
+                self.place(&mut batches, src + done, piece);
+                done += piece;
+            }
+        }
+        batches
+    }
+
+    /// Byte extents `(offset, len)` of the sorted, merged plans.
+    fn merged_extents(&self, plans: &[PrefetchPlan]) -> Vec<(u64, u64)> {
+        prefetcher::coalesce(plans)
+            .into_iter()
+            .map(|p| (p.start * self.block_size, p.len * self.block_size))
+            .collect()
+    }
+
+    fn place(&self, batches: &mut Vec<StagingBatch>, src_offset: u64, len: u64) {
+        let align = |x: u64| (x + self.alignment - 1) & !(self.alignment - 1);
+        let fits = |b: &StagingBatch| {
+            b.copies.len() < self.max_copies && align(b.tail()) + len <= self.buffer_size
+        };
+        let idx = match batches.iter().position(fits) {
+            Some(i) => i,
+            None => {
+                batches.push(StagingBatch {
+                    buffer_index: batches.len(),
+                    copies: Vec::new(),
+                });
+                batches.len() - 1
+            }
+        };
+        let batch = &mut batches[idx];
+        let dst_offset = align(batch.tail());
+        batch.copies.push(StagingCopy {
+            src_offset,
+            dst_offset,
+            len,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+
+    /// No two copies overlap in a buffer, everything is aligned and in
+    /// bounds, and the source bytes add up to `expected`.
+    fn check(planner: &StagingPlanner, batches: &[StagingBatch], expected: u64) {
+        let mut total = 0;
+        for (i, b) in batches.iter().enumerate() {
+            assert_eq!(b.buffer_index, i);
+            assert!(!b.copies.is_empty());
+            for (j, c) in b.copies.iter().enumerate() {
+                assert_eq!(c.dst_offset % planner.alignment(), 0);
+                assert!(c.dst_offset + c.len <= planner.buffer_size());
+                for d in &b.copies[j + 1..] {
+                    assert!(
+                        c.dst_offset + c.len <= d.dst_offset
+                            || d.dst_offset + d.len <= c.dst_offset,
+                        "{c:?} overlaps {d:?}"
+                    );
+                }
+            }
+            total += b.bytes();
+        }
+        assert_eq!(total, expected);
+    }
+
+    #[test]
+    fn test_split_extent_larger_than_buffer() {
+        let planner = StagingPlanner::new(MIB, 4 * KIB);
+        // 600 blocks of 4 KiB = 2.34 MiB → 1 MiB + 1 MiB + 352 KiB.
+        let batches = planner.plan(&[PrefetchPlan::new(1000, 600)]);
+        check(&planner, &batches, 600 * 4 * KIB);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].copies[0].src_offset, 1000 * 4 * KIB);
+        assert_eq!(batches[1].copies[0].src_offset, 1000 * 4 * KIB + MIB);
+        assert_eq!(batches[2].copies[0].len, 88 * 4 * KIB);
+    }
+
+    #[test]
+    fn test_exact_fit_fills_one_buffer() {
+        let planner = StagingPlanner::new(MIB, 4 * KIB);
+        let plans = [PrefetchPlan::new(0, 128), PrefetchPlan::new(500, 128)];
+        let batches = planner.plan(&plans);
+        check(&planner, &batches, MIB);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].copies[1].dst_offset, MIB / 2);
+        assert_eq!(batches[0].tail(), MIB);
+    }
+
+    #[test]
+    fn test_adjacent_and_overlapping_sources_merge() {
+        let planner = StagingPlanner::default();
+        let plans = [
+            PrefetchPlan::new(10, 5),
+            PrefetchPlan::new(0, 10),
+            PrefetchPlan::new(12, 8),
+            PrefetchPlan::new(40, 0),
+        ];
+        let batches = planner.plan(&plans);
+        check(&planner, &batches, 20 * 4 * KIB);
+        assert_eq!(batches[0].copies.len(), 1);
+    }
+
+    #[test]
+    fn test_first_fit_and_copy_limit() {
+        let planner = StagingPlanner::new(64 * KIB, 4 * KIB)
+            .with_max_copies(2)
+            .with_block_size(512);
+        // 3 sectors → 1.5 KiB each, padded to 4 KiB slots.
+        let plans: Vec<_> = (0..5).map(|i| PrefetchPlan::new(i * 100, 3)).collect();
+        let batches = planner.plan(&plans);
+        check(&planner, &batches, 5 * 1536);
+        assert_eq!(
+            batches.iter().map(|b| b.copies.len()).collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+        assert_eq!(batches[0].copies[1].dst_offset, 4 * KIB);
+
+        // A small piece back-fills an earlier buffer with room.
+        let planner = StagingPlanner::new(64 * KIB, 4 * KIB).with_block_size(KIB);
+        let plans = [
+            PrefetchPlan::new(0, 40),
+            PrefetchPlan::new(100, 40),
+            PrefetchPlan::new(200, 8),
+        ];
+        let batches = planner.plan(&plans);
+        check(&planner, &batches, 88 * KIB);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].copies[1].src_offset, 200 * KIB);
+    }
+}
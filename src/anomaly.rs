@@ -0,0 +1,261 @@
+//! Flagging I/O that does not look like the volume's usual traffic.
+//!
+//! The telemetry that drives prefetching also describes the workload, so a
+//! sudden change in it — a full-disk sequential sweep on a volume that only
+//! ever sees random database pages — is worth reporting even when it is
+//! perfectly prefetchable.  With
+//! [`AetherLinkKernel::enable_anomaly_detection`] the kernel keeps a
+//! baseline of two per-cycle features: the window span and its change since
+//! the previous cycle, the quantities behind the Δ and C telemetry.  Both
+//! are taken signed, so windows that move backwards do not wrap to 2⁶⁴, and
+//! tracked as exponentially weighted means and variances on a
+//! `ln(1 + |x|)` scale, so shifts of orders of magnitude are what count.
+//! Every cycle is scored by the root-mean-square of the features' deviations
+//! from the baseline in standard deviations, and an alarm is raised once
+//! the score has stayed above [`AnomalyOptions::threshold`] for
+//! [`AnomalyOptions::consecutive`] cycles.
+//!
+//! The baseline keeps learning from cycles that score below the threshold,
+//! so slow drift becomes the new normal over a few multiples of
+//! `1 / alpha` cycles, while an abrupt change keeps alarming until
+//! detection is re-enabled with a fresh baseline.  Detection does not
+//! change decisions unless [`AnomalyOptions::suppress_prefetch`] couples
+//! the two.
+
+use crate::audit::MathMode;
+use crate::AetherLinkKernel;
+
+/// Features the baseline tracks.
+const FEATURES: usize = 2;
+
+/// Knobs for anomaly detection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnomalyOptions {
+    /// Score above which a cycle counts as anomalous.
+    pub threshold: f32,
+    /// Consecutive anomalous cycles that raise an alarm.
+    pub consecutive: u32,
+    /// Cycles spent building the baseline (plain running mean and
+    /// variance) before anything is scored.
+    pub warmup: u64,
+    /// Weight of each new cycle in the baseline after warm-up.  Cycles
+    /// above the threshold are not added.
+    pub alpha: f32,
+    /// Withhold prefetches while an alarm is raised.
+    pub suppress_prefetch: bool,
+}
+
+impl Default for AnomalyOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 4.0,
+            consecutive: 8,
+            warmup: 256,
+            alpha: 1.0 / 1024.0,
+            suppress_prefetch: false,
+        }
+    }
+}
+
+/// Baseline and alarm state.
+#[derive(Debug, Clone)]
+pub(crate) struct AnomalyDetector {
+    opts: AnomalyOptions,
+    mean: [f32; FEATURES],
+    var: [f32; FEATURES],
+    seen: u64,
+    last_span: i64,
+    score: f32,
+    streak: u32,
+    alarms: u64,
+}
+
+impl AnomalyDetector {
+    fn new(opts: AnomalyOptions) -> Self {
+        Self {
+            opts,
+            mean: [0.0; FEATURES],
+            var: [0.0; FEATURES],
+            seen: 0,
+            last_span: 0,
+            score: 0.0,
+            streak: 0,
+            alarms: 0,
+        }
+    }
+
+    /// Score the cycle over a window spanning `first ..= last` and fold it
+    /// into the baseline.
+    #[inline]
+    pub(crate) fn observe(&mut self, first: u64, last: u64, math: MathMode) {
+        let span = last.wrapping_sub(first) as i64;
+        let change = span.wrapping_sub(self.last_span);
+        self.last_span = span;
+        let x = [span, change].map(|v| math.ln(1.0 + v.unsigned_abs() as f32));
+        self.seen += 1;
+
+        if self.seen <= self.opts.warmup {
+            // Welford until the baseline is established.
+            let n = self.seen as f32;
+            for ((&x, mean), var) in x.iter().zip(&mut self.mean).zip(&mut self.var) {
+                let d = x - *mean;
+                *mean += d / n;
+                *var += (d * (x - *mean) - *var) / n;
+            }
+            return;
+        }
+
+        let mut d = [0.0; FEATURES];
+        let mut sum = 0.0;
+        for ((d, &x), (&mean, &var)) in d.iter_mut().zip(&x).zip(self.mean.iter().zip(&self.var)) {
+            *d = x - mean;
+            // The floor keeps features that were constant from dividing by
+            // zero; a change of 0.1 in log units is then one deviation.
+            let z = *d * math.inv_sqrt(var + 1e-2);
+            sum += z * z;
+        }
+        self.score = math.sqrt(sum / FEATURES as f32);
+
+        if self.score <= self.opts.threshold {
+            let a = self.opts.alpha;
+            for ((&d, mean), var) in d.iter().zip(&mut self.mean).zip(&mut self.var) {
+                *mean += a * d;
+                *var = (1.0 - a) * (*var + a * d * d);
+            }
+        }
+        if self.score > self.opts.threshold {
+            self.streak = self.streak.saturating_add(1);
+            if self.streak == self.opts.consecutive.max(1) {
+                self.alarms += 1;
+            }
+        } else {
+            self.streak = 0;
+        }
+    }
+
+    /// `true` while the score has been above threshold long enough.
+    #[inline]
+    pub(crate) fn alarmed(&self) -> bool {
+        self.streak >= self.opts.consecutive.max(1)
+    }
+
+    #[inline]
+    pub(crate) fn suppresses(&self) -> bool {
+        self.opts.suppress_prefetch && self.alarmed()
+    }
+}
+
+impl AetherLinkKernel {
+    /// Start scoring cycles against a fresh baseline.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::anomaly::AnomalyOptions;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_anomaly_detection(AnomalyOptions::default());
+    /// for i in 0..1000u64 {
+    ///     kernel.push_lba(i.wrapping_mul(0x9E37_79B9) % 100_000);
+    /// }
+    /// assert!(!kernel.anomaly_alarm());
+    /// ```
+    pub fn enable_anomaly_detection(&mut self, opts: AnomalyOptions) {
+        self.anomaly = Some(AnomalyDetector::new(opts));
+    }
+
+    /// Stop scoring and drop the baseline.
+    #[inline]
+    pub fn disable_anomaly_detection(&mut self) {
+        self.anomaly = None;
+    }
+
+    /// Score of the last cycle: RMS deviation from the baseline in standard
+    /// deviations.  0 when detection is off or still warming up.
+    #[inline]
+    pub fn anomaly_score(&self) -> f32 {
+        self.anomaly.as_ref().map_or(0.0, |a| a.score)
+    }
+
+    /// `true` while an alarm is raised: the last
+    /// [`consecutive`](AnomalyOptions::consecutive) cycles all scored above
+    /// the threshold.
+    #[inline]
+    pub fn anomaly_alarm(&self) -> bool {
+        self.anomaly.as_ref().is_some_and(|a| a.alarmed())
+    }
+
+    /// Alarms raised since detection was enabled.  Poll it and compare to
+    /// react once per alarm.
+    #[inline]
+    pub fn anomaly_alarms(&self) -> u64 {
+        self.anomaly.as_ref().map_or(0, |a| a.alarms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::{Pattern, Phased};
+
+    const STABLE: u64 = 20_000;
+
+    fn flipped() -> Phased {
+        Phased::new(11)
+            .then(Pattern::RANDOM, STABLE)
+            .then_at(Pattern::SEQUENTIAL, 2_000, 1 << 32)
+    }
+
+    #[test]
+    fn test_detects_sweep_on_random_volume() {
+        let opts = AnomalyOptions::default();
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_anomaly_detection(opts);
+        let mut first_alarm = None;
+        for (i, lba) in flipped().iter().enumerate() {
+            kernel.push_lba(lba);
+            if i < STABLE as usize {
+                assert!(
+                    !kernel.anomaly_alarm(),
+                    "false alarm at {i}, score {}",
+                    kernel.anomaly_score()
+                );
+            } else if kernel.anomaly_alarm() && first_alarm.is_none() {
+                first_alarm = Some(i as u64 - STABLE);
+            }
+        }
+        // The window needs 16 accesses to fill with the sweep.
+        let latency = first_alarm.expect("sweep was not flagged");
+        assert!(
+            latency <= 16 + opts.consecutive as u64 + 8,
+            "flagged after {latency}"
+        );
+        assert_eq!(kernel.anomaly_alarms(), 1);
+    }
+
+    #[test]
+    fn test_decisions_unchanged_unless_coupled() {
+        let mut plain = AetherLinkKernel::default();
+        let mut watched = plain.clone();
+        watched.enable_anomaly_detection(AnomalyOptions::default());
+        let mut coupled = plain.clone();
+        coupled.enable_anomaly_detection(AnomalyOptions {
+            suppress_prefetch: true,
+            ..AnomalyOptions::default()
+        });
+        let mut suppressed = 0;
+        for lba in flipped().iter() {
+            let p = plain.push_lba(lba);
+            assert_eq!(p, watched.push_lba(lba));
+            let c = coupled.push_lba(lba);
+            if coupled.anomaly_alarm() {
+                assert!(c.map_or(true, |d| !d.fire));
+                suppressed += p.is_some_and(|d| d.fire) as u32;
+            }
+        }
+        assert!(suppressed > 0, "the sweep fires and is withheld");
+        assert_eq!(plain.epsilon, watched.epsilon);
+    }
+}
@@ -0,0 +1,274 @@
+//! One kernel shared by many threads without a lock.
+//!
+//! An [`AetherLinkKernel`] behind a `Mutex` serialises every decision, and
+//! under contention the lock costs more than the cycle itself.
+//! [`AtomicAetherKernel`] keeps the two learned parameters, ε and φ, as
+//! `f32` bit patterns in `AtomicU32`s and the counters in `AtomicU64`s, so
+//! any number of threads can decide through a shared reference:
+//!
+//! - the decision reads ε and φ with `Relaxed` loads;
+//! - the adaptation step is applied with a compare-exchange loop, so
+//!   concurrent steps are never lost, only reordered;
+//! - counters are `Relaxed` increments.
+//!
+//! Telemetry is per stream, not per kernel: each thread (or stream) keeps
+//! its own [`StreamTelemetry`] and passes it in, exactly the state a
+//! [`KernelPool`](crate::KernelPool) would give every stream its own kernel
+//! for.
+//!
+//! # Consistency
+//!
+//! Every value read is a complete `f32` that some thread stored — there is
+//! no torn state — and ε stays inside its clamp.  What is not guaranteed is
+//! freshness: a decision may measure with a φ that another thread has just
+//! rotated, and the ε it compares against includes its own step but
+//! possibly not a concurrent one.  Counters are exact once all threads
+//! have finished, but a read while decisions are in flight is only a
+//! lower bound.  Run single-threaded, the kernel makes exactly the
+//! decisions of an [`AetherLinkKernel`] with the same parameters.
+//!
+//! Only the core open-loop cycle is shared; the threshold policies, idle
+//! decay, anomaly detection and watchdog of [`AetherLinkKernel`] are not
+//! available here.
+
+use crate::config::KernelConfig;
+use crate::{
+    fast_sigmoid, threshold, wrap_angle, AetherLinkKernel, CycleOutcome, MathMode, TelemetryDSP,
+};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering::Relaxed};
+
+/// Telemetry state of one stream fed to an [`AtomicAetherKernel`].
+#[derive(Debug, Clone, Default)]
+pub struct StreamTelemetry {
+    dsp: TelemetryDSP,
+}
+
+/// Kernel whose decision cycle takes `&self`.  See the [module
+/// docs](self) for the consistency model.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::atomic::{AtomicAetherKernel, StreamTelemetry};
+/// use std::sync::Arc;
+///
+/// let kernel = Arc::new(AtomicAetherKernel::default());
+/// let workers: Vec<_> = (0..4u64)
+///     .map(|t| {
+///         let kernel = Arc::clone(&kernel);
+///         std::thread::spawn(move || {
+///             let mut stream = StreamTelemetry::default();
+///             for i in 0..100 {
+///                 let base = t << 32 | i * 4;
+///                 kernel.process_io_cycle(&mut stream, &[base, base + 1, base + 2, base + 3]);
+///             }
+///         })
+///     })
+///     .collect();
+/// for w in workers {
+///     w.join().unwrap();
+/// }
+/// assert_eq!(kernel.cycles(), 400);
+/// ```
+#[derive(Debug)]
+pub struct AtomicAetherKernel {
+    epsilon: AtomicU32,
+    phi: AtomicU32,
+    lambda: [f32; 3],
+    bias: f32,
+    cycles: AtomicU64,
+    prefetches: AtomicU64,
+}
+
+impl AtomicAetherKernel {
+    /// Shared kernel with the same parameters as
+    /// [`AetherLinkKernel::new`].
+    pub fn new(epsilon: f32, phi: f32, lambda: [f32; 3], bias: f32) -> Self {
+        Self {
+            epsilon: AtomicU32::new(epsilon.to_bits()),
+            phi: AtomicU32::new(phi.to_bits()),
+            lambda,
+            bias,
+            cycles: AtomicU64::new(0),
+            prefetches: AtomicU64::new(0),
+        }
+    }
+
+    /// Execute one decision cycle over `lba_stream`, using and updating
+    /// `stream`'s telemetry.  See [`AetherLinkKernel::process_io_cycle`].
+    #[inline]
+    pub fn process_io_cycle(&self, stream: &mut StreamTelemetry, lba_stream: &[u64]) -> bool {
+        self.process_io_cycle_qos(stream, lba_stream, 0.0).fetch
+    }
+
+    /// [`process_io_cycle`](Self::process_io_cycle) with a threshold shift;
+    /// see [`AetherLinkKernel::process_io_cycle_qos`].
+    pub fn process_io_cycle_qos(
+        &self,
+        stream: &mut StreamTelemetry,
+        lba_stream: &[u64],
+        threshold_shift: f32,
+    ) -> CycleOutcome {
+        let [first, .., last] = lba_stream else {
+            return CycleOutcome::short_window(self.epsilon(), threshold_shift);
+        };
+        let span = last.wrapping_sub(*first) as i64;
+        let telemetry = stream
+            .dsp
+            .features(span.unsigned_abs() as f32, MathMode::Fast);
+        self.cycles.fetch_add(1, Relaxed);
+        let bloch = AetherLinkKernel::bloch_state(telemetry, MathMode::Fast);
+        let phi = f32::from_bits(self.phi.load(Relaxed));
+        let (o1, o2, o3) = AetherLinkKernel::povm_measure(&bloch, phi, MathMode::Fast);
+
+        let step = self.lambda[1] * o2;
+        update(&self.phi, |phi| wrap_angle(phi, step));
+        let step = self.lambda[0] * o1;
+        let epsilon = update(&self.epsilon, |eps| {
+            (eps + step).clamp(threshold::EPSILON_CLAMP.0, threshold::EPSILON_CLAMP.1)
+        });
+
+        let p_fetch = fast_sigmoid(-(self.lambda[2] * o3 + self.bias));
+        let threshold = (epsilon + threshold_shift).clamp(0.0, 1.0);
+        let fetch = p_fetch > threshold && span != 0;
+        if fetch {
+            self.prefetches.fetch_add(1, Relaxed);
+        }
+        CycleOutcome {
+            fetch,
+            p_fetch,
+            threshold,
+            degraded: false,
+            damping: 1.0,
+        }
+    }
+
+    /// Current threshold ε.
+    #[inline]
+    pub fn epsilon(&self) -> f32 {
+        f32::from_bits(self.epsilon.load(Relaxed))
+    }
+
+    /// Current POVM basis angle φ.
+    #[inline]
+    pub fn phi(&self) -> f32 {
+        f32::from_bits(self.phi.load(Relaxed))
+    }
+
+    /// Cycles processed.
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles.load(Relaxed)
+    }
+
+    /// Prefetches triggered.
+    #[inline]
+    pub fn prefetches(&self) -> u64 {
+        self.prefetches.load(Relaxed)
+    }
+
+    /// Current parameters, learned ε and φ included.
+    pub fn config(&self) -> KernelConfig {
+        KernelConfig {
+            epsilon: self.epsilon(),
+            phi: self.phi(),
+            lambda: self.lambda,
+            bias: self.bias,
+        }
+    }
+}
+
+impl Default for AtomicAetherKernel {
+    fn default() -> Self {
+        Self::from(&KernelConfig::default())
+    }
+}
+
+impl From<&KernelConfig> for AtomicAetherKernel {
+    fn from(config: &KernelConfig) -> Self {
+        Self::new(config.epsilon, config.phi, config.lambda, config.bias)
+    }
+}
+
+/// Apply `f` to the `f32` in `cell` atomically and return the new value.
+#[inline(always)]
+fn update(cell: &AtomicU32, f: impl Fn(f32) -> f32) -> f32 {
+    let mut current = cell.load(Relaxed);
+    loop {
+        let new = f(f32::from_bits(current));
+        match cell.compare_exchange_weak(current, new.to_bits(), Relaxed, Relaxed) {
+            Ok(_) => return new,
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use core::f32::consts::PI;
+    use std::sync::Arc;
+
+    fn window(rng: &mut SplitMix64) -> [u64; 4] {
+        let base = rng.below(1 << 20);
+        let stride = rng.below(4);
+        [0, 1, 2, 3].map(|i| base + i * stride)
+    }
+
+    #[test]
+    fn test_single_thread_matches_kernel() {
+        let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05);
+        let shared = AtomicAetherKernel::from(&kernel.config());
+        let mut stream = StreamTelemetry::default();
+        let mut rng = SplitMix64::new(9);
+        for _ in 0..5_000 {
+            let w = window(&mut rng);
+            assert_eq!(
+                shared.process_io_cycle_qos(&mut stream, &w, 0.0),
+                kernel.process_io_cycle_qos(&w, 0.0)
+            );
+        }
+        assert_eq!(shared.config(), kernel.config());
+        assert_eq!(shared.cycles(), kernel.cycles);
+        assert_eq!(shared.prefetches(), kernel.prefetches);
+        assert_eq!(
+            shared.process_io_cycle(&mut stream, &[7]),
+            kernel.process_io_cycle(&[7])
+        );
+    }
+
+    #[test]
+    fn test_concurrent_stress_keeps_state_valid() {
+        const THREADS: u64 = 16;
+        const CYCLES: u64 = 20_000;
+        let kernel = Arc::new(AtomicAetherKernel::new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05));
+        let workers: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let kernel = Arc::clone(&kernel);
+                std::thread::spawn(move || {
+                    let mut stream = StreamTelemetry::default();
+                    let mut rng = SplitMix64::new(t);
+                    let mut fired = 0;
+                    for _ in 0..CYCLES {
+                        let out = kernel.process_io_cycle_qos(&mut stream, &window(&mut rng), 0.0);
+                        fired += out.fetch as u64;
+                        assert!(out.p_fetch.is_finite() && out.threshold.is_finite());
+                        let (eps, phi) = (kernel.epsilon(), kernel.phi());
+                        assert!(
+                            (threshold::EPSILON_CLAMP.0..=threshold::EPSILON_CLAMP.1)
+                                .contains(&eps),
+                            "{eps}"
+                        );
+                        assert!((0.0..=2.0 * PI).contains(&phi), "{phi}");
+                    }
+                    fired
+                })
+            })
+            .collect();
+        let fired: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+        assert_eq!(kernel.cycles(), THREADS * CYCLES);
+        assert_eq!(kernel.prefetches(), fired);
+        kernel.config().validate().unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+//! Strict math mode, for showing the fast path decides as exact math does.
+//!
+//! The decision path computes its transcendentals with the approximations
+//! in the crate's fast-math layer: a Quake-style inverse square root to
+//! normalise the Bloch vector and the spectral energy, single-precision
+//! libm elsewhere, and under the `deterministic` feature the portable
+//! polynomials.  A kernel built
+//! [`with_math_mode(MathMode::Strict)`](AetherLinkKernel::with_math_mode)
+//! runs the same code with every one of them — atan, exp in the sigmoid,
+//! sin, cos, acos, atan2, ln and the square roots — computed in double
+//! precision by libm and rounded once to `f32`.  Nothing else changes, so
+//! the two modes of one binary differ only by the approximations.
+//!
+//! [`compare_modes`] replays a trace through a fast and a strict kernel of
+//! one [`KernelConfig`] side by side and reports, as a [`ModeComparison`],
+//! how many decisions differ and how far apart `p_fetch` gets.  Differences
+//! compound: a φ a few ULP off measures differently on the next cycle.  On
+//! the standard workloads — every [`Pattern`](crate::workload::Pattern)
+//! preset under every kernel preset — the comparison stays within
+//! [`DECISION_DIVERGENCE_BOUND`] and [`P_FETCH_DEVIATION_BOUND`]: at most
+//! 0.2 % of decisions differ and `p_fetch` stays within 0.002.  Those are
+//! the figures to cite, and the tests of this module hold the crate to
+//! them.  When they were set the worst case over 200 000 accesses of each
+//! workload was 0.09 % (the database preset on hot/cold traffic, where
+//! `p_fetch` hovers at ε) and 7.7 × 10⁻⁴.
+//!
+//! Strict mode applies to [`AetherLinkKernel`] and everything built on
+//! it.  [`AtomicAetherKernel`](crate::AtomicAetherKernel) and
+//! [`KernelBank`](crate::bank::KernelBank) always run the fast path, and a
+//! [snapshot](crate::snapshot) does not record the mode.
+
+use crate::fast_math;
+use crate::{AetherLinkKernel, KernelConfig};
+use core::fmt;
+
+/// Share of decisions allowed to differ between the modes on the standard
+/// workloads.
+pub const DECISION_DIVERGENCE_BOUND: f64 = 0.002;
+
+/// Largest `|p_fetch_fast − p_fetch_strict|` allowed on the standard
+/// workloads.
+pub const P_FETCH_DEVIATION_BOUND: f32 = 0.002;
+
+/// Which implementation of the transcendentals a kernel decides with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MathMode {
+    /// The fast-path approximations.
+    #[default]
+    Fast,
+    /// Double-precision libm, rounded to `f32`.
+    Strict,
+}
+
+impl MathMode {
+    /// Arctangent.
+    #[inline(always)]
+    pub(crate) fn atan(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::fast_atanf(x),
+            Self::Strict => libm::atan(x as f64) as f32,
+        }
+    }
+
+    /// σ(x) = 1 / (1 + exp(−x)).
+    #[inline(always)]
+    pub(crate) fn sigmoid(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::fast_sigmoid(x),
+            Self::Strict => (1.0 / (1.0 + libm::exp(-(x as f64)))) as f32,
+        }
+    }
+
+    /// Sine (radians).
+    #[inline(always)]
+    pub(crate) fn sin(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::sin(x),
+            Self::Strict => libm::sin(x as f64) as f32,
+        }
+    }
+
+    /// Cosine (radians).
+    #[inline(always)]
+    pub(crate) fn cos(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::cos(x),
+            Self::Strict => libm::cos(x as f64) as f32,
+        }
+    }
+
+    /// Arccosine, NaN outside [-1, 1].
+    #[inline(always)]
+    pub(crate) fn acos(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::acos(x),
+            Self::Strict => libm::acos(x as f64) as f32,
+        }
+    }
+
+    /// Four-quadrant arctangent of `y / x`.
+    #[inline(always)]
+    pub(crate) fn atan2(self, y: f32, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::atan2(y, x),
+            Self::Strict => libm::atan2(y as f64, x as f64) as f32,
+        }
+    }
+
+    /// Natural logarithm.
+    #[inline(always)]
+    pub(crate) fn ln(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::ln(x),
+            Self::Strict => libm::log(x as f64) as f32,
+        }
+    }
+
+    /// Square root of a non-negative `x`.
+    #[inline(always)]
+    pub(crate) fn sqrt(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::fast_sqrt(x),
+            Self::Strict => libm::sqrt(x as f64) as f32,
+        }
+    }
+
+    /// `1 / sqrt(x)` for a positive `x`.
+    #[inline(always)]
+    pub(crate) fn inv_sqrt(self, x: f32) -> f32 {
+        match self {
+            Self::Fast => fast_math::fast_inv_sqrt(x),
+            Self::Strict => (1.0 / libm::sqrt(x as f64)) as f32,
+        }
+    }
+}
+
+impl fmt::Display for MathMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fast => "fast",
+            Self::Strict => "strict",
+        })
+    }
+}
+
+impl AetherLinkKernel {
+    /// This kernel, deciding with `math` from its next cycle on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::audit::MathMode;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_hft().with_math_mode(MathMode::Strict);
+    /// assert_eq!(kernel.math_mode(), MathMode::Strict);
+    /// kernel.process_io_cycle(&[100, 101, 102, 103]);
+    /// ```
+    #[inline]
+    pub fn with_math_mode(mut self, math: MathMode) -> Self {
+        self.math = math;
+        self
+    }
+
+    /// The math mode this kernel decides with.
+    #[inline]
+    pub fn math_mode(&self) -> MathMode {
+        self.math
+    }
+}
+
+/// How far a strict kernel's decisions are from a fast one's on a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeComparison {
+    /// Decision cycles run by each kernel.
+    pub cycles: u64,
+    /// Cycles on which one kernel fired and the other did not.
+    pub differing: u64,
+    /// Cycle number of the first differing decision.
+    pub first_difference: Option<u64>,
+    /// Largest `|p_fetch_fast − p_fetch_strict|` on one cycle.
+    pub max_p_fetch_deviation: f32,
+    /// Mean `|p_fetch_fast − p_fetch_strict|`.
+    pub mean_p_fetch_deviation: f32,
+    /// Prefetches the fast kernel triggered.
+    pub fast_triggers: u64,
+    /// Prefetches the strict kernel triggered.
+    pub strict_triggers: u64,
+}
+
+impl ModeComparison {
+    /// Share of cycles whose decisions differ.
+    #[inline]
+    pub fn divergence(&self) -> f64 {
+        if self.cycles == 0 {
+            0.0
+        } else {
+            self.differing as f64 / self.cycles as f64
+        }
+    }
+
+    /// Whether the comparison is within [`DECISION_DIVERGENCE_BOUND`] and
+    /// [`P_FETCH_DEVIATION_BOUND`].
+    #[inline]
+    pub fn within_bounds(&self) -> bool {
+        self.divergence() <= DECISION_DIVERGENCE_BOUND
+            && self.max_p_fetch_deviation <= P_FETCH_DEVIATION_BOUND
+    }
+}
+
+impl fmt::Display for ModeComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} decisions differ ({:.4}%), p_fetch deviation max {:.2e} mean {:.2e}, \
+             triggers fast {} strict {}",
+            self.differing,
+            self.cycles,
+            self.divergence() * 100.0,
+            self.max_p_fetch_deviation,
+            self.mean_p_fetch_deviation,
+            self.fast_triggers,
+            self.strict_triggers,
+        )
+    }
+}
+
+/// Stream `trace` through a fast and a strict kernel built from `config`
+/// (unvalidated, as [`AetherLinkKernel::new`] takes it) and compare their
+/// decisions cycle by cycle.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::audit::compare_modes;
+/// use aether_link::AetherLinkKernel;
+///
+/// let cmp = compare_modes(0..10_000u64, &AetherLinkKernel::new_gaming().config());
+/// assert_eq!(cmp.cycles, 9_999);
+/// assert!(cmp.within_bounds(), "{cmp}");
+/// ```
+pub fn compare_modes<I>(trace: I, config: &KernelConfig) -> ModeComparison
+where
+    I: IntoIterator<Item = u64>,
+{
+    let kernel = |math| {
+        AetherLinkKernel::new(config.epsilon, config.phi, config.lambda, config.bias)
+            .with_math_mode(math)
+    };
+    let (mut fast, mut strict) = (kernel(MathMode::Fast), kernel(MathMode::Strict));
+    let mut out = ModeComparison::default();
+    let mut deviation_sum = 0.0f64;
+    for lba in trace {
+        let (Some(a), Some(b)) = (fast.push_lba_qos(lba, 0.0), strict.push_lba_qos(lba, 0.0))
+        else {
+            continue;
+        };
+        out.cycles += 1;
+        if a.fetch != b.fetch {
+            out.differing += 1;
+            out.first_difference.get_or_insert(out.cycles);
+        }
+        let deviation = (a.p_fetch - b.p_fetch).abs();
+        out.max_p_fetch_deviation = out.max_p_fetch_deviation.max(deviation);
+        deviation_sum += deviation as f64;
+    }
+    out.fast_triggers = fast.prefetches;
+    out.strict_triggers = strict.prefetches;
+    if out.cycles > 0 {
+        out.mean_p_fetch_deviation = (deviation_sum / out.cycles as f64) as f32;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+    use crate::Preset;
+
+    #[test]
+    fn test_standard_workloads_within_bounds() {
+        let patterns = [
+            Pattern::SEQUENTIAL,
+            Pattern::RANDOM,
+            Pattern::BURSTY,
+            Pattern::HFT_TICK,
+            Pattern::Zipfian {
+                n_blocks: 100_000,
+                theta: 0.99,
+            },
+            Pattern::Hotspot {
+                n_blocks: 100_000,
+                hot_fraction: 0.1,
+                hot_access_fraction: 0.9,
+            },
+        ];
+        for preset in Preset::ALL {
+            for pattern in patterns {
+                let trace = pattern.workload(7).take(50_000);
+                let cmp = compare_modes(trace, &preset.kernel().config());
+                assert_eq!(cmp.cycles, 49_999);
+                assert!(cmp.within_bounds(), "{preset} on {}: {cmp}", pattern.name());
+                // The modes are never bit-identical: the comparison sees
+                // the approximations at all.
+                assert!(cmp.mean_p_fetch_deviation > 0.0, "{preset}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_mode_is_the_default_path() {
+        let trace = || Pattern::BURSTY.workload(3).take(5_000);
+        let mut plain = AetherLinkKernel::new_gaming();
+        let mut fast = AetherLinkKernel::new_gaming().with_math_mode(MathMode::Fast);
+        let mut strict = AetherLinkKernel::new_gaming().with_math_mode(MathMode::Strict);
+        assert_eq!(plain.math_mode(), MathMode::Fast);
+        for lba in trace() {
+            let a = plain.push_lba_qos(lba, 0.0);
+            assert_eq!(a, fast.push_lba_qos(lba, 0.0));
+            strict.push_lba_qos(lba, 0.0);
+        }
+        assert_eq!(plain.phi.to_bits(), fast.phi.to_bits());
+        assert_ne!(plain.phi.to_bits(), strict.phi.to_bits());
+        assert_eq!(strict.clone().math_mode(), MathMode::Strict);
+
+        let empty = compare_modes(core::iter::empty(), &KernelConfig::default());
+        assert_eq!(empty, ModeComparison::default());
+        assert!(empty.within_bounds());
+        assert_eq!(MathMode::Strict.to_string(), "strict");
+    }
+}
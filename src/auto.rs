@@ -0,0 +1,463 @@
+//! Preset switching driven by the workload classifier.
+//!
+//! [`AutoKernel`] classifies the stream after every access (see
+//! [`classify`](crate::classify)).  Once a new class has held for
+//! [`AutoOptions::confirm`] consecutive accesses it becomes the current
+//! class, and the kernel takes the parameters of the preset mapped to it
+//! (see [`Preset::apply`]): ε, φ, λ, the bias, the window, the horizon, the
+//! target lead and burst damping.  Counters, telemetry and history carry
+//! over.  With a non-zero [`AutoOptions::transition`], ε walks linearly to
+//! the preset's value instead of jumping, so the fire rate does not fall
+//! off a cliff at the switch.
+//!
+//! A [`ProfileStore`] set with [`AutoKernel::set_profiles`] overrides the
+//! preset for every class it has a profile of the same name for.
+//!
+//! Every confirmed class change is reported as a [`SwitchEvent`] in the
+//! return value of [`AutoKernel::push_lba`].
+//!
+//! Classification sorts the history window on every access, which costs
+//! far more than the decision itself; use this for adaptivity, not in the
+//! tightest loops.
+
+use crate::classify::{ClassifierThresholds, WorkloadClass};
+use crate::prefetcher::{PrefetchDecision, Prefetcher};
+use crate::profile::ProfileStore;
+use crate::{AetherLinkKernel, CycleOutcome, Preset};
+
+/// Which preset each workload class runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassPresets {
+    /// For [`WorkloadClass::Sequential`].
+    pub sequential: Preset,
+    /// For [`WorkloadClass::Strided`].
+    pub strided: Preset,
+    /// For [`WorkloadClass::Random`].
+    pub random: Preset,
+    /// For [`WorkloadClass::Bursty`].
+    pub bursty: Preset,
+    /// For [`WorkloadClass::Mixed`].
+    pub mixed: Preset,
+}
+
+impl ClassPresets {
+    /// The preset for `class`, `None` for [`WorkloadClass::Unknown`] and
+    /// [`WorkloadClass::ReRead`], which never prefetches whatever the
+    /// preset.
+    pub fn get(&self, class: WorkloadClass) -> Option<Preset> {
+        match class {
+            WorkloadClass::Sequential => Some(self.sequential),
+            WorkloadClass::Strided => Some(self.strided),
+            WorkloadClass::Random => Some(self.random),
+            WorkloadClass::Bursty => Some(self.bursty),
+            WorkloadClass::Mixed => Some(self.mixed),
+            WorkloadClass::ReRead | WorkloadClass::Unknown => None,
+        }
+    }
+}
+
+impl Default for ClassPresets {
+    /// Aggressive on scans, conservative on random and short-run traffic.
+    fn default() -> Self {
+        Self {
+            sequential: Preset::Gaming,
+            strided: Preset::Gaming,
+            random: Preset::Hft,
+            bursty: Preset::Hft,
+            mixed: Preset::Default,
+        }
+    }
+}
+
+/// Knobs for [`AutoKernel`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoOptions {
+    /// Consecutive accesses a new class must hold before it is acted on.
+    /// Short excursions (the few `Mixed` windows around a phase boundary)
+    /// are ignored.  0 acts on the first classification.
+    pub confirm: u32,
+    /// Accesses over which ε moves to the new preset's value; 0 jumps.
+    pub transition: u32,
+    /// Class-to-preset mapping.
+    pub presets: ClassPresets,
+    /// Classifier thresholds.
+    pub thresholds: ClassifierThresholds,
+}
+
+impl Default for AutoOptions {
+    fn default() -> Self {
+        Self {
+            confirm: 32,
+            transition: 16,
+            presets: ClassPresets::default(),
+            thresholds: ClassifierThresholds::default(),
+        }
+    }
+}
+
+/// A confirmed change of workload class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwitchEvent {
+    /// Access index (0-based) at which the change was confirmed.
+    pub at: u64,
+    /// Class before the change.
+    pub from: WorkloadClass,
+    /// Class after the change.
+    pub to: WorkloadClass,
+    /// Preset whose parameters the kernel took, `None` when the new class
+    /// maps to the preset already in use or a profile was applied.
+    pub preset: Option<Preset>,
+    /// `true` when the parameters came from a profile (see
+    /// [`AutoKernel::set_profiles`]).
+    pub profile: bool,
+}
+
+/// Result of one [`AutoKernel::push_lba`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoCycle {
+    /// The kernel's decision; `None` during warm-up.
+    pub outcome: Option<CycleOutcome>,
+    /// The class change confirmed by this access, if any.
+    pub switch: Option<SwitchEvent>,
+}
+
+/// ε interpolation in progress.
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    from: f32,
+    to: f32,
+    step: u32,
+}
+
+/// A kernel that re-parameterises itself when the workload changes class.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::auto::{AutoKernel, AutoOptions};
+/// use aether_link::classify::WorkloadClass;
+///
+/// let mut auto = AutoKernel::new(Default::default(), AutoOptions::default());
+/// for lba in 0..200 {
+///     if let Some(event) = auto.push_lba(lba).switch {
+///         assert_eq!(event.from, WorkloadClass::Unknown);
+///         assert_eq!(event.to, WorkloadClass::Sequential);
+///     }
+/// }
+/// assert_eq!(auto.class(), WorkloadClass::Sequential);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutoKernel {
+    kernel: AetherLinkKernel,
+    opts: AutoOptions,
+    class: WorkloadClass,
+    preset: Option<Preset>,
+    /// Class waiting for confirmation and how long it has held.
+    candidate: (WorkloadClass, u32),
+    transition: Option<Transition>,
+    profiles: Option<ProfileStore>,
+    accesses: u64,
+    /// Confirmed class changes so far.
+    pub switches: u64,
+}
+
+impl AutoKernel {
+    /// Wrap `kernel`.  Its parameters stay in use until the first class is
+    /// confirmed.
+    pub fn new(kernel: AetherLinkKernel, opts: AutoOptions) -> Self {
+        Self {
+            kernel,
+            opts,
+            class: WorkloadClass::Unknown,
+            preset: None,
+            candidate: (WorkloadClass::Unknown, 0),
+            transition: None,
+            profiles: None,
+            accesses: 0,
+            switches: 0,
+        }
+    }
+
+    /// Feed one LBA: step any ε transition, run the kernel, then update the
+    /// classification.
+    pub fn push_lba(&mut self, lba: u64) -> AutoCycle {
+        if let Some(t) = &mut self.transition {
+            t.step += 1;
+            let k = t.step as f32 / self.opts.transition.max(1) as f32;
+            self.kernel.epsilon = t.from + (t.to - t.from) * k.min(1.0);
+            if t.step >= self.opts.transition {
+                self.transition = None;
+            }
+        }
+        let outcome = self.kernel.push_lba_qos(lba, 0.0);
+        let at = self.accesses;
+        self.accesses += 1;
+
+        let seen = self.kernel.classification(&self.opts.thresholds).class;
+        if seen == self.class {
+            self.candidate = (seen, 0);
+            return AutoCycle {
+                outcome,
+                switch: None,
+            };
+        }
+        let held = if seen == self.candidate.0 {
+            self.candidate.1 + 1
+        } else {
+            1
+        };
+        self.candidate = (seen, held);
+        let switch = (held > self.opts.confirm).then(|| self.switch_to(seen, at));
+        AutoCycle { outcome, switch }
+    }
+
+    fn switch_to(&mut self, class: WorkloadClass, at: u64) -> SwitchEvent {
+        let from = self.class;
+        self.class = class;
+        self.candidate = (class, 0);
+        self.switches += 1;
+
+        let profile = self
+            .profiles
+            .as_ref()
+            .and_then(|store| store.get(class.name()))
+            .copied();
+        let epsilon = self.kernel.epsilon;
+        let (applied, preset) = match profile {
+            Some(config) => {
+                self.preset = None;
+                self.kernel.set_config(&config);
+                (true, None)
+            }
+            None => {
+                let preset = self
+                    .opts
+                    .presets
+                    .get(class)
+                    .filter(|&p| self.preset != Some(p));
+                if let Some(p) = preset {
+                    self.preset = preset;
+                    p.apply(&mut self.kernel);
+                }
+                (preset.is_some(), preset)
+            }
+        };
+        if applied && self.opts.transition > 0 {
+            let to = self.kernel.epsilon;
+            self.kernel.epsilon = epsilon;
+            self.transition = Some(Transition {
+                from: epsilon,
+                to,
+                step: 0,
+            });
+        }
+        SwitchEvent {
+            at,
+            from,
+            to: class,
+            preset,
+            profile: profile.is_some(),
+        }
+    }
+
+    /// Prefer the profile named after a class (`"sequential"`, `"random"`,
+    /// …; see [`WorkloadClass::name`]) over its preset when switching to
+    /// it.  Classes without a profile keep using the preset mapping.
+    pub fn set_profiles(&mut self, profiles: ProfileStore) {
+        self.profiles = Some(profiles);
+    }
+
+    /// The confirmed workload class.
+    #[inline]
+    pub fn class(&self) -> WorkloadClass {
+        self.class
+    }
+
+    /// Preset whose parameters are in use, `None` before the first switch
+    /// and while a profile is.
+    #[inline]
+    pub fn preset(&self) -> Option<Preset> {
+        self.preset
+    }
+
+    /// `true` while ε is still moving towards the current preset.
+    #[inline]
+    pub fn in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// The underlying kernel.
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+}
+
+impl Default for AutoKernel {
+    fn default() -> Self {
+        Self::new(AetherLinkKernel::default(), AutoOptions::default())
+    }
+}
+
+impl Prefetcher for AutoKernel {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        match self.push_lba(lba).outcome {
+            Some(out) if out.fetch => PrefetchDecision::trigger(None),
+            _ => PrefetchDecision::SKIP,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "aether-auto"
+    }
+
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        self.kernel.record_pollution(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::DEFAULT_WINDOW;
+    use crate::workload::{Pattern, Phased};
+    use crate::KernelConfig;
+
+    #[test]
+    fn test_one_switch_per_phase_near_boundary() {
+        let opts = AutoOptions::default();
+        let workload = Phased::new(9)
+            .then(Pattern::SEQUENTIAL, 2_000)
+            .then(Pattern::RANDOM, 2_000)
+            .then(Pattern::BURSTY, 2_000)
+            .then(Pattern::Sequential { stride: 8 }, 2_000)
+            .then_at(Pattern::SEQUENTIAL, 2_000, 1 << 30);
+        let expected = [
+            WorkloadClass::Sequential,
+            WorkloadClass::Random,
+            WorkloadClass::Bursty,
+            WorkloadClass::Strided,
+            WorkloadClass::Sequential,
+        ];
+        let mut auto = AutoKernel::new(AetherLinkKernel::default(), opts);
+        let events: Vec<SwitchEvent> = workload
+            .iter()
+            .filter_map(|lba| auto.push_lba(lba).switch)
+            .collect();
+
+        assert_eq!(events.len(), expected.len(), "{events:?}");
+        let latest = DEFAULT_WINDOW as u64 + opts.confirm as u64;
+        for ((event, boundary), class) in events.iter().zip(workload.boundaries()).zip(expected) {
+            assert_eq!(event.to, class, "{event:?}");
+            assert!(
+                (boundary..=boundary + latest).contains(&event.at),
+                "{event:?} vs boundary {boundary}"
+            );
+        }
+        assert_eq!(events[1].preset, Some(Preset::Hft));
+        assert_eq!(events[2].preset, None, "random and bursty share a preset");
+        assert_eq!(auto.kernel().cycles, workload.len() - 1);
+    }
+
+    #[test]
+    fn test_transition_blends_epsilon() {
+        let opts = AutoOptions {
+            confirm: 0,
+            ..AutoOptions::default()
+        };
+        let mut auto = AutoKernel::new(AetherLinkKernel::new_hft(), opts);
+        let mut lba = 0;
+        while auto.push_lba(lba).switch.is_none() {
+            lba += 1;
+        }
+        assert_eq!(auto.preset(), Some(Preset::Gaming));
+        assert_eq!(auto.kernel().lambda, Preset::Gaming.kernel().lambda);
+        assert!(auto.in_transition());
+
+        // With λ₁ zeroed the cycles leave ε alone, exposing the ramp.
+        auto.kernel.lambda[0] = 0.0;
+        let from = auto.kernel().epsilon;
+        let to = Preset::Gaming.kernel().epsilon;
+        for step in 1..=opts.transition {
+            lba += 1;
+            auto.push_lba(lba);
+            let expected = from + (to - from) * step as f32 / opts.transition as f32;
+            assert!(
+                (auto.kernel().epsilon - expected).abs() < 1e-5,
+                "step {step}"
+            );
+        }
+        assert!(!auto.in_transition());
+    }
+
+    #[test]
+    fn test_switch_applies_the_whole_preset() {
+        let opts = AutoOptions {
+            presets: ClassPresets {
+                sequential: Preset::MlTraining,
+                ..ClassPresets::default()
+            },
+            transition: 0,
+            ..AutoOptions::default()
+        };
+        let mut auto = AutoKernel::new(AetherLinkKernel::default(), opts);
+        let workload = Phased::new(3)
+            .then(Pattern::SEQUENTIAL, 1_000)
+            .then(Pattern::RANDOM, 1_000);
+        let mut events = Vec::new();
+        for lba in workload.iter() {
+            if let Some(event) = auto.push_lba(lba).switch {
+                if event.preset == Some(Preset::MlTraining) {
+                    assert_eq!(auto.kernel().horizon(), Some(64));
+                    assert_eq!(auto.kernel().window(), 64);
+                    assert_eq!(auto.kernel().config(), Preset::MlTraining.kernel().config());
+                }
+                events.push(event);
+            }
+        }
+        assert_eq!(events.len(), 2, "{events:?}");
+        assert_eq!(events[0].preset, Some(Preset::MlTraining));
+        // Leaving it turns the horizon off and restores the window.
+        assert_eq!(events[1].preset, Some(Preset::Hft));
+        assert_eq!(auto.kernel().horizon(), None);
+        assert_eq!(auto.kernel().window(), DEFAULT_WINDOW);
+    }
+
+    #[test]
+    fn test_profiles_override_presets() {
+        let oltp = KernelConfig {
+            epsilon: 0.7,
+            lambda: [0.0, 0.05, 0.1],
+            ..KernelConfig::default()
+        };
+        let mut store = ProfileStore::new();
+        store.insert("random", oltp).unwrap();
+        let opts = AutoOptions {
+            transition: 0,
+            ..AutoOptions::default()
+        };
+        let mut auto = AutoKernel::new(AetherLinkKernel::default(), opts);
+        auto.set_profiles(store);
+        let workload = Phased::new(2)
+            .then(Pattern::SEQUENTIAL, 500)
+            .then(Pattern::RANDOM, 500);
+        let events: Vec<SwitchEvent> = workload
+            .iter()
+            .filter_map(|lba| auto.push_lba(lba).switch)
+            .collect();
+        assert_eq!(events.len(), 2, "{events:?}");
+        assert_eq!(
+            (events[0].preset, events[0].profile),
+            (Some(Preset::Gaming), false)
+        );
+        assert_eq!((events[1].preset, events[1].profile), (None, true));
+        assert_eq!(auto.preset(), None);
+        // λ₁ = 0 froze ε at the profile's value.
+        assert_eq!(auto.kernel().epsilon, 0.7);
+    }
+}
@@ -0,0 +1,223 @@
+//! Byte-rate limit for prefetch traffic.
+//!
+//! Prefetches compete with demand reads for the same device.  A
+//! [`BandwidthBudget`] is a token bucket in bytes: it holds at most
+//! `burst` bytes, is refilled either at a fixed rate per caller tick
+//! ([`tick`](BandwidthBudget::tick)) or by explicit
+//! [`refill`](BandwidthBudget::refill) calls driven by the caller's own
+//! clock, and is consulted by [`PrefetchQueue::pop_within`] before each
+//! dispatch.  A plan that does not fit stays at the head of the queue until
+//! enough bytes have accumulated, so prefetches are deferred rather than
+//! lost.  Over any `w` ticks at most `burst + rate · w` bytes go out, as
+//! long as no single plan exceeds `burst`; a larger plan is let through
+//! once the bucket is full and the overdraft is repaid by later refills.
+//!
+//! The decision path can see the budget too: [`threshold_shift`] raises
+//! the fetch threshold as the bucket empties, for use with
+//! [`AetherLinkKernel::process_io_cycle_qos`].
+//!
+//! [`PrefetchQueue::pop_within`]: crate::queue::PrefetchQueue::pop_within
+//! [`threshold_shift`]: BandwidthBudget::threshold_shift
+//! [`AetherLinkKernel::process_io_cycle_qos`]: crate::AetherLinkKernel::process_io_cycle_qos
+
+use crate::prefetcher::PrefetchPlan;
+
+/// Default device block size in bytes.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
+/// Token bucket of prefetch bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::bandwidth::BandwidthBudget;
+/// use aether_link::{AetherLinkKernel, PrefetchPlan, PrefetchQueue};
+///
+/// // 64 MiB/s with a 1 ms tick, 4 MiB of burst.
+/// let mut budget = BandwidthBudget::new(4 << 20).with_rate(64 << 10);
+/// let mut queue = PrefetchQueue::new(64);
+/// let mut kernel = AetherLinkKernel::default();
+///
+/// for lba in (0..4096u64).step_by(256) {
+///     let shift = budget.threshold_shift(0.2);
+///     if kernel.process_io_cycle_qos(&[lba, lba + 8], shift).fetch {
+///         queue.push(PrefetchPlan::new(lba + 8, 256));
+///     }
+///     while let Some(plan) = queue.pop_within(&mut budget) {
+///         // issue `plan`
+/// #       let _ = plan;
+///     }
+///     budget.tick(1);
+/// }
+/// assert!(budget.consumed_bytes() <= (4 << 20) + 16 * (64 << 10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BandwidthBudget {
+    burst: u64,
+    /// Bytes available; negative while a plan wider than the burst is
+    /// being repaid.
+    tokens: i64,
+    rate: u64,
+    block_size: u64,
+    consumed: u64,
+    deferred: u64,
+}
+
+impl BandwidthBudget {
+    /// Bucket of at most `burst` bytes (at least 1), starting full, with
+    /// no automatic refill.
+    pub fn new(burst: u64) -> Self {
+        let burst = burst.clamp(1, i64::MAX as u64);
+        Self {
+            burst,
+            tokens: burst as i64,
+            rate: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            consumed: 0,
+            deferred: 0,
+        }
+    }
+
+    /// Add `bytes_per_tick` for every tick passed to [`tick`](Self::tick).
+    pub fn with_rate(mut self, bytes_per_tick: u64) -> Self {
+        self.rate = bytes_per_tick;
+        self
+    }
+
+    /// Bytes per block when sizing plans (default [`DEFAULT_BLOCK_SIZE`]).
+    pub fn with_block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Refill for `ticks` ticks at the configured rate.
+    #[inline]
+    pub fn tick(&mut self, ticks: u64) {
+        self.refill(self.rate.saturating_mul(ticks));
+    }
+
+    /// Add `bytes`, up to the burst size.
+    #[inline]
+    pub fn refill(&mut self, bytes: u64) {
+        let bytes = bytes.min(i64::MAX as u64) as i64;
+        self.tokens = self.tokens.saturating_add(bytes).min(self.burst as i64);
+    }
+
+    /// Bytes `plan` covers.
+    #[inline]
+    pub fn plan_bytes(&self, plan: PrefetchPlan) -> u64 {
+        plan.len.saturating_mul(self.block_size)
+    }
+
+    /// Take the bytes for `plan` if they are available.  A plan wider than
+    /// the burst is allowed once the bucket is full.
+    pub fn try_consume(&mut self, plan: PrefetchPlan) -> bool {
+        let bytes = self.plan_bytes(plan);
+        let needed = bytes.min(self.burst) as i64;
+        if self.tokens < needed {
+            return false;
+        }
+        self.tokens -= bytes.min(i64::MAX as u64) as i64;
+        self.consumed = self.consumed.saturating_add(bytes);
+        true
+    }
+
+    /// Count `plan` as deferred for lack of budget.
+    #[inline]
+    pub(crate) fn defer(&mut self, plan: PrefetchPlan) {
+        self.deferred = self.deferred.saturating_add(self.plan_bytes(plan));
+    }
+
+    /// Bytes available now (0 while repaying an overdraft).
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.tokens.max(0) as u64
+    }
+
+    /// Fill level in [0, 1].
+    #[inline]
+    pub fn fill(&self) -> f32 {
+        self.remaining() as f32 / self.burst as f32
+    }
+
+    /// Threshold shift rising from 0 with a full bucket to `max_shift` with
+    /// an empty one.  Pass it to
+    /// [`process_io_cycle_qos`](crate::AetherLinkKernel::process_io_cycle_qos)
+    /// to make the kernel more reluctant as the budget runs out.
+    #[inline]
+    pub fn threshold_shift(&self, max_shift: f32) -> f32 {
+        max_shift * (1.0 - self.fill())
+    }
+
+    /// Bytes of dispatched plans.
+    #[inline]
+    pub fn consumed_bytes(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Bytes of plans held back at least once, each plan counted once.
+    #[inline]
+    pub fn deferred_bytes(&self) -> u64 {
+        self.deferred
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::PrefetchQueue;
+
+    #[test]
+    fn test_burst_never_exceeds_rate() {
+        const BURST: u64 = 256 << 10;
+        const RATE: u64 = 32 << 10;
+        let mut budget = BandwidthBudget::new(BURST).with_rate(RATE);
+        let mut queue = PrefetchQueue::new(4_096).with_recent(0);
+        for i in 0..400 {
+            // 64 .. 256 KiB each, far more than the rate.
+            queue.push(PrefetchPlan::new(i * 1_000, 16 + i % 48));
+        }
+
+        let mut per_tick = Vec::new();
+        while !queue.is_empty() {
+            let mut sent = 0;
+            while let Some(plan) = queue.pop_within(&mut budget) {
+                sent += budget.plan_bytes(plan);
+            }
+            per_tick.push(sent);
+            budget.tick(1);
+        }
+        for w in 1..=64 {
+            let worst = per_tick.windows(w).map(|s| s.iter().sum::<u64>()).max();
+            assert!(
+                worst.unwrap_or(0) <= BURST + RATE * (w as u64 - 1),
+                "window {w}"
+            );
+        }
+        let total: u64 = per_tick.iter().sum();
+        assert_eq!(total, budget.consumed_bytes());
+        assert_eq!(queue.stats().accepted, 400, "nothing dropped");
+        assert!(budget.deferred_bytes() > 0);
+        assert!(budget.deferred_bytes() <= total);
+    }
+
+    #[test]
+    fn test_explicit_refill_and_oversized_plans() {
+        let mut budget = BandwidthBudget::new(8 * 4096);
+        let big = PrefetchPlan::new(0, 20);
+        assert!(budget.try_consume(big), "full bucket lets it through");
+        assert_eq!(budget.remaining(), 0);
+        budget.refill(8 * 4096);
+        assert!(
+            !budget.try_consume(PrefetchPlan::new(0, 1)),
+            "still in debt"
+        );
+        budget.refill(5 * 4096);
+        assert!(budget.try_consume(PrefetchPlan::new(0, 1)));
+        budget.refill(u64::MAX);
+        assert_eq!(budget.remaining(), 8 * 4096);
+        assert_eq!(budget.threshold_shift(0.4), 0.0);
+        assert!(budget.try_consume(PrefetchPlan::new(0, 6)));
+        assert!((budget.threshold_shift(0.4) - 0.3).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,296 @@
+//! Up to 64 independent kernels decided together.
+//!
+//! Running one [`AetherLinkKernel`] per symbol means 64 passes through the
+//! whole decision path per tick, each on its own scattered struct.  A
+//! [`KernelBank`] keeps the parameters in structure-of-arrays layout — one
+//! cache-aligned array per parameter — and runs each stage of the cycle
+//! (angle encoding, observables, adaptation, sigmoid, threshold) across
+//! every kernel before moving to the next, through the slice forms in
+//! `fast_math`.  The adaptation and comparison stages are plain arithmetic
+//! over the arrays and vectorise; the transcendental stages are loops over
+//! the scalar functions, so every kernel decides bit-for-bit as a scalar
+//! kernel fed the same span would.
+//!
+//! Each kernel is fed the span of its window (`last − first`, as
+//! [`AetherLinkKernel::process_io_cycle`] computes it) and keeps its own
+//! telemetry.  Only the open-loop cycle is batched: QoS shifts, feedback
+//! thresholds, idle decay, anomaly detection and the watchdog stay with
+//! [`AetherLinkKernel`].
+
+use crate::config::KernelConfig;
+use crate::fast_math;
+use crate::{threshold, wrap_angle, AetherLinkKernel, MathMode, TelemetryDSP};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Kernels per bank: one bit each in the trigger mask.
+pub const MAX_KERNELS: usize = 64;
+
+/// One value per kernel, on whole cache lines.
+#[derive(Debug, Clone, Copy)]
+#[repr(align(64))]
+struct Lanes([f32; MAX_KERNELS]);
+
+impl Lanes {
+    const ZERO: Self = Self([0.0; MAX_KERNELS]);
+}
+
+/// Structure-of-arrays batch of kernels.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::bank::KernelBank;
+/// use aether_link::KernelConfig;
+///
+/// let mut bank = KernelBank::new(&[KernelConfig::default(); 8]);
+/// let spans = [16.0, 3.0, 900.0, 16.0, 0.0, 64.0, 16.0, 1.0];
+/// let fired = bank.process_all(&spans);
+/// assert!(fired < 1 << 8);
+/// assert_eq!(bank.cycles(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KernelBank {
+    len: usize,
+    epsilon: Lanes,
+    phi: Lanes,
+    lambda: [Lanes; 3],
+    bias: Lanes,
+    dsp: Vec<TelemetryDSP>,
+    cycles: u64,
+    prefetches: Vec<u64>,
+}
+
+impl KernelBank {
+    /// One kernel per config, in order.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than [`MAX_KERNELS`] configs.
+    pub fn new(configs: &[KernelConfig]) -> Self {
+        assert!(
+            configs.len() <= MAX_KERNELS,
+            "a bank holds at most {MAX_KERNELS} kernels, got {}",
+            configs.len()
+        );
+        let mut bank = Self {
+            len: configs.len(),
+            epsilon: Lanes::ZERO,
+            phi: Lanes::ZERO,
+            lambda: [Lanes::ZERO; 3],
+            bias: Lanes::ZERO,
+            dsp: vec![TelemetryDSP::default(); configs.len()],
+            cycles: 0,
+            prefetches: vec![0; configs.len()],
+        };
+        for (k, c) in configs.iter().enumerate() {
+            bank.epsilon.0[k] = c.epsilon;
+            bank.phi.0[k] = c.phi;
+            for (lanes, &l) in bank.lambda.iter_mut().zip(&c.lambda) {
+                lanes.0[k] = l;
+            }
+            bank.bias.0[k] = c.bias;
+        }
+        bank
+    }
+
+    /// Run one cycle of every kernel; kernel `k` sees window span
+    /// `spans[k]`.  Returns the trigger mask: bit `k` is set if kernel `k`
+    /// fired.
+    ///
+    /// # Panics
+    ///
+    /// If `spans.len()` differs from [`len`](Self::len).
+    // `max`/`min` rather than `clamp`, to match the scalar decision bit for bit.
+    #[allow(clippy::manual_clamp)]
+    pub fn process_all(&mut self, spans: &[f32]) -> u64 {
+        assert_eq!(spans.len(), self.len, "one span per kernel");
+        let n = self.len;
+        self.cycles += 1;
+
+        // Telemetry: per-kernel running state, scattered back into lanes.
+        let mut f = [Lanes::ZERO; 6];
+        for (k, (dsp, &span)) in self.dsp.iter_mut().zip(spans).enumerate() {
+            for (lanes, value) in f.iter_mut().zip(dsp.features(span, MathMode::Fast)) {
+                lanes.0[k] = value;
+            }
+        }
+
+        // Angle encoding, as `AetherLinkKernel::prepare_quantum_state`.
+        let scales = [64.0, 32.0, 128.0, 16.0];
+        let mut t = f;
+        for (lanes, scale) in t.iter_mut().zip(scales) {
+            lanes.0[..n].iter_mut().for_each(|x| *x /= scale);
+        }
+        t[5].0[..n].iter_mut().for_each(|x| *x = (*x - 1.0) / 4.0);
+        for lanes in &mut t {
+            fast_math::atan_slice(&mut lanes.0[..n]);
+            lanes.0[..n].iter_mut().for_each(|x| *x *= 2.0);
+        }
+        let w = [0.5_f32, 0.3, 0.1, 0.05, 0.03, 0.02];
+        let (mut theta, mut phi_az) = (Lanes::ZERO, Lanes::ZERO);
+        for k in 0..n {
+            let tk = t.map(|lanes| lanes.0[k]);
+            theta.0[k] = (tk[0] * w[0]
+                + tk[1] * w[1]
+                + tk[2] * w[2]
+                + tk[3] * w[3]
+                + tk[4] * w[4]
+                + tk[5] * w[5])
+                / (w[0] + w[1] + w[2] + w[3] + w[4] + w[5]);
+            phi_az.0[k] = (tk[0] * 0.6 + tk[1] * 0.3 + tk[3] * 0.1) / (w[0] + w[1] + w[3]);
+        }
+        let (mut sin_theta, mut cos_theta) = (theta, theta);
+        for k in 0..n {
+            sin_theta.0[k] *= 0.5;
+            cos_theta.0[k] *= 0.5;
+        }
+        fast_math::sin_slice(&mut sin_theta.0[..n]);
+        fast_math::cos_slice(&mut cos_theta.0[..n]);
+        let (mut sin_phi, mut cos_phi) = (phi_az, phi_az);
+        fast_math::sin_slice(&mut sin_phi.0[..n]);
+        fast_math::cos_slice(&mut cos_phi.0[..n]);
+        let (mut rx, mut ry) = (Lanes::ZERO, Lanes::ZERO);
+        for k in 0..n {
+            let (x, y, z) = (
+                sin_theta.0[k] * cos_phi.0[k],
+                sin_theta.0[k] * sin_phi.0[k],
+                cos_theta.0[k],
+            );
+            let r2 = x * x + y * y + z * z;
+            let r_inv = fast_math::select(r2 > 1e-8, fast_math::fast_inv_sqrt(r2.max(1e-8)), 1.0);
+            rx.0[k] = x * r_inv;
+            ry.0[k] = y * r_inv;
+        }
+
+        // Observables, as `AetherLinkKernel::povm_measure`.
+        let mut theta = ry;
+        fast_math::acos_slice(&mut theta.0[..n]);
+        let mut phi_az = ry;
+        fast_math::atan2_slice(&mut phi_az.0[..n], &rx.0[..n]);
+        let (mut e1, mut e2, mut e3) = (Lanes::ZERO, Lanes::ZERO, Lanes::ZERO);
+        for k in 0..n {
+            e1.0[k] = theta.0[k] + self.phi.0[k];
+            e2.0[k] = theta.0[k] * 0.5 - self.phi.0[k];
+            e3.0[k] = theta.0[k] * phi_az.0[k];
+        }
+        fast_math::cos_slice(&mut e1.0[..n]);
+        fast_math::sin_slice(&mut e2.0[..n]);
+        fast_math::cos_slice(&mut e3.0[..n]);
+
+        // Adaptation and fetch probability.
+        let (lo, hi) = threshold::EPSILON_CLAMP;
+        let mut p_fetch = Lanes::ZERO;
+        for k in 0..n {
+            self.phi.0[k] = wrap_angle(self.phi.0[k], self.lambda[1].0[k] * e2.0[k]);
+            let eps = self.epsilon.0[k] + self.lambda[0].0[k] * e1.0[k];
+            self.epsilon.0[k] = eps.max(lo).min(hi);
+            p_fetch.0[k] = -(self.lambda[2].0[k] * e3.0[k] + self.bias.0[k]);
+        }
+        fast_math::sigmoid_slice(&mut p_fetch.0[..n]);
+
+        let mut mask = 0u64;
+        for (k, &span) in spans.iter().enumerate() {
+            let threshold = self.epsilon.0[k].max(0.0).min(1.0);
+            // A zero span is a re-read and never fires (see `degenerate`).
+            let fetch = (p_fetch.0[k] > threshold) & (span != 0.0);
+            self.prefetches[k] += fetch as u64;
+            mask |= (fetch as u64) << k;
+        }
+        mask
+    }
+
+    /// Number of kernels.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// `true` for a bank without kernels.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Cycles run, the same for every kernel.
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Prefetches triggered by kernel `k`.
+    #[inline]
+    pub fn prefetches(&self, k: usize) -> u64 {
+        self.prefetches[k]
+    }
+
+    /// Current parameters of kernel `k`, learned ε and φ included.
+    pub fn config(&self, k: usize) -> KernelConfig {
+        assert!(k < self.len, "kernel {k} out of {}", self.len);
+        KernelConfig {
+            epsilon: self.epsilon.0[k],
+            phi: self.phi.0[k],
+            lambda: self.lambda.map(|l| l.0[k]),
+            bias: self.bias.0[k],
+        }
+    }
+}
+
+impl From<&[AetherLinkKernel]> for KernelBank {
+    /// A bank with the parameters of `kernels`; telemetry and counters
+    /// start fresh.
+    fn from(kernels: &[AetherLinkKernel]) -> Self {
+        let configs: Vec<KernelConfig> = kernels.iter().map(|k| k.config()).collect();
+        Self::new(&configs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use crate::tune::ParamSpace;
+
+    #[test]
+    fn test_matches_scalar_kernels() {
+        let configs = ParamSpace::default_ranges().candidates(MAX_KERNELS, 3);
+        let mut bank = KernelBank::new(&configs);
+        let mut scalar: Vec<AetherLinkKernel> =
+            configs.iter().map(|c| c.build().unwrap()).collect();
+        let mut rng = SplitMix64::new(1);
+        let mut fired = 0;
+        for _ in 0..3_000 {
+            let spans: Vec<u64> = (0..MAX_KERNELS)
+                .map(|_| [0, 1, 8, rng.below(1 << 24)][rng.below(4) as usize])
+                .collect();
+            let as_f32: Vec<f32> = spans.iter().map(|&s| s as f32).collect();
+            let mask = bank.process_all(&as_f32);
+            for (k, kernel) in scalar.iter_mut().enumerate() {
+                let fetch = kernel.process_io_cycle(&[0, spans[k]]);
+                assert_eq!(mask >> k & 1 == 1, fetch, "kernel {k}");
+                let (a, b) = (bank.config(k), kernel.config());
+                assert_eq!(a.epsilon.to_bits(), b.epsilon.to_bits(), "kernel {k}");
+                assert_eq!(a.phi.to_bits(), b.phi.to_bits(), "kernel {k}");
+            }
+            fired += mask.count_ones();
+        }
+        assert!(fired > 0 && fired < 3_000 * 64, "{fired}");
+        for (k, kernel) in scalar.iter().enumerate() {
+            assert_eq!(bank.prefetches(k), kernel.prefetches);
+            assert_eq!(bank.config(k), kernel.config());
+        }
+        assert_eq!(bank.cycles(), 3_000);
+    }
+
+    #[test]
+    fn test_partial_bank_and_limits() {
+        let kernels = [AetherLinkKernel::new_hft(), AetherLinkKernel::default()];
+        let mut bank = KernelBank::from(&kernels[..]);
+        assert_eq!(bank.len(), 2);
+        let mask = bank.process_all(&[16.0, 16.0]);
+        assert_eq!(mask >> 2, 0, "no bits beyond the bank");
+        assert!(KernelBank::new(&[]).is_empty());
+        let too_many = std::panic::catch_unwind(|| KernelBank::new(&[KernelConfig::default(); 65]));
+        assert!(too_many.is_err());
+    }
+}
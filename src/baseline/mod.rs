@@ -0,0 +1,13 @@
+//! Classic prefetchers to measure the kernel against.
+//!
+//! Every baseline implements [`Prefetcher`](crate::Prefetcher), so
+//! [`replay`](crate::replay::replay) and the tuning/evaluation tooling run
+//! them exactly like the kernel.
+
+pub mod ghb;
+pub mod nextn;
+pub mod stride;
+
+pub use ghb::GhbPrefetcher;
+pub use nextn::NextNPrefetcher;
+pub use stride::StridePrefetcher;
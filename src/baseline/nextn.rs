@@ -0,0 +1,117 @@
+//! Next-N sequential read-ahead.
+//!
+//! The simplest baseline, and roughly what an OS page cache does: after a
+//! read, fetch the `n` blocks that follow it.  The ramp-up variant mimics
+//! Linux read-ahead, which only starts once it has seen the stream behave
+//! sequentially.
+
+use crate::prefetcher::{PrefetchDecision, PrefetchPlan, Prefetcher};
+
+/// Default number of blocks fetched per trigger.
+pub const DEFAULT_N: u32 = 8;
+
+/// Fetch the `n` blocks after each access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextNPrefetcher {
+    /// Blocks fetched per trigger, `[lba + 1, lba + n]`.
+    pub n: u32,
+    ramp_up: bool,
+    last: Option<u64>,
+    /// Consecutive sequential accesses seen.
+    run: u32,
+}
+
+impl Default for NextNPrefetcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_N)
+    }
+}
+
+impl NextNPrefetcher {
+    /// Fire on every access.
+    pub fn new(n: u32) -> Self {
+        Self {
+            n,
+            ramp_up: false,
+            last: None,
+            run: 0,
+        }
+    }
+
+    /// Fire only after two consecutive sequential accesses.
+    ///
+    /// An access is sequential when it lands at most `n` blocks past the
+    /// previous one, i.e. inside the window a trigger there would have
+    /// fetched; this tolerates multi-block requests, whose next start is
+    /// past `lba + 1`.
+    pub fn ramp_up(n: u32) -> Self {
+        Self {
+            ramp_up: true,
+            ..Self::new(n)
+        }
+    }
+
+    /// `true` for the ramp-up variant.
+    #[inline]
+    pub fn is_ramp_up(&self) -> bool {
+        self.ramp_up
+    }
+}
+
+impl Prefetcher for NextNPrefetcher {
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        let sequential = self
+            .last
+            .replace(lba)
+            .is_some_and(|last| lba > last && lba - last <= u64::from(self.n));
+        self.run = if sequential {
+            self.run.saturating_add(1)
+        } else {
+            0
+        };
+
+        if self.n == 0 || (self.ramp_up && self.run < 2) {
+            return PrefetchDecision::SKIP;
+        }
+        PrefetchDecision::trigger(Some(PrefetchPlan::new(
+            lba.saturating_add(1),
+            u64::from(self.n),
+        )))
+    }
+
+    fn name(&self) -> &str {
+        if self.ramp_up {
+            "next-n-ramp"
+        } else {
+            "next-n"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_always_fires_next_n() {
+        let mut p = NextNPrefetcher::new(4);
+        for lba in [10, 500, 3] {
+            assert_eq!(
+                p.observe(lba),
+                PrefetchDecision::trigger(Some(PrefetchPlan::new(lba + 1, 4)))
+            );
+        }
+        assert!(!NextNPrefetcher::new(0).observe(1).fire);
+    }
+
+    #[test]
+    fn test_ramp_up_waits_for_two_sequential_accesses() {
+        let mut p = NextNPrefetcher::ramp_up(8);
+        let fired: Vec<bool> = [100, 101, 109, 110, 500, 501, 502]
+            .iter()
+            .map(|&lba| p.observe(lba).fire)
+            .collect();
+        assert_eq!(fired, [false, false, true, true, false, false, true]);
+        assert_eq!(p.name(), "next-n-ramp");
+    }
+}
@@ -0,0 +1,209 @@
+//! Table-based stride prefetcher.
+//!
+//! The storage equivalent of the reference-prediction table found in CPU
+//! caches.  Without a program counter to key on, streams are tagged by the
+//! address region they touch: each region owns one slot of a small
+//! direct-mapped table holding the last address, the last stride and a
+//! 2-bit saturating confidence counter.  A stream crossing into the next
+//! region carries its slot along.
+
+use crate::prefetcher::{PrefetchDecision, PrefetchPlan, Prefetcher};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Default number of table slots.
+pub const DEFAULT_TABLE_SIZE: usize = 64;
+/// Default prefetch distance in strides.
+pub const DEFAULT_DEGREE: u32 = 4;
+/// Default confidence needed to fire.
+pub const DEFAULT_THRESHOLD: u8 = 1;
+/// Default region size, as a power of two in blocks (64 Ki blocks).
+pub const DEFAULT_REGION_BITS: u32 = 16;
+/// Largest value of the 2-bit confidence counter.
+pub const MAX_CONFIDENCE: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    tag: u64,
+    last: u64,
+    stride: i64,
+    confidence: u8,
+}
+
+/// Direct-mapped stride table.
+#[derive(Debug, Clone)]
+pub struct StridePrefetcher {
+    table: Vec<Option<Entry>>,
+    degree: u32,
+    threshold: u8,
+    region_bits: u32,
+}
+
+impl Default for StridePrefetcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_TABLE_SIZE)
+    }
+}
+
+impl StridePrefetcher {
+    /// Prefetcher with `table_size` slots (at least 1).
+    pub fn new(table_size: usize) -> Self {
+        Self {
+            table: vec![None; table_size.max(1)],
+            degree: DEFAULT_DEGREE,
+            threshold: DEFAULT_THRESHOLD,
+            region_bits: DEFAULT_REGION_BITS,
+        }
+    }
+
+    /// Predict `degree` strides ahead of the current access (at least 1).
+    pub fn with_degree(mut self, degree: u32) -> Self {
+        self.degree = degree.max(1);
+        self
+    }
+
+    /// Fire once the confidence counter reaches `threshold`, clamped to
+    /// `1..=`[`MAX_CONFIDENCE`].  The counter gains one per repeated
+    /// stride, so the default of 1 fires on the third access of a stream.
+    pub fn with_threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold.clamp(1, MAX_CONFIDENCE);
+        self
+    }
+
+    /// Tag streams by regions of `2^bits` blocks (at most 63).
+    pub fn with_region_bits(mut self, bits: u32) -> Self {
+        self.region_bits = bits.min(63);
+        self
+    }
+
+    /// Number of table slots.
+    #[inline]
+    pub fn table_size(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Forget all tracked streams.
+    pub fn reset(&mut self) {
+        self.table.fill(None);
+    }
+
+    #[inline]
+    fn slot(&self, tag: u64) -> usize {
+        (tag % self.table.len() as u64) as usize
+    }
+
+    /// The entry tracking `tag`, migrating one from the preceding region
+    /// when a stream has just crossed into this one.
+    fn lookup(&mut self, tag: u64) -> Option<Entry> {
+        let here = self.slot(tag);
+        match self.table[here] {
+            Some(e) if e.tag == tag => return Some(e),
+            _ => {}
+        }
+        let prev_tag = tag.checked_sub(1)?;
+        let prev = self.slot(prev_tag);
+        match self.table[prev] {
+            Some(e) if e.tag == prev_tag => {
+                self.table[prev] = None;
+                Some(e)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Prefetcher for StridePrefetcher {
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        let tag = lba >> self.region_bits;
+        let slot = self.slot(tag);
+        let Some(mut entry) = self.lookup(tag) else {
+            self.table[slot] = Some(Entry {
+                tag,
+                last: lba,
+                stride: 0,
+                confidence: 0,
+            });
+            return PrefetchDecision::SKIP;
+        };
+
+        let stride = lba.wrapping_sub(entry.last) as i64;
+        if stride == entry.stride && stride != 0 {
+            entry.confidence = (entry.confidence + 1).min(MAX_CONFIDENCE);
+        } else {
+            entry.stride = stride;
+            entry.confidence = 0;
+        }
+        entry.tag = tag;
+        entry.last = lba;
+        self.table[slot] = Some(entry);
+
+        if entry.confidence < self.threshold {
+            return PrefetchDecision::SKIP;
+        }
+        let distance = i128::from(entry.stride) * i128::from(self.degree);
+        match u64::try_from(i128::from(lba) + distance) {
+            Ok(target) => PrefetchDecision::trigger(Some(PrefetchPlan::new(target, 1))),
+            Err(_) => PrefetchDecision::SKIP,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "stride"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learns_stride_in_three_observations() {
+        let mut p = StridePrefetcher::default();
+        assert!(!p.observe(100).fire);
+        assert!(!p.observe(104).fire);
+        let d = p.observe(108);
+        assert!(d.fire);
+        assert_eq!(
+            d.target,
+            Some(PrefetchPlan::new(108 + 4 * DEFAULT_DEGREE as u64, 1))
+        );
+
+        // Descending strides predict downwards.
+        let mut p = StridePrefetcher::default().with_degree(2);
+        for lba in [900, 890] {
+            assert!(!p.observe(lba).fire);
+        }
+        assert_eq!(p.observe(880).target, Some(PrefetchPlan::new(860, 1)));
+    }
+
+    #[test]
+    fn test_resets_on_stride_change() {
+        let mut p = StridePrefetcher::default().with_threshold(2);
+        for lba in [0, 4, 8] {
+            assert!(!p.observe(lba).fire);
+        }
+        assert!(p.observe(12).fire);
+        // A new stride drops confidence back to zero and must be relearned.
+        assert!(!p.observe(20).fire);
+        assert!(!p.observe(28).fire);
+        assert_eq!(p.observe(36).target, Some(PrefetchPlan::new(68, 1)));
+    }
+
+    #[test]
+    fn test_stream_survives_region_crossing() {
+        let mut p = StridePrefetcher::new(8).with_region_bits(4);
+        let fired: Vec<bool> = (0..10).map(|i| p.observe(i * 3).fire).collect();
+        assert_eq!(fired[..2], [false, false]);
+        assert!(fired[2..].iter().all(|&f| f), "{fired:?}");
+    }
+
+    #[test]
+    fn test_quiet_on_random_input() {
+        let mut p = StridePrefetcher::default();
+        let mut rng = crate::rng::SplitMix64::new(7);
+        let fired = (0..10_000)
+            .filter(|_| p.observe(rng.below(1_000_000)).fire)
+            .count();
+        assert!(fired < 10, "fired {fired} times on random input");
+    }
+}
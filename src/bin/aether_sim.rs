@@ -0,0 +1,880 @@
+//! `aether-sim` — evaluate the AETHER-Link kernel on recorded I/O traces.
+//!
+//! ```text
+//! aether-sim replay --trace foo.csv --format csv --preset hft --window 64 \
+//!     --block-size 4096 --simulate-cache 256MiB --out report.json \
+//!     --export-decisions decisions.jsonl
+//! aether-sim replay --synthetic zipf:1000000:0.99 --events 200000
+//! aether-sim soak --preset hft --time-budget 3600 --save soak.json
+//! ```
+//!
+//! Build with: cargo build --features cli --bin aether-sim
+
+use aether_link::eval::EvalReport;
+use aether_link::replay::{self, DecisionRecord, ReplayOptions, ReplaySummary};
+use aether_link::sim::CacheSim;
+use aether_link::soak::{Soak, SoakCheckpoint, SoakOptions};
+use aether_link::stream::{DEFAULT_WINDOW, MAX_WINDOW};
+use aether_link::trace::{self, IoEvent, TraceFormat};
+use aether_link::tune::{self, EvalSetup, ParamSpace, Split};
+use aether_link::workload::Pattern;
+use aether_link::{AetherLinkKernel, Preset, ProfileStore};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: aether-sim <COMMAND> [OPTIONS]
+
+Commands:
+  replay    Replay a trace through a kernel and print a summary
+  tune      Search kernel parameters against a trace and save the best
+  soak      Run a kernel for a long time, checking its invariants
+
+Run 'aether-sim <COMMAND> --help' for command options.";
+
+const REPLAY_USAGE: &str = "\
+Usage: aether-sim replay (--trace <PATH> | --synthetic <PATTERN>) [OPTIONS]
+
+Options:
+  --trace <PATH>               Trace file to replay
+  --format <csv|blkparse>      Trace encoding [default: from extension, else csv]
+  --synthetic <PATTERN>        Generate the trace instead, e.g. zipf:1000000:0.99
+                               (sequential, random, bursty, hft-tick, zipf, hotspot)
+  --events <N>                 Synthetic trace length [default: 100000]
+  --synthetic-seed <N>         Synthetic trace seed [default: 0]
+  --preset <NAME>              Kernel preset: default, hft, gaming, database,
+                               ml-training, video-streaming, wsl2
+                               [default: default]
+  --profiles <PATH>            Profile store (TOML) to take '--profile' from
+  --profile <NAME>             Start from a stored profile instead of a preset
+  --window <N>                 LBAs per decision window, 2-64
+                               [default: the preset's, 16 for a profile]
+  --block-size <BYTES>         Block size used to convert offsets [default: 4096]
+  --prefetch-blocks <N>        Blocks prefetched per untargeted trigger; kernel
+                               triggers carry their own extent [default: 8]
+  --simulate-cache <SIZE>      Simulate an LRU cache of SIZE (e.g. 256MiB)
+  --out <PATH>                 Write the JSON report to PATH
+  --export-decisions <PATH>    Write every decision as JSON lines to PATH
+  -h, --help                   Print this help";
+
+const TUNE_USAGE: &str = "\
+Usage: aether-sim tune (--trace <PATH> | --synthetic <PATTERN>) [OPTIONS]
+
+Options:
+  --trace <PATH>               Trace file to tune against
+  --format <csv|blkparse>      Trace encoding [default: from extension, else csv]
+  --synthetic <PATTERN>        Generate the trace instead (see 'replay --help')
+  --events <N>                 Synthetic trace length [default: 100000]
+  --synthetic-seed <N>         Synthetic trace seed [default: 0]
+  --objective <NAME>           hit-rate or frugal [default: hit-rate]
+  --budget <N>                 Candidates to evaluate [default: 100]
+  --strategy <random|grid>     Search strategy [default: random]
+  --seed <N>                   Random search seed [default: 0]
+  --holdout <FRACTION>         Tune on the rest, report the best config on
+                               the last FRACTION of the trace
+  --window <N>                 LBAs per decision window, 2-64 [default: 16]
+  --block-size <BYTES>         Block size used to convert offsets [default: 4096]
+  --prefetch-blocks <N>        Blocks prefetched per untargeted trigger; kernel
+                               triggers carry their own extent [default: 8]
+  --simulate-cache <SIZE>      Simulated LRU cache size [default: 64MiB]
+  --out <PATH>                 Best config file, .toml or .json [default: aether-tuned.toml]
+  --profiles <PATH>            Profile store (TOML) to add the best config to
+  --save-profile <NAME>        Store the best config as NAME in '--profiles'
+                               instead of writing '--out'
+  -h, --help                   Print this help";
+
+const SOAK_USAGE: &str = "\
+Usage: aether-sim soak [OPTIONS]
+
+Options:
+  --preset <NAME>              Kernel preset (see 'replay --help') [default: default]
+  --cycles <N>                 Decision cycles in total, counting resumed ones
+                               [default: 1000000000]
+  --check-every <N>            Cycles between invariant checks [default: 1048576]
+  --samples <N>                State samples kept in the summary [default: 64]
+  --pinned-checks <N>          Check intervals with ε held on a clamp bound
+                               that fail the run, 0 for never [default: 64]
+  --time-budget <SECS>         Stop at the first check after SECS seconds
+  --seed <N>                   Workload seed [default: 0]
+  --resume <PATH>              Carry on from a checkpoint saved with '--save'
+                               (replaces '--preset'; keep the same '--seed')
+  --save <PATH>                Write a checkpoint to resume from to PATH
+  -h, --help                   Print this help";
+
+/// Cache size used by `tune` when `--simulate-cache` is not given.
+const DEFAULT_TUNE_CACHE: u64 = 64 << 20;
+
+/// Train-minus-test objective above which `tune --holdout` warns.
+const HOLDOUT_MAX_GAP: f64 = 0.05;
+
+/// Failure modes, mapped to distinct exit codes.
+enum CliError {
+    /// Bad invocation (exit 2); the string is shown above a usage hint.
+    Usage(String),
+    /// The command itself failed (exit 1).
+    Runtime(String),
+}
+
+impl CliError {
+    fn runtime(context: impl std::fmt::Display, err: impl std::fmt::Display) -> Self {
+        Self::Runtime(format!("{context}: {err}"))
+    }
+}
+
+/// Minimal `--key value` argument bag.
+///
+/// Options are consumed as they are read so that anything left over at the
+/// end can be reported as unknown.
+struct Args {
+    items: Vec<String>,
+}
+
+impl Args {
+    fn new(items: Vec<String>) -> Self {
+        Self { items }
+    }
+
+    fn flag(&mut self, names: &[&str]) -> bool {
+        let before = self.items.len();
+        self.items.retain(|a| !names.contains(&a.as_str()));
+        self.items.len() != before
+    }
+
+    fn value(&mut self, name: &str) -> Result<Option<String>, CliError> {
+        let Some(pos) = self.items.iter().position(|a| a == name) else {
+            return Ok(None);
+        };
+        if pos + 1 >= self.items.len() || self.items[pos + 1].starts_with("--") {
+            return Err(CliError::Usage(format!("option '{name}' requires a value")));
+        }
+        let value = self.items.remove(pos + 1);
+        self.items.remove(pos);
+        Ok(Some(value))
+    }
+
+    fn parsed<T>(&mut self, name: &str) -> Result<Option<T>, CliError>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.value(name)? {
+            None => Ok(None),
+            Some(raw) => raw
+                .parse()
+                .map(Some)
+                .map_err(|e| CliError::Usage(format!("invalid value '{raw}' for '{name}': {e}"))),
+        }
+    }
+
+    fn finish(self) -> Result<(), CliError> {
+        match self.items.first() {
+            None => Ok(()),
+            Some(extra) if extra.starts_with('-') => {
+                Err(CliError::Usage(format!("unknown option '{extra}'")))
+            }
+            Some(extra) => Err(CliError::Usage(format!("unexpected argument '{extra}'"))),
+        }
+    }
+}
+
+/// Parse a byte size such as `4096`, `64KiB`, `256MiB`, `1G` or `10MB`.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("'{s}' is not a size (try e.g. 256MiB)"))?;
+    let mult: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        other => return Err(format!("unknown size unit '{other}'")),
+    };
+    n.checked_mul(mult)
+        .ok_or_else(|| format!("size '{s}' is too large"))
+}
+
+/// Guess the trace format from the file extension.
+fn infer_format(path: &Path) -> TraceFormat {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("blktrace") | Some("blkparse") => TraceFormat::Blkparse,
+        _ => TraceFormat::Csv,
+    }
+}
+
+/// Where the events come from.
+enum TraceSource {
+    File {
+        path: PathBuf,
+        format: TraceFormat,
+    },
+    Synthetic {
+        spec: String,
+        pattern: Pattern,
+        events: usize,
+        seed: u64,
+    },
+}
+
+impl TraceSource {
+    fn parse(args: &mut Args) -> Result<Self, CliError> {
+        let path = args.value("--trace")?;
+        let format = args.parsed::<TraceFormat>("--format")?;
+        let spec = args.value("--synthetic")?;
+        let events = args.parsed::<usize>("--events")?;
+        let seed = args.parsed::<u64>("--synthetic-seed")?;
+        match (path, spec) {
+            (Some(_), Some(_)) => Err(CliError::Usage(
+                "'--trace' and '--synthetic' cannot be combined".into(),
+            )),
+            (None, None) => Err(CliError::Usage(
+                "missing required option '--trace <PATH>' (or '--synthetic <PATTERN>')".into(),
+            )),
+            (Some(path), None) => {
+                if events.is_some() || seed.is_some() {
+                    return Err(CliError::Usage(
+                        "'--events' and '--synthetic-seed' need '--synthetic'".into(),
+                    ));
+                }
+                let path = PathBuf::from(path);
+                let format = format.unwrap_or_else(|| infer_format(&path));
+                Ok(Self::File { path, format })
+            }
+            (None, Some(spec)) => {
+                if format.is_some() {
+                    return Err(CliError::Usage(
+                        "'--format' cannot be combined with '--synthetic'".into(),
+                    ));
+                }
+                let pattern = spec.parse().map_err(|e| {
+                    CliError::Usage(format!("invalid value '{spec}' for '--synthetic': {e}"))
+                })?;
+                Ok(Self::Synthetic {
+                    spec,
+                    pattern,
+                    events: events.unwrap_or(100_000),
+                    seed: seed.unwrap_or(0),
+                })
+            }
+        }
+    }
+
+    /// Trace path, or `synthetic:<PATTERN>`.
+    fn label(&self) -> String {
+        match self {
+            Self::File { path, .. } => path.display().to_string(),
+            Self::Synthetic { spec, .. } => format!("synthetic:{spec}"),
+        }
+    }
+
+    fn format_name(&self) -> String {
+        match self {
+            Self::File { format, .. } => format.to_string(),
+            Self::Synthetic { .. } => "synthetic".to_owned(),
+        }
+    }
+}
+
+/// Options shared by every command that replays a trace.
+struct TraceArgs {
+    source: TraceSource,
+    /// `None` keeps the kernel's own window.
+    window: Option<usize>,
+    block_size: u64,
+    prefetch_blocks: u32,
+    cache_bytes: Option<u64>,
+}
+
+impl TraceArgs {
+    fn parse(args: &mut Args) -> Result<Self, CliError> {
+        let source = TraceSource::parse(args)?;
+        let window: Option<usize> = args.parsed("--window")?;
+        if window.is_some_and(|w| !(2..=MAX_WINDOW).contains(&w)) {
+            return Err(CliError::Usage(format!(
+                "'--window' must be between 2 and {MAX_WINDOW}"
+            )));
+        }
+        let block_size = match args.value("--block-size")? {
+            Some(raw) => parse_size(&raw).map_err(CliError::Usage)?,
+            None => 4096,
+        };
+        if block_size == 0 {
+            return Err(CliError::Usage("'--block-size' must be non-zero".into()));
+        }
+        let prefetch_blocks = args.parsed("--prefetch-blocks")?.unwrap_or(8u32);
+        let cache_bytes = match args.value("--simulate-cache")? {
+            Some(raw) => Some(parse_size(&raw).map_err(CliError::Usage)?),
+            None => None,
+        };
+        Ok(Self {
+            source,
+            window,
+            block_size,
+            prefetch_blocks,
+            cache_bytes,
+        })
+    }
+
+    fn load(&self) -> Result<Vec<IoEvent>, CliError> {
+        match &self.source {
+            TraceSource::File { path, format } => {
+                let file = File::open(path)
+                    .map_err(|e| CliError::runtime(format!("cannot open {}", path.display()), e))?;
+                trace::parse(BufReader::new(file), *format, self.block_size)
+                    .map_err(|e| CliError::runtime(path.display(), e))
+            }
+            TraceSource::Synthetic {
+                pattern,
+                events,
+                seed,
+                ..
+            } => Ok(pattern
+                .workload(*seed)
+                .take(*events)
+                .map(IoEvent::read)
+                .collect()),
+        }
+    }
+
+    fn replay_options(&self) -> ReplayOptions {
+        ReplayOptions {
+            prefetch_blocks: self.prefetch_blocks,
+            block_size: self.block_size,
+        }
+    }
+
+    fn cache_blocks(&self) -> Option<u64> {
+        self.cache_bytes.map(|b| (b / self.block_size).max(1))
+    }
+}
+
+/// `--profiles <PATH>` with the name given alongside it, both or neither.
+fn profile_args(args: &mut Args, name_option: &str) -> Result<Option<(PathBuf, String)>, CliError> {
+    let path = args.value("--profiles")?.map(PathBuf::from);
+    let name = args.value(name_option)?;
+    match (path, name) {
+        (Some(path), Some(name)) => Ok(Some((path, name))),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(CliError::Usage(format!(
+            "'--profiles' needs '{name_option}'"
+        ))),
+        (None, Some(_)) => Err(CliError::Usage(format!(
+            "'{name_option}' needs '--profiles <PATH>'"
+        ))),
+    }
+}
+
+fn load_profiles(path: &Path) -> Result<ProfileStore, CliError> {
+    ProfileStore::load(path).map_err(|e| CliError::runtime(path.display(), e))
+}
+
+struct ReplayArgs {
+    common: TraceArgs,
+    preset: Preset,
+    /// Profile store and name, replacing the preset.
+    profile: Option<(PathBuf, String)>,
+    out: Option<PathBuf>,
+    export_decisions: Option<PathBuf>,
+}
+
+impl ReplayArgs {
+    fn parse(mut args: Args) -> Result<Option<Self>, CliError> {
+        if args.flag(&["-h", "--help"]) {
+            return Ok(None);
+        }
+        let common = TraceArgs::parse(&mut args)?;
+        let preset = args.parsed("--preset")?;
+        let profile = profile_args(&mut args, "--profile")?;
+        if preset.is_some() && profile.is_some() {
+            return Err(CliError::Usage(
+                "'--preset' and '--profile' cannot be combined".into(),
+            ));
+        }
+        let out = args.value("--out")?.map(PathBuf::from);
+        let export_decisions = args.value("--export-decisions")?.map(PathBuf::from);
+        args.finish()?;
+        Ok(Some(Self {
+            common,
+            preset: preset.unwrap_or(Preset::Default),
+            profile,
+            out,
+            export_decisions,
+        }))
+    }
+}
+
+/// What `tune` maximises.
+#[derive(Clone, Copy)]
+enum Objective {
+    HitRate,
+    Frugal,
+}
+
+impl std::str::FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hit-rate" => Ok(Self::HitRate),
+            "frugal" => Ok(Self::Frugal),
+            other => Err(format!(
+                "unknown objective '{other}' (expected hit-rate or frugal)"
+            )),
+        }
+    }
+}
+
+impl Objective {
+    fn score(self, report: &EvalReport) -> f64 {
+        match self {
+            Self::HitRate => tune::hit_rate(report),
+            Self::Frugal => tune::frugal_hit_rate(report),
+        }
+    }
+}
+
+struct TuneArgs {
+    common: TraceArgs,
+    objective: Objective,
+    budget: usize,
+    seed: u64,
+    grid: bool,
+    /// Share of the trace held out for testing.
+    holdout: Option<f64>,
+    out: PathBuf,
+    /// Profile store and name to save the best config under.
+    save_profile: Option<(PathBuf, String)>,
+}
+
+impl TuneArgs {
+    fn parse(mut args: Args) -> Result<Option<Self>, CliError> {
+        if args.flag(&["-h", "--help"]) {
+            return Ok(None);
+        }
+        let mut common = TraceArgs::parse(&mut args)?;
+        // Every tuning objective is a cache metric.
+        common.cache_bytes.get_or_insert(DEFAULT_TUNE_CACHE);
+        let objective = args.parsed("--objective")?.unwrap_or(Objective::HitRate);
+        let budget = args.parsed("--budget")?.unwrap_or(100usize);
+        if budget == 0 {
+            return Err(CliError::Usage("'--budget' must be at least 1".into()));
+        }
+        let seed = args.parsed("--seed")?.unwrap_or(0u64);
+        let grid = match args.value("--strategy")?.as_deref() {
+            None | Some("random") => false,
+            Some("grid") => true,
+            Some(other) => {
+                return Err(CliError::Usage(format!(
+                    "unknown strategy '{other}' (expected random or grid)"
+                )))
+            }
+        };
+        let holdout = args.parsed::<f64>("--holdout")?;
+        if holdout.is_some_and(|h| !(h > 0.0 && h < 1.0)) {
+            return Err(CliError::Usage(
+                "'--holdout' must be between 0 and 1".into(),
+            ));
+        }
+        let out = args.value("--out")?.map(PathBuf::from);
+        let save_profile = profile_args(&mut args, "--save-profile")?;
+        if out.is_some() && save_profile.is_some() {
+            return Err(CliError::Usage(
+                "'--out' and '--save-profile' cannot be combined".into(),
+            ));
+        }
+        let out = out.unwrap_or_else(|| PathBuf::from("aether-tuned.toml"));
+        args.finish()?;
+        Ok(Some(Self {
+            common,
+            objective,
+            budget,
+            seed,
+            grid,
+            holdout,
+            out,
+            save_profile,
+        }))
+    }
+}
+
+struct SoakArgs {
+    preset: Preset,
+    opts: SoakOptions,
+    resume: Option<PathBuf>,
+    save: Option<PathBuf>,
+}
+
+impl SoakArgs {
+    fn parse(mut args: Args) -> Result<Option<Self>, CliError> {
+        if args.flag(&["-h", "--help"]) {
+            return Ok(None);
+        }
+        let preset = args.parsed("--preset")?;
+        let resume = args.value("--resume")?.map(PathBuf::from);
+        if preset.is_some() && resume.is_some() {
+            return Err(CliError::Usage(
+                "'--preset' and '--resume' cannot be combined".into(),
+            ));
+        }
+        let defaults = SoakOptions::default();
+        let opts = SoakOptions {
+            cycles: args.parsed("--cycles")?.unwrap_or(defaults.cycles),
+            check_every: args
+                .parsed("--check-every")?
+                .unwrap_or(defaults.check_every),
+            samples: args.parsed("--samples")?.unwrap_or(defaults.samples),
+            pinned_checks: args
+                .parsed("--pinned-checks")?
+                .unwrap_or(defaults.pinned_checks),
+            time_budget: args
+                .parsed::<f64>("--time-budget")?
+                .map(|secs| {
+                    Duration::try_from_secs_f64(secs).map_err(|_| {
+                        CliError::Usage("'--time-budget' must be a number of seconds".into())
+                    })
+                })
+                .transpose()?,
+            seed: args.parsed("--seed")?.unwrap_or(defaults.seed),
+        };
+        if opts.check_every == 0 {
+            return Err(CliError::Usage("'--check-every' must be at least 1".into()));
+        }
+        let save = args.value("--save")?.map(PathBuf::from);
+        args.finish()?;
+        Ok(Some(Self {
+            preset: preset.unwrap_or(Preset::Default),
+            opts,
+            resume,
+            save,
+        }))
+    }
+}
+
+/// JSON report written by `--out`.
+#[derive(serde::Serialize)]
+struct Report<'a> {
+    trace: String,
+    format: String,
+    preset: Preset,
+    profile: Option<&'a str>,
+    window: usize,
+    block_size: u64,
+    prefetch_blocks: u32,
+    cache_blocks: Option<u64>,
+    summary: &'a ReplaySummary,
+    cycles: u64,
+    final_epsilon: f32,
+    final_phi: f32,
+    hit_rate: Option<f64>,
+}
+
+/// One line of `--export-decisions`: the decision plus the kernel state
+/// right after it.
+#[derive(serde::Serialize)]
+struct ExportedDecision<'a> {
+    #[serde(flatten)]
+    decision: &'a DecisionRecord,
+    epsilon: f32,
+    phi: f32,
+}
+
+fn print_summary(
+    summary: &ReplaySummary,
+    kernel: &AetherLinkKernel,
+    args: &ReplayArgs,
+    cache_blocks: Option<u64>,
+) {
+    let row = |label: &str, value: String| println!("│ {label:<14} │ {value:>14} │");
+    println!("┌────────────────┬────────────────┐");
+    match &args.profile {
+        Some((_, name)) => row("Profile", name.clone()),
+        None => row("Preset", args.preset.to_string()),
+    }
+    row("Events", summary.events.to_string());
+    row("Cycles", kernel.cycles.to_string());
+    row("Triggers", summary.triggers.to_string());
+    row("Ratio", format!("{:.1}%", summary.ratio * 100.0));
+    row("Final ε", format!("{:.4}", kernel.epsilon));
+    row("Final φ", format!("{:.4}", kernel.phi));
+    if let (Some(stats), Some(blocks)) = (summary.cache, cache_blocks) {
+        row("Cache blocks", blocks.to_string());
+        row("Hits", stats.hits.to_string());
+        row("Misses", stats.misses.to_string());
+        row("Hit rate", format!("{:.1}%", stats.hit_rate() * 100.0));
+    }
+    println!("└────────────────┴────────────────┘");
+}
+
+fn run_replay(args: ReplayArgs) -> Result<(), CliError> {
+    let common = &args.common;
+    let events = common.load()?;
+    let cache_blocks = common.cache_blocks();
+    let mut cache = cache_blocks.map(|blocks| CacheSim::new(blocks as usize));
+    let mut kernel = match &args.profile {
+        Some((path, name)) => load_profiles(path)?
+            .require(name)
+            .and_then(|config| config.build())
+            .map_err(|e| CliError::runtime(path.display(), e))?,
+        None => args.preset.kernel(),
+    };
+    if let Some(window) = common.window {
+        kernel.set_window(window);
+    }
+    let opts = common.replay_options();
+
+    let mut decisions = match &args.export_decisions {
+        Some(path) => Some(BufWriter::new(File::create(path).map_err(|e| {
+            CliError::runtime(format!("cannot create {}", path.display()), e)
+        })?)),
+        None => None,
+    };
+    let mut write_err = None;
+    let summary = replay::replay(
+        &mut kernel,
+        events,
+        &opts,
+        cache.as_mut(),
+        |rec: &DecisionRecord, kernel: &AetherLinkKernel| {
+            if let (Some(w), None) = (decisions.as_mut(), write_err.as_ref()) {
+                let line = ExportedDecision {
+                    decision: rec,
+                    epsilon: kernel.epsilon,
+                    phi: kernel.phi,
+                };
+                let res = serde_json::to_writer(&mut *w, &line)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| w.write_all(b"\n"));
+                write_err = res.err();
+            }
+        },
+    );
+    if let Some(mut w) = decisions {
+        let path = args.export_decisions.as_deref().unwrap_or(Path::new(""));
+        if let Some(e) = write_err {
+            return Err(CliError::runtime(format!("writing {}", path.display()), e));
+        }
+        w.flush()
+            .map_err(|e| CliError::runtime(format!("writing {}", path.display()), e))?;
+    }
+
+    print_summary(&summary, &kernel, &args, cache_blocks);
+
+    if let Some(path) = &args.out {
+        let report = Report {
+            trace: common.source.label(),
+            format: common.source.format_name(),
+            preset: args.preset,
+            profile: args.profile.as_ref().map(|(_, name)| name.as_str()),
+            window: kernel.window(),
+            block_size: common.block_size,
+            prefetch_blocks: common.prefetch_blocks,
+            cache_blocks,
+            summary: &summary,
+            cycles: kernel.cycles,
+            final_epsilon: kernel.epsilon,
+            final_phi: kernel.phi,
+            hit_rate: summary.cache.map(|c| c.hit_rate()),
+        };
+        let file = File::create(path)
+            .map_err(|e| CliError::runtime(format!("cannot create {}", path.display()), e))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &report)
+            .map_err(|e| CliError::runtime(format!("writing {}", path.display()), e))?;
+    }
+    Ok(())
+}
+
+fn run_tune(args: TuneArgs) -> Result<(), CliError> {
+    let common = &args.common;
+    let events = common.load()?;
+    let setup = EvalSetup {
+        window: common.window.unwrap_or(DEFAULT_WINDOW),
+        replay: common.replay_options(),
+        cache_blocks: common.cache_blocks().unwrap_or(1) as usize,
+    };
+    let space = if args.grid {
+        ParamSpace::default_grid()
+    } else {
+        ParamSpace::default_ranges()
+    };
+    let objective = args.objective;
+    let (train, test) = tune::split(&events, &Split::Ratio(1.0 - args.holdout.unwrap_or(0.0)));
+    let results = tune::search(
+        train,
+        |s| objective.score(s),
+        &space,
+        &setup,
+        args.budget,
+        args.seed,
+    );
+    let Some(best) = results.first() else {
+        return Err(CliError::Runtime("no valid candidate was evaluated".into()));
+    };
+
+    println!(
+        "Evaluated {} candidates on {} events ({} cache blocks)",
+        results.len(),
+        train.len(),
+        setup.cache_blocks
+    );
+    println!("┌──────┬──────────┬────────┬────────┬────────┬────────┬────────┬────────┬──────────┬─────────┐");
+    println!("│ Rank │  Score   │   ε    │   φ    │   λ₁   │   λ₂   │   λ₃   │  bias  │ Hit rate │  Ratio  │");
+    println!("├──────┼──────────┼────────┼────────┼────────┼────────┼────────┼────────┼──────────┼─────────┤");
+    for (rank, r) in results.iter().take(5).enumerate() {
+        let c = &r.config;
+        println!(
+            "│ {:>4} │ {:>8.4} │ {:>6.3} │ {:>6.3} │ {:>6.3} │ {:>6.3} │ {:>6.3} │ {:>6.3} │ {:>7.1}% │ {:>6.1}% │",
+            rank + 1,
+            r.score,
+            c.epsilon,
+            c.phi,
+            c.lambda[0],
+            c.lambda[1],
+            c.lambda[2],
+            c.bias,
+            r.report.hit_rate * 100.0,
+            r.report.triggers as f64 / r.report.events.max(1) as f64 * 100.0,
+        );
+    }
+    println!("└──────┴──────────┴────────┴────────┴────────┴────────┴────────┴────────┴──────────┴─────────┘");
+
+    if args.holdout.is_some() {
+        let scored = tune::holdout(
+            std::slice::from_ref(best),
+            test,
+            |s| objective.score(s),
+            &setup,
+            HOLDOUT_MAX_GAP,
+        );
+        if let Some(h) = scored.first() {
+            println!(
+                "Held-out {} events: score {:.4} (train {:.4}, gap {:+.4})",
+                test.len(),
+                h.test,
+                h.train,
+                h.gap()
+            );
+            if h.overfit {
+                println!(
+                    "Warning: the best configuration loses more than {HOLDOUT_MAX_GAP} on held-out data and may be overfit"
+                );
+            }
+        }
+    }
+
+    if let Some((path, name)) = &args.save_profile {
+        let mut store = if path.exists() {
+            load_profiles(path)?
+        } else {
+            ProfileStore::new()
+        };
+        store
+            .insert(name.as_str(), best.config)
+            .and_then(|_| store.save(path))
+            .map_err(|e| CliError::runtime(format!("writing {}", path.display()), e))?;
+        println!("Best configuration saved as '{name}' in {}", path.display());
+        return Ok(());
+    }
+    best.config
+        .save(&args.out)
+        .map_err(|e| CliError::runtime(format!("writing {}", args.out.display()), e))?;
+    println!("Best configuration written to {}", args.out.display());
+    Ok(())
+}
+
+fn run_soak(args: SoakArgs) -> Result<(), CliError> {
+    let mut soak = match &args.resume {
+        Some(path) => {
+            let file = File::open(path)
+                .map_err(|e| CliError::runtime(format!("cannot open {}", path.display()), e))?;
+            let checkpoint: SoakCheckpoint = serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| CliError::runtime(format!("reading {}", path.display()), e))?;
+            println!(
+                "Resuming at cycle {} from {}",
+                checkpoint.cycles,
+                path.display()
+            );
+            Soak::resume(&checkpoint, args.opts)
+        }
+        None => Soak::new(args.preset.kernel(), args.opts),
+    };
+    let result = soak.run();
+    if let Some(path) = &args.save {
+        let file = File::create(path)
+            .map_err(|e| CliError::runtime(format!("cannot create {}", path.display()), e))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &soak.checkpoint())
+            .map_err(|e| CliError::runtime(format!("writing {}", path.display()), e))?;
+    }
+    match result {
+        Ok(summary) => {
+            print!("{summary}");
+            if let Some(path) = &args.save {
+                println!("Checkpoint written to {}", path.display());
+            }
+            Ok(())
+        }
+        Err(violation) => Err(CliError::Runtime(format!(
+            "invariant violated at {violation}"
+        ))),
+    }
+}
+
+fn run(argv: Vec<String>) -> Result<(), CliError> {
+    let mut argv = argv.into_iter();
+    let Some(command) = argv.next() else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+    let args = Args::new(argv.collect());
+    match command.as_str() {
+        "-h" | "--help" | "help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        "replay" => match ReplayArgs::parse(args)? {
+            Some(a) => run_replay(a),
+            None => {
+                println!("{REPLAY_USAGE}");
+                Ok(())
+            }
+        },
+        "tune" => match TuneArgs::parse(args)? {
+            Some(a) => run_tune(a),
+            None => {
+                println!("{TUNE_USAGE}");
+                Ok(())
+            }
+        },
+        "soak" => match SoakArgs::parse(args)? {
+            Some(a) => run_soak(a),
+            None => {
+                println!("{SOAK_USAGE}");
+                Ok(())
+            }
+        },
+        other => Err(CliError::Usage(format!("unknown command '{other}'"))),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(msg)) => {
+            eprintln!("error: {msg}\n\nFor more information, try '--help'.");
+            ExitCode::from(2)
+        }
+        Err(CliError::Runtime(msg)) => {
+            eprintln!("error: {msg}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Two-speed prediction.
+//!
+//! A kernel with large adaptation steps follows a workload change within a
+//! few cycles but also chases noise; one with small steps is steady but
+//! slow to recover.  [`BlendedKernel`] runs one of each on every access and
+//! fires on a convex combination of their fetch probabilities (and of their
+//! thresholds).  With feedback enabled, the blend weight moves towards
+//! whichever component has been right more often recently, so right after a
+//! change the fast kernel dominates and in steady state the blend settles
+//! wherever the two are equally good.
+//!
+//! Everything is inline state; the decision path does not allocate.
+
+use crate::prefetcher::{PrefetchDecision, Prefetcher};
+use crate::{AetherLinkKernel, CycleOutcome};
+
+/// Knobs for [`BlendedKernel`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlendOptions {
+    /// Initial weight of the fast component, in [0, 1].
+    pub weight: f32,
+    /// Weight step per reported outcome on which exactly one component was
+    /// right.  0 keeps the weight fixed.
+    pub adapt_rate: f32,
+    /// Bounds the weight is kept in while adapting, so neither component
+    /// is ever switched off entirely.
+    pub weight_bounds: (f32, f32),
+}
+
+impl Default for BlendOptions {
+    fn default() -> Self {
+        Self {
+            weight: 0.5,
+            adapt_rate: 0.05,
+            weight_bounds: (0.05, 0.95),
+        }
+    }
+}
+
+/// Per-component counters, for debugging a blend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentStats {
+    /// Cycles the component voted to fire.
+    pub fires: u64,
+    /// Reported outcomes.
+    pub outcomes: u64,
+    /// Outcomes the component's own vote agreed with.
+    pub correct: u64,
+}
+
+impl ComponentStats {
+    /// Share of outcomes the component got right, `None` before any.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.outcomes > 0).then(|| self.correct as f64 / self.outcomes as f64)
+    }
+}
+
+/// A fast and a slow kernel voting on every access.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::blend::{BlendOptions, BlendedKernel};
+/// use aether_link::{AetherLinkKernel, FeedbackSteps, ThresholdPolicy};
+///
+/// let mut fast = AetherLinkKernel::default();
+/// fast.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps {
+///     hit: 0.02,
+///     waste: 0.04,
+///     miss: 0.1,
+/// }));
+/// let mut slow = AetherLinkKernel::default();
+/// slow.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+///
+/// let mut blend = BlendedKernel::new(fast, slow, BlendOptions::default());
+/// let mut prev: Option<u64> = None;
+/// for lba in [10, 11, 12, 13, 500, 9000] {
+///     if let Some(prev) = prev {
+///         // Would a prefetch after `prev` have covered `lba`?
+///         blend.record_outcome(lba > prev && lba <= prev + 8);
+///     }
+///     blend.push_lba(lba);
+///     prev = Some(lba);
+/// }
+/// # let _ = blend.weight();
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlendedKernel {
+    fast: AetherLinkKernel,
+    slow: AetherLinkKernel,
+    opts: BlendOptions,
+    weight: f32,
+    /// Each component's vote on the last cycle, until its outcome arrives.
+    votes: Option<[bool; 2]>,
+    stats: [ComponentStats; 2],
+    /// Blended cycles executed.
+    pub cycles: u64,
+    /// Blended prefetches triggered.
+    pub prefetches: u64,
+}
+
+impl BlendedKernel {
+    /// Blend `fast` and `slow`.  The components are used as given; what
+    /// makes one fast is its parameters (larger λ₁/λ₂, or larger
+    /// [`FeedbackSteps`](crate::FeedbackSteps)).
+    pub fn new(fast: AetherLinkKernel, slow: AetherLinkKernel, opts: BlendOptions) -> Self {
+        Self {
+            fast,
+            slow,
+            weight: opts.weight.clamp(0.0, 1.0),
+            opts,
+            votes: None,
+            stats: [ComponentStats::default(); 2],
+            cycles: 0,
+            prefetches: 0,
+        }
+    }
+
+    /// Feed one LBA to both components and blend their outcomes.
+    ///
+    /// Returns `None` during the components' warm-up (see
+    /// [`AetherLinkKernel::push_lba`]).
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<CycleOutcome> {
+        let fast = self.fast.push_lba_qos(lba, 0.0);
+        let slow = self.slow.push_lba_qos(lba, 0.0);
+        Some(self.blend(fast?, slow?))
+    }
+
+    /// Run one cycle over a window of LBAs on both components.
+    #[inline]
+    pub fn process_io_cycle(&mut self, lba_stream: &[u64]) -> bool {
+        let fast = self.fast.process_io_cycle_qos(lba_stream, 0.0);
+        let slow = self.slow.process_io_cycle_qos(lba_stream, 0.0);
+        self.blend(fast, slow).fetch
+    }
+
+    #[inline(always)]
+    fn blend(&mut self, fast: CycleOutcome, slow: CycleOutcome) -> CycleOutcome {
+        let w = self.weight;
+        let p_fetch = w * fast.p_fetch + (1.0 - w) * slow.p_fetch;
+        let threshold = w * fast.threshold + (1.0 - w) * slow.threshold;
+        let fetch = p_fetch > threshold;
+
+        self.cycles += 1;
+        if fetch {
+            self.prefetches += 1;
+        }
+        for (stats, vote) in self.stats.iter_mut().zip([fast.fetch, slow.fetch]) {
+            stats.fires += vote as u64;
+        }
+        self.votes = Some([fast.fetch, slow.fetch]);
+
+        CycleOutcome {
+            fetch,
+            p_fetch,
+            threshold,
+            degraded: fast.degraded | slow.degraded,
+            damping: w * fast.damping + (1.0 - w) * slow.damping,
+        }
+    }
+
+    /// Report whether prefetching on the last cycle would have paid off.
+    ///
+    /// Each component is scored on its own vote and passed the matching
+    /// signal ([`AetherLinkKernel::record_prefetch_outcome`] if it voted
+    /// to fire, [`AetherLinkKernel::record_missed_opportunity`] if it
+    /// declined a useful prefetch); then the weight steps towards the
+    /// component that was right, if only one was.  Only the first report
+    /// after a cycle counts.
+    pub fn record_outcome(&mut self, useful: bool) {
+        let Some(votes) = self.votes.take() else {
+            return;
+        };
+        let kernels = [&mut self.fast, &mut self.slow];
+        let mut right = [false; 2];
+        for ((kernel, stats), (vote, right)) in kernels
+            .into_iter()
+            .zip(&mut self.stats)
+            .zip(votes.into_iter().zip(&mut right))
+        {
+            kernel.record_vote_outcome(vote, useful);
+            *right = vote == useful;
+            stats.outcomes += 1;
+            stats.correct += *right as u64;
+        }
+        let step = match right {
+            _ if self.opts.adapt_rate == 0.0 => return,
+            [true, false] => self.opts.adapt_rate,
+            [false, true] => -self.opts.adapt_rate,
+            _ => return,
+        };
+        let (lo, hi) = self.opts.weight_bounds;
+        self.weight = (self.weight + step).clamp(lo, hi);
+    }
+
+    /// Current weight of the fast component.
+    #[inline]
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// The fast component.
+    #[inline]
+    pub fn fast(&self) -> &AetherLinkKernel {
+        &self.fast
+    }
+
+    /// The slow component.
+    #[inline]
+    pub fn slow(&self) -> &AetherLinkKernel {
+        &self.slow
+    }
+
+    /// Counters of the fast component.
+    #[inline]
+    pub fn fast_stats(&self) -> ComponentStats {
+        self.stats[0]
+    }
+
+    /// Counters of the slow component.
+    #[inline]
+    pub fn slow_stats(&self) -> ComponentStats {
+        self.stats[1]
+    }
+}
+
+impl Prefetcher for BlendedKernel {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        match self.push_lba(lba) {
+            Some(out) if out.fetch => PrefetchDecision::trigger(None),
+            _ => PrefetchDecision::SKIP,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "aether-blend"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threshold::{FeedbackSteps, ThresholdPolicy};
+    use crate::workload::{Pattern, Phased};
+
+    const PHASE: u64 = 4_000;
+    const SETTLE: u64 = 200;
+
+    fn kernel(scale: f32) -> AetherLinkKernel {
+        let mut kernel = AetherLinkKernel::default();
+        let steps = FeedbackSteps::default();
+        kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps {
+            hit: steps.hit * scale,
+            waste: steps.waste * scale,
+            miss: steps.miss * scale,
+        }));
+        kernel
+    }
+
+    /// Decision accuracy against "the next access follows within 8
+    /// blocks", over the first `SETTLE` accesses of every phase after the
+    /// first and over the last `SETTLE` of every phase.
+    fn accuracy(mut decide: impl FnMut(u64, Option<bool>) -> bool) -> (f64, f64) {
+        let workload = Phased::new(4)
+            .then(Pattern::SEQUENTIAL, PHASE)
+            .then(Pattern::RANDOM, PHASE)
+            .then(Pattern::SEQUENTIAL, PHASE)
+            .then(Pattern::RANDOM, PHASE);
+        let lbas: Vec<u64> = workload.iter().collect();
+        let (mut settle, mut steady) = ([0u64; 2], [0u64; 2]);
+        let mut prev: Option<(u64, bool)> = None;
+        for (i, &lba) in lbas.iter().enumerate() {
+            let label = prev.map(|(p, _)| lba > p && lba <= p + 8);
+            if let (Some((_, fired)), Some(label)) = (prev, label) {
+                let at = (i - 1) as u64 % PHASE;
+                let bucket = if i as u64 > PHASE && at < SETTLE {
+                    Some(&mut settle)
+                } else if at >= PHASE - SETTLE {
+                    Some(&mut steady)
+                } else {
+                    None
+                };
+                if let Some(bucket) = bucket {
+                    bucket[0] += 1;
+                    bucket[1] += (fired == label) as u64;
+                }
+            }
+            prev = Some((lba, decide(lba, label)));
+        }
+        let ratio = |b: [u64; 2]| b[1] as f64 / b[0] as f64;
+        (ratio(settle), ratio(steady))
+    }
+
+    #[test]
+    fn test_blend_recovers_faster_than_slow() {
+        let mut blend = BlendedKernel::new(kernel(20.0), kernel(1.0), BlendOptions::default());
+        let (blend_settle, blend_steady) = accuracy(|lba, label| {
+            if let Some(useful) = label {
+                blend.record_outcome(useful);
+            }
+            blend.push_lba(lba).is_some_and(|o| o.fetch)
+        });
+        let slow_only = BlendOptions {
+            weight: 0.0,
+            adapt_rate: 0.0,
+            ..BlendOptions::default()
+        };
+        let mut slow = BlendedKernel::new(kernel(20.0), kernel(1.0), slow_only);
+        let (slow_settle, slow_steady) = accuracy(|lba, label| {
+            if let Some(useful) = label {
+                slow.record_outcome(useful);
+            }
+            slow.push_lba(lba).is_some_and(|o| o.fetch)
+        });
+        assert!(
+            blend_settle > slow_settle + 0.1,
+            "settle: blend {blend_settle:.3} slow {slow_settle:.3}"
+        );
+        assert!(
+            blend_steady >= slow_steady - 0.01,
+            "steady: blend {blend_steady:.3} slow {slow_steady:.3}"
+        );
+        assert_eq!(slow.weight(), 0.0);
+        let (fast, slow) = (blend.fast_stats(), blend.slow_stats());
+        assert_eq!(fast.outcomes, slow.outcomes);
+        assert!(fast.accuracy() > slow.accuracy());
+    }
+}
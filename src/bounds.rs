@@ -0,0 +1,155 @@
+//! Keeping recommended extents on the device.
+//!
+//! Extents are extrapolated from the stream, so near the end of a volume
+//! they run past it.  [`AetherLinkKernel::set_address_range`] gives the
+//! first and last valid LBA; every plan the kernel emits — horizon plans,
+//! lead-capped extents from [`push_lba_at`](AetherLinkKernel::push_lba_at)
+//! and micro-batch continuations — is trimmed to it.  A plan with no block
+//! left inside is dropped and counted in
+//! [`KernelStats::out_of_range`](crate::KernelStats::out_of_range); the
+//! streaming decision that carried it becomes a skip.  So does a firing
+//! decision without a plan (whose extent the caller places after the
+//! access) when the access is the last block of the range.
+//!
+//! The default range is all of `u64`, which leaves every plan unchanged.
+
+use crate::prefetcher::PrefetchPlan;
+use crate::AetherLinkKernel;
+
+/// The part of `plan` inside `first..=last`, if any.
+#[inline]
+pub(crate) fn clamp(plan: PrefetchPlan, (first, last): (u64, u64)) -> Option<PrefetchPlan> {
+    let start = plan.start.max(first);
+    let end = plan.end().min(last.saturating_add(1));
+    (start < end).then(|| PrefetchPlan::new(start, end - start))
+}
+
+impl AetherLinkKernel {
+    /// Trim every emitted plan to `min_lba..=max_lba` (the bounds are
+    /// swapped if given in the wrong order).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_horizon(16);
+    /// kernel.set_address_range(0, 999);
+    /// for lba in 900..1_000u64 {
+    ///     if let Some(plan) = kernel.push_lba(lba).and_then(|d| d.target) {
+    ///         assert!(plan.end() <= 1_000);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_address_range(&mut self, min_lba: u64, max_lba: u64) {
+        self.address_range = (min_lba.min(max_lba), min_lba.max(max_lba));
+    }
+
+    /// Back to the full `u64` range.
+    #[inline]
+    pub fn clear_address_range(&mut self) {
+        self.address_range = (0, u64::MAX);
+    }
+
+    /// First and last LBA plans are trimmed to.
+    #[inline]
+    pub fn address_range(&self) -> (u64, u64) {
+        self.address_range
+    }
+
+    /// `plan` trimmed to the address range; `None`, counted, if nothing is
+    /// left.
+    #[inline]
+    pub(crate) fn bound_plan(&mut self, plan: PrefetchPlan) -> Option<PrefetchPlan> {
+        let bounded = clamp(plan, self.address_range);
+        self.counters.out_of_range(bounded.is_none());
+        bounded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_LBA: u64 = 10_000;
+
+    #[test]
+    fn test_clamp() {
+        let range = (100, 199);
+        let plan = |start, len| PrefetchPlan::new(start, len);
+        assert_eq!(clamp(plan(120, 10), range), Some(plan(120, 10)));
+        assert_eq!(clamp(plan(190, 20), range), Some(plan(190, 10)));
+        assert_eq!(clamp(plan(199, 5), range), Some(plan(199, 1)));
+        assert_eq!(clamp(plan(200, 5), range), None);
+        assert_eq!(clamp(plan(90, 20), range), Some(plan(100, 10)));
+        assert_eq!(clamp(plan(90, 10), range), None);
+        assert_eq!(clamp(plan(120, 0), range), None);
+        let all = (0, u64::MAX);
+        assert_eq!(
+            clamp(plan(u64::MAX - 4, 10), all),
+            Some(plan(u64::MAX - 4, 4))
+        );
+    }
+
+    #[test]
+    fn test_sequential_stream_stops_at_max_lba() {
+        for horizon in [None, Some(8), Some(64)] {
+            let mut kernel = AetherLinkKernel::new_gaming();
+            if let Some(n) = horizon {
+                kernel.set_horizon(n);
+            }
+            kernel.set_address_range(0, MAX_LBA);
+            let mut last_end = 0;
+            let mut trimmed = false;
+            for lba in MAX_LBA - 2_000..=MAX_LBA {
+                let Some(d) = kernel.push_lba(lba) else {
+                    continue;
+                };
+                if let Some(plan) = d.target {
+                    assert!(plan.end() <= MAX_LBA + 1, "{horizon:?}: {plan:?}");
+                    trimmed |= plan.end() == MAX_LBA + 1;
+                    last_end = last_end.max(plan.end());
+                }
+                if lba == MAX_LBA {
+                    assert!(!d.fire, "{horizon:?}: fired at the last block");
+                }
+            }
+            if horizon.is_some() {
+                assert!(trimmed, "{horizon:?}: no plan reached max_lba");
+                assert_eq!(last_end, MAX_LBA + 1);
+            }
+            assert!(kernel.stats().out_of_range > 0, "{horizon:?}");
+        }
+    }
+
+    #[test]
+    fn test_lead_and_batch_extents_are_bounded() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.set_address_range(1_000, 2_000);
+        kernel.set_target_lead(100);
+        let mut ends = Vec::new();
+        for i in 0..300u64 {
+            if let Some(plan) = kernel.push_lba_at(1_700 + i, i * 10).and_then(|d| d.target) {
+                assert!(plan.start >= 1_000, "{plan:?}");
+                ends.push(plan.end());
+            }
+        }
+        assert_eq!(ends.iter().max(), Some(&2_001), "{ends:?}");
+
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.set_address_range(0, 1_010);
+        let mut extents = Vec::new();
+        for start in (900..1_100).step_by(16) {
+            let batch: Vec<u64> = (start..start + 16).collect();
+            extents.extend(kernel.process_micro_batch(&batch).extent);
+        }
+        assert!(!extents.is_empty());
+        assert!(extents.iter().all(|e| e.end() <= 1_011), "{extents:?}");
+
+        kernel.clear_address_range();
+        assert_eq!(kernel.address_range(), (0, u64::MAX));
+        kernel.set_address_range(50, 10);
+        assert_eq!(kernel.address_range(), (10, 50));
+    }
+}
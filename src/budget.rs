@@ -0,0 +1,173 @@
+//! Latency budget: a bounded-cost fallback for the telemetry pipeline.
+//!
+//! Every cycle pays for the full feature set, and the entropy estimate (a
+//! logarithm for each of the sixteen recent spans) dominates it.  With
+//! [`AetherLinkKernel::set_latency_budget`] the kernel runs the full
+//! pipeline on at most `full_cycles` cycles out of every
+//! [`BUDGET_WINDOW`]; the rest take a degraded path that updates only the
+//! O(1) running moments and reuses the last entropy.  The choice is one
+//! counter comparison, and the degraded path has no loops, so a window
+//! costs at most `full_cycles` full cycles whatever the input.
+//!
+//! [`CycleOutcome::degraded`](crate::CycleOutcome::degraded) reports which
+//! path a cycle took, and [`AetherLinkKernel::degraded_cycles`] counts the
+//! degraded ones.  A budget of 0 always takes the degraded path; a budget
+//! of [`BUDGET_WINDOW`] or more never does.
+
+use crate::AetherLinkKernel;
+
+/// Cycles per budget window.
+pub const BUDGET_WINDOW: u32 = 64;
+
+/// Budget and the position in the current window.
+#[derive(Debug, Clone)]
+pub(crate) struct LatencyBudget {
+    full_cycles: u32,
+    /// Cycles seen in the current window.
+    pos: u32,
+    /// Full-pipeline cycles used in the current window.
+    used: u32,
+    pub(crate) degraded_cycles: u64,
+    /// Whether the telemetry of the cycle in progress was degraded.
+    last_degraded: bool,
+}
+
+impl LatencyBudget {
+    /// Count one cycle; `true` if it may run the full pipeline.
+    #[inline(always)]
+    pub(crate) fn admit(&mut self) -> bool {
+        self.used *= (self.pos != 0) as u32;
+        self.pos = (self.pos + 1) % BUDGET_WINDOW;
+        let full = self.used < self.full_cycles;
+        self.used += full as u32;
+        self.degraded_cycles += !full as u64;
+        self.last_degraded = !full;
+        full
+    }
+
+    /// Whether the cycle in progress was degraded, clearing the flag for
+    /// the next one.
+    #[inline(always)]
+    pub(crate) fn take_degraded(&mut self) -> bool {
+        core::mem::take(&mut self.last_degraded)
+    }
+}
+
+impl AetherLinkKernel {
+    /// Run the full telemetry pipeline on at most `full_cycles` cycles in
+    /// every [`BUDGET_WINDOW`]; the others take the degraded path.  Off by
+    /// default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_hft();
+    /// kernel.set_latency_budget(16);
+    /// for i in 0..64 {
+    ///     kernel.process_io_cycle(&[i, i + 4]);
+    /// }
+    /// assert_eq!(kernel.degraded_cycles(), 48);
+    /// ```
+    pub fn set_latency_budget(&mut self, full_cycles: u32) {
+        self.budget = Some(LatencyBudget {
+            full_cycles,
+            pos: 0,
+            used: 0,
+            degraded_cycles: 0,
+            last_degraded: false,
+        });
+    }
+
+    /// Back to the full pipeline on every cycle.
+    #[inline]
+    pub fn clear_latency_budget(&mut self) {
+        self.budget = None;
+    }
+
+    /// Full-pipeline cycles allowed per window, if a budget is set.
+    #[inline]
+    pub fn latency_budget(&self) -> Option<u32> {
+        self.budget.as_ref().map(|b| b.full_cycles)
+    }
+
+    /// Cycles that took the degraded path since the budget was set or the
+    /// stats were last reset.
+    #[inline]
+    pub fn degraded_cycles(&self) -> u64 {
+        self.budget.as_ref().map_or(0, |b| b.degraded_cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use crate::threshold;
+
+    fn window(rng: &mut SplitMix64) -> [u64; 2] {
+        let start = rng.below(1 << 30);
+        [
+            start,
+            start + [1, 8, 64, rng.below(1 << 20)][rng.below(4) as usize],
+        ]
+    }
+
+    #[test]
+    fn test_zero_budget_always_degrades() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_latency_budget(0);
+        let mut rng = SplitMix64::new(5);
+        let mut fired = 0;
+        for _ in 0..5_000 {
+            let out = kernel.process_io_cycle_qos(&window(&mut rng), 0.0);
+            assert!(out.degraded);
+            assert!((0.0..=1.0).contains(&out.p_fetch), "{out:?}");
+            assert_eq!(out.fetch, out.p_fetch > out.threshold);
+            assert!(kernel.epsilon >= threshold::EPSILON_CLAMP.0);
+            assert!(kernel.epsilon <= threshold::EPSILON_CLAMP.1);
+            assert!(kernel.phi.is_finite());
+            fired += out.fetch as u64;
+        }
+        assert_eq!(kernel.degraded_cycles(), 5_000);
+        assert_eq!(kernel.prefetches, fired);
+    }
+
+    #[test]
+    fn test_full_budget_matches_unbudgeted() {
+        let mut plain = AetherLinkKernel::new_gaming();
+        let mut budgeted = plain.clone();
+        budgeted.set_latency_budget(BUDGET_WINDOW);
+        let mut rng = SplitMix64::new(8);
+        for _ in 0..2_000 {
+            let lbas = window(&mut rng);
+            let a = plain.process_io_cycle_qos(&lbas, 0.0);
+            let b = budgeted.process_io_cycle_qos(&lbas, 0.0);
+            assert_eq!(a, b);
+        }
+        assert_eq!(budgeted.degraded_cycles(), 0);
+        assert_eq!(plain.config(), budgeted.config());
+    }
+
+    #[test]
+    fn test_budget_is_per_window() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_latency_budget(10);
+        let degraded: Vec<bool> = (0..3 * BUDGET_WINDOW as u64)
+            .map(|i| kernel.process_io_cycle_qos(&[i, i + 3], 0.0).degraded)
+            .collect();
+        for w in degraded.chunks(BUDGET_WINDOW as usize) {
+            assert!(w[..10].iter().all(|&d| !d));
+            assert!(w[10..].iter().all(|&d| d));
+        }
+        assert_eq!(kernel.degraded_cycles(), 3 * 54);
+
+        // Spans too short for telemetry are not degraded cycles.
+        assert!(!kernel.process_io_cycle_qos(&[7], 0.0).degraded);
+        kernel.reset_stats();
+        assert_eq!(kernel.degraded_cycles(), 0);
+        kernel.clear_latency_budget();
+        assert_eq!(kernel.latency_budget(), None);
+    }
+}
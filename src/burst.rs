@@ -0,0 +1,129 @@
+//! Damping by access scatter.
+//!
+//! Bursts of unrelated accesses — metadata storms on a network or
+//! virtualised filesystem, say — look to the span-based telemetry like
+//! fast movement and can trigger data prefetches that are never used.
+//! With [`AetherLinkKernel::set_burst_damping`] the kernel keeps a smoothed
+//! share of recent cycles whose access landed more than [`SCATTER_STEP`]
+//! blocks from the previous one, and scales every decision's fetch
+//! probability by `1 − strength · scatter` (at least 0), on top of the
+//! [queue-depth](AetherLinkKernel::set_queue_depth) factor.  A sequential
+//! stream, even one slightly reordered in flight, scatters nothing and is
+//! left undamped.
+
+use crate::AetherLinkKernel;
+
+/// Largest step, in blocks, that does not count as scattered.
+pub const SCATTER_STEP: u64 = 256;
+
+/// Weight of the newest cycle in the scatter estimate.
+const SCATTER_ALPHA: f32 = 0.125;
+
+/// Damping strength and scatter estimate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BurstDamping {
+    strength: f32,
+    /// Newest access of the previous cycle.
+    prev: Option<u64>,
+    scatter: f32,
+}
+
+impl BurstDamping {
+    /// Account for the cycle's access, before the decision.
+    #[inline(always)]
+    pub(crate) fn observe(&mut self, lba: u64) {
+        if let Some(prev) = self.prev {
+            let far = (lba.abs_diff(prev) > SCATTER_STEP) as u8 as f32;
+            self.scatter += SCATTER_ALPHA * (far - self.scatter);
+        }
+        self.prev = Some(lba);
+    }
+
+    /// Factor for the cycle's fetch probability.
+    #[inline(always)]
+    pub(crate) fn factor(&self) -> f32 {
+        (1.0 - self.strength * self.scatter).max(0.0)
+    }
+}
+
+impl AetherLinkKernel {
+    /// Damp decisions by `strength` (at least 0) times the share of
+    /// recent scattered accesses.  Starts from no scatter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_burst_damping(2.0);
+    /// for i in 0..64u64 {
+    ///     kernel.push_lba(i.wrapping_mul(0x9e37_79b9) % 1_000_000);
+    /// }
+    /// let out = kernel.push_lba_qos(123_456_789, 0.0).unwrap();
+    /// assert!(!out.fetch);
+    /// assert_eq!(out.damping, 0.0);
+    /// ```
+    pub fn set_burst_damping(&mut self, strength: f32) {
+        self.burst = Some(BurstDamping {
+            strength: if strength.is_nan() {
+                0.0
+            } else {
+                strength.max(0.0)
+            },
+            prev: None,
+            scatter: 0.0,
+        });
+    }
+
+    /// Stop damping by scatter.
+    #[inline]
+    pub fn clear_burst_damping(&mut self) {
+        self.burst = None;
+    }
+
+    /// Damping strength, if burst damping is on.
+    #[inline]
+    pub fn burst_damping(&self) -> Option<f32> {
+        self.burst.map(|b| b.strength)
+    }
+
+    /// Smoothed share of scattered accesses in [0, 1], if burst damping
+    /// is on.
+    #[inline]
+    pub fn scatter(&self) -> Option<f32> {
+        self.burst.map(|b| b.scatter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_storm_is_damped_and_stream_is_not() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_burst_damping(2.0);
+        let mut rng = SplitMix64::new(4);
+        let storm = (0..200)
+            .filter_map(|_| kernel.push_lba(rng.below(1 << 16) * 512))
+            .filter(|d| d.fire)
+            .count();
+        assert!(kernel.scatter().unwrap() > 0.9);
+        assert!(storm < 20, "{storm} triggers in the storm");
+
+        // Reordered pairs of a sequential stream do not scatter.
+        let stream = (0..400u64)
+            .map(|i| (1 << 30) + (i ^ 1) * 16)
+            .filter_map(|lba| kernel.push_lba(lba))
+            .skip(100)
+            .filter(|d| d.fire)
+            .count();
+        assert!(kernel.scatter().unwrap() < 0.01);
+        assert!(stream > 250, "{stream} triggers on the stream");
+
+        kernel.clear_burst_damping();
+        assert_eq!(kernel.scatter(), None);
+    }
+}
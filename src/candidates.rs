@@ -0,0 +1,178 @@
+//! Ranking candidates the caller already has.
+//!
+//! A caller that knows where the next read can come from — the next block
+//! of each open file, say — wants them ranked rather than an extrapolation
+//! of the kernel's own.  [`AetherLinkKernel::score_candidates`] scores each
+//! candidate as the next access after a history window:
+//!
+//! - the fetch probability the kernel would compute for the window
+//!   `history` followed by the candidate (undamped, with the current φ and
+//!   telemetry state);
+//! - times how well the candidate continues the history: the ratio of its
+//!   step from the newest access to the history's mean step, smaller over
+//!   larger, and 0 against the history's direction.
+//!
+//! The window's oldest access and mean step are computed once, and each
+//! candidate costs one telemetry read on a copy of the DSP state: the
+//! whole set is O(K), not O(K × window).  Nothing in the kernel changes.
+//! A candidate that would make a [re-read](crate::degenerate) window, or a
+//! non-finite score, scores 0.
+
+use crate::AetherLinkKernel;
+use alloc::vec;
+use alloc::vec::Vec;
+
+impl AetherLinkKernel {
+    /// Score every candidate as the access following `history` (oldest
+    /// first) into `out_scores`; higher is a better prefetch.
+    ///
+    /// # Panics
+    ///
+    /// If `out_scores.len()` differs from `candidates.len()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let kernel = AetherLinkKernel::default();
+    /// let history: Vec<u64> = (0..16).map(|i| 1_000 + 4 * i).collect();
+    /// let candidates = [1_061, 1_064, 1_068, 9_999];
+    /// let mut scores = [0.0; 4];
+    /// kernel.score_candidates(&history, &candidates, &mut scores);
+    /// assert_eq!(kernel.top_k(&history, &candidates, 1), [1]);
+    /// ```
+    pub fn score_candidates(&self, history: &[u64], candidates: &[u64], out_scores: &mut [f32]) {
+        assert_eq!(
+            out_scores.len(),
+            candidates.len(),
+            "one score per candidate"
+        );
+        let (Some(&first), Some(&last)) = (history.first(), history.last()) else {
+            out_scores.fill(0.0);
+            return;
+        };
+        let steps = history.len() as f64 - 1.0;
+        let mean_step = (steps > 0.0).then(|| last.wrapping_sub(first) as i64 as f64 / steps);
+        for (score, &candidate) in out_scores.iter_mut().zip(candidates) {
+            let span = candidate.wrapping_sub(first) as i64;
+            if span == 0 {
+                *score = 0.0;
+                continue;
+            }
+            let telemetry = self
+                .dsp
+                .clone()
+                .features(span.unsigned_abs() as f32, self.math);
+            let bloch = Self::bloch_state(telemetry, self.math);
+            let (_, _, o3) = Self::povm_measure(&bloch, self.phi, self.math);
+            let p_fetch = self.math.sigmoid(-(self.lambda[2] * o3 + self.bias));
+            let step = candidate.wrapping_sub(last) as i64 as f64;
+            let fit = mean_step.map_or(1.0, |mean| continuation(step, mean));
+            let s = p_fetch * fit as f32;
+            *score = if s.is_finite() { s } else { 0.0 };
+        }
+    }
+
+    /// Indices of the `k` best-scoring candidates, best first (ties in
+    /// candidate order); see [`score_candidates`](Self::score_candidates).
+    pub fn top_k(&self, history: &[u64], candidates: &[u64], k: usize) -> Vec<usize> {
+        let mut scores = vec![0.0; candidates.len()];
+        self.score_candidates(history, candidates, &mut scores);
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        order.truncate(k);
+        order
+    }
+}
+
+/// How well a step continues steps of `mean`: smaller magnitude over
+/// larger, 0 for opposite directions, 1 for two zero steps.
+fn continuation(step: f64, mean: f64) -> f64 {
+    if step == mean {
+        return 1.0;
+    }
+    if step * mean < 0.0 {
+        return 0.0;
+    }
+    let (a, b) = (step.abs(), mean.abs());
+    a.min(b) / a.max(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presets() -> [AetherLinkKernel; 4] {
+        [
+            AetherLinkKernel::default(),
+            AetherLinkKernel::new_database(),
+            AetherLinkKernel::new_gaming(),
+            AetherLinkKernel::new_hft(),
+        ]
+    }
+
+    #[test]
+    fn test_stride_continuation_scores_highest() {
+        for descending in [false, true] {
+            let history: Vec<u64> = (0..16u64)
+                .map(|i| {
+                    if descending {
+                        100_000 - 4 * i
+                    } else {
+                        1_000 + 4 * i
+                    }
+                })
+                .collect();
+            let last = *history.last().unwrap();
+            let next = if descending { last - 4 } else { last + 4 };
+            let candidates = [
+                last + 1,
+                last + 2,
+                last + 3,
+                last + 8,
+                last + 4_096,
+                last - 1,
+                last - 4,
+                last + 4,
+                last - 8,
+                history[0],
+                0x9e37_79b9_7f4a_7c15,
+            ];
+            let best = candidates.iter().position(|&c| c == next).unwrap();
+            for mut kernel in presets() {
+                // Trained or not, the continuation wins.
+                for _ in 0..2 {
+                    let mut scores = [0.0; 11];
+                    kernel.score_candidates(&history, &candidates, &mut scores);
+                    assert_eq!(kernel.top_k(&history, &candidates, 1), [best], "{scores:?}");
+                    assert_eq!(scores[9], 0.0, "a re-read window");
+                    for lba in history.iter().copied() {
+                        kernel.push_lba(lba);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pathological_candidates_finite_and_state_untouched() {
+        let candidates = [0, 1, u64::MAX, u64::MAX - 1, 1 << 63, 42];
+        let histories: [&[u64]; 5] = [&[], &[42], &[u64::MAX, 0], &[0, u64::MAX], &[7; 9]];
+        for kernel in presets() {
+            let before = kernel.clone();
+            for history in histories {
+                let mut scores = [f32::NAN; 6];
+                kernel.score_candidates(history, &candidates, &mut scores);
+                assert!(
+                    scores.iter().all(|s| s.is_finite() && *s >= 0.0),
+                    "{scores:?}"
+                );
+                assert_eq!(kernel.top_k(history, &candidates, 10).len(), 6);
+                assert!(kernel.top_k(history, &candidates, 0).is_empty());
+            }
+            assert_eq!(kernel.diff(&before), Default::default());
+            assert_eq!(kernel.stats(), before.stats());
+        }
+    }
+}
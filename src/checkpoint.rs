@@ -0,0 +1,209 @@
+//! Periodic checkpoints without polling.
+//!
+//! For crash recovery a caller wants a recent [`KernelSnapshot`] on hand at
+//! all times.  With [`AetherLinkKernel::set_checkpoint`] the kernel takes one
+//! on every cycle whose count is a multiple of the interval and hands it to
+//! a sink; [`AetherLinkKernel::enable_checkpoints`] only keeps the newest
+//! for [`latest_checkpoint`](AetherLinkKernel::latest_checkpoint) to read.
+//! Either way every other cycle pays one integer comparison: the count of
+//! the next boundary is kept next to the cycle counter, and set to
+//! `u64::MAX` while checkpoints are off.
+//!
+//! The sink runs synchronously, inside the cycle, after the cycle has
+//! updated the state — keep it cheap (a channel send, a copy into a slot).
+//! Nothing is caught: a sink that panics unwinds through the cycle that
+//! called it.  The kernel has already recorded the checkpoint and moved
+//! the next boundary by then, so it stays consistent, but callers that
+//! cannot rule out such a sink should use the pull-based form instead.
+//!
+//! A clone of the kernel keeps the interval and the newest checkpoint but
+//! not the sink, which cannot be cloned.
+
+use crate::{AetherLinkKernel, KernelSnapshot};
+use alloc::boxed::Box;
+use core::fmt;
+
+/// Where checkpoints go.
+struct Sink(Box<dyn FnMut(KernelSnapshot) + Send>);
+
+// SAFETY: the closure is only ever called through `&mut Sink`, so a shared
+// `&Sink` gives no access to it and sharing one across threads is harmless.
+unsafe impl Sync for Sink {}
+
+/// Interval, newest checkpoint and sink.
+pub(crate) struct Checkpoints {
+    every: u64,
+    latest: Option<KernelSnapshot>,
+    sink: Option<Sink>,
+}
+
+impl Clone for Checkpoints {
+    fn clone(&self) -> Self {
+        Self {
+            every: self.every,
+            latest: self.latest,
+            sink: None,
+        }
+    }
+}
+
+impl fmt::Debug for Checkpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checkpoints")
+            .field("every", &self.every)
+            .field("latest", &self.latest)
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl AetherLinkKernel {
+    /// Take a snapshot on every cycle whose count is a multiple of
+    /// `every_cycles` (at least 1) and pass it to `sink`, replacing any
+    /// previous checkpoint setting.  See [`checkpoint`](crate::checkpoint)
+    /// for what the sink may do.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    /// use std::sync::mpsc;
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_checkpoint(1_000, move |snapshot| {
+    ///     let _ = tx.send(snapshot);
+    /// });
+    /// for i in 0..2_500u64 {
+    ///     kernel.process_io_cycle(&[i, i + 1]);
+    /// }
+    /// let cycles: Vec<u64> = rx.try_iter().map(|s| s.cycles).collect();
+    /// assert_eq!(cycles, [1_000, 2_000]);
+    /// ```
+    pub fn set_checkpoint(
+        &mut self,
+        every_cycles: u64,
+        sink: impl FnMut(KernelSnapshot) + Send + 'static,
+    ) {
+        self.start_checkpoints(every_cycles, Some(Sink(Box::new(sink))));
+    }
+
+    /// Keep a snapshot of every cycle whose count is a multiple of
+    /// `every_cycles` (at least 1) for
+    /// [`latest_checkpoint`](Self::latest_checkpoint), with no callback,
+    /// replacing any previous checkpoint setting.
+    pub fn enable_checkpoints(&mut self, every_cycles: u64) {
+        self.start_checkpoints(every_cycles, None);
+    }
+
+    fn start_checkpoints(&mut self, every_cycles: u64, sink: Option<Sink>) {
+        let every = every_cycles.max(1);
+        self.checkpoints = Some(Checkpoints {
+            every,
+            latest: None,
+            sink,
+        });
+        self.next_checkpoint = next_boundary(self.cycles, every);
+    }
+
+    /// Stop taking checkpoints and drop the sink and the newest checkpoint.
+    #[inline]
+    pub fn clear_checkpoint(&mut self) {
+        self.checkpoints = None;
+        self.next_checkpoint = u64::MAX;
+    }
+
+    /// Cycles between checkpoints, if they are on.
+    #[inline]
+    pub fn checkpoint_interval(&self) -> Option<u64> {
+        self.checkpoints.as_ref().map(|c| c.every)
+    }
+
+    /// The newest checkpoint, once a boundary cycle has run.
+    #[inline]
+    pub fn latest_checkpoint(&self) -> Option<KernelSnapshot> {
+        self.checkpoints.as_ref().and_then(|c| c.latest)
+    }
+
+    /// Called at the end of every cycle.
+    #[inline(always)]
+    pub(crate) fn checkpoint_due(&mut self) {
+        if self.cycles >= self.next_checkpoint {
+            self.take_checkpoint();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn take_checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        let Some(checkpoints) = &mut self.checkpoints else {
+            self.next_checkpoint = u64::MAX;
+            return;
+        };
+        checkpoints.latest = Some(snapshot);
+        self.next_checkpoint = next_boundary(self.cycles, checkpoints.every);
+        if let Some(sink) = &mut checkpoints.sink {
+            (sink.0)(snapshot);
+        }
+    }
+}
+
+/// First multiple of `every` above `cycles`, `u64::MAX` past the end.
+fn next_boundary(cycles: u64, every: u64) -> u64 {
+    (cycles / every).saturating_add(1).saturating_mul(every)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_sink_fires_exactly_on_multiples() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut kernel = AetherLinkKernel::new_database();
+        let sink = Arc::clone(&seen);
+        kernel.set_checkpoint(7, move |snapshot| sink.lock().unwrap().push(snapshot));
+        let mut expected = Vec::new();
+        for i in 0..100u64 {
+            kernel.process_io_cycle(&[i * 13, i * 13 + i % 5 + 1]);
+            if kernel.cycles % 7 == 0 {
+                expected.push(kernel.snapshot());
+            }
+            // Short windows run no cycle and take no checkpoint.
+            kernel.process_io_cycle(&[i]);
+        }
+        assert_eq!(*seen.lock().unwrap(), expected);
+        assert_eq!(expected.len(), 14);
+        assert_eq!(kernel.latest_checkpoint(), expected.last().copied());
+        assert_eq!(kernel.checkpoint_interval(), Some(7));
+
+        // The boundaries count from zero, not from when checkpoints began.
+        kernel.enable_checkpoints(10);
+        assert_eq!(kernel.latest_checkpoint(), None);
+        for i in 0..10u64 {
+            kernel.process_io_cycle(&[i, i + 2]);
+        }
+        assert_eq!(kernel.latest_checkpoint().map(|s| s.cycles), Some(110));
+        assert_eq!(seen.lock().unwrap().len(), 14);
+    }
+
+    #[test]
+    fn test_clone_keeps_pull_side_and_clear_stops() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_checkpoint(0, |_| {});
+        assert_eq!(kernel.checkpoint_interval(), Some(1));
+        kernel.process_io_cycle(&[1, 2]);
+        let mut clone = kernel.clone();
+        assert!(format!("{clone:?}").contains("sink: false"));
+        clone.process_io_cycle(&[2, 3]);
+        assert_eq!(clone.latest_checkpoint(), Some(clone.snapshot()));
+        assert_eq!(kernel.latest_checkpoint().map(|s| s.cycles), Some(1));
+
+        kernel.clear_checkpoint();
+        kernel.process_io_cycle(&[2, 3]);
+        assert_eq!(kernel.latest_checkpoint(), None);
+        assert_eq!(kernel.checkpoint_interval(), None);
+    }
+}
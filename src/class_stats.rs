@@ -0,0 +1,316 @@
+//! Counters sliced by what the workload looked like.
+//!
+//! A global trigger count does not say whether the kernel earns its keep
+//! everywhere or only on sequential phases.  With
+//! [`AetherLinkKernel::enable_class_stats`] every cycle is also counted
+//! against the [`WorkloadClass`] the kernel currently believes in, and
+//! every reported outcome against the class in effect when it is
+//! reported.  [`AetherLinkKernel::stats_by_class`] returns one
+//! [`ClassStats`] per class, whose counts add up to those of
+//! [`KernelStats`](crate::KernelStats) kept over the same cycles.
+//!
+//! Classifying looks at the whole history window, so the belief is only
+//! refreshed every `reclassify_every` cycles; the cycles in between pay
+//! a countdown and three increments.  Kernels driven through
+//! [`process_io_cycle`](AetherLinkKernel::process_io_cycle) keep no
+//! history and count everything as [`WorkloadClass::Unknown`].
+
+use crate::classify::{WorkloadClass, N_CLASSES};
+use crate::metrics::{MetricsSink, PrometheusSink};
+use crate::AetherLinkKernel;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+
+/// Counts for one workload class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassStats {
+    /// The class counted.
+    pub class: WorkloadClass,
+    /// Decision cycles run while the kernel believed in `class`.
+    pub cycles: u64,
+    /// Of those, cycles that fired a prefetch.
+    pub prefetches: u64,
+    /// Prefetches reported useful while the kernel believed in `class`.
+    pub hits: u64,
+    /// Prefetches reported evicted unused.
+    pub wasted: u64,
+    /// Demand misses reported on declined prefetches.
+    pub missed: u64,
+}
+
+impl ClassStats {
+    /// `prefetches / cycles` (0 with no cycles).
+    #[inline]
+    pub fn prefetch_ratio(&self) -> f32 {
+        if self.cycles == 0 {
+            0.0
+        } else {
+            self.prefetches as f32 / self.cycles as f32
+        }
+    }
+}
+
+const EMPTY: [ClassStats; N_CLASSES] = {
+    let mut out = [ClassStats {
+        class: WorkloadClass::Unknown,
+        cycles: 0,
+        prefetches: 0,
+        hits: 0,
+        wasted: 0,
+        missed: 0,
+    }; N_CLASSES];
+    let mut i = 0;
+    while i < N_CLASSES {
+        out[i].class = WorkloadClass::ALL[i];
+        i += 1;
+    }
+    out
+};
+
+/// Current belief and per-class counts.
+#[derive(Debug, Clone)]
+pub(crate) struct ClassTracker {
+    every: u32,
+    countdown: u32,
+    current: WorkloadClass,
+    by_class: [ClassStats; N_CLASSES],
+}
+
+impl ClassTracker {
+    #[inline(always)]
+    fn current(&mut self) -> Option<&mut ClassStats> {
+        self.by_class.get_mut(self.current.index())
+    }
+}
+
+impl AetherLinkKernel {
+    /// Count every following cycle and reported outcome against the
+    /// workload class, re-classifying every `reclassify_every` cycles (at
+    /// least 1), and replace any earlier counts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::classify::WorkloadClass;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_class_stats(16);
+    /// for lba in 0..1_000u64 {
+    ///     kernel.push_lba(lba);
+    /// }
+    /// let sequential = kernel.stats_by_class()[WorkloadClass::Sequential.index()];
+    /// assert!(sequential.cycles > 900);
+    /// ```
+    pub fn enable_class_stats(&mut self, reclassify_every: u32) {
+        self.class_stats = Some(Box::new(ClassTracker {
+            every: reclassify_every.max(1),
+            countdown: 0,
+            current: WorkloadClass::Unknown,
+            by_class: EMPTY,
+        }));
+    }
+
+    /// Whether per-class counting is on.
+    #[inline]
+    pub fn class_stats_enabled(&self) -> bool {
+        self.class_stats.is_some()
+    }
+
+    /// Stop counting and drop the counts.
+    #[inline]
+    pub fn disable_class_stats(&mut self) {
+        self.class_stats = None;
+    }
+
+    /// Counts per class, in [`WorkloadClass::ALL`] order, since they were
+    /// enabled or the stats were reset; all zero while disabled.
+    #[inline]
+    pub fn stats_by_class(&self) -> [ClassStats; N_CLASSES] {
+        self.class_stats.as_ref().map_or(EMPTY, |t| t.by_class)
+    }
+
+    /// Count a decided cycle.
+    #[inline(always)]
+    pub(crate) fn class_decided(&mut self, fetch: bool) {
+        let Some(tracker) = &mut self.class_stats else {
+            return;
+        };
+        if tracker.countdown == 0 {
+            self.reclassify();
+        }
+        if let Some(tracker) = &mut self.class_stats {
+            tracker.countdown = tracker.countdown.saturating_sub(1);
+            if let Some(stats) = tracker.current() {
+                stats.cycles = stats.cycles.wrapping_add(1);
+                stats.prefetches = stats.prefetches.wrapping_add(fetch as u64);
+            }
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn reclassify(&mut self) {
+        let class = self.classify();
+        if let Some(tracker) = &mut self.class_stats {
+            tracker.current = class;
+            tracker.countdown = tracker.every;
+        }
+    }
+
+    /// Count a reported outcome with `count`.
+    #[inline]
+    pub(crate) fn class_outcome(&mut self, count: fn(&mut ClassStats)) {
+        if let Some(stats) = self.class_stats.as_mut().and_then(|t| t.current()) {
+            count(stats);
+        }
+    }
+
+    /// Zero the counts, keeping the belief and the tracking on.
+    pub(crate) fn reset_class_stats(&mut self) {
+        if let Some(tracker) = &mut self.class_stats {
+            tracker.by_class = EMPTY;
+        }
+    }
+}
+
+/// Prometheus text exposition of per-class counts, one series per class
+/// under a `class` label, every metric name starting with `prefix`.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::{class_stats, AetherLinkKernel};
+///
+/// let mut kernel = AetherLinkKernel::default();
+/// kernel.enable_class_stats(1);
+/// let text = class_stats::to_prometheus(&kernel.stats_by_class(), "aether");
+/// assert!(text.contains("aether_class_cycles_total{class=\"sequential\"} 0\n"));
+/// ```
+pub fn to_prometheus(by_class: &[ClassStats; N_CLASSES], prefix: &str) -> String {
+    type Counter = fn(&ClassStats) -> u64;
+    let metrics: [(&str, &str, Counter); 5] = [
+        ("cycles_total", "Decision cycles by workload class.", |s| {
+            s.cycles
+        }),
+        (
+            "prefetches_total",
+            "Prefetches triggered by workload class.",
+            |s| s.prefetches,
+        ),
+        (
+            "hits_total",
+            "Prefetches reported useful by workload class.",
+            |s| s.hits,
+        ),
+        (
+            "wasted_total",
+            "Prefetches reported wasted by workload class.",
+            |s| s.wasted,
+        ),
+        (
+            "missed_total",
+            "Misses on declined prefetches by workload class.",
+            |s| s.missed,
+        ),
+    ];
+    let mut sink = PrometheusSink::new();
+    for (name, help, value) in metrics {
+        for stats in by_class {
+            let series = format!("{prefix}_class_{name}{{class=\"{}\"}}", stats.class);
+            sink.describe(&series, help);
+            sink.counter(&series, value(stats));
+        }
+    }
+    sink.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    #[test]
+    fn test_phased_trace_splits_by_class() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_class_stats(8);
+        let phases = [
+            (Pattern::SEQUENTIAL, WorkloadClass::Sequential),
+            (Pattern::RANDOM, WorkloadClass::Random),
+            (Pattern::Sequential { stride: 8 }, WorkloadClass::Strided),
+        ];
+        for (seed, (pattern, _)) in phases.iter().enumerate() {
+            for (i, lba) in pattern.workload(seed as u64).take(2_000).enumerate() {
+                if let Some(decision) = kernel.push_lba(lba) {
+                    if decision.fire {
+                        kernel.record_prefetch_outcome(i % 3 != 0);
+                    } else if i % 5 == 0 {
+                        kernel.record_missed_opportunity();
+                    }
+                }
+            }
+        }
+
+        let by_class = kernel.stats_by_class();
+        let total = kernel.stats();
+        let sum = |f: fn(&ClassStats) -> u64| by_class.iter().map(f).sum::<u64>();
+        assert_eq!(sum(|s| s.cycles), total.cycles);
+        assert_eq!(sum(|s| s.prefetches), total.prefetches);
+        assert_eq!(sum(|s| s.hits), total.hits);
+        assert_eq!(sum(|s| s.wasted), total.wasted);
+        assert_eq!(sum(|s| s.missed), total.missed);
+        for (i, s) in by_class.iter().enumerate() {
+            assert_eq!(s.class, WorkloadClass::ALL[i]);
+        }
+        // Each phase lands in its class, bar the window it takes the
+        // history to turn over.
+        for (_, class) in phases {
+            let cycles = by_class[class.index()].cycles;
+            assert!(cycles > 1_800, "{class}: {by_class:?}");
+        }
+
+        kernel.reset_stats();
+        assert!(kernel.stats_by_class().iter().all(|s| s.cycles == 0));
+        kernel.push_lba(1 << 40);
+        assert_eq!(
+            kernel
+                .stats_by_class()
+                .iter()
+                .map(|s| s.cycles)
+                .sum::<u64>(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_off_and_slice_driven_kernels() {
+        let mut kernel = AetherLinkKernel::default();
+        for i in 0..100u64 {
+            kernel.push_lba(i);
+        }
+        assert_eq!(kernel.stats_by_class(), EMPTY);
+
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_class_stats(0);
+        for i in 0..100u64 {
+            kernel.process_io_cycle(&[i, i + 1, i + 2]);
+        }
+        let unknown = kernel.stats_by_class()[WorkloadClass::Unknown.index()];
+        assert_eq!(unknown.cycles, 100);
+        assert!(kernel.report().to_string().contains("unknown"));
+
+        let text = to_prometheus(&kernel.stats_by_class(), "aether");
+        assert!(text.contains("# TYPE aether_class_cycles_total counter\n"));
+        assert!(text.contains("aether_class_cycles_total{class=\"unknown\"} 100\n"));
+        assert!(text.contains("aether_class_hits_total{class=\"re-read\"} 0\n"));
+        assert_eq!(
+            text.lines().filter(|l| !l.starts_with('#')).count(),
+            5 * N_CLASSES
+        );
+
+        kernel.disable_class_stats();
+        assert_eq!(kernel.stats_by_class(), EMPTY);
+    }
+}
@@ -0,0 +1,368 @@
+//! What the workload looks like, in the kernel's opinion.
+//!
+//! [`AetherLinkKernel::classify`] labels the recent access stream without
+//! touching the decision path: everything is computed on demand from the
+//! LBA history that [`push_lba`](AetherLinkKernel::push_lba) already keeps.
+//! From the per-access deltas of that window it derives five
+//! [`WorkloadFeatures`] and applies [`ClassifierThresholds`] in order:
+//!
+//! 1. too few deltas → [`WorkloadClass::Unknown`];
+//! 2. share of repeats ≥ `reread` → [`WorkloadClass::ReRead`];
+//! 3. sequentiality ≥ `sequential` → [`WorkloadClass::Sequential`];
+//! 4. stride confidence ≥ `strided` → [`WorkloadClass::Strided`];
+//! 5. delta entropy ≥ `random` → [`WorkloadClass::Random`];
+//! 6. burstiness ≥ `bursty` → [`WorkloadClass::Bursty`];
+//! 7. otherwise [`WorkloadClass::Mixed`].
+//!
+//! A repeat — a delta of 0 — is no step, so every feature but the share
+//! of repeats is computed over the other deltas: a stream reading each
+//! block twice is as sequential as one reading it once (see
+//! [`degenerate`](crate::degenerate)).
+//!
+//! Kernels driven only through
+//! [`process_io_cycle`](AetherLinkKernel::process_io_cycle) keep no history
+//! and always classify as `Unknown`.
+
+use crate::stream::MAX_WINDOW;
+use crate::AetherLinkKernel;
+use core::fmt;
+
+/// Coarse workload label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WorkloadClass {
+    /// Consecutive blocks, one after the other, ascending or descending.
+    Sequential,
+    /// A constant step other than ±1.
+    Strided,
+    /// No repeating step.
+    Random,
+    /// Sequential runs broken by jumps.
+    Bursty,
+    /// None of the above clearly.
+    Mixed,
+    /// The same block again and again.
+    ReRead,
+    /// Not enough history yet.
+    #[default]
+    Unknown,
+}
+
+/// Number of [`WorkloadClass`] values.
+pub const N_CLASSES: usize = 7;
+
+impl WorkloadClass {
+    /// Every class, in [`index`](Self::index) order.
+    pub const ALL: [WorkloadClass; N_CLASSES] = [
+        Self::Sequential,
+        Self::Strided,
+        Self::Random,
+        Self::Bursty,
+        Self::Mixed,
+        Self::ReRead,
+        Self::Unknown,
+    ];
+
+    /// Position in [`ALL`](Self::ALL), below [`N_CLASSES`].
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Lower-case name used by [`Display`](fmt::Display).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sequential => "sequential",
+            Self::Strided => "strided",
+            Self::Random => "random",
+            Self::Bursty => "bursty",
+            Self::Mixed => "mixed",
+            Self::ReRead => "re-read",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for WorkloadClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Decision thresholds for [`AetherLinkKernel::classification`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassifierThresholds {
+    /// Deltas needed before anything but `Unknown` is returned.  Values
+    /// above the history window minus one never classify.
+    pub min_deltas: usize,
+    /// Minimum share of repeats for `ReRead`.
+    pub reread: f32,
+    /// Minimum sequentiality for `Sequential`.
+    pub sequential: f32,
+    /// Minimum stride confidence for `Strided`.
+    pub strided: f32,
+    /// Minimum normalised delta entropy for `Random`.
+    pub random: f32,
+    /// Minimum burstiness for `Bursty`.
+    pub bursty: f32,
+}
+
+impl Default for ClassifierThresholds {
+    fn default() -> Self {
+        Self {
+            min_deltas: 8,
+            reread: 0.95,
+            sequential: 0.95,
+            strided: 0.95,
+            random: 0.8,
+            bursty: 0.5,
+        }
+    }
+}
+
+/// Statistics of the per-access deltas in the history window.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkloadFeatures {
+    /// Deltas in the window.
+    pub deltas: usize,
+    /// Share of deltas equal to 0: repeats of the previous block.  The
+    /// features below leave them out, unless every delta is one.
+    pub duplicates: f32,
+    /// Share of deltas equal to +1, or to −1 in a window with more of
+    /// those (a descending scan).
+    pub sequentiality: f32,
+    /// Most common delta.
+    pub stride: i64,
+    /// Share of deltas equal to `stride`.
+    pub stride_confidence: f32,
+    /// Shannon entropy of the deltas over its maximum, `log2(deltas)`, so
+    /// 0 is a single repeated step and 1 all steps distinct.
+    pub delta_entropy: f32,
+    /// Share of deltas inside sequential runs when the window also holds
+    /// at least one jump; 0 for a purely sequential window.
+    pub burstiness: f32,
+}
+
+/// A label, how sure the classifier is, and what it was based on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Classification {
+    /// The label.
+    pub class: WorkloadClass,
+    /// In [0, 1]: the deciding feature for a definite label, the margin
+    /// below every threshold for `Mixed`, 0 for `Unknown`.
+    pub confidence: f32,
+    /// The features the label was derived from.
+    pub features: WorkloadFeatures,
+}
+
+impl AetherLinkKernel {
+    /// Label the recent workload with the default thresholds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::classify::WorkloadClass;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// assert_eq!(kernel.classify(), WorkloadClass::Unknown);
+    /// for lba in 1000..1100 {
+    ///     kernel.push_lba(lba);
+    /// }
+    /// assert_eq!(kernel.classify(), WorkloadClass::Sequential);
+    /// ```
+    #[inline]
+    pub fn classify(&self) -> WorkloadClass {
+        self.classification(&ClassifierThresholds::default()).class
+    }
+
+    /// Label the recent workload with explicit thresholds, with the
+    /// confidence and features behind the label.
+    pub fn classification(&self, thresholds: &ClassifierThresholds) -> Classification {
+        let features = self.workload_features();
+        let t = thresholds;
+        if features.deltas == 0 || features.deltas < t.min_deltas {
+            return Classification {
+                class: WorkloadClass::Unknown,
+                confidence: 0.0,
+                features,
+            };
+        }
+        let (class, confidence) = if features.duplicates >= t.reread {
+            (WorkloadClass::ReRead, features.duplicates)
+        } else if features.sequentiality >= t.sequential {
+            (WorkloadClass::Sequential, features.sequentiality)
+        } else if features.stride_confidence >= t.strided {
+            (WorkloadClass::Strided, features.stride_confidence)
+        } else if features.delta_entropy >= t.random {
+            (WorkloadClass::Random, features.delta_entropy)
+        } else if features.burstiness >= t.bursty {
+            (WorkloadClass::Bursty, 1.0 - features.delta_entropy)
+        } else {
+            let closest = (features.stride_confidence / t.strided)
+                .max(features.delta_entropy / t.random)
+                .max(features.burstiness / t.bursty);
+            (WorkloadClass::Mixed, (1.0 - closest).clamp(0.0, 1.0))
+        };
+        Classification {
+            class,
+            confidence,
+            features,
+        }
+    }
+
+    /// Delta statistics over the history window.  Watchdog checks run
+    /// this inside a cycle, so it indexes nothing it has not bounded (see
+    /// [panic-free](crate#panics)).
+    fn workload_features(&self) -> WorkloadFeatures {
+        let mut all = [0i64; MAX_WINDOW];
+        let mut len = 0;
+        let steps = self.history.iter().zip(self.history.iter().skip(1));
+        for (slot, (p, lba)) in all.iter_mut().zip(steps) {
+            *slot = lba.wrapping_sub(p) as i64;
+            len += 1;
+        }
+        if len == 0 {
+            return WorkloadFeatures::default();
+        }
+        let all = &all[..len.min(MAX_WINDOW)];
+        let repeats = all.iter().filter(|&&d| d == 0).count();
+        let duplicates = repeats as f32 / len as f32;
+        let mut moves = [0i64; MAX_WINDOW];
+        let mut n = 0;
+        for (slot, &d) in moves.iter_mut().zip(all.iter().filter(|&&d| d != 0)) {
+            *slot = d;
+            n += 1;
+        }
+        // A window of re-reads only keeps its zero deltas.
+        let deltas = if n == 0 {
+            n = len;
+            all
+        } else {
+            &moves[..n.min(MAX_WINDOW)]
+        };
+        let ascending = deltas.iter().filter(|&&d| d == 1).count();
+        let descending = deltas.iter().filter(|&&d| d == -1).count();
+        let sequential = ascending.max(descending);
+
+        // Counts of each distinct delta give the mode (the smallest of
+        // equally common ones) and the entropy.  Quadratic in the window,
+        // but without a sort, whose panic paths the optimiser cannot rule
+        // out.
+        let total = n as f32;
+        let (mut stride, mut best) = (0, 0);
+        let mut entropy = 0.0f32;
+        for (i, &value) in deltas.iter().enumerate() {
+            if deltas.iter().take(i).any(|&d| d == value) {
+                continue;
+            }
+            let count = deltas.iter().filter(|&&d| d == value).count();
+            if count > best || (count == best && value < stride) {
+                (stride, best) = (value, count);
+            }
+            let p = count as f32 / total;
+            entropy -= p * libm::log2f(p);
+        }
+        let max_entropy = libm::log2f(total);
+        let sequentiality = sequential as f32 / total;
+        WorkloadFeatures {
+            deltas: len,
+            duplicates,
+            sequentiality,
+            stride,
+            stride_confidence: best as f32 / total,
+            delta_entropy: if max_entropy > 0.0 {
+                entropy / max_entropy
+            } else {
+                0.0
+            },
+            burstiness: if sequential < n { sequentiality } else { 0.0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    fn classify(pattern: Pattern) -> Classification {
+        let mut kernel = AetherLinkKernel::default();
+        for lba in pattern.workload(3).take(500) {
+            kernel.push_lba(lba);
+        }
+        kernel.classification(&ClassifierThresholds::default())
+    }
+
+    #[test]
+    fn test_generator_patterns() {
+        let cases = [
+            (Pattern::SEQUENTIAL, WorkloadClass::Sequential),
+            (Pattern::Sequential { stride: 8 }, WorkloadClass::Strided),
+            (Pattern::RANDOM, WorkloadClass::Random),
+            (Pattern::BURSTY, WorkloadClass::Bursty),
+            (Pattern::HFT_TICK, WorkloadClass::Bursty),
+            (
+                Pattern::Zipfian {
+                    n_blocks: 1_000_000,
+                    theta: 0.99,
+                },
+                WorkloadClass::Random,
+            ),
+            (
+                Pattern::Hotspot {
+                    n_blocks: 1_000_000,
+                    hot_fraction: 0.1,
+                    hot_access_fraction: 0.9,
+                },
+                WorkloadClass::Random,
+            ),
+        ];
+        for (pattern, expected) in cases {
+            let c = classify(pattern);
+            assert_eq!(c.class, expected, "{pattern:?}: {c:?}");
+            assert!(c.confidence > 0.5, "{pattern:?}: {c:?}");
+        }
+        assert_eq!(
+            classify(Pattern::Sequential { stride: 8 }).features.stride,
+            8
+        );
+    }
+
+    #[test]
+    fn test_unknown_until_enough_history() {
+        let mut kernel = AetherLinkKernel::default();
+        assert_eq!(kernel.classify(), WorkloadClass::Unknown);
+        for lba in 0..8 {
+            kernel.push_lba(lba);
+            assert_eq!(kernel.classify(), WorkloadClass::Unknown);
+        }
+        kernel.push_lba(8);
+        assert_eq!(kernel.classify(), WorkloadClass::Sequential);
+
+        // Slice-driven kernels keep no history.
+        let mut sliced = AetherLinkKernel::default();
+        for i in 0..100u64 {
+            sliced.process_io_cycle(&[i, i + 1, i + 2, i + 3]);
+        }
+        assert_eq!(sliced.classify(), WorkloadClass::Unknown);
+    }
+
+    #[test]
+    fn test_mixed_between_thresholds() {
+        // Two alternating strides: neither sequential, strided, random
+        // nor bursty.
+        let mut kernel = AetherLinkKernel::default();
+        let mut lba = 0u64;
+        for i in 0..64 {
+            lba += if i % 2 == 0 { 4 } else { 12 };
+            kernel.push_lba(lba);
+        }
+        let c = kernel.classification(&ClassifierThresholds::default());
+        assert_eq!(c.class, WorkloadClass::Mixed, "{c:?}");
+        assert!((0.0..=1.0).contains(&c.confidence));
+    }
+}
@@ -0,0 +1,243 @@
+//! Kernel parameter sets.
+//!
+//! [`KernelConfig`] is the plain-value form of the four constructor
+//! parameters.  It is what tuners produce, what profile files store, and —
+//! via [`AetherLinkKernel::try_new`] — the single place where parameter
+//! validity is checked.
+
+use crate::AetherLinkKernel;
+use alloc::string::String;
+use core::f32::consts::PI;
+use core::fmt;
+use core::ops::RangeInclusive;
+
+/// Accepted range for the initial threshold ε.
+pub const EPSILON_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+/// Accepted range for the initial POVM basis angle φ (radians).
+pub const PHI_RANGE: RangeInclusive<f32> = 0.0..=(2.0 * PI);
+/// Accepted range for each λ coefficient.
+pub const LAMBDA_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+/// Accepted range for the sigmoid bias.
+pub const BIAS_RANGE: RangeInclusive<f32> = -5.0..=5.0;
+
+/// Why a parameter set or config file was rejected.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A parameter was NaN or infinite.
+    NonFinite {
+        /// Parameter name (`"epsilon"`, `"lambda[1]"`, …).
+        field: &'static str,
+    },
+    /// A parameter was outside its documented range.
+    OutOfRange {
+        /// Parameter name.
+        field: &'static str,
+        /// Offending value.
+        value: f32,
+        /// Inclusive lower bound.
+        min: f32,
+        /// Inclusive upper bound.
+        max: f32,
+    },
+    /// Reading or writing a config file failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// A config file could not be decoded or encoded.
+    Format(String),
+    /// No profile of that name (see [`ProfileStore`](crate::profile::ProfileStore)).
+    UnknownProfile(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonFinite { field } => write!(f, "{field} must be finite"),
+            Self::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(f, "{field} = {value} is outside [{min}, {max}]"),
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(f, "config file: {e}"),
+            Self::Format(msg) => write!(f, "config format: {msg}"),
+            Self::UnknownProfile(name) => write!(f, "unknown profile '{name}'"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The tunable parameters of an [`AetherLinkKernel`].
+///
+/// Field meanings match [`AetherLinkKernel::new`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelConfig {
+    /// Initial adaptive threshold ε.
+    pub epsilon: f32,
+    /// Initial POVM basis angle φ (radians).
+    pub phi: f32,
+    /// Adaptation coefficients [λ₁, λ₂, λ₃].
+    pub lambda: [f32; 3],
+    /// Sigmoid bias.
+    pub bias: f32,
+}
+
+impl Default for KernelConfig {
+    fn default() -> Self {
+        AetherLinkKernel::default().config()
+    }
+}
+
+#[inline]
+fn check(field: &'static str, value: f32, range: &RangeInclusive<f32>) -> Result<(), ConfigError> {
+    if !value.is_finite() {
+        return Err(ConfigError::NonFinite { field });
+    }
+    if !range.contains(&value) {
+        return Err(ConfigError::OutOfRange {
+            field,
+            value,
+            min: *range.start(),
+            max: *range.end(),
+        });
+    }
+    Ok(())
+}
+
+impl KernelConfig {
+    /// Check every parameter against its documented range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check("epsilon", self.epsilon, &EPSILON_RANGE)?;
+        check("phi", self.phi, &PHI_RANGE)?;
+        const NAMES: [&str; 3] = ["lambda[0]", "lambda[1]", "lambda[2]"];
+        for (name, &l) in NAMES.iter().zip(&self.lambda) {
+            check(name, l, &LAMBDA_RANGE)?;
+        }
+        check("bias", self.bias, &BIAS_RANGE)
+    }
+
+    /// Build a fresh kernel from this config after validating it.
+    #[inline]
+    pub fn build(&self) -> Result<AetherLinkKernel, ConfigError> {
+        AetherLinkKernel::try_new(self.epsilon, self.phi, self.lambda, self.bias)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl KernelConfig {
+    /// Decode and validate a TOML document.
+    pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+        let cfg: Self = toml::from_str(s).map_err(|e| ConfigError::Format(e.to_string()))?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Encode as a TOML document.
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        toml::to_string(self).map_err(|e| ConfigError::Format(e.to_string()))
+    }
+
+    /// Decode and validate a JSON document.
+    pub fn from_json_str(s: &str) -> Result<Self, ConfigError> {
+        let cfg: Self = serde_json::from_str(s).map_err(|e| ConfigError::Format(e.to_string()))?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Encode as pretty-printed JSON.
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        serde_json::to_string_pretty(self).map_err(|e| ConfigError::Format(e.to_string()))
+    }
+
+    /// Load a config file; `.json` files are read as JSON, anything else as
+    /// TOML.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_toml_str(&text)
+        }
+    }
+
+    /// Write a config file, choosing the encoding like [`load`](Self::load).
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let text = if is_json(path) {
+            self.to_json_string()?
+        } else {
+            self.to_toml_string()?
+        };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_json(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in crate::Preset::ALL {
+            preset.kernel().config().validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_values() {
+        let cfg = KernelConfig {
+            epsilon: f32::NAN,
+            ..KernelConfig::default()
+        };
+        assert!(matches!(
+            cfg.validate(),
+            Err(ConfigError::NonFinite { field: "epsilon" })
+        ));
+        let mut cfg = KernelConfig::default();
+        cfg.lambda[2] = 3.0;
+        assert!(matches!(
+            cfg.validate(),
+            Err(ConfigError::OutOfRange {
+                field: "lambda[2]",
+                ..
+            })
+        ));
+        assert!(AetherLinkKernel::try_new(0.5, -1.0, [0.1; 3], 0.0).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_toml_and_json_round_trip() {
+        let cfg = AetherLinkKernel::new_hft().config();
+        let toml = cfg.to_toml_string().unwrap();
+        assert_eq!(KernelConfig::from_toml_str(&toml).unwrap(), cfg);
+        let json = cfg.to_json_string().unwrap();
+        assert_eq!(KernelConfig::from_json_str(&json).unwrap(), cfg);
+        assert!(KernelConfig::from_toml_str("epsilon = 'high'").is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! Delta correlation: what to fetch once the kernel decides to.
+//!
+//! Stride extrapolation has nothing to offer a stream whose steps follow
+//! an irregular but repeating sequence — pointer chasing through a fixed
+//! structure, a tree walk over the same pages.  With
+//! [`AetherLinkKernel::enable_delta_correlation`] the kernel keeps, next to
+//! the POVM decision, a correlation table in the manner of the
+//! [global history buffer](crate::baseline::ghb): each cycle's step (its
+//! access minus the previous cycle's) is filed under the two steps before
+//! it, in a direct-mapped table of [`CORRELATION_SLOTS`] entries with a
+//! two-bit confidence that rises when the same step follows the same pair
+//! again and falls when another one does.
+//!
+//! The decision still says whether to prefetch; the table says what.  On
+//! a firing cycle, confident entries are chained from the newest two steps
+//! into up to [`CORRELATION_DEGREE`] predicted steps, and
+//! [`correlation_candidates`](AetherLinkKernel::correlation_candidates)
+//! returns the blocks they lead to.  Without a confident entry the
+//! candidates fall back to stride extrapolation, the newest step repeated.
+//! [`CorrelationStats`] counts the candidates of each source and the
+//! accesses that landed on one still outstanding (those of the newest
+//! firing cycle), so the two can be weighed against each other.
+
+use crate::AetherLinkKernel;
+use alloc::boxed::Box;
+
+/// Entries in the correlation table.
+pub const CORRELATION_SLOTS: usize = 256;
+
+/// Most candidates a firing cycle produces.
+pub const CORRELATION_DEGREE: usize = 4;
+
+/// Lowest confidence an entry is predicted from (of 3).
+const CONFIDENT: u8 = 2;
+
+/// Where a cycle's candidates came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CandidateSource {
+    /// Chained confident entries of the correlation table.
+    Correlation,
+    /// The newest step repeated.
+    Stride,
+}
+
+/// Candidate and hit counters per source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CorrelationStats {
+    /// Firing cycles whose candidates came from the table.
+    pub correlated: u64,
+    /// Firing cycles that fell back to stride extrapolation.
+    pub extrapolated: u64,
+    /// Candidates the table produced.
+    pub table_candidates: u64,
+    /// Accesses that landed on an outstanding table candidate.
+    pub table_hits: u64,
+    /// Candidates stride extrapolation produced.
+    pub stride_candidates: u64,
+    /// Accesses that landed on an outstanding stride candidate.
+    pub stride_hits: u64,
+}
+
+/// The step that followed `key`; `confidence == 0` marks a free slot.
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    key: (i64, i64),
+    next: i64,
+    confidence: u8,
+}
+
+/// Correlation table, the newest steps and the outstanding candidates.
+#[derive(Debug, Clone)]
+pub(crate) struct DeltaCorrelation {
+    table: [Slot; CORRELATION_SLOTS],
+    /// Newest access.
+    prev: Option<u64>,
+    /// The two newest steps, older first; `steps_seen` of them are real.
+    steps: [i64; 2],
+    steps_seen: u8,
+    candidates: [u64; CORRELATION_DEGREE],
+    len: usize,
+    source: CandidateSource,
+    /// Whether the newest cycle fired.
+    fired: bool,
+    /// Candidates not yet hit, one bit each.
+    pending: u8,
+    stats: CorrelationStats,
+}
+
+fn slot(key: (i64, i64)) -> usize {
+    let h = (key.0 as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .rotate_left(31)
+        ^ (key.1 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    (h % CORRELATION_SLOTS as u64) as usize
+}
+
+impl DeltaCorrelation {
+    fn new() -> Self {
+        Self {
+            table: [Slot::default(); CORRELATION_SLOTS],
+            prev: None,
+            steps: [0; 2],
+            steps_seen: 0,
+            candidates: [0; CORRELATION_DEGREE],
+            len: 0,
+            source: CandidateSource::Stride,
+            fired: false,
+            pending: 0,
+            stats: CorrelationStats::default(),
+        }
+    }
+
+    /// Account for the cycle's access, before the decision: score the
+    /// outstanding candidates and train the table on the new step.
+    #[inline]
+    pub(crate) fn observe(&mut self, lba: u64) {
+        let mut hit = 0;
+        for (i, &candidate) in self.candidates.iter().take(self.len).enumerate() {
+            hit |= ((candidate == lba) as u8) << i;
+        }
+        if self.pending & hit != 0 {
+            match self.source {
+                CandidateSource::Correlation => self.stats.table_hits += 1,
+                CandidateSource::Stride => self.stats.stride_hits += 1,
+            }
+        }
+        self.pending &= !hit;
+        let Some(prev) = self.prev.replace(lba) else {
+            return;
+        };
+        let step = lba.wrapping_sub(prev) as i64;
+        if self.steps_seen == 2 {
+            self.train((self.steps[0], self.steps[1]), step);
+        }
+        self.steps = [self.steps[1], step];
+        self.steps_seen = (self.steps_seen + 1).min(2);
+    }
+
+    fn train(&mut self, key: (i64, i64), step: i64) {
+        let slot = &mut self.table[slot(key)];
+        if slot.confidence == 0 || slot.key != key {
+            *slot = Slot {
+                key,
+                next: step,
+                confidence: 1,
+            };
+        } else if slot.next == step {
+            slot.confidence = (slot.confidence + 1).min(3);
+        } else {
+            slot.confidence -= 1;
+            if slot.confidence == 0 {
+                slot.next = step;
+                slot.confidence = 1;
+            }
+        }
+    }
+
+    fn predict(&self, key: (i64, i64)) -> Option<i64> {
+        let slot = &self.table[slot(key)];
+        (slot.key == key && slot.confidence >= CONFIDENT).then_some(slot.next)
+    }
+
+    /// Record the cycle's decision, producing candidates if it fired.
+    #[inline]
+    pub(crate) fn decided(&mut self, fetch: bool) {
+        self.fired = fetch;
+        let Some(lba) = self.prev.filter(|_| fetch) else {
+            return;
+        };
+        let mut len = 0;
+        let mut addr = lba;
+        if self.steps_seen == 2 {
+            let mut key = (self.steps[0], self.steps[1]);
+            while len < CORRELATION_DEGREE {
+                let Some(next) = self.predict(key) else {
+                    break;
+                };
+                let Some(target) = addr.checked_add_signed(next) else {
+                    break;
+                };
+                self.candidates[len] = target;
+                len += 1;
+                addr = target;
+                key = (key.1, next);
+            }
+        }
+        if len > 0 {
+            self.source = CandidateSource::Correlation;
+            self.stats.correlated += 1;
+            self.stats.table_candidates += len as u64;
+        } else {
+            let stride = self.steps[1];
+            if self.steps_seen > 0 && stride != 0 {
+                while len < CORRELATION_DEGREE {
+                    let Some(target) = addr.checked_add_signed(stride) else {
+                        break;
+                    };
+                    self.candidates[len] = target;
+                    len += 1;
+                    addr = target;
+                }
+            }
+            self.source = CandidateSource::Stride;
+            self.stats.extrapolated += 1;
+            self.stats.stride_candidates += len as u64;
+        }
+        self.len = len;
+        self.pending = (1 << len) - 1;
+    }
+}
+
+impl AetherLinkKernel {
+    /// Keep a delta-correlation table and produce candidates on firing
+    /// cycles, starting from an empty table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::correlation::CandidateSource;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_delta_correlation();
+    /// let mut lba = 10_000u64;
+    /// for i in 0..200 {
+    ///     lba += [3, 1, 2][i % 3];
+    ///     kernel.push_lba(lba);
+    /// }
+    /// // The next steps are 2, 3, 1, 2.
+    /// let (source, candidates) = kernel.correlation_candidates().unwrap();
+    /// assert_eq!(source, CandidateSource::Correlation);
+    /// assert_eq!(candidates, [lba + 2, lba + 5, lba + 6, lba + 8]);
+    /// ```
+    pub fn enable_delta_correlation(&mut self) {
+        self.correlation = Some(Box::new(DeltaCorrelation::new()));
+    }
+
+    /// Stop correlating and drop the table.
+    #[inline]
+    pub fn disable_delta_correlation(&mut self) {
+        self.correlation = None;
+    }
+
+    /// Blocks to fetch for the newest cycle and where they came from, if
+    /// it fired with correlation on.  Stride extrapolation of a still
+    /// stream produces no candidates.
+    pub fn correlation_candidates(&self) -> Option<(CandidateSource, &[u64])> {
+        let c = self.correlation.as_ref().filter(|c| c.fired)?;
+        Some((c.source, &c.candidates[..c.len]))
+    }
+
+    /// Per-source candidate and hit counters; all zero while correlation
+    /// is off.
+    pub fn correlation_stats(&self) -> CorrelationStats {
+        self.correlation
+            .as_ref()
+            .map_or_else(CorrelationStats::default, |c| c.stats)
+    }
+
+    /// Zero the correlation counters, keeping the table.
+    pub(crate) fn reset_correlation_stats(&mut self) {
+        if let Some(c) = &mut self.correlation {
+            c.stats = CorrelationStats::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An irregular step sequence with net forward drift.
+    const STEPS: [i64; 8] = [3, 1, -2, 5, 1, 1, -1, 2];
+
+    fn trace(n: usize) -> Vec<u64> {
+        let mut lba: u64 = 1 << 24;
+        (0..n)
+            .map(|i| {
+                lba = lba.checked_add_signed(STEPS[i % STEPS.len()]).unwrap();
+                lba
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hybrid_beats_extrapolation_on_repeating_steps() {
+        let lbas = trace(8_000);
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_delta_correlation();
+
+        // Pure extrapolation from the same firing cycles.
+        let (mut pending, mut extrapolated_hits) = (Vec::new(), 0);
+        let mut prev = None;
+        for (at, &lba) in lbas.iter().enumerate() {
+            if let Some(i) = pending.iter().position(|&c| c == lba) {
+                pending.swap_remove(i);
+                extrapolated_hits += 1;
+            }
+            let fired = kernel.push_lba(lba).is_some_and(|d| d.fire);
+            if fired {
+                let stride = lba.wrapping_sub(prev.unwrap()) as i64;
+                pending = (1..=CORRELATION_DEGREE as i64)
+                    .map(|k| lba.wrapping_add((stride * k) as u64))
+                    .collect();
+                let (source, candidates) = kernel.correlation_candidates().unwrap();
+                assert!(at < 3 || !candidates.is_empty(), "{at}");
+                if source == CandidateSource::Correlation {
+                    let next = trace(at + 1 + candidates.len());
+                    assert_eq!(candidates, &next[at + 1..]);
+                }
+            } else {
+                assert!(kernel.correlation_candidates().is_none());
+            }
+            prev = Some(lba);
+        }
+
+        let s = kernel.correlation_stats();
+        let n = lbas.len() as f64;
+        let hybrid = (s.table_hits + s.stride_hits) as f64 / n;
+        let extrapolated = extrapolated_hits as f64 / n;
+        assert!(s.correlated > 10 * s.extrapolated, "{s:?}");
+        assert!(hybrid > 0.5, "{s:?}");
+        assert!(hybrid > 3.0 * extrapolated, "{hybrid} vs {extrapolated}");
+        // The first table candidate is always the next access.
+        assert!(s.table_hits + 1 >= s.correlated, "{s:?}");
+
+        kernel.reset_stats();
+        assert_eq!(kernel.correlation_stats(), CorrelationStats::default());
+    }
+
+    #[test]
+    fn test_falls_back_to_stride_and_unlearns() {
+        let mut kernel = AetherLinkKernel::new_database();
+        kernel.enable_delta_correlation();
+        // A constant stride is learned as the pair (4, 4) → 4 as well.
+        let mut fired = 0;
+        for lba in (0..400u64).map(|i| 5_000 + i * 4) {
+            kernel.push_lba(lba);
+            if let Some((source, candidates)) = kernel.correlation_candidates() {
+                if lba > 5_100 {
+                    assert_eq!(source, CandidateSource::Correlation);
+                    assert_eq!(candidates, [lba + 4, lba + 8, lba + 12, lba + 16]);
+                    fired += 1;
+                }
+            }
+        }
+        assert!(fired > 100);
+
+        // A new, never-repeating walk has no confident entries.
+        let mut lba = 1 << 30;
+        for i in 1..200u64 {
+            lba += i;
+            kernel.push_lba(lba);
+        }
+        let s = kernel.correlation_stats();
+        if let Some((source, candidates)) = kernel.correlation_candidates() {
+            assert_eq!(source, CandidateSource::Stride);
+            assert_eq!(candidates[0], lba + 199);
+        }
+        assert!(s.extrapolated > 0 && s.stride_candidates > 0, "{s:?}");
+
+        kernel.disable_delta_correlation();
+        assert_eq!(kernel.correlation_candidates(), None);
+        assert_eq!(kernel.correlation_stats(), CorrelationStats::default());
+    }
+}
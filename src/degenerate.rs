@@ -0,0 +1,175 @@
+//! Degenerate streams: empty, single-block, repeated and stuttering
+//! windows.
+//!
+//! Some windows give the telemetry nothing, or less than it seems, to go
+//! on.  What the kernel does with each:
+//!
+//! - **Fewer than two LBAs** — an empty or one-element slice, the first
+//!   [`push_lba`](AetherLinkKernel::push_lba), an empty micro-batch — have
+//!   no span.  No cycle runs: ε, φ, the DSP state, the `cycles` counter and
+//!   every optional stage are left as they were, the call is counted in
+//!   [`KernelStats::short_windows`](crate::KernelStats::short_windows), and
+//!   it returns a non-firing outcome (`None` from the streaming calls).
+//! - **A window that ends on the block it started at** — all-identical
+//!   being the pure case — is a re-read.  It has no direction to
+//!   extrapolate, so the cycle never fires, whatever `p_fetch` says; it
+//!   adapts as any other cycle does and is counted in
+//!   [`KernelStats::rereads`](crate::KernelStats::rereads), a block-reuse
+//!   signal for the caller's cache.  A history made of repeats classifies
+//!   as [`WorkloadClass::ReRead`](crate::classify::WorkloadClass::ReRead).
+//! - **A window that advances but repeats blocks** — each block read
+//!   twice, say — moves at the pace of its moves.  Repeats are left out of
+//!   the [classifier](crate::classify)'s stride, sequentiality and entropy,
+//!   and out of the [horizon](crate::horizon)'s velocity estimate, so a
+//!   stride-64 stream read twice over plans stride-64 extents rather than
+//!   ones half as long.
+
+use crate::{AetherLinkKernel, CycleOutcome};
+
+impl CycleOutcome {
+    /// Outcome of a window with fewer than two LBAs, for a kernel at
+    /// threshold `epsilon`: no cycle ran, nothing fires.
+    #[inline]
+    pub(crate) fn short_window(epsilon: f32, threshold_shift: f32) -> Self {
+        Self {
+            fetch: false,
+            p_fetch: 0.0,
+            threshold: (epsilon + threshold_shift).clamp(0.0, 1.0),
+            degraded: false,
+            damping: 1.0,
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Outcome of a call that saw fewer than two LBAs: counted, and
+    /// nothing else.
+    #[inline]
+    pub(crate) fn short_window(&mut self, threshold_shift: f32) -> CycleOutcome {
+        self.counters.short_window();
+        CycleOutcome::short_window(self.epsilon, threshold_shift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classify::{ClassifierThresholds, WorkloadClass};
+    use crate::gap::GapPolicy;
+    use crate::hint::Hint;
+    use crate::AetherLinkKernel;
+
+    /// A kernel with some history and the stateful stages on.
+    fn warmed() -> AetherLinkKernel {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(8);
+        kernel.enable_heatmap(0..1 << 20, 64);
+        for i in 0..500u64 {
+            kernel.push_lba(1_000 + 3 * i);
+            kernel.process_io_cycle(&[i, i + 5, i + 9]);
+        }
+        kernel
+    }
+
+    #[test]
+    fn test_empty_and_single_windows_skip_adaptation() {
+        let mut kernel = warmed();
+        let reference = kernel.clone();
+        for window in [&[][..], &[42]] {
+            assert!(!kernel.process_io_cycle(window));
+            let out = kernel.process_io_cycle_qos(window, -0.3);
+            assert!(!out.fetch && out.p_fetch == 0.0, "{out:?}");
+            assert!(!kernel.process_io_cycle_hinted(window, Hint::Boost));
+        }
+        // A one-LBA micro-batch extends the history; an empty one does not.
+        assert_eq!(kernel.process_micro_batch(&[]).outcome, None);
+        assert_eq!(kernel.epsilon.to_bits(), reference.epsilon.to_bits());
+        assert_eq!(kernel.phi.to_bits(), reference.phi.to_bits());
+        assert_eq!(kernel.horizon_stats(), reference.horizon_stats());
+        let (s, r) = (kernel.stats(), reference.stats());
+        assert_eq!(s.cycles, r.cycles);
+        assert_eq!(s.short_windows, r.short_windows + 7);
+        assert_eq!(
+            kernel.stats().delta(&r),
+            crate::KernelStats {
+                short_windows: 7,
+                rolling_ratio: r.rolling_ratio,
+                ..Default::default()
+            }
+        );
+
+        // The state left behind decides the next cycle as if the short
+        // windows never came.
+        let mut reference = reference;
+        for i in 0..200u64 {
+            let window = [i * 7, i * 7 + 2, i * 7 + 3];
+            assert_eq!(
+                kernel.process_io_cycle_qos(&window, 0.0),
+                reference.process_io_cycle_qos(&window, 0.0)
+            );
+        }
+
+        // A fresh stream's first push is the streaming counterpart.
+        let mut fresh = AetherLinkKernel::default();
+        assert_eq!(fresh.push_lba(5), None);
+        assert_eq!((fresh.cycles, fresh.stats().short_windows), (0, 1));
+    }
+
+    #[test]
+    fn test_identical_windows_reread_and_never_fire() {
+        // The default kernel fires on nearly any window that moves.
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(8);
+        for _ in 0..1_000 {
+            assert!(!kernel.process_io_cycle(&[77; 8]));
+            assert!(!kernel.push_lba(4_096).is_some_and(|d| d.fire));
+        }
+        let s = kernel.stats();
+        assert_eq!(s.prefetches, 0);
+        assert_eq!(s.rereads, 1_000 + 999);
+        assert_eq!(kernel.horizon_plan(), None);
+        let c = kernel.classification(&ClassifierThresholds::default());
+        assert_eq!((c.class, c.confidence), (WorkloadClass::ReRead, 1.0));
+        assert_eq!(c.class.to_string(), "re-read");
+
+        // Once the stream moves again it fires as before.
+        let fired = (4_097..4_200u64)
+            .filter(|&lba| kernel.push_lba(lba).is_some_and(|d| d.fire))
+            .count();
+        assert!(fired > 0);
+        assert_eq!(kernel.classify(), WorkloadClass::Sequential);
+    }
+
+    #[test]
+    fn test_duplicates_discounted_from_stride_and_velocity() {
+        for (stride, class) in [
+            (1u64, WorkloadClass::Sequential),
+            (64, WorkloadClass::Strided),
+        ] {
+            let mut kernel = AetherLinkKernel::default();
+            kernel.set_horizon(8);
+            kernel.set_gap_policy(GapPolicy::Exact);
+            let mut plans = 0;
+            for i in 0..2_000u64 {
+                let lba = 1_000_000 + i / 2 * stride;
+                let Some(plan) = kernel.push_lba(lba).and_then(|d| d.target) else {
+                    continue;
+                };
+                // The plan runs at the stream's stride, not half of it.
+                let reads: Vec<u64> = kernel.target_extents(plan).map(|e| e.start).collect();
+                if stride == 1 {
+                    assert_eq!(plan.start, lba + 1, "{plan:?}");
+                } else {
+                    let points: Vec<u64> = (1..=8).map(|k| lba + k * stride).collect();
+                    assert_eq!(reads, points);
+                }
+                plans += 1;
+            }
+            assert!(plans > 10, "stride {stride}: {plans} plans");
+
+            let c = kernel.classification(&ClassifierThresholds::default());
+            assert_eq!(c.class, class, "{c:?}");
+            assert_eq!(c.features.stride, stride as i64);
+            assert!((c.features.duplicates - 0.5).abs() < 0.05, "{c:?}");
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Damping by device queue occupancy.
+//!
+//! Prefetch reads added to an already deep submission queue sit in front
+//! of demand reads and stretch their tail latency.  Reporting the queue
+//! with [`AetherLinkKernel::set_queue_depth`] scales every following
+//! decision's fetch probability by a factor that falls from 1 on an empty
+//! queue to 0 on a full one, along a [`DepthCurve`].  The factor is
+//! computed in the setter, so the decision path pays one multiply; it
+//! stays in force until the next report.
+
+use crate::{fast_math, AetherLinkKernel};
+
+/// Shape of the damping factor over queue occupancy `o` in [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthCurve {
+    /// `1 − o`.
+    #[default]
+    Linear,
+    /// A logistic step centred on `midpoint`, rescaled to run exactly from
+    /// 1 at `o = 0` to 0 at `o = 1`: little damping until the queue nears
+    /// the midpoint, then a sharp drop.
+    Sigmoid {
+        /// Occupancy of the steepest drop.
+        midpoint: f32,
+        /// Slope of the step; larger is sharper.
+        steepness: f32,
+    },
+}
+
+impl DepthCurve {
+    /// Damping factor at occupancy `o` (clamped to [0, 1]).
+    pub fn factor(self, o: f32) -> f32 {
+        let o = if o.is_nan() { 0.0 } else { o.clamp(0.0, 1.0) };
+        match self {
+            Self::Linear => 1.0 - o,
+            Self::Sigmoid {
+                midpoint,
+                steepness,
+            } => {
+                let s = |x: f32| fast_math::fast_sigmoid(-steepness * (x - midpoint));
+                let (at0, at1) = (s(0.0), s(1.0));
+                if at0 == at1 {
+                    return 1.0 - o;
+                }
+                ((s(o) - at1) / (at0 - at1)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Reported occupancy and the factor it gives.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DepthDamping {
+    pub(crate) curve: DepthCurve,
+    pub(crate) occupancy: f32,
+    pub(crate) factor: f32,
+}
+
+impl Default for DepthDamping {
+    fn default() -> Self {
+        Self {
+            curve: DepthCurve::Linear,
+            occupancy: 0.0,
+            factor: 1.0,
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Report that `current` of `max` queue slots are in use; decisions
+    /// from the next cycle on are damped accordingly.  `max == 0` counts
+    /// as an empty queue.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// kernel.set_queue_depth(32, 32);
+    /// let out = kernel.process_io_cycle_qos(&[10, 11, 12, 13], 0.0);
+    /// assert_eq!(out.damping, 0.0);
+    /// assert!(!out.fetch);
+    /// ```
+    #[inline]
+    pub fn set_queue_depth(&mut self, current: u32, max: u32) {
+        let occupancy = if max == 0 {
+            0.0
+        } else {
+            current.min(max) as f32 / max as f32
+        };
+        self.depth.occupancy = occupancy;
+        self.depth.factor = self.depth.curve.factor(occupancy);
+    }
+
+    /// Use `curve` for queue-depth damping (default
+    /// [`DepthCurve::Linear`]).
+    pub fn set_depth_curve(&mut self, curve: DepthCurve) {
+        self.depth.curve = curve;
+        self.depth.factor = curve.factor(self.depth.occupancy);
+    }
+
+    /// Last reported queue occupancy in [0, 1].
+    #[inline]
+    pub fn queue_occupancy(&self) -> f32 {
+        self.depth.occupancy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [DepthCurve; 3] = [
+        DepthCurve::Linear,
+        DepthCurve::Sigmoid {
+            midpoint: 0.7,
+            steepness: 12.0,
+        },
+        DepthCurve::Sigmoid {
+            midpoint: 0.5,
+            steepness: 0.0,
+        },
+    ];
+
+    #[test]
+    fn test_factor_is_monotonic_from_one_to_zero() {
+        for curve in CURVES {
+            assert_eq!(curve.factor(0.0), 1.0, "{curve:?}");
+            assert_eq!(curve.factor(1.0), 0.0, "{curve:?}");
+            let mut prev = 1.0;
+            for i in 0..=1_000 {
+                let f = curve.factor(i as f32 / 1_000.0);
+                assert!(f <= prev, "{curve:?} at {i}");
+                prev = f;
+            }
+        }
+        assert!(CURVES[1].factor(0.4) > 0.9, "sigmoid holds off until busy");
+    }
+
+    #[test]
+    fn test_triggers_fall_with_occupancy() {
+        let lbas: Vec<[u64; 2]> = (0..2_000u64).map(|i| [i * 7, i * 7 + i % 40]).collect();
+        for curve in CURVES {
+            let mut fired = Vec::new();
+            for depth in [0, 8, 16, 24, 32] {
+                let mut kernel = AetherLinkKernel::new_gaming();
+                kernel.set_depth_curve(curve);
+                kernel.set_queue_depth(depth, 32);
+                let n = lbas
+                    .iter()
+                    .filter(|w| kernel.process_io_cycle(&w[..]))
+                    .count();
+                fired.push(n);
+            }
+            assert!(
+                fired.windows(2).all(|w| w[1] <= w[0]),
+                "{curve:?} {fired:?}"
+            );
+            assert!(fired[0] > 0, "{curve:?}");
+            assert_eq!(fired[4], 0, "{curve:?}");
+        }
+    }
+
+    #[test]
+    fn test_empty_queue_is_unchanged() {
+        let mut plain = AetherLinkKernel::default();
+        let mut damped = plain.clone();
+        damped.set_queue_depth(0, 64);
+        for i in 0..500u64 {
+            let a = plain.process_io_cycle_qos(&[i, i * 3], 0.0);
+            let b = damped.process_io_cycle_qos(&[i, i * 3], 0.0);
+            assert_eq!(a, b);
+            assert_eq!(b.damping, 1.0);
+        }
+        damped.set_queue_depth(5, 0);
+        assert_eq!(damped.queue_occupancy(), 0.0);
+    }
+}
@@ -0,0 +1,221 @@
+//! How far two kernels' learned states have drifted apart.
+//!
+//! Replicas built from one configuration and fed the same traffic learn
+//! the same ε and φ; when their states part, they are seeing different
+//! traffic or one of them misbehaves.  [`AetherLinkKernel::diff`] measures
+//! the gap as a [`StateDelta`]: absolute differences of ε, the bias and
+//! each λ, the circular distance between the two angles φ (so 0.01 and
+//! 2π − 0.01 are 0.02 apart, not 2π − 0.02), and the relative skew of the
+//! cycle and trigger counters.  [`StateDelta::divergence`] folds them into
+//! one number under [`DivergenceWeights`], and the `Display` impl writes a
+//! one-line summary for logs.
+
+use crate::AetherLinkKernel;
+use core::f32::consts::PI;
+use core::fmt;
+
+/// Per-parameter differences between two kernels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateDelta {
+    /// `|ε₁ − ε₂|`.
+    pub epsilon: f32,
+    /// Circular distance between the two φ, in `[0, π]`.
+    pub phi: f32,
+    /// `|λ₁ − λ₁'|`, `|λ₂ − λ₂'|`, `|λ₃ − λ₃'|`.
+    pub lambda: [f32; 3],
+    /// `|bias₁ − bias₂|`.
+    pub bias: f32,
+    /// `|a − b| / max(a, b)` of the cycle counters, 0 when both are 0.
+    pub cycle_skew: f32,
+    /// The same skew of the trigger counters.
+    pub prefetch_skew: f32,
+}
+
+/// Weight of each difference in [`StateDelta::divergence_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DivergenceWeights {
+    /// Weight of the ε difference.
+    pub epsilon: f32,
+    /// Weight of the φ distance.
+    pub phi: f32,
+    /// Weight of the summed λ differences.
+    pub lambda: f32,
+    /// Weight of the bias difference.
+    pub bias: f32,
+    /// Weight of the larger counter skew.
+    pub counters: f32,
+}
+
+impl Default for DivergenceWeights {
+    /// Every difference at face value, except φ, scaled so that opposite
+    /// angles count 1.
+    fn default() -> Self {
+        Self {
+            epsilon: 1.0,
+            phi: 1.0 / PI,
+            lambda: 1.0,
+            bias: 1.0,
+            counters: 1.0,
+        }
+    }
+}
+
+impl StateDelta {
+    /// [`divergence_with`](Self::divergence_with) the default weights.
+    #[inline]
+    pub fn divergence(&self) -> f32 {
+        self.divergence_with(&DivergenceWeights::default())
+    }
+
+    /// Weighted sum of the differences: 0 for identical states.
+    pub fn divergence_with(&self, weights: &DivergenceWeights) -> f32 {
+        let lambda: f32 = self.lambda.iter().sum();
+        weights.epsilon * self.epsilon
+            + weights.phi * self.phi
+            + weights.lambda * lambda
+            + weights.bias * self.bias
+            + weights.counters * self.cycle_skew.max(self.prefetch_skew)
+    }
+}
+
+impl fmt::Display for StateDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [l1, l2, l3] = self.lambda;
+        write!(
+            f,
+            "Δε {:.4}  Δφ {:.4}  Δλ [{l1:.4}, {l2:.4}, {l3:.4}]  Δbias {:.4}  \
+             skew cycles {:.1}% triggers {:.1}%  divergence {:.4}",
+            self.epsilon,
+            self.phi,
+            self.bias,
+            self.cycle_skew * 100.0,
+            self.prefetch_skew * 100.0,
+            self.divergence(),
+        )
+    }
+}
+
+/// Distance between two angles around the circle, in `[0, π]`.
+fn circular_distance(a: f32, b: f32) -> f32 {
+    let d = libm::fmodf(libm::fabsf(a - b), 2.0 * PI);
+    d.min(2.0 * PI - d)
+}
+
+/// `|a − b| / max(a, b)`, 0 when both are 0.
+fn skew(a: u64, b: u64) -> f32 {
+    let hi = a.max(b);
+    if hi == 0 {
+        0.0
+    } else {
+        (a.abs_diff(b) as f64 / hi as f64) as f32
+    }
+}
+
+impl AetherLinkKernel {
+    /// Differences between this kernel's learned state and `other`'s.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut a = AetherLinkKernel::default();
+    /// let mut b = a.clone();
+    /// for i in 0..1_000u64 {
+    ///     a.process_io_cycle(&[i, i + 1]);
+    ///     b.process_io_cycle(&[i << 20, (i << 20) + 9_000]);
+    /// }
+    /// let delta = a.diff(&b);
+    /// assert!(delta.divergence() > 0.0);
+    /// assert_eq!(a.diff(&a.clone()).divergence(), 0.0);
+    /// println!("{delta}");
+    /// ```
+    pub fn diff(&self, other: &AetherLinkKernel) -> StateDelta {
+        StateDelta {
+            epsilon: libm::fabsf(self.epsilon - other.epsilon),
+            phi: circular_distance(self.phi, other.phi),
+            lambda: core::array::from_fn(|i| libm::fabsf(self.lambda[i] - other.lambda[i])),
+            bias: libm::fabsf(self.bias - other.bias),
+            cycle_skew: skew(self.cycles, other.cycles),
+            prefetch_skew: skew(self.prefetches, other.prefetches),
+        }
+    }
+
+    /// Whether the [divergence](StateDelta::divergence) from `other` is at
+    /// most `tolerance`.
+    #[inline]
+    pub fn is_close(&self, other: &AetherLinkKernel, tolerance: f32) -> bool {
+        self.diff(other).divergence() <= tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phi_distance_across_the_seam() {
+        let mut a = AetherLinkKernel::default();
+        let mut b = a.clone();
+        a.phi = 0.05;
+        b.phi = 2.0 * PI - 0.05;
+        let delta = a.diff(&b);
+        assert!((delta.phi - 0.1).abs() < 1e-5, "{delta}");
+        assert_eq!(delta.phi, b.diff(&a).phi);
+        assert!(a.is_close(&b, 0.1) && !a.is_close(&b, 0.01));
+
+        // Opposite angles are as far apart as angles get.
+        b.phi = 0.05 + PI;
+        assert!((a.diff(&b).phi - PI).abs() < 1e-5);
+        assert!((a.diff(&b).divergence() - 1.0).abs() < 1e-5);
+        assert!(circular_distance(1.0, 1.0 + 4.0 * PI) < 1e-5);
+    }
+
+    #[test]
+    fn test_clone_diff_is_exactly_zero() {
+        let mut a = AetherLinkKernel::new_database();
+        for i in 0..5_000u64 {
+            a.process_io_cycle(&[i * 17, i * 17 + i % 9]);
+        }
+        let delta = a.diff(&a.clone());
+        assert_eq!(delta, StateDelta::default());
+        assert_eq!(delta.divergence(), 0.0);
+        assert!(a.is_close(&a.clone(), 0.0));
+    }
+
+    #[test]
+    fn test_each_difference_and_its_weight() {
+        let a = AetherLinkKernel::default();
+        let mut b = a.clone();
+        b.epsilon += 0.1;
+        b.bias -= 0.2;
+        b.lambda[2] += 0.3;
+        b.cycles = 100;
+        b.prefetches = 40;
+        let mut c = a.clone();
+        c.cycles = 75;
+        c.prefetches = 40;
+        let delta = b.diff(&c);
+        assert!((delta.epsilon - 0.1).abs() < 1e-6);
+        assert!((delta.bias - 0.2).abs() < 1e-6);
+        assert!((delta.lambda[2] - 0.3).abs() < 1e-6 && delta.lambda[0] == 0.0);
+        assert_eq!((delta.cycle_skew, delta.prefetch_skew), (0.25, 0.0));
+        assert!((delta.divergence() - 0.85).abs() < 1e-5);
+
+        let counters_only = DivergenceWeights {
+            epsilon: 0.0,
+            phi: 0.0,
+            lambda: 0.0,
+            bias: 0.0,
+            counters: 1.0,
+        };
+        assert_eq!(delta.divergence_with(&counters_only), 0.25);
+        let line = delta.to_string();
+        assert!(
+            line.starts_with("Δε 0.1000") && line.contains("cycles 25.0%"),
+            "{line}"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Scan direction: prefetching below a descending stream.
+//!
+//! Reverse scans — a log read tail-first, a backward index scan — move
+//! down the device one step at a time, and are as predictable as forward
+//! ones.  The kernel treats them alike:
+//!
+//! - the window span feeding the telemetry is signed, and the features see
+//!   its magnitude, so a descending run produces the same decisions as the
+//!   ascending run it mirrors;
+//! - [`AetherLinkKernel::scan_direction`] is the sign of the newest window
+//!   span (a still window keeps the previous direction);
+//! - [horizon](crate::horizon) plans follow the sign of the movement
+//!   estimate, covering the blocks just below the access for a descending
+//!   stream;
+//! - a firing decision without a horizon, streamed or sliced, targets one
+//!   stride of the run ending the window and [`LOOKAHEAD_BLOCKS`] more
+//!   below a descending access, as it does above an ascending one (see
+//!   [`process_io_cycle_decision`](AetherLinkKernel::process_io_cycle_decision));
+//! - [micro-batch](crate::microbatch) continuations extend a descending
+//!   batch downwards;
+//! - the [classifier](crate::classify) counts steps of −1 as sequential in
+//!   a window that mostly descends.
+//!
+//! Extents below the access end at block 0: they are trimmed there, and
+//! an access to block 0 has no extent left, so it is dropped like any plan
+//! outside the [address range](AetherLinkKernel::set_address_range).
+
+use crate::horizon::MAX_HORIZON_EXTENT;
+use crate::prefetcher::{PrefetchDecision, PrefetchPlan};
+use crate::AetherLinkKernel;
+
+/// Which way the stream is moving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScanDirection {
+    /// Towards higher LBAs (also before any movement was seen).
+    #[default]
+    Forward,
+    /// Towards lower LBAs.
+    Backward,
+}
+
+/// Steps that must agree before a run's stride sizes an extent.
+pub const MIN_RUN_STEPS: u32 = 2;
+/// Blocks a firing decision fetches beyond the next expected access, or
+/// beyond the access itself when the window ends without a run.
+pub const LOOKAHEAD_BLOCKS: u64 = 8;
+
+/// Stride of the monotone run ending a window given newest LBA first, and
+/// whether it descends.  The stride is the newest step; `None` unless the
+/// [`MIN_RUN_STEPS`] newest steps all move its way and are within a
+/// factor of two of it, so neither a scattered window nor a jump sizes an
+/// extent by its widest step.
+pub(crate) fn run_stride(mut newest_first: impl Iterator<Item = u64>) -> Option<(u64, bool)> {
+    let last = newest_first.next()?;
+    let mut first = newest_first.next()?;
+    if first == last {
+        return None;
+    }
+    let descending = first > last;
+    let stride = first.abs_diff(last);
+    for _ in 1..MIN_RUN_STEPS {
+        let lba = newest_first.next()?;
+        let onward = if descending { lba > first } else { lba < first };
+        let step = lba.abs_diff(first);
+        if !onward || step > stride.saturating_mul(2) || step.saturating_mul(2) < stride {
+            return None;
+        }
+        first = lba;
+    }
+    Some((stride, descending))
+}
+
+/// The `len` blocks just below `lba`, trimmed at block 0.
+#[inline]
+pub(crate) fn below(lba: u64, len: u64) -> PrefetchPlan {
+    PrefetchPlan::new(lba.saturating_sub(len), len.min(lba))
+}
+
+impl AetherLinkKernel {
+    /// Direction of the newest window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::{AetherLinkKernel, ScanDirection};
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// let mut below = 0;
+    /// for lba in (1_000..2_000u64).rev() {
+    ///     if let Some(plan) = kernel.push_lba(lba).and_then(|d| d.target) {
+    ///         assert!(plan.end() <= lba);
+    ///         below += 1;
+    ///     }
+    /// }
+    /// assert_eq!(kernel.scan_direction(), ScanDirection::Backward);
+    /// assert!(below > 500);
+    /// ```
+    #[inline]
+    pub fn scan_direction(&self) -> ScanDirection {
+        if self.descending {
+            ScanDirection::Backward
+        } else {
+            ScanDirection::Forward
+        }
+    }
+
+    /// Target of every firing decision without a horizon plan: for a window
+    /// ending in a run, the blocks up to the next access one stride past
+    /// `last` and [`LOOKAHEAD_BLOCKS`] beyond it, in the run's direction;
+    /// otherwise [`LOOKAHEAD_BLOCKS`] past `last` in the scan direction.
+    #[inline]
+    pub(crate) fn run_target(&mut self, run: Option<(u64, bool)>, last: u64) -> PrefetchDecision {
+        let (len, descending) = run.map_or((LOOKAHEAD_BLOCKS, self.descending), |(stride, d)| {
+            (stride.saturating_add(LOOKAHEAD_BLOCKS), d)
+        });
+        let len = len.min(MAX_HORIZON_EXTENT);
+        let plan = if descending {
+            below(last, len)
+        } else if last == u64::MAX {
+            // Nothing above the last block.
+            PrefetchPlan::default()
+        } else {
+            PrefetchPlan::new(last + 1, len)
+        };
+        self.bound_plan(plan)
+            .map_or(PrefetchDecision::SKIP, |plan| {
+                PrefetchDecision::trigger(Some(plan))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::WorkloadClass;
+
+    const TOP: u64 = 1 << 20;
+
+    /// Fired decisions and their targets for `lbas`.
+    fn run(kernel: &mut AetherLinkKernel, lbas: &[u64]) -> Vec<(u64, Option<PrefetchPlan>)> {
+        lbas.iter()
+            .filter_map(|&lba| Some((lba, kernel.push_lba(lba)?)))
+            .filter(|(_, d)| d.fire)
+            .map(|(lba, d)| (lba, d.target))
+            .collect()
+    }
+
+    #[test]
+    fn test_descending_triggers_like_ascending_with_extents_below() {
+        for stride in [1u64, 2, 16, 64] {
+            let up: Vec<u64> = (0..5_000).map(|i| TOP + i * stride).collect();
+            let down: Vec<u64> = (0..5_000).map(|i| TOP - i * stride).collect();
+            for horizon in [None, Some(8)] {
+                let make = || {
+                    let mut kernel = AetherLinkKernel::default();
+                    if let Some(n) = horizon {
+                        kernel.set_horizon(n);
+                    }
+                    kernel
+                };
+                let (mut ascending, mut descending) = (make(), make());
+                let fired_up = run(&mut ascending, &up);
+                let fired_down = run(&mut descending, &down);
+                let (a, d) = (fired_up.len() as f64, fired_down.len() as f64);
+                assert!(a > 100.0, "stride {stride}, {horizon:?}: {a}");
+                assert!(
+                    (d - a).abs() <= 0.05 * a,
+                    "stride {stride}, {horizon:?}: {a} vs {d}"
+                );
+                assert_eq!(descending.scan_direction(), ScanDirection::Backward);
+                for (lba, target) in fired_down {
+                    let plan = target.unwrap();
+                    assert!(plan.end() <= lba && !plan.is_empty(), "{lba}: {plan:?}");
+                    if horizon.is_none() {
+                        // Sized by the run's stride, not the window span.
+                        assert!(plan.len <= stride + LOOKAHEAD_BLOCKS, "{lba}: {plan:?}");
+                    }
+                }
+                if horizon.is_some() {
+                    let (up, down) = (ascending.horizon_stats(), descending.horizon_stats());
+                    assert!(down.coverage() > 0.9 * up.coverage(), "{up:?} vs {down:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_descent_to_zero_never_underflows() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.set_horizon(32);
+        let lbas: Vec<u64> = (0..=3_000u64).rev().collect();
+        for (lba, target) in run(&mut kernel, &lbas) {
+            let plan = target.unwrap();
+            assert!(plan.end() <= lba, "{lba}: {plan:?}");
+        }
+        assert!(kernel.push_lba(0).map_or(true, |d| !d.fire));
+        assert!(kernel.stats().out_of_range > 0);
+
+        assert_eq!(below(10, 4), PrefetchPlan::new(6, 4));
+        assert_eq!(below(3, 8), PrefetchPlan::new(0, 3));
+        assert!(below(0, 8).is_empty());
+    }
+
+    #[test]
+    fn test_slice_decisions_target_the_run_stride_ahead() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        let up = kernel.process_io_cycle_decision(&[TOP, TOP + 2, TOP + 4, TOP + 8]);
+        assert_eq!(up.target, Some(PrefetchPlan::new(TOP + 9, 12)));
+        let down = kernel.process_io_cycle_decision(&[TOP + 8, TOP + 4, TOP]);
+        assert_eq!(down.target, Some(PrefetchPlan::new(TOP - 12, 12)));
+        assert!(up.fire && down.fire);
+
+        // Trimmed to the range, and nothing past its ends.
+        kernel.set_address_range(0, TOP + 11);
+        let trimmed = kernel.process_io_cycle_decision(&[TOP, TOP + 4, TOP + 8]);
+        assert_eq!(trimmed.target, Some(PrefetchPlan::new(TOP + 9, 3)));
+        kernel.clear_address_range();
+        let top = kernel.process_io_cycle_decision(&[u64::MAX - 8, u64::MAX - 4, u64::MAX]);
+        let bottom = kernel.process_io_cycle_decision(&[8, 4, 0]);
+        assert!(!top.fire && !bottom.fire);
+        assert_eq!(bottom.target, None);
+        assert!(kernel.stats().out_of_range >= 2);
+        assert!(bottom.confidence > 0.0, "the cycle still ran");
+
+        // A jump ends the run: the fallback extent, not the jump's length.
+        let jump = kernel.process_io_cycle_decision(&[TOP, TOP + 1, TOP + 2, TOP + 3_000]);
+        assert_eq!(
+            jump.target,
+            Some(PrefetchPlan::new(TOP + 3_001, LOOKAHEAD_BLOCKS))
+        );
+
+        let short = kernel.process_io_cycle_decision(&[TOP]);
+        assert_eq!((short.fire, short.target), (false, None));
+
+        // A horizon plan takes precedence.
+        kernel.set_horizon(16);
+        for i in 0..64 {
+            let d = kernel.process_io_cycle_decision(&[TOP + i, TOP + i + 1]);
+            if d.fire {
+                assert_eq!(d.target, kernel.horizon_plan());
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_stride() {
+        let run = |lbas: &[u64]| run_stride(lbas.iter().rev().copied());
+        assert_eq!(run(&[5, 10, 20, 30, 40]), Some((10, false)));
+        assert_eq!(run(&[90, 80, 70, 62]), Some((8, true)));
+        // Only the newest steps count.
+        assert_eq!(run(&[30, 20, 24, 28]), Some((4, false)));
+        // Reversals and steps out of proportion are no run.
+        assert_eq!(run(&[50, 40, 44]), None);
+        assert_eq!(run(&[0, 1, 2, 3, 900, 901]), None);
+        assert_eq!(run(&[0, 1, 2, 3, 900]), None);
+        assert_eq!(run(&[4, 4, 4]), None);
+        assert_eq!(run(&[3, 4]), None);
+    }
+
+    #[test]
+    fn test_descending_run_classifies_as_sequential() {
+        let mut kernel = AetherLinkKernel::default();
+        for lba in (0..500u64).rev() {
+            kernel.push_lba(TOP + lba);
+        }
+        let c = kernel.classification(&Default::default());
+        assert_eq!(c.class, WorkloadClass::Sequential);
+        assert_eq!((c.features.sequentiality, c.features.stride), (1.0, -1));
+
+        // Alternating ±1 steps are not a run either way.
+        let mut kernel = AetherLinkKernel::default();
+        for i in 0..500u64 {
+            kernel.push_lba(TOP + i % 2);
+        }
+        assert!(
+            kernel
+                .classification(&Default::default())
+                .features
+                .sequentiality
+                < 0.6
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Exponential-weights voting over the built-in presets.
+//!
+//! Instead of picking one of the built-in [`Preset`]s up front,
+//! [`PresetEnsemble`] runs one kernel per preset on every access and fires
+//! when the weighted share of firing votes exceeds one half.  Each reported
+//! outcome scores every preset's own vote with a [`Reward`] and multiplies
+//! its weight by `exp(η·gain)` (Hedge), so weight flows to whichever preset
+//! has been paying off.  Optionally the ensemble hard-switches to a preset
+//! once its weight passes a threshold and from then on runs that kernel
+//! alone.
+//!
+//! All state is fixed-size; the decision path does not allocate.
+
+use crate::prefetcher::{PrefetchDecision, Prefetcher};
+use crate::threshold::Reward;
+use crate::{AetherLinkKernel, Preset};
+
+/// Number of presets the ensemble runs.
+const N: usize = Preset::ALL.len();
+
+/// Knobs for [`PresetEnsemble`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnsembleOptions {
+    /// Hedge learning rate η.
+    pub learning_rate: f32,
+    /// Floor every weight is kept above after an update, so a preset that
+    /// fell behind can still win back weight when the workload changes.
+    pub min_weight: f32,
+    /// Hard-switch to the leading preset once its weight reaches this
+    /// value.  `None` keeps voting forever.
+    pub switch_at: Option<f32>,
+    /// Value of each vote's outcome.  [`Reward::per_byte`] is not used: the
+    /// ensemble does not see prefetch sizes.
+    pub reward: Reward,
+}
+
+impl Default for EnsembleOptions {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.1,
+            min_weight: 1e-3,
+            switch_at: None,
+            reward: Reward::default(),
+        }
+    }
+}
+
+/// One kernel per [`Preset`], combined by exponential weights.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::ensemble::{EnsembleOptions, PresetEnsemble};
+///
+/// let mut ensemble = PresetEnsemble::new(EnsembleOptions::default());
+/// let mut prev: Option<u64> = None;
+/// for lba in [10, 11, 12, 13, 14, 15] {
+///     if let Some(prev) = prev {
+///         // Would a prefetch after `prev` have covered `lba`?
+///         ensemble.record_outcome(lba > prev && lba <= prev + 8);
+///     }
+///     ensemble.push_lba(lba);
+///     prev = Some(lba);
+/// }
+/// let (leader, weight) = ensemble.leader();
+/// println!("{leader}: {weight:.2}");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PresetEnsemble {
+    kernels: [AetherLinkKernel; N],
+    weights: [f32; N],
+    opts: EnsembleOptions,
+    /// Each preset's vote on the last cycle, until its outcome arrives.
+    votes: Option<[bool; N]>,
+    switched: Option<usize>,
+    /// Ensemble cycles executed.
+    pub cycles: u64,
+    /// Ensemble prefetches triggered.
+    pub prefetches: u64,
+}
+
+impl PresetEnsemble {
+    /// Fresh kernels for every preset, equally weighted.
+    pub fn new(opts: EnsembleOptions) -> Self {
+        Self {
+            kernels: Preset::ALL.map(Preset::kernel),
+            weights: [1.0 / N as f32; N],
+            opts,
+            votes: None,
+            switched: None,
+            cycles: 0,
+            prefetches: 0,
+        }
+    }
+
+    /// Feed one LBA to every active kernel and return the combined vote.
+    ///
+    /// Returns `None` during the kernels' warm-up (see
+    /// [`AetherLinkKernel::push_lba`]).
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<bool> {
+        if let Some(i) = self.switched {
+            let fire = self.kernels[i].push_lba(lba)?.fire;
+            return Some(self.single(i, fire));
+        }
+        // Every kernel sees the LBA, even while warming up.
+        let mut decisions = [None; N];
+        for (kernel, decision) in self.kernels.iter_mut().zip(&mut decisions) {
+            *decision = kernel.push_lba(lba);
+        }
+        let mut votes = [false; N];
+        for (vote, decision) in votes.iter_mut().zip(decisions) {
+            *vote = decision?.fire;
+        }
+        Some(self.combine(votes))
+    }
+
+    /// Run one cycle over a window of LBAs on every active kernel.
+    #[inline]
+    pub fn process_io_cycle(&mut self, lba_stream: &[u64]) -> bool {
+        if let Some(i) = self.switched {
+            let fire = self.kernels[i].process_io_cycle(lba_stream);
+            return self.single(i, fire);
+        }
+        let mut votes = [false; N];
+        for (kernel, vote) in self.kernels.iter_mut().zip(&mut votes) {
+            *vote = kernel.process_io_cycle(lba_stream);
+        }
+        self.combine(votes)
+    }
+
+    #[inline(always)]
+    fn combine(&mut self, votes: [bool; N]) -> bool {
+        let share: f32 = self
+            .weights
+            .iter()
+            .zip(votes)
+            .filter(|(_, vote)| *vote)
+            .map(|(w, _)| w)
+            .sum();
+        self.votes = Some(votes);
+        self.count(share > 0.5)
+    }
+
+    /// Decision of the hard-switched kernel `i`.
+    #[inline(always)]
+    fn single(&mut self, i: usize, fire: bool) -> bool {
+        let mut votes = [false; N];
+        votes[i] = fire;
+        self.votes = Some(votes);
+        self.count(fire)
+    }
+
+    #[inline(always)]
+    fn count(&mut self, fire: bool) -> bool {
+        self.cycles += 1;
+        if fire {
+            self.prefetches += 1;
+        }
+        fire
+    }
+
+    /// Report whether prefetching on the last cycle would have paid off.
+    ///
+    /// Every preset's own vote is scored (a used prefetch earns
+    /// [`Reward::hit`], a wasted one costs [`Reward::waste`], declining a
+    /// useful one costs [`Reward::missed`]) and passed on to its kernel as
+    /// [`AetherLinkKernel::record_prefetch_outcome`] or
+    /// [`AetherLinkKernel::record_missed_opportunity`].  After a hard
+    /// switch only the chosen kernel hears about it.  Only the first report
+    /// after a cycle counts.
+    pub fn record_outcome(&mut self, useful: bool) {
+        let Some(votes) = self.votes.take() else {
+            return;
+        };
+        if let Some(i) = self.switched {
+            self.kernels[i].record_vote_outcome(votes[i], useful);
+            return;
+        }
+        let reward = self.opts.reward;
+        let mut gains = [0.0f32; N];
+        for ((kernel, gain), vote) in self.kernels.iter_mut().zip(&mut gains).zip(votes) {
+            kernel.record_vote_outcome(vote, useful);
+            *gain = match (vote, useful) {
+                (true, true) => reward.hit,
+                (true, false) => -reward.waste,
+                (false, true) => -reward.missed,
+                (false, false) => 0.0,
+            };
+        }
+        self.hedge(gains);
+
+        if let Some(at) = self.opts.switch_at {
+            let (leader, weight) = self.leader_index();
+            if weight >= at {
+                self.switched = Some(leader);
+            }
+        }
+    }
+
+    /// Multiply each weight by `exp(η·gain)` and renormalise.  Gains are
+    /// shifted by their maximum first, which leaves the normalised result
+    /// unchanged and keeps every factor in (0, 1].
+    fn hedge(&mut self, gains: [f32; N]) {
+        let best = gains.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        for (w, g) in self.weights.iter_mut().zip(gains) {
+            *w *= libm::expf(self.opts.learning_rate * (g - best));
+        }
+        self.normalize();
+        for w in &mut self.weights {
+            *w = w.max(self.opts.min_weight);
+        }
+        self.normalize();
+    }
+
+    #[inline]
+    fn normalize(&mut self) {
+        let total: f32 = self.weights.iter().sum();
+        for w in &mut self.weights {
+            *w /= total;
+        }
+    }
+
+    /// Current weights, in [`Preset::ALL`] order; they sum to 1.
+    #[inline]
+    pub fn weights(&self) -> [f32; N] {
+        self.weights
+    }
+
+    /// Weight of one preset.
+    #[inline]
+    pub fn weight(&self, preset: Preset) -> f32 {
+        self.weights[index(preset)]
+    }
+
+    /// The preset with the largest weight, and that weight.
+    pub fn leader(&self) -> (Preset, f32) {
+        let (i, w) = self.leader_index();
+        (Preset::ALL[i], w)
+    }
+
+    fn leader_index(&self) -> (usize, f32) {
+        self.weights
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |best, (i, w)| {
+                if w > best.1 {
+                    (i, w)
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// The preset the ensemble hard-switched to, if it has.
+    #[inline]
+    pub fn switched(&self) -> Option<Preset> {
+        self.switched.map(|i| Preset::ALL[i])
+    }
+
+    /// Go back to voting after a hard switch.  The other kernels resume
+    /// with the state they had at the switch; weights are kept.
+    pub fn unswitch(&mut self) {
+        self.switched = None;
+        self.votes = None;
+    }
+
+    /// The kernel running `preset`.
+    #[inline]
+    pub fn kernel(&self, preset: Preset) -> &AetherLinkKernel {
+        &self.kernels[index(preset)]
+    }
+}
+
+impl Default for PresetEnsemble {
+    fn default() -> Self {
+        Self::new(EnsembleOptions::default())
+    }
+}
+
+#[inline]
+fn index(preset: Preset) -> usize {
+    match preset {
+        Preset::Default => 0,
+        Preset::Hft => 1,
+        Preset::Gaming => 2,
+        Preset::Database => 3,
+        Preset::MlTraining => 4,
+        Preset::VideoStreaming => 5,
+        Preset::Wsl2 => 6,
+    }
+}
+
+impl Prefetcher for PresetEnsemble {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        match self.push_lba(lba) {
+            Some(true) => PrefetchDecision::trigger(None),
+            _ => PrefetchDecision::SKIP,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "aether-ensemble"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    /// Run `ensemble` over `pattern`, reporting "the next access follows
+    /// within 8 blocks" as the outcome of every cycle.
+    fn run(ensemble: &mut PresetEnsemble, pattern: Pattern, events: usize) {
+        let lbas: Vec<u64> = pattern.workload(7).take(events).collect();
+        for w in lbas.windows(2) {
+            ensemble.push_lba(w[0]);
+            ensemble.record_outcome(w[1] > w[0] && w[1] <= w[0] + 8);
+        }
+    }
+
+    #[test]
+    fn test_index_matches_all() {
+        for (i, preset) in Preset::ALL.into_iter().enumerate() {
+            assert_eq!(index(preset), i);
+        }
+    }
+
+    #[test]
+    fn test_gaming_dominates_sequential() {
+        let mut ensemble = PresetEnsemble::default();
+        run(&mut ensemble, Pattern::SEQUENTIAL, 5_000);
+        assert_eq!(
+            ensemble.leader().0,
+            Preset::Gaming,
+            "{:?}",
+            ensemble.weights()
+        );
+        assert!((ensemble.weights().iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(ensemble.weights().iter().all(|&w| w >= 1e-3 - 1e-6));
+    }
+
+    #[test]
+    fn test_frugal_presets_dominate_when_waste_is_costly() {
+        // On the HFT tick trace the HFT preset's conservative threshold
+        // keeps it from firing at all; it only overtakes the eager presets
+        // once a wasted prefetch costs more than the ~20 hits they land.
+        // The database preset, which fires only on real runs, leads either
+        // way.
+        let mut ensemble = PresetEnsemble::new(EnsembleOptions {
+            reward: Reward {
+                waste: 32.0,
+                ..Reward::bandwidth_frugal()
+            },
+            ..EnsembleOptions::default()
+        });
+        run(&mut ensemble, Pattern::HFT_TICK, 5_000);
+        assert_eq!(
+            ensemble.leader().0,
+            Preset::Database,
+            "{:?}",
+            ensemble.weights()
+        );
+        assert!(ensemble.weight(Preset::Hft) >= ensemble.weight(Preset::Gaming));
+
+        let mut symmetric = PresetEnsemble::default();
+        run(&mut symmetric, Pattern::HFT_TICK, 5_000);
+        assert_eq!(symmetric.leader().0, Preset::Database);
+    }
+
+    #[test]
+    fn test_hard_switch_runs_one_kernel() {
+        let mut ensemble = PresetEnsemble::new(EnsembleOptions {
+            learning_rate: 1.0,
+            switch_at: Some(0.9),
+            ..EnsembleOptions::default()
+        });
+        run(&mut ensemble, Pattern::SEQUENTIAL, 2_000);
+        assert_eq!(ensemble.switched(), Some(Preset::Gaming));
+        let cycles = ensemble.kernel(Preset::Hft).cycles;
+        ensemble.push_lba(5_000);
+        assert_eq!(ensemble.kernel(Preset::Hft).cycles, cycles);
+        ensemble.unswitch();
+        ensemble.push_lba(5_001);
+        assert_eq!(ensemble.kernel(Preset::Hft).cycles, cycles + 1);
+    }
+}
@@ -0,0 +1,903 @@
+//! End-to-end prefetch evaluation.
+//!
+//! [`run`] replays a trace through a [`Prefetcher`] and a [`CacheSim`] and
+//! reduces the cache counters to the standard prefetching metrics:
+//!
+//! | Metric | Definition |
+//! |--------|------------|
+//! | coverage | prefetch hits / (prefetch hits + demand misses) — the share of would-be misses a prefetch removed |
+//! | accuracy | prefetch hits / blocks prefetched — the share of prefetches that were used |
+//! | pollution rate | prefetched blocks evicted unused / blocks prefetched |
+//! | timeliness | accesses between a prefetch and its first use, see [`Timeliness`] |
+//!
+//! [`compare`] runs several predictors on identical copies of a trace and
+//! cache and ranks them side by side, and [`compare_gap_policies`] one
+//! kernel under each [`GapPolicy`]; [`run_phases`] breaks a run over a
+//! [`Phased`] workload down by phase to show recovery after each
+//! transition.
+//!
+//! With the `parallel` feature, [`compare_par`] runs the predictors on the
+//! rayon thread pool.  Parallelism is across predictors only: each one
+//! still replays the whole trace in order through its own cache, so the
+//! report is identical to [`compare`]'s.  Splitting one replay into trace
+//! segments would change the cache state each segment starts from, and is
+//! deliberately not done.
+
+use crate::gap::GapPolicy;
+use crate::prefetcher::{Named, PrefetchPlan, Prefetcher};
+use crate::replay::{self, ReplayOptions};
+use crate::sim::{CacheSim, CacheStats, Timeliness, TIMELINESS_BUCKETS};
+use crate::trace::IoEvent;
+use crate::workload::Phased;
+use crate::AetherLinkKernel;
+use core::fmt;
+use core::str::FromStr;
+
+/// Outcome of one evaluation run.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalReport {
+    /// [`Prefetcher::name`] of the evaluated predictor.
+    pub prefetcher: String,
+    /// Trace events replayed.
+    pub events: u64,
+    /// Events on which the predictor fired.
+    pub triggers: u64,
+    /// Raw cache counters.
+    pub cache: CacheStats,
+    /// Demand hit rate in [0, 1].
+    pub hit_rate: f64,
+    /// Fraction of would-be misses served by a prefetch.
+    pub coverage: f64,
+    /// Fraction of prefetched blocks that were used.
+    pub accuracy: f64,
+    /// Fraction of prefetched blocks evicted unused.
+    pub pollution_rate: f64,
+}
+
+impl EvalReport {
+    /// Derive the metrics from `cache` counters.
+    pub fn new(prefetcher: &str, events: u64, triggers: u64, cache: CacheStats) -> Self {
+        let ratio = |num: u64, den: u64| {
+            if den == 0 {
+                0.0
+            } else {
+                num as f64 / den as f64
+            }
+        };
+        Self {
+            prefetcher: prefetcher.to_owned(),
+            events,
+            triggers,
+            cache,
+            hit_rate: cache.hit_rate(),
+            coverage: ratio(cache.prefetch_hits, cache.prefetch_hits + cache.misses),
+            accuracy: ratio(cache.prefetch_hits, cache.prefetched),
+            pollution_rate: ratio(cache.pollution, cache.prefetched),
+        }
+    }
+
+    /// Timeliness histogram of the run.
+    #[inline]
+    pub fn timeliness(&self) -> &Timeliness {
+        &self.cache.timeliness
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pct = |x: f64| format!("{:.1}%", x * 100.0);
+        let c = &self.cache;
+        writeln!(f, "{:<12} {:>12}", "prefetcher", self.prefetcher)?;
+        writeln!(f, "{:<12} {:>12}", "events", self.events)?;
+        writeln!(f, "{:<12} {:>12}", "triggers", self.triggers)?;
+        writeln!(f, "{:<12} {:>12}", "hits", c.hits)?;
+        writeln!(f, "{:<12} {:>12}", "misses", c.misses)?;
+        writeln!(f, "{:<12} {:>12}", "prefetched", c.prefetched)?;
+        writeln!(f, "{:<12} {:>12}", "hit rate", pct(self.hit_rate))?;
+        writeln!(f, "{:<12} {:>12}", "coverage", pct(self.coverage))?;
+        writeln!(f, "{:<12} {:>12}", "accuracy", pct(self.accuracy))?;
+        writeln!(f, "{:<12} {:>12}", "pollution", pct(self.pollution_rate))?;
+        write!(f, "{:<12}", "timeliness")?;
+        for i in 0..TIMELINESS_BUCKETS {
+            let n = c.timeliness.buckets[i];
+            write!(f, " {}:{n}", Timeliness::label(i))?;
+        }
+        Ok(())
+    }
+}
+
+/// Metric a [`ComparisonReport`] is ranked by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Metric {
+    /// [`EvalReport::hit_rate`].
+    HitRate,
+    /// [`EvalReport::coverage`].
+    #[default]
+    Coverage,
+    /// [`EvalReport::accuracy`].
+    Accuracy,
+    /// [`EvalReport::pollution_rate`]; lower ranks higher.
+    Pollution,
+}
+
+impl Metric {
+    /// Every metric, in display order.
+    pub const ALL: [Metric; 4] = [
+        Metric::HitRate,
+        Metric::Coverage,
+        Metric::Accuracy,
+        Metric::Pollution,
+    ];
+
+    /// Lower-case name used in reports and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::HitRate => "hit-rate",
+            Metric::Coverage => "coverage",
+            Metric::Accuracy => "accuracy",
+            Metric::Pollution => "pollution",
+        }
+    }
+
+    /// The metric's value in `report`.
+    pub fn of(self, report: &EvalReport) -> f64 {
+        match self {
+            Metric::HitRate => report.hit_rate,
+            Metric::Coverage => report.coverage,
+            Metric::Accuracy => report.accuracy,
+            Metric::Pollution => report.pollution_rate,
+        }
+    }
+
+    /// `false` for metrics where smaller is better.
+    #[inline]
+    pub fn higher_is_better(self) -> bool {
+        self != Metric::Pollution
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|m| m.name()).collect();
+                format!(
+                    "unknown metric '{s}' (expected one of: {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Difference in one metric between two ranked predictors.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Delta {
+    /// Predictor the delta is reported for.
+    pub prefetcher: String,
+    /// Predictor it is compared against.
+    pub baseline: String,
+    /// Compared metric.
+    pub metric: Metric,
+    /// `metric(prefetcher) − metric(baseline)`, in [-1, 1].
+    pub value: f64,
+}
+
+impl fmt::Display for Delta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:+.1}% {} vs {}",
+            self.prefetcher,
+            self.value * 100.0,
+            self.metric,
+            self.baseline
+        )
+    }
+}
+
+/// Side-by-side [`EvalReport`]s, best first.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComparisonReport {
+    /// Metric the reports are ranked by.
+    pub metric: Metric,
+    /// Cache capacity every predictor ran with, in blocks.
+    pub cache_blocks: usize,
+    /// One report per predictor, best first; ties keep input order.
+    pub reports: Vec<EvalReport>,
+}
+
+impl ComparisonReport {
+    /// Re-rank by `metric`.
+    pub fn ranked_by(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        let key = |r: &EvalReport| {
+            let v = metric.of(r);
+            if metric.higher_is_better() {
+                -v
+            } else {
+                v
+            }
+        };
+        self.reports.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        self
+    }
+
+    /// The best-ranked report, if any predictor ran.
+    pub fn winner(&self) -> Option<&EvalReport> {
+        self.reports.first()
+    }
+
+    /// `metric` difference between the predictors at ranks `a` and `b`.
+    ///
+    /// # Panics
+    ///
+    /// If either rank is out of range.
+    pub fn delta(&self, a: usize, b: usize, metric: Metric) -> Delta {
+        let (ra, rb) = (&self.reports[a], &self.reports[b]);
+        Delta {
+            prefetcher: ra.prefetcher.clone(),
+            baseline: rb.prefetcher.clone(),
+            metric,
+            value: metric.of(ra) - metric.of(rb),
+        }
+    }
+
+    /// Ranking-metric deltas for every pair, higher rank first:
+    /// `(0, 1), (0, 2), …, (1, 2), …`.
+    pub fn deltas(&self) -> Vec<Delta> {
+        let n = self.reports.len();
+        (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .map(|(a, b)| self.delta(a, b, self.metric))
+            .collect()
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pct = |x: f64| format!("{:.1}%", x * 100.0);
+        writeln!(
+            f,
+            "{:<4} {:<16} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "rank", "prefetcher", "triggers", "hit rate", "coverage", "accuracy", "pollution"
+        )?;
+        for (i, r) in self.reports.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<4} {:<16} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                i + 1,
+                r.prefetcher,
+                r.triggers,
+                pct(r.hit_rate),
+                pct(r.coverage),
+                pct(r.accuracy),
+                pct(r.pollution_rate)
+            )?;
+        }
+        write!(
+            f,
+            "ranked by {} with a {}-block cache",
+            self.metric, self.cache_blocks
+        )?;
+        for i in 1..self.reports.len() {
+            write!(f, "\n{}", self.delta(0, i, self.metric))?;
+        }
+        Ok(())
+    }
+}
+
+/// Run each of `prefetchers` over `trace` with its own fresh LRU cache of
+/// `cache_blocks` blocks and rank the results by [`Metric::default`]
+/// (coverage); use [`ComparisonReport::ranked_by`] for another metric.
+pub fn compare(
+    trace: &[IoEvent],
+    prefetchers: Vec<Box<dyn Prefetcher>>,
+    cache_blocks: usize,
+) -> ComparisonReport {
+    let reports = prefetchers
+        .into_iter()
+        .map(|mut p| {
+            let mut cache = CacheSim::new(cache_blocks);
+            run(p.as_mut(), &mut cache, trace.iter().copied())
+        })
+        .collect();
+    ranked(reports, cache_blocks)
+}
+
+/// [`compare`] with one predictor per rayon task.  The report is identical
+/// to a serial [`compare`] over the same predictors.
+#[cfg(feature = "parallel")]
+pub fn compare_par(
+    trace: &[IoEvent],
+    prefetchers: Vec<Box<dyn Prefetcher + Send>>,
+    cache_blocks: usize,
+) -> ComparisonReport {
+    use rayon::prelude::*;
+    let reports = prefetchers
+        .into_par_iter()
+        .map(|mut p| {
+            let mut cache = CacheSim::new(cache_blocks);
+            run(p.as_mut(), &mut cache, trace.iter().copied())
+        })
+        .collect();
+    ranked(reports, cache_blocks)
+}
+
+/// Run a copy of `kernel` per [`GapPolicy`] over `trace`, each with its own
+/// fresh LRU cache of `cache_blocks` blocks, and rank them like
+/// [`compare`].  Reports are named after the policy (`"exact"`,
+/// `"fill-8"`, `"contiguous"`); their accuracy and pollution rate show
+/// what filling the gaps costs, their coverage what it buys.
+pub fn compare_gap_policies(
+    trace: &[IoEvent],
+    kernel: &AetherLinkKernel,
+    policies: &[GapPolicy],
+    cache_blocks: usize,
+) -> ComparisonReport {
+    let prefetchers = policies
+        .iter()
+        .map(|&policy| {
+            let mut kernel = kernel.clone();
+            kernel.set_gap_policy(policy);
+            Box::new(Named::new(policy.to_string(), kernel)) as Box<dyn Prefetcher>
+        })
+        .collect();
+    compare(trace, prefetchers, cache_blocks)
+}
+
+fn ranked(reports: Vec<EvalReport>, cache_blocks: usize) -> ComparisonReport {
+    ComparisonReport {
+        metric: Metric::default(),
+        cache_blocks,
+        reports,
+    }
+    .ranked_by(Metric::default())
+}
+
+/// Replay `events` through `prefetcher` and `cache` and report the
+/// prefetching metrics.
+///
+/// Firing decisions without a target prefetch the default
+/// [`ReplayOptions::prefetch_blocks`] after the request (a bare kernel
+/// always targets its own); see [`run_with`].
+/// Pollution is reported back through [`Prefetcher::record_pollution`], so
+/// a kernel with a [`Reward::pollution`](crate::Reward::pollution) penalty
+/// adapts during the run.
+/// The report covers everything `cache` has counted, so pass a fresh (or
+/// [`clear`](CacheSim::clear)ed) cache.
+pub fn run<P, I>(prefetcher: &mut P, cache: &mut CacheSim, events: I) -> EvalReport
+where
+    P: Prefetcher + ?Sized,
+    I: Iterator<Item = IoEvent>,
+{
+    run_with(prefetcher, cache, events, &ReplayOptions::default())
+}
+
+/// [`run`] with explicit replay options.
+pub fn run_with<P, I>(
+    prefetcher: &mut P,
+    cache: &mut CacheSim,
+    events: I,
+    opts: &ReplayOptions,
+) -> EvalReport
+where
+    P: Prefetcher + ?Sized,
+    I: Iterator<Item = IoEvent>,
+{
+    let summary = replay::replay(prefetcher, events, opts, Some(&mut *cache), |_, _| {});
+    EvalReport::new(
+        prefetcher.name(),
+        summary.events,
+        summary.triggers,
+        cache.stats(),
+    )
+}
+
+/// [`run_with`] for a kernel that is told how its decisions worked out.
+///
+/// After every demand access the cache counters are diffed: each prefetched
+/// block that was used or evicted unused is reported through
+/// [`AetherLinkKernel::record_prefetch_outcome`], and a demand miss right
+/// after a cycle that did not prefetch through
+/// [`AetherLinkKernel::record_missed_opportunity`].  Every issued plan is
+/// read as its [gap policy](crate::gap) says and reported through
+/// [`AetherLinkKernel::record_prefetch_issued`] in bytes of
+/// [`ReplayOptions::block_size`], and the blocks each event left evicted
+/// unused through [`AetherLinkKernel::record_pollution`].  Only kernels using
+/// [`ThresholdPolicy::Feedback`](crate::ThresholdPolicy::Feedback) react.
+pub fn run_with_feedback<I>(
+    kernel: &mut AetherLinkKernel,
+    cache: &mut CacheSim,
+    events: I,
+    opts: &ReplayOptions,
+) -> EvalReport
+where
+    I: Iterator<Item = IoEvent>,
+{
+    let mut events_seen = 0u64;
+    let mut triggers = 0u64;
+    let mut fired = true;
+    for ev in events {
+        events_seen += 1;
+        let polluted = cache.stats().pollution;
+        for lba in ev.lba..ev.end() {
+            let before = cache.stats();
+            cache.access(lba);
+            let after = cache.stats();
+            for _ in before.prefetch_hits..after.prefetch_hits {
+                kernel.record_prefetch_outcome(true);
+            }
+            for _ in before.pollution..after.pollution {
+                kernel.record_prefetch_outcome(false);
+            }
+            if after.misses > before.misses && !fired {
+                kernel.record_missed_opportunity();
+            }
+        }
+
+        let decision = kernel.observe(ev.lba);
+        fired = decision.fire;
+        if fired {
+            triggers += 1;
+            let plan = decision
+                .target
+                .unwrap_or_else(|| PrefetchPlan::new(ev.end(), u64::from(opts.prefetch_blocks)));
+            let mut issued = 0u64;
+            kernel.extents(plan, &mut |extent| {
+                issued += extent.len;
+                for lba in extent.start..extent.end() {
+                    cache.prefetch(lba);
+                }
+            });
+            kernel.record_prefetch_issued(issued.saturating_mul(opts.block_size));
+        }
+        let count = cache.stats().pollution - polluted;
+        if count > 0 {
+            kernel.record_pollution(count);
+        }
+    }
+    EvalReport::new(kernel.name(), events_seen, triggers, cache.stats())
+}
+
+/// Metrics for one phase of a [`Phased`] workload.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseReport {
+    /// Name of the phase's pattern.
+    pub pattern: String,
+    /// Index of the phase's first event in the trace.
+    pub start: u64,
+    /// The first `settle` events after the transition.
+    pub settle: EvalReport,
+    /// The whole phase, settle window included.
+    pub report: EvalReport,
+}
+
+/// Replay `workload` through `prefetcher` and `cache` and report every
+/// phase separately.
+///
+/// Each phase also gets a report over its first `settle` events, which
+/// compared with the whole phase shows how quickly the predictor recovers
+/// after a change.  Nothing is reset between phases.
+pub fn run_phases<P>(
+    prefetcher: &mut P,
+    cache: &mut CacheSim,
+    workload: &Phased,
+    settle: u64,
+) -> Vec<PhaseReport>
+where
+    P: Prefetcher + ?Sized,
+{
+    let opts = ReplayOptions::default();
+    let mut events = workload.iter().map(IoEvent::read);
+    let mut reports = Vec::with_capacity(workload.segments().len());
+    for (segment, start) in workload.segments().iter().zip(workload.boundaries()) {
+        let head_len = settle.min(segment.len);
+        let before = cache.stats();
+        let head = replay::replay(
+            &mut *prefetcher,
+            events.by_ref().take(head_len as usize),
+            &opts,
+            Some(&mut *cache),
+            |_, _| {},
+        );
+        let mid = cache.stats();
+        let tail = replay::replay(
+            &mut *prefetcher,
+            events.by_ref().take((segment.len - head_len) as usize),
+            &opts,
+            Some(&mut *cache),
+            |_, _| {},
+        );
+        let name = prefetcher.name();
+        reports.push(PhaseReport {
+            pattern: segment.pattern.name().to_owned(),
+            start,
+            settle: EvalReport::new(name, head.events, head.triggers, mid.since(&before)),
+            report: EvalReport::new(
+                name,
+                head.events + tail.events,
+                head.triggers + tail.triggers,
+                cache.stats().since(&before),
+            ),
+        });
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::{GhbPrefetcher, NextNPrefetcher, StridePrefetcher};
+    use crate::prefetcher::Named;
+
+    #[test]
+    fn test_sequential_next_one_by_hand() {
+        // Access 0 misses and prefetches 1; every later access hits the
+        // block prefetched one access earlier.  Block 10 is left unused but
+        // still resident.
+        let mut p = NextNPrefetcher::new(1);
+        let mut cache = CacheSim::new(64);
+        let report = run(&mut p, &mut cache, (0..10).map(IoEvent::read));
+        assert_eq!(report.prefetcher, "next-n");
+        assert_eq!((report.events, report.triggers), (10, 10));
+        let c = report.cache;
+        assert_eq!((c.hits, c.misses, c.prefetched), (9, 1, 10));
+        assert_eq!((c.prefetch_hits, c.pollution), (9, 0));
+        assert_eq!(report.coverage, 0.9);
+        assert_eq!(report.accuracy, 0.9);
+        assert_eq!(report.pollution_rate, 0.0);
+        assert_eq!(report.timeliness().buckets[0], 9);
+        assert_eq!(report.timeliness().total(), 9);
+    }
+
+    #[test]
+    fn test_random_jumps_only_pollute() {
+        // Two-block cache, next-2 on 0, 100, 200: every prefetched block is
+        // pushed out before use except the last two, which stay resident.
+        //   0   miss → [0]    prefetch 1, 2   → [1, 2]    (0 evicted)
+        //   100 miss → evicts 1 (polluted)    prefetch 101, 102 evicts 2 (p), 100
+        //   200 miss → evicts 101 (p)         prefetch 201, 202 evicts 102 (p), 200
+        let mut p = NextNPrefetcher::new(2);
+        let mut cache = CacheSim::new(2);
+        let dyn_p: &mut dyn Prefetcher = &mut p;
+        let report = run(
+            dyn_p,
+            &mut cache,
+            [0, 100, 200].map(IoEvent::read).into_iter(),
+        );
+        let c = report.cache;
+        assert_eq!((c.hits, c.misses, c.prefetched, c.pollution), (0, 3, 6, 4));
+        assert_eq!((report.coverage, report.accuracy), (0.0, 0.0));
+        assert!((report.pollution_rate - 4.0 / 6.0).abs() < 1e-12);
+        assert_eq!(report.timeliness().total(), 0);
+
+        let text = report.to_string();
+        assert!(
+            text.contains("pollution") && text.contains("66.7%"),
+            "{text}"
+        );
+        assert!(text.contains("128+:0"), "{text}");
+    }
+
+    /// Sequential runs with periodic jumps and a stretch of random reads.
+    fn mixed_trace() -> Vec<IoEvent> {
+        let mut rng = crate::rng::SplitMix64::new(3);
+        (0..600u64)
+            .map(|i| match i / 200 {
+                0 => IoEvent::read(i),
+                1 => IoEvent::read(rng.below(100_000)),
+                _ => IoEvent::read(50_000 + (i % 16) * 4 + (i / 16) * 1000),
+            })
+            .collect()
+    }
+
+    fn lineup() -> Vec<Box<dyn Prefetcher>> {
+        vec![
+            Box::new(StridePrefetcher::default()),
+            Box::new(Named::new("next-4", NextNPrefetcher::new(4))),
+            Box::new(GhbPrefetcher::default()),
+        ]
+    }
+
+    #[test]
+    fn test_compare_ranks_and_deltas() {
+        let trace = mixed_trace();
+        let report = compare(&trace, lineup(), 256);
+        assert_eq!(report.reports.len(), 3);
+        assert_eq!(
+            report,
+            compare(&trace, lineup(), 256),
+            "ranking must be stable"
+        );
+        let coverage: Vec<f64> = report.reports.iter().map(|r| r.coverage).collect();
+        assert!(coverage.windows(2).all(|w| w[0] >= w[1]), "{coverage:?}");
+
+        // Each entry matches a standalone run on a fresh cache.
+        for r in &report.reports {
+            let mut p = lineup()
+                .into_iter()
+                .find(|p| p.name() == r.prefetcher)
+                .unwrap();
+            let mut cache = CacheSim::new(256);
+            assert_eq!(&run(p.as_mut(), &mut cache, trace.iter().copied()), r);
+        }
+
+        let deltas = report.deltas();
+        assert_eq!(deltas.len(), 3);
+        for d in &deltas {
+            let of = |name: &str| {
+                let r = report
+                    .reports
+                    .iter()
+                    .find(|r| r.prefetcher == name)
+                    .unwrap();
+                r.coverage
+            };
+            assert_eq!(d.value, of(&d.prefetcher) - of(&d.baseline));
+            assert!(d.value >= 0.0);
+        }
+        let text = report.to_string();
+        assert!(text.contains(&deltas[0].to_string()), "{text}");
+
+        // Pollution ranks ascending.
+        let by_pollution = report.ranked_by(Metric::Pollution);
+        let p: Vec<f64> = by_pollution
+            .reports
+            .iter()
+            .map(|r| r.pollution_rate)
+            .collect();
+        assert!(p.windows(2).all(|w| w[0] <= w[1]), "{p:?}");
+        assert_eq!("Hit-Rate".parse::<Metric>(), Ok(Metric::HitRate));
+        assert!("speed".parse::<Metric>().is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_compare_par_matches_serial() {
+        let trace = mixed_trace();
+        let lineup_send = || -> Vec<Box<dyn Prefetcher + Send>> {
+            vec![
+                Box::new(StridePrefetcher::default()),
+                Box::new(Named::new("next-4", NextNPrefetcher::new(4))),
+                Box::new(GhbPrefetcher::default()),
+                Box::new(AetherLinkKernel::new_hft()),
+            ]
+        };
+        let mut serial = lineup_send();
+        let serial = compare(
+            &trace,
+            serial.drain(..).map(|p| p as Box<dyn Prefetcher>).collect(),
+            256,
+        );
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let parallel = pool.install(|| compare_par(&trace, lineup_send(), 256));
+        assert_eq!(format!("{parallel:?}"), format!("{serial:?}"));
+        assert_eq!(parallel.to_string(), serial.to_string());
+    }
+
+    #[test]
+    fn test_run_phases_splits_metrics() {
+        use crate::workload::Pattern;
+
+        let workload = Phased::new(1)
+            .then(Pattern::SEQUENTIAL, 400)
+            .then_at(Pattern::Random { span: 1 << 30 }, 300, 1 << 40)
+            .then_at(Pattern::SEQUENTIAL, 300, 1 << 50);
+        let mut p = NextNPrefetcher::ramp_up(8);
+        let mut cache = CacheSim::new(1024);
+        let phases = run_phases(&mut p, &mut cache, &workload, 50);
+        assert_eq!(phases.len(), 3);
+        assert_eq!(
+            phases.iter().map(|p| p.start).collect::<Vec<_>>(),
+            [0, 400, 700]
+        );
+        assert_eq!(phases[1].pattern, "Random");
+        // Phase counters add up to the whole run.
+        let total: u64 = phases.iter().map(|p| p.report.cache.misses).sum();
+        assert_eq!(total, cache.stats().misses);
+        assert_eq!(phases.iter().map(|p| p.report.events).sum::<u64>(), 1000);
+        // Random reads defeat read-ahead; sequential ones come back after
+        // the ramp-up.
+        assert!(phases[1].report.coverage < 0.01);
+        assert!(phases[2].report.coverage > 0.95);
+        assert!(phases[2].settle.coverage < phases[2].report.coverage);
+        assert_eq!(phases[2].settle.events, 50);
+    }
+
+    /// OLTP-like trace: 8 KiB point reads (two blocks) at Zipfian-skewed
+    /// pages, with a 64-page range scan every 400 requests.  Scan requests
+    /// are flagged.
+    fn oltp_trace(seed: u64, requests: usize) -> Vec<(IoEvent, bool)> {
+        let mut pages = crate::workload::Zipfian {
+            n_blocks: 1 << 20,
+            theta: 0.99,
+            seed,
+        }
+        .workload();
+        let page = |lba| IoEvent {
+            blocks: 2,
+            ..IoEvent::read(lba)
+        };
+        let mut trace = Vec::with_capacity(requests + 64);
+        for i in 1u64.. {
+            if trace.len() >= requests {
+                break;
+            }
+            if i % 400 == 0 {
+                let start = (1 << 22) + (i * 7919 % (1 << 20)) * 2;
+                trace.extend((0..64).map(|k| (page(start + k * 2), true)));
+            } else {
+                trace.push((page(pages.next().unwrap() * 2), false));
+            }
+        }
+        trace
+    }
+
+    /// Accuracy, share of scan blocks served by a prefetch, and triggers.
+    fn oltp_run(kernel: &mut AetherLinkKernel, trace: &[(IoEvent, bool)]) -> (f64, f64, u64) {
+        let mut cache = CacheSim::new(4096);
+        let (mut scan_blocks, mut scan_hits, mut triggers) = (0, 0, 0);
+        let mut accuracy = 0.0;
+        for &(ev, scan) in trace {
+            let hits = cache.stats().prefetch_hits;
+            let report = run(kernel, &mut cache, std::iter::once(ev));
+            accuracy = report.accuracy;
+            triggers += report.triggers;
+            if scan {
+                scan_blocks += u64::from(ev.blocks);
+                scan_hits += cache.stats().prefetch_hits - hits;
+            }
+        }
+        (accuracy, scan_hits as f64 / scan_blocks as f64, triggers)
+    }
+
+    #[test]
+    fn test_database_preset_on_oltp_trace() {
+        for seed in [1, 9] {
+            let trace = oltp_trace(seed, 12_000);
+            let (db, db_scans, db_triggers) =
+                oltp_run(&mut AetherLinkKernel::new_database(), &trace);
+            let (gaming, _, gaming_triggers) =
+                oltp_run(&mut AetherLinkKernel::new_gaming(), &trace);
+            // Every cached prefetch counts, so accuracy covers the whole run.
+            assert!(db > 5.0 * gaming, "seed {seed}: accuracy {db} vs {gaming}");
+            assert!(db_scans >= 0.8, "seed {seed}: scan coverage {db_scans}");
+            // Short hops between hot pages count the same in either
+            // direction, so the database preset still fires on some.
+            assert!(db_triggers * 3 < gaming_triggers, "seed {seed}");
+        }
+    }
+
+    const SHARD_READS: u64 = 2048;
+
+    /// Epoch reads: 128 KiB requests (32 blocks) through a 256 MiB shard
+    /// (65 536 blocks), then a jump to another shard, `shards` times.
+    fn epoch_trace(seed: u64, shards: usize) -> Vec<IoEvent> {
+        let mut rng = crate::rng::SplitMix64::new(seed);
+        (0..shards)
+            .flat_map(|_| {
+                let base = rng.below(1 << 20) << 16;
+                (0..SHARD_READS).map(move |k| IoEvent {
+                    blocks: 32,
+                    ..IoEvent::read(base + k * 32)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ml_training_preset_on_epoch_trace() {
+        for seed in [1, 5] {
+            let trace = epoch_trace(seed, 8);
+            let mut kernel = AetherLinkKernel::new_ml_training();
+            let mut cache = CacheSim::new(1 << 14);
+            let mut fired = Vec::new();
+            let summary = replay::replay(
+                &mut kernel,
+                trace.iter().copied(),
+                &ReplayOptions::default(),
+                Some(&mut cache),
+                |d, _| {
+                    if d.prefetch {
+                        fired.push(d.index);
+                    }
+                },
+            );
+            let report = EvalReport::new("ml", summary.events, summary.triggers, cache.stats());
+            // Jump misses included, so the sequential stretches do better.
+            assert!(report.coverage > 0.95, "seed {seed}: {report}");
+            for jump in (SHARD_READS..summary.events).step_by(SHARD_READS as usize) {
+                let first = fired.iter().find(|&&i| i >= jump).unwrap();
+                assert!(
+                    first - jump < 5,
+                    "seed {seed}: jump at {jump}, trigger at {first}"
+                );
+            }
+            let gaming = run(
+                &mut AetherLinkKernel::new_gaming(),
+                &mut CacheSim::new(1 << 14),
+                trace.iter().copied(),
+            );
+            assert!(report.coverage > 2.0 * gaming.coverage, "{gaming}");
+        }
+    }
+
+    /// WSL2-like trace: files read in 16-block requests issued every 50 µs,
+    /// broken by drvfs metadata storms of 48 scattered single-block reads.
+    /// Each request completes after 300 µs plus up to 2 ms of skewed
+    /// jitter, and the trace is in completion order, so the jitter
+    /// reorders requests in flight.
+    fn wsl2_trace(seed: u64, requests: usize) -> Vec<IoEvent> {
+        let mut rng = crate::rng::SplitMix64::new(seed);
+        let mut issued = Vec::with_capacity(requests + 176);
+        let mut now = 0;
+        while issued.len() < requests {
+            let file = (1 << 20) + rng.below(1 << 24) * 16;
+            for k in 0..128 {
+                now += 50_000;
+                let read = IoEvent {
+                    blocks: 16,
+                    ..IoEvent::read(file + k * 16)
+                };
+                issued.push((now, read));
+            }
+            for _ in 0..48 {
+                now += 10_000;
+                issued.push((now, IoEvent::read(rng.below(1 << 16))));
+            }
+        }
+        let mut completed: Vec<IoEvent> = issued
+            .into_iter()
+            .map(|(at, ev)| IoEvent {
+                timestamp_ns: at + 300_000 + rng.below(2_000_000) * rng.below(1_000) / 1_000,
+                ..ev
+            })
+            .collect();
+        completed.sort_by_key(|ev| ev.timestamp_ns);
+        completed
+    }
+
+    #[test]
+    fn test_wsl2_preset_covers_more_for_less_waste() {
+        for seed in [1, 2] {
+            let trace = wsl2_trace(seed, 10_000);
+            let eval = |mut kernel: AetherLinkKernel| {
+                let r = run(
+                    &mut kernel,
+                    &mut CacheSim::new(1 << 13),
+                    trace.iter().copied(),
+                );
+                (r.coverage, r.cache.prefetched - r.cache.prefetch_hits)
+            };
+            let (coverage, waste) = eval(AetherLinkKernel::new_wsl2());
+            let (base_coverage, base_waste) = eval(AetherLinkKernel::default());
+            assert!(
+                coverage > 1.5 * base_coverage,
+                "seed {seed}: coverage {coverage} vs {base_coverage}"
+            );
+            assert!(
+                waste < base_waste,
+                "seed {seed}: waste {waste} vs {base_waste}"
+            );
+        }
+    }
+}
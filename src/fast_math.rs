@@ -13,6 +13,79 @@
 //! | `fast_atanf` | ~1.5 ns | ≤ 1 ULP | libm atanf, hardware-supported |
 //! | `fast_exp` | ~1.5 ns | full precision | hardware `x.exp()` |
 //! | `fast_sigmoid` | ~3.0 ns | < 1% | composed from fast_exp |
+//!
+//! # Deterministic mode
+//!
+//! With the `deterministic` feature every transcendental on the decision
+//! path — the ones above plus [`sin`], [`cos`], [`acos`], [`atan2`] and
+//! [`ln`] — is computed by the polynomials in `portable` instead of the
+//! platform libm, so a trace yields the same decisions on every target.
+//! Those are a few ULP less accurate; see `portable` for the bounds.
+
+#[cfg_attr(not(feature = "deterministic"), allow(dead_code))]
+mod portable;
+
+/// Float methods `core` lacks: the inherent ones with `std`, the `libm`
+/// crate's without, so `std` builds keep the platform libm's results.
+#[allow(dead_code, unused_imports)]
+pub(crate) mod float {
+    #[cfg(feature = "std")]
+    mod imp {
+        #[inline(always)]
+        pub fn exp(x: f32) -> f32 {
+            x.exp()
+        }
+        #[inline(always)]
+        pub fn sin(x: f32) -> f32 {
+            x.sin()
+        }
+        #[inline(always)]
+        pub fn cos(x: f32) -> f32 {
+            x.cos()
+        }
+        #[inline(always)]
+        pub fn acos(x: f32) -> f32 {
+            x.acos()
+        }
+        #[inline(always)]
+        pub fn atan2(y: f32, x: f32) -> f32 {
+            y.atan2(x)
+        }
+        #[inline(always)]
+        pub fn ln(x: f32) -> f32 {
+            x.ln()
+        }
+        #[inline(always)]
+        pub fn ceil(x: f32) -> f32 {
+            x.ceil()
+        }
+        #[inline(always)]
+        pub fn ceil_f64(x: f64) -> f64 {
+            x.ceil()
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    mod imp {
+        pub use libm::{
+            acosf as acos, atan2f as atan2, ceil as ceil_f64, ceilf as ceil, cosf as cos,
+            expf as exp, logf as ln, sinf as sin,
+        };
+    }
+
+    pub(crate) use imp::*;
+
+    /// `x.rem_euclid(rhs)`, computed the way `std` does.
+    #[inline(always)]
+    pub(crate) fn rem_euclid(x: f32, rhs: f32) -> f32 {
+        let r = x % rhs;
+        if r < 0.0 {
+            r + rhs.abs()
+        } else {
+            r
+        }
+    }
+}
 
 // Note: FRAC_PI_2 removed — previously used in a different atan strategy.
 // libm::atanf handles the full range without range-reduction.
@@ -36,6 +109,9 @@ use core::f32::consts::FRAC_PI_2;
 /// within measurement noise of the hardware FLOPS ceiling).
 #[inline(always)]
 pub fn fast_atanf(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::atan(x);
+    #[cfg(not(feature = "deterministic"))]
     libm::atanf(x)
 }
 
@@ -50,9 +126,57 @@ pub fn fast_atanf(x: f32) -> f32 {
 /// ~1.5 ns with FMA + F16C.
 #[inline(always)]
 pub fn fast_exp(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::exp(x);
     // SAFETY: `exp` is a safe intrinsic with no UB conditions.
     // The compiler is trusted to emit the optimal instruction sequence.
-    x.exp()
+    #[cfg(not(feature = "deterministic"))]
+    float::exp(x)
+}
+
+/// Sine (radians).
+#[inline(always)]
+pub fn sin(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::sin(x);
+    #[cfg(not(feature = "deterministic"))]
+    float::sin(x)
+}
+
+/// Cosine (radians).
+#[inline(always)]
+pub fn cos(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::cos(x);
+    #[cfg(not(feature = "deterministic"))]
+    float::cos(x)
+}
+
+/// Arccosine, NaN outside [-1, 1].
+#[inline(always)]
+pub fn acos(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::acos(x);
+    #[cfg(not(feature = "deterministic"))]
+    float::acos(x)
+}
+
+/// Four-quadrant arctangent of `y / x`.
+#[inline(always)]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::atan2(y, x);
+    #[cfg(not(feature = "deterministic"))]
+    float::atan2(y, x)
+}
+
+/// Natural logarithm.
+#[inline(always)]
+pub fn ln(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return portable::ln(x);
+    #[cfg(not(feature = "deterministic"))]
+    float::ln(x)
 }
 
 /// Fast sigmoid: σ(x) = 1 / (1 + exp(-x))
@@ -84,6 +208,92 @@ pub fn fast_inv_sqrt(x: f32) -> f32 {
     y * (1.5 - 0.5 * x * y * y)
 }
 
+/// `if cond { a } else { b }` as a bit mask instead of a branch, for
+/// decision-path code whose condition flips with the data.
+#[inline(always)]
+pub(crate) fn select(cond: bool, a: f32, b: f32) -> f32 {
+    let mask = (cond as u32).wrapping_neg();
+    f32::from_bits((a.to_bits() & mask) | (b.to_bits() & !mask))
+}
+
+/// `(phi + step).rem_euclid(2π)` without the floating-point modulo: three
+/// adds and two selects.
+///
+/// Valid only in range: `phi` must already be in [0, 2π] and `|step|` below
+/// 2π, which holds for the φ update since |E₂| ≤ 1 and λ ≤ 1.  The result
+/// is in [0, 2π) and within a few ULP of `rem_euclid` — which itself can
+/// round up to exactly 2π for a sum just below zero, where this returns
+/// the float below 2π.
+#[inline(always)]
+pub fn wrap_angle(phi: f32, step: f32) -> f32 {
+    const TAU: f32 = 2.0 * core::f32::consts::PI;
+    // Both corrections are computed from the sum in parallel; the lower one
+    // is capped below 2π, where a sum just under zero would round to.
+    let below_tau = f32::from_bits(TAU.to_bits() - 1);
+    let x = phi + step;
+    let up = (x + TAU).min(below_tau);
+    let down = x - TAU;
+    select(x < 0.0, up, select(x >= TAU, down, x))
+}
+
+// ---------------------------------------------------------------------------
+// Slice forms
+// ---------------------------------------------------------------------------
+//
+// In-place lane-wise versions for structure-of-arrays batches
+// ([`KernelBank`](crate::bank::KernelBank)).  Each is a straight loop over
+// the scalar function, so every lane is bit-identical to a scalar call; the
+// compiler vectorises whatever the scalar body allows (the arithmetic
+// polynomials under `deterministic`, not the libm calls otherwise).
+
+/// [`fast_atanf`] on every element.
+#[inline]
+pub(crate) fn atan_slice(xs: &mut [f32]) {
+    for x in xs {
+        *x = fast_atanf(*x);
+    }
+}
+
+/// [`sin`] on every element.
+#[inline]
+pub(crate) fn sin_slice(xs: &mut [f32]) {
+    for x in xs {
+        *x = sin(*x);
+    }
+}
+
+/// [`cos`] on every element.
+#[inline]
+pub(crate) fn cos_slice(xs: &mut [f32]) {
+    for x in xs {
+        *x = cos(*x);
+    }
+}
+
+/// [`acos`] on every element.
+#[inline]
+pub(crate) fn acos_slice(xs: &mut [f32]) {
+    for x in xs {
+        *x = acos(*x);
+    }
+}
+
+/// `ys[i] = atan2(ys[i], xs[i])`.
+#[inline]
+pub(crate) fn atan2_slice(ys: &mut [f32], xs: &[f32]) {
+    for (y, &x) in ys.iter_mut().zip(xs) {
+        *y = atan2(*y, x);
+    }
+}
+
+/// [`fast_sigmoid`] on every element.
+#[inline]
+pub(crate) fn sigmoid_slice(xs: &mut [f32]) {
+    for x in xs {
+        *x = fast_sigmoid(*x);
+    }
+}
+
 /// Fast square root via `fast_inv_sqrt`.
 #[inline(always)]
 pub fn fast_sqrt(x: f32) -> f32 {
@@ -0,0 +1,292 @@
+//! Portable transcendentals for the `deterministic` feature.
+//!
+//! Each function is a fixed range reduction plus a polynomial evaluated
+//! with plain `f32` `+ - * /`, which IEEE 754 rounds identically on every
+//! target (Rust never contracts to FMA on its own).  Results are therefore
+//! bit-identical across platforms, compilers and libm versions, at the cost
+//! of a few ULP against a correctly rounded libm:
+//!
+//! | Function | Max error vs std (tested) |
+//! |----------|---------------------------|
+//! | `atan` | 3e-7 abs |
+//! | `sin`, `cos`, `atan2`, `acos` | 5e-7 abs |
+//! | `exp` | 1e-6 rel |
+//! | `ln` | 1e-6 abs |
+//!
+//! Edge cases (NaN, ±∞, out-of-domain `acos`) follow std.
+
+use core::f32::consts::{FRAC_PI_2, LN_2, PI, SQRT_2};
+
+/// ln 2 split so that `k * LN2_HI` is exact for |k| < 2¹¹.
+const LN2_HI: f32 = 0.693_145_75;
+const LN2_LO: f32 = 1.428_606_8e-6;
+/// 2π split the same way, for reducing angles.
+const TAU_HI: f32 = 6.281_25;
+const TAU_LO: f32 = 1.935_307_2e-3;
+
+/// Round half away from zero, without `f32::round` (std-only on our MSRV).
+#[inline(always)]
+fn round(x: f32) -> f32 {
+    (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32 as f32
+}
+
+/// `x * 2^k` for `k` in `-252..=254`.
+#[inline(always)]
+fn scale(x: f32, k: i32) -> f32 {
+    let pow2 = |k: i32| f32::from_bits(((k + 127) as u32) << 23);
+    if k < -126 {
+        x * pow2(k + 126) * pow2(-126)
+    } else if k > 127 {
+        x * pow2(k - 127) * pow2(127)
+    } else {
+        x * pow2(k)
+    }
+}
+
+/// Arctangent.  Reduces to [0, 1] via `atan(x) = π/2 − atan(1/x)`, then a
+/// degree-17 odd polynomial (Abramowitz & Stegun 4.4.49, |ε| ≤ 2e-8).
+#[inline]
+pub fn atan(x: f32) -> f32 {
+    if x.is_nan() {
+        return x;
+    }
+    // Range reduction and sign restoration are selects, not branches.
+    let a = x.abs();
+    let invert = a > 1.0;
+    let t = super::select(invert, 1.0 / a, a);
+    let t2 = t * t;
+    let p = 1.0
+        + t2 * (-0.333_331_45
+            + t2 * (0.199_935_51
+                + t2 * (-0.142_089
+                    + t2 * (0.106_562_64
+                        + t2 * (-0.075_289_64
+                            + t2 * (0.042_909_614
+                                + t2 * (-0.016_165_737 + t2 * 0.002_866_225_7)))))));
+    let r = t * p;
+    let r = super::select(invert, FRAC_PI_2 - r, r);
+    super::select(x < 0.0, -r, r)
+}
+
+/// Four-quadrant arctangent of `y / x`.
+#[inline]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if x.is_nan() || y.is_nan() {
+        return f32::NAN;
+    }
+    if x == 0.0 {
+        return if y > 0.0 {
+            FRAC_PI_2
+        } else if y < 0.0 {
+            -FRAC_PI_2
+        } else {
+            0.0
+        };
+    }
+    let a = atan(y / x);
+    if x > 0.0 {
+        a
+    } else if y >= 0.0 {
+        a + PI
+    } else {
+        a - PI
+    }
+}
+
+/// Arccosine via `atan2(√(1 − y²), y)`; NaN outside [-1, 1] like std.
+#[inline]
+pub fn acos(y: f32) -> f32 {
+    if y.is_nan() || y.abs() > 1.0 {
+        return f32::NAN;
+    }
+    // `sqrtf` is correctly rounded by IEEE 754, hence portable.
+    atan2(libm::sqrtf((1.0 - y) * (1.0 + y)), y)
+}
+
+/// `x` reduced to [-π, π].
+#[inline(always)]
+fn reduce_angle(x: f32) -> f32 {
+    let k = round(x * (1.0 / (TAU_HI + TAU_LO)));
+    (x - k * TAU_HI) - k * TAU_LO
+}
+
+/// Sine on [-π/2, π/2]: Taylor series to x¹¹ (|ε| ≤ 6e-8).
+#[inline(always)]
+fn sin_poly(x: f32) -> f32 {
+    let x2 = x * x;
+    x + x
+        * x2
+        * (-1.0 / 6.0
+            + x2 * (1.0 / 120.0
+                + x2 * (-1.0 / 5_040.0 + x2 * (1.0 / 362_880.0 + x2 * (-1.0 / 39_916_800.0)))))
+}
+
+/// Sine.
+#[inline]
+pub fn sin(x: f32) -> f32 {
+    let r = reduce_angle(x);
+    let r = if r > FRAC_PI_2 {
+        PI - r
+    } else if r < -FRAC_PI_2 {
+        -PI - r
+    } else {
+        r
+    };
+    sin_poly(r)
+}
+
+/// Cosine, as `sin(π/2 − |x|)` after reduction.
+#[inline]
+pub fn cos(x: f32) -> f32 {
+    sin_poly(FRAC_PI_2 - reduce_angle(x).abs())
+}
+
+/// Natural exponential.  `x = k·ln 2 + r` with |r| ≤ ln 2 / 2, then a
+/// degree-7 Taylor polynomial for `eʳ` (|ε| ≤ 5e-9) scaled by `2ᵏ`.
+#[inline]
+pub fn exp(x: f32) -> f32 {
+    if x.is_nan() {
+        return x;
+    }
+    if x > 88.722_84 {
+        return f32::INFINITY;
+    }
+    if x < -103.972_08 {
+        return 0.0;
+    }
+    let k = round(x * (1.0 / LN_2));
+    let r = (x - k * LN2_HI) - k * LN2_LO;
+    let p = 1.0
+        + r * (1.0
+            + r * (1.0 / 2.0
+                + r * (1.0 / 6.0
+                    + r * (1.0 / 24.0
+                        + r * (1.0 / 120.0 + r * (1.0 / 720.0 + r * (1.0 / 5_040.0)))))));
+    scale(p, k as i32)
+}
+
+/// Natural logarithm.  Splits `x = m·2ᵉ` with m ∈ [√½, √2), then
+/// `ln m = 2·atanh(s)` with `s = (m − 1)/(m + 1)` to s⁹ (|ε| ≤ 2e-9).
+#[inline]
+pub fn ln(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    if x.is_infinite() {
+        return x;
+    }
+    let (x, mut e) = if x < f32::MIN_POSITIVE {
+        (x * 33_554_432.0, -25) // 2²⁵: subnormal → normal
+    } else {
+        (x, 0)
+    };
+    let bits = x.to_bits();
+    e += ((bits >> 23) as i32) - 127;
+    let mut m = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+    if m > SQRT_2 {
+        m *= 0.5;
+        e += 1;
+    }
+    let s = (m - 1.0) / (m + 1.0);
+    let s2 = s * s;
+    let ln_m = 2.0 * s * (1.0 + s2 * (1.0 / 3.0 + s2 * (1.0 / 5.0 + s2 * (1.0 / 7.0 + s2 / 9.0))));
+    let e = e as f32;
+    e * LN2_HI + (ln_m + e * LN2_LO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(lo: f32, hi: f32) -> impl Iterator<Item = f32> {
+        (0..=2000).map(move |i| lo + (hi - lo) * i as f32 / 2000.0)
+    }
+
+    /// `atan` before its range reduction became a select.
+    fn branchy_atan(x: f32) -> f32 {
+        if x.is_nan() {
+            return x;
+        }
+        let a = x.abs();
+        let (t, invert) = if a > 1.0 { (1.0 / a, true) } else { (a, false) };
+        let t2 = t * t;
+        let p = 1.0
+            + t2 * (-0.333_331_45
+                + t2 * (0.199_935_51
+                    + t2 * (-0.142_089
+                        + t2 * (0.106_562_64
+                            + t2 * (-0.075_289_64
+                                + t2 * (0.042_909_614
+                                    + t2 * (-0.016_165_737 + t2 * 0.002_866_225_7)))))));
+        let r = t * p;
+        let r = if invert { FRAC_PI_2 - r } else { r };
+        if x < 0.0 {
+            -r
+        } else {
+            r
+        }
+    }
+
+    #[test]
+    fn test_branch_free_atan_is_bit_identical() {
+        let specials = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+        ];
+        // Every 4099th bit pattern covers all exponents and both signs.
+        for x in specials
+            .into_iter()
+            .chain((0..=u32::MAX).step_by(4099).map(f32::from_bits))
+        {
+            if !x.is_nan() {
+                assert_eq!(atan(x).to_bits(), branchy_atan(x).to_bits(), "atan({x})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_std_within_documented_error() {
+        for x in grid(-50.0, 50.0) {
+            assert!((atan(x) - x.atan()).abs() < 3e-7, "atan({x})");
+        }
+        for x in grid(-10.0, 10.0) {
+            assert!((sin(x) - x.sin()).abs() < 5e-7, "sin({x})");
+            assert!((cos(x) - x.cos()).abs() < 5e-7, "cos({x})");
+            let y = x * 0.5;
+            assert!((atan2(y, x) - y.atan2(x)).abs() < 5e-7, "atan2({y}, {x})");
+        }
+        for x in grid(-1.0, 1.0) {
+            assert!((acos(x) - x.acos()).abs() < 5e-7, "acos({x})");
+        }
+        for x in grid(-80.0, 80.0) {
+            let rel = (exp(x) - x.exp()).abs() / x.exp();
+            assert!(rel < 1e-6, "exp({x})");
+        }
+        for x in grid(1e-3, 1e4) {
+            assert!((ln(x) - x.ln()).abs() < 1e-6, "ln({x})");
+        }
+    }
+
+    #[test]
+    fn test_edge_cases_follow_std() {
+        assert!(ln(-1.0).is_nan() && ln(f32::NAN).is_nan());
+        assert_eq!(ln(0.0), f32::NEG_INFINITY);
+        assert_eq!(ln(f32::INFINITY), f32::INFINITY);
+        assert!((ln(1e-40) - 1e-40_f32.ln()).abs() < 1e-4);
+        assert_eq!(exp(100.0), f32::INFINITY);
+        assert_eq!(exp(-200.0), 0.0);
+        assert!(acos(1.5).is_nan());
+        assert_eq!(atan(f32::INFINITY), FRAC_PI_2);
+        assert_eq!(atan2(0.0, 0.0), 0.0);
+        assert!((atan2(1.0, -1.0) - 0.75 * PI).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,385 @@
+//! Spatial footprints: the blocks a region's visits touch.
+//!
+//! Extrapolating a stream misses the common layout where every visit to a
+//! region touches the same scattered set of blocks — fixed records in
+//! each segment, an index page and its leaves.  With
+//! [`AetherLinkKernel::enable_spatial_footprints`] the kernel learns, in
+//! the manner of spatial memory streaming, a bitmap of the blocks each
+//! visit touched:
+//!
+//! - the device is split into regions of [`FOOTPRINT_BLOCKS`] blocks, and a
+//!   visit lasts while consecutive cycle accesses stay in one region;
+//! - when a visit ends, its bitmap replaces the region's entry in a table
+//!   of [`FOOTPRINT_REGIONS`] entries, evicting the least recently used
+//!   region when the table is full;
+//! - entering a region with an entry makes that bitmap the
+//!   [`predicted_footprint`](AetherLinkKernel::predicted_footprint) for the
+//!   visit, and the first firing [`push_lba`](AetherLinkKernel::push_lba)
+//!   decision of the visit targets the smallest extent holding the
+//!   predicted blocks not yet touched, instead of the horizon plan or the
+//!   caller's choice.
+//!
+//! The table is a fixed array allocated when footprints are enabled; the
+//! decision path only sets bits and scans it.  A working set of more
+//! regions than entries thrashes the table: visits then find no entry and
+//! the decisions are those of a kernel without footprints.
+
+use crate::prefetcher::{PrefetchDecision, PrefetchPlan};
+use crate::AetherLinkKernel;
+use alloc::boxed::Box;
+
+/// Blocks per region, one footprint bit each.
+pub const FOOTPRINT_BLOCKS: u64 = 256;
+
+/// Regions the footprint table holds.
+pub const FOOTPRINT_REGIONS: usize = 64;
+
+const WORDS: usize = (FOOTPRINT_BLOCKS / 64) as usize;
+
+type Bits = [u64; WORDS];
+
+/// Blocks of one region a visit is expected to touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    /// First block of the region.
+    pub region_start: u64,
+    /// One bit per block of the region, block `region_start + i` at bit
+    /// `i % 64` of word `i / 64`.
+    pub bits: [u64; WORDS],
+}
+
+impl Footprint {
+    /// Whether the footprint holds `lba`.
+    #[inline]
+    pub fn contains(&self, lba: u64) -> bool {
+        let offset = lba.wrapping_sub(self.region_start);
+        offset < FOOTPRINT_BLOCKS && test(&self.bits, offset as usize)
+    }
+
+    /// Blocks in the footprint.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.bits.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// `true` if the footprint holds no block.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits == [0; WORDS]
+    }
+
+    /// The blocks in ascending order.
+    pub fn blocks(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..FOOTPRINT_BLOCKS as usize)
+            .filter(move |&i| test(&self.bits, i))
+            .map(move |i| self.region_start + i as u64)
+    }
+
+    /// Smallest extent holding every block, if any.
+    pub fn extent(&self) -> Option<PrefetchPlan> {
+        let first = self.bits.iter().enumerate().find(|(_, &w)| w != 0)?;
+        let last = self.bits.iter().enumerate().rfind(|(_, &w)| w != 0)?;
+        let lo = first.0 as u64 * 64 + first.1.trailing_zeros() as u64;
+        let hi = last.0 as u64 * 64 + 63 - last.1.leading_zeros() as u64;
+        Some(PrefetchPlan::new(self.region_start + lo, hi - lo + 1))
+    }
+}
+
+#[inline(always)]
+fn test(bits: &Bits, i: usize) -> bool {
+    bits[i / 64] >> (i % 64) & 1 != 0
+}
+
+/// Visit and table counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FootprintStats {
+    /// Region visits started.
+    pub visits: u64,
+    /// Visits that found a learned footprint.
+    pub predicted: u64,
+    /// Footprint plans emitted.
+    pub emitted: u64,
+    /// Table entries evicted to learn another region.
+    pub evictions: u64,
+}
+
+/// A region's learned footprint; `used == 0` marks a free slot.
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    region: u64,
+    bits: Bits,
+    used: u64,
+}
+
+/// Footprint table and the visit in progress.
+#[derive(Debug, Clone)]
+pub(crate) struct SpatialFootprints {
+    table: [Entry; FOOTPRINT_REGIONS],
+    /// Last-use stamp for the LRU order.
+    clock: u64,
+    region: Option<u64>,
+    touched: Bits,
+    predicted: Option<Bits>,
+    emitted: bool,
+    stats: FootprintStats,
+}
+
+impl SpatialFootprints {
+    fn new() -> Self {
+        Self {
+            table: [Entry::default(); FOOTPRINT_REGIONS],
+            clock: 0,
+            region: None,
+            touched: [0; WORDS],
+            predicted: None,
+            emitted: false,
+            stats: FootprintStats::default(),
+        }
+    }
+
+    /// Account for the cycle's access, before the decision.
+    #[inline]
+    pub(crate) fn observe(&mut self, lba: u64) {
+        let region = lba / FOOTPRINT_BLOCKS;
+        if self.region != Some(region) {
+            if let Some(prev) = self.region {
+                self.learn(prev);
+            }
+            self.region = Some(region);
+            self.touched = [0; WORDS];
+            self.emitted = false;
+            self.predicted = self.lookup(region);
+            self.stats.visits += 1;
+            self.stats.predicted += self.predicted.is_some() as u64;
+        }
+        let i = (lba % FOOTPRINT_BLOCKS) as usize;
+        self.touched[i / 64] |= 1 << (i % 64);
+    }
+
+    fn lookup(&mut self, region: u64) -> Option<Bits> {
+        let entry = self
+            .table
+            .iter_mut()
+            .find(|e| e.used != 0 && e.region == region)?;
+        self.clock += 1;
+        entry.used = self.clock;
+        Some(entry.bits)
+    }
+
+    /// Store the finished visit's footprint for `region`.
+    fn learn(&mut self, region: u64) {
+        self.clock += 1;
+        let slot = match self
+            .table
+            .iter()
+            .position(|e| e.used != 0 && e.region == region)
+        {
+            Some(i) => i,
+            None => {
+                let (i, victim) = self
+                    .table
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, e)| e.used)
+                    .expect("table is not empty");
+                self.stats.evictions += (victim.used != 0) as u64;
+                i
+            }
+        };
+        if let Some(entry) = self.table.get_mut(slot) {
+            *entry = Entry {
+                region,
+                bits: self.touched,
+                used: self.clock,
+            };
+        }
+    }
+
+    /// The predicted blocks not yet touched, once per visit.
+    #[inline]
+    fn plan(&mut self) -> Option<PrefetchPlan> {
+        if self.emitted {
+            return None;
+        }
+        let predicted = self.predicted?;
+        let mut remaining = Footprint {
+            region_start: self.region? * FOOTPRINT_BLOCKS,
+            bits: predicted,
+        };
+        for (word, touched) in remaining.bits.iter_mut().zip(self.touched) {
+            *word &= !touched;
+        }
+        let extent = remaining.extent()?;
+        self.emitted = true;
+        self.stats.emitted += 1;
+        Some(extent)
+    }
+}
+
+impl AetherLinkKernel {
+    /// Learn region footprints and target them on re-entry, starting from
+    /// an empty table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_spatial_footprints();
+    /// // Two visits to each of two regions, touching blocks 0, 40 and 90.
+    /// for _ in 0..2 {
+    ///     for region in [10u64, 20] {
+    ///         for offset in [0, 40, 90] {
+    ///             kernel.push_lba(region * 256 + offset);
+    ///         }
+    ///     }
+    /// }
+    /// let footprint = kernel.predicted_footprint().unwrap();
+    /// assert_eq!(footprint.blocks().collect::<Vec<_>>(), [5_120, 5_160, 5_210]);
+    /// ```
+    pub fn enable_spatial_footprints(&mut self) {
+        self.footprints = Some(Box::new(SpatialFootprints::new()));
+    }
+
+    /// Stop learning footprints and drop the table.
+    #[inline]
+    pub fn disable_spatial_footprints(&mut self) {
+        self.footprints = None;
+    }
+
+    /// Footprint learned for the region of the newest access, if its visit
+    /// found one.
+    pub fn predicted_footprint(&self) -> Option<Footprint> {
+        let f = self.footprints.as_ref()?;
+        Some(Footprint {
+            region_start: f.region? * FOOTPRINT_BLOCKS,
+            bits: f.predicted?,
+        })
+    }
+
+    /// Visit and table counters; all zero while footprints are off.
+    pub fn footprint_stats(&self) -> FootprintStats {
+        self.footprints
+            .as_ref()
+            .map_or_else(FootprintStats::default, |f| f.stats)
+    }
+
+    /// Zero the footprint counters, keeping the table.
+    pub(crate) fn reset_footprint_stats(&mut self) {
+        if let Some(f) = &mut self.footprints {
+            f.stats = FootprintStats::default();
+        }
+    }
+
+    /// A firing streaming decision targeting the visit's footprint, if it
+    /// has one left to emit.
+    #[inline]
+    pub(crate) fn footprint_decision(&mut self) -> Option<PrefetchDecision> {
+        let plan = self.footprints.as_mut()?.plan()?;
+        Some(
+            self.bound_plan(plan)
+                .map_or(PrefetchDecision::SKIP, |plan| {
+                    PrefetchDecision::trigger(Some(plan))
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    /// Blocks every visit touches, in order, as offsets into the region.
+    const LAYOUT: [u64; 10] = [0, 3, 17, 18, 40, 96, 130, 131, 200, 250];
+
+    /// Visits in random order to `regions` regions, each touching the
+    /// layout blocks with probability 9/10 (the first always).
+    fn trace(regions: u64, visits: usize, seed: u64) -> Vec<u64> {
+        let mut rng = SplitMix64::new(seed);
+        let mut lbas = Vec::new();
+        for _ in 0..visits {
+            let base = (1 << 20) + rng.below(regions) * 7 * FOOTPRINT_BLOCKS;
+            for (k, &offset) in LAYOUT.iter().enumerate() {
+                if k == 0 || rng.below(10) != 0 {
+                    lbas.push(base + offset);
+                }
+            }
+        }
+        lbas
+    }
+
+    #[test]
+    fn test_repeating_layout_is_recalled_and_targeted() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.enable_spatial_footprints();
+        let (mut touched, mut recalled) = (0, 0);
+        let mut targets = 0;
+        let mut prediction = None;
+        for lba in trace(32, 2_000, 5) {
+            let d = kernel.push_lba(lba);
+            let region = lba / FOOTPRINT_BLOCKS;
+            match prediction {
+                Some(f @ Footprint { region_start, .. })
+                    if region_start / FOOTPRINT_BLOCKS == region =>
+                {
+                    touched += 1;
+                    recalled += f.contains(lba) as u64;
+                }
+                _ => prediction = kernel.predicted_footprint(),
+            }
+            if kernel.footprint_stats().emitted > targets {
+                let plan = d.unwrap().target.unwrap();
+                assert_eq!(plan.start / FOOTPRINT_BLOCKS, region, "{lba}: {plan:?}");
+                assert_eq!((plan.end() - 1) / FOOTPRINT_BLOCKS, region);
+                assert!(plan.start > lba - lba % FOOTPRINT_BLOCKS);
+                targets += 1;
+            }
+        }
+        let recall = recalled as f64 / touched as f64;
+        assert!(recall > 0.85, "recall {recall}");
+        let s = kernel.footprint_stats();
+        assert_eq!(s.evictions, 0, "{s:?}");
+        assert!(s.predicted > s.visits * 9 / 10, "{s:?}");
+        assert!(targets > 500, "{s:?}");
+
+        kernel.reset_stats();
+        assert_eq!(kernel.footprint_stats(), FootprintStats::default());
+        kernel.disable_spatial_footprints();
+        assert_eq!(kernel.predicted_footprint(), None);
+    }
+
+    #[test]
+    fn test_thrashing_table_falls_back_to_plain_decisions() {
+        // Cycling through four times as many regions as entries evicts
+        // every footprint before its region comes round again.
+        let regions = 4 * FOOTPRINT_REGIONS as u64;
+        let lbas: Vec<u64> = (0..8 * regions)
+            .flat_map(|v| {
+                let base = (v % regions) * 3 * FOOTPRINT_BLOCKS;
+                LAYOUT.iter().map(move |offset| base + offset)
+            })
+            .collect();
+        let mut plain = AetherLinkKernel::new_gaming();
+        let mut learning = AetherLinkKernel::new_gaming();
+        learning.enable_spatial_footprints();
+        for &lba in &lbas {
+            assert_eq!(learning.push_lba(lba), plain.push_lba(lba));
+        }
+        let s = learning.footprint_stats();
+        assert_eq!((s.predicted, s.emitted), (0, 0), "{s:?}");
+        assert_eq!(s.evictions, s.visits - FOOTPRINT_REGIONS as u64 - 1);
+
+        // A working set that fits is learned again.
+        for &lba in lbas
+            .iter()
+            .take(FOOTPRINT_REGIONS / 2 * LAYOUT.len())
+            .cycle()
+            .take(20_000)
+        {
+            learning.push_lba(lba);
+        }
+        assert!(learning.footprint_stats().predicted > 1_000);
+    }
+}
@@ -0,0 +1,294 @@
+//! Gap interpolation: which blocks of a strided plan to read.
+//!
+//! A [horizon](crate::horizon) plan covers the movement expected over the
+//! next cycles, so for a stream reading every other block it spans the
+//! skipped blocks too.  Devices read at extent granularity, and for short
+//! strides reading the whole extent costs about as much as reading the
+//! predicted blocks alone; for long ones most of it is waste.
+//! [`AetherLinkKernel::set_gap_policy`] chooses between the two:
+//!
+//! - [`GapPolicy::Contiguous`] (the default) reads the covering extent;
+//! - [`GapPolicy::Exact`] reads only the predicted stride points — the
+//!   access plus one, two, … steps — each as a one-block extent;
+//! - [`GapPolicy::FillSmallGaps`] reads the covering extent while the gaps
+//!   between stride points are shorter than `max_gap` blocks, i.e. while
+//!   the stride is at most `max_gap`, and the stride points otherwise.
+//!
+//! Decisions and their targets are unchanged: the policy applies when a
+//! target is turned into reads, through
+//! [`AetherLinkKernel::target_extents`] or
+//! [`Prefetcher::extents`](crate::Prefetcher::extents), which
+//! [`replay`](crate::replay::replay) and the [`eval`](crate::eval) harness
+//! use.  Only the active horizon plan carries a stride; every other target
+//! is read whole.  [`eval::compare_gap_policies`](crate::eval::compare_gap_policies)
+//! reports the accuracy and waste of each policy on a trace.
+
+use crate::prefetcher::PrefetchPlan;
+use crate::AetherLinkKernel;
+use core::fmt;
+
+/// How the blocks between predicted stride points are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GapPolicy {
+    /// Only the predicted stride points.
+    Exact,
+    /// The covering extent when the gaps are below `max_gap` blocks, the
+    /// stride points otherwise.
+    FillSmallGaps {
+        /// Gap length, in blocks, from which stride points are read alone.
+        max_gap: u64,
+    },
+    /// Always the covering extent.
+    #[default]
+    Contiguous,
+}
+
+impl GapPolicy {
+    /// Whether a plan sized with steps of `stride` blocks is read as one
+    /// extent.  A stride of 0 or 1 leaves no gap and always is.
+    #[inline]
+    pub fn fills(self, stride: u64) -> bool {
+        match self {
+            Self::Exact => stride <= 1,
+            Self::FillSmallGaps { max_gap } => stride <= max_gap.max(1),
+            Self::Contiguous => true,
+        }
+    }
+}
+
+impl fmt::Display for GapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact => f.write_str("exact"),
+            Self::FillSmallGaps { max_gap } => write!(f, "fill-{max_gap}"),
+            Self::Contiguous => f.write_str("contiguous"),
+        }
+    }
+}
+
+/// Extents to read for one target, nearest the access first; see
+/// [`AetherLinkKernel::target_extents`].
+#[derive(Debug, Clone)]
+pub struct TargetExtents {
+    /// The whole target while it is still to be returned, when read
+    /// whole (`stride` 0).
+    whole: Option<PrefetchPlan>,
+    plan: PrefetchPlan,
+    anchor: u64,
+    stride: u64,
+    descending: bool,
+    /// Steps from the anchor to the next stride point.
+    k: u64,
+}
+
+impl Iterator for TargetExtents {
+    type Item = PrefetchPlan;
+
+    fn next(&mut self) -> Option<PrefetchPlan> {
+        if self.stride == 0 {
+            return self.whole.take();
+        }
+        loop {
+            let distance = self.k.checked_mul(self.stride)?;
+            self.k += 1;
+            let point = if self.descending {
+                self.anchor.checked_sub(distance)?
+            } else {
+                self.anchor.checked_add(distance)?
+            };
+            // Points run away from the anchor; the far end of the plan
+            // stops them, the near end (after trimming) skips them.
+            let beyond = if self.descending {
+                point < self.plan.start
+            } else {
+                point >= self.plan.end()
+            };
+            if beyond {
+                return None;
+            }
+            if point >= self.plan.start && point < self.plan.end() {
+                return Some(PrefetchPlan::new(point, 1));
+            }
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Read horizon plans as `policy` says.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::gap::GapPolicy;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_horizon(4);
+    /// kernel.set_gap_policy(GapPolicy::Exact);
+    /// let (lba, plan) = (0..100u64)
+    ///     .map(|i| 1_000 + 64 * i)
+    ///     .find_map(|lba| Some((lba, kernel.push_lba(lba)?.target?)))
+    ///     .unwrap();
+    /// let reads: Vec<u64> = kernel.target_extents(plan).map(|e| e.start).collect();
+    /// assert_eq!(reads, [lba + 64, lba + 128, lba + 192, lba + 256]);
+    /// ```
+    pub fn set_gap_policy(&mut self, policy: GapPolicy) {
+        self.gap_policy = policy;
+    }
+
+    /// How horizon plans are read.
+    #[inline]
+    pub fn gap_policy(&self) -> GapPolicy {
+        self.gap_policy
+    }
+
+    /// Extents to read for `target`, a target this kernel just returned:
+    /// the stride points of the active horizon plan unless the
+    /// [gap policy](Self::set_gap_policy) fills its gaps, and the whole
+    /// target otherwise.
+    pub fn target_extents(&self, target: PrefetchPlan) -> TargetExtents {
+        let mut extents = TargetExtents {
+            whole: Some(target),
+            plan: target,
+            anchor: 0,
+            stride: 0,
+            descending: false,
+            k: 1,
+        };
+        let planned = self.horizon.as_ref().and_then(|h| h.planned());
+        if let Some((plan, anchor, stride, descending)) = planned {
+            if plan == target && !self.gap_policy.fills(stride) {
+                extents.whole = None;
+                extents.anchor = anchor;
+                extents.stride = stride;
+                extents.descending = descending;
+            }
+        }
+        extents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+    use crate::prefetcher::Prefetcher;
+    use crate::trace::IoEvent;
+
+    const HORIZON: u32 = 8;
+
+    /// Extents read for every firing decision on a run with `stride`.
+    fn reads(stride: u64, policy: GapPolicy) -> Vec<(u64, Vec<PrefetchPlan>)> {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(HORIZON);
+        kernel.set_gap_policy(policy);
+        let mut out = Vec::new();
+        for i in 0..2_000u64 {
+            let lba = 1_000_000 + i * stride;
+            let Some(plan) = kernel.observe(lba).target else {
+                continue;
+            };
+            let mut extents = Vec::new();
+            kernel.extents(plan, &mut |e| extents.push(e));
+            out.push((lba, extents));
+        }
+        assert!(out.len() > 100, "stride {stride}, {policy}: {}", out.len());
+        out
+    }
+
+    /// Whether `extents` are exactly the `HORIZON` stride points after
+    /// `lba`.
+    fn stride_points(lba: u64, stride: u64, extents: &[PrefetchPlan]) -> bool {
+        let points: Vec<PrefetchPlan> = (1..=HORIZON as u64)
+            .map(|k| PrefetchPlan::new(lba + k * stride, 1))
+            .collect();
+        extents == points
+    }
+
+    #[test]
+    fn test_stride_2_and_64_under_each_policy() {
+        let fill = GapPolicy::FillSmallGaps { max_gap: 16 };
+        for stride in [2u64, 64] {
+            let covering = |lba: u64| vec![PrefetchPlan::new(lba + 1, stride * HORIZON as u64)];
+            for (lba, extents) in reads(stride, GapPolicy::Contiguous) {
+                assert_eq!(extents, covering(lba), "stride {stride}");
+            }
+            for (lba, extents) in reads(stride, GapPolicy::Exact) {
+                assert!(stride_points(lba, stride, &extents), "{extents:?}");
+            }
+            for (lba, extents) in reads(stride, fill) {
+                if stride == 2 {
+                    assert_eq!(extents, covering(lba));
+                } else {
+                    assert!(stride_points(lba, stride, &extents), "{extents:?}");
+                }
+            }
+        }
+
+        // The harness reports what each policy costs: on stride 64 the
+        // covering extent is 1/64 useful, the stride points all of it, for
+        // the same coverage.
+        let trace: Vec<IoEvent> = (0..20_000u64)
+            .map(|i| IoEvent::read(1_000_000 + i * 64))
+            .collect();
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(HORIZON);
+        let report = eval::compare_gap_policies(
+            &trace,
+            &kernel,
+            &[GapPolicy::Exact, fill, GapPolicy::Contiguous],
+            1 << 16,
+        );
+        let by = |name: &str| {
+            report
+                .reports
+                .iter()
+                .find(|r| r.prefetcher == name)
+                .unwrap()
+        };
+        let (exact, filled, whole) = (by("exact"), by("fill-16"), by("contiguous"));
+        assert!(exact.accuracy > 0.95, "{exact}");
+        assert!(whole.accuracy < 0.05, "{whole}");
+        assert!(whole.pollution_rate > exact.pollution_rate);
+        assert!(whole.cache.prefetched > 32 * exact.cache.prefetched);
+        assert!((exact.coverage - whole.coverage).abs() < 0.05, "{report}");
+        assert_eq!(filled.cache, exact.cache);
+    }
+
+    #[test]
+    fn test_max_gap_equal_to_stride_is_the_boundary() {
+        for stride in [2u64, 16, 64] {
+            let at = GapPolicy::FillSmallGaps { max_gap: stride };
+            let below = GapPolicy::FillSmallGaps {
+                max_gap: stride - 1,
+            };
+            assert!(at.fills(stride) && !below.fills(stride), "stride {stride}");
+            for (lba, extents) in reads(stride, at) {
+                assert_eq!(extents.len(), 1);
+                assert_eq!(extents[0].start, lba + 1);
+            }
+            for (lba, extents) in reads(stride, below) {
+                assert!(stride_points(lba, stride, &extents), "{extents:?}");
+            }
+        }
+        assert!(GapPolicy::Exact.fills(1) && GapPolicy::FillSmallGaps { max_gap: 0 }.fills(1));
+        assert!(!GapPolicy::Exact.fills(2) && GapPolicy::Contiguous.fills(u64::MAX));
+
+        // Targets other than the horizon plan, and kernels without one,
+        // are read whole.
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_gap_policy(GapPolicy::Exact);
+        let target = PrefetchPlan::new(50, 10);
+        assert_eq!(kernel.target_extents(target).collect::<Vec<_>>(), [target]);
+        kernel.set_horizon(4);
+        for lba in (0..2_000).map(|i| 1_000 + i * 16) {
+            kernel.push_lba(lba);
+            if kernel.horizon_plan().is_some() {
+                break;
+            }
+        }
+        assert_ne!(kernel.horizon_plan(), Some(target));
+        assert_eq!(kernel.target_extents(target).collect::<Vec<_>>(), [target]);
+    }
+}
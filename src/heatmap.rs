@@ -0,0 +1,350 @@
+//! Where on the device accesses and prefetches land.
+//!
+//! [`AetherLinkKernel::enable_heatmap`] splits an address range into equal
+//! buckets and counts, per bucket, the accesses of every cycle (the newest
+//! LBA of its window) and the prefetches it fired, at the start of the
+//! plan the decision targets: below the access for a
+//! [descending](crate::direction) stream, wherever a horizon plan or a
+//! [footprint](crate::footprint) begins.  A firing decision without a
+//! target counts at the block after the access, where the caller's extent
+//! would start, as does every firing cycle of the `bool` entry points;
+//! a cycle whose plan was dropped outside the
+//! [address range](AetherLinkKernel::set_address_range) does not count.
+//! LBAs outside the heatmap's range count in the first or last bucket.
+//! The counters live in two vectors allocated when the heatmap is enabled,
+//! so the decision path only adds.
+//!
+//! [`AetherLinkKernel::heatmap`] copies the counts into a
+//! [`HeatmapSnapshot`] with the bucket boundaries, for CSV or (with the
+//! `serde` feature) JSON export.
+//!
+//! # Reset and decay
+//!
+//! The heatmap is meant to accumulate over longer periods than the other
+//! stats, so [`reset_stats`](AetherLinkKernel::reset_stats) leaves it
+//! alone.  [`reset_heatmap`](AetherLinkKernel::reset_heatmap) zeroes it,
+//! and [`decay_heatmap`](AetherLinkKernel::decay_heatmap) halves every
+//! count `shift` times, so calling it on a timer keeps recent traffic
+//! dominant without losing the overall shape.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::AetherLinkKernel;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Address range, bucket geometry and counts.
+#[derive(Debug, Clone)]
+pub(crate) struct Heatmap {
+    start: u64,
+    end: u64,
+    width: u64,
+    demand: Vec<u64>,
+    prefetch: Vec<u64>,
+    /// Bucket of the block after the newest access.
+    next: usize,
+    /// Bucket the newest cycle's prefetch is counted in, if it fired.
+    placed: Option<usize>,
+}
+
+impl Heatmap {
+    /// Bucket holding `lba`, clamped to the range.  Written without
+    /// `clamp` or a plain division so that it cannot panic (the range is
+    /// never empty and the width never 0, but the optimiser cannot know).
+    #[inline(always)]
+    fn bucket(&self, lba: u64) -> usize {
+        let offset = lba
+            .min(self.end.saturating_sub(1))
+            .saturating_sub(self.start);
+        let bucket = offset.checked_div(self.width).unwrap_or(0) as usize;
+        bucket.min(self.demand.len().saturating_sub(1))
+    }
+
+    /// Count the cycle's access.
+    #[inline(always)]
+    pub(crate) fn observe(&mut self, lba: u64) {
+        let b = self.bucket(lba);
+        if let Some(demand) = self.demand.get_mut(b) {
+            *demand += 1;
+        }
+        self.next = self.bucket(lba.saturating_add(1));
+    }
+
+    /// Count the cycle's prefetch, if it fired, after the access until
+    /// the decision [places](Self::place) it.
+    #[inline(always)]
+    pub(crate) fn decided(&mut self, fetch: bool) {
+        if let Some(prefetch) = self.prefetch.get_mut(self.next) {
+            *prefetch += fetch as u64;
+        }
+        self.placed = fetch.then_some(self.next);
+    }
+
+    /// Move the cycle's prefetch to the start of the plan `decision`
+    /// targets, or take it back if the decision no longer fires.
+    #[inline(always)]
+    pub(crate) fn place(&mut self, decision: &PrefetchDecision) {
+        let Some(from) = self.placed.take() else {
+            return;
+        };
+        if let Some(prefetch) = self.prefetch.get_mut(from) {
+            *prefetch = prefetch.saturating_sub(1);
+        }
+        if !decision.fire {
+            return;
+        }
+        let to = decision
+            .target
+            .map_or(self.next, |plan| self.bucket(plan.start));
+        if let Some(prefetch) = self.prefetch.get_mut(to) {
+            *prefetch += 1;
+        }
+        self.placed = Some(to);
+    }
+}
+
+/// Copy of the heatmap counts with their bucket boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatmapSnapshot {
+    /// First LBA of the range.
+    pub start: u64,
+    /// End of the range (exclusive).
+    pub end: u64,
+    /// Blocks per bucket; the last bucket may be shorter.
+    pub bucket_width: u64,
+    /// Accesses per bucket.
+    pub demand: Vec<u64>,
+    /// Prefetches fired per bucket.
+    pub prefetch: Vec<u64>,
+}
+
+impl HeatmapSnapshot {
+    /// LBAs bucket `i` covers.
+    pub fn bucket_range(&self, i: usize) -> Range<u64> {
+        let first = self
+            .start
+            .saturating_add(self.bucket_width.saturating_mul(i as u64));
+        first.min(self.end)..first.saturating_add(self.bucket_width).min(self.end)
+    }
+
+    /// Write one CSV row per bucket under a
+    /// `bucket,first_lba,last_lba,demand,prefetch` header.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_heatmap(0..1_000, 4);
+    /// for i in 0..100u64 {
+    ///     kernel.push_lba(i);
+    /// }
+    /// let mut csv = Vec::new();
+    /// kernel.heatmap().unwrap().to_csv(&mut csv).unwrap();
+    /// let csv = String::from_utf8(csv).unwrap();
+    /// assert!(csv.starts_with("bucket,first_lba,last_lba,demand,prefetch\n0,0,249,99,"));
+    /// assert_eq!(csv.lines().count(), 5);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_csv<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "bucket,first_lba,last_lba,demand,prefetch")?;
+        for (i, (demand, prefetch)) in self.demand.iter().zip(&self.prefetch).enumerate() {
+            let range = self.bucket_range(i);
+            let last = range.end.saturating_sub(1).max(range.start);
+            writeln!(w, "{i},{},{last},{demand},{prefetch}", range.start)?;
+        }
+        Ok(())
+    }
+}
+
+impl AetherLinkKernel {
+    /// Count accesses and prefetches over `range` in buckets of
+    /// `⌈len / buckets⌉` blocks, replacing any earlier heatmap.  The last
+    /// bucket may be shorter, and fewer than `buckets` (at least 1) are
+    /// used when that is enough to cover the range.  An empty range is
+    /// widened to one block.
+    pub fn enable_heatmap(&mut self, range: Range<u64>, buckets: usize) {
+        let start = range.start;
+        let end = range.end.max(start.saturating_add(1));
+        let span = end - start;
+        let ceil_div = |a: u64, b: u64| a / b + (a % b != 0) as u64;
+        let width = ceil_div(span, (buckets as u64).max(1));
+        let buckets = ceil_div(span, width) as usize;
+        self.heatmap = Some(Heatmap {
+            start,
+            end,
+            width,
+            demand: vec![0; buckets],
+            prefetch: vec![0; buckets],
+            next: 0,
+            placed: None,
+        });
+    }
+
+    /// Count this cycle's prefetch where `decision` targets, and pass it on.
+    #[inline(always)]
+    pub(crate) fn heatmap_placed(&mut self, decision: PrefetchDecision) -> PrefetchDecision {
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.place(&decision);
+        }
+        decision
+    }
+
+    /// Stop counting and drop the heatmap.
+    #[inline]
+    pub fn disable_heatmap(&mut self) {
+        self.heatmap = None;
+    }
+
+    /// The counts so far, if the heatmap is enabled.
+    pub fn heatmap(&self) -> Option<HeatmapSnapshot> {
+        self.heatmap.as_ref().map(|h| HeatmapSnapshot {
+            start: h.start,
+            end: h.end,
+            bucket_width: h.width,
+            demand: h.demand.clone(),
+            prefetch: h.prefetch.clone(),
+        })
+    }
+
+    /// Zero the heatmap counts, keeping its range.
+    pub fn reset_heatmap(&mut self) {
+        if let Some(h) = &mut self.heatmap {
+            h.demand.fill(0);
+            h.prefetch.fill(0);
+        }
+    }
+
+    /// Halve every heatmap count `shift` times (a right shift).
+    pub fn decay_heatmap(&mut self, shift: u32) {
+        if let Some(h) = &mut self.heatmap {
+            for count in h.demand.iter_mut().chain(&mut h.prefetch) {
+                *count = count.checked_shr(shift).unwrap_or(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heatmap(range: Range<u64>, buckets: usize) -> Heatmap {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_heatmap(range, buckets);
+        kernel.heatmap.unwrap()
+    }
+
+    #[test]
+    fn test_bucket_boundaries() {
+        // Even split, uneven split (last bucket shorter) and the full space.
+        for (range, buckets) in [(1_000..2_000, 10), (0..1_001, 10), (0..u64::MAX, 64)] {
+            let h = heatmap(range.clone(), buckets);
+            let last = buckets - 1;
+            assert_eq!(h.bucket(range.start), 0);
+            assert_eq!(h.bucket(range.end - 1), last);
+            assert_eq!(h.bucket(0), 0, "below clamps");
+            assert_eq!(h.bucket(u64::MAX), last, "above clamps");
+            for i in 0..buckets as u64 {
+                let first = range.start + i * h.width;
+                assert_eq!(h.bucket(first), i as usize);
+                if i > 0 {
+                    assert_eq!(h.bucket(first - 1), i as usize - 1);
+                }
+            }
+        }
+        assert_eq!(heatmap(1_000..2_000, 10).width, 100);
+        assert_eq!(heatmap(0..1_001, 10).width, 101);
+        // More buckets than blocks, too many to cover exactly, and an
+        // empty range.
+        assert_eq!(heatmap(0..3, 10).demand.len(), 3);
+        let h = heatmap(0..11, 10);
+        assert_eq!((h.width, h.demand.len(), h.bucket(10)), (2, 6, 5));
+        let h = heatmap(5..5, 0);
+        assert_eq!((h.end, h.demand.len(), h.bucket(5)), (6, 1, 0));
+    }
+
+    #[test]
+    fn test_prefetches_count_where_the_plan_starts() {
+        for horizon in [None, Some(8)] {
+            let mut kernel = AetherLinkKernel::new_gaming();
+            if let Some(n) = horizon {
+                kernel.set_horizon(n);
+            }
+            kernel.enable_heatmap(0..4_000, 40);
+            // Plans wholly below block 1_000 are dropped.
+            kernel.set_address_range(1_000, u64::MAX);
+            let mut expected = vec![0u64; 40];
+            for lba in (1_000..3_000u64).rev() {
+                match kernel.push_lba(lba) {
+                    Some(d) if d.fire => {
+                        let start = d.target.map_or(lba + 1, |plan| plan.start);
+                        expected[(start / 100) as usize] += 1;
+                    }
+                    _ => {}
+                }
+            }
+            let snap = kernel.heatmap().unwrap();
+            assert_eq!(snap.prefetch, expected, "{horizon:?}");
+            assert!(snap.prefetch[10] > 0, "{horizon:?}: {snap:?}");
+            assert!(kernel.stats().out_of_range > 0, "{horizon:?}");
+            assert!(snap.prefetch.iter().sum::<u64>() < kernel.stats().prefetches);
+        }
+    }
+
+    #[test]
+    fn test_counts_csv_and_decay() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.enable_heatmap(0..4_000, 4);
+        for i in 0..500u64 {
+            kernel.push_lba(1_000 + i);
+        }
+        kernel.push_lba(1 << 40);
+        let snap = kernel.heatmap().unwrap();
+        assert_eq!(snap.demand, [0, 499, 0, 1]);
+        assert_eq!(snap.prefetch[0] + snap.prefetch[2], 0);
+        assert!(snap.prefetch[1] > 400, "{snap:?}");
+        assert_eq!(snap.bucket_range(3), 3_000..4_000);
+
+        let mut csv = Vec::new();
+        snap.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|r| r.len() == 5), "{csv}");
+        assert_eq!(rows[2][..4], ["1", "1000", "1999", "499"]);
+        assert_eq!(rows[4][..4], ["3", "3000", "3999", "1"]);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&snap).unwrap();
+            assert!(json.contains(r#""bucket_width":1000"#), "{json}");
+            let back: HeatmapSnapshot = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, snap);
+        }
+
+        // Only the heatmap's own calls clear it.
+        kernel.reset_stats();
+        assert_eq!(kernel.heatmap().unwrap().demand, [0, 499, 0, 1]);
+        kernel.decay_heatmap(1);
+        assert_eq!(kernel.heatmap().unwrap().demand, [0, 249, 0, 0]);
+        kernel.decay_heatmap(64);
+        assert_eq!(kernel.heatmap().unwrap().demand, [0; 4]);
+        kernel.push_lba(3_500);
+        kernel.reset_heatmap();
+        assert_eq!(
+            kernel.heatmap().unwrap(),
+            HeatmapSnapshot {
+                prefetch: vec![0; 4],
+                demand: vec![0; 4],
+                ..snap
+            }
+        );
+        kernel.disable_heatmap();
+        assert!(kernel.heatmap().is_none());
+    }
+}
@@ -0,0 +1,265 @@
+//! Caller hints: biasing a single decision.
+//!
+//! The caller sometimes knows more than the telemetry: a read that belongs
+//! to a readahead the application already issued gains nothing from a
+//! second prefetch, one on a critical path is worth a generous one.
+//! [`AetherLinkKernel::process_io_cycle_hinted`] takes that knowledge as a
+//! [`Hint`] applied after `p_fetch` is computed:
+//!
+//! - [`Boost`](Hint::Boost) and [`Suppress`](Hint::Suppress) lower or raise
+//!   the threshold for the cycle by the amounts set with
+//!   [`set_hint_shifts`](AetherLinkKernel::set_hint_shifts), as a
+//!   [QoS shift](AetherLinkKernel::process_io_cycle_qos) does;
+//! - [`Force`](Hint::Force) and [`Forbid`](Hint::Forbid) override the
+//!   outcome outright.
+//!
+//! None of them reaches the learned state.  ε and φ adapt as they always
+//! do, and the kernel's counters, horizon, heatmap and every other stage
+//! see the decision the cycle would have made without an override; the
+//! overrides are counted apart in [`HintStats`].  A stream served with
+//! any mix of hints leaves the kernel where an unhinted one would.
+
+use crate::AetherLinkKernel;
+
+/// Threshold shift of [`Hint::Boost`] and [`Hint::Suppress`] until
+/// [`set_hint_shifts`](AetherLinkKernel::set_hint_shifts) says otherwise.
+pub const DEFAULT_HINT_SHIFT: f32 = 0.1;
+
+/// What the caller knows about one request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Hint {
+    /// Nothing: the cycle decides as [`process_io_cycle`](AetherLinkKernel::process_io_cycle).
+    #[default]
+    Neutral,
+    /// Worth prefetching for: the threshold drops by the boost shift.
+    Boost,
+    /// Probably covered already: the threshold rises by the suppress shift.
+    Suppress,
+    /// Prefetch whatever the cycle decides.
+    Force,
+    /// Never prefetch, whatever the cycle decides.
+    Forbid,
+}
+
+/// Hinted cycles since the kernel was built or the stats were reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HintStats {
+    /// Cycles run with [`Hint::Boost`].
+    pub boosted: u64,
+    /// Cycles run with [`Hint::Suppress`].
+    pub suppressed: u64,
+    /// Cycles run with [`Hint::Force`].
+    pub forced: u64,
+    /// Cycles run with [`Hint::Forbid`].
+    pub forbidden: u64,
+    /// Forced or forbidden cycles whose outcome the override changed.
+    pub overridden: u64,
+}
+
+/// Configured shifts and the counters.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Hints {
+    boost: f32,
+    suppress: f32,
+    stats: HintStats,
+}
+
+impl Default for Hints {
+    fn default() -> Self {
+        Self {
+            boost: DEFAULT_HINT_SHIFT,
+            suppress: DEFAULT_HINT_SHIFT,
+            stats: HintStats::default(),
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Execute one decision cycle under a caller [`Hint`].
+    ///
+    /// Returns whether to prefetch.  For [`Hint::Force`] and
+    /// [`Hint::Forbid`] that is the override, while the kernel adapts and
+    /// counts the cycle as an unhinted one; see [`hint`](crate::hint).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::hint::Hint;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// assert!(kernel.process_io_cycle_hinted(&[0, 1_000_000], Hint::Force));
+    /// assert!(!kernel.process_io_cycle_hinted(&[10, 11, 12, 13], Hint::Forbid));
+    /// assert_eq!(kernel.hint_stats().forced, 1);
+    /// ```
+    #[inline]
+    pub fn process_io_cycle_hinted(&mut self, lba_stream: &[u64], hint: Hint) -> bool {
+        let shift = match hint {
+            Hint::Boost => -self.hints.boost,
+            Hint::Suppress => self.hints.suppress,
+            Hint::Neutral | Hint::Force | Hint::Forbid => 0.0,
+        };
+        let fetch = self.process_io_cycle_qos(lba_stream, shift).fetch;
+        let stats = &mut self.hints.stats;
+        match hint {
+            Hint::Neutral => fetch,
+            Hint::Boost => {
+                stats.boosted += 1;
+                fetch
+            }
+            Hint::Suppress => {
+                stats.suppressed += 1;
+                fetch
+            }
+            Hint::Force => {
+                stats.forced += 1;
+                stats.overridden += !fetch as u64;
+                true
+            }
+            Hint::Forbid => {
+                stats.forbidden += 1;
+                stats.overridden += fetch as u64;
+                false
+            }
+        }
+    }
+
+    /// Set how far [`Hint::Boost`] lowers and [`Hint::Suppress`] raises the
+    /// threshold (both [`DEFAULT_HINT_SHIFT`] by default).  Negative or
+    /// NaN shifts count as 0.
+    pub fn set_hint_shifts(&mut self, boost: f32, suppress: f32) {
+        self.hints.boost = boost.max(0.0);
+        self.hints.suppress = suppress.max(0.0);
+    }
+
+    /// Boost and suppress shifts, as set with
+    /// [`set_hint_shifts`](Self::set_hint_shifts).
+    #[inline]
+    pub fn hint_shifts(&self) -> (f32, f32) {
+        (self.hints.boost, self.hints.suppress)
+    }
+
+    /// Counters of hinted cycles.
+    #[inline]
+    pub fn hint_stats(&self) -> HintStats {
+        self.hints.stats
+    }
+
+    /// Zero the hint counters, keeping the shifts.
+    pub(crate) fn reset_hint_stats(&mut self) {
+        self.hints.stats = HintStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    /// Windows mixing short runs and jumps, so that `p_fetch` lands on
+    /// both sides of the threshold.
+    fn windows(seed: u64, n: usize) -> Vec<[u64; 4]> {
+        let mut rng = SplitMix64::new(seed);
+        (0..n)
+            .map(|_| {
+                let base = rng.below(1 << 30);
+                let scale = rng.below(20) as u32;
+                let step = 1 + rng.below(1 << scale);
+                [base, base + step, base + 2 * step, base + 3 * step]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_each_hint_moves_the_outcome_its_way() {
+        let mut kernels = [(); 5].map(|_| AetherLinkKernel::new_database());
+        let hints = [
+            Hint::Neutral,
+            Hint::Boost,
+            Hint::Suppress,
+            Hint::Force,
+            Hint::Forbid,
+        ];
+        let mut fired = [0u64; 5];
+        for window in windows(7, 5_000) {
+            let out: Vec<bool> = kernels
+                .iter_mut()
+                .zip(hints)
+                .map(|(kernel, hint)| kernel.process_io_cycle_hinted(&window, hint))
+                .collect();
+            // Hints never change the learned state, so the kernels stay in
+            // step and each cycle can be compared across them.
+            assert!(out[0] <= out[1] && out[2] <= out[0], "{out:?}");
+            assert!(out[3] && !out[4]);
+            for (n, fetch) in fired.iter_mut().zip(out) {
+                *n += fetch as u64;
+            }
+        }
+        assert!(fired[1] > fired[0] && fired[0] > fired[2], "{fired:?}");
+        assert!(fired[2] > 0, "{fired:?}");
+        assert_eq!((fired[3], fired[4]), (5_000, 0));
+
+        // Shifted decisions are the kernel's own; overridden ones count as
+        // the unhinted decision, and the overrides apart.
+        let prefetches: Vec<u64> = kernels.iter().map(|k| k.prefetches).collect();
+        assert_eq!(
+            prefetches,
+            [fired[0], fired[1], fired[2], fired[0], fired[0]]
+        );
+        assert_eq!(kernels[1].hint_stats().boosted, 5_000);
+        assert_eq!(kernels[2].hint_stats().suppressed, 5_000);
+        let (forced, forbidden) = (kernels[3].hint_stats(), kernels[4].hint_stats());
+        assert_eq!(
+            (forced.forced, forced.overridden),
+            (5_000, 5_000 - fired[0])
+        );
+        assert_eq!(
+            (forbidden.forbidden, forbidden.overridden),
+            (5_000, fired[0])
+        );
+        assert_eq!(kernels[0].hint_stats(), HintStats::default());
+
+        // Narrower shifts move fewer decisions.
+        let mut narrow = AetherLinkKernel::new_database();
+        narrow.set_hint_shifts(0.01, f32::NAN);
+        assert_eq!(narrow.hint_shifts(), (0.01, 0.0));
+        let boosted = windows(7, 5_000)
+            .iter()
+            .filter(|w| narrow.process_io_cycle_hinted(&w[..], Hint::Boost))
+            .count() as u64;
+        assert!(
+            fired[0] < boosted && boosted < fired[1],
+            "{boosted} vs {fired:?}"
+        );
+        narrow.reset_stats();
+        assert_eq!(narrow.hint_stats(), HintStats::default());
+    }
+
+    #[test]
+    fn test_overrides_leave_the_trajectory_untouched() {
+        let mut rng = SplitMix64::new(11);
+        let overrides = [Hint::Neutral, Hint::Force, Hint::Forbid];
+        for mut reference in [
+            AetherLinkKernel::default(),
+            AetherLinkKernel::new_database(),
+            AetherLinkKernel::new_gaming(),
+        ] {
+            reference.set_horizon(8);
+            reference.enable_heatmap(0..1 << 30, 64);
+            let mut hinted = reference.clone();
+            for window in windows(13, 20_000) {
+                let hint = overrides[rng.below(3) as usize];
+                hinted.process_io_cycle_hinted(&window, hint);
+                reference.process_io_cycle(&window);
+                assert_eq!(hinted.epsilon.to_bits(), reference.epsilon.to_bits());
+                assert_eq!(hinted.phi.to_bits(), reference.phi.to_bits());
+            }
+            assert_eq!(hinted.stats(), reference.stats());
+            assert_eq!(hinted.horizon_stats(), reference.horizon_stats());
+            let s = hinted.hint_stats();
+            assert!(s.forced > 6_000 && s.forbidden > 6_000 && s.overridden > 0);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Distribution of the fetch probability.
+//!
+//! The trigger count says how often `p_fetch` cleared ε, not where the
+//! probabilities sit, which is what choosing a threshold needs.  With
+//! [`AetherLinkKernel::enable_p_fetch_histogram`] every cycle adds its
+//! `p_fetch` to one of [`P_FETCH_BUCKETS`] equal buckets over [0, 1] — a
+//! multiply, a saturating cast and an increment.  Disabled, the decision
+//! path skips it entirely.  [`AetherLinkKernel::p_fetch_quantile`]
+//! interpolates linearly within the bucket holding the quantile, so its
+//! error is at most one bucket width.
+
+use crate::AetherLinkKernel;
+
+/// Buckets over [0, 1]; bucket `b` holds `[b/32, (b+1)/32)`, the last one
+/// includes 1.
+pub const P_FETCH_BUCKETS: usize = 32;
+
+const EMPTY: [u64; P_FETCH_BUCKETS] = [0; P_FETCH_BUCKETS];
+
+/// Bucket counts of the fetch probabilities seen.
+#[derive(Debug, Clone)]
+pub(crate) struct PFetchHistogram {
+    buckets: [u64; P_FETCH_BUCKETS],
+    count: u64,
+}
+
+impl PFetchHistogram {
+    /// Count one cycle's probability.  Values below 0 and NaN land in the
+    /// first bucket, values of 1 and above in the last.
+    #[inline(always)]
+    pub(crate) fn record(&mut self, p_fetch: f32) {
+        let b = ((p_fetch * P_FETCH_BUCKETS as f32) as usize).min(P_FETCH_BUCKETS - 1);
+        self.buckets[b] += 1;
+        self.count += 1;
+    }
+
+    fn quantile(&self, q: f32) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let q = if q.is_nan() { 0.0 } else { q.clamp(0.0, 1.0) };
+        let target = q as f64 * self.count as f64;
+        let mut below = 0;
+        for (b, &n) in self.buckets.iter().enumerate() {
+            if n > 0 && (below + n) as f64 >= target {
+                let within = ((target - below as f64) / n as f64).max(0.0);
+                return (b as f64 + within) as f32 / P_FETCH_BUCKETS as f32;
+            }
+            below += n;
+        }
+        1.0
+    }
+}
+
+impl AetherLinkKernel {
+    /// Count every following cycle's `p_fetch` into a histogram of
+    /// [`P_FETCH_BUCKETS`] buckets, replacing any earlier counts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// kernel.enable_p_fetch_histogram();
+    /// for i in 0..1_000u64 {
+    ///     kernel.process_io_cycle(&[i, i + 1, i + 2]);
+    /// }
+    /// assert_eq!(kernel.p_fetch_histogram().iter().sum::<u64>(), 1_000);
+    /// let median = kernel.p_fetch_quantile(0.5);
+    /// assert!(median <= kernel.p_fetch_quantile(0.99));
+    /// ```
+    pub fn enable_p_fetch_histogram(&mut self) {
+        self.p_fetch_histogram = Some(PFetchHistogram {
+            buckets: EMPTY,
+            count: 0,
+        });
+    }
+
+    /// Stop counting and drop the histogram.
+    #[inline]
+    pub fn disable_p_fetch_histogram(&mut self) {
+        self.p_fetch_histogram = None;
+    }
+
+    /// Per-bucket counts since the histogram was enabled or the stats were
+    /// reset; all zero while disabled.
+    #[inline]
+    pub fn p_fetch_histogram(&self) -> &[u64; P_FETCH_BUCKETS] {
+        self.p_fetch_histogram
+            .as_ref()
+            .map_or(&EMPTY, |h| &h.buckets)
+    }
+
+    /// The `q`-quantile (`q` in [0, 1]) of the counted `p_fetch` values,
+    /// interpolated within its bucket; 0 with nothing counted.
+    pub fn p_fetch_quantile(&self, q: f32) -> f32 {
+        self.p_fetch_histogram
+            .as_ref()
+            .map_or(0.0, |h| h.quantile(q))
+    }
+
+    /// Zero the histogram, keeping it enabled.
+    pub(crate) fn reset_p_fetch_histogram(&mut self) {
+        if self.p_fetch_histogram.is_some() {
+            self.enable_p_fetch_histogram();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A kernel whose `p_fetch` is σ(−bias) whatever the input: λ₃ = 0
+    /// removes the spectral term.
+    fn fixed(p: f32) -> AetherLinkKernel {
+        let bias = -(p / (1.0 - p)).ln();
+        let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.1, 0.2, 0.0], bias);
+        kernel.enable_p_fetch_histogram();
+        kernel
+    }
+
+    fn centre(b: usize) -> f32 {
+        (b as f32 + 0.5) / P_FETCH_BUCKETS as f32
+    }
+
+    #[test]
+    fn test_bucket_placement() {
+        for b in [0, 5, 16, 31] {
+            let mut kernel = fixed(centre(b));
+            for i in 0..100u64 {
+                let out = kernel.process_io_cycle_qos(&[i, i * 7 + 3], 0.0);
+                assert!((out.p_fetch - centre(b)).abs() < 0.25 / 32.0, "{out:?}");
+            }
+            let hist = kernel.p_fetch_histogram();
+            assert_eq!(hist[b], 100, "bucket {b}: {hist:?}");
+            assert_eq!(hist.iter().sum::<u64>(), 100);
+        }
+
+        let mut edges = fixed(0.5);
+        for p in [-1.0, f32::NAN, 0.0, 1.0, 2.0, f32::INFINITY] {
+            edges.p_fetch_histogram.as_mut().unwrap().record(p);
+        }
+        let hist = edges.p_fetch_histogram();
+        assert_eq!((hist[0], hist[31]), (3, 3));
+    }
+
+    #[test]
+    fn test_quantile_interpolates_within_buckets() {
+        let mut kernel = AetherLinkKernel::default();
+        assert_eq!(kernel.p_fetch_quantile(0.5), 0.0, "disabled");
+        assert_eq!(kernel.p_fetch_histogram(), &[0; P_FETCH_BUCKETS]);
+        kernel.enable_p_fetch_histogram();
+        assert_eq!(kernel.p_fetch_quantile(0.5), 0.0, "empty");
+
+        // Half the cycles in bucket 8, half in bucket 24.
+        let mut low = fixed(centre(8));
+        let mut high = fixed(centre(24));
+        for i in 0..400u64 {
+            low.process_io_cycle(&[i, i + 1]);
+            high.process_io_cycle(&[i, i + 1]);
+        }
+        let mut both = low.p_fetch_histogram.clone().unwrap();
+        for (b, n) in both.buckets.iter_mut().zip(high.p_fetch_histogram()) {
+            *b += n;
+        }
+        both.count += 400;
+        let w = 1.0 / P_FETCH_BUCKETS as f32;
+        for (q, expected) in [
+            (0.0, 8.0 * w),
+            (0.25, 8.5 * w),
+            (0.5, 9.0 * w),
+            (0.75, 24.5 * w),
+            (1.0, 25.0 * w),
+            (f32::NAN, 8.0 * w),
+        ] {
+            assert!((both.quantile(q) - expected).abs() < 1e-6, "q {q}");
+        }
+
+        low.reset_stats();
+        assert_eq!(low.p_fetch_histogram(), &[0; P_FETCH_BUCKETS]);
+        low.process_io_cycle(&[0, 1]);
+        assert_eq!(low.p_fetch_histogram()[8], 1, "still enabled");
+        low.disable_p_fetch_histogram();
+        assert_eq!(low.p_fetch_quantile(1.0), 0.0);
+    }
+}
@@ -0,0 +1,331 @@
+//! Prediction horizon: one plan for the next `n` cycles.
+//!
+//! By default every cycle is a fresh decision, so a sequential stream
+//! triggers on nearly every access.  With
+//! [`AetherLinkKernel::set_horizon`] a positive decision instead plans an
+//! extent sized for the movement expected over the next `n` cycles — the
+//! mean per-cycle step of the newest access (an exponentially weighted
+//! average) times `n`, clamped to `1..=`[`MAX_HORIZON_EXTENT`] blocks,
+//! starting after the triggering access, or ending just below it when the
+//! average step is negative (see [`direction`]).  A step longer than the
+//! largest plan is a jump, not movement: it is left out of the average,
+//! and the step after it starts a fresh one.  A repeat of the previous
+//! access is no step at all and is left out too (see
+//! [`degenerate`](crate::degenerate)).  While later accesses land inside
+//! that plan, triggers are withheld.  An access outside it invalidates the
+//! plan, and the cycle whose access leaves less than one step of the plan
+//! consumes it; either way that same cycle decides afresh, so a stream
+//! that keeps moving gets its next plan without a gap.
+//!
+//! The access of a cycle is the newest LBA of its window.  The active plan
+//! is [`AetherLinkKernel::horizon_plan`], also returned as the target of
+//! firing [`push_lba`](AetherLinkKernel::push_lba) decisions, and
+//! [`HorizonStats`] counts how many accesses the plans covered.
+
+use crate::fast_math::float;
+use crate::prefetcher::PrefetchPlan;
+use crate::{bounds, direction, AetherLinkKernel};
+
+/// Largest extent a horizon plan covers, in blocks.
+pub const MAX_HORIZON_EXTENT: u64 = 4096;
+
+/// Weight of the newest step in the per-cycle movement estimate.
+const VELOCITY_ALPHA: f32 = 0.25;
+
+/// Plan coverage counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HorizonStats {
+    /// Accesses seen with the horizon on.
+    pub accesses: u64,
+    /// Accesses that landed inside an active plan.
+    pub covered: u64,
+    /// Plans emitted.
+    pub plans: u64,
+    /// Plans dropped because an access fell outside them.
+    pub invalidated: u64,
+}
+
+impl HorizonStats {
+    /// Fraction of accesses inside an active plan (0 with no accesses).
+    pub fn coverage(&self) -> f64 {
+        if self.accesses == 0 {
+            0.0
+        } else {
+            self.covered as f64 / self.accesses as f64
+        }
+    }
+}
+
+/// Horizon length, movement estimate and the active plan.
+#[derive(Debug, Clone)]
+pub(crate) struct Horizon {
+    cycles: u32,
+    /// Newest access of the previous cycle.
+    prev: Option<u64>,
+    /// Smoothed signed step between consecutive cycles' accesses.
+    velocity: f32,
+    /// Whether `velocity` holds a step since the last jump.
+    seeded: bool,
+    plan: Option<PrefetchPlan>,
+    /// Whether `plan` lies below the access that made it.
+    descending: bool,
+    /// Access that made `plan`, and the step it was sized with.
+    anchor: u64,
+    stride: u64,
+    /// Whether the cycle in progress landed inside the plan.
+    quiet: bool,
+    stats: HorizonStats,
+}
+
+impl Horizon {
+    /// Account for the cycle's access, before the decision.
+    #[inline]
+    pub(crate) fn observe(&mut self, lba: u64) {
+        if let Some(prev) = self.prev {
+            if lba.abs_diff(prev) > MAX_HORIZON_EXTENT {
+                self.seeded = false;
+            } else if lba == prev {
+                // A re-read: the stream's pace is that of its moves.
+            } else if self.seeded {
+                let step = lba.wrapping_sub(prev) as i64 as f32;
+                self.velocity += VELOCITY_ALPHA * (step - self.velocity);
+            } else {
+                self.velocity = lba.wrapping_sub(prev) as i64 as f32;
+                self.seeded = true;
+            }
+        }
+        self.prev = Some(lba);
+        self.stats.accesses += 1;
+        self.quiet = false;
+        let Some(plan) = self.plan else {
+            return;
+        };
+        if lba < plan.start || lba >= plan.end() {
+            self.plan = None;
+            self.stats.invalidated += 1;
+            return;
+        }
+        self.stats.covered += 1;
+        let consumed = if self.descending {
+            lba < plan.start.saturating_add(self.step())
+        } else {
+            lba.saturating_add(self.step()) >= plan.end()
+        };
+        if consumed {
+            self.plan = None;
+        } else {
+            self.quiet = true;
+        }
+    }
+
+    /// Whether the cycle in progress must not trigger.
+    #[inline]
+    pub(crate) fn suppresses(&self) -> bool {
+        self.quiet
+    }
+
+    /// Record the cycle's decision, planning ahead when it fired with an
+    /// extent of at most `cap` blocks trimmed to `bounds`.  The plan goes
+    /// below the access when the movement estimate is negative, or, before
+    /// there is one, when the window is `descending`.  Returns `true` if
+    /// the plan fell outside `bounds` and was dropped.
+    #[inline]
+    pub(crate) fn decided(
+        &mut self,
+        fetch: bool,
+        cap: u64,
+        bounds: (u64, u64),
+        descending: bool,
+    ) -> bool {
+        if !fetch {
+            return false;
+        }
+        let Some(lba) = self.prev else {
+            return false;
+        };
+        self.anchor = lba;
+        self.stride = self.step();
+        let extent = float::ceil(self.stride as f32 * self.cycles as f32) as u64;
+        let extent = extent.clamp(1, MAX_HORIZON_EXTENT.min(cap).max(1));
+        self.descending = if self.velocity == 0.0 {
+            descending
+        } else {
+            self.velocity < 0.0
+        };
+        let plan = if self.descending {
+            direction::below(lba, extent)
+        } else {
+            PrefetchPlan::new(lba.saturating_add(1), extent)
+        };
+        self.plan = bounds::clamp(plan, bounds);
+        self.stats.plans += self.plan.is_some() as u64;
+        self.plan.is_none()
+    }
+
+    /// The active plan with the access that made it, the step it was
+    /// sized with and whether it lies below the access.
+    #[inline]
+    pub(crate) fn planned(&self) -> Option<(PrefetchPlan, u64, u64, bool)> {
+        self.plan
+            .map(|plan| (plan, self.anchor, self.stride, self.descending))
+    }
+
+    /// Expected forward movement per cycle, at least one block.
+    #[inline]
+    fn step(&self) -> u64 {
+        (float::ceil(self.velocity.abs()) as u64).max(1)
+    }
+}
+
+impl AetherLinkKernel {
+    /// Plan `n_cycles` (at least 1) ahead on each trigger and stay quiet
+    /// while accesses stay inside the plan.  Starts with no plan and fresh
+    /// stats.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_horizon(8);
+    /// let triggers = (0..1_000u64)
+    ///     .filter_map(|lba| kernel.push_lba(lba))
+    ///     .filter(|d| d.fire)
+    ///     .count();
+    /// assert!(triggers < 200);
+    /// assert!(kernel.horizon_stats().coverage() > 0.8);
+    /// ```
+    pub fn set_horizon(&mut self, n_cycles: u32) {
+        self.horizon = Some(Horizon {
+            cycles: n_cycles.max(1),
+            prev: None,
+            velocity: 0.0,
+            seeded: false,
+            plan: None,
+            descending: false,
+            anchor: 0,
+            stride: 1,
+            quiet: false,
+            stats: HorizonStats::default(),
+        });
+    }
+
+    /// Back to a fresh decision on every cycle.
+    #[inline]
+    pub fn clear_horizon(&mut self) {
+        self.horizon = None;
+    }
+
+    /// Cycles planned ahead, if the horizon is on.
+    #[inline]
+    pub fn horizon(&self) -> Option<u32> {
+        self.horizon.as_ref().map(|h| h.cycles)
+    }
+
+    /// The plan currently covering upcoming accesses.
+    #[inline]
+    pub fn horizon_plan(&self) -> Option<PrefetchPlan> {
+        self.horizon.as_ref().and_then(|h| h.plan)
+    }
+
+    /// Coverage counters since the horizon was set or the stats reset;
+    /// all zero with the horizon off.
+    #[inline]
+    pub fn horizon_stats(&self) -> HorizonStats {
+        self.horizon
+            .as_ref()
+            .map_or_else(HorizonStats::default, |h| h.stats)
+    }
+
+    /// Zero the coverage counters, keeping the active plan.
+    pub(crate) fn reset_horizon_stats(&mut self) {
+        if let Some(h) = &mut self.horizon {
+            h.stats = HorizonStats::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::direction::LOOKAHEAD_BLOCKS;
+    use crate::rng::SplitMix64;
+
+    /// Triggers with and without a horizon of `n`, and the coverage with.
+    fn run(lbas: &[u64], n: u32) -> (usize, usize, f64) {
+        let triggers = |kernel: &mut AetherLinkKernel| {
+            lbas.iter()
+                .filter_map(|&lba| kernel.push_lba(lba))
+                .filter(|d| d.fire)
+                .count()
+        };
+        let mut plain = AetherLinkKernel::default();
+        let mut planned = AetherLinkKernel::default();
+        planned.set_horizon(n);
+        let base = triggers(&mut plain);
+        let with = triggers(&mut planned);
+        (base, with, planned.horizon_stats().coverage())
+    }
+
+    #[test]
+    fn test_triggers_drop_by_horizon_on_sequential_and_strided() {
+        // Strides the default preset fires on without a horizon.
+        for stride in [1u64, 2, 16, 64] {
+            let lbas: Vec<u64> = (0..20_000).map(|i| 1_000 + i * stride).collect();
+            for n in [4u32, 8, 32] {
+                let (base, with, coverage) = run(&lbas, n);
+                let ratio = base as f64 / with.max(1) as f64;
+                assert!(base > 10_000, "stride {stride}: {base}");
+                assert!(
+                    ratio > 0.8 * n as f64 && ratio < 1.2 * n as f64,
+                    "stride {stride}, n {n}: {base} → {with}"
+                );
+                assert!(coverage > 0.9, "stride {stride}, n {n}: {coverage}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_extent_and_target() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(10);
+        let fired = (0..100u64)
+            .map(|i| i * 4)
+            .filter_map(|lba| kernel.push_lba(lba).map(|d| (lba, d)))
+            .find(|(_, d)| d.fire)
+            .unwrap();
+        assert_eq!(fired.1.target, Some(PrefetchPlan::new(fired.0 + 1, 40)));
+        assert_eq!(kernel.horizon_plan(), fired.1.target);
+    }
+
+    #[test]
+    fn test_access_outside_plan_rearms() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_horizon(16);
+        let mut rng = SplitMix64::new(2);
+        for lba in 0..50 {
+            kernel.push_lba(lba);
+        }
+        assert!(kernel.horizon_plan().is_some());
+        let before = kernel.horizon_stats();
+        kernel.push_lba(rng.below(1 << 30) + (1 << 20));
+        let after = kernel.horizon_stats();
+        assert_eq!(after.invalidated, before.invalidated + 1);
+        assert_eq!(after.covered, before.covered);
+        // The jump's own cycle decided afresh: any plan now starts past it.
+        if let Some(plan) = kernel.horizon_plan() {
+            assert!(plan.start > 1 << 20);
+        }
+
+        kernel.reset_stats();
+        assert_eq!(kernel.horizon_stats(), HorizonStats::default());
+        kernel.clear_horizon();
+        assert_eq!(kernel.horizon(), None);
+        // Without a horizon a jump fires for the lookahead past it.
+        let decision = kernel.push_lba(1 << 40).unwrap();
+        assert_eq!(kernel.horizon_plan(), None);
+        let plan = decision.target.unwrap();
+        assert_eq!((plan.start, plan.len), ((1 << 40) + 1, LOOKAHEAD_BLOCKS));
+    }
+}
@@ -0,0 +1,192 @@
+//! Decay of the learned state while the kernel sits idle.
+//!
+//! ε and φ track whatever the kernel saw last.  After a long quiet period
+//! (overnight, between jobs) that is usually the wrong starting point, so
+//! with [`AetherLinkKernel::set_idle_decay`] every reported gap pulls both
+//! back towards a prior by `1 − 2^(−gap / half_life)` of the remaining
+//! distance.  The factor is computed in closed form, so one gap of `a + b`
+//! lands where two gaps of `a` and `b` would, whatever the lengths.
+//!
+//! Gaps are reported either explicitly with [`AetherLinkKernel::tick`] or
+//! implicitly by timestamping cycles with
+//! [`AetherLinkKernel::process_io_cycle_at`].  The unit is the caller's —
+//! cycles, nanoseconds — as long as the half-life uses the same one.
+
+use crate::fast_math::float;
+use crate::snapshot::KernelSnapshot;
+use crate::{threshold, AetherLinkKernel};
+use core::f32::consts::PI;
+
+/// Idle-decay settings and the last timestamp seen.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IdleDecay {
+    half_life: u64,
+    prior: KernelSnapshot,
+    last: Option<u64>,
+}
+
+impl AetherLinkKernel {
+    /// Pull ε and φ towards `prior` over idle gaps, halving the distance
+    /// every `half_life` units of idle time.  Only the prior's ε and φ are
+    /// used.
+    ///
+    /// # Panics
+    ///
+    /// If `half_life` is 0.
+    pub fn set_idle_decay(&mut self, half_life: u64, prior: KernelSnapshot) {
+        assert!(half_life > 0, "idle half-life must be non-zero");
+        self.idle_decay = Some(IdleDecay {
+            half_life,
+            prior,
+            last: None,
+        });
+    }
+
+    /// Turn idle decay off.
+    #[inline]
+    pub fn clear_idle_decay(&mut self) {
+        self.idle_decay = None;
+    }
+
+    /// Report `elapsed` units of idle time: decays ε and φ under
+    /// [`set_idle_decay`](Self::set_idle_decay), and a long enough gap
+    /// sends the [mode](Self::mode) back to Idle.
+    pub fn tick(&mut self, elapsed: u64) {
+        self.mode.idle(self.cycles, elapsed);
+        self.decay(elapsed);
+    }
+
+    /// Pull ε and φ towards the prior over `elapsed` units of idle time.
+    fn decay(&mut self, elapsed: u64) {
+        let Some(decay) = self.idle_decay else {
+            return;
+        };
+        if elapsed == 0 {
+            return;
+        }
+        let keep = libm::exp2(-(elapsed as f64) / decay.half_life as f64) as f32;
+        let prior = decay.prior;
+
+        self.epsilon = (prior.epsilon + (self.epsilon - prior.epsilon) * keep)
+            .clamp(threshold::EPSILON_CLAMP.0, threshold::EPSILON_CLAMP.1);
+
+        // Shortest way round the circle.
+        let mut delta = float::rem_euclid(self.phi - prior.phi, 2.0 * PI);
+        if delta > PI {
+            delta -= 2.0 * PI;
+        }
+        self.phi = float::rem_euclid(prior.phi + delta * keep, 2.0 * PI);
+    }
+
+    /// [`process_io_cycle`](Self::process_io_cycle) at time `now`.  The gap
+    /// since the previous timestamped cycle is first passed to
+    /// [`tick`](Self::tick); the first timestamp only starts the clock.
+    /// Timestamps that go backwards count as no gap.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// let prior = kernel.snapshot();
+    /// kernel.set_idle_decay(3_600_000_000_000, prior); // 1 h in ns
+    /// kernel.process_io_cycle_at(&[10, 11, 12, 13], 0);
+    /// kernel.process_io_cycle_at(&[90, 91, 92, 93], 8 * 3_600_000_000_000);
+    /// ```
+    pub fn process_io_cycle_at(&mut self, lba_stream: &[u64], now: u64) -> bool {
+        self.idle_gap(now);
+        self.process_io_cycle(lba_stream)
+    }
+
+    /// Pass the gap since the previous timestamp to the mode and to idle
+    /// decay, each of which keeps its own clock.
+    pub(crate) fn idle_gap(&mut self, now: u64) {
+        let gap = self.mode.stamp(now);
+        self.mode.idle(self.cycles, gap);
+        if let Some(decay) = &mut self.idle_decay {
+            let gap = decay.last.map_or(0, |last| now.saturating_sub(last));
+            decay.last = Some(decay.last.map_or(now, |last| last.max(now)));
+            self.decay(gap);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KernelConfig;
+
+    const HALF_LIFE: u64 = 1_000;
+
+    /// Angular distance between two basis angles, in [0, π].
+    fn angle_between(a: f32, b: f32) -> f32 {
+        let d = (a - b).rem_euclid(2.0 * PI);
+        d.min(2.0 * PI - d)
+    }
+
+    fn prior() -> KernelSnapshot {
+        KernelSnapshot::from(KernelConfig {
+            epsilon: 0.5,
+            phi: 0.3,
+            ..KernelConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_ten_half_lives_reach_prior() {
+        let prior = prior();
+        for eps in [0.1, 0.25, 0.7, 0.9] {
+            for phi in [0.0, 1.0, 3.0, 3.5, 6.2] {
+                let mut kernel = AetherLinkKernel::new(eps, phi, [0.1; 3], 0.0);
+                kernel.set_idle_decay(HALF_LIFE, prior);
+                kernel.tick(10 * HALF_LIFE);
+                let eps_err = (kernel.epsilon - prior.epsilon).abs();
+                let phi_err = angle_between(kernel.phi, prior.phi);
+                assert!(
+                    eps_err <= 1e-3 * (eps - prior.epsilon).abs() + 1e-6,
+                    "eps {eps} -> {}",
+                    kernel.epsilon
+                );
+                assert!(
+                    phi_err <= 1e-3 * angle_between(phi, prior.phi) + 1e-6,
+                    "phi {phi} -> {}",
+                    kernel.phi
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_decay_is_exact_for_split_gaps() {
+        let mut once = AetherLinkKernel::new(0.85, 5.0, [0.1; 3], 0.0);
+        once.set_idle_decay(HALF_LIFE, prior());
+        let mut split = once.clone();
+        once.tick(HALF_LIFE);
+        assert!((once.epsilon - 0.675).abs() < 1e-6);
+        for gap in [1, 9, 90, 400, 500] {
+            split.tick(gap);
+        }
+        assert!((once.epsilon - split.epsilon).abs() < 1e-5);
+        assert!(angle_between(once.phi, split.phi) < 1e-5);
+    }
+
+    #[test]
+    fn test_timestamped_cycles_decay_gaps() {
+        let mut kernel = AetherLinkKernel::new(0.85, 0.3, [0.0; 3], 0.0);
+        kernel.process_io_cycle_at(&[1, 2, 3, 4], 0);
+        kernel.set_idle_decay(HALF_LIFE, prior());
+        kernel.process_io_cycle_at(&[1, 2, 3, 4], 5);
+        assert_eq!(
+            kernel.epsilon, 0.85,
+            "first timestamp only starts the clock"
+        );
+        kernel.process_io_cycle_at(&[1, 2, 3, 4], 5 + 20 * HALF_LIFE);
+        assert!((kernel.epsilon - 0.5).abs() < 1e-5);
+
+        kernel.clear_idle_decay();
+        kernel.epsilon = 0.85;
+        kernel.tick(100 * HALF_LIFE);
+        assert_eq!(kernel.epsilon, 0.85);
+    }
+}
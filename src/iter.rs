@@ -0,0 +1,247 @@
+//! Decisions over any iterator of LBAs.
+//!
+//! Scripts and tests that already hold a trace seldom want to manage a
+//! window.  [`AetherIteratorExt`], implemented for every iterator of
+//! `u64`, turns one into an iterator of decisions: each LBA goes through
+//! [`push_lba`](AetherLinkKernel::push_lba) as it is pulled, so nothing is
+//! computed ahead and nothing is allocated per item.  The adapters borrow
+//! the kernel for as long as they live, and it keeps what it learned once
+//! they are dropped.  Both are fused: after the source first returns
+//! `None` they do too, and the kernel sees nothing more.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::{AetherLinkKernel, CycleOutcome};
+use core::iter::{Fuse, FusedIterator};
+
+/// What one pulled LBA did, as yielded by
+/// [`decisions_detailed`](AetherIteratorExt::decisions_detailed).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecisionReport {
+    /// The access.
+    pub lba: u64,
+    /// What [`push_lba`](AetherLinkKernel::push_lba) returned, or
+    /// [`PrefetchDecision::SKIP`] before the window held two LBAs.
+    pub decision: PrefetchDecision,
+    /// The cycle behind the decision, `None` when no cycle ran.
+    pub outcome: Option<CycleOutcome>,
+    /// [`effective_window`](AetherLinkKernel::effective_window) the cycle
+    /// ran with.
+    pub window: usize,
+}
+
+/// Kernel decisions over an iterator of LBAs.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::iter::AetherIteratorExt;
+/// use aether_link::AetherLinkKernel;
+///
+/// let trace: Vec<u64> = (1_000..1_100).collect();
+/// let mut kernel = AetherLinkKernel::default();
+/// let fired = trace
+///     .iter()
+///     .copied()
+///     .prefetch_decisions(&mut kernel)
+///     .filter(|&(_, fire)| fire)
+///     .count();
+/// assert_eq!(fired as u64, kernel.prefetches);
+/// ```
+pub trait AetherIteratorExt: Iterator<Item = u64> + Sized {
+    /// `(lba, fire)` for every LBA, `fire` being whether
+    /// [`push_lba`](AetherLinkKernel::push_lba) decided to prefetch.
+    #[inline]
+    fn prefetch_decisions(self, kernel: &mut AetherLinkKernel) -> PrefetchIter<'_, Self> {
+        PrefetchIter {
+            iter: self.fuse(),
+            kernel,
+        }
+    }
+
+    /// A [`DecisionReport`] for every LBA: the decision with its target
+    /// and the cycle outcome behind it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::iter::AetherIteratorExt;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_horizon(8);
+    /// for report in (0..100u64).decisions_detailed(&mut kernel) {
+    ///     if let Some(out) = report.outcome {
+    ///         assert_eq!(out.fetch, report.decision.fire);
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    fn decisions_detailed(self, kernel: &mut AetherLinkKernel) -> DetailedDecisions<'_, Self> {
+        DetailedDecisions {
+            iter: self.fuse(),
+            kernel,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u64>> AetherIteratorExt for I {}
+
+/// Iterator returned by
+/// [`prefetch_decisions`](AetherIteratorExt::prefetch_decisions).
+#[derive(Debug)]
+pub struct PrefetchIter<'k, I> {
+    iter: Fuse<I>,
+    kernel: &'k mut AetherLinkKernel,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for PrefetchIter<'_, I> {
+    type Item = (u64, bool);
+
+    #[inline]
+    fn next(&mut self) -> Option<(u64, bool)> {
+        let lba = self.iter.next()?;
+        Some((lba, self.kernel.push_lba(lba).is_some_and(|d| d.fire)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I: ExactSizeIterator<Item = u64>> ExactSizeIterator for PrefetchIter<'_, I> {}
+
+impl<I: Iterator<Item = u64>> FusedIterator for PrefetchIter<'_, I> {}
+
+/// Iterator returned by
+/// [`decisions_detailed`](AetherIteratorExt::decisions_detailed).
+#[derive(Debug)]
+pub struct DetailedDecisions<'k, I> {
+    iter: Fuse<I>,
+    kernel: &'k mut AetherLinkKernel,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for DetailedDecisions<'_, I> {
+    type Item = DecisionReport;
+
+    #[inline]
+    fn next(&mut self) -> Option<DecisionReport> {
+        let lba = self.iter.next()?;
+        let window = self.kernel.effective_window();
+        let outcome = self.kernel.push_lba_qos(lba, 0.0);
+        let decision = outcome.map_or(PrefetchDecision::SKIP, |out| {
+            self.kernel.streamed_decision(lba, &out)
+        });
+        Some(DecisionReport {
+            lba,
+            decision,
+            outcome,
+            window,
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I: ExactSizeIterator<Item = u64>> ExactSizeIterator for DetailedDecisions<'_, I> {}
+
+impl<I: Iterator<Item = u64>> FusedIterator for DetailedDecisions<'_, I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    /// Runs, strides and jumps, with repeats.
+    fn trace(seed: u64, n: usize) -> Vec<u64> {
+        let mut rng = SplitMix64::new(seed);
+        let mut lba = 1 << 20;
+        (0..n)
+            .map(|_| {
+                lba = match rng.below(8) {
+                    0 => rng.below(1 << 30),
+                    1 => lba,
+                    2 => lba + 64,
+                    _ => lba + 1,
+                };
+                lba
+            })
+            .collect()
+    }
+
+    fn kernels() -> [AetherLinkKernel; 3] {
+        let mut horizon = AetherLinkKernel::new_database();
+        horizon.set_horizon(8);
+        let mut footprints = AetherLinkKernel::default();
+        footprints.enable_spatial_footprints();
+        [AetherLinkKernel::default(), horizon, footprints]
+    }
+
+    #[test]
+    fn test_adapters_match_manual_push_lba() {
+        let trace = trace(5, 5_000);
+        for reference in kernels() {
+            let (mut plain, mut detailed, mut manual) =
+                (reference.clone(), reference.clone(), reference);
+            let fires: Vec<(u64, bool)> = trace
+                .iter()
+                .copied()
+                .prefetch_decisions(&mut plain)
+                .collect();
+            let reports: Vec<DecisionReport> = trace
+                .iter()
+                .copied()
+                .decisions_detailed(&mut detailed)
+                .collect();
+            assert_eq!((fires.len(), reports.len()), (trace.len(), trace.len()));
+            for ((&lba, &(l, fire)), report) in trace.iter().zip(&fires).zip(&reports) {
+                let decision = manual.push_lba(lba);
+                assert_eq!((l, fire), (lba, decision.is_some_and(|d| d.fire)));
+                assert_eq!(report.lba, lba);
+                assert_eq!(report.decision, decision.unwrap_or_default());
+                assert_eq!(report.outcome.is_some(), decision.is_some());
+                if let Some(out) = report.outcome {
+                    assert_eq!(out.fetch, report.decision.fire);
+                }
+            }
+            assert_eq!(plain.stats(), manual.stats());
+            assert_eq!(detailed.stats(), manual.stats());
+            assert_eq!(detailed.epsilon.to_bits(), manual.epsilon.to_bits());
+            assert_eq!(detailed.horizon_stats(), manual.horizon_stats());
+        }
+    }
+
+    /// Yields 1, 2, 3, then `None`, then 4, 5 … forever.
+    struct Flaky(u64);
+
+    impl Iterator for Flaky {
+        type Item = u64;
+
+        fn next(&mut self) -> Option<u64> {
+            self.0 += 1;
+            (self.0 != 4).then_some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_fused_after_first_none() {
+        let mut kernel = AetherLinkKernel::default();
+        let mut iter = Flaky(0).prefetch_decisions(&mut kernel);
+        let lbas: Vec<u64> = iter.by_ref().map(|(lba, _)| lba).collect();
+        assert_eq!(lbas, [1, 2, 3]);
+        assert_eq!((iter.next(), iter.next()), (None, None));
+        assert_eq!(kernel.cycles, 2);
+
+        let mut iter = Flaky(0).decisions_detailed(&mut kernel);
+        assert_eq!(iter.by_ref().count(), 3);
+        assert!(iter.next().is_none());
+        assert_eq!(kernel.cycles, 5);
+
+        // Exact sizes pass through.
+        let iter = [7u64, 8, 9].into_iter().prefetch_decisions(&mut kernel);
+        assert_eq!(iter.len(), 3);
+    }
+}
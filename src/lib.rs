@@ -20,10 +20,10 @@
 //!
 //! | Index | Feature | Symbol | Description |
 //! |-------|---------|--------|-------------|
-//! | 0 | Delta | Δ | LBA span: last − first |
+//! | 0 | Delta | Δ | LBA span: \|last − first\| |
 //! | 1 | Velocity | V | Δ × 0.5 (acceleration proxy) |
-//! | 2 | Variance | σ² | Welford running variance over stream |
-//! | 3 | Spectrum | C | Running Chebyshev spectral norm |
+//! | 2 | Variance | σ² | Welford variance over the last 16 deltas |
+//! | 3 | Spectrum | C | Upper-band energy of a 16-point DFT of the last 16 deltas |
 //! | 4 | History | H | Exponential decay temporal weight |
 //! | 5 | Context | Ω | Workload entropy via log-density ratio |
 //!
@@ -53,19 +53,226 @@
 //!     println!("PREFETCH: Direct bypass triggered!");
 //! }
 //! ```
+//!
+//! ## Allocation
+//!
+//! Once constructed, a kernel never allocates.  Guaranteed allocation-free
+//! (and checked by `tests/no_alloc.rs` with a counting allocator):
+//!
+//! - [`AetherLinkKernel::process_io_cycle`], [`process_io_cycle_qos`],
+//!   [`push_lba`], [`push_lba_qos`], [`push_io`] and
+//!   [`process_micro_batch`], with any of the optional stages (anomaly
+//!   detection, watchdog, latency budget, queue-depth damping, feedback
+//!   threshold, write coalescing, spatial footprints, delta correlation)
+//!   enabled, and the `record_*` outcome reports;
+//! - [`PrefetchQueue`] push and pop, and [`BandwidthBudget`] refills;
+//! - [`prefetcher::coalesce_in_place`].
+//!
+//! Allocating: constructors of the buffered types ([`PrefetchQueue::new`],
+//! [`ShardedKernelPool::new`], [`bank::KernelBank::new`]),
+//! [`prefetcher::coalesce`], staging plans, trace loading, replay and
+//! evaluation reports, tuning, and everything in the CLI.
+//!
+//! [`process_io_cycle_qos`]: AetherLinkKernel::process_io_cycle_qos
+//! [`push_lba`]: AetherLinkKernel::push_lba
+//! [`push_lba_qos`]: AetherLinkKernel::push_lba_qos
+//! [`push_io`]: AetherLinkKernel::push_io
+//! [`process_micro_batch`]: AetherLinkKernel::process_micro_batch
+//! [`process_io_cycle_raw`]: AetherLinkKernel::process_io_cycle_raw
+//! [`BandwidthBudget`]: bandwidth::BandwidthBudget
+//!
+//! ## `no_std`
+//!
+//! With default features off the crate is `#![no_std]` and needs only
+//! `core` and `alloc`, for firmware and RTOS targets with an allocator:
+//! the kernel, its optional stages, the baselines and the routers all
+//! build, and the float methods `core` lacks come from `libm`.  The
+//! default `std` feature adds what needs an operating system or is only
+//! used off-target: trace parsing, dispatchers and adapters, the statsd
+//! sink, kernel pools and hubs, the cache simulator, workloads,
+//! evaluation, tuning, record and replay, and the soak harness.  The
+//! [`atomic`] kernel also needs 64-bit atomics.
+//!
+//! ```toml
+//! aether-link = { version = "0.2", default-features = false }
+//! ```
+//!
+//! ## Panics
+//!
+//! The decision path cannot panic, whatever the input and whatever the
+//! kernel's state — NaN or infinite parameters from
+//! [`AetherLinkKernel::new`], counters at `u64::MAX`, LBAs at either end of
+//! the address space, empty slices — with any of the optional stages on:
+//!
+//! - [`AetherLinkKernel::process_io_cycle`], [`process_io_cycle_qos`],
+//!   [`push_lba`], [`push_lba_qos`], [`process_micro_batch`] and
+//!   [`process_io_cycle_raw`];
+//! - [`AetherLinkKernel::extract_telemetry`] and
+//!   [`AetherLinkKernel::prepare_quantum_state`].
+//!
+//! Counters wrap instead of overflowing.  Two callbacks run after the
+//! guaranteed part, just before the call returns, and may panic on their
+//! own account: the [mode hook](AetherLinkKernel::set_mode_hook) and the
+//! [checkpoint sink](AetherLinkKernel::set_checkpoint).  Configuration
+//! methods that can panic say so under their own "Panics" heading.
+//!
+//! `tests/no_panic.rs` runs the entry points on hostile kernels, and the
+//! `decision_path` fuzz target runs them on arbitrary bytes.  With the
+//! `no-panic` feature an optimised build fails to link if any panic is
+//! still reachable from them:
+//! `cargo test --release --features no-panic --test no_panic`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+// Crate-level only, and the zerocopy derives behind `state-view` generate
+// `ẕ`-prefixed names.
+#![cfg_attr(not(feature = "state-view"), deny(non_ascii_idents))]
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod adapters;
+pub mod anomaly;
+#[cfg(target_has_atomic = "64")]
+pub mod atomic;
+pub mod audit;
+pub mod auto;
+pub mod bandwidth;
+pub mod bank;
+pub mod baseline;
+pub mod blend;
+pub mod bounds;
+pub mod budget;
+pub mod burst;
+pub mod candidates;
+pub mod checkpoint;
+pub mod class_stats;
+pub mod classify;
+pub mod config;
+pub mod correlation;
+pub mod degenerate;
+pub mod depth;
+pub mod diff;
+pub mod direction;
+pub mod ensemble;
+#[cfg(feature = "std")]
+pub mod eval;
 mod fast_math;
+pub mod footprint;
+pub mod gap;
+pub mod heatmap;
+pub mod hint;
+pub mod histogram;
+pub mod horizon;
+mod idle;
+pub mod iter;
+#[cfg(all(target_os = "linux", feature = "linux-io"))]
+pub mod linux;
+pub mod metrics;
+pub mod microbatch;
+pub mod mode;
+mod no_panic;
+#[cfg(feature = "std")]
+pub mod numa;
+pub mod pacing;
+#[cfg(feature = "std")]
+pub mod pool;
+pub mod prefetcher;
+pub mod profile;
+pub mod queue;
+pub mod raw;
+#[cfg(feature = "std")]
+pub mod record;
+pub mod region;
+#[cfg(feature = "std")]
+pub mod replay;
+pub mod report;
+#[cfg(any(test, feature = "std"))]
+mod rng;
+#[cfg(feature = "std")]
+pub mod runner;
+#[cfg(feature = "selftime")]
+pub mod selftime;
+pub mod separate;
+#[cfg(feature = "std")]
+pub mod shard;
+#[cfg(feature = "std")]
+pub mod sim;
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod soak;
+#[cfg(feature = "state-view")]
+pub mod state_view;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stats_hub;
+pub mod stream;
+pub mod threshold;
+pub mod trace;
+#[cfg(feature = "std")]
+pub mod tune;
+pub mod watchdog;
+pub mod window;
+#[cfg(feature = "std")]
+pub mod workload;
+pub mod write_hint;
 
-use core::f32::consts::PI;
-pub use fast_math::{fast_atanf as fast_atan, fast_exp, fast_sigmoid};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(target_has_atomic = "64")]
+pub use atomic::AtomicAetherKernel;
+pub use audit::MathMode;
+pub use class_stats::ClassStats;
+pub use classify::WorkloadClass;
+pub use config::{ConfigError, KernelConfig};
+use core::fmt;
+use core::str::FromStr;
+pub use direction::ScanDirection;
+pub use fast_math::{fast_atanf as fast_atan, fast_exp, fast_sigmoid, wrap_angle};
+pub use iter::AetherIteratorExt;
+#[cfg(feature = "std")]
+pub use pool::{AetherLinkManager, KernelPool};
+#[cfg(feature = "std")]
+pub use prefetcher::PrefetchDispatcher;
+pub use prefetcher::{Named, PrefetchDecision, PrefetchPlan, Prefetcher};
+pub use profile::ProfileStore;
+pub use queue::PrefetchQueue;
+pub use region::RegionRouter;
+pub use report::KernelReport;
+pub use separate::StreamSeparator;
+#[cfg(feature = "std")]
+pub use shard::ShardedKernelPool;
+pub use snapshot::KernelSnapshot;
+pub use stats::KernelStats;
+pub use threshold::{CancelReason, FeedbackSteps, Reward, ThresholdPolicy};
 
 // ---------------------------------------------------------------------------
-// Telemetry DSP — Welford running stats + Chebyshev spectral norm + entropy
+// Telemetry DSP — windowed Welford variance + spectral projection + entropy
 // ---------------------------------------------------------------------------
 
+/// Deltas the variance and spectrum features are taken over.
+const SPAN_WINDOW: usize = 16;
+
+/// Fourier bins of the spectrum feature: 4 to 7 cycles per window, the
+/// upper band below Nyquist.
+const SPECTRUM_BINS: usize = 4;
+
+/// First bin of the spectrum feature.
+const SPECTRUM_LOW: usize = 4;
+
+/// `cos(2πj/16)`; `sin(2πj/16)` is entry `(j + 12) % 16`.
+const COS16: [f32; SPAN_WINDOW] = {
+    use core::f32::consts::FRAC_1_SQRT_2 as R2;
+    const C1: f32 = 0.923_879_5;
+    const C3: f32 = 0.382_683_43;
+    [
+        1.0, C1, R2, C3, 0.0, -C3, -R2, -C1, -1.0, -C1, -R2, -C3, 0.0, C3, R2, C1,
+    ]
+};
+
 /// Internal DSP state for the telemetry extraction pipeline.
 ///
 /// Maintains running statistics across I/O cycles so that each call to
@@ -73,18 +280,21 @@ pub use fast_math::{fast_atanf as fast_atan, fast_exp, fast_sigmoid};
 /// values — not hardcoded constants.
 #[derive(Debug, Clone)]
 struct TelemetryDSP {
-    /// Welford running mean.
+    /// Mean of the deltas in `span_window`.
     mean: f32,
-    /// Welford M2 accumulator (sum of squared deviations).
+    /// Sum of their squared deviations from `mean` (Welford's M2).
     m2: f32,
     /// Number of samples seen so far.
     count: u64,
+    /// The last [`SPAN_WINDOW`] deltas, the newest at `(count − 1) % 16`.
+    span_window: [f32; SPAN_WINDOW],
+    /// The [`SPECTRUM_BINS`] spectrum bins of `span_window`, taken in slot
+    /// order, as (re, im).
+    spectrum: [[f32; 2]; SPECTRUM_BINS],
+    /// Running mean of the spectrum bins' energy (spectral norm squared).
+    spectral_energy: f32,
     /// Exponentially-weighted history buffer (decay factor = 0.8).
     history_weight: f32,
-    /// Last delta value for velocity derivative.
-    last_delta: f32,
-    /// Running Chebyshev norm (spectral energy proxy).
-    spectral_energy: f32,
     /// Entropy of recent LBA inter-arrival rates (nats).
     entropy: f32,
     /// Ring buffer of recent deltas for entropy estimation.
@@ -99,9 +309,10 @@ impl Default for TelemetryDSP {
             mean: 0.0,
             m2: 0.0,
             count: 0,
-            history_weight: 0.8,
-            last_delta: 0.0,
+            span_window: [0.0; SPAN_WINDOW],
+            spectrum: [[0.0; 2]; SPECTRUM_BINS],
             spectral_energy: 0.0,
+            history_weight: 0.8,
             entropy: 0.0,
             recent_deltas: [0.0_f32; 16],
             delta_ring_idx: 0,
@@ -112,44 +323,138 @@ impl Default for TelemetryDSP {
 impl TelemetryDSP {
     /// Update with a new delta value (last − first LBA span).
     #[inline(always)]
-    fn update(&mut self, delta: f32) {
-        // Welford online variance.
-        self.count += 1;
-        let n = self.count as f32;
-        let delta晓 = delta - self.mean;
-        self.mean += delta晓 / n;
-        let delta_new = delta - self.mean;
-        self.m2 += delta晓 * delta_new;
-
-        // Chebyshev spectral energy (running RMS of delta differences).
-        let ddiff = delta - self.last_delta;
-        self.spectral_energy = 0.95 * self.spectral_energy + 0.05 * ddiff * ddiff;
-        self.last_delta = delta;
-
-        // Entropy via log-density ratio on recent deltas.
-        self.recent_deltas[self.delta_ring_idx] = delta.abs().max(1e-3_f32);
+    fn update(&mut self, delta: f32, math: MathMode) {
+        self.update_moments(delta);
+        self.update_entropy(delta, math);
+    }
+
+    /// The O(1) part of [`update`](Self::update): windowed variance and
+    /// spectrum.
+    #[inline(always)]
+    fn update_moments(&mut self, delta: f32) {
+        // Welford over a sliding window: while it fills, the usual update;
+        // then each delta replaces the oldest one.  The sums are taken
+        // again exactly once per turn of the window, so rounding does not
+        // accumulate and a non-finite delta is forgotten once it leaves.
+        let slot = self.count as usize % SPAN_WINDOW;
+        let oldest = self.span_window[slot];
+        self.span_window[slot] = delta;
+        self.count = self.count.wrapping_add(1);
+        if self.count <= SPAN_WINDOW as u64 {
+            let dev = delta - self.mean;
+            self.mean += dev / self.count as f32;
+            self.m2 += dev * (delta - self.mean);
+        } else if slot == SPAN_WINDOW - 1 {
+            let n = SPAN_WINDOW as f32;
+            self.mean = self.span_window.iter().sum::<f32>() / n;
+            self.m2 = self
+                .span_window
+                .iter()
+                .map(|&d| (d - self.mean) * (d - self.mean))
+                .sum();
+        } else {
+            let mean = self.mean + (delta - oldest) / SPAN_WINDOW as f32;
+            self.m2 += (delta - oldest) * (delta - mean + oldest - self.mean);
+            self.mean = mean;
+        }
+
+        // Spectrum: a 16-point Fourier projection of the window.  Taken in
+        // slot order rather than age order, which is a circular shift and
+        // changes only the phases, so replacing one delta moves each bin by
+        // the change times its slot's twiddle.  Taken again from the window
+        // once per turn, like the variance.
+        if slot == SPAN_WINDOW - 1 {
+            self.spectrum = project(&self.span_window);
+        } else {
+            let change = delta - oldest;
+            for (k, bin) in self.spectrum.iter_mut().enumerate() {
+                let j = (k + SPECTRUM_LOW) * slot % SPAN_WINDOW;
+                bin[0] += change * COS16[j];
+                bin[1] -= change * COS16[(j + 12) % SPAN_WINDOW];
+            }
+        }
+        // Mean square of the window's component in the band, times 4 (about
+        // the gain of a first difference up there), keeps the reading on the
+        // scale of the span-difference energy it replaced.  Smoothed over
+        // time like the rest of the telemetry.
+        let energy: f32 = self.spectrum.iter().map(|[re, im]| re * re + im * im).sum();
+        let band = 8.0 * energy / (SPAN_WINDOW * SPAN_WINDOW) as f32;
+        self.spectral_energy = 0.95 * self.spectral_energy + 0.05 * band;
+    }
+
+    /// Entropy via log-density ratio on recent deltas.
+    #[inline(always)]
+    fn update_entropy(&mut self, delta: f32, math: MathMode) {
+        self.recent_deltas[self.delta_ring_idx & 0xF] = delta.abs().max(1e-3_f32);
         self.delta_ring_idx = (self.delta_ring_idx + 1) & 0xF;
         let mut log_sum = 0.0_f32;
         for &d in &self.recent_deltas {
-            log_sum += d.ln();
+            log_sum += math.ln(d);
         }
         // Entropy: H_nats = log(n) - mean(log |delta|) for uniform proxy.
-        self.entropy = 16.0_f32.ln() - (log_sum / 16.0);
+        self.entropy = math.ln(16.0) - (log_sum / 16.0);
         // Clamp to positive; very regular streams → entropy ≈ 0.
         self.entropy = self.entropy.max(0.0);
     }
 
-    /// Return current running variance (σ²).  Returns 0 if < 2 samples.
+    /// Fold in the span `delta` and return the six telemetry features.
+    #[inline(always)]
+    fn features(&mut self, delta: f32, math: MathMode) -> [f32; 6] {
+        let velocity = delta * 0.5;
+
+        // Update DSP state before reading — ensures variance/spectrum/entropy
+        // reflect the current observation.
+        self.update(delta, math);
+        self.read_features(delta, velocity, math)
+    }
+
+    /// [`features`](Self::features) without the entropy update: the context
+    /// feature keeps its last value.  See [`budget`].
+    #[inline(always)]
+    fn features_degraded(&mut self, delta: f32, math: MathMode) -> [f32; 6] {
+        let velocity = delta * 0.5;
+        self.update_moments(delta);
+        self.read_features(delta, velocity, math)
+    }
+
+    #[inline(always)]
+    fn read_features(&self, delta: f32, velocity: f32, math: MathMode) -> [f32; 6] {
+        let variance = self.variance();
+        let spectrum = math.sqrt(self.spectral_energy);
+        let history = self.history_weight; // Decay factor = 0.8 (fixed)
+        let context = self.entropy.min(10.0); // Clamp large entropy
+
+        [delta, velocity, variance, spectrum, history, context]
+    }
+
+    /// Sample variance (σ²) of the deltas in the window.  Returns 0 if
+    /// < 2 samples.
     #[inline(always)]
     fn variance(&self) -> f32 {
-        if self.count < 2 {
+        let n = self.count.min(SPAN_WINDOW as u64);
+        if n < 2 {
             0.0
         } else {
-            self.m2 / (self.count as f32 - 1.0)
+            (self.m2 / (n as f32 - 1.0)).max(0.0)
         }
     }
 }
 
+/// The [`SPECTRUM_BINS`] bins from [`SPECTRUM_LOW`] up of `window`, as
+/// (re, im).
+#[inline(always)]
+fn project(window: &[f32; SPAN_WINDOW]) -> [[f32; 2]; SPECTRUM_BINS] {
+    let mut bins = [[0.0; 2]; SPECTRUM_BINS];
+    for (k, bin) in bins.iter_mut().enumerate() {
+        for (s, &d) in window.iter().enumerate() {
+            let j = (k + SPECTRUM_LOW) * s % SPAN_WINDOW;
+            bin[0] += d * COS16[j];
+            bin[1] -= d * COS16[(j + 12) % SPAN_WINDOW];
+        }
+    }
+    bins
+}
+
 // ---------------------------------------------------------------------------
 // Core kernel
 // ---------------------------------------------------------------------------
@@ -196,8 +501,120 @@ pub struct AetherLinkKernel {
     /// Statistics: Total prefetch triggers.
     pub prefetches: u64,
 
+    /// Cycle count of the next checkpoint, `u64::MAX` while off (see
+    /// [`set_checkpoint`](Self::set_checkpoint)).
+    next_checkpoint: u64,
+
     /// Internal telemetry DSP state.
     dsp: TelemetryDSP,
+
+    /// Recent LBAs fed through [`push_lba`](Self::push_lba).
+    history: stream::History,
+
+    /// Window resizing (see
+    /// [`enable_adaptive_window`](Self::enable_adaptive_window)).
+    adaptive_window: Option<window::AdaptiveWindow>,
+
+    /// Whether the newest moving window descended (see
+    /// [`scan_direction`](Self::scan_direction)).
+    descending: bool,
+
+    /// Whether the newest window ended on the block it started at (see
+    /// [`degenerate`]).
+    rereading: bool,
+
+    /// Regime and its signals (see [`mode`](Self::mode)).
+    mode: mode::ModeTracker,
+
+    /// Spectral observable E₃ of the last cycle (the term λ₃ scales).
+    spectral: f32,
+
+    /// Implementation of the transcendentals (see [`audit`]).
+    math: MathMode,
+
+    /// Rule that moves ε (see [`threshold`]).
+    threshold_policy: ThresholdPolicy,
+
+    /// Weights scaling the feedback steps (see [`Reward`]).
+    reward: Reward,
+
+    /// Reported prefetch outcomes (see
+    /// [`record_pollution`](Self::record_pollution)).
+    outcomes: threshold::Outcomes,
+
+    /// Counters behind [`stats`](Self::stats).
+    counters: stats::Counters,
+
+    /// Pull towards a prior over idle gaps (see
+    /// [`set_idle_decay`](Self::set_idle_decay)).
+    idle_decay: Option<idle::IdleDecay>,
+
+    /// Telemetry baseline (see
+    /// [`enable_anomaly_detection`](Self::enable_anomaly_detection)).
+    anomaly: Option<anomaly::AnomalyDetector>,
+
+    /// Stuck-decision detector (see
+    /// [`enable_watchdog`](Self::enable_watchdog)).
+    watchdog: Option<watchdog::Watchdog>,
+
+    /// Degraded-path budget (see
+    /// [`set_latency_budget`](Self::set_latency_budget)).
+    budget: Option<budget::LatencyBudget>,
+
+    /// Queue-depth damping (see [`set_queue_depth`](Self::set_queue_depth)).
+    depth: depth::DepthDamping,
+
+    /// Boost and suppress shifts and override counts (see
+    /// [`process_io_cycle_hinted`](Self::process_io_cycle_hinted)).
+    hints: hint::Hints,
+
+    /// Scatter damping (see [`set_burst_damping`](Self::set_burst_damping)).
+    burst: Option<burst::BurstDamping>,
+
+    /// Multi-cycle plans (see [`set_horizon`](Self::set_horizon)).
+    horizon: Option<horizon::Horizon>,
+
+    /// Playback-lead cap (see [`set_target_lead`](Self::set_target_lead)).
+    pacing: Option<pacing::Pacing>,
+
+    /// Fetch-probability distribution (see
+    /// [`enable_p_fetch_histogram`](Self::enable_p_fetch_histogram)).
+    p_fetch_histogram: Option<histogram::PFetchHistogram>,
+
+    /// Per-region access and prefetch counts (see
+    /// [`enable_heatmap`](Self::enable_heatmap)).
+    heatmap: Option<heatmap::Heatmap>,
+
+    /// Learned region footprints (see
+    /// [`enable_spatial_footprints`](Self::enable_spatial_footprints)).
+    footprints: Option<Box<footprint::SpatialFootprints>>,
+
+    /// Delta-correlation table (see
+    /// [`enable_delta_correlation`](Self::enable_delta_correlation)).
+    correlation: Option<Box<correlation::DeltaCorrelation>>,
+
+    /// Per-class counts (see [`enable_class_stats`](Self::enable_class_stats)).
+    class_stats: Option<Box<class_stats::ClassTracker>>,
+
+    /// First and last LBA emitted plans stay within (see
+    /// [`set_address_range`](Self::set_address_range)).
+    address_range: (u64, u64),
+
+    /// How horizon plans are read (see
+    /// [`set_gap_policy`](Self::set_gap_policy)).
+    gap_policy: gap::GapPolicy,
+
+    /// Write-run tracker (see
+    /// [`enable_write_coalescing`](Self::enable_write_coalescing)).
+    write_runs: Option<write_hint::Coalescer>,
+
+    /// Checkpoint interval, sink and newest checkpoint (see [`checkpoint`]).
+    checkpoints: Option<checkpoint::Checkpoints>,
+
+    /// Sampled cycle latencies (see
+    /// [`enable_self_timing`](Self::enable_self_timing)).
+    #[cfg(feature = "selftime")]
+    selftime: Option<selftime::SelfTimer>,
 }
 
 impl AetherLinkKernel {
@@ -227,8 +644,96 @@ impl AetherLinkKernel {
             bias,
             cycles: 0,
             prefetches: 0,
+            next_checkpoint: u64::MAX,
             dsp: TelemetryDSP::default(),
+            history: stream::History::default(),
+            adaptive_window: None,
+            descending: false,
+            rereading: false,
+            mode: mode::ModeTracker::default(),
+            spectral: 0.0,
+            math: MathMode::Fast,
+            threshold_policy: ThresholdPolicy::OpenLoop,
+            reward: Reward::default(),
+            outcomes: threshold::Outcomes::default(),
+            counters: stats::Counters::default(),
+            idle_decay: None,
+            anomaly: None,
+            watchdog: None,
+            budget: None,
+            depth: depth::DepthDamping::default(),
+            hints: hint::Hints::default(),
+            burst: None,
+            horizon: None,
+            pacing: None,
+            p_fetch_histogram: None,
+            heatmap: None,
+            footprints: None,
+            correlation: None,
+            class_stats: None,
+            address_range: (0, u64::MAX),
+            gap_policy: gap::GapPolicy::Contiguous,
+            write_runs: None,
+            checkpoints: None,
+            #[cfg(feature = "selftime")]
+            selftime: None,
+        }
+    }
+
+    /// Create a kernel after checking every parameter against its documented
+    /// range (see [`config`]).
+    ///
+    /// Use this instead of [`new`](Self::new) when parameters come from
+    /// untrusted input such as config files or tuners.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// assert!(AetherLinkKernel::try_new(0.5, 0.1, [0.1, 0.2, 0.3], 0.05).is_ok());
+    /// assert!(AetherLinkKernel::try_new(f32::NAN, 0.1, [0.1, 0.2, 0.3], 0.05).is_err());
+    /// ```
+    pub fn try_new(
+        epsilon: f32,
+        phi: f32,
+        lambda: [f32; 3],
+        bias: f32,
+    ) -> Result<Self, ConfigError> {
+        KernelConfig {
+            epsilon,
+            phi,
+            lambda,
+            bias,
         }
+        .validate()?;
+        Ok(Self::new(epsilon, phi, lambda, bias))
+    }
+
+    /// Current parameters as a [`KernelConfig`].
+    ///
+    /// `epsilon` and `phi` are the *learned* values, so building a kernel
+    /// from the result resumes from the current operating point (with fresh
+    /// telemetry and counters).
+    #[inline]
+    pub fn config(&self) -> KernelConfig {
+        KernelConfig {
+            epsilon: self.epsilon,
+            phi: self.phi,
+            lambda: self.lambda,
+            bias: self.bias,
+        }
+    }
+
+    /// Take the parameters of `config` in place, keeping counters,
+    /// telemetry and history.  The config is used as given; see
+    /// [`KernelConfig::validate`].
+    #[inline]
+    pub fn set_config(&mut self, config: &KernelConfig) {
+        self.epsilon = config.epsilon;
+        self.phi = config.phi;
+        self.lambda = config.lambda;
+        self.bias = config.bias;
     }
 
     /// Create a kernel tuned for HFT workloads.
@@ -248,20 +753,92 @@ impl AetherLinkKernel {
         Self::new(0.4, 0.2, [0.15, 0.25, 0.35], 0.05)
     }
 
+    /// Create a kernel tuned for OLTP database workloads.
+    ///
+    /// Database traffic is mostly Zipfian point reads, which no prefetch
+    /// helps, broken by occasional range scans, which prefetching helps a
+    /// lot.  The other presets fire on nearly every point read.  This one
+    /// holds a high ε that never adapts (λ₁ = 0), so the threshold cannot
+    /// drift down through long point-read phases, and scales the spectral
+    /// observable strongly (λ₃ = 0.75) against a negative bias, so only a
+    /// window moving in small regular steps clears it.  The window is 8
+    /// LBAs, short enough to recognise a scan a few pages in.
+    #[inline]
+    pub fn new_database() -> Self {
+        let mut kernel = Self::new(0.6, 0.05, [0.0, 0.05, 0.75], -0.85);
+        kernel.set_window(8);
+        kernel
+    }
+
+    /// Create a kernel tuned for ML training data loaders.
+    ///
+    /// Epoch reads stream whole shard files sequentially and jump to
+    /// another shard between them.  A low ε and a strong spectral term
+    /// (λ₃ = 0.9) fire on the first cycles of a new shard, the full 64-LBA
+    /// window keeps one jump from dominating the stride statistics, and a
+    /// 64-cycle [horizon](Self::set_horizon) plans megabytes ahead per
+    /// trigger instead of firing on every read.
+    #[inline]
+    pub fn new_ml_training() -> Self {
+        let mut kernel = Self::new(0.2, 0.2, [0.05, 0.25, 0.9], 0.0);
+        kernel.set_window(stream::MAX_WINDOW);
+        kernel.set_horizon(64);
+        kernel
+    }
+
+    /// Create a kernel tuned for paced video and asset streaming.
+    ///
+    /// Playback reads are steadily sequential, so a moderate threshold
+    /// locks on quickly, and a 64-cycle [horizon](Self::set_horizon)
+    /// plans ahead instead of firing on every read.  Reads are consumed at
+    /// the bitrate, though, so with timestamps in nanoseconds (fed through
+    /// [`push_lba_at`](Self::push_lba_at)) each plan is capped to a
+    /// [target lead](Self::set_target_lead) of two seconds of playback at
+    /// the observed rate.  Set another lead for other timestamp units.
+    #[inline]
+    pub fn new_video_streaming() -> Self {
+        let mut kernel = Self::new(0.45, 0.15, [0.1, 0.2, 0.35], 0.05);
+        kernel.set_horizon(64);
+        kernel.set_target_lead(2_000_000_000);
+        kernel
+    }
+
+    /// Create a kernel tuned for storage under WSL2.
+    ///
+    /// Requests through 9p/drvfs and virtio-blk take far longer, and vary
+    /// far more, than on bare metal, which lowers the break-even point of a
+    /// prefetch and makes large batched ones pay.  The threshold is low and
+    /// fixed (λ₁ = 0), and an 8-cycle [horizon](Self::set_horizon) fetches
+    /// eight requests' worth per trigger.  Variable latency also reorders
+    /// requests in flight, which the horizon absorbs, and drvfs metadata
+    /// storms — bursts of scattered small reads — are held off by strong
+    /// [burst damping](Self::set_burst_damping).
+    #[inline]
+    pub fn new_wsl2() -> Self {
+        let mut kernel = Self::new(0.3, 0.1, [0.0, 0.2, 0.3], 0.05);
+        kernel.set_horizon(8);
+        kernel.set_burst_damping(2.0);
+        kernel
+    }
+
     /// Extract 6D telemetry features from the LBA stream.
     ///
     /// Features:
     ///  - Δ (Delta):     LBA span = last − first
     ///  - V (Velocity):  Δ × 0.5 (acceleration proxy)
-    ///  - σ² (Variance): Welford running variance over all seen streams
-    ///  - C (Chebyshev): Running spectral energy (squared delta-diff RMS)
+    ///  - σ² (Variance): Welford variance over the last 16 window spans
+    ///  - C (Spectrum):  Upper-band energy of a 16-point DFT of the last 16 spans
     ///  - H (History):   Decay-weighted temporal context
     ///  - Ω (Context):   Log-density entropy of recent inter-arrival rates
     ///
+    /// Streams shorter than 2 LBAs have no span and yield all-zero
+    /// telemetry.
+    ///
     /// # Safety
     ///
-    /// Uses `get_unchecked` for the hot path.  Caller must ensure `lba_stream`
-    /// has at least 2 elements.
+    /// Safe for any slice: the hot path reads the two ends with
+    /// `get_unchecked` only after checking the length, which the
+    /// `process_cycle` fuzz target (`fuzz/`) exercises.
     #[inline(always)]
     pub fn extract_telemetry(&mut self, lba_stream: &[u64]) -> [f32; 6] {
         let len = lba_stream.len();
@@ -269,23 +846,50 @@ impl AetherLinkKernel {
             return [0.0; 6];
         }
 
+        let guard = no_panic::Guard::arm();
         // SAFETY: Bounds checked above — stream has ≥ 2 elements.
         let last = unsafe { *lba_stream.get_unchecked(len - 1) };
         let first = unsafe { *lba_stream.get_unchecked(0) };
 
-        let delta = (last.wrapping_sub(first)) as f32;
-        let velocity = delta * 0.5;
-
-        // Update DSP state before reading — ensures variance/spectrum/entropy
-        // reflect the current observation.
-        self.dsp.update(delta);
-
-        let variance = self.dsp.variance();
-        let spectrum = fast_math::fast_sqrt(self.dsp.spectral_energy);
-        let history = self.dsp.history_weight; // Decay factor = 0.8 (fixed)
-        let context = self.dsp.entropy.min(10.0); // Clamp large entropy
+        let telemetry = self.span_telemetry(first, last);
+        guard.disarm();
+        telemetry
+    }
 
-        [delta, velocity, variance, spectrum, history, context]
+    /// Telemetry for a window spanning `first ..= last`.  Shared by the
+    /// slice and streaming entry points.
+    #[inline(always)]
+    fn span_telemetry(&mut self, first: u64, last: u64) -> [f32; 6] {
+        // Signed span; the features see its magnitude, so a descending
+        // window reads like the ascending one it mirrors (see `direction`).
+        let span = last.wrapping_sub(first) as i64;
+        self.descending = (span < 0) | (self.descending & (span == 0));
+        self.rereading = span == 0;
+        let delta = span.unsigned_abs() as f32;
+        self.mode.observe(delta);
+        if let Some(detector) = &mut self.anomaly {
+            detector.observe(first, last, self.math);
+        }
+        if let Some(burst) = &mut self.burst {
+            burst.observe(last);
+        }
+        if let Some(horizon) = &mut self.horizon {
+            horizon.observe(last);
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.observe(last);
+        }
+        if let Some(footprints) = &mut self.footprints {
+            footprints.observe(last);
+        }
+        if let Some(correlation) = &mut self.correlation {
+            correlation.observe(last);
+        }
+        if self.budget.as_mut().map_or(true, |b| b.admit()) {
+            self.dsp.features(delta, self.math)
+        } else {
+            self.dsp.features_degraded(delta, self.math)
+        }
     }
 
     /// Encode 6D telemetry features into a Bloch sphere quantum state.
@@ -302,13 +906,23 @@ impl AetherLinkKernel {
     /// missing — the raw sum had no guarantee of unit length.
     #[inline]
     pub fn prepare_quantum_state(&self, features: [f32; 6]) -> [f32; 8] {
+        let guard = no_panic::Guard::arm();
+        let bloch = Self::bloch_state(features, self.math);
+        guard.disarm();
+        bloch
+    }
+
+    /// [`prepare_quantum_state`](Self::prepare_quantum_state) without a
+    /// kernel: the encoding depends on the features and the math mode alone.
+    #[inline(always)]
+    fn bloch_state(features: [f32; 6], math: MathMode) -> [f32; 8] {
         // Polar angles: θᵢ = 2·atan(fᵢ)  maps real line → [−π, π].
-        let t0 = fast_atan(features[0] / 64.0) * 2.0; // delta (scale down for atan)
-        let t1 = fast_atan(features[1] / 32.0) * 2.0; // velocity
-        let t2 = fast_atan(features[2] / 128.0) * 2.0; // variance (typically small)
-        let t3 = fast_atan(features[3] / 16.0) * 2.0; // spectrum
-        let t4 = fast_atan(features[4]) * 2.0; // history ∈ [0, 1]
-        let t5 = fast_atan((features[5] - 1.0) / 4.0) * 2.0; // entropy offset
+        let t0 = math.atan(features[0] / 64.0) * 2.0; // delta (scale down for atan)
+        let t1 = math.atan(features[1] / 32.0) * 2.0; // velocity
+        let t2 = math.atan(features[2] / 128.0) * 2.0; // variance (typically small)
+        let t3 = math.atan(features[3] / 16.0) * 2.0; // spectrum
+        let t4 = math.atan(features[4]) * 2.0; // history ∈ [0, 1]
+        let t5 = math.atan((features[5] - 1.0) / 4.0) * 2.0; // entropy offset
 
         // Chebyshev weights (spectral → spatial → temporal ordering).
         // These correspond to the POVM observable axes.
@@ -321,10 +935,10 @@ impl AetherLinkKernel {
         let phi_az = (t0 * 0.6 + t1 * 0.3 + t3 * 0.1) / (w[0] + w[1] + w[3]);
 
         // Convert to Cartesian on unit sphere.
-        let sin_theta = (theta * 0.5).sin();
-        let cos_theta = (theta * 0.5).cos();
-        let sin_phi = phi_az.sin();
-        let cos_phi = phi_az.cos();
+        let sin_theta = math.sin(theta * 0.5);
+        let cos_theta = math.cos(theta * 0.5);
+        let sin_phi = math.sin(phi_az);
+        let cos_phi = math.cos(phi_az);
 
         // Bloch vector (rx, ry, rz).
         let rx = sin_theta * cos_phi;
@@ -333,11 +947,7 @@ impl AetherLinkKernel {
 
         // Normalise to unit length using fast_inv_sqrt.
         let r2 = rx * rx + ry * ry + rz * rz;
-        let r_inv = if r2 > 1e-8 {
-            fast_math::fast_inv_sqrt(r2)
-        } else {
-            1.0
-        };
+        let r_inv = fast_math::select(r2 > 1e-8, math.inv_sqrt(r2.max(1e-8)), 1.0);
         let rx = rx * r_inv;
         let ry = ry * r_inv;
         let rz = rz * r_inv;
@@ -359,40 +969,209 @@ impl AetherLinkKernel {
     /// # Side Effects
     ///
     /// Updates internal DSP state, POVM basis (`phi`), and adaptive threshold
-    /// (`epsilon`).  Increments `cycles` and `prefetches` counters.
+    /// (`epsilon`).  Increments `cycles` and `prefetches` counters.  Windows
+    /// of fewer than two LBAs run no cycle and change none of them (see
+    /// [`degenerate`]).
     ///
     /// # Performance
     ///
     /// Benchmarked at **~18.1 ns** per cycle on x86_64 with AVX2.
     #[inline]
     pub fn process_io_cycle(&mut self, lba_stream: &[u64]) -> bool {
-        self.cycles += 1;
+        self.process_io_cycle_qos(lba_stream, 0.0).fetch
+    }
+
+    /// Execute one decision cycle with a quality-of-service threshold shift.
+    ///
+    /// `threshold_shift` is added to the adapted threshold ε for this
+    /// comparison only: negative values make the cycle more eager to fetch
+    /// (urgent traffic), positive values more reluctant (background
+    /// traffic).  The effective threshold is clamped to [0, 1].  Adaptation
+    /// of ε and φ is unaffected, so a stream's learned state does not depend
+    /// on the priorities it was served with.
+    ///
+    /// Returns the full [`CycleOutcome`] so callers can rank decisions by
+    /// their margin over the threshold.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// let out = kernel.process_io_cycle_qos(&[10, 11, 12, 13], -0.1);
+    /// assert_eq!(out.fetch, out.p_fetch > out.threshold);
+    /// ```
+    #[inline]
+    pub fn process_io_cycle_qos(
+        &mut self,
+        lba_stream: &[u64],
+        threshold_shift: f32,
+    ) -> CycleOutcome {
+        self.guarded(|kernel| {
+            if lba_stream.len() < 2 {
+                return kernel.short_window(threshold_shift);
+            }
+            kernel.timed(|kernel| {
+                let telemetry = kernel.extract_telemetry(lba_stream);
+                kernel.decide(telemetry, threshold_shift)
+            })
+        })
+    }
 
-        let telemetry = self.extract_telemetry(lba_stream);
+    /// [`process_io_cycle`](Self::process_io_cycle) returning what to
+    /// prefetch, not only whether.
+    ///
+    /// A firing decision targets the [`horizon_plan`](Self::horizon_plan)
+    /// when a horizon is set, and otherwise the blocks past the newest LBA
+    /// up to one stride of the run ending the window and
+    /// [`LOOKAHEAD_BLOCKS`](direction::LOOKAHEAD_BLOCKS) beyond, above it
+    /// for an ascending run and below it for a descending one; a window
+    /// not ending in a run gets the lookahead alone.  Streamed decisions
+    /// from [`push_lba`](Self::push_lba) are targeted the same way (see
+    /// [`direction`]).  Targets are trimmed to the
+    /// [address range](Self::set_address_range); a decision left with none
+    /// does not fire.  The confidence is the cycle's `p_fetch`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::{AetherLinkKernel, PrefetchPlan};
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// let decision = kernel.process_io_cycle_decision(&[100, 101, 102, 103]);
+    /// assert!(decision.fire);
+    /// assert_eq!(decision.target, Some(PrefetchPlan::new(104, 9)));
+    /// assert!(decision.confidence > kernel.epsilon);
+    /// ```
+    #[inline]
+    pub fn process_io_cycle_decision(&mut self, lba_stream: &[u64]) -> PrefetchDecision {
+        let outcome = self.process_io_cycle_qos(lba_stream, 0.0);
+        let decision = match (lba_stream, outcome.fetch) {
+            ([_, .., last], true) => match self.horizon {
+                Some(_) => self.horizon_plan().map_or(PrefetchDecision::SKIP, |plan| {
+                    PrefetchDecision::trigger(Some(plan))
+                }),
+                None => {
+                    let run = direction::run_stride(lba_stream.iter().rev().copied());
+                    self.run_target(run, *last)
+                }
+            },
+            _ => PrefetchDecision::SKIP,
+        };
+        self.heatmap_placed(decision)
+            .with_confidence(outcome.p_fetch)
+    }
+
+    /// Run a public decision entry point under the [`no_panic`] guard,
+    /// then call the mode hook and the checkpoint sink, which the
+    /// guarantee does not cover.
+    #[inline(always)]
+    fn guarded<R>(&mut self, entry: impl FnOnce(&mut Self) -> R) -> R {
+        let guard = no_panic::Guard::arm();
+        let out = entry(self);
+        guard.disarm();
+        self.mode.flush();
+        self.checkpoint_due();
+        out
+    }
+
+    /// Run one cycle; with the `selftime` feature, sampled cycles are timed
+    /// (see [`selftime`]).
+    #[cfg(not(feature = "selftime"))]
+    #[inline(always)]
+    fn timed<R>(&mut self, cycle: impl FnOnce(&mut Self) -> R) -> R {
+        cycle(self)
+    }
+
+    /// Measurement, adaptation and threshold comparison for one cycle.
+    #[inline(always)]
+    fn decide(&mut self, telemetry: [f32; 6], threshold_shift: f32) -> CycleOutcome {
+        self.decide_weighted(telemetry, threshold_shift, [1.0; 2])
+    }
+
+    /// [`decide`](Self::decide) with the ε and φ steps scaled by
+    /// `weights`, for a cycle standing for several accesses (see
+    /// [`microbatch`]).
+    // `max`/`min` rather than `clamp`, which checks its bounds and maps NaN
+    // differently; see the branch-free note below.
+    #[allow(clippy::manual_clamp)]
+    #[inline(always)]
+    fn decide_weighted(
+        &mut self,
+        telemetry: [f32; 6],
+        threshold_shift: f32,
+        weights: [f32; 2],
+    ) -> CycleOutcome {
+        self.cycles = self.cycles.wrapping_add(1);
+        let weights = self.mode.scale(weights);
         let bloch_vec = self.prepare_quantum_state(telemetry);
 
         // POVM-inspired measurement on the Bloch vector.
         // Three observables (E1=spatial, E2=temporal, E3=spectral) project
         // the Bloch vector onto the adaptive measurement basis phi.
-        let (o1, o2, o3) = self.povm_measure(&bloch_vec, self.phi);
+        let (o1, o2, o3) = Self::povm_measure(&bloch_vec, self.phi, self.math);
 
         // Adaptive POVM basis rotation (feedback from measurement).
-        self.phi = (self.phi + self.lambda[1] * o2) % (2.0 * PI);
+        self.phi = wrap_angle(self.phi, self.lambda[1] * o2 * weights[1]);
 
         // Adaptive threshold evolution (feedback from spatial observable).
-        self.epsilon += self.lambda[0] * o1;
-        self.epsilon = self.epsilon.clamp(0.1, 0.9);
+        // Under the feedback policy ε moves only on reported outcomes.
+        if self.threshold_policy == ThresholdPolicy::OpenLoop {
+            self.epsilon += self.lambda[0] * o1 * weights[0];
+        }
+        // The decision itself is branch-free: clamps are max/min, the
+        // trigger is a comparison result and the counters add it as 0 or 1,
+        // so its cost does not depend on which way it goes.  The optional
+        // stages below branch only on whether they are enabled.
+        self.epsilon = self
+            .epsilon
+            .max(threshold::EPSILON_CLAMP.0)
+            .min(threshold::EPSILON_CLAMP.1);
 
         // Fetch probability via sigmoid on the spectral observable.
+        self.spectral = o3;
         let exponent = -(self.lambda[2] * o3 + self.bias);
-        let p_fetch = fast_sigmoid(exponent);
+        let damping = self.depth.factor * self.burst.as_ref().map_or(1.0, |b| b.factor());
+        let p_fetch = self.math.sigmoid(exponent) * damping;
+        if let Some(histogram) = &mut self.p_fetch_histogram {
+            histogram.record(p_fetch);
+        }
 
-        let should_fetch = p_fetch > self.epsilon;
-        if should_fetch {
-            self.prefetches += 1;
+        let threshold = (self.epsilon + threshold_shift).max(0.0).min(1.0);
+        let suppressed = self.anomaly.as_ref().is_some_and(|a| a.suppresses())
+            | self.horizon.as_ref().is_some_and(|h| h.suppresses());
+        let cleared = p_fetch > threshold;
+        let fetch = cleared & !suppressed & !self.rereading;
+        self.counters.decided(fetch, cleared & suppressed, p_fetch);
+        self.counters.reread(self.rereading);
+        let alarm = self.anomaly.as_ref().is_some_and(|a| a.alarmed());
+        self.mode
+            .decided(self.cycles, self.counters.rolling_ratio(), alarm);
+        let cap = self.extent_cap();
+        if let Some(horizon) = &mut self.horizon {
+            let dropped = horizon.decided(fetch, cap, self.address_range, self.descending);
+            self.counters.out_of_range(dropped);
+        }
+        self.prefetches = self.prefetches.wrapping_add(fetch as u64);
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.decided(fetch);
+        }
+        if let Some(correlation) = &mut self.correlation {
+            correlation.decided(fetch);
         }
+        self.class_decided(fetch);
+        self.voted(fetch);
+        self.watch(fetch, p_fetch);
+        let degraded = self.budget.as_mut().is_some_and(|b| b.take_degraded());
 
-        should_fetch
+        CycleOutcome {
+            fetch,
+            p_fetch,
+            threshold,
+            degraded,
+            damping,
+        }
     }
 
     /// POVM-inspired measurement on a Bloch vector.
@@ -415,8 +1194,9 @@ impl AetherLinkKernel {
     ///
     /// * `bloch` - 8-element Bloch vector [rx, ry, rz, 0, …, 0]
     /// * `phi`  - Current POVM basis angle (radians)
+    /// * `math` - Math mode of the kernel measuring
     #[inline(always)]
-    fn povm_measure(&self, bloch: &[f32; 8], phi: f32) -> (f32, f32, f32) {
+    fn povm_measure(bloch: &[f32; 8], phi: f32, math: MathMode) -> (f32, f32, f32) {
         let rx = bloch[0];
         let ry = bloch[1];
         let rz = bloch[2];
@@ -424,17 +1204,27 @@ impl AetherLinkKernel {
 
         // Polar angle θ and azimuthal angle φ of the Bloch vector.
         // Bloch vector is already unit-length from prepare_quantum_state.
-        let theta = ry.acos(); // [0, π] from the y-component
-        let phi_az = ry.atan2(rx); // azimuthal from [rx, ry] plane
+        let theta = math.acos(ry); // [0, π] from the y-component
+        let phi_az = math.atan2(ry, rx); // azimuthal from [rx, ry] plane
 
         // Three POVM observables.
-        let e1 = libm::cosf(theta + phi);
-        let e2 = libm::sinf(theta * 0.5 - phi);
-        let e3 = libm::cosf(theta * phi_az);
+        let e1 = math.cos(theta + phi);
+        let e2 = math.sin(theta * 0.5 - phi);
+        let e3 = math.cos(theta * phi_az);
 
         (e1, e2, e3)
     }
 
+    /// Spectral observable of the last cycle.  `p_fetch` is
+    /// `σ(−(λ₃·E₃ + bias))`, and E₃ does not depend on λ₃ or the bias,
+    /// which is what lets [`tune::fit()`] treat them as logistic-regression
+    /// weights.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn last_spectral(&self) -> f32 {
+        self.spectral
+    }
+
     /// Get current prefetch ratio (prefetches / total cycles).
     #[inline]
     pub fn prefetch_ratio(&self) -> f32 {
@@ -445,11 +1235,56 @@ impl AetherLinkKernel {
         }
     }
 
-    /// Reset statistics counters (DSP state is preserved for continuity).
+    /// Reset statistics counters, reported outcomes, degraded cycles,
+    /// the other [`stats`](Self::stats) counters, horizon coverage, the
+    /// `p_fetch` histogram and self-timing samples included (DSP state is
+    /// preserved for continuity).
     #[inline]
     pub fn reset_stats(&mut self) {
         self.cycles = 0;
         self.prefetches = 0;
+        self.reset_outcomes();
+        self.reset_counters();
+        if let Some(budget) = &mut self.budget {
+            budget.degraded_cycles = 0;
+        }
+        self.reset_horizon_stats();
+        self.reset_p_fetch_histogram();
+        self.reset_coalesce_stats();
+        self.reset_footprint_stats();
+        self.reset_correlation_stats();
+        self.reset_hint_stats();
+        self.reset_class_stats();
+        #[cfg(feature = "selftime")]
+        self.reset_self_timing();
+    }
+}
+
+/// Result of one decision cycle, as returned by
+/// [`AetherLinkKernel::process_io_cycle_qos`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CycleOutcome {
+    /// Whether a prefetch was triggered.
+    pub fetch: bool,
+    /// Fetch probability produced by the POVM sigmoid.
+    pub p_fetch: f32,
+    /// Effective threshold `p_fetch` was compared against.
+    pub threshold: f32,
+    /// Whether the telemetry took the degraded path of the
+    /// [latency budget](AetherLinkKernel::set_latency_budget).
+    pub degraded: bool,
+    /// Factor `p_fetch` was scaled by for queue depth (see
+    /// [`AetherLinkKernel::set_queue_depth`]) and scatter (see
+    /// [`AetherLinkKernel::set_burst_damping`]); 1 when undamped.
+    pub damping: f32,
+}
+
+impl CycleOutcome {
+    /// Signed distance of `p_fetch` above the threshold — a confidence
+    /// score for ranking decisions (positive when the cycle fired).
+    #[inline]
+    pub fn margin(&self) -> f32 {
+        self.p_fetch - self.threshold
     }
 }
 
@@ -459,6 +1294,144 @@ impl Default for AetherLinkKernel {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Presets
+// ---------------------------------------------------------------------------
+
+/// Named parameter presets.
+///
+/// Lets tools and configuration files refer to the built-in constructors by
+/// name (`"hft"`, `"gaming"`, …) instead of repeating their parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Preset {
+    /// [`AetherLinkKernel::default`].
+    Default,
+    /// [`AetherLinkKernel::new_hft`].
+    Hft,
+    /// [`AetherLinkKernel::new_gaming`].
+    Gaming,
+    /// [`AetherLinkKernel::new_database`].
+    Database,
+    /// [`AetherLinkKernel::new_ml_training`].
+    #[cfg_attr(feature = "serde", serde(rename = "ml-training"))]
+    MlTraining,
+    /// [`AetherLinkKernel::new_video_streaming`].
+    #[cfg_attr(feature = "serde", serde(rename = "video-streaming"))]
+    VideoStreaming,
+    /// [`AetherLinkKernel::new_wsl2`].
+    Wsl2,
+}
+
+impl Preset {
+    /// Every preset, in declaration order.
+    pub const ALL: [Preset; 7] = [
+        Preset::Default,
+        Preset::Hft,
+        Preset::Gaming,
+        Preset::Database,
+        Preset::MlTraining,
+        Preset::VideoStreaming,
+        Preset::Wsl2,
+    ];
+
+    /// Lower-case name used by [`FromStr`] and [`Display`](fmt::Display).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Hft => "hft",
+            Self::Gaming => "gaming",
+            Self::Database => "database",
+            Self::MlTraining => "ml-training",
+            Self::VideoStreaming => "video-streaming",
+            Self::Wsl2 => "wsl2",
+        }
+    }
+
+    /// Build a fresh kernel with this preset's parameters.
+    #[inline]
+    pub fn kernel(self) -> AetherLinkKernel {
+        match self {
+            Self::Default => AetherLinkKernel::default(),
+            Self::Hft => AetherLinkKernel::new_hft(),
+            Self::Gaming => AetherLinkKernel::new_gaming(),
+            Self::Database => AetherLinkKernel::new_database(),
+            Self::MlTraining => AetherLinkKernel::new_ml_training(),
+            Self::VideoStreaming => AetherLinkKernel::new_video_streaming(),
+            Self::Wsl2 => AetherLinkKernel::new_wsl2(),
+        }
+    }
+
+    /// Give `kernel` this preset's parameters in place: its
+    /// [`config`](AetherLinkKernel::config),
+    /// [window](AetherLinkKernel::set_window),
+    /// [horizon](AetherLinkKernel::set_horizon),
+    /// [target lead](AetherLinkKernel::set_target_lead) and
+    /// [burst damping](AetherLinkKernel::set_burst_damping), turning off
+    /// the last three where the preset has none.  Counters, telemetry and
+    /// history are kept, and so is the state of a horizon, lead or damping
+    /// the preset leaves at the same setting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::{AetherLinkKernel, Preset};
+    ///
+    /// let mut kernel = AetherLinkKernel::new_hft();
+    /// Preset::MlTraining.apply(&mut kernel);
+    /// assert_eq!((kernel.window(), kernel.horizon()), (64, Some(64)));
+    /// Preset::Hft.apply(&mut kernel);
+    /// assert_eq!((kernel.window(), kernel.horizon()), (16, None));
+    /// ```
+    pub fn apply(self, kernel: &mut AetherLinkKernel) {
+        let preset = self.kernel();
+        kernel.set_config(&preset.config());
+        kernel.set_window(preset.window());
+        if kernel.horizon() != preset.horizon() {
+            match preset.horizon() {
+                Some(n) => kernel.set_horizon(n),
+                None => kernel.clear_horizon(),
+            }
+        }
+        if kernel.target_lead() != preset.target_lead() {
+            match preset.target_lead() {
+                Some(lead) => kernel.set_target_lead(lead),
+                None => kernel.clear_target_lead(),
+            }
+        }
+        if kernel.burst_damping() != preset.burst_damping() {
+            match preset.burst_damping() {
+                Some(strength) => kernel.set_burst_damping(strength),
+                None => kernel.clear_burst_damping(),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown preset '{s}' (expected one of: {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -493,6 +1466,66 @@ mod tests {
         assert!(t[2] >= 0.0, "variance must be non-negative");
     }
 
+    #[test]
+    fn test_variance_covers_the_recent_window() {
+        let mut dsp = TelemetryDSP::default();
+        for i in 0..1_000u32 {
+            dsp.update_moments((i * 7_919 % 1_000) as f32);
+        }
+        assert!(dsp.variance() > 10_000.0, "{}", dsp.variance());
+        // A steady phase of one span: variance falls to zero within a
+        // window, instead of decaying like 1/n over everything seen.
+        for n in 1..SPAN_WINDOW {
+            dsp.update_moments(64.0);
+            assert!(dsp.variance() > 0.0, "{n}");
+        }
+        dsp.update_moments(64.0);
+        assert_eq!(dsp.variance(), 0.0);
+        assert_eq!(dsp.mean, 64.0);
+
+        // The incremental update matches the direct sum, and a non-finite
+        // delta is forgotten a turn after it leaves the window.
+        dsp.update_moments(f32::NAN);
+        for i in 0..2 * SPAN_WINDOW as u32 {
+            dsp.update_moments((i % 5) as f32);
+        }
+        let window = &dsp.span_window;
+        let mean = window.iter().sum::<f32>() / SPAN_WINDOW as f32;
+        let m2: f32 = window.iter().map(|&d| (d - mean) * (d - mean)).sum();
+        assert!(
+            (dsp.variance() - m2 / 15.0).abs() < 1e-4,
+            "{}",
+            dsp.variance()
+        );
+    }
+
+    #[test]
+    fn test_spectrum_tracks_the_window() {
+        // A span alternating every cycle sits at Nyquist, outside the band;
+        // one alternating every other cycle (4 cycles per window) is in it.
+        let energy = |period: u32| {
+            let mut dsp = TelemetryDSP::default();
+            for i in 0..200u32 {
+                dsp.update_moments(if i / period % 2 == 0 { 64.0 } else { 0.0 });
+            }
+            dsp.spectral_energy
+        };
+        assert!(energy(1) < 0.1, "{}", energy(1));
+        assert!(energy(2) > 100.0, "{}", energy(2));
+
+        // The incremental bins match a projection taken directly, in the
+        // middle of a turn.
+        let mut dsp = TelemetryDSP::default();
+        for i in 0..3 * SPAN_WINDOW as u32 + 5 {
+            dsp.update_moments((i * 7_919 % 1_000) as f32);
+        }
+        let direct = project(&dsp.span_window);
+        for (bin, want) in dsp.spectrum.iter().zip(&direct) {
+            assert!((bin[0] - want[0]).abs() < 0.1, "{bin:?} vs {want:?}");
+            assert!((bin[1] - want[1]).abs() < 0.1, "{bin:?} vs {want:?}");
+        }
+    }
+
     #[test]
     fn test_empty_stream() {
         let mut kernel = AetherLinkKernel::default();
@@ -509,12 +1542,124 @@ mod tests {
         assert_eq!(kernel.cycles, 1);
     }
 
+    #[test]
+    fn test_qos_shift_leaves_adaptation_alone() {
+        let mut plain = AetherLinkKernel::new_gaming();
+        let mut eager = AetherLinkKernel::new_gaming();
+        for base in 0..50u64 {
+            let stream = [base * 7, base * 7 + 1, base * 7 + 3, base * 7 + 9];
+            let a = plain.process_io_cycle_qos(&stream, 0.0);
+            let b = eager.process_io_cycle_qos(&stream, -1.0);
+            assert_eq!(a.p_fetch, b.p_fetch);
+            assert_eq!(b.threshold, 0.0);
+            assert!(b.fetch);
+            assert_eq!(plain.epsilon, eager.epsilon);
+            assert_eq!(plain.phi, eager.phi);
+        }
+        assert_eq!(eager.prefetches, 50);
+    }
+
+    #[test]
+    fn test_adapts_across_phases() {
+        use crate::workload::{Pattern, Phased};
+
+        let workload = Phased::new(7)
+            .then(Pattern::SEQUENTIAL, 300)
+            .then_at(Pattern::Random { span: 1 << 24 }, 300, 1 << 32)
+            .then_at(Pattern::SEQUENTIAL, 300, 1 << 40);
+        let mut kernel = AetherLinkKernel::default();
+        let mut phi = Vec::new();
+        for (i, lba) in workload.iter().enumerate() {
+            kernel.push_lba(lba);
+            if (i + 1) % 300 == 0 {
+                phi.push(kernel.phi);
+            }
+        }
+        // The measurement basis moves with the workload and moves back.
+        assert!((phi[1] - phi[0]).abs() > 0.1, "{phi:?}");
+        assert!((phi[2] - phi[0]).abs() < (phi[1] - phi[0]).abs(), "{phi:?}");
+    }
+
+    /// The decision step as it was before the hot path went branch-free.
+    fn reference_cycle(k: &mut AetherLinkKernel, lbas: &[u64], shift: f32) -> CycleOutcome {
+        if lbas.len() < 2 {
+            return CycleOutcome::short_window(k.epsilon, shift);
+        }
+        let telemetry = k.extract_telemetry(lbas);
+        k.cycles += 1;
+        let bloch = k.prepare_quantum_state(telemetry);
+        let (o1, o2, o3) = AetherLinkKernel::povm_measure(&bloch, k.phi, k.math);
+        k.phi = wrap_angle(k.phi, k.lambda[1] * o2);
+        k.epsilon += k.lambda[0] * o1;
+        k.epsilon = k
+            .epsilon
+            .clamp(threshold::EPSILON_CLAMP.0, threshold::EPSILON_CLAMP.1);
+        k.spectral = o3;
+        let p_fetch = fast_sigmoid(-(k.lambda[2] * o3 + k.bias));
+        let threshold = (k.epsilon + shift).clamp(0.0, 1.0);
+        let fetch = p_fetch > threshold && lbas[0] != lbas[lbas.len() - 1];
+        if fetch {
+            k.prefetches += 1;
+        }
+        CycleOutcome {
+            fetch,
+            p_fetch,
+            threshold,
+            degraded: false,
+            damping: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_branch_free_cycle_matches_reference() {
+        let mut rng = rng::SplitMix64::new(42);
+        for preset in [
+            AetherLinkKernel::default(),
+            AetherLinkKernel::new_hft(),
+            AetherLinkKernel::new(0.5, 0.1, [0.4, 0.2, 0.3], -0.5),
+            AetherLinkKernel::new(0.1, 3.0, [0.0, 0.5, 2.0], 0.0),
+        ] {
+            let (mut kernel, mut reference) = (preset.clone(), preset);
+            let mut window = Vec::with_capacity(16);
+            for _ in 0..50_000 {
+                window.clear();
+                let base = rng.below(1 << 40);
+                let stride = [0, 1, 8, rng.below(1 << 20)][rng.below(4) as usize];
+                let len = rng.below(17);
+                window.extend((0..len).map(|i| base.wrapping_add(i * stride)));
+                // Large shifts drive the threshold into both of its clamps.
+                let shift = (rng.below(2001) as f32 - 1000.0) / 1000.0;
+                let got = kernel.process_io_cycle_qos(&window, shift);
+                let want = reference_cycle(&mut reference, &window, shift);
+                assert_eq!(got.fetch, want.fetch);
+                assert_eq!(got.p_fetch.to_bits(), want.p_fetch.to_bits());
+                assert_eq!(got.threshold.to_bits(), want.threshold.to_bits());
+                assert_eq!(kernel.epsilon.to_bits(), reference.epsilon.to_bits());
+                assert_eq!(kernel.phi.to_bits(), reference.phi.to_bits());
+            }
+            assert_eq!(
+                (kernel.cycles, kernel.prefetches),
+                (reference.cycles, reference.prefetches)
+            );
+        }
+    }
+
     #[test]
     fn test_hft_preset() {
         let kernel = AetherLinkKernel::new_hft();
         assert!(kernel.epsilon > 0.5); // Conservative.
     }
 
+    #[test]
+    fn test_preset_round_trip() {
+        for preset in Preset::ALL {
+            assert_eq!(preset.name().parse::<Preset>(), Ok(preset));
+        }
+        assert_eq!("HFT".parse::<Preset>(), Ok(Preset::Hft));
+        assert!("turbo".parse::<Preset>().is_err());
+        assert!((Preset::Hft.kernel().epsilon - 0.65).abs() < 1e-6);
+    }
+
     #[test]
     fn test_bloch_normalised() {
         // Verify the Bloch vector always has unit length.
@@ -537,7 +1682,7 @@ mod tests {
         let kernel = AetherLinkKernel::default();
         let features = [50.0_f32, 25.0, 1.0, 0.5, 0.8, 1.0];
         let bloch = kernel.prepare_quantum_state(features);
-        let (e1, e2, e3) = kernel.povm_measure(&bloch, 0.1);
+        let (e1, e2, e3) = AetherLinkKernel::povm_measure(&bloch, 0.1, MathMode::Fast);
         // All observables must be in [−1, 1].
         assert!((e1.abs() - 1.0).abs() <= 1e-6 || e1.abs() <= 1.0);
         assert!(e1.abs() <= 1.0 && e2.abs() <= 1.0 && e3.abs() <= 1.0);
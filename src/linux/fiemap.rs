@@ -0,0 +1,482 @@
+//! File-offset → LBA translation through the `FIEMAP` ioctl.
+//!
+//! On extent-based filesystems (ext4, xfs, btrfs) a file offset maps to a
+//! device byte address through the file's extent list.  [`FileExtentMap`]
+//! fetches that list once and answers lookups with a binary search, so the
+//! per-request cost is O(log extents) and no syscalls.
+
+use crate::AetherLinkKernel;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+/// Default translation granularity in bytes.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
+/// `_IOWR('f', 11, struct fiemap)`.
+const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+/// Flush dirty data first so delayed allocations get real addresses.
+const FIEMAP_FLAG_SYNC: u32 = 0x1;
+const FIEMAP_EXTENT_LAST: u32 = 0x1;
+const FIEMAP_EXTENT_UNKNOWN: u32 = 0x2;
+const FIEMAP_EXTENT_DELALLOC: u32 = 0x4;
+const FIEMAP_EXTENT_DATA_INLINE: u32 = 0x200;
+const FIEMAP_EXTENT_UNWRITTEN: u32 = 0x800;
+/// Extents fetched per ioctl round trip.
+const EXTENTS_PER_CALL: usize = 128;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct RawExtent {
+    fe_logical: u64,
+    fe_physical: u64,
+    fe_length: u64,
+    fe_reserved64: [u64; 2],
+    fe_flags: u32,
+    fe_reserved: [u32; 3],
+}
+
+#[repr(C)]
+struct RawFiemap {
+    fm_start: u64,
+    fm_length: u64,
+    fm_flags: u32,
+    fm_mapped_extents: u32,
+    fm_extent_count: u32,
+    fm_reserved: u32,
+    fm_extents: [RawExtent; EXTENTS_PER_CALL],
+}
+
+/// One mapped extent, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    /// File offset of the first byte.
+    pub logical: u64,
+    /// Device byte address of the first byte.
+    pub physical: u64,
+    /// Length in bytes.
+    pub length: u64,
+    /// `true` for allocated-but-unwritten (e.g. `fallocate`d) space, which
+    /// reads as zeros and is never worth prefetching.
+    pub unwritten: bool,
+}
+
+impl Extent {
+    #[inline]
+    fn contains(&self, offset: u64) -> bool {
+        offset >= self.logical && offset - self.logical < self.length
+    }
+}
+
+/// Cached extent list of one file.
+#[derive(Debug, Clone)]
+pub struct FileExtentMap {
+    extents: Vec<Extent>,
+    block_size: u64,
+    file_len: u64,
+}
+
+impl FileExtentMap {
+    /// Map `file` at [`DEFAULT_BLOCK_SIZE`] granularity.
+    pub fn new(file: &File) -> io::Result<Self> {
+        Self::with_block_size(file, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Map `file`, expressing LBAs in units of `block_size` bytes.
+    pub fn with_block_size(file: &File, block_size: u64) -> io::Result<Self> {
+        if block_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "block size must be non-zero",
+            ));
+        }
+        let mut map = Self {
+            extents: Vec::new(),
+            block_size,
+            file_len: 0,
+        };
+        map.refresh(file)?;
+        Ok(map)
+    }
+
+    /// Re-read the extent list, e.g. after the file was truncated, extended
+    /// or defragmented.
+    pub fn refresh(&mut self, file: &File) -> io::Result<()> {
+        self.file_len = file.metadata()?.len();
+        self.extents = fetch_extents(file)?;
+        Ok(())
+    }
+
+    /// Mapped extents in file order.
+    #[inline]
+    pub fn extents(&self) -> &[Extent] {
+        &self.extents
+    }
+
+    /// File length observed at the last refresh.
+    #[inline]
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    /// Translation granularity in bytes.
+    #[inline]
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Device LBA holding file byte `offset`.
+    ///
+    /// Returns `None` for holes, unwritten extents and offsets past the end
+    /// of the file as of the last refresh.
+    pub fn offset_to_lba(&self, offset: u64) -> Option<u64> {
+        if offset >= self.file_len {
+            return None;
+        }
+        let idx = self
+            .extents
+            .partition_point(|e| e.logical + e.length <= offset);
+        let ext = self.extents.get(idx).filter(|e| e.contains(offset))?;
+        if ext.unwritten {
+            return None;
+        }
+        Some((ext.physical + (offset - ext.logical)) / self.block_size)
+    }
+
+    /// Translate a window of offsets, appending LBAs to `out` in order.
+    ///
+    /// Unmapped offsets are skipped rather than replaced by a placeholder,
+    /// so they never show up as artificial jumps in the telemetry.  Returns
+    /// the number of offsets skipped.
+    pub fn translate_window(&self, offsets: &[u64], out: &mut Vec<u64>) -> usize {
+        let before = out.len();
+        out.extend(offsets.iter().filter_map(|&o| self.offset_to_lba(o)));
+        offsets.len() - (out.len() - before)
+    }
+}
+
+fn fetch_extents(file: &File) -> io::Result<Vec<Extent>> {
+    let fd = file.as_raw_fd();
+    let mut extents = Vec::new();
+    let mut start = 0u64;
+    loop {
+        let mut req = Box::new(RawFiemap {
+            fm_start: start,
+            fm_length: u64::MAX - start,
+            fm_flags: FIEMAP_FLAG_SYNC,
+            fm_mapped_extents: 0,
+            fm_extent_count: EXTENTS_PER_CALL as u32,
+            fm_reserved: 0,
+            fm_extents: [RawExtent::default(); EXTENTS_PER_CALL],
+        });
+        // SAFETY: `req` is a properly sized, initialised `struct fiemap` with
+        // room for `fm_extent_count` extents, and outlives the call.
+        let rc = unsafe { libc::ioctl(fd, FS_IOC_FIEMAP as _, &mut *req as *mut RawFiemap) };
+        if rc < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mapped = (req.fm_mapped_extents as usize).min(EXTENTS_PER_CALL);
+        if mapped == 0 {
+            return Ok(extents);
+        }
+        let from = start;
+        let last = take_batch(&req.fm_extents[..mapped], &mut start, &mut extents);
+        // A batch that maps nothing past `from` would be asked for again
+        // forever.
+        if last || start <= from {
+            return Ok(extents);
+        }
+    }
+}
+
+/// Append the translatable extents of one `FIEMAP` batch to `extents`,
+/// moving `start` to the end of the last one.  Extents ending at or before
+/// `start` (empty, or within what is already mapped) are skipped, and one
+/// reaching back before `start` is trimmed to begin there, so `extents`
+/// stays sorted and disjoint.  Returns whether the batch ends the mapping:
+/// it holds the last extent, or one that runs past `u64::MAX`.
+fn take_batch(batch: &[RawExtent], start: &mut u64, extents: &mut Vec<Extent>) -> bool {
+    let mut last = false;
+    for raw in batch {
+        last |= raw.fe_flags & FIEMAP_EXTENT_LAST != 0;
+        let Some(end) = raw.fe_logical.checked_add(raw.fe_length) else {
+            return true;
+        };
+        if end <= *start {
+            continue;
+        }
+        let overlap = start.saturating_sub(raw.fe_logical);
+        let logical = raw.fe_logical + overlap;
+        *start = end;
+        // Extents without a stable device address cannot be translated.
+        let unstable = FIEMAP_EXTENT_UNKNOWN | FIEMAP_EXTENT_DELALLOC | FIEMAP_EXTENT_DATA_INLINE;
+        let Some(physical) = raw.fe_physical.checked_add(overlap) else {
+            continue;
+        };
+        if raw.fe_flags & unstable != 0 {
+            continue;
+        }
+        extents.push(Extent {
+            logical,
+            physical,
+            length: end - logical,
+            unwritten: raw.fe_flags & FIEMAP_EXTENT_UNWRITTEN != 0,
+        });
+    }
+    last
+}
+
+/// A kernel that is fed file offsets instead of LBAs.
+///
+/// Offsets are translated through a [`FileExtentMap`]; holes and unwritten
+/// extents are dropped from the window.  If a lookup misses and the file
+/// size has changed since the map was built (truncation or growth), the map
+/// is refreshed once and the window translated again.
+#[derive(Debug)]
+pub struct FileBackedKernel {
+    kernel: AetherLinkKernel,
+    map: FileExtentMap,
+    file: File,
+    scratch: Vec<u64>,
+    refreshes: u64,
+    skipped: u64,
+}
+
+impl FileBackedKernel {
+    /// Wrap `kernel`, translating offsets of `file` at the default block
+    /// size.
+    pub fn new(file: File, kernel: AetherLinkKernel) -> io::Result<Self> {
+        let map = FileExtentMap::new(&file)?;
+        Ok(Self {
+            kernel,
+            map,
+            file,
+            scratch: Vec::new(),
+            refreshes: 0,
+            skipped: 0,
+        })
+    }
+
+    /// Run one decision cycle on a window of file offsets.
+    ///
+    /// Returns `false` without running a cycle when fewer than two offsets
+    /// in the window are mapped.
+    pub fn process_offsets(&mut self, offsets: &[u64]) -> bool {
+        self.scratch.clear();
+        let mut skipped = self.map.translate_window(offsets, &mut self.scratch);
+        if skipped > 0 && self.size_changed() && self.map.refresh(&self.file).is_ok() {
+            self.refreshes += 1;
+            self.scratch.clear();
+            skipped = self.map.translate_window(offsets, &mut self.scratch);
+        }
+        self.skipped += skipped as u64;
+        if self.scratch.len() < 2 {
+            return false;
+        }
+        self.kernel.process_io_cycle(&self.scratch)
+    }
+
+    fn size_changed(&self) -> bool {
+        self.file
+            .metadata()
+            .is_ok_and(|m| m.len() != self.map.file_len())
+    }
+
+    /// The wrapped kernel.
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+
+    /// The wrapped kernel, mutably.
+    #[inline]
+    pub fn kernel_mut(&mut self) -> &mut AetherLinkKernel {
+        &mut self.kernel
+    }
+
+    /// The current extent map.
+    #[inline]
+    pub fn map(&self) -> &FileExtentMap {
+        &self.map
+    }
+
+    /// Times the extent map was rebuilt after a size change.
+    #[inline]
+    pub fn refreshes(&self) -> u64 {
+        self.refreshes
+    }
+
+    /// Offsets dropped because they were unmapped.
+    #[inline]
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Seek, SeekFrom, Write};
+
+    const MIB: u64 = 1 << 20;
+
+    /// File with `len` bytes preallocated by `fallocate` and then written, so
+    /// its extents are real and not flagged unwritten.
+    fn allocated_file(len: u64) -> File {
+        let mut file = tempfile::tempfile().unwrap();
+        // SAFETY: valid fd, mode 0 allocates [0, len).
+        let rc = unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, len as libc::off_t) };
+        assert_eq!(rc, 0, "fallocate: {}", io::Error::last_os_error());
+        file.write_all(&vec![0xA5; len as usize]).unwrap();
+        file.sync_all().unwrap();
+        file
+    }
+
+    fn map_or_skip(file: &File) -> Option<FileExtentMap> {
+        match FileExtentMap::new(file) {
+            Ok(map) => Some(map),
+            Err(e) if e.raw_os_error() == Some(libc::EOPNOTSUPP) => {
+                eprintln!("skipping: filesystem does not support FIEMAP");
+                None
+            }
+            Err(e) => panic!("FIEMAP failed: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_batches_that_do_not_advance_end_the_mapping() {
+        let raw = |logical: u64, length: u64, flags: u32| RawExtent {
+            fe_logical: logical,
+            fe_physical: logical.wrapping_add(MIB),
+            fe_length: length,
+            fe_flags: flags,
+            ..RawExtent::default()
+        };
+        let mut extents = Vec::new();
+        let mut start = 0;
+        assert!(!take_batch(
+            &[raw(0, 4096, 0), raw(4096, 4096, 0)],
+            &mut start,
+            &mut extents
+        ));
+        assert_eq!((start, extents.len()), (8192, 2));
+
+        // A zero-length or overlapping batch without the last flag leaves
+        // `start` where it was, which `fetch_extents` stops on.
+        for batch in [[raw(8192, 0, 0)], [raw(4096, 4096, 0)]] {
+            assert!(!take_batch(&batch, &mut start, &mut extents));
+            assert_eq!((start, extents.len()), (8192, 2));
+        }
+
+        // One reaching back into mapped space keeps only its new part.
+        assert!(!take_batch(
+            &[raw(6144, 4096, 0), raw(10240, 2048, 0)],
+            &mut start,
+            &mut extents
+        ));
+        assert_eq!(start, 12288);
+        assert_eq!(
+            extents[2],
+            Extent {
+                logical: 8192,
+                physical: 8192 + MIB,
+                length: 2048,
+                unwritten: false,
+            }
+        );
+        assert!(extents
+            .windows(2)
+            .all(|w| w[0].logical + w[0].length <= w[1].logical));
+
+        // An extent running past the end of the address space ends it.
+        assert!(take_batch(
+            &[raw(u64::MAX - 10, 4096, 0), raw(16384, 4096, 0)],
+            &mut start,
+            &mut extents
+        ));
+        assert_eq!((start, extents.len()), (12288, 4));
+    }
+
+    #[test]
+    fn test_translation_monotonic_within_extent() {
+        let file = allocated_file(4 * MIB);
+        let Some(map) = map_or_skip(&file) else {
+            return;
+        };
+        assert!(!map.extents().is_empty());
+        for ext in map.extents().iter().filter(|e| !e.unwritten) {
+            let mut prev = None;
+            let mut off = ext.logical;
+            while off < ext.logical + ext.length && off < map.file_len() {
+                let lba = map.offset_to_lba(off).expect("mapped offset");
+                if let Some(p) = prev {
+                    assert_eq!(lba, p + 1, "non-contiguous inside extent at {off}");
+                }
+                prev = Some(lba);
+                off += DEFAULT_BLOCK_SIZE;
+            }
+        }
+        assert_eq!(map.offset_to_lba(4 * MIB), None, "past EOF");
+    }
+
+    #[test]
+    fn test_holes_and_unwritten_are_unmapped() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&[1; 4096]).unwrap();
+        file.seek(SeekFrom::Start(2 * MIB)).unwrap();
+        file.write_all(&[2; 4096]).unwrap();
+        // Unwritten tail: allocated without data.
+        // SAFETY: valid fd; KEEP_SIZE extends allocation only.
+        let rc = unsafe {
+            libc::fallocate(
+                file.as_raw_fd(),
+                libc::FALLOC_FL_KEEP_SIZE,
+                (2 * MIB + 4096) as libc::off_t,
+                MIB as libc::off_t,
+            )
+        };
+        assert_eq!(rc, 0);
+        file.sync_all().unwrap();
+        let Some(map) = map_or_skip(&file) else {
+            return;
+        };
+        assert!(map.offset_to_lba(0).is_some());
+        assert_eq!(map.offset_to_lba(MIB), None, "hole");
+        assert!(map.offset_to_lba(2 * MIB).is_some());
+
+        let mut out = Vec::new();
+        let skipped = map.translate_window(&[0, MIB, 2 * MIB, 3 * MIB], &mut out);
+        assert_eq!((skipped, out.len()), (2, 2));
+    }
+
+    #[test]
+    fn test_file_backed_kernel_refreshes_after_growth() {
+        let file = allocated_file(MIB);
+        if map_or_skip(&file).is_none() {
+            return;
+        }
+        let mut handle = file.try_clone().unwrap();
+        let mut fbk = FileBackedKernel::new(file, AetherLinkKernel::default()).unwrap();
+        let window: Vec<u64> = (0..8).map(|i| i * DEFAULT_BLOCK_SIZE).collect();
+        fbk.process_offsets(&window);
+        assert_eq!(fbk.kernel().cycles, 1);
+        assert_eq!(fbk.refreshes(), 0);
+
+        // Offsets past the old end miss, the size changed → one refresh.
+        handle.seek(SeekFrom::End(0)).unwrap();
+        handle.write_all(&vec![0x5A; MIB as usize]).unwrap();
+        handle.sync_all().unwrap();
+        let beyond: Vec<u64> = (0..8).map(|i| MIB + i * DEFAULT_BLOCK_SIZE).collect();
+        fbk.process_offsets(&beyond);
+        assert_eq!(fbk.refreshes(), 1);
+        assert_eq!(fbk.map().file_len(), 2 * MIB);
+        assert_eq!(fbk.skipped(), 0);
+        assert_eq!(fbk.kernel().cycles, 2);
+
+        // Past EOF with an unchanged size: skipped, no refresh, no cycle.
+        let eof: Vec<u64> = (0..8).map(|i| 4 * MIB + i * DEFAULT_BLOCK_SIZE).collect();
+        assert!(!fbk.process_offsets(&eof));
+        assert_eq!((fbk.refreshes(), fbk.skipped()), (1, 8));
+        assert_eq!(fbk.kernel().cycles, 2);
+    }
+}
@@ -0,0 +1,14 @@
+//! Linux-specific I/O integration (`linux-io` feature).
+//!
+//! These adapters translate between what applications see (file offsets)
+//! and what the kernel reasons about (device LBAs), using Linux-only
+//! interfaces such as the `FIEMAP` ioctl, capture live block-layer events
+//! from tracefs, and issue prefetches with `readahead(2)`.
+
+pub mod fiemap;
+pub mod readahead;
+pub mod tracefs;
+
+pub use fiemap::{Extent, FileBackedKernel, FileExtentMap};
+pub use readahead::ReadaheadDispatcher;
+pub use tracefs::{BlockEvent, BlockTracer, EventReader};
@@ -0,0 +1,165 @@
+//! `readahead(2)` dispatch.
+//!
+//! `posix_fadvise(WILLNEED)` is advisory and some filesystems ignore it;
+//! `readahead(2)` queues the reads before returning, which is what a
+//! medium-priority prefetch wants.  [`ReadaheadDispatcher`] issues one call
+//! per coalesced plan and caps how many bytes a single batch may pull in.
+
+use crate::prefetcher::{self, DispatchReport, PrefetchDispatcher, PrefetchPlan};
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+/// Default block size plans are expressed in.
+pub const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
+/// Issues `readahead(2)` for plans addressing one file.
+///
+/// Plan LBAs are file blocks of `block_size` bytes.  Adjacent and
+/// overlapping plans are merged before issuing, and each batch stops once
+/// `max_outstanding` bytes have been requested — the plan that crosses the
+/// budget is trimmed and the rest are counted as skipped.  Because
+/// `readahead(2)` has queued its reads by the time it returns, that budget
+/// bounds the I/O a single burst of triggers can put in flight.
+#[derive(Debug)]
+pub struct ReadaheadDispatcher {
+    file: File,
+    block_size: u64,
+    max_outstanding: u64,
+}
+
+impl ReadaheadDispatcher {
+    /// Dispatcher for `file` with 4 KiB blocks and no budget.
+    pub fn new(file: File) -> Self {
+        Self {
+            file,
+            block_size: DEFAULT_BLOCK_SIZE,
+            max_outstanding: u64::MAX,
+        }
+    }
+
+    /// Interpret plans in blocks of `block_size` bytes (at least 1).
+    pub fn with_block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Request at most `bytes` per [`dispatch`](PrefetchDispatcher::dispatch)
+    /// call.
+    pub fn with_max_outstanding(mut self, bytes: u64) -> Self {
+        self.max_outstanding = bytes;
+        self
+    }
+
+    /// The file reads are issued against.
+    #[inline]
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    fn readahead(&self, offset: u64, count: u64) -> io::Result<()> {
+        let offset = libc::off64_t::try_from(offset)
+            .map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        // SAFETY: plain syscall on a descriptor we own; no memory is passed.
+        let rc = unsafe { libc::readahead(self.file.as_raw_fd(), offset, count) };
+        if rc < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl PrefetchDispatcher for ReadaheadDispatcher {
+    fn dispatch(&mut self, plans: &[PrefetchPlan]) -> DispatchReport {
+        let mut report = DispatchReport::default();
+        let mut budget = self.max_outstanding / self.block_size;
+        for plan in prefetcher::coalesce(plans) {
+            let len = plan.len.min(budget);
+            report.skipped_blocks += plan.len - len;
+            if len == 0 {
+                continue;
+            }
+            let issue = PrefetchPlan::new(plan.start, len);
+            match self.readahead(
+                issue.start.saturating_mul(self.block_size),
+                len.saturating_mul(self.block_size),
+            ) {
+                Ok(()) => {
+                    report.issued += 1;
+                    report.bytes += len * self.block_size;
+                    budget -= len;
+                }
+                // EBADF (not open for reading), ESPIPE/EINVAL (not a
+                // regular file) and friends are reported per plan.
+                Err(e) => report.errors.push((issue, e)),
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::io::FromRawFd;
+
+    fn data_file(blocks: u64) -> File {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&vec![3u8; (blocks * DEFAULT_BLOCK_SIZE) as usize])
+            .unwrap();
+        file
+    }
+
+    #[test]
+    fn test_readahead_succeeds_and_coalesces() {
+        let mut d = ReadaheadDispatcher::new(data_file(64));
+        let plans = [
+            PrefetchPlan::new(0, 8),
+            PrefetchPlan::new(8, 8),
+            PrefetchPlan::new(12, 2),
+            PrefetchPlan::new(40, 4),
+        ];
+        let report = d.dispatch(&plans);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.issued, 2);
+        assert_eq!(report.bytes, 20 * DEFAULT_BLOCK_SIZE);
+        assert_eq!(report.skipped_blocks, 0);
+    }
+
+    #[test]
+    fn test_budget_trims_and_skips() {
+        let mut d = ReadaheadDispatcher::new(data_file(64)).with_max_outstanding(10 * 4096);
+        let plans = [
+            PrefetchPlan::new(0, 6),
+            PrefetchPlan::new(20, 6),
+            PrefetchPlan::new(40, 6),
+        ];
+        let report = d.dispatch(&plans);
+        assert_eq!(report.issued, 2);
+        assert_eq!(report.bytes, 10 * 4096);
+        assert_eq!(report.skipped_blocks, 8);
+        // The budget is per call.
+        assert_eq!(d.dispatch(&plans[..1]).bytes, 6 * 4096);
+    }
+
+    #[test]
+    fn test_errors_reported_per_plan() {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for the two descriptors pipe(2) returns.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        // SAFETY: both descriptors are fresh and owned by these handles.
+        let (read_end, _write_end) =
+            unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        let mut d = ReadaheadDispatcher::new(read_end);
+        let report = d.dispatch(&[PrefetchPlan::new(0, 1), PrefetchPlan::new(10, 1)]);
+        assert_eq!(report.issued, 0);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report
+            .errors
+            .iter()
+            .all(|(_, e)| e.raw_os_error().is_some()));
+    }
+}
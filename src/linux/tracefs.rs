@@ -0,0 +1,301 @@
+//! Live block-layer events from tracefs.
+//!
+//! The `block:block_rq_issue` tracepoint fires whenever a request is handed
+//! to a device driver.  [`BlockTracer`] enables it and restores the previous
+//! enable state when dropped; [`EventReader`]s stream its `trace_pipe`, and
+//! [`parse_line`] decodes one `trace_pipe` line into a [`BlockEvent`].
+
+use crate::trace::IoOp;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Where tracefs is usually mounted, newest convention first.
+pub const TRACEFS_ROOTS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+const EVENT_ENABLE: &str = "events/block/block_rq_issue/enable";
+const EVENT_TAG: &str = "block_rq_issue:";
+
+/// One issued block request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockEvent {
+    /// Trace clock timestamp in nanoseconds.
+    pub timestamp_ns: u64,
+    /// Device major number.
+    pub major: u32,
+    /// Device minor number.
+    pub minor: u32,
+    /// First 512-byte sector.
+    pub sector: u64,
+    /// Length in sectors (always ≥ 1).
+    pub sectors: u32,
+    /// Request direction.
+    pub op: IoOp,
+}
+
+impl BlockEvent {
+    /// `(major, minor)` packed into one key, `major << 32 | minor`.
+    #[inline]
+    pub fn device(&self) -> u64 {
+        (u64::from(self.major) << 32) | u64::from(self.minor)
+    }
+}
+
+/// Decode a `block_rq_issue` line from `trace_pipe`.
+///
+/// Accepts both the classic layout and the one with an I/O priority field
+/// added in Linux 6.x:
+///
+/// ```text
+/// fio-4021 [002] d..1. 8611.372143: block_rq_issue: 259,0 R 4096 () 2048 + 8 [fio]
+/// kworker/0:1H-153 [000] ..... 91.5: block_rq_issue: 8,0 WS 0 () 1234 + 16 be,0,4 [kworker/0:1H]
+/// ```
+///
+/// Returns `None` for other events, malformed lines, zero-length requests
+/// (flushes) and requests that are neither reads nor writes (discards).
+pub fn parse_line(line: &str) -> Option<BlockEvent> {
+    let tag = line.find(EVENT_TAG)?;
+    let timestamp_ns = parse_timestamp(line[..tag].trim_end().strip_suffix(':')?)?;
+
+    let mut fields = line[tag + EVENT_TAG.len()..].split_whitespace();
+    let (major, minor) = fields.next()?.split_once(',')?;
+    let (major, minor) = (major.parse().ok()?, minor.parse().ok()?);
+    let rwbs = fields.next()?;
+    let _bytes = fields.next()?;
+    // The command field is `()` unless it is a passthrough request, in
+    // which case it is a parenthesised hex dump that may contain spaces.
+    let mut cmd = fields.next()?;
+    while !cmd.ends_with(')') {
+        cmd = fields.next()?;
+    }
+    let sector = fields.next()?.parse().ok()?;
+    if fields.next()? != "+" {
+        return None;
+    }
+    let sectors: u32 = fields.next()?.parse().ok()?;
+    if sectors == 0 {
+        return None;
+    }
+    // rwbs: D (discard) and F (flush) come before R/W when present.
+    let op = match rwbs.trim_start_matches('F').chars().next()? {
+        'R' => IoOp::Read,
+        'W' => IoOp::Write,
+        _ => return None,
+    };
+    Some(BlockEvent {
+        timestamp_ns,
+        major,
+        minor,
+        sector,
+        sectors,
+        op,
+    })
+}
+
+/// Parse the `seconds.micros` timestamp that ends the line prefix.
+fn parse_timestamp(prefix: &str) -> Option<u64> {
+    let stamp = prefix.rsplit(' ').next()?;
+    let (secs, frac) = stamp.split_once('.').unwrap_or((stamp, ""));
+    let secs: u64 = secs.parse().ok()?;
+    let mut nanos = 0u64;
+    let mut scale = 100_000_000u64;
+    for c in frac.chars().take(9) {
+        nanos += u64::from(c.to_digit(10)?) * scale;
+        scale /= 10;
+    }
+    Some(secs * 1_000_000_000 + nanos)
+}
+
+/// Locate a mounted tracefs.
+pub fn find_tracefs() -> Option<PathBuf> {
+    TRACEFS_ROOTS
+        .iter()
+        .map(PathBuf::from)
+        .find(|p| p.join("trace_pipe").exists())
+}
+
+/// An enabled `block_rq_issue` tracepoint and its `trace_pipe`.
+///
+/// Keep the tracer alive for as long as events are read: dropping it turns
+/// the tracepoint back off unless it was already on.  A process killed by a
+/// signal leaves it enabled (`echo 0 > …/block_rq_issue/enable` undoes that).
+#[derive(Debug)]
+pub struct BlockTracer {
+    root: PathBuf,
+    was_enabled: bool,
+    pipe: File,
+}
+
+impl BlockTracer {
+    /// Enable the tracepoint on the first mounted tracefs and open its pipe.
+    ///
+    /// Fails with a message naming the likely cause when tracefs is not
+    /// mounted or the caller lacks the privileges to use it (root or
+    /// `CAP_SYS_ADMIN`/`CAP_PERFMON`, depending on the kernel).
+    pub fn open() -> io::Result<Self> {
+        let root = find_tracefs().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "tracefs is not mounted (try: mount -t tracefs nodev /sys/kernel/tracing)",
+            )
+        })?;
+        Self::open_at(&root)
+    }
+
+    /// Like [`open`](Self::open) with an explicit tracefs mount point.
+    pub fn open_at(root: &Path) -> io::Result<Self> {
+        let enable = root.join(EVENT_ENABLE);
+        let was_enabled = fs::read_to_string(&enable)
+            .map_err(|e| explain(&enable, e))?
+            .trim()
+            == "1";
+        fs::write(&enable, "1").map_err(|e| explain(&enable, e))?;
+        let pipe_path = root.join("trace_pipe");
+        let pipe = match File::open(&pipe_path) {
+            Ok(f) => f,
+            Err(e) => {
+                if !was_enabled {
+                    let _ = fs::write(&enable, "0");
+                }
+                return Err(explain(&pipe_path, e));
+            }
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            was_enabled,
+            pipe,
+        })
+    }
+
+    /// A reader over the tracer's `trace_pipe`, e.g. for a reader thread.
+    pub fn events(&self) -> io::Result<EventReader> {
+        Ok(EventReader {
+            pipe: BufReader::new(self.pipe.try_clone()?),
+            line: String::new(),
+        })
+    }
+}
+
+/// Streams [`BlockEvent`]s from a [`BlockTracer`]'s `trace_pipe`.
+#[derive(Debug)]
+pub struct EventReader {
+    pipe: BufReader<File>,
+    line: String,
+}
+
+impl EventReader {
+    /// Block until the next `block_rq_issue` event arrives.
+    ///
+    /// Lines for other events are skipped; `Ok(None)` means the pipe was
+    /// closed.
+    pub fn next_event(&mut self) -> io::Result<Option<BlockEvent>> {
+        loop {
+            self.line.clear();
+            if self.pipe.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            if let Some(ev) = parse_line(&self.line) {
+                return Ok(Some(ev));
+            }
+        }
+    }
+}
+
+impl Drop for BlockTracer {
+    fn drop(&mut self) {
+        if !self.was_enabled {
+            let _ = fs::write(self.root.join(EVENT_ENABLE), "0");
+        }
+    }
+}
+
+fn explain(path: &Path, e: io::Error) -> io::Error {
+    let hint = match e.kind() {
+        io::ErrorKind::PermissionDenied => " — tracefs needs root (try sudo)",
+        io::ErrorKind::NotFound => " — is the block tracepoint compiled into this kernel?",
+        _ => "",
+    };
+    io::Error::new(e.kind(), format!("{}: {e}{hint}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_classic_line() {
+        let ev = parse_line(
+            "             fio-4021    [002] d..1.  8611.372143: block_rq_issue: 259,0 R 4096 () 2048 + 8 [fio]",
+        )
+        .unwrap();
+        assert_eq!((ev.major, ev.minor), (259, 0));
+        assert_eq!((ev.sector, ev.sectors, ev.op), (2048, 8, IoOp::Read));
+        assert_eq!(ev.timestamp_ns, 8_611_372_143_000);
+        assert_eq!(ev.device(), 259 << 32);
+    }
+
+    #[test]
+    fn test_parse_ioprio_and_sync_write() {
+        let ev = parse_line(
+            "    kworker/0:1H-153     [000] .....    91.500001: block_rq_issue: 8,16 WS 0 () 1234 + 16 be,0,4 [kworker/0:1H]",
+        )
+        .unwrap();
+        assert_eq!((ev.major, ev.minor), (8, 16));
+        assert_eq!((ev.sector, ev.sectors, ev.op), (1234, 16, IoOp::Write));
+        assert_eq!(ev.timestamp_ns, 91_500_001_000);
+    }
+
+    #[test]
+    fn test_parse_flush_write_and_passthrough_cmd() {
+        let ev = parse_line(
+            "jbd2/sda1-8-245 [001] d..1. 55.000100: block_rq_issue: 8,0 FWFS 4096 () 88 + 8 [jbd2/sda1-8]",
+        )
+        .unwrap();
+        assert_eq!(ev.op, IoOp::Write);
+        let ev = parse_line(
+            "nvme-cli-9 [003] ..... 12.5: block_rq_issue: 259,1 R 512 (02 00 00 00) 77 + 1 [nvme]",
+        )
+        .unwrap();
+        assert_eq!((ev.sector, ev.sectors), (77, 1));
+    }
+
+    #[test]
+    fn test_rejects_other_lines() {
+        for line in [
+            "",
+            "# tracer: nop",
+            "fio-1 [000] d..1. 1.0: block_rq_complete: 8,0 R () 2048 + 8 [0]",
+            "kworker-1 [000] ..... 2.0: block_rq_issue: 8,0 FF 0 () 0 + 0 [kworker]",
+            "fstrim-7 [001] ..... 3.0: block_rq_issue: 8,0 DS 1048576 () 4096 + 2048 [fstrim]",
+            "fio-1 [000] d..1. 1.0: block_rq_issue: 8,0 R 4096 () notanumber + 8 [fio]",
+        ] {
+            assert_eq!(parse_line(line), None, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_tracer_restores_enable_state() {
+        let root = tempfile::tempdir().unwrap();
+        let enable = root.path().join(EVENT_ENABLE);
+        fs::create_dir_all(enable.parent().unwrap()).unwrap();
+        fs::write(&enable, "0\n").unwrap();
+        fs::write(
+            root.path().join("trace_pipe"),
+            "fio-1 [000] d..1. 1.0: block_rq_issue: 8,0 R 4096 () 10 + 8 [fio]\n\
+             fio-1 [000] d..1. 1.1: block_rq_complete: 8,0 R () 10 + 8 [0]\n\
+             fio-1 [000] d..1. 1.2: block_rq_issue: 8,0 R 4096 () 18 + 8 [fio]\n",
+        )
+        .unwrap();
+
+        let tracer = BlockTracer::open_at(root.path()).unwrap();
+        assert_eq!(fs::read_to_string(&enable).unwrap(), "1");
+        let mut reader = tracer.events().unwrap();
+        assert_eq!(reader.next_event().unwrap().unwrap().sector, 10);
+        assert_eq!(reader.next_event().unwrap().unwrap().sector, 18);
+        assert_eq!(reader.next_event().unwrap(), None);
+        drop(tracer);
+        assert_eq!(fs::read_to_string(&enable).unwrap(), "0");
+
+        let missing = BlockTracer::open_at(&root.path().join("nope")).unwrap_err();
+        assert!(missing.to_string().contains("block_rq_issue"), "{missing}");
+    }
+}
@@ -0,0 +1,655 @@
+//! Pushing kernel metrics to whatever collects them.
+//!
+//! A [`MetricsSink`] takes gauges, counter increments and counter totals
+//! by name; [`flush_metrics`] writes the standard set of a kernel's
+//! metrics to one.
+//! Two sinks come with the crate:
+//!
+//! - [`PrometheusSink`] accumulates what it is given and renders the
+//!   Prometheus text exposition, for pull-based scraping (it is what
+//!   [`HubStats::to_prometheus`](crate::stats_hub::HubStats::to_prometheus)
+//!   is built on);
+//! - [`StatsdSink`] formats statsd lines and sends them over UDP, packed
+//!   into datagrams of at most [`MAX_DATAGRAM`] bytes (with the `std`
+//!   feature).
+//!
+//! [`flush_metrics`] reports the counters as
+//! [totals](MetricsSink::counter_total) since construction or the last
+//! [`reset_stats`](AetherLinkKernel::reset_stats).  A Prometheus sink sets
+//! them and a statsd sink sends the increase since the total it was last
+//! given, so either can be flushed repeatedly.  A sink of increments that
+//! does not override [`counter_total`](MetricsSink::counter_total) adds
+//! every total again; give it the interval's [`KernelStats::delta`]
+//! through [`flush_stats`] instead:
+//!
+//! ```rust
+//! use aether_link::metrics::{flush_stats, StatsdSink};
+//! use aether_link::AetherLinkKernel;
+//!
+//! let mut kernel = AetherLinkKernel::default();
+//! let mut sink = StatsdSink::new(Vec::<Vec<u8>>::new());
+//! let mut last = kernel.stats();
+//! for i in 0..100u64 {
+//!     kernel.push_lba(i);
+//! }
+//! let now = kernel.stats();
+//! flush_stats(&now.delta(&last), "aether", &mut sink);
+//! last = now;
+//! sink.flush().unwrap();
+//! let datagram = String::from_utf8(sink.socket()[0].clone()).unwrap();
+//! assert!(datagram.starts_with("aether_cycles_total:99|c\n"));
+//! # let _ = last;
+//! ```
+
+use crate::{AetherLinkKernel, KernelStats};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// Largest datagram [`StatsdSink`] sends by default: an Ethernet MTU less
+/// IP and UDP headers, with room to spare.
+pub const MAX_DATAGRAM: usize = 1400;
+
+/// Destination of named metrics.
+pub trait MetricsSink {
+    /// Set gauge `name` to `v`.
+    fn gauge(&mut self, name: &str, v: f64);
+
+    /// Add `delta` to counter `name`.
+    fn counter(&mut self, name: &str, delta: u64);
+
+    /// Set counter `name` to the running total `total`.  By default the
+    /// total is added as an increment, which is only right for a sink
+    /// given one total per counter; sinks of increments flushed repeatedly
+    /// should override it to send the change, as [`StatsdSink`] does.
+    #[inline]
+    fn counter_total(&mut self, name: &str, total: u64) {
+        self.counter(name, total);
+    }
+
+    /// One line of help text for `name`, given before its first value.
+    /// Ignored unless the sink has somewhere to put it.
+    #[inline]
+    fn describe(&mut self, name: &str, help: &str) {
+        let _ = (name, help);
+    }
+}
+
+impl<S: MetricsSink + ?Sized> MetricsSink for &mut S {
+    #[inline]
+    fn gauge(&mut self, name: &str, v: f64) {
+        (**self).gauge(name, v);
+    }
+
+    #[inline]
+    fn counter(&mut self, name: &str, delta: u64) {
+        (**self).counter(name, delta);
+    }
+
+    #[inline]
+    fn counter_total(&mut self, name: &str, total: u64) {
+        (**self).counter_total(name, total);
+    }
+
+    #[inline]
+    fn describe(&mut self, name: &str, help: &str) {
+        (**self).describe(name, help);
+    }
+}
+
+/// Write `kernel`'s gauges (ε, φ, the rolling trigger ratio and the
+/// prefetch accuracy) and its [`KernelStats`] counters as
+/// [totals](MetricsSink::counter_total) since construction or the last
+/// reset, every name starting with `prefix`.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::metrics::{flush_metrics, PrometheusSink};
+/// use aether_link::AetherLinkKernel;
+///
+/// let mut kernel = AetherLinkKernel::default();
+/// for i in 0..100u64 {
+///     kernel.push_lba(i);
+/// }
+/// let mut sink = PrometheusSink::new();
+/// flush_metrics(&kernel, "aether", &mut sink);
+/// kernel.push_lba(100);
+/// flush_metrics(&kernel, "aether", &mut sink);
+/// assert!(sink.render().contains("aether_cycles_total 100\n"));
+/// ```
+pub fn flush_metrics(kernel: &AetherLinkKernel, prefix: &str, sink: &mut dyn MetricsSink) {
+    let stats = kernel.stats();
+    let gauges = [
+        ("epsilon", "Adaptive threshold.", kernel.epsilon as f64),
+        ("phi", "POVM basis angle (radians).", kernel.phi as f64),
+        (
+            "rolling_ratio",
+            "Trigger ratio over about the last 64 cycles.",
+            stats.rolling_ratio as f64,
+        ),
+        (
+            "prefetch_accuracy",
+            "Share of reported prefetch outcomes that were useful.",
+            kernel.prefetch_accuracy() as f64,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let name = format!("{prefix}_{name}");
+        sink.describe(&name, help);
+        sink.gauge(&name, value);
+    }
+    for (name, help, total) in counters(&stats) {
+        let name = format!("{prefix}_{name}");
+        sink.describe(&name, help);
+        sink.counter_total(&name, total);
+    }
+}
+
+/// Write the counters of `stats` as increments, every name starting with
+/// `prefix`; the rolling ratio, a gauge, is left out.
+pub fn flush_stats(stats: &KernelStats, prefix: &str, sink: &mut dyn MetricsSink) {
+    for (name, help, delta) in counters(stats) {
+        let name = format!("{prefix}_{name}");
+        sink.describe(&name, help);
+        sink.counter(&name, delta);
+    }
+}
+
+/// Name, help text and value of every counter in `stats`.
+fn counters(stats: &KernelStats) -> [(&'static str, &'static str, u64); 13] {
+    [
+        ("cycles_total", "Decision cycles.", stats.cycles),
+        (
+            "prefetches_total",
+            "Prefetches triggered.",
+            stats.prefetches,
+        ),
+        (
+            "suppressed_total",
+            "Cycles kept from firing by an alarm or a horizon plan.",
+            stats.suppressed,
+        ),
+        (
+            "degraded_total",
+            "Cycles on the degraded telemetry path.",
+            stats.degraded,
+        ),
+        (
+            "numeric_faults_total",
+            "Cycles with a non-finite fetch probability.",
+            stats.numeric_faults,
+        ),
+        (
+            "short_windows_total",
+            "Calls with fewer than two LBAs.",
+            stats.short_windows,
+        ),
+        (
+            "rereads_total",
+            "Cycles whose window ended where it began.",
+            stats.rereads,
+        ),
+        (
+            "out_of_range_total",
+            "Plans outside the address range.",
+            stats.out_of_range,
+        ),
+        ("hits_total", "Prefetches reported useful.", stats.hits),
+        (
+            "wasted_total",
+            "Prefetches reported evicted unused.",
+            stats.wasted,
+        ),
+        (
+            "missed_total",
+            "Demand misses on declined prefetches.",
+            stats.missed,
+        ),
+        (
+            "cancelled_total",
+            "Prefetches cancelled before they were issued.",
+            stats.cancelled,
+        ),
+        (
+            "late_hits_total",
+            "Prefetches cancelled because the demand read came first.",
+            stats.late_hits,
+        ),
+    ]
+}
+
+/// Value of a [`PrometheusSink`] metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Gauge(f64),
+    Counter(u64),
+}
+
+/// One metric of a [`PrometheusSink`].
+#[derive(Debug, Clone)]
+struct Metric {
+    name: String,
+    help: Option<String>,
+    value: Option<Value>,
+}
+
+/// Sink that keeps the latest gauges and the running counter totals and
+/// renders them as Prometheus text.
+///
+/// Metrics render in the order they were first named.  A name may carry
+/// labels (`aether_cycles_total{shard="3"}`); the `HELP` and `TYPE` lines
+/// go before the first series of each family.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusSink {
+    metrics: Vec<Metric>,
+}
+
+impl PrometheusSink {
+    /// Empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The text exposition of everything written so far.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut family = "";
+        for metric in &self.metrics {
+            let Some(value) = metric.value else {
+                continue;
+            };
+            let base = metric.name.split('{').next().unwrap_or_default();
+            if base != family {
+                family = base;
+                if let Some(help) = &metric.help {
+                    out += &format!("# HELP {base} {help}\n");
+                }
+                let kind = match value {
+                    Value::Gauge(_) => "gauge",
+                    Value::Counter(_) => "counter",
+                };
+                out += &format!("# TYPE {base} {kind}\n");
+            }
+            match value {
+                Value::Gauge(v) => out += &format!("{} {v}\n", metric.name),
+                Value::Counter(v) => out += &format!("{} {v}\n", metric.name),
+            }
+        }
+        out
+    }
+
+    /// Forget every metric.
+    pub fn clear(&mut self) {
+        self.metrics.clear();
+    }
+
+    fn metric(&mut self, name: &str) -> &mut Metric {
+        let i = match self.metrics.iter().position(|m| m.name == name) {
+            Some(i) => i,
+            None => {
+                self.metrics.push(Metric {
+                    name: name.to_owned(),
+                    help: None,
+                    value: None,
+                });
+                self.metrics.len() - 1
+            }
+        };
+        &mut self.metrics[i]
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn gauge(&mut self, name: &str, v: f64) {
+        self.metric(name).value = Some(Value::Gauge(v));
+    }
+
+    fn counter(&mut self, name: &str, delta: u64) {
+        let metric = self.metric(name);
+        let total = match metric.value {
+            Some(Value::Counter(total)) => total,
+            _ => 0,
+        };
+        metric.value = Some(Value::Counter(total.wrapping_add(delta)));
+    }
+
+    fn counter_total(&mut self, name: &str, total: u64) {
+        self.metric(name).value = Some(Value::Counter(total));
+    }
+
+    fn describe(&mut self, name: &str, help: &str) {
+        self.metric(name).help = Some(help.to_owned());
+    }
+}
+
+/// Where a [`StatsdSink`] sends its datagrams.
+#[cfg(feature = "std")]
+pub trait DatagramSocket {
+    /// Send one datagram.
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl DatagramSocket for UdpSocket {
+    /// Send to the connected address.
+    #[inline]
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()> {
+        self.send(datagram).map(drop)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DatagramSocket for Vec<Vec<u8>> {
+    /// Keep the datagram, e.g. to inspect it in tests.
+    #[inline]
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()> {
+        self.push(datagram.to_vec());
+        Ok(())
+    }
+}
+
+/// Sink that sends statsd lines (`name:value|g`, `name:delta|c`) over UDP.
+///
+/// Lines are buffered and packed, newline-separated, into datagrams of at
+/// most [`MAX_DATAGRAM`] bytes; a datagram goes out when the next line
+/// would not fit, on [`flush`](Self::flush) and when the sink is dropped.
+/// A line longer than the limit goes out alone.  Negative gauges are sent
+/// as a reset to 0 followed by the value, since statsd reads a signed
+/// gauge as a change; non-finite gauges are skipped.  A counter
+/// [total](MetricsSink::counter_total) is sent as its increase over the
+/// total last given for the same name (all of it after a drop, as when
+/// the stats were reset).  Send errors are counted, not returned, except
+/// by `flush`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use aether_link::metrics::{flush_metrics, StatsdSink};
+/// use aether_link::AetherLinkKernel;
+///
+/// let kernel = AetherLinkKernel::default();
+/// let mut sink = StatsdSink::connect("127.0.0.1:8125")?;
+/// flush_metrics(&kernel, "aether", &mut sink);
+/// sink.flush()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct StatsdSink<S: DatagramSocket = UdpSocket> {
+    socket: S,
+    buf: Vec<u8>,
+    max_datagram: usize,
+    send_errors: u64,
+    /// Counter totals last given, by name.
+    totals: HashMap<String, u64>,
+}
+
+#[cfg(feature = "std")]
+impl StatsdSink<UdpSocket> {
+    /// Sink sending to the statsd server at `addr` from an ephemeral
+    /// local port.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut last = None;
+        for addr in addr.to_socket_addrs()? {
+            let local = if addr.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            match UdpSocket::bind(local).and_then(|s| s.connect(addr).map(|()| s)) {
+                Ok(socket) => return Ok(Self::new(socket)),
+                Err(e) => last = Some(e),
+            }
+        }
+        Err(last.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to send to")
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: DatagramSocket> StatsdSink<S> {
+    /// Sink sending through `socket`.
+    pub fn new(socket: S) -> Self {
+        Self {
+            socket,
+            buf: Vec::with_capacity(MAX_DATAGRAM),
+            max_datagram: MAX_DATAGRAM,
+            send_errors: 0,
+            totals: HashMap::new(),
+        }
+    }
+
+    /// Pack datagrams up to `bytes` (at least 1) instead.
+    pub fn with_max_datagram(mut self, bytes: usize) -> Self {
+        self.max_datagram = bytes.max(1);
+        self
+    }
+
+    /// Send what is buffered.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let sent = self.socket.send_datagram(&self.buf);
+        self.buf.clear();
+        if sent.is_err() {
+            self.send_errors += 1;
+        }
+        sent
+    }
+
+    /// The socket the sink sends through.
+    #[inline]
+    pub fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    /// Datagrams that failed to send.
+    #[inline]
+    pub fn send_errors(&self) -> u64 {
+        self.send_errors
+    }
+
+    fn line(&mut self, line: &str) {
+        let needed = line.len() + !self.buf.is_empty() as usize;
+        if self.buf.len() + needed > self.max_datagram {
+            let _ = self.flush();
+        }
+        if !self.buf.is_empty() {
+            self.buf.push(b'\n');
+        }
+        self.buf.extend_from_slice(line.as_bytes());
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: DatagramSocket> MetricsSink for StatsdSink<S> {
+    fn gauge(&mut self, name: &str, v: f64) {
+        if !v.is_finite() {
+            return;
+        }
+        if v.is_sign_negative() && v != 0.0 {
+            self.line(&format!("{name}:0|g"));
+        }
+        self.line(&format!("{name}:{v}|g"));
+    }
+
+    fn counter(&mut self, name: &str, delta: u64) {
+        self.line(&format!("{name}:{delta}|c"));
+    }
+
+    fn counter_total(&mut self, name: &str, total: u64) {
+        let last = self.totals.insert(name.to_owned(), total).unwrap_or(0);
+        let delta = total.checked_sub(last).unwrap_or(total);
+        self.counter(name, delta);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: DatagramSocket> Drop for StatsdSink<S> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every call, in order.
+    #[derive(Default)]
+    struct Recorder(Vec<(String, f64)>);
+
+    impl MetricsSink for Recorder {
+        fn gauge(&mut self, name: &str, v: f64) {
+            self.0.push((name.to_owned(), v));
+        }
+
+        fn counter(&mut self, name: &str, delta: u64) {
+            self.0.push((name.to_owned(), delta as f64));
+        }
+    }
+
+    #[test]
+    fn test_flush_metrics_emits_the_standard_set() {
+        let mut kernel = AetherLinkKernel::new_hft();
+        for i in 0..200u64 {
+            if let Some(d) = kernel.push_lba(i * 8) {
+                if d.fire {
+                    kernel.record_prefetch_outcome(i % 4 != 0);
+                }
+            }
+        }
+        let mut sink = Recorder::default();
+        flush_metrics(&kernel, "aether", &mut sink);
+        let names: Vec<&str> = sink.0.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names.len(), 17);
+        assert_eq!(
+            names[..6],
+            [
+                "aether_epsilon",
+                "aether_phi",
+                "aether_rolling_ratio",
+                "aether_prefetch_accuracy",
+                "aether_cycles_total",
+                "aether_prefetches_total",
+            ]
+        );
+        assert_eq!(names[16], "aether_late_hits_total");
+        let value = |name: &str| sink.0.iter().find(|(n, _)| n == name).unwrap().1;
+        let stats = kernel.stats();
+        assert_eq!(value("aether_epsilon"), kernel.epsilon as f64);
+        assert_eq!(value("aether_cycles_total"), 199.0);
+        assert_eq!(value("aether_hits_total"), stats.hits as f64);
+        assert_eq!(
+            value("aether_prefetch_accuracy"),
+            kernel.prefetch_accuracy() as f64
+        );
+
+        // Prometheus counters accumulate increments and are set by
+        // totals, so flushing the totals again does not count twice;
+        // gauges keep the latest value.
+        let mut prometheus = PrometheusSink::new();
+        flush_stats(&stats, "aether", &mut prometheus);
+        flush_stats(&stats, "aether", &mut prometheus);
+        assert!(prometheus.render().contains("aether_cycles_total 398\n"));
+        flush_metrics(&kernel, "aether", &mut prometheus);
+        flush_metrics(&kernel, "aether", &mut prometheus);
+        let text = prometheus.render();
+        assert!(text.contains(
+            "# HELP aether_cycles_total Decision cycles.\n\
+             # TYPE aether_cycles_total counter\n\
+             aether_cycles_total 199\n"
+        ));
+        assert!(text.contains("# TYPE aether_epsilon gauge\n"), "{text}");
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 17);
+    }
+
+    #[test]
+    fn test_statsd_lines_and_datagram_boundaries() {
+        let mut sink = StatsdSink::new(Vec::new()).with_max_datagram(40);
+        sink.counter("a.cycles", 12); // 13 bytes
+        sink.gauge("a.eps", 0.5); // 11 bytes, 25 with the separator
+        sink.gauge("a.phi", -1.25); // 9 bytes (35), then 13: 49 > 40
+        sink.gauge("a.nan", f64::NAN);
+        sink.counter(&"x".repeat(50), 1);
+        sink.counter("a.tail", 3);
+        sink.flush().unwrap();
+        sink.flush().unwrap();
+        let datagrams: Vec<String> = sink
+            .socket()
+            .iter()
+            .map(|d| String::from_utf8(d.clone()).unwrap())
+            .collect();
+        assert_eq!(
+            datagrams,
+            [
+                "a.cycles:12|c\na.eps:0.5|g\na.phi:0|g",
+                "a.phi:-1.25|g",
+                &format!("{}:1|c", "x".repeat(50)),
+                "a.tail:3|c",
+            ]
+        );
+        assert!(datagrams.iter().all(|d| !d.ends_with('\n')));
+
+        // Exactly at the limit still fits.
+        let mut sink = StatsdSink::new(Vec::new()).with_max_datagram(12);
+        sink.counter("ab", 1);
+        sink.counter("c", 1);
+        sink.counter("d", 1);
+        sink.flush().unwrap();
+        assert_eq!(sink.socket()[..], [&b"ab:1|c\nc:1|c"[..], b"d:1|c"]);
+    }
+
+    #[test]
+    fn test_statsd_sends_totals_as_increments() {
+        /// The cycles line of one more `flush_metrics`.
+        fn cycles(kernel: &AetherLinkKernel, sink: &mut StatsdSink<Vec<Vec<u8>>>) -> String {
+            flush_metrics(kernel, "a", sink);
+            let sent = sink.socket().iter().rev();
+            let mut lines = sent.map(|d| String::from_utf8(d.clone()).unwrap());
+            lines.find(|l| l.starts_with("a_cycles_total:")).unwrap()
+        }
+
+        let mut kernel = AetherLinkKernel::default();
+        let mut sink = StatsdSink::new(Vec::new()).with_max_datagram(1);
+        for i in 0..100u64 {
+            kernel.push_lba(i);
+        }
+        assert_eq!(cycles(&kernel, &mut sink), "a_cycles_total:99|c");
+        assert_eq!(cycles(&kernel, &mut sink), "a_cycles_total:0|c");
+        kernel.push_lba(100);
+        assert_eq!(cycles(&kernel, &mut sink), "a_cycles_total:1|c");
+        // After a reset the total starts over.
+        kernel.reset_stats();
+        kernel.push_lba(101);
+        kernel.push_lba(102);
+        assert_eq!(cycles(&kernel, &mut sink), "a_cycles_total:2|c");
+    }
+
+    #[test]
+    fn test_statsd_over_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let kernel = AetherLinkKernel::default();
+        {
+            let mut sink = StatsdSink::connect(server.local_addr().unwrap()).unwrap();
+            flush_metrics(&kernel, "aether", &mut sink);
+            // Dropping the sink sends what is left.
+        }
+        let mut buf = [0u8; 2048];
+        let len = server.recv(&mut buf).unwrap();
+        assert!(len <= MAX_DATAGRAM);
+        let text = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(text.starts_with("aether_epsilon:0.5|g\n"), "{text}");
+        assert_eq!(text.lines().count(), 17);
+    }
+}
@@ -0,0 +1,230 @@
+//! Micro-batched decisions.
+//!
+//! Completion handlers often see 8–32 LBAs of one stream at once.
+//! [`AetherLinkKernel::process_micro_batch`] pushes them all into the
+//! history ring, computes telemetry once over a window that covers the
+//! whole batch, and takes one adaptation step scaled so that ε and φ move
+//! about as far as they would have over the `k` per-LBA cycles the batch
+//! replaces.  The result is one decision for the batch plus an extent
+//! covering its predicted continuation.
+//!
+//! The ε step does not depend on ε, so it is multiplied by `k`.  The φ
+//! step is a pull towards the measured angle (E₂ = sin(θ/2 − φ)), and `k`
+//! such steps close all but `(1 − λ₂)^k` of the gap, so φ's step is
+//! multiplied by `(1 − (1 − λ₂)^k) / λ₂` instead: close to `k` for small
+//! batches, never more than `1/λ₂`, and never overshooting where `k` times
+//! the step would.
+
+use crate::prefetcher::PrefetchPlan;
+use crate::{direction, AetherLinkKernel, CycleOutcome};
+
+/// Largest mean stride (blocks between consecutive batch entries) for
+/// which a continuation extent is suggested; wider batches look random.
+pub const MAX_BATCH_STRIDE: u64 = 64;
+
+/// Decision for one micro-batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MicroBatchDecision {
+    /// Whether to prefetch.
+    pub fire: bool,
+    /// The cycle behind the decision; `None` while the history is still
+    /// shorter than two LBAs (or for an empty batch), when no cycle runs.
+    pub outcome: Option<CycleOutcome>,
+    /// Blocks continuing the batch at its mean stride (after it, or below it
+    /// for a descending batch), as many as the batch held, when the
+    /// decision fires and the mean stride is at most [`MAX_BATCH_STRIDE`].
+    pub extent: Option<PrefetchPlan>,
+}
+
+impl MicroBatchDecision {
+    const NONE: Self = Self {
+        fire: false,
+        outcome: None,
+        extent: None,
+    };
+}
+
+impl AetherLinkKernel {
+    /// Feed `lbas` (oldest first) and run one decision cycle for all of
+    /// them.
+    ///
+    /// The telemetry window is the usual [`window`](Self::window) ending at
+    /// the last LBA, widened to reach back to the LBA before the batch when
+    /// the batch is longer than the window.  The open-loop ε step is
+    /// multiplied by `lbas.len()` and the φ rotation by the equivalent gain
+    /// of that many steps (see the [module docs](self)).  `cycles` counts
+    /// the batch as one cycle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// let batch: Vec<u64> = (1_000..1_016).collect();
+    /// let d = kernel.process_micro_batch(&batch);
+    /// assert_eq!(kernel.cycles, 1);
+    /// if let Some(extent) = d.extent {
+    ///     assert_eq!((extent.start, extent.len), (1_016, 16));
+    /// }
+    /// ```
+    pub fn process_micro_batch(&mut self, lbas: &[u64]) -> MicroBatchDecision {
+        self.guarded(|kernel| kernel.micro_batch(lbas))
+    }
+
+    /// [`process_micro_batch`](Self::process_micro_batch) without the
+    /// entry-point guard.
+    #[inline(always)]
+    fn micro_batch(&mut self, lbas: &[u64]) -> MicroBatchDecision {
+        let (Some(&head), Some(&tail)) = (lbas.first(), lbas.last()) else {
+            self.counters.short_window();
+            return MicroBatchDecision::NONE;
+        };
+        let before = (self.history.len() > 0).then(|| self.history.span().1);
+        for &lba in lbas {
+            self.history.push(lba);
+        }
+        if self.history.len() < 2 {
+            self.counters.short_window();
+            return MicroBatchDecision::NONE;
+        }
+        let weights = [lbas.len() as f32, phi_gain(self.lambda[1], lbas.len())];
+        let outcome = self.timed(|kernel| {
+            let (mut first, last) = kernel.history.span();
+            if lbas.len() >= kernel.history.len() {
+                first = before.unwrap_or(head);
+            }
+            let telemetry = kernel.span_telemetry(first, last);
+            kernel.decide_weighted(telemetry, 0.0, weights)
+        });
+        let extent = outcome
+            .fetch
+            .then(|| continuation(head, tail, lbas.len() as u64))
+            .flatten()
+            .and_then(|plan| self.bound_plan(plan));
+        MicroBatchDecision {
+            fire: outcome.fetch,
+            outcome: Some(outcome),
+            extent,
+        }
+    }
+}
+
+/// Total gain of `k` φ steps with rate `lambda`, relative to one.
+fn phi_gain(lambda: f32, k: usize) -> f32 {
+    if lambda <= 0.0 {
+        return k as f32;
+    }
+    let mut remaining = 1.0;
+    for _ in 0..k {
+        remaining *= 1.0 - lambda.min(1.0);
+    }
+    (1.0 - remaining) / lambda
+}
+
+/// `n` blocks' worth of the batch's mean stride past `tail`: after it for
+/// a batch running forward, below it for a descending one.
+fn continuation(head: u64, tail: u64, n: u64) -> Option<PrefetchPlan> {
+    let stride = (tail.abs_diff(head) / n.saturating_sub(1).max(1)).max(1);
+    if stride > MAX_BATCH_STRIDE {
+        return None;
+    }
+    if tail < head {
+        Some(direction::below(tail, stride * n)).filter(|plan| !plan.is_empty())
+    } else {
+        Some(PrefetchPlan::new(tail.saturating_add(1), stride * n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    /// Sequential runs broken by random jumps, in completion-sized batches.
+    fn trace(seed: u64, n: usize) -> Vec<u64> {
+        let mut rng = SplitMix64::new(seed);
+        let mut lba = 0u64;
+        (0..n)
+            .map(|_| {
+                lba = if rng.below(8) == 0 {
+                    rng.below(1 << 24)
+                } else {
+                    lba + 1 + rng.below(3)
+                };
+                lba
+            })
+            .collect()
+    }
+
+    /// Mean |Δε| and |Δφ| between a per-LBA kernel and a batched one,
+    /// sampled at batch boundaries.  Unweighted batches take one plain
+    /// step each.
+    fn drift(kernel: &AetherLinkKernel, batch: usize, weighted: bool) -> (f32, f32) {
+        let lbas = trace(3, 32_000);
+        let mut single = kernel.clone();
+        let mut batched = kernel.clone();
+        let (mut de, mut dp, mut n) = (0.0, 0.0, 0);
+        for chunk in lbas.chunks(batch) {
+            for &lba in chunk {
+                single.push_lba(lba);
+            }
+            if weighted {
+                batched.process_micro_batch(chunk);
+            } else {
+                for &lba in chunk {
+                    batched.history.push(lba);
+                }
+                let (first, last) = batched.history.span();
+                let t = batched.span_telemetry(first, last);
+                batched.decide(t, 0.0);
+            }
+            de += (single.epsilon - batched.epsilon).abs();
+            let d = (single.phi - batched.phi).abs();
+            dp += d.min(std::f32::consts::TAU - d);
+            n += 1;
+        }
+        (de / n as f32, dp / n as f32)
+    }
+
+    #[test]
+    fn test_weighted_batches_track_per_element_adaptation() {
+        let presets = [
+            AetherLinkKernel::default(),
+            AetherLinkKernel::new_gaming(),
+            AetherLinkKernel::new_hft(),
+        ];
+        for kernel in &presets {
+            for batch in [8, 16, 32] {
+                let (de, dp) = drift(kernel, batch, true);
+                let (de_flat, dp_flat) = drift(kernel, batch, false);
+                assert!(de < 0.01, "batch {batch}: ε drift {de}");
+                assert!(dp < 0.1, "batch {batch}: φ drift {dp}");
+                assert!(
+                    de + dp < de_flat + dp_flat,
+                    "batch {batch}: ({de}, {dp}) vs unweighted ({de_flat}, {dp_flat})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_extent_and_warmup() {
+        let mut kernel = AetherLinkKernel::default();
+        assert_eq!(kernel.process_micro_batch(&[]), MicroBatchDecision::NONE);
+        assert!(kernel.process_micro_batch(&[7]).outcome.is_none());
+        assert_eq!(kernel.cycles, 0);
+        assert!(kernel.process_micro_batch(&[8]).outcome.is_some());
+
+        assert_eq!(phi_gain(0.0, 8), 8.0);
+        assert_eq!(phi_gain(1.0, 8), 1.0);
+        assert!((phi_gain(0.01, 8) - 7.7255).abs() < 1e-3);
+
+        assert_eq!(continuation(100, 114, 8), Some(PrefetchPlan::new(115, 16)));
+        assert_eq!(continuation(5, 5, 1), Some(PrefetchPlan::new(6, 1)));
+        assert_eq!(continuation(200, 152, 4), Some(PrefetchPlan::new(88, 64)));
+        assert_eq!(continuation(40, 10, 4), Some(PrefetchPlan::new(0, 10)));
+        assert_eq!(continuation(30, 0, 4), None, "nothing below 0");
+        assert_eq!(continuation(0, 1 << 20, 4), None, "random");
+    }
+}
@@ -0,0 +1,422 @@
+//! Operating mode: which regime the kernel believes it is in.
+//!
+//! [`AetherLinkKernel::mode`] reports one of four [`KernelMode`]s, moved
+//! between by three signals:
+//!
+//! - the **rolling trigger ratio** r of [`KernelStats`](crate::KernelStats)
+//!   (about the last 64 cycles); the kernel is *decisive* while
+//!   `|2r − 1| ≥ `[`decisiveness`](ModeOptions::decisiveness), i.e. it
+//!   nearly always fires or nearly never does;
+//! - the **span fidelity** F, how closely each window's span matches the
+//!   previous one: `min(Δₜ, Δₜ₋₁) / max(Δₜ, Δₜ₋₁)` (1 for two empty
+//!   spans), averaged with weight 1/16 on the newest window.  A run or a
+//!   fixed stride keeps F at 1; scattered accesses hold it near 0.4;
+//! - **drift**: F below [`drift_fidelity`](ModeOptions::drift_fidelity), or
+//!   a raised [anomaly alarm](AetherLinkKernel::anomaly_alarm) when
+//!   detection is on.
+//!
+//! The transitions, checked once per decision cycle after the cycle is
+//! counted (and by [`tick`](AetherLinkKernel::tick) for idle time):
+//!
+//! | From | To | When |
+//! |------|----|------|
+//! | any but Idle | Idle | an idle gap of at least [`idle_after`](ModeOptions::idle_after) is reported |
+//! | Idle | Ramp | any cycle; F restarts at 1 |
+//! | Ramp, Steady | Turbulent | the cycle drifts |
+//! | Ramp | Steady | F ≥ [`steady_fidelity`](ModeOptions::steady_fidelity) and decisive for [`steady_cycles`](ModeOptions::steady_cycles) cycles in a row |
+//! | Steady | Ramp | no longer decisive, without drift |
+//! | Turbulent | Ramp | [`settle_cycles`](ModeOptions::settle_cycles) cycles in a row without drift |
+//!
+//! A new kernel starts Idle.  A [`ModeHook`] set with
+//! [`set_mode_hook`](AetherLinkKernel::set_mode_hook) is called with every
+//! [`ModeTransition`].  With [`ModeOptions::gains`], the mode also scales
+//! the ε and φ steps: faster while a pattern forms, slower once locked on.
+//! Without gains the mode only observes, and decisions are exactly those
+//! of a kernel that never looked.
+
+use crate::AetherLinkKernel;
+
+/// Weight of the newest window in the span fidelity.
+const FIDELITY_ALPHA: f32 = 1.0 / 16.0;
+
+/// Regime the kernel believes it is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KernelMode {
+    /// No recent activity.
+    #[default]
+    Idle,
+    /// A pattern forming, not trusted yet.
+    Ramp,
+    /// Locked onto a pattern.
+    Steady,
+    /// The pattern recently drifted.
+    Turbulent,
+}
+
+/// Multipliers on the ε and φ steps by mode; Idle and Turbulent keep the
+/// configured rates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeGains {
+    /// Step multiplier while ramping.
+    pub ramp: f32,
+    /// Step multiplier while steady.
+    pub steady: f32,
+}
+
+impl Default for ModeGains {
+    fn default() -> Self {
+        Self {
+            ramp: 2.0,
+            steady: 0.5,
+        }
+    }
+}
+
+/// Knobs for the mode machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeOptions {
+    /// Idle gap, in the unit of [`tick`](AetherLinkKernel::tick), that
+    /// sends the kernel back to Idle; `None` never does.
+    pub idle_after: Option<u64>,
+    /// Span fidelity a steady pattern holds.
+    pub steady_fidelity: f32,
+    /// Span fidelity below which the pattern counts as drifting.
+    pub drift_fidelity: f32,
+    /// Least `|2r − 1|` of the rolling trigger ratio r for a steady
+    /// pattern.
+    pub decisiveness: f32,
+    /// Cycles in a row a pattern must hold before Ramp turns Steady.
+    pub steady_cycles: u32,
+    /// Cycles in a row without drift before Turbulent calms to Ramp.
+    pub settle_cycles: u32,
+    /// Adaptation-rate multipliers; `None` leaves the rates alone.
+    pub gains: Option<ModeGains>,
+}
+
+impl Default for ModeOptions {
+    fn default() -> Self {
+        Self {
+            idle_after: None,
+            steady_fidelity: 0.9,
+            drift_fidelity: 0.6,
+            decisiveness: 0.8,
+            steady_cycles: 64,
+            settle_cycles: 64,
+            gains: None,
+        }
+    }
+}
+
+/// One change of mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeTransition {
+    /// Kernel cycles counted when it happened.
+    pub cycle: u64,
+    /// Mode left.
+    pub from: KernelMode,
+    /// Mode entered.
+    pub to: KernelMode,
+}
+
+/// Called with each mode transition.
+pub type ModeHook = fn(ModeTransition);
+
+/// Mode, signals and the hook.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ModeTracker {
+    opts: ModeOptions,
+    hook: Option<ModeHook>,
+    mode: KernelMode,
+    fidelity: f32,
+    last_span: f32,
+    /// Cycles in a row meeting the condition to leave the current mode.
+    run: u32,
+    /// Newest timestamp seen by [`AetherLinkKernel::idle_gap`].
+    last_seen: Option<u64>,
+    /// Transition made by the cycle in progress, for the hook once the
+    /// cycle is done.
+    pending: Option<ModeTransition>,
+}
+
+impl ModeTracker {
+    /// Fold a window span into the fidelity.
+    #[inline(always)]
+    pub(crate) fn observe(&mut self, span: f32) {
+        let (lo, hi) = (span.min(self.last_span), span.max(self.last_span));
+        let f = if hi > 0.0 { lo / hi } else { 1.0 };
+        self.last_span = span;
+        if self.mode == KernelMode::Idle {
+            self.fidelity = 1.0;
+        } else {
+            self.fidelity += FIDELITY_ALPHA * (f - self.fidelity);
+        }
+    }
+
+    /// ε and φ step weights for the current mode.
+    #[inline(always)]
+    pub(crate) fn scale(&self, weights: [f32; 2]) -> [f32; 2] {
+        let gain = match (self.opts.gains, self.mode) {
+            (Some(g), KernelMode::Ramp) => g.ramp,
+            (Some(g), KernelMode::Steady) => g.steady,
+            _ => 1.0,
+        };
+        weights.map(|w| w * gain)
+    }
+
+    /// Advance the machine after cycle `cycle` with rolling trigger ratio
+    /// `ratio` and anomaly alarm `alarm`.
+    #[inline]
+    pub(crate) fn decided(&mut self, cycle: u64, ratio: f32, alarm: bool) {
+        let opts = &self.opts;
+        let drift = (self.fidelity < opts.drift_fidelity) | alarm;
+        let decisive = (2.0 * ratio - 1.0).abs() >= opts.decisiveness;
+        let locked = (self.fidelity >= opts.steady_fidelity) & decisive;
+        let next = match self.mode {
+            KernelMode::Idle => Some(KernelMode::Ramp),
+            KernelMode::Ramp | KernelMode::Steady if drift => Some(KernelMode::Turbulent),
+            KernelMode::Ramp => {
+                self.run = if locked {
+                    self.run.saturating_add(1)
+                } else {
+                    0
+                };
+                (locked && self.run >= opts.steady_cycles).then_some(KernelMode::Steady)
+            }
+            KernelMode::Steady => (!decisive).then_some(KernelMode::Ramp),
+            KernelMode::Turbulent => {
+                self.run = if drift { 0 } else { self.run.saturating_add(1) };
+                (!drift && self.run >= opts.settle_cycles).then_some(KernelMode::Ramp)
+            }
+        };
+        if let Some(to) = next {
+            self.enter(cycle, to);
+        }
+    }
+
+    /// Account for `gap` units of idle time.
+    pub(crate) fn idle(&mut self, cycle: u64, gap: u64) {
+        if self.opts.idle_after.is_some_and(|after| gap >= after) {
+            self.enter(cycle, KernelMode::Idle);
+            self.flush();
+        }
+    }
+
+    /// Gap since the previous timestamp; the first one only starts the
+    /// clock, and timestamps that go backwards count as no gap.
+    pub(crate) fn stamp(&mut self, now: u64) -> u64 {
+        let gap = self.last_seen.map_or(0, |last| now.saturating_sub(last));
+        self.last_seen = Some(self.last_seen.map_or(now, |last| last.max(now)));
+        gap
+    }
+
+    fn enter(&mut self, cycle: u64, to: KernelMode) {
+        let from = self.mode;
+        if from == to {
+            return;
+        }
+        self.mode = to;
+        self.run = 0;
+        self.pending = Some(ModeTransition { cycle, from, to });
+    }
+
+    /// Call the hook with the pending transition, if any.  Cycles defer
+    /// the call to here so that it runs outside the
+    /// [panic-free](crate#panics) part of the entry point.
+    #[inline(always)]
+    pub(crate) fn flush(&mut self) {
+        if let (Some(transition), Some(hook)) = (self.pending.take(), self.hook) {
+            hook(transition);
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Regime the kernel is in; see [`mode`](crate::mode) for the
+    /// transitions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::mode::KernelMode;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// assert_eq!(kernel.mode(), KernelMode::Idle);
+    /// for lba in 0..500u64 {
+    ///     kernel.push_lba(lba);
+    /// }
+    /// assert_eq!(kernel.mode(), KernelMode::Steady);
+    /// ```
+    #[inline]
+    pub fn mode(&self) -> KernelMode {
+        self.mode.mode
+    }
+
+    /// Span fidelity the mode machine currently sees, in [0, 1].
+    #[inline]
+    pub fn span_fidelity(&self) -> f32 {
+        self.mode.fidelity
+    }
+
+    /// Replace the mode machine's options.  The current mode is kept.
+    pub fn set_mode_options(&mut self, opts: ModeOptions) {
+        self.mode.opts = opts;
+        self.mode.run = 0;
+    }
+
+    /// Options of the mode machine.
+    #[inline]
+    pub fn mode_options(&self) -> ModeOptions {
+        self.mode.opts
+    }
+
+    /// Call `hook` with every mode transition, e.g. to log regime changes.
+    /// A transition made by a cycle is reported just before the call that
+    /// ran the cycle returns.
+    pub fn set_mode_hook(&mut self, hook: ModeHook) {
+        self.mode.hook = Some(hook);
+    }
+
+    /// Stop calling the mode hook.
+    #[inline]
+    pub fn clear_mode_hook(&mut self) {
+        self.mode.hook = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use std::sync::Mutex;
+
+    /// The kernel's current φ step from the same state, as an angle.
+    fn phi_step(kernel: &AetherLinkKernel, window: &[u64]) -> f32 {
+        let mut probe = kernel.clone();
+        probe.process_io_cycle(window);
+        let d = (probe.phi - kernel.phi).rem_euclid(core::f32::consts::TAU);
+        d.min(core::f32::consts::TAU - d)
+    }
+
+    #[test]
+    fn test_silence_sequential_random_sequence() {
+        static SEEN: Mutex<Vec<ModeTransition>> = Mutex::new(Vec::new());
+        fn hook(t: ModeTransition) {
+            SEEN.lock().unwrap().push(t);
+        }
+
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_mode_options(ModeOptions {
+            idle_after: Some(1_000),
+            ..ModeOptions::default()
+        });
+        kernel.set_mode_hook(hook);
+        let mut rng = SplitMix64::new(5);
+        let sequential = |from: u64| (from..from + 2_000).collect::<Vec<u64>>();
+        // Each phase follows the previous after a gap; the last one after
+        // a silence.
+        let phases = [
+            (1, sequential(10_000)),
+            (1, (0..2_000).map(|_| rng.below(1 << 30)).collect()),
+            (1, sequential(50_000_000)),
+            (5_000, sequential(90_000_000)),
+        ];
+        let mut now = 0;
+        let mut modes = vec![kernel.mode()];
+        for (gap, lbas) in phases {
+            now += gap;
+            for lba in lbas {
+                kernel.push_lba_at(lba, now);
+                now += 1;
+                if modes.last() != Some(&kernel.mode()) {
+                    modes.push(kernel.mode());
+                }
+            }
+        }
+
+        use KernelMode::*;
+        let seen = SEEN.lock().unwrap();
+        let hooked: Vec<KernelMode> = seen.iter().map(|t| t.to).collect();
+        assert_eq!(
+            hooked,
+            [Ramp, Steady, Turbulent, Ramp, Steady, Idle, Ramp, Steady]
+        );
+        assert!(seen.windows(2).all(|w| w[0].to == w[1].from));
+        // The silence is only Idle between its end and the next cycle.
+        assert_eq!(
+            modes,
+            [Idle, Ramp, Steady, Turbulent, Ramp, Steady, Ramp, Steady]
+        );
+        assert_eq!(seen[5].cycle + 1, seen[6].cycle);
+        // The first access only fills the window; the first cycle ramps.
+        assert_eq!(seen[0].cycle, 1);
+        // Steady after `steady_cycles` locked cycles at the earliest, and
+        // turbulence within a few dozen scattered accesses.
+        assert!(seen[1].cycle > 64, "{seen:?}");
+        assert!(seen[2].cycle < 2_000 + 64, "{seen:?}");
+        drop(seen);
+
+        // Explicit idle time counts from `idle_after` on.
+        kernel.tick(999);
+        assert_eq!(kernel.mode(), Steady);
+        kernel.tick(1_000);
+        assert_eq!(kernel.mode(), Idle);
+        assert_eq!(kernel.mode_options().idle_after, Some(1_000));
+    }
+
+    #[test]
+    fn test_gains_scale_steps_by_mode() {
+        let gains = ModeGains {
+            ramp: 2.0,
+            steady: 0.25,
+        };
+        let mut plain = AetherLinkKernel::default();
+        let mut scaled = AetherLinkKernel::default();
+        scaled.set_mode_options(ModeOptions {
+            gains: Some(gains),
+            ..ModeOptions::default()
+        });
+        let mut rng = SplitMix64::new(9);
+        let mut checked = [0; 3];
+        let mut lbas: Vec<u64> = (0..1_000).collect();
+        lbas.extend((0..1_000).map(|_| rng.below(1 << 30)));
+        for w in lbas.windows(4) {
+            // Same state, so only the gain differs between the two steps.
+            plain.clone_from(&scaled);
+            plain.set_mode_options(ModeOptions::default());
+            let (base, step) = (phi_step(&plain, w), phi_step(&scaled, w));
+            let (gain, slot) = match scaled.mode() {
+                KernelMode::Ramp => (gains.ramp, 0),
+                KernelMode::Steady => (gains.steady, 1),
+                _ => (1.0, 2),
+            };
+            if base > 1e-5 {
+                assert!(
+                    (step - gain * base).abs() <= 1e-2 * gain * base + 1e-6,
+                    "{w:?}"
+                );
+                checked[slot] += 1;
+            }
+            scaled.process_io_cycle(w);
+        }
+        assert!(checked.iter().all(|&n| n > 10), "{checked:?}");
+
+        // Without gains the mode never touches the decisions.
+        let mut observed = AetherLinkKernel::default();
+        let mut reference = AetherLinkKernel::default();
+        reference.set_mode_options(ModeOptions {
+            steady_cycles: 1,
+            drift_fidelity: 0.9,
+            ..ModeOptions::default()
+        });
+        for w in lbas.windows(4) {
+            assert_eq!(observed.process_io_cycle(w), reference.process_io_cycle(w));
+        }
+        assert_eq!(observed.epsilon.to_bits(), reference.epsilon.to_bits());
+        assert_eq!(observed.phi.to_bits(), reference.phi.to_bits());
+    }
+}
@@ -0,0 +1,46 @@
+//! Link-time proof that the decision path cannot panic.
+//!
+//! The functions listed under "Panics" in the crate docs open a [`Guard`]
+//! on entry and disarm it before returning.  With the `no-panic` feature in
+//! an optimised build, dropping an armed guard calls a symbol that exists
+//! nowhere, and the only way to drop it armed is to unwind out of the
+//! guarded code.  If the optimiser can prove no panic is reachable, the
+//! call is removed and the build links; otherwise linking fails with the
+//! symbol name as the error.  The same technique is used by the
+//! `no-panic` crate.
+//!
+//! Run the check with
+//! `cargo test --release --features no-panic --test no_panic`.  Debug
+//! builds keep overflow checks and skip inlining, so the guard is inert
+//! there, as it is without the feature.
+
+/// Armed on entry to a guarded function; see the module docs.
+pub(crate) struct Guard;
+
+impl Guard {
+    #[inline(always)]
+    pub(crate) fn arm() -> Self {
+        Guard
+    }
+
+    /// The guarded code returned normally.
+    #[inline(always)]
+    #[allow(clippy::forget_non_drop)] // `Drop` only exists with the feature.
+    pub(crate) fn disarm(self) {
+        core::mem::forget(self);
+    }
+}
+
+#[cfg(all(feature = "no-panic", not(debug_assertions)))]
+impl Drop for Guard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        extern "C" {
+            #[link_name = "\n\nERROR[aether-link]: a function guarded by `no_panic::Guard` may panic\n\n"]
+            fn may_panic() -> !;
+        }
+        // SAFETY: never called: the call is only linked if it is reachable,
+        // and linking it fails.
+        unsafe { may_panic() }
+    }
+}
@@ -0,0 +1,271 @@
+//! NUMA topology and node-local placement.
+//!
+//! On a multi-socket machine, memory lives on one node and every access
+//! from a CPU on another node crosses the interconnect.
+//! [`NumaTopology`] reads which CPUs belong to which node from sysfs
+//! (`/sys/devices/system/node/node*/cpulist`), and
+//! [`ShardedKernelPool::with_topology`](crate::ShardedKernelPool::with_topology)
+//! uses it to assign shards to nodes and map CPUs to local shards.
+//!
+//! Placing memory and pinning threads needs `mbind(2)` and
+//! `sched_setaffinity(2)`, available with the `linux-io` feature on Linux.
+//! Everywhere else — and whenever the calls fail — placement and
+//! [`pin_current_thread`] return `false` and nothing changes, so callers
+//! fall back to the plain, non-NUMA behaviour without checking.
+
+use std::io;
+use std::path::Path;
+
+/// Where Linux publishes the node topology.
+pub const SYSFS_NODE_DIR: &str = "/sys/devices/system/node";
+
+/// One NUMA node and its CPUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaNode {
+    /// Kernel node id (`N` in `nodeN`).
+    pub id: usize,
+    /// CPU ids, ascending.
+    pub cpus: Vec<usize>,
+}
+
+/// CPUs grouped by NUMA node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaTopology {
+    nodes: Vec<NumaNode>,
+}
+
+impl NumaTopology {
+    /// The running machine's topology, or `None` if sysfs does not
+    /// describe one (non-Linux, containers without `/sys`).
+    pub fn detect() -> Option<Self> {
+        Self::from_sysfs(Path::new(SYSFS_NODE_DIR)).ok()
+    }
+
+    /// Read `nodeN/cpulist` under `dir`.  Nodes without CPUs
+    /// (memory-only) are kept with an empty CPU list.
+    pub fn from_sysfs(dir: &Path) -> io::Result<Self> {
+        let mut nodes = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(id) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("node"))
+                .and_then(|n| n.parse().ok())
+            else {
+                continue;
+            };
+            let list = std::fs::read_to_string(entry.path().join("cpulist"))?;
+            let cpus = parse_cpulist(&list).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("node{id}: bad cpulist {:?}", list.trim()),
+                )
+            })?;
+            nodes.push(NumaNode { id, cpus });
+        }
+        Self::new(nodes).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no nodeN entries in {}", dir.display()),
+            )
+        })
+    }
+
+    /// Topology of the given nodes, e.g. to describe a machine by hand.
+    /// Sorted by id, CPU lists sorted.  `None` without nodes.
+    pub fn new(mut nodes: Vec<NumaNode>) -> Option<Self> {
+        if nodes.is_empty() {
+            return None;
+        }
+        for node in &mut nodes {
+            node.cpus.sort_unstable();
+            node.cpus.dedup();
+        }
+        nodes.sort_by_key(|n| n.id);
+        Some(Self { nodes })
+    }
+
+    /// One node (id 0) holding CPUs `0..cpus`.
+    pub fn single_node(cpus: usize) -> Self {
+        Self {
+            nodes: vec![NumaNode {
+                id: 0,
+                cpus: (0..cpus).collect(),
+            }],
+        }
+    }
+
+    /// Nodes in ascending id order.
+    #[inline]
+    pub fn nodes(&self) -> &[NumaNode] {
+        &self.nodes
+    }
+
+    /// Number of nodes (at least one).
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always `false`: a topology has at least one node.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Id of the node `cpu` belongs to.
+    pub fn node_of_cpu(&self, cpu: usize) -> Option<usize> {
+        self.nodes
+            .iter()
+            .find(|n| n.cpus.binary_search(&cpu).is_ok())
+            .map(|n| n.id)
+    }
+}
+
+/// Parse a kernel CPU list such as `"0-3,8,10-11"`.  An empty list is
+/// valid (memory-only node).
+pub fn parse_cpulist(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+    let mut cpus = Vec::new();
+    if list.is_empty() {
+        return Some(cpus);
+    }
+    for part in list.split(',') {
+        match part.split_once('-') {
+            Some((lo, hi)) => {
+                let (lo, hi): (usize, usize) = (lo.parse().ok()?, hi.parse().ok()?);
+                if lo > hi {
+                    return None;
+                }
+                cpus.extend(lo..=hi);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Some(cpus)
+}
+
+/// Ask the kernel to back `len` bytes at `ptr` (page-aligned) with memory
+/// from `node`, moving pages already touched.  `false` if unsupported or
+/// refused.
+#[cfg(all(target_os = "linux", feature = "linux-io"))]
+pub(crate) fn bind_to_node(ptr: *mut u8, len: usize, node: usize) -> bool {
+    const MPOL_PREFERRED: libc::c_long = 1;
+    const MPOL_MF_MOVE: libc::c_ulong = 1 << 1;
+    const MASK_BITS: usize = 1024;
+    if node >= MASK_BITS {
+        return false;
+    }
+    let mut mask = [0 as libc::c_ulong; MASK_BITS / 64];
+    mask[node / 64] |= 1 << (node % 64);
+    // SAFETY: mbind only changes the placement policy of the range; the
+    // mask outlives the call and `maxnode` matches its size.
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            ptr,
+            len,
+            MPOL_PREFERRED,
+            mask.as_ptr(),
+            MASK_BITS as libc::c_ulong,
+            MPOL_MF_MOVE,
+        )
+    };
+    rc == 0
+}
+
+/// Without `linux-io` on Linux there is no placement; always `false`.
+#[cfg(not(all(target_os = "linux", feature = "linux-io")))]
+pub(crate) fn bind_to_node(ptr: *mut u8, len: usize, node: usize) -> bool {
+    let _ = (ptr, len, node);
+    false
+}
+
+/// Restrict the calling thread to the CPUs of `node` in `topology`.
+/// `false` if unsupported, the node is unknown or has no CPUs.
+#[cfg(all(target_os = "linux", feature = "linux-io"))]
+pub fn pin_current_thread(topology: &NumaTopology, node: usize) -> bool {
+    let Some(node) = topology.nodes.iter().find(|n| n.id == node) else {
+        return false;
+    };
+    if node.cpus.is_empty() {
+        return false;
+    }
+    // SAFETY: cpu_set_t is plain data; CPU_SET bounds-checks the index.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in &node.cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+/// Without `linux-io` on Linux threads cannot be pinned; always `false`.
+#[cfg(not(all(target_os = "linux", feature = "linux-io")))]
+pub fn pin_current_thread(topology: &NumaTopology, node: usize) -> bool {
+    let _ = (topology, node);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn fake_sysfs(nodes: &[(usize, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for &(id, list) in nodes {
+            let node = dir.path().join(format!("node{id}"));
+            fs::create_dir(&node).unwrap();
+            fs::write(node.join("cpulist"), format!("{list}\n")).unwrap();
+        }
+        fs::write(dir.path().join("online"), "0\n").unwrap();
+        fs::create_dir(dir.path().join("power")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_cpulist() {
+        assert_eq!(
+            parse_cpulist("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpulist("5"), Some(vec![5]));
+        assert_eq!(parse_cpulist(""), Some(vec![]));
+        assert_eq!(parse_cpulist("3-1"), None);
+        assert_eq!(parse_cpulist("0-x"), None);
+    }
+
+    #[test]
+    fn test_single_node_machine() {
+        let dir = fake_sysfs(&[(0, "0-7")]);
+        let topo = NumaTopology::from_sysfs(dir.path()).unwrap();
+        assert_eq!(topo, NumaTopology::single_node(8));
+        assert_eq!(topo.node_of_cpu(7), Some(0));
+        assert_eq!(topo.node_of_cpu(8), None);
+    }
+
+    #[test]
+    fn test_two_sockets_and_memory_only_node() {
+        let dir = fake_sysfs(&[(1, "8-15,24-31"), (0, "0-7,16-23"), (2, "")]);
+        let topo = NumaTopology::from_sysfs(dir.path()).unwrap();
+        assert_eq!(topo.len(), 3);
+        assert_eq!(topo.nodes()[0].id, 0);
+        assert_eq!(topo.node_of_cpu(20), Some(0));
+        assert_eq!(topo.node_of_cpu(24), Some(1));
+        assert!(topo.nodes()[2].cpus.is_empty());
+    }
+
+    #[test]
+    fn test_missing_topology_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(NumaTopology::from_sysfs(dir.path()).is_err());
+        assert!(NumaTopology::from_sysfs(&dir.path().join("absent")).is_err());
+        let bad = fake_sysfs(&[(0, "zero")]);
+        assert!(NumaTopology::from_sysfs(bad.path()).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Pacing: cap prefetch distance to a playback lead.
+//!
+//! Paced readers — video players, asset streamers — consume blocks at a
+//! bitrate, so data fetched far beyond the next few seconds of playback
+//! only occupies memory.  With [`AetherLinkKernel::set_target_lead`] and
+//! timestamped accesses through [`AetherLinkKernel::push_lba_at`], the
+//! kernel estimates the consumption rate (smoothed forward blocks over
+//! smoothed elapsed time between accesses) and caps every recommended
+//! extent to the blocks that rate consumes within the lead.  The cap
+//! applies to [horizon](crate::horizon) plans, so the plan is used up, and
+//! the next one planned, at the pace of the reader.
+//!
+//! Timestamps and the lead are in the caller's unit, as for
+//! [idle decay](AetherLinkKernel::set_idle_decay).  Backward steps (seeks)
+//! and timestamps that go backwards do not update the rate.
+
+use crate::fast_math::float;
+use crate::prefetcher::{PrefetchDecision, PrefetchPlan};
+use crate::AetherLinkKernel;
+
+/// Weight of the newest step in the rate estimate.
+const RATE_ALPHA: f64 = 0.125;
+
+/// Target lead and consumption-rate estimate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Pacing {
+    lead: u64,
+    /// LBA and timestamp of the previous access.
+    last: Option<(u64, u64)>,
+    /// Smoothed forward blocks per step.
+    blocks: f64,
+    /// Smoothed elapsed time per step.
+    elapsed: f64,
+    steps: u64,
+}
+
+impl Pacing {
+    fn observe(&mut self, lba: u64, now: u64) {
+        if let Some((prev, then)) = self.last {
+            if lba < prev || now < then {
+                self.last = Some((lba, now.max(then)));
+                return;
+            }
+            let (db, dt) = ((lba - prev) as f64, (now - then) as f64);
+            if self.steps == 0 {
+                (self.blocks, self.elapsed) = (db, dt);
+            } else {
+                self.blocks += RATE_ALPHA * (db - self.blocks);
+                self.elapsed += RATE_ALPHA * (dt - self.elapsed);
+            }
+            self.steps += 1;
+        }
+        self.last = Some((lba, now));
+    }
+
+    /// Blocks consumed per time unit, once a step with elapsed time has
+    /// been seen.
+    fn rate(&self) -> Option<f64> {
+        (self.steps > 0 && self.elapsed > 0.0).then(|| self.blocks / self.elapsed)
+    }
+
+    /// Largest extent, in blocks, that stays within the lead (at least 1);
+    /// unbounded while the rate is unknown.
+    pub(crate) fn lead_blocks(&self) -> u64 {
+        self.rate().map_or(u64::MAX, |rate| {
+            (float::ceil_f64(rate * self.lead as f64) as u64).max(1)
+        })
+    }
+}
+
+impl AetherLinkKernel {
+    /// Cap recommended extents to the blocks consumed within `lead` time
+    /// units at the rate observed by [`push_lba_at`](Self::push_lba_at).
+    /// Starts a fresh rate estimate.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_horizon(64);
+    /// kernel.set_target_lead(1_000); // ms
+    /// // 4 blocks every 100 ms: 40 blocks per second of lead.
+    /// for i in 0..100u64 {
+    ///     if let Some(plan) = kernel.push_lba_at(i * 4, i * 100).and_then(|d| d.target) {
+    ///         assert!(plan.len <= 40);
+    ///     }
+    /// }
+    /// assert_eq!(kernel.lead_blocks(), Some(40));
+    /// ```
+    pub fn set_target_lead(&mut self, lead: u64) {
+        self.pacing = Some(Pacing {
+            lead,
+            last: None,
+            blocks: 0.0,
+            elapsed: 0.0,
+            steps: 0,
+        });
+    }
+
+    /// Stop capping extents to a lead.
+    #[inline]
+    pub fn clear_target_lead(&mut self) {
+        self.pacing = None;
+    }
+
+    /// The lead extents are capped to, if set.
+    #[inline]
+    pub fn target_lead(&self) -> Option<u64> {
+        self.pacing.map(|p| p.lead)
+    }
+
+    /// Observed consumption rate in blocks per time unit, once two
+    /// timestamped forward accesses have been seen.
+    #[inline]
+    pub fn consumption_rate(&self) -> Option<f64> {
+        self.pacing.and_then(|p| p.rate())
+    }
+
+    /// Current extent cap in blocks, once the rate is known.
+    #[inline]
+    pub fn lead_blocks(&self) -> Option<u64> {
+        self.pacing
+            .filter(|p| p.rate().is_some())
+            .map(|p| p.lead_blocks())
+    }
+
+    /// [`push_lba`](Self::push_lba) for an access at time `now`.  The
+    /// timestamp updates the consumption rate and, as in
+    /// [`process_io_cycle_at`](Self::process_io_cycle_at), idle decay.  A
+    /// firing decision without a horizon plan targets the lead's worth of
+    /// blocks after `lba`, once the rate is known.
+    pub fn push_lba_at(&mut self, lba: u64, now: u64) -> Option<PrefetchDecision> {
+        self.idle_gap(now);
+        if let Some(pacing) = &mut self.pacing {
+            pacing.observe(lba, now);
+        }
+        let decision = self.push_lba(lba)?;
+        if !decision.fire || decision.target.is_some() {
+            return Some(decision);
+        }
+        let Some(len) = self.lead_blocks() else {
+            return Some(decision);
+        };
+        let paced = self
+            .bound_plan(PrefetchPlan::new(lba.saturating_add(1), len))
+            .map_or(PrefetchDecision::SKIP, |plan| {
+                PrefetchDecision::trigger(Some(plan))
+            });
+        Some(self.heatmap_placed(paced))
+    }
+
+    /// Extent cap for a plan decided this cycle.
+    #[inline(always)]
+    pub(crate) fn extent_cap(&self) -> u64 {
+        self.pacing.as_ref().map_or(u64::MAX, Pacing::lead_blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// 64 KiB reads (16 blocks) at 1 MiB/s with ±10% jitter on the
+    /// inter-arrival time: (lba, timestamp in ns).
+    fn paced(seed: u64, reads: u64) -> Vec<(u64, u64)> {
+        let mut rng = SplitMix64::new(seed);
+        let period = SECOND / 16;
+        let mut now = 0;
+        (0..reads)
+            .map(|i| {
+                now += period - period / 10 + rng.below(period / 5);
+                (1_000_000 + i * 16, now)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extents_stay_within_lead_and_ahead_of_read_head() {
+        for seed in [1, 2, 3] {
+            let mut kernel = AetherLinkKernel::new_video_streaming();
+            // 256 blocks per second at 4 KiB blocks: 512 in two seconds.
+            let cap = 512 * 6 / 5;
+            let mut frontier = None;
+            let mut plans = 0;
+            for (lba, now) in paced(seed, 4_000) {
+                if let Some(end) = frontier {
+                    assert!(lba < end, "seed {seed}: read head {lba} past {end}");
+                }
+                let Some(d) = kernel.push_lba_at(lba, now) else {
+                    continue;
+                };
+                if let Some(plan) = d.target.filter(|_| d.fire) {
+                    assert!(plan.start > lba, "seed {seed}: {plan:?} behind {lba}");
+                    assert!(plan.len <= cap, "seed {seed}: {plan:?} over the lead");
+                    frontier = frontier.max(Some(plan.end()));
+                    plans += 1;
+                }
+            }
+            let lead = kernel.lead_blocks().unwrap();
+            assert!((460..=570).contains(&lead), "seed {seed}: {lead}");
+            assert!(plans < 4_000 / 16, "seed {seed}: {plans} plans");
+        }
+    }
+
+    #[test]
+    fn test_without_timestamps_plans_are_uncapped() {
+        let mut kernel = AetherLinkKernel::new_video_streaming();
+        assert_eq!(kernel.target_lead(), Some(2 * SECOND));
+        assert_eq!(kernel.lead_blocks(), None);
+        let longest = (0..500u64)
+            .filter_map(|i| kernel.push_lba(i * 16))
+            .filter_map(|d| d.target)
+            .map(|plan| plan.len)
+            .max();
+        assert_eq!(longest, Some(16 * 64));
+
+        // A seek backwards leaves the rate alone.
+        kernel.push_lba_at(8_000, 0);
+        kernel.push_lba_at(8_016, 100);
+        let rate = kernel.consumption_rate().unwrap();
+        kernel.push_lba_at(16, 200);
+        assert_eq!(kernel.consumption_rate(), Some(rate));
+        kernel.clear_target_lead();
+        assert_eq!(kernel.consumption_rate(), None);
+    }
+}
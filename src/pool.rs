@@ -0,0 +1,275 @@
+//! Keyed pool of independent kernels.
+//!
+//! Workloads that interleave many streams (objects, files, queues) need one
+//! kernel per stream, or the streams' telemetry pollutes each other.
+//! [`KernelPool`] owns those kernels, creates them on first use from a
+//! template, and bounds memory by evicting idle and least-recently-used
+//! entries.  [`push_lba`](KernelPool::push_lba) and its siblings route a
+//! `(stream_id, lba)` observation to the stream's kernel in one call.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::trace::IoEvent;
+use crate::{stats, AetherLinkKernel, KernelStats};
+use std::collections::HashMap;
+
+/// Default number of streams kept resident.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// One pooled stream: its kernel plus caller-defined per-stream state.
+#[derive(Debug, Clone)]
+pub struct PoolEntry<T> {
+    /// The stream's kernel.
+    pub kernel: AetherLinkKernel,
+    /// Caller state kept alongside the kernel.
+    pub state: T,
+    last_used: u64,
+}
+
+/// Kernels keyed by a 64-bit stream id.
+///
+/// Time is logical: every [`entry`](Self::entry) call advances the pool's
+/// clock by one.  An entry not touched for `max_idle` ticks is idle and is
+/// dropped by [`evict_idle`](Self::evict_idle), which also runs
+/// automatically when a new stream arrives at a full pool.  If nothing is
+/// idle, the least-recently-used entry makes room.  Eviction scans the
+/// pool, so its cost is O(capacity) but it is only paid on insertion into
+/// a full pool.
+#[derive(Debug, Clone)]
+pub struct KernelPool<T = ()> {
+    template: AetherLinkKernel,
+    entries: HashMap<u64, PoolEntry<T>>,
+    capacity: usize,
+    max_idle: u64,
+    clock: u64,
+    evictions: u64,
+}
+
+/// Per-stream kernel manager; another name for [`KernelPool`].
+pub type AetherLinkManager<T = ()> = KernelPool<T>;
+
+impl<T: Default> KernelPool<T> {
+    /// Pool of at most `capacity` streams (at least 1), each starting as a
+    /// clone of `template`.
+    pub fn new(template: AetherLinkKernel, capacity: usize) -> Self {
+        Self {
+            template,
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            max_idle: u64::MAX,
+            clock: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Treat entries untouched for `ticks` calls as idle.
+    pub fn with_max_idle(mut self, ticks: u64) -> Self {
+        self.max_idle = ticks;
+        self
+    }
+
+    /// The entry for `key`, created from the template if absent.
+    pub fn entry(&mut self, key: u64) -> &mut PoolEntry<T> {
+        self.clock += 1;
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && self.evict_idle() == 0
+        {
+            self.evict_lru();
+        }
+        let now = self.clock;
+        let template = &self.template;
+        let entry = self.entries.entry(key).or_insert_with(|| PoolEntry {
+            kernel: template.clone(),
+            state: T::default(),
+            last_used: now,
+        });
+        entry.last_used = now;
+        entry
+    }
+
+    /// The entry for `key`, if resident.  Does not count as a use.
+    #[inline]
+    pub fn get(&self, key: u64) -> Option<&PoolEntry<T>> {
+        self.entries.get(&key)
+    }
+
+    /// Remove and return the entry for `key`.
+    #[inline]
+    pub fn remove(&mut self, key: u64) -> Option<PoolEntry<T>> {
+        self.entries.remove(&key)
+    }
+
+    /// Drop every entry idle for more than `max_idle` ticks; returns how
+    /// many were dropped.
+    pub fn evict_idle(&mut self) -> usize {
+        let (now, max_idle) = (self.clock, self.max_idle);
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, e| now.saturating_sub(e.last_used) <= max_idle);
+        let dropped = before - self.entries.len();
+        self.evictions += dropped as u64;
+        dropped
+    }
+
+    fn evict_lru(&mut self) {
+        let lru = self
+            .entries
+            .iter()
+            .min_by_key(|(_, e)| e.last_used)
+            .map(|(&k, _)| k);
+        if let Some(key) = lru {
+            self.entries.remove(&key);
+            self.evictions += 1;
+        }
+    }
+
+    /// Iterate over resident `(key, entry)` pairs in arbitrary order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &PoolEntry<T>)> {
+        self.entries.iter().map(|(&k, e)| (k, e))
+    }
+
+    /// Number of resident streams.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` if no stream is resident.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Maximum number of resident streams.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Entries dropped so far, idle and LRU combined.
+    #[inline]
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// [`AetherLinkKernel::push_lba`] on the kernel of stream `key`,
+    /// created from the template if absent.  A stream evicted and seen
+    /// again starts over from the template.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::{AetherLinkKernel, KernelPool};
+    ///
+    /// let mut pool: KernelPool = KernelPool::new(AetherLinkKernel::new_hft(), 64);
+    /// // Two files read in lockstep: each kernel sees only its own file.
+    /// for i in 0..100u64 {
+    ///     pool.push_lba(7, 1_000 + i);
+    ///     pool.push_lba(9, 900_000 + i * 8);
+    /// }
+    /// assert_eq!(pool.get(7).unwrap().kernel.cycles, 99);
+    /// assert_eq!(pool.stats().cycles, 198);
+    /// ```
+    #[inline]
+    pub fn push_lba(&mut self, key: u64, lba: u64) -> Option<PrefetchDecision> {
+        self.entry(key).kernel.push_lba(lba)
+    }
+
+    /// [`AetherLinkKernel::push_io`] on the kernel of stream `key`.
+    #[inline]
+    pub fn push_io(&mut self, key: u64, event: &IoEvent) -> Option<PrefetchDecision> {
+        self.entry(key).kernel.push_io(event)
+    }
+
+    /// [`AetherLinkKernel::process_io_cycle`] on the kernel of stream
+    /// `key`.
+    #[inline]
+    pub fn process_io_cycle(&mut self, key: u64, lba_stream: &[u64]) -> bool {
+        self.entry(key).kernel.process_io_cycle(lba_stream)
+    }
+
+    /// Counters of every resident stream added up, as
+    /// [`RegionRouter::stats`](crate::RegionRouter::stats) does.  Evicted
+    /// streams' counts leave with them.
+    pub fn stats(&self) -> KernelStats {
+        stats::combined(self.entries.values().map(|e| &e.kernel))
+    }
+}
+
+impl<T: Default> Default for KernelPool<T> {
+    fn default() -> Self {
+        Self::new(AetherLinkKernel::default(), DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams_are_independent() {
+        let mut pool: KernelPool = KernelPool::default();
+        pool.entry(1).kernel.process_io_cycle(&[0, 1, 2, 3]);
+        pool.entry(1).kernel.process_io_cycle(&[4, 5, 6, 7]);
+        pool.entry(2).kernel.process_io_cycle(&[9, 500]);
+        assert_eq!(pool.get(1).unwrap().kernel.cycles, 2);
+        assert_eq!(pool.get(2).unwrap().kernel.cycles, 1);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_routed_streams_match_separate_kernels() {
+        let mut pool: KernelPool = KernelPool::new(AetherLinkKernel::new_gaming(), 4);
+        let mut alone = [
+            AetherLinkKernel::new_gaming(),
+            AetherLinkKernel::new_gaming(),
+        ];
+        for i in 0..300u64 {
+            let (key, lba) = if i % 3 == 0 {
+                (0, 5_000_000 - i * 64)
+            } else {
+                (1, i * 2)
+            };
+            let routed = pool.push_lba(key, lba).map(|d| d.fire);
+            let direct = alone[key as usize].push_lba(lba).map(|d| d.fire);
+            assert_eq!(routed, direct, "access {i}");
+        }
+        for (key, kernel) in alone.iter().enumerate() {
+            let pooled = &pool.get(key as u64).unwrap().kernel;
+            assert_eq!(
+                (pooled.cycles, pooled.epsilon),
+                (kernel.cycles, kernel.epsilon)
+            );
+        }
+        assert_eq!(pool.stats().cycles, 298);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_lru() {
+        let mut pool: KernelPool<u32> = KernelPool::new(AetherLinkKernel::new_hft(), 2);
+        pool.entry(1).state = 10;
+        pool.entry(2).state = 20;
+        pool.entry(1);
+        pool.entry(3);
+        assert!(pool.get(2).is_none(), "2 was least recently used");
+        assert_eq!(pool.get(1).unwrap().state, 10);
+        assert_eq!(pool.get(3).unwrap().state, 0);
+        assert_eq!(pool.evictions(), 1);
+    }
+
+    #[test]
+    fn test_idle_entries_evicted_first() {
+        let mut pool: KernelPool = KernelPool::new(AetherLinkKernel::default(), 3).with_max_idle(2);
+        pool.entry(1);
+        pool.entry(2);
+        pool.entry(3);
+        pool.entry(3);
+        pool.entry(3);
+        // Clock is 6: 1 and 2 are idle; inserting 4 drops both.
+        pool.entry(4);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.get(1).is_none() && pool.get(2).is_none());
+        assert_eq!(pool.evictions(), 2);
+    }
+}
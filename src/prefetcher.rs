@@ -0,0 +1,367 @@
+//! Prediction and dispatch interfaces.
+//!
+//! A [`Prefetcher`] watches a stream of LBAs and answers each one with a
+//! [`PrefetchDecision`].  The kernel implements it, as does every baseline,
+//! so replay and evaluation tooling can run any of them interchangeably.
+//! [`PrefetchPlan`] is the unit handed from a decision to whatever issues
+//! the I/O — a run of consecutive blocks — and a [`PrefetchDispatcher`]
+//! turns batches of plans into real requests.
+
+use crate::AetherLinkKernel;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A run of `len` blocks starting at LBA `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefetchPlan {
+    /// First block.
+    pub start: u64,
+    /// Number of blocks.
+    pub len: u64,
+}
+
+impl PrefetchPlan {
+    /// Plan `len` blocks starting at `start`.
+    #[inline]
+    pub fn new(start: u64, len: u64) -> Self {
+        Self { start, len }
+    }
+
+    /// One past the last block.
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.len)
+    }
+
+    /// `true` if the plan covers no blocks.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<u64> for PrefetchPlan {
+    /// The single block at `lba`.
+    #[inline]
+    fn from(lba: u64) -> Self {
+        Self::new(lba, 1)
+    }
+}
+
+/// Answer to one observed access.
+///
+/// The `f32` [`confidence`](Self::confidence) makes this `PartialEq` only:
+/// it is no longer `Eq`, so it cannot key a `HashSet` or satisfy an `Eq`
+/// bound.  Compare [`fire`](Self::fire) and [`target`](Self::target),
+/// which are, when the score does not matter.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefetchDecision {
+    /// Whether to prefetch.
+    pub fire: bool,
+    /// Blocks to fetch, if the predictor chose them.  `None` on a firing
+    /// decision leaves the extent to the caller (typically the blocks
+    /// following the current request).
+    pub target: Option<PrefetchPlan>,
+    /// How sure the predictor is that prefetching pays off, in [0, 1].
+    /// The kernel reports the cycle's `p_fetch`, on skips too; predictors
+    /// without a score report 1 when they fire and 0 when they do not.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub confidence: f32,
+}
+
+impl PrefetchDecision {
+    /// Do not prefetch.
+    pub const SKIP: Self = Self {
+        fire: false,
+        target: None,
+        confidence: 0.0,
+    };
+
+    /// Prefetch `target` (or a caller-chosen extent when `None`), with
+    /// confidence 1.
+    #[inline]
+    pub fn trigger(target: Option<PrefetchPlan>) -> Self {
+        Self {
+            fire: true,
+            target,
+            confidence: 1.0,
+        }
+    }
+
+    /// The decision with `confidence`, clamped to [0, 1] (NaN reads as 0).
+    #[inline]
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = if confidence.is_nan() {
+            0.0
+        } else {
+            confidence.clamp(0.0, 1.0)
+        };
+        self
+    }
+}
+
+/// An online prefetch predictor fed one access at a time.
+pub trait Prefetcher {
+    /// Observe an access to `lba` and decide whether to prefetch.
+    fn observe(&mut self, lba: u64) -> PrefetchDecision;
+
+    /// Short identifier used in reports (`"aether"`, `"stride"`, …).
+    fn name(&self) -> &str;
+
+    /// Told that `count` prefetched blocks were evicted without being used.
+    /// Ignored unless the predictor learns from it.
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        let _ = count;
+    }
+
+    /// Pass the extents to read for a firing decision's `target` to
+    /// `issue`.  By default the whole target; the kernel splits strided
+    /// plans by its [gap policy](crate::gap).
+    #[inline]
+    fn extents(&self, target: PrefetchPlan, issue: &mut dyn FnMut(PrefetchPlan)) {
+        issue(target)
+    }
+}
+
+impl<P: Prefetcher + ?Sized> Prefetcher for &mut P {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        (**self).observe(lba)
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        (**self).record_pollution(count)
+    }
+
+    #[inline]
+    fn extents(&self, target: PrefetchPlan, issue: &mut dyn FnMut(PrefetchPlan)) {
+        (**self).extents(target, issue)
+    }
+}
+
+impl<P: Prefetcher + ?Sized> Prefetcher for Box<P> {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        (**self).observe(lba)
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        (**self).record_pollution(count)
+    }
+
+    #[inline]
+    fn extents(&self, target: PrefetchPlan, issue: &mut dyn FnMut(PrefetchPlan)) {
+        (**self).extents(target, issue)
+    }
+}
+
+/// A prefetcher reported under a different name, e.g. to tell two kernel
+/// presets apart in a comparison.
+#[derive(Debug, Clone)]
+pub struct Named<P> {
+    name: String,
+    inner: P,
+}
+
+impl<P: Prefetcher> Named<P> {
+    /// Wrap `inner` so that [`Prefetcher::name`] returns `name`.
+    pub fn new(name: impl Into<String>, inner: P) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+
+    /// The wrapped prefetcher.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: Prefetcher> Prefetcher for Named<P> {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        self.inner.observe(lba)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        self.inner.record_pollution(count)
+    }
+
+    #[inline]
+    fn extents(&self, target: PrefetchPlan, issue: &mut dyn FnMut(PrefetchPlan)) {
+        self.inner.extents(target, issue)
+    }
+}
+
+/// Streams through [`AetherLinkKernel::push_lba`]; the warm-up access
+/// before the first full span is a skip.
+impl Prefetcher for AetherLinkKernel {
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        self.push_lba(lba).unwrap_or_default()
+    }
+
+    fn name(&self) -> &str {
+        "aether"
+    }
+
+    #[inline]
+    fn record_pollution(&mut self, count: u64) {
+        AetherLinkKernel::record_pollution(self, count)
+    }
+
+    #[inline]
+    fn extents(&self, target: PrefetchPlan, issue: &mut dyn FnMut(PrefetchPlan)) {
+        self.target_extents(target).for_each(issue)
+    }
+}
+
+/// Sort `plans` and merge overlapping or adjacent ones.
+///
+/// Empty plans are dropped, so every block appears in exactly one output
+/// plan.  Allocates the result; [`coalesce_in_place`] does the same in the
+/// caller's buffer.
+pub fn coalesce(plans: &[PrefetchPlan]) -> Vec<PrefetchPlan> {
+    let mut merged = plans.to_vec();
+    let n = coalesce_in_place(&mut merged);
+    merged.truncate(n);
+    merged
+}
+
+/// [`coalesce`] without allocating: sorts and merges `plans` in place and
+/// returns how many leading entries hold the result.  The rest of the
+/// slice is left in unspecified order.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::prefetcher::coalesce_in_place;
+/// use aether_link::PrefetchPlan;
+///
+/// let mut plans = [PrefetchPlan::new(8, 4), PrefetchPlan::new(0, 8), PrefetchPlan::new(30, 2)];
+/// let n = coalesce_in_place(&mut plans);
+/// assert_eq!(&plans[..n], [PrefetchPlan::new(0, 12), PrefetchPlan::new(30, 2)]);
+/// ```
+pub fn coalesce_in_place(plans: &mut [PrefetchPlan]) -> usize {
+    plans.sort_unstable();
+    let mut n: usize = 0;
+    for i in 0..plans.len() {
+        let p = plans[i];
+        if p.is_empty() {
+            continue;
+        }
+        match n.checked_sub(1).map(|j| &mut plans[j]) {
+            Some(last) if p.start <= last.end() => {
+                last.len = last.len.max(p.end() - last.start);
+            }
+            _ => {
+                plans[n] = p;
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+/// Outcome of handing a batch of plans to a [`PrefetchDispatcher`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct DispatchReport {
+    /// Requests issued to the device or OS.
+    pub issued: usize,
+    /// Bytes covered by the issued requests.
+    pub bytes: u64,
+    /// Blocks left out because of a budget.
+    pub skipped_blocks: u64,
+    /// Plans that failed, each with its error.  A failure never stops the
+    /// rest of the batch.
+    pub errors: Vec<(PrefetchPlan, std::io::Error)>,
+}
+
+/// Something that turns [`PrefetchPlan`]s into actual I/O.
+#[cfg(feature = "std")]
+pub trait PrefetchDispatcher {
+    /// Issue `plans`, reporting per-plan failures instead of aborting.
+    fn dispatch(&mut self, plans: &[PrefetchPlan]) -> DispatchReport;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_merges_adjacent_and_overlapping() {
+        let plans = [
+            PrefetchPlan::new(20, 4),
+            PrefetchPlan::new(0, 8),
+            PrefetchPlan::new(8, 2),
+            PrefetchPlan::new(5, 1),
+            PrefetchPlan::new(22, 10),
+            PrefetchPlan::new(50, 0),
+        ];
+        assert_eq!(
+            coalesce(&plans),
+            [PrefetchPlan::new(0, 10), PrefetchPlan::new(20, 12)]
+        );
+        assert!(coalesce(&[]).is_empty());
+
+        let mut buf = plans;
+        let n = coalesce_in_place(&mut buf);
+        assert_eq!(buf[..n], coalesce(&plans));
+    }
+
+    #[test]
+    fn test_kernel_as_dyn_prefetcher() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        let p: &mut dyn Prefetcher = &mut kernel;
+        assert_eq!(p.name(), "aether");
+        assert_eq!(p.observe(10), PrefetchDecision::SKIP);
+        let fired = (11..100).filter(|&lba| p.observe(lba).fire).count() as u64;
+        assert_eq!(kernel.cycles, 89);
+        assert_eq!(kernel.prefetches, fired);
+    }
+
+    #[test]
+    fn test_confidence_is_the_cycle_p_fetch() {
+        let mut kernel = AetherLinkKernel::new_database();
+        let mut twin = kernel.clone();
+        let mut fired = [0; 2];
+        for i in 0..2_000u64 {
+            let lba = if i % 3 == 0 { i * 7_919 % 50_000 } else { i };
+            let (Some(d), Some(out)) = (kernel.push_lba(lba), twin.push_lba_qos(lba, 0.0)) else {
+                continue;
+            };
+            assert_eq!(d.confidence, out.p_fetch);
+            assert_eq!(d.fire, out.fetch);
+            fired[d.fire as usize] += 1;
+        }
+        assert!(fired[0] > 0 && fired[1] > 0, "{fired:?}");
+
+        assert_eq!(PrefetchDecision::trigger(None).confidence, 1.0);
+        let clamped = PrefetchDecision::SKIP.with_confidence(1.5);
+        assert_eq!(clamped.confidence, 1.0);
+        assert_eq!(clamped.with_confidence(f32::NAN).confidence, 0.0);
+    }
+}
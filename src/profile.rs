@@ -0,0 +1,224 @@
+//! Named parameter sets.
+//!
+//! A [`ProfileStore`] maps names such as `"nightly-backup"` or `"oltp"` to
+//! [`KernelConfig`]s, so parameter sets found by tuning (or learned in
+//! production, via [`capture`](ProfileStore::capture)) can be kept and
+//! reapplied by name.  Every config is validated on the way in.  With the
+//! `serde` feature a store persists as a TOML file with one table per
+//! profile:
+//!
+//! ```toml
+//! [oltp]
+//! epsilon = 0.65
+//! phi = 0.05
+//! lambda = [0.03, 0.08, 0.15]
+//! bias = -0.02
+//! ```
+
+use crate::config::{ConfigError, KernelConfig};
+use crate::AetherLinkKernel;
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+/// Validated kernel configs by name, iterated in name order.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ProfileStore {
+    profiles: BTreeMap<String, KernelConfig>,
+}
+
+impl ProfileStore {
+    /// Empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a profile after validating it; returns the one it
+    /// replaced.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        config: KernelConfig,
+    ) -> Result<Option<KernelConfig>, ConfigError> {
+        config.validate()?;
+        Ok(self.profiles.insert(name.into(), config))
+    }
+
+    /// Save the kernel's current (learned) parameters as `name`.
+    pub fn capture(
+        &mut self,
+        kernel: &AetherLinkKernel,
+        name: impl Into<String>,
+    ) -> Result<Option<KernelConfig>, ConfigError> {
+        self.insert(name, kernel.config())
+    }
+
+    /// Switch `kernel` to the parameters of profile `name`, keeping its
+    /// counters, telemetry and history.
+    pub fn apply(&self, kernel: &mut AetherLinkKernel, name: &str) -> Result<(), ConfigError> {
+        kernel.set_config(self.require(name)?);
+        Ok(())
+    }
+
+    /// The profile called `name`.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&KernelConfig> {
+        self.profiles.get(name)
+    }
+
+    /// The profile called `name`, or [`ConfigError::UnknownProfile`].
+    pub fn require(&self, name: &str) -> Result<&KernelConfig, ConfigError> {
+        self.get(name)
+            .ok_or_else(|| ConfigError::UnknownProfile(name.to_owned()))
+    }
+
+    /// Drop profile `name`, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<KernelConfig> {
+        self.profiles.remove(name)
+    }
+
+    /// Profile names in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// Profiles in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KernelConfig)> {
+        self.profiles.iter().map(|(n, c)| (n.as_str(), c))
+    }
+
+    /// Number of profiles.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// `true` when the store holds no profile.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ProfileStore {
+    /// Decode and validate a TOML document.
+    pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+        let store: Self = toml::from_str(s).map_err(|e| ConfigError::Format(e.to_string()))?;
+        store.validate()?;
+        Ok(store)
+    }
+
+    /// Encode as a TOML document.
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        toml::to_string(self).map_err(|e| ConfigError::Format(e.to_string()))
+    }
+
+    /// Load and validate a profile file.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, ConfigError> {
+        Self::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write a profile file.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), ConfigError> {
+        std::fs::write(path, self.to_toml_string()?)?;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (name, config) in &self.profiles {
+            config
+                .validate()
+                .map_err(|e| ConfigError::Format(format!("profile '{name}': {e}")))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preset;
+
+    fn store() -> ProfileStore {
+        let mut store = ProfileStore::new();
+        store
+            .insert("asset-streaming", Preset::Gaming.kernel().config())
+            .unwrap();
+        store.insert("oltp", Preset::Hft.kernel().config()).unwrap();
+        store
+            .insert(
+                "nightly-backup",
+                KernelConfig {
+                    epsilon: 0.3,
+                    bias: 0.5,
+                    ..KernelConfig::default()
+                },
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_apply_mid_run_keeps_counters() {
+        let store = store();
+        let mut kernel = AetherLinkKernel::default();
+        for i in 0..50u64 {
+            kernel.process_io_cycle(&[i, i + 1, i + 2, i + 3]);
+        }
+        store.apply(&mut kernel, "oltp").unwrap();
+        assert_eq!(kernel.config(), Preset::Hft.kernel().config());
+        assert_eq!(kernel.cycles, 50);
+
+        let mut captured = ProfileStore::new();
+        kernel.process_io_cycle(&[7, 8, 9, 10]);
+        captured.capture(&kernel, "learned").unwrap();
+        assert_eq!(captured.get("learned"), Some(&kernel.config()));
+    }
+
+    #[test]
+    fn test_unknown_and_invalid_profiles() {
+        let mut kernel = AetherLinkKernel::default();
+        let before = kernel.config();
+        let err = store().apply(&mut kernel, "olap").unwrap_err();
+        assert!(matches!(&err, ConfigError::UnknownProfile(n) if n == "olap"));
+        assert_eq!(kernel.config(), before);
+
+        let bad = KernelConfig {
+            bias: 9.0,
+            ..KernelConfig::default()
+        };
+        assert!(ProfileStore::new().insert("bad", bad).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles.toml");
+        let store = store();
+        store.save(&path).unwrap();
+        let loaded = ProfileStore::load(&path).unwrap();
+        assert_eq!(loaded, store);
+        assert_eq!(
+            loaded.names().collect::<Vec<_>>(),
+            ["asset-streaming", "nightly-backup", "oltp"]
+        );
+
+        std::fs::write(&path, "[oltp]\nepsilon = 'high'\n").unwrap();
+        assert!(matches!(
+            ProfileStore::load(&path),
+            Err(ConfigError::Format(_))
+        ));
+        let out_of_range =
+            "[oltp]\nepsilon = 0.5\nphi = 0.1\nlambda = [0.1, 0.2, 2.0]\nbias = 0.0\n";
+        let err = ProfileStore::from_toml_str(out_of_range).unwrap_err();
+        assert!(err.to_string().contains("oltp"), "{err}");
+        assert!(matches!(
+            ProfileStore::load(dir.path().join("missing.toml")),
+            Err(ConfigError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,736 @@
+//! Bounded queue of pending prefetches with duplicate suppression.
+//!
+//! A kernel firing on overlapping windows recommends the same blocks over
+//! and over.  [`PrefetchQueue`] sits between the decisions and the
+//! dispatcher: [`push`](PrefetchQueue::push) checks each plan against the
+//! last `recent` plans accepted (queued or already popped) and drops, trims
+//! or keeps overlapping ones according to an [`OverlapPolicy`].  A plan
+//! wholly inside a recent one is always a duplicate.
+//!
+//! The overlap check is a linear scan of the recent plans, fronted by a
+//! counting Bloom filter over 64-block granules: when none of a plan's
+//! granules is in the filter the scan is skipped, which is the common case
+//! for a queue fed by mostly fresh recommendations.  Plans wider than
+//! [`MAX_FILTER_GRANULES`] granules bypass the filter and always scan.
+//!
+//! Suggestions go stale: once the demand read has happened, fetching the
+//! block is wasted bandwidth.  With [`PrefetchQueue::with_ttl`] every plan
+//! is stamped when pushed and dropped as expired when it reaches the head
+//! of the queue `ttl` or more time units later.  Expiry is lazy — only
+//! [`pop`](PrefetchQueue::pop) and [`drain`](PrefetchQueue::drain) look at
+//! ages, nothing scans the queue — and an expired plan is also forgotten
+//! by the deduplication, so its blocks can be suggested again.  Time is
+//! whatever the caller stamps with [`push_at`](PrefetchQueue::push_at)
+//! (kernel cycles, nanoseconds); plain [`push`](PrefetchQueue::push)
+//! advances the clock by one, so ages count pushes.
+//!
+//! A plan can also be withdrawn before it is dispatched, typically because
+//! the demand read for its blocks arrived first: [`cancel`](PrefetchQueue::cancel)
+//! removes every pending plan overlapping the blocks given and, like
+//! expiry, forgets them in the deduplication.
+//!
+//! All storage is sized at construction; `push`, `pop` and `drain` never
+//! allocate.
+
+use crate::bandwidth::BandwidthBudget;
+use crate::prefetcher::PrefetchPlan;
+use alloc::boxed::Box;
+use alloc::vec;
+
+/// Default number of pending plans.
+pub const DEFAULT_CAPACITY: usize = 256;
+/// Default number of accepted plans remembered for deduplication.
+pub const DEFAULT_RECENT: usize = 64;
+/// Widest plan, in 64-block granules, tracked by the Bloom filter.
+pub const MAX_FILTER_GRANULES: u64 = 8;
+
+/// Blocks per filter granule, as a shift.
+const GRANULE_SHIFT: u32 = 6;
+/// Counters in the Bloom filter (a power of two).
+const FILTER_SLOTS: usize = 1024;
+
+/// What [`PrefetchQueue::push`] does with a plan that partly overlaps a
+/// recent one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverlapPolicy {
+    /// Drop it as a duplicate.
+    #[default]
+    Drop,
+    /// Cut the covered blocks off its ends and queue the rest.  A recent
+    /// plan strictly inside it is left in, since a plan is one run.
+    Trim,
+    /// Queue it unchanged.
+    Accept,
+}
+
+/// What happened to a pushed plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOutcome {
+    /// Queued, possibly trimmed to the plan carried.
+    Accepted(PrefetchPlan),
+    /// Dropped: empty, or already covered by a recent plan.
+    Deduplicated,
+    /// Dropped: the queue was full.
+    Full,
+}
+
+/// Counters kept by a [`PrefetchQueue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueStats {
+    /// Plans queued, trimmed ones included.
+    pub accepted: u64,
+    /// Accepted plans that were trimmed first.
+    pub trimmed: u64,
+    /// Plans dropped as duplicates.
+    pub deduplicated: u64,
+    /// Plans dropped because the queue was full.
+    pub dropped_full: u64,
+    /// Plans dropped at the head for being older than the TTL.
+    pub expired: u64,
+    /// Pending plans removed by [`PrefetchQueue::cancel`].
+    pub cancelled: u64,
+}
+
+/// Called with each expired plan and its age.
+pub type ExpiryHook = fn(PrefetchPlan, u64);
+
+/// A queued plan and the time it was pushed.
+#[derive(Debug, Clone, Copy, Default)]
+struct Pending {
+    plan: PrefetchPlan,
+    at: u64,
+    /// Already counted as deferred by a bandwidth budget.
+    deferred: bool,
+}
+
+/// Counting Bloom filter over granules, two probes per key.
+#[derive(Debug, Clone)]
+struct GranuleFilter {
+    counts: Box<[u16; FILTER_SLOTS]>,
+}
+
+impl GranuleFilter {
+    fn new() -> Self {
+        Self {
+            counts: Box::new([0; FILTER_SLOTS]),
+        }
+    }
+
+    #[inline]
+    fn slots(granule: u64) -> [usize; 2] {
+        let h = granule.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mask = FILTER_SLOTS as u64 - 1;
+        [(h >> 32 & mask) as usize, (h >> 48 & mask) as usize]
+    }
+
+    /// Granules of `plan`, or `None` if it is too wide to track.
+    #[inline]
+    fn granules(plan: PrefetchPlan) -> Option<core::ops::RangeInclusive<u64>> {
+        let first = plan.start >> GRANULE_SHIFT;
+        let last = (plan.end() - 1) >> GRANULE_SHIFT;
+        (last - first < MAX_FILTER_GRANULES).then_some(first..=last)
+    }
+
+    fn add(&mut self, plan: PrefetchPlan, delta: i16) {
+        if let Some(granules) = Self::granules(plan) {
+            for g in granules {
+                for s in Self::slots(g) {
+                    self.counts[s] = self.counts[s].wrapping_add_signed(delta);
+                }
+            }
+        }
+    }
+
+    /// `false` only if no tracked plan can share a granule with `plan`.
+    fn may_overlap(&self, plan: PrefetchPlan) -> bool {
+        match Self::granules(plan) {
+            Some(mut granules) => {
+                granules.any(|g| Self::slots(g).iter().all(|&s| self.counts[s] > 0))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Fixed-capacity FIFO of [`PrefetchPlan`]s that suppresses recently seen
+/// blocks.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::queue::{OverlapPolicy, PrefetchQueue, PushOutcome};
+/// use aether_link::PrefetchPlan;
+///
+/// let mut queue = PrefetchQueue::new(16).with_policy(OverlapPolicy::Trim);
+/// queue.push(PrefetchPlan::new(100, 8));
+/// assert_eq!(queue.push(PrefetchPlan::new(100, 8)), PushOutcome::Deduplicated);
+/// assert_eq!(
+///     queue.push(PrefetchPlan::new(104, 8)),
+///     PushOutcome::Accepted(PrefetchPlan::new(108, 4))
+/// );
+/// let plans: Vec<_> = queue.drain().collect();
+/// assert_eq!(plans, [PrefetchPlan::new(100, 8), PrefetchPlan::new(108, 4)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrefetchQueue {
+    pending: Box<[Pending]>,
+    /// Slot of the oldest pending plan.
+    head: usize,
+    len: usize,
+    /// Ring of recently accepted plans; empty plans mark free slots.
+    recent: Box<[PrefetchPlan]>,
+    /// Slot the next accepted plan is remembered in.
+    recent_pos: usize,
+    /// Remembered plans too wide for the filter.
+    wide: usize,
+    filter: GranuleFilter,
+    policy: OverlapPolicy,
+    /// Latest time seen.
+    now: u64,
+    ttl: Option<u64>,
+    on_expire: Option<ExpiryHook>,
+    stats: QueueStats,
+}
+
+impl PrefetchQueue {
+    /// Queue of at most `capacity` pending plans (at least 1), remembering
+    /// the last [`DEFAULT_RECENT`] accepted ones.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            pending: vec![Pending::default(); capacity.max(1)].into_boxed_slice(),
+            head: 0,
+            len: 0,
+            recent: vec![PrefetchPlan::default(); DEFAULT_RECENT].into_boxed_slice(),
+            recent_pos: 0,
+            wide: 0,
+            filter: GranuleFilter::new(),
+            policy: OverlapPolicy::default(),
+            now: 0,
+            ttl: None,
+            on_expire: None,
+            stats: QueueStats::default(),
+        }
+    }
+
+    /// Remember the last `recent` accepted plans instead (0 disables
+    /// deduplication).  Forgets the ones remembered so far.
+    pub fn with_recent(mut self, recent: usize) -> Self {
+        self.recent = vec![PrefetchPlan::default(); recent].into_boxed_slice();
+        self.recent_pos = 0;
+        self.wide = 0;
+        self.filter = GranuleFilter::new();
+        self
+    }
+
+    /// Handle partial overlaps with `policy`.
+    pub fn with_policy(mut self, policy: OverlapPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Expire plans that are `ttl` or more time units old when they reach
+    /// the head.
+    pub fn with_ttl(mut self, ttl: u64) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Call `hook` with every expired plan and its age, e.g. to log a
+    /// dispatcher that keeps falling behind.
+    pub fn with_expiry_hook(mut self, hook: ExpiryHook) -> Self {
+        self.on_expire = Some(hook);
+        self
+    }
+
+    /// Queue `plan` unless it repeats recent blocks or the queue is full,
+    /// one time unit after the latest time seen.
+    pub fn push(&mut self, plan: PrefetchPlan) -> PushOutcome {
+        self.push_at(plan, self.now.saturating_add(1))
+    }
+
+    /// [`push`](Self::push) stamped with time `now`.  Times earlier than
+    /// one already seen count as that one.
+    pub fn push_at(&mut self, plan: PrefetchPlan, now: u64) -> PushOutcome {
+        self.now = self.now.max(now);
+        let original = plan;
+        let Some(plan) = self.deduplicate(plan) else {
+            self.stats.deduplicated += 1;
+            return PushOutcome::Deduplicated;
+        };
+        if self.len == self.pending.len() {
+            self.stats.dropped_full += 1;
+            return PushOutcome::Full;
+        }
+        let tail = (self.head + self.len) % self.pending.len();
+        self.pending[tail] = Pending {
+            plan,
+            at: self.now,
+            deferred: false,
+        };
+        self.len += 1;
+        self.remember(plan);
+        self.stats.accepted += 1;
+        self.stats.trimmed += (plan != original) as u64;
+        PushOutcome::Accepted(plan)
+    }
+
+    /// The oldest pending plan that has not expired at the latest time
+    /// seen.  Expired plans in front of it are dropped.
+    pub fn pop(&mut self) -> Option<PrefetchPlan> {
+        self.skip_expired();
+        self.take_head()
+    }
+
+    /// [`pop`](Self::pop), but only if `budget` has the bytes for the plan.
+    /// Otherwise the plan stays at the head, is counted as deferred, and
+    /// `None` is returned until the budget has been refilled.
+    pub fn pop_within(&mut self, budget: &mut BandwidthBudget) -> Option<PrefetchPlan> {
+        self.skip_expired();
+        let head = self.pending.get_mut(self.head).filter(|_| self.len > 0)?;
+        if budget.try_consume(head.plan) {
+            return self.take_head();
+        }
+        if !head.deferred {
+            head.deferred = true;
+            budget.defer(head.plan);
+        }
+        None
+    }
+
+    /// [`pop`](Self::pop) at time `now`.
+    pub fn pop_at(&mut self, now: u64) -> Option<PrefetchPlan> {
+        self.now = self.now.max(now);
+        self.pop()
+    }
+
+    /// Pop every pending plan, oldest first, skipping expired ones.  Plans
+    /// not consumed from the iterator stay queued.
+    pub fn drain(&mut self) -> Drain<'_> {
+        Drain { queue: self }
+    }
+
+    /// [`drain`](Self::drain) at time `now`.
+    pub fn drain_at(&mut self, now: u64) -> Drain<'_> {
+        self.now = self.now.max(now);
+        self.drain()
+    }
+
+    /// Remove every pending plan sharing a block with `target` (an LBA or
+    /// a [`PrefetchPlan`]) and forget it in the deduplication, so its
+    /// blocks can be queued again.  Returns the number of plans removed, 0
+    /// if none was pending (already dispatched, expired, or never queued).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::queue::PrefetchQueue;
+    /// use aether_link::PrefetchPlan;
+    ///
+    /// let mut queue = PrefetchQueue::new(16);
+    /// queue.push(PrefetchPlan::new(100, 8));
+    /// queue.push(PrefetchPlan::new(200, 8));
+    /// assert_eq!(queue.cancel(103), 1);
+    /// assert_eq!(queue.cancel(103), 0);
+    /// assert_eq!(queue.pop(), Some(PrefetchPlan::new(200, 8)));
+    /// ```
+    pub fn cancel(&mut self, target: impl Into<PrefetchPlan>) -> usize {
+        let target = target.into();
+        let cap = self.pending.len();
+        let mut kept = 0;
+        for i in 0..self.len {
+            let entry = self.pending[(self.head + i) % cap];
+            let plan = entry.plan;
+            if plan.start < target.end() && plan.end() > target.start {
+                self.stats.cancelled += 1;
+                self.unremember(plan);
+            } else {
+                self.pending[(self.head + kept) % cap] = entry;
+                kept += 1;
+            }
+        }
+        let removed = self.len - kept;
+        self.len = kept;
+        removed
+    }
+
+    /// Pending plans.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// `true` if nothing is pending.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Most plans that can be pending at once.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Counters since construction.
+    #[inline]
+    pub fn stats(&self) -> QueueStats {
+        self.stats
+    }
+
+    /// `plan` as it should be queued under the policy, or `None` for a
+    /// duplicate.
+    fn deduplicate(&self, plan: PrefetchPlan) -> Option<PrefetchPlan> {
+        if plan.is_empty() {
+            return None;
+        }
+        if self.wide == 0 && !self.filter.may_overlap(plan) {
+            return Some(plan);
+        }
+        let overlapping = || {
+            self.recent
+                .iter()
+                .filter(move |r| !r.is_empty() && r.start < plan.end() && r.end() > plan.start)
+        };
+        if overlapping().any(|r| r.start <= plan.start && r.end() >= plan.end()) {
+            return None;
+        }
+        match self.policy {
+            OverlapPolicy::Accept => Some(plan),
+            OverlapPolicy::Drop => overlapping().next().is_none().then_some(plan),
+            OverlapPolicy::Trim => self.trim(plan),
+        }
+    }
+
+    /// `plan` with the blocks of overlapping recent plans cut off its ends.
+    fn trim(&self, mut plan: PrefetchPlan) -> Option<PrefetchPlan> {
+        // Trimming one end can expose an overlap with a plan already
+        // passed, so repeat until nothing changes.
+        loop {
+            let before = plan;
+            for r in self.recent.iter().filter(|r| !r.is_empty()) {
+                if r.start >= plan.end() || r.end() <= plan.start {
+                    continue;
+                }
+                if r.start <= plan.start && r.end() >= plan.end() {
+                    return None;
+                }
+                if r.start <= plan.start {
+                    plan = PrefetchPlan::new(r.end(), plan.end() - r.end());
+                } else if r.end() >= plan.end() {
+                    plan.len = r.start - plan.start;
+                }
+            }
+            if plan == before {
+                return Some(plan);
+            }
+        }
+    }
+
+    /// Add an accepted plan to the recent ring, evicting the oldest.
+    fn remember(&mut self, plan: PrefetchPlan) {
+        if self.recent.is_empty() {
+            return;
+        }
+        let old = self.recent[self.recent_pos];
+        self.forget(old);
+        if GranuleFilter::granules(plan).is_none() {
+            self.wide += 1;
+        }
+        self.filter.add(plan, 1);
+        self.recent[self.recent_pos] = plan;
+        self.recent_pos = (self.recent_pos + 1) % self.recent.len();
+    }
+
+    /// Drop expired plans from the head.
+    fn skip_expired(&mut self) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        while self.len > 0 {
+            let Pending { plan, at, .. } = self.pending[self.head];
+            let age = self.now - at;
+            if age < ttl {
+                return;
+            }
+            self.take_head();
+            self.expire(plan, age);
+        }
+    }
+
+    fn take_head(&mut self) -> Option<PrefetchPlan> {
+        if self.len == 0 {
+            return None;
+        }
+        let plan = self.pending[self.head].plan;
+        self.head = (self.head + 1) % self.pending.len();
+        self.len -= 1;
+        Some(plan)
+    }
+
+    /// Count an expired plan and forget it so its blocks can be queued
+    /// again.
+    fn expire(&mut self, plan: PrefetchPlan, age: u64) {
+        self.stats.expired += 1;
+        self.unremember(plan);
+        if let Some(hook) = self.on_expire {
+            hook(plan, age);
+        }
+    }
+
+    /// Take `plan` out of the recent ring, if it is still there.
+    fn unremember(&mut self, plan: PrefetchPlan) {
+        if let Some(slot) = self.recent.iter().position(|&r| r == plan) {
+            self.recent[slot] = PrefetchPlan::default();
+            self.forget(plan);
+        }
+    }
+
+    /// Drop `plan`'s share of the filter and wide count.
+    fn forget(&mut self, plan: PrefetchPlan) {
+        if plan.is_empty() {
+            return;
+        }
+        if GranuleFilter::granules(plan).is_none() {
+            self.wide -= 1;
+        }
+        self.filter.add(plan, -1);
+    }
+}
+
+impl Default for PrefetchQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Iterator returned by [`PrefetchQueue::drain`].
+#[derive(Debug)]
+pub struct Drain<'a> {
+    queue: &'a mut PrefetchQueue,
+}
+
+impl Iterator for Drain<'_> {
+    type Item = PrefetchPlan;
+
+    #[inline]
+    fn next(&mut self) -> Option<PrefetchPlan> {
+        self.queue.pop()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.queue.len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    fn plan(start: u64, len: u64) -> PrefetchPlan {
+        PrefetchPlan::new(start, len)
+    }
+
+    #[test]
+    fn test_exact_duplicates_suppressed() {
+        for policy in [
+            OverlapPolicy::Drop,
+            OverlapPolicy::Trim,
+            OverlapPolicy::Accept,
+        ] {
+            let mut queue = PrefetchQueue::new(8).with_policy(policy);
+            assert_eq!(queue.push(plan(10, 8)), PushOutcome::Accepted(plan(10, 8)));
+            assert_eq!(queue.push(plan(10, 8)), PushOutcome::Deduplicated);
+            assert_eq!(queue.push(plan(12, 2)), PushOutcome::Deduplicated);
+            // Dispatched plans still count as recent.
+            assert_eq!(queue.pop(), Some(plan(10, 8)));
+            assert_eq!(queue.push(plan(10, 8)), PushOutcome::Deduplicated);
+            assert_eq!(queue.push(plan(5, 0)), PushOutcome::Deduplicated);
+            let stats = queue.stats();
+            assert_eq!((stats.accepted, stats.deduplicated), (1, 4), "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn test_partial_overlap_policies() {
+        let mut drop = PrefetchQueue::new(8);
+        drop.push(plan(100, 10));
+        assert_eq!(drop.push(plan(105, 10)), PushOutcome::Deduplicated);
+
+        let mut accept = PrefetchQueue::new(8).with_policy(OverlapPolicy::Accept);
+        accept.push(plan(100, 10));
+        assert_eq!(
+            accept.push(plan(105, 10)),
+            PushOutcome::Accepted(plan(105, 10))
+        );
+
+        let mut trim = PrefetchQueue::new(8).with_policy(OverlapPolicy::Trim);
+        trim.push(plan(100, 10));
+        trim.push(plan(120, 10));
+        assert_eq!(trim.push(plan(95, 10)), PushOutcome::Accepted(plan(95, 5)));
+        assert_eq!(
+            trim.push(plan(108, 14)),
+            PushOutcome::Accepted(plan(110, 10))
+        );
+        // Recent plans strictly inside do not split it.
+        assert_eq!(trim.push(plan(90, 45)), PushOutcome::Accepted(plan(90, 45)));
+        assert_eq!(trim.push(plan(130, 6)), PushOutcome::Accepted(plan(135, 1)));
+        assert_eq!(trim.push(plan(92, 40)), PushOutcome::Deduplicated);
+        assert_eq!(trim.stats().trimmed, 3);
+
+        // A later recent plan covering it wins over a partial overlap.
+        let mut accept = PrefetchQueue::new(8).with_policy(OverlapPolicy::Accept);
+        accept.push(plan(0, 6));
+        accept.push(plan(4, 20));
+        assert_eq!(accept.push(plan(5, 10)), PushOutcome::Deduplicated);
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_recovers() {
+        let mut queue = PrefetchQueue::new(3);
+        for i in 0..3 {
+            assert!(matches!(
+                queue.push(plan(i * 100, 4)),
+                PushOutcome::Accepted(_)
+            ));
+        }
+        assert_eq!(queue.push(plan(1_000, 4)), PushOutcome::Full);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.pop(), Some(plan(0, 4)));
+        // The rejected plan was not remembered, so it is not a duplicate now.
+        assert_eq!(
+            queue.push(plan(1_000, 4)),
+            PushOutcome::Accepted(plan(1_000, 4))
+        );
+        let drained: Vec<_> = queue.drain().collect();
+        assert_eq!(drained, [plan(100, 4), plan(200, 4), plan(1_000, 4)]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.stats().dropped_full, 1);
+    }
+
+    #[test]
+    fn test_recent_window_forgets() {
+        let mut queue = PrefetchQueue::new(64).with_recent(4);
+        queue.push(plan(0, 8));
+        for i in 1..=4 {
+            queue.push(plan(i * 1_000, 8));
+        }
+        assert_eq!(queue.push(plan(0, 8)), PushOutcome::Accepted(plan(0, 8)));
+        assert_eq!(queue.push(plan(4_000, 8)), PushOutcome::Deduplicated);
+
+        let mut off = PrefetchQueue::new(4).with_recent(0);
+        off.push(plan(0, 8));
+        assert_eq!(off.push(plan(0, 8)), PushOutcome::Accepted(plan(0, 8)));
+    }
+
+    #[test]
+    fn test_ttl_expires_at_the_boundary() {
+        let mut queue = PrefetchQueue::new(8).with_ttl(10);
+        queue.push_at(plan(0, 8), 100);
+        queue.push_at(plan(100, 8), 101);
+        queue.push_at(plan(200, 8), 105);
+        assert_eq!(queue.pop_at(109), Some(plan(0, 8)), "age 9 < ttl");
+        // Age 10 expires the second plan; the third (age 5) is untouched.
+        assert_eq!(queue.pop_at(111), Some(plan(200, 8)));
+        assert_eq!(queue.stats().expired, 1);
+        assert!(queue.is_empty());
+
+        // Nothing expires while it sits in the queue, only when popped.
+        queue.push_at(plan(300, 8), 120);
+        queue.push_at(plan(400, 8), 125);
+        assert_eq!(queue.len(), 2);
+        let drained: Vec<_> = queue.drain_at(134).collect();
+        assert_eq!(drained, [plan(400, 8)]);
+        assert_eq!(queue.stats().expired, 2);
+    }
+
+    #[test]
+    fn test_expired_blocks_can_be_suggested_again() {
+        let mut queue = PrefetchQueue::new(8).with_ttl(3);
+        queue.push(plan(0, 8));
+        queue.push(plan(50, 8));
+        assert_eq!(queue.push(plan(0, 8)), PushOutcome::Deduplicated);
+        for i in 0..3 {
+            queue.push(plan(1_000 + 100 * i, 8));
+        }
+        // Clock 6: the first two plans are 5 and 4 old.
+        assert_eq!(queue.pop(), Some(plan(1_000, 8)));
+        assert_eq!(queue.push(plan(0, 8)), PushOutcome::Accepted(plan(0, 8)));
+        assert_eq!(queue.push(plan(52, 4)), PushOutcome::Accepted(plan(52, 4)));
+        // A dispatched plan that did not expire stays remembered.
+        assert_eq!(queue.push(plan(1_000, 8)), PushOutcome::Deduplicated);
+    }
+
+    #[test]
+    fn test_expiry_hook_sees_age() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static AGES: AtomicU64 = AtomicU64::new(0);
+        fn hook(_: PrefetchPlan, age: u64) {
+            AGES.fetch_add(age, Ordering::Relaxed);
+        }
+
+        let mut queue = PrefetchQueue::new(4).with_ttl(1_000).with_expiry_hook(hook);
+        queue.push_at(plan(0, 1), 0);
+        queue.push_at(plan(10, 1), 500);
+        assert_eq!(queue.pop_at(1_700), None);
+        assert_eq!(AGES.load(Ordering::Relaxed), 1_700 + 1_200);
+    }
+
+    #[test]
+    fn test_cancel_removes_pending_and_forgets() {
+        let mut queue = PrefetchQueue::new(4);
+        for i in 0..4 {
+            queue.push(plan(i * 100, 8));
+        }
+        assert_eq!(queue.pop(), Some(plan(0, 8)));
+        // The head has wrapped; cancel from the middle and the tail.
+        queue.push(plan(400, 8));
+        assert_eq!(queue.cancel(plan(104, 150)), 2);
+        assert_eq!(queue.cancel(407), 1);
+        assert_eq!(queue.len(), 1);
+        // Dispatched and unknown blocks cancel nothing.
+        assert_eq!(queue.cancel(3), 0);
+        assert_eq!(queue.cancel(plan(5_000, 8)), 0);
+        assert_eq!(queue.stats().cancelled, 3);
+        // Cancelled blocks can be suggested again; the dispatched ones
+        // are still remembered.
+        assert_eq!(
+            queue.push(plan(100, 8)),
+            PushOutcome::Accepted(plan(100, 8))
+        );
+        assert_eq!(queue.push(plan(0, 8)), PushOutcome::Deduplicated);
+        let drained: Vec<_> = queue.drain().collect();
+        assert_eq!(drained, [plan(300, 8), plan(100, 8)]);
+    }
+
+    #[test]
+    fn test_filter_never_hides_an_overlap() {
+        let mut rng = SplitMix64::new(3);
+        let mut queue = PrefetchQueue::new(1 << 16).with_recent(16);
+        let mut accepted: Vec<PrefetchPlan> = Vec::new();
+        for _ in 0..20_000 {
+            let len = if rng.below(50) == 0 {
+                1_000
+            } else {
+                1 + rng.below(40)
+            };
+            let p = plan(rng.below(1 << 14), len);
+            let recent = &accepted[accepted.len().saturating_sub(16)..];
+            let overlaps = recent
+                .iter()
+                .any(|r| r.start < p.end() && r.end() > p.start);
+            match queue.push(p) {
+                PushOutcome::Accepted(q) => {
+                    assert!(!overlaps, "{p:?}");
+                    accepted.push(q);
+                }
+                outcome => assert!(overlaps, "{p:?} {outcome:?}"),
+            }
+        }
+        assert!(queue.stats().deduplicated > 1_000);
+        assert!(queue.stats().accepted > 1_000);
+    }
+}
@@ -0,0 +1,110 @@
+//! The decision core and nothing else.
+//!
+//! [`AetherLinkKernel::process_io_cycle_raw`] takes the span a window
+//! covers — what [`extract_telemetry`](AetherLinkKernel::extract_telemetry)
+//! derives from its first and last LBA — and runs only the telemetry
+//! update, the POVM measurement, the φ and ε steps and the sigmoid against
+//! ε.  It reads no LBAs and touches no counter, history or optional stage:
+//!
+//! - `cycles` and `prefetches` stay where they are, and so does every
+//!   [`KernelStats`](crate::KernelStats) counter, the mode machine and the
+//!   checkpoint boundary;
+//! - anomaly suppression, the watchdog, horizon plans, the latency budget,
+//!   queue-depth and burst damping, the feedback threshold policy (ε takes
+//!   the open-loop step regardless) and the QoS shift are skipped, enabled
+//!   or not.
+//!
+//! On a kernel with none of those enabled, fed the same spans, it decides
+//! exactly like [`process_io_cycle`](AetherLinkKernel::process_io_cycle)
+//! and leaves ε and φ bit-identical.  Mixing the two on one kernel is
+//! fine; the raw cycles simply go uncounted.
+
+use crate::fast_math::wrap_angle;
+use crate::{no_panic, threshold, AetherLinkKernel};
+
+impl AetherLinkKernel {
+    /// One decision from the span `last_delta` (`|last − first|` of the
+    /// window, in blocks, so finite and not negative), with none of the
+    /// bookkeeping; see [`raw`](crate::raw) for what is left out.  A span
+    /// of 0, a window that ends where it began, never fires.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut full = AetherLinkKernel::new_hft();
+    /// let mut raw = full.clone();
+    /// for i in 0..100u64 {
+    ///     let window = [i * 8, i * 8 + 1 + i % 3];
+    ///     let span = (window[1] - window[0]) as f32;
+    ///     assert_eq!(raw.process_io_cycle_raw(span), full.process_io_cycle(&window));
+    /// }
+    /// assert_eq!(raw.cycles, 0);
+    /// ```
+    // `max`/`min` rather than `clamp`, as in the full cycle.
+    #[allow(clippy::manual_clamp)]
+    #[inline]
+    pub fn process_io_cycle_raw(&mut self, last_delta: f32) -> bool {
+        let guard = no_panic::Guard::arm();
+        let telemetry = self.dsp.features(last_delta, self.math);
+        let bloch_vec = self.prepare_quantum_state(telemetry);
+        let (o1, o2, o3) = Self::povm_measure(&bloch_vec, self.phi, self.math);
+        self.phi = wrap_angle(self.phi, self.lambda[1] * o2);
+        self.epsilon = (self.epsilon + self.lambda[0] * o1)
+            .max(threshold::EPSILON_CLAMP.0)
+            .min(threshold::EPSILON_CLAMP.1);
+        self.spectral = o3;
+        let p_fetch = self.math.sigmoid(-(self.lambda[2] * o3 + self.bias));
+        let fetch = (p_fetch > self.epsilon) & (last_delta != 0.0);
+        guard.disarm();
+        fetch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+    use crate::Preset;
+
+    #[test]
+    fn test_matches_full_cycle_with_stages_off() {
+        // Preset parameters without the stages some presets enable.
+        for preset in Preset::ALL {
+            for pattern in [Pattern::SEQUENTIAL, Pattern::RANDOM, Pattern::BURSTY] {
+                let mut full = preset.kernel().config().build().unwrap();
+                let mut raw = full.clone();
+                let lbas: Vec<u64> = pattern.workload(9).take(2_001).collect();
+                let mut fired = 0;
+                for window in lbas.windows(4) {
+                    let span = window[3].wrapping_sub(window[0]) as i64;
+                    let decision = raw.process_io_cycle_raw(span.unsigned_abs() as f32);
+                    assert_eq!(
+                        decision,
+                        full.process_io_cycle(window),
+                        "{preset} {pattern:?}"
+                    );
+                    fired += decision as u64;
+                }
+                assert_eq!(raw.epsilon.to_bits(), full.epsilon.to_bits());
+                assert_eq!(raw.phi.to_bits(), full.phi.to_bits());
+                assert_eq!((raw.cycles, raw.prefetches), (0, 0));
+                assert_eq!(full.prefetches, fired);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rereads_and_large_spans() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        for _ in 0..100 {
+            assert!(!kernel.process_io_cycle_raw(0.0));
+        }
+        for span in [1.0, 1e12, 3.0, 1e-3] {
+            kernel.process_io_cycle_raw(span);
+            assert!((0.1..=0.9).contains(&kernel.epsilon), "{span}");
+        }
+        assert_eq!(kernel.stats(), crate::KernelStats::default());
+    }
+}
@@ -0,0 +1,500 @@
+//! Binary record of a kernel run, and a replay that checks it.
+//!
+//! Reproducing a production misprediction needs the exact windows the
+//! kernel saw, from the exact state it started in.  A [`Writer`] puts a
+//! [`KernelSnapshot`] at the head of an `.atrace` stream and appends every
+//! window passed to [`process_io_cycle`](AetherLinkKernel::process_io_cycle),
+//! optionally with the decision it produced; [`finish`](Writer::finish)
+//! closes it with a hash of the final state.  [`replay`] rebuilds the kernel
+//! from the header, re-runs every window and reports the first place where
+//! the recomputed run leaves the recorded one.
+//!
+//! Layout (integers little-endian, varints LEB128):
+//!
+//! | field      | encoding                                                 |
+//! |------------|----------------------------------------------------------|
+//! | magic      | `b"ATRC"`                                                |
+//! | version    | `u8`, [`VERSION`]                                        |
+//! | flags      | `u8`; bit 0: decisions recorded                          |
+//! | start      | ε, φ, λ₁, λ₂, λ₃, bias as `f32`; cycles, prefetches `u64` |
+//! | window     | varint `len << 2 \| fired << 1`, then `len` zigzag varints, each LBA minus the one before it (the first minus the previous window's last) |
+//! | end        | varint `1`, varint window count, `u64` [`state_hash`]    |
+//!
+//! A sequential window costs about one byte per LBA.
+//!
+//! The record holds windows, not kernel configuration: record a kernel
+//! with no optional stages, built with
+//! [`from_snapshot`](AetherLinkKernel::from_snapshot) from the snapshot
+//! handed to the writer — a fresh kernel or a restored checkpoint.  A live
+//! kernel's telemetry is not in its snapshot, so recording one mid-run
+//! shows up as an early divergence.
+
+use crate::{AetherLinkKernel, KernelSnapshot};
+use core::fmt;
+use std::io::{self, Read, Write};
+
+/// First bytes of every record.
+pub const MAGIC: [u8; 4] = *b"ATRC";
+/// Format version written, and the only one read.
+pub const VERSION: u8 = 1;
+/// Longest window [`replay`] accepts, against corrupt lengths.
+pub const MAX_WINDOW_LBAS: usize = 1 << 20;
+
+const DECISIONS: u8 = 1;
+const END: u64 = 1;
+
+/// FNV-1a over the bits of every snapshot field, as stored at the end of a
+/// record.  Equal hashes mean bit-identical state.
+pub fn state_hash(snapshot: &KernelSnapshot) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut eat = |bytes: &[u8]| {
+        for &b in bytes {
+            hash = (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for bits in header_floats(snapshot) {
+        eat(&bits.to_le_bytes());
+    }
+    eat(&snapshot.cycles.to_le_bytes());
+    eat(&snapshot.prefetches.to_le_bytes());
+    hash
+}
+
+fn header_floats(s: &KernelSnapshot) -> [u32; 6] {
+    let [l1, l2, l3] = s.lambda;
+    [s.epsilon, s.phi, l1, l2, l3, s.bias].map(f32::to_bits)
+}
+
+/// Appends windows to an `.atrace` stream.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::record::{self, Writer};
+/// use aether_link::AetherLinkKernel;
+///
+/// let start = AetherLinkKernel::new_database().snapshot();
+/// let mut kernel = AetherLinkKernel::from_snapshot(&start);
+/// let mut writer = Writer::new(Vec::new(), &start, true)?;
+/// for i in 0..100u64 {
+///     writer.process_io_cycle(&mut kernel, &[i * 4, i * 4 + 1, i * 4 + 2])?;
+/// }
+/// let bytes = writer.finish(&kernel)?;
+///
+/// let outcome = record::replay(&bytes[..])?;
+/// assert!(outcome.is_faithful());
+/// assert_eq!(outcome.final_state, kernel.snapshot());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    out: W,
+    decisions: bool,
+    prev: u64,
+    windows: u64,
+}
+
+impl<W: Write> Writer<W> {
+    /// Write the header for a run starting from `start`, recording the
+    /// decision of every window if `decisions` is set.
+    pub fn new(mut out: W, start: &KernelSnapshot, decisions: bool) -> io::Result<Self> {
+        out.write_all(&MAGIC)?;
+        out.write_all(&[VERSION, if decisions { DECISIONS } else { 0 }])?;
+        for bits in header_floats(start) {
+            out.write_all(&bits.to_le_bytes())?;
+        }
+        out.write_all(&start.cycles.to_le_bytes())?;
+        out.write_all(&start.prefetches.to_le_bytes())?;
+        Ok(Self {
+            out,
+            decisions,
+            prev: 0,
+            windows: 0,
+        })
+    }
+
+    /// Run `kernel` on `lbas` and record the window and its decision.
+    pub fn process_io_cycle(
+        &mut self,
+        kernel: &mut AetherLinkKernel,
+        lbas: &[u64],
+    ) -> io::Result<bool> {
+        let fired = kernel.process_io_cycle(lbas);
+        self.write_window(lbas, fired)?;
+        Ok(fired)
+    }
+
+    /// Record a window the caller ran itself, and what it decided; the
+    /// decision is dropped if the writer does not record decisions.
+    pub fn write_window(&mut self, lbas: &[u64], fired: bool) -> io::Result<()> {
+        let fired = fired && self.decisions;
+        write_varint(
+            &mut self.out,
+            (lbas.len() as u64) << 2 | (fired as u64) << 1,
+        )?;
+        for &lba in lbas {
+            let delta = lba.wrapping_sub(self.prev) as i64;
+            write_varint(&mut self.out, ((delta << 1) ^ (delta >> 63)) as u64)?;
+            self.prev = lba;
+        }
+        self.windows += 1;
+        Ok(())
+    }
+
+    /// Windows written so far.
+    #[inline]
+    pub fn windows(&self) -> u64 {
+        self.windows
+    }
+
+    /// Close the record with the state `kernel` ended in and return the
+    /// underlying writer, flushed.
+    pub fn finish(mut self, kernel: &AetherLinkKernel) -> io::Result<W> {
+        write_varint(&mut self.out, END)?;
+        write_varint(&mut self.out, self.windows)?;
+        self.out
+            .write_all(&state_hash(&kernel.snapshot()).to_le_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn write_varint(out: &mut impl Write, mut v: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut n = 0;
+    while v >= 0x80 {
+        buf[n] = v as u8 | 0x80;
+        v >>= 7;
+        n += 1;
+    }
+    buf[n] = v as u8;
+    out.write_all(&buf[..=n])
+}
+
+/// Where a replay first left the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// A window decided differently.
+    Decision {
+        /// 0-based index of the window.
+        window: u64,
+        /// Decision in the record.
+        recorded: bool,
+        /// Decision of the replay.
+        replayed: bool,
+    },
+    /// Every recorded decision matched but the final state did not.
+    FinalState {
+        /// [`state_hash`] in the record.
+        recorded: u64,
+        /// [`state_hash`] of the replayed kernel.
+        replayed: u64,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Decision {
+                window,
+                recorded,
+                replayed,
+            } => write!(
+                f,
+                "window {window}: recorded fire={recorded}, replayed fire={replayed}"
+            ),
+            Self::FinalState { recorded, replayed } => write!(
+                f,
+                "final state hash {replayed:#018x}, recorded {recorded:#018x}"
+            ),
+        }
+    }
+}
+
+/// What [`replay`] found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOutcome {
+    /// State the record starts from.
+    pub start: KernelSnapshot,
+    /// Windows replayed.
+    pub windows: u64,
+    /// Whether the record carries decisions to compare.
+    pub decisions: bool,
+    /// State of the replayed kernel at the end.
+    pub final_state: KernelSnapshot,
+    /// First divergence, if any.
+    pub divergence: Option<Divergence>,
+}
+
+impl ReplayOutcome {
+    /// The replay matched every recorded decision and the final state.
+    #[inline]
+    pub fn is_faithful(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Error raised while reading a record.
+#[derive(Debug)]
+pub enum RecordError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// The stream does not start with [`MAGIC`].
+    BadMagic,
+    /// The record was written by another format version.
+    UnsupportedVersion(u8),
+    /// The stream ended before the end marker.
+    Truncated {
+        /// Windows read before it ended.
+        window: u64,
+    },
+    /// Bytes that no writer produces.
+    Malformed {
+        /// Window being read.
+        window: u64,
+        /// What was wrong.
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "record read failed: {e}"),
+            Self::BadMagic => f.write_str("not an .atrace record"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported .atrace version {v}"),
+            Self::Truncated { window } => write!(f, "record ends inside window {window}"),
+            Self::Malformed { window, reason } => write!(f, "window {window}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the fields of a record, counting windows for error reports.
+struct Reader<R> {
+    inner: R,
+    window: u64,
+}
+
+impl<R: Read> Reader<R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], RecordError> {
+        let mut buf = [0; N];
+        self.inner
+            .read_exact(&mut buf)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => RecordError::Truncated {
+                    window: self.window,
+                },
+                _ => RecordError::Io(e),
+            })?;
+        Ok(buf)
+    }
+
+    fn u64(&mut self) -> Result<u64, RecordError> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, RecordError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let [b] = self.bytes()?;
+            v |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(self.malformed("varint longer than 10 bytes"))
+    }
+
+    fn malformed(&self, reason: &'static str) -> RecordError {
+        RecordError::Malformed {
+            window: self.window,
+            reason,
+        }
+    }
+}
+
+/// Rebuild the kernel from the header of `reader`, re-run every recorded
+/// window and compare decisions and final state.  Reads byte by byte;
+/// wrap files in a [`BufReader`](std::io::BufReader).
+pub fn replay(reader: impl Read) -> Result<ReplayOutcome, RecordError> {
+    let mut r = Reader {
+        inner: reader,
+        window: 0,
+    };
+    if r.bytes()? != MAGIC {
+        return Err(RecordError::BadMagic);
+    }
+    let [version, flags] = r.bytes()?;
+    if version != VERSION {
+        return Err(RecordError::UnsupportedVersion(version));
+    }
+    if flags & !DECISIONS != 0 {
+        return Err(r.malformed("unknown flags"));
+    }
+    let decisions = flags & DECISIONS != 0;
+    let mut floats = [0.0f32; 6];
+    for x in &mut floats {
+        *x = f32::from_bits(u32::from_le_bytes(r.bytes()?));
+    }
+    let [epsilon, phi, l1, l2, l3, bias] = floats;
+    let start = KernelSnapshot {
+        epsilon,
+        phi,
+        lambda: [l1, l2, l3],
+        bias,
+        cycles: r.u64()?,
+        prefetches: r.u64()?,
+    };
+
+    let mut kernel = AetherLinkKernel::from_snapshot(&start);
+    let mut divergence = None;
+    let mut lbas = Vec::new();
+    let mut prev = 0u64;
+    loop {
+        let head = r.varint()?;
+        if head == END {
+            break;
+        }
+        if head & 1 != 0 {
+            return Err(r.malformed("unknown window tag"));
+        }
+        let len = usize::try_from(head >> 2)
+            .ok()
+            .filter(|&len| len <= MAX_WINDOW_LBAS)
+            .ok_or_else(|| r.malformed("window too long"))?;
+        lbas.clear();
+        for _ in 0..len {
+            let zz = r.varint()?;
+            let delta = (zz >> 1) as i64 ^ -((zz & 1) as i64);
+            prev = prev.wrapping_add(delta as u64);
+            lbas.push(prev);
+        }
+        let replayed = kernel.process_io_cycle(&lbas);
+        let recorded = head & 2 != 0;
+        if decisions && replayed != recorded && divergence.is_none() {
+            divergence = Some(Divergence::Decision {
+                window: r.window,
+                recorded,
+                replayed,
+            });
+        }
+        r.window += 1;
+    }
+    if r.varint()? != r.window {
+        return Err(r.malformed("window count does not match"));
+    }
+    let recorded = r.u64()?;
+    let final_state = kernel.snapshot();
+    let replayed = state_hash(&final_state);
+    if recorded != replayed && divergence.is_none() {
+        divergence = Some(Divergence::FinalState { recorded, replayed });
+    }
+    Ok(ReplayOutcome {
+        start,
+        windows: r.window,
+        decisions,
+        final_state,
+        divergence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    /// Record `windows` sliding windows of a bursty workload.
+    fn record(decisions: bool, windows: usize) -> (Vec<u8>, AetherLinkKernel) {
+        let start = AetherLinkKernel::new_gaming().snapshot();
+        let mut kernel = AetherLinkKernel::from_snapshot(&start);
+        let mut writer = Writer::new(Vec::new(), &start, decisions).unwrap();
+        let lbas: Vec<u64> = Pattern::BURSTY.workload(5).take(windows + 8).collect();
+        for window in lbas.windows(8).take(windows) {
+            writer.process_io_cycle(&mut kernel, window).unwrap();
+        }
+        // Short windows are recorded and replayed like any other.
+        writer.process_io_cycle(&mut kernel, &[u64::MAX]).unwrap();
+        writer.process_io_cycle(&mut kernel, &[]).unwrap();
+        assert_eq!(writer.windows(), windows as u64 + 2);
+        (writer.finish(&kernel).unwrap(), kernel)
+    }
+
+    #[test]
+    fn test_round_trip_is_faithful() {
+        for decisions in [true, false] {
+            let (bytes, kernel) = record(decisions, 2_000);
+            let outcome = replay(&bytes[..]).unwrap();
+            assert!(outcome.is_faithful(), "{outcome:?}");
+            assert_eq!(outcome.decisions, decisions);
+            assert_eq!(outcome.windows, 2_002);
+            assert_eq!(outcome.start, AetherLinkKernel::new_gaming().snapshot());
+            assert_eq!(outcome.final_state, kernel.snapshot());
+            assert!(kernel.prefetches > 0);
+        }
+        // Deltas keep a bursty stream near two bytes per LBA.
+        let (bytes, _) = record(true, 2_000);
+        assert!(bytes.len() < 2_000 * 8 * 3, "{} bytes", bytes.len());
+    }
+
+    #[test]
+    fn test_corruption_is_reported() {
+        // Windows `4i, 4i + 1, 4i + 2` encode as four one-byte varints
+        // each: the head and deltas of 2, 1, 1.
+        let start = AetherLinkKernel::new_database().snapshot();
+        let mut kernel = AetherLinkKernel::from_snapshot(&start);
+        let mut writer = Writer::new(Vec::new(), &start, true).unwrap();
+        for i in 0..500u64 {
+            writer
+                .process_io_cycle(&mut kernel, &[i * 4, i * 4 + 1, i * 4 + 2])
+                .unwrap();
+        }
+        let bytes = writer.finish(&kernel).unwrap();
+        let header = MAGIC.len() + 2 + 6 * 4 + 2 * 8;
+        let at = header + 4 * 250 + 2;
+        assert_eq!(bytes[at], 2, "delta of +1");
+
+        // Another one-byte delta still parses, so the replay runs to the
+        // end and has to notice.
+        let mut corrupt = bytes.clone();
+        corrupt[at] = 0x7e;
+        let outcome = replay(&corrupt[..]).unwrap();
+        let divergence = outcome.divergence.expect("corruption unnoticed");
+        assert!(!divergence.to_string().is_empty());
+        assert!(replay(&bytes[..]).unwrap().is_faithful());
+
+        // Recorded decisions that cannot all be right.
+        let start = KernelSnapshot::from(AetherLinkKernel::default().config());
+        let mut writer = Writer::new(Vec::new(), &start, true).unwrap();
+        let mut kernel = AetherLinkKernel::from_snapshot(&start);
+        for i in 0..10u64 {
+            let fired = kernel.process_io_cycle(&[i, i + 1]);
+            writer.write_window(&[i, i + 1], fired ^ (i == 6)).unwrap();
+        }
+        let outcome = replay(&writer.finish(&kernel).unwrap()[..]).unwrap();
+        assert!(matches!(
+            outcome.divergence,
+            Some(Divergence::Decision { window: 6, .. })
+        ));
+
+        assert!(matches!(replay(&b"ATRX"[..]), Err(RecordError::BadMagic)));
+        assert!(matches!(
+            replay(&bytes[..bytes.len() - 3]),
+            Err(RecordError::Truncated { .. })
+        ));
+        let mut future = bytes;
+        future[4] = VERSION + 1;
+        assert!(matches!(
+            replay(&future[..]),
+            Err(RecordError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+//! One kernel per region of the device.
+//!
+//! A single kernel learns one ε and φ for the whole device, so a journal
+//! written sequentially, a data area read at random and a swap partition
+//! all pull on the same parameters, and their interleaved accesses make
+//! windows that span regions.  A [`RegionRouter`] splits the LBA space into
+//! up to [`MAX_REGIONS`] contiguous regions — equal ones over a device, or
+//! at given boundaries — and owns a kernel per region cloned from a
+//! template.  Every access goes to the kernel of the region holding it;
+//! a request or window that straddles a boundary goes to the region of
+//! its last block.  Each kernel keeps its own window, telemetry and
+//! optional stages, and [`stats`](RegionRouter::stats) adds their
+//! counters up.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::trace::IoEvent;
+use crate::{stats, AetherLinkKernel, KernelStats};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Most regions a router splits the device into.
+pub const MAX_REGIONS: usize = 16;
+
+/// Kernels for contiguous regions of the LBA space.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::{AetherLinkKernel, RegionRouter};
+///
+/// // A journal in the first 1 Mi blocks, data after it.
+/// let mut template = AetherLinkKernel::default();
+/// template.set_burst_damping(1.0);
+/// let mut router = RegionRouter::with_boundaries(&[1 << 20], template);
+/// for i in 0..1_000u64 {
+///     router.push_lba(i);
+///     router.push_lba((1 << 30) + i * 7_919 % 65_536 * 64);
+/// }
+/// assert_eq!(router.region_of(500), 0);
+/// assert!(router.kernel(0).prefetches > router.kernel(1).prefetches);
+/// assert_eq!(router.stats().cycles, 2 * 999);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegionRouter {
+    /// First LBA of each region, ascending; `starts[0]` is 0.
+    starts: [u64; MAX_REGIONS],
+    kernels: Vec<AetherLinkKernel>,
+}
+
+impl RegionRouter {
+    /// `n_regions` (clamped to `1..=`[`MAX_REGIONS`]) regions of equal size
+    /// over `device_blocks` blocks; the last one also takes every LBA past
+    /// the device.
+    pub fn new(n_regions: usize, device_blocks: u64, template: AetherLinkKernel) -> Self {
+        let n = n_regions.clamp(1, MAX_REGIONS) as u64;
+        let size = (device_blocks / n).max(1);
+        let boundaries: Vec<u64> = (1..n).map(|i| i * size).collect();
+        Self::with_boundaries(&boundaries, template)
+    }
+
+    /// Regions starting at 0 and at each of `boundaries`.  The boundaries
+    /// are sorted and deduplicated, 0 is dropped, and only the lowest
+    /// [`MAX_REGIONS`]` − 1` are kept.
+    pub fn with_boundaries(boundaries: &[u64], template: AetherLinkKernel) -> Self {
+        let mut sorted: Vec<u64> = boundaries.iter().copied().filter(|&b| b > 0).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+        sorted.truncate(MAX_REGIONS - 1);
+        let mut starts = [0; MAX_REGIONS];
+        starts[1..=sorted.len()].copy_from_slice(&sorted);
+        Self {
+            starts,
+            kernels: vec![template; sorted.len() + 1],
+        }
+    }
+
+    /// Number of regions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.kernels.len()
+    }
+
+    /// Always `false`: a router has at least one region.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.kernels.is_empty()
+    }
+
+    /// Region holding `lba`.
+    #[inline]
+    pub fn region_of(&self, lba: u64) -> usize {
+        self.starts[1..self.len()].partition_point(|&start| start <= lba)
+    }
+
+    /// First and last LBA of region `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is not below [`len`](Self::len).
+    pub fn bounds(&self, idx: usize) -> (u64, u64) {
+        assert!(idx < self.len(), "region {idx} out of {}", self.len());
+        let last = if idx + 1 < self.len() {
+            self.starts[idx + 1] - 1
+        } else {
+            u64::MAX
+        };
+        (self.starts[idx], last)
+    }
+
+    /// Kernel of region `idx`.
+    #[inline]
+    pub fn kernel(&self, idx: usize) -> &AetherLinkKernel {
+        &self.kernels[idx]
+    }
+
+    /// Mutable kernel of region `idx`, to configure it apart from the
+    /// others.
+    #[inline]
+    pub fn kernel_mut(&mut self, idx: usize) -> &mut AetherLinkKernel {
+        &mut self.kernels[idx]
+    }
+
+    /// [`AetherLinkKernel::push_lba`] on the kernel of `lba`'s region.
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<PrefetchDecision> {
+        let idx = self.region_of(lba);
+        self.kernels[idx].push_lba(lba)
+    }
+
+    /// [`AetherLinkKernel::push_io`] on the kernel of the region holding
+    /// the request's last block.
+    #[inline]
+    pub fn push_io(&mut self, event: &IoEvent) -> Option<PrefetchDecision> {
+        let idx = self.region_of(event.end().saturating_sub(1).max(event.lba));
+        self.kernels[idx].push_io(event)
+    }
+
+    /// [`AetherLinkKernel::process_io_cycle`] on the kernel of the region
+    /// holding the window's last LBA (region 0 for an empty window).
+    #[inline]
+    pub fn process_io_cycle(&mut self, lba_stream: &[u64]) -> bool {
+        let idx = lba_stream.last().map_or(0, |&lba| self.region_of(lba));
+        self.kernels[idx].process_io_cycle(lba_stream)
+    }
+
+    /// Counters of every region added up; the rolling trigger ratio is
+    /// the mean of the regions' weighted by their cycles.
+    pub fn stats(&self) -> KernelStats {
+        stats::combined(&self.kernels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    const REGION: u64 = 1 << 30;
+
+    #[test]
+    fn test_split_kernels_separate_stream_from_noise() {
+        // A sequential stream in region 0, random reads all over region 1.
+        let mut rng = SplitMix64::new(3);
+        let trace: Vec<u64> = (0..20_000u64)
+            .map(|i| {
+                if i % 2 == 0 {
+                    4_096 + i / 2
+                } else {
+                    REGION + rng.below(REGION)
+                }
+            })
+            .collect();
+        let rate = |fired: u64, n: u64| fired as f64 / n as f64;
+
+        // Burst damping holds back kernels whose accesses scatter: the
+        // noise region's, and a shared kernel's, whose every step jumps
+        // between the regions.
+        let mut template = AetherLinkKernel::default();
+        template.set_burst_damping(1.0);
+        let mut router = RegionRouter::new(2, 2 * REGION, template.clone());
+        let mut shared = template;
+        let mut shared_on_stream = 0;
+        for &lba in &trace {
+            router.push_lba(lba);
+            let fired = shared.push_lba(lba).is_some_and(|d| d.fire);
+            shared_on_stream += (fired && lba < REGION) as u64;
+        }
+        let (a, b) = (router.kernel(0), router.kernel(1));
+        let stream = rate(a.prefetches, a.cycles);
+        let noise = rate(b.prefetches, b.cycles);
+        let shared = rate(shared_on_stream, 10_000);
+        assert!(stream > 0.9, "stream region fired {stream}");
+        assert!(noise < 0.1, "noise region fired {noise}");
+        assert!(shared < 0.5, "shared kernel fired {shared} on the stream");
+
+        let s = router.stats();
+        assert_eq!(s.cycles, a.cycles + b.cycles);
+        assert_eq!(s.cycles, 20_000 - 2);
+        assert_eq!(s.prefetches, a.prefetches + b.prefetches);
+        assert_eq!(s.short_windows, 2);
+        let mean = (a.stats().rolling_ratio + b.stats().rolling_ratio) / 2.0;
+        assert!((s.rolling_ratio - mean).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_boundaries_and_straddling_requests() {
+        let mut router =
+            RegionRouter::with_boundaries(&[5_000, 0, 1_000, 5_000], Default::default());
+        assert_eq!(router.len(), 3);
+        assert_eq!(
+            (0..3).map(|i| router.bounds(i)).collect::<Vec<_>>(),
+            [(0, 999), (1_000, 4_999), (5_000, u64::MAX)]
+        );
+        for (lba, region) in [
+            (0, 0),
+            (999, 0),
+            (1_000, 1),
+            (4_999, 1),
+            (5_000, 2),
+            (u64::MAX, 2),
+        ] {
+            assert_eq!(router.region_of(lba), region, "{lba}");
+        }
+
+        // Requests and windows go to the region of their last block.
+        let straddling = IoEvent {
+            blocks: 20,
+            ..IoEvent::read(990)
+        };
+        router.push_io(&straddling);
+        router.push_io(&straddling);
+        router.process_io_cycle(&[4_990, 5_001]);
+        router.process_io_cycle(&[]);
+        let cycles: Vec<u64> = (0..3).map(|i| router.kernel(i).cycles).collect();
+        assert_eq!(cycles, [0, 1, 1]);
+        assert_eq!(router.kernel(0).stats().short_windows, 1);
+
+        // Equal regions, clamped in number.
+        let router = RegionRouter::new(100, 1 << 20, AetherLinkKernel::default());
+        assert_eq!(router.len(), MAX_REGIONS);
+        assert_eq!(router.bounds(1), (1 << 16, (2 << 16) - 1));
+        assert_eq!(
+            RegionRouter::new(0, 10, AetherLinkKernel::default()).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Human-readable kernel status.
+//!
+//! `Display` for [`AetherLinkKernel`] is a one-line summary;
+//! [`AetherLinkKernel::report`] takes a [`KernelReport`] whose `Display`
+//! is the boxed table the examples and the CLI print.  Only `core::fmt` is
+//! used, so neither allocates.
+
+use crate::class_stats::ClassStats;
+use crate::classify::N_CLASSES;
+use crate::horizon::HorizonStats;
+use crate::{AetherLinkKernel, Preset};
+use core::fmt;
+
+/// Point-in-time kernel status.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KernelReport {
+    /// Preset whose fixed parameters (λ and bias) the kernel has, if any.
+    pub preset: Option<Preset>,
+    /// Current threshold ε.
+    pub epsilon: f32,
+    /// Current basis angle φ.
+    pub phi: f32,
+    /// Scaling coefficients [λ₁, λ₂, λ₃].
+    pub lambda: [f32; 3],
+    /// Sigmoid bias.
+    pub bias: f32,
+    /// Cycles since construction or the last stats reset.
+    pub cycles: u64,
+    /// Cycles that triggered a prefetch.
+    pub prefetches: u64,
+    /// `prefetches / cycles` (0 with no cycles).
+    pub prefetch_ratio: f32,
+    /// Share of reported prefetch outcomes that were useful, once any
+    /// outcome has been reported.
+    pub accuracy: Option<f32>,
+    /// Share of reported useful prefetches the kernel made, once any hit
+    /// or missed opportunity has been reported.
+    pub coverage: Option<f32>,
+    /// Prefetched blocks evicted unused per trigger, once any pollution
+    /// has been reported.
+    pub pollution_rate: Option<f32>,
+    /// Degraded cycles, with a latency budget set.
+    pub degraded_cycles: Option<u64>,
+    /// Plan coverage, with a horizon set.
+    pub horizon: Option<HorizonStats>,
+    /// Counts per workload class, with
+    /// [class stats](AetherLinkKernel::enable_class_stats) on.
+    pub by_class: Option<[ClassStats; N_CLASSES]>,
+}
+
+impl AetherLinkKernel {
+    /// Snapshot of the kernel's status for display.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_gaming();
+    /// for i in 0..100u64 {
+    ///     kernel.process_io_cycle(&[i, i + 1, i + 2]);
+    /// }
+    /// let report = kernel.report();
+    /// assert_eq!(report.cycles, 100);
+    /// println!("{kernel}");
+    /// println!("{report}");
+    /// ```
+    pub fn report(&self) -> KernelReport {
+        let reported = self.outcomes_reported();
+        KernelReport {
+            preset: Preset::ALL
+                .into_iter()
+                .find(|p| p.kernel().lambda == self.lambda && p.kernel().bias == self.bias),
+            epsilon: self.epsilon,
+            phi: self.phi,
+            lambda: self.lambda,
+            bias: self.bias,
+            cycles: self.cycles,
+            prefetches: self.prefetches,
+            prefetch_ratio: self.prefetch_ratio(),
+            accuracy: reported.0.then(|| self.prefetch_accuracy()),
+            coverage: reported.2.then(|| self.prefetch_coverage()),
+            pollution_rate: reported.1.then(|| self.pollution_rate()),
+            degraded_cycles: self.budget.is_some().then(|| self.degraded_cycles()),
+            horizon: self.horizon.is_some().then(|| self.horizon_stats()),
+            by_class: self.class_stats_enabled().then(|| self.stats_by_class()),
+        }
+    }
+}
+
+impl fmt::Display for AetherLinkKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report();
+        match report.preset {
+            Some(preset) => write!(f, "aether[{preset}]")?,
+            None => f.write_str("aether[custom]")?,
+        }
+        write!(
+            f,
+            " ε={:.4} φ={:.4} cycles={} triggers={} ({:.1}%)",
+            report.epsilon,
+            report.phi,
+            report.cycles,
+            report.prefetches,
+            report.prefetch_ratio * 100.0
+        )
+    }
+}
+
+impl fmt::Display for KernelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter<'_>, label: &str, value: fmt::Arguments<'_>| {
+            let mut cell = Cell::default();
+            fmt::Write::write_fmt(&mut cell, value)?;
+            writeln!(f, "│ {label:<14} │ {:>14} │", cell.as_str())
+        };
+        let pct = |x: f32| x * 100.0;
+        writeln!(f, "┌────────────────┬────────────────┐")?;
+        match self.preset {
+            Some(preset) => row(f, "Preset", format_args!("{preset}"))?,
+            None => row(f, "Preset", format_args!("custom"))?,
+        }
+        row(f, "ε", format_args!("{:.4}", self.epsilon))?;
+        row(f, "φ", format_args!("{:.4}", self.phi))?;
+        let [l1, l2, l3] = self.lambda;
+        row(f, "λ", format_args!("{l1:.2}/{l2:.2}/{l3:.2}"))?;
+        row(f, "Bias", format_args!("{:.4}", self.bias))?;
+        row(f, "Cycles", format_args!("{}", self.cycles))?;
+        row(f, "Triggers", format_args!("{}", self.prefetches))?;
+        row(f, "Ratio", format_args!("{:.1}%", pct(self.prefetch_ratio)))?;
+        if let Some(accuracy) = self.accuracy {
+            row(f, "Accuracy", format_args!("{:.1}%", pct(accuracy)))?;
+        }
+        if let Some(coverage) = self.coverage {
+            row(f, "Coverage", format_args!("{:.1}%", pct(coverage)))?;
+        }
+        if let Some(rate) = self.pollution_rate {
+            row(f, "Pollution", format_args!("{rate:.3}/trigger"))?;
+        }
+        if let Some(degraded) = self.degraded_cycles {
+            row(f, "Degraded", format_args!("{degraded}"))?;
+        }
+        if let Some(horizon) = self.horizon {
+            row(f, "Plans", format_args!("{}", horizon.plans))?;
+            let coverage = horizon.coverage() as f32;
+            row(f, "Plan coverage", format_args!("{:.1}%", pct(coverage)))?;
+        }
+        if let Some(by_class) = &self.by_class {
+            row(f, "By class", format_args!("triggers/cycles"))?;
+            for s in by_class.iter().filter(|s| s.cycles > 0) {
+                let value = format_args!("{}/{}", s.prefetches, s.cycles);
+                row(f, s.class.name(), value)?;
+            }
+        }
+        write!(f, "└────────────────┴────────────────┘")
+    }
+}
+
+/// Fixed-size buffer a table cell is formatted into, so it can be padded
+/// without allocating.  Text past its capacity is cut at a character
+/// boundary.
+#[derive(Default)]
+struct Cell {
+    buf: [u8; 32],
+    len: usize,
+}
+
+impl Cell {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for Cell {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let n = c.len_utf8();
+            if self.len + n > self.buf.len() {
+                break;
+            }
+            c.encode_utf8(&mut self.buf[self.len..]);
+            self.len += n;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_cycles_print_no_nan() {
+        for preset in Preset::ALL {
+            let kernel = preset.kernel();
+            let line = kernel.to_string();
+            let table = kernel.report().to_string();
+            assert!(line.contains(&format!("aether[{preset}]")), "{line}");
+            assert!(line.contains("cycles=0 triggers=0 (0.0%)"), "{line}");
+            for text in [&line, &table] {
+                assert!(!text.contains("NaN") && !text.contains("inf"), "{text}");
+            }
+            assert!(
+                table.contains("│ Ratio          │           0.0% │"),
+                "{table}"
+            );
+            assert!(!table.contains("Accuracy"), "{table}");
+        }
+    }
+
+    #[test]
+    fn test_report_rows_follow_enabled_stats() {
+        let mut kernel = AetherLinkKernel::new(0.3, 0.1, [0.1, 0.1, 0.1], 0.0);
+        assert!(kernel.to_string().starts_with("aether[custom] ε=0.3000"));
+        kernel.set_latency_budget(16);
+        kernel.set_horizon(4);
+        for i in 0..200u64 {
+            if kernel.process_io_cycle(&[i * 4, i * 4 + 4]) {
+                kernel.record_prefetch_outcome(i % 4 != 0);
+            }
+        }
+        kernel.record_pollution(10);
+        let report = kernel.report();
+        assert_eq!(report.cycles, 200);
+        assert!(report.accuracy.unwrap() > 0.7);
+        let table = report.to_string();
+        for row in [
+            "Accuracy",
+            "Coverage",
+            "Pollution",
+            "Degraded",
+            "Plans",
+            "Plan coverage",
+        ] {
+            assert!(table.contains(&format!("│ {row:<14} │")), "{table}");
+        }
+        assert!(
+            table.contains("│ Cycles         │            200 │"),
+            "{table}"
+        );
+        let widths: Vec<usize> = table.lines().map(|l| l.chars().count()).collect();
+        assert!(widths.iter().all(|&w| w == widths[0]), "{table}");
+    }
+}
@@ -0,0 +1,164 @@
+//! A decision thread fed through a channel.
+//!
+//! A common layout has producer threads sending LBAs into an
+//! `std::sync::mpsc` channel and one thread deciding on them.
+//! [`run_on_receiver`] is that thread's loop: it blocks for an LBA, drains
+//! whatever else is already waiting — up to [`MAX_DRAIN`] LBAs in all —
+//! into one [micro-batch](crate::microbatch), and hands every triggered
+//! plan to a callback.  Under light load batches hold a single LBA and the
+//! kernel decides per access; under heavy load they grow and the thread
+//! keeps up.  When every sender is gone the LBAs still queued are
+//! decided, and the loop returns its totals.
+
+use crate::prefetcher::PrefetchPlan;
+use crate::AetherLinkKernel;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+/// Most LBAs drained into one micro-batch.
+pub const MAX_DRAIN: usize = 64;
+
+/// Totals of one [`run_on_receiver`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunSummary {
+    /// LBAs received.
+    pub lbas: u64,
+    /// Micro-batches decided.
+    pub batches: u64,
+    /// Batches that fired, each handed to the callback once.
+    pub triggers: u64,
+}
+
+/// Decide on LBAs from `rx` until every sender has hung up.
+///
+/// Each firing batch passes its [extent](crate::microbatch::MicroBatchDecision::extent)
+/// to `on_trigger`, or, when the batch suggests none, as many blocks
+/// after its last LBA as it held.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::runner::run_on_receiver;
+/// use aether_link::AetherLinkKernel;
+/// use std::sync::mpsc;
+///
+/// let (tx, rx) = mpsc::channel();
+/// let producer = std::thread::spawn(move || {
+///     for lba in 0..10_000u64 {
+///         tx.send(lba).unwrap();
+///     }
+/// });
+/// let mut kernel = AetherLinkKernel::default();
+/// let mut plans = Vec::new();
+/// let summary = run_on_receiver(&mut kernel, rx, |plan| plans.push(plan));
+/// producer.join().unwrap();
+/// assert_eq!(summary.lbas, 10_000);
+/// assert_eq!(summary.triggers, plans.len() as u64);
+/// ```
+pub fn run_on_receiver(
+    kernel: &mut AetherLinkKernel,
+    rx: Receiver<u64>,
+    mut on_trigger: impl FnMut(PrefetchPlan),
+) -> RunSummary {
+    let mut summary = RunSummary::default();
+    let mut batch = [0u64; MAX_DRAIN];
+    let mut open = true;
+    while open {
+        let Ok(first) = rx.recv() else {
+            break;
+        };
+        batch[0] = first;
+        let mut n = 1;
+        while n < MAX_DRAIN {
+            match rx.try_recv() {
+                Ok(lba) => {
+                    batch[n] = lba;
+                    n += 1;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        let lbas = &batch[..n];
+        let decision = kernel.process_micro_batch(lbas);
+        summary.lbas += n as u64;
+        summary.batches += 1;
+        if decision.fire {
+            let after = PrefetchPlan::new(lbas[n - 1].saturating_add(1), n as u64);
+            on_trigger(decision.extent.unwrap_or(after));
+            summary.triggers += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_spawned_producers_every_trigger_seen() {
+        let (tx, rx) = mpsc::channel();
+        let producers: Vec<_> = (0..4u64)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..5_000u64 {
+                        tx.send((p << 30) + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let mut kernel = AetherLinkKernel::default();
+        let mut plans = Vec::new();
+        let summary = run_on_receiver(&mut kernel, rx, |plan| plans.push(plan));
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(summary.lbas, 20_000);
+        assert!(summary.batches >= 20_000 / MAX_DRAIN as u64, "{summary:?}");
+        assert!(summary.batches <= summary.lbas);
+        assert_eq!(summary.triggers, plans.len() as u64);
+        assert!(summary.triggers > 0, "{summary:?}");
+        // Every batch but a first single LBA is one kernel cycle.
+        assert!(kernel.cycles + 1 >= summary.batches, "{summary:?}");
+        assert_eq!(kernel.prefetches, summary.triggers);
+        assert!(plans.iter().all(|plan| !plan.is_empty()));
+    }
+
+    #[test]
+    fn test_drains_queued_lbas_in_bounded_batches_after_hangup() {
+        // Everything is queued and the sender gone before the loop starts:
+        // it still decides on all of it, in full batches.
+        let (tx, rx) = mpsc::channel();
+        for lba in 0..1_000u64 {
+            tx.send(lba).unwrap();
+        }
+        drop(tx);
+        let mut kernel = AetherLinkKernel::default();
+        let mut seen = 0;
+        let summary = run_on_receiver(&mut kernel, rx, |_| seen += 1);
+        let batches = (1_000 + MAX_DRAIN as u64 - 1) / MAX_DRAIN as u64;
+        assert_eq!(
+            summary,
+            RunSummary {
+                lbas: 1_000,
+                batches,
+                triggers: seen,
+            }
+        );
+        assert_eq!(kernel.cycles, batches);
+
+        // A channel closed empty returns at once.
+        let (tx, rx) = mpsc::channel::<u64>();
+        drop(tx);
+        let summary = run_on_receiver(&mut kernel, rx, |_| unreachable!());
+        assert_eq!(summary, RunSummary::default());
+    }
+}
@@ -0,0 +1,319 @@
+//! Self-timing: the kernel's own cycle latency, measured in production.
+//!
+//! With the `selftime` feature, [`AetherLinkKernel::enable_self_timing`]
+//! makes every `every`-th cycle read a cycle counter before and after
+//! itself and add the difference to a [`LatencyHistogram`] of 65
+//! power-of-two buckets, so the cost is two counter reads on sampled
+//! cycles and one countdown on the rest.
+//! [`AetherLinkKernel::latency_percentiles`] converts the histogram to
+//! nanoseconds.
+//!
+//! On x86_64 the counter is `rdtsc`, converted with a TSC frequency
+//! calibrated against [`Instant`] once per process (a few milliseconds, on
+//! the first report).  Elsewhere the counter is [`Instant`] itself, in
+//! nanoseconds.  Without the feature the timing hook compiles away and the
+//! kernel has no extra field.
+
+use crate::AetherLinkKernel;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Number of histogram buckets: one for 0 and one per bit of a `u64`.
+pub const BUCKETS: usize = 65;
+
+/// Counts of tick values in power-of-two buckets.  Bucket 0 holds 0 and
+/// bucket `b > 0` holds `[2^(b-1), 2^b)`, so a reported value is within a
+/// factor of two of the true one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Bucket holding `ticks`.
+    #[inline(always)]
+    pub fn bucket_of(ticks: u64) -> usize {
+        (u64::BITS - ticks.leading_zeros()) as usize
+    }
+
+    /// Largest value bucket `b` holds.
+    #[inline]
+    pub fn bucket_upper(b: usize) -> u64 {
+        match b {
+            0 => 0,
+            64.. => u64::MAX,
+            _ => (1u64 << b) - 1,
+        }
+    }
+
+    /// Count one sample.
+    #[inline(always)]
+    pub fn record(&mut self, ticks: u64) {
+        self.buckets[Self::bucket_of(ticks)] += 1;
+        self.count += 1;
+    }
+
+    /// Samples recorded.
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Per-bucket counts.
+    #[inline]
+    pub fn buckets(&self) -> &[u64; BUCKETS] {
+        &self.buckets
+    }
+
+    /// Upper bound of the bucket holding the `q`-quantile (`q` in [0, 1]),
+    /// in ticks; 0 without samples.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let q = if q.is_nan() { 0.0 } else { q.clamp(0.0, 1.0) };
+        let rank = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (b, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Self::bucket_upper(b);
+            }
+        }
+        u64::MAX
+    }
+
+    /// Forget all samples.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Cycle latency percentiles in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LatencyReport {
+    /// Median.
+    pub p50: f64,
+    /// 99th percentile.
+    pub p99: f64,
+    /// 99.9th percentile.
+    pub p999: f64,
+    /// Sampled cycles the report is based on; 0 when nothing was measured.
+    pub samples: u64,
+}
+
+/// Sampling state and histogram.
+#[derive(Debug, Clone)]
+pub(crate) struct SelfTimer {
+    every: u32,
+    /// Cycles left until the next sample.
+    countdown: u32,
+    histogram: LatencyHistogram,
+}
+
+impl SelfTimer {
+    /// Count one cycle; `true` if it should be timed.
+    #[inline(always)]
+    fn due(&mut self) -> bool {
+        self.countdown -= 1;
+        if self.countdown == 0 {
+            self.countdown = self.every;
+            return true;
+        }
+        false
+    }
+}
+
+/// Current value of the cycle counter.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub fn now_ticks() -> u64 {
+    // SAFETY: rdtsc has no preconditions on x86_64 (`_rdtsc` is an unsafe
+    // fn on older toolchains only).
+    #[allow(unused_unsafe)]
+    unsafe {
+        core::arch::x86_64::_rdtsc()
+    }
+}
+
+/// Current value of the cycle counter: nanoseconds since the first call.
+#[cfg(not(target_arch = "x86_64"))]
+#[inline(always)]
+pub fn now_ticks() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Counter ticks per nanosecond, calibrated once per process.
+pub fn ticks_per_ns() -> f64 {
+    static RATE: OnceLock<f64> = OnceLock::new();
+    *RATE.get_or_init(|| {
+        if cfg!(not(target_arch = "x86_64")) {
+            return 1.0;
+        }
+        let (t0, i0) = (now_ticks(), Instant::now());
+        while i0.elapsed() < Duration::from_millis(5) {
+            std::hint::spin_loop();
+        }
+        let (t1, elapsed) = (now_ticks(), i0.elapsed());
+        let rate = t1.wrapping_sub(t0) as f64 / elapsed.as_nanos().max(1) as f64;
+        if rate.is_finite() && rate > 0.0 {
+            rate
+        } else {
+            1.0
+        }
+    })
+}
+
+impl AetherLinkKernel {
+    /// Time one cycle in every `every` (at least 1) into the latency
+    /// histogram, replacing any earlier samples.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_hft();
+    /// kernel.enable_self_timing(4);
+    /// for i in 0..1_000u64 {
+    ///     kernel.process_io_cycle(&[i, i + 1]);
+    /// }
+    /// let report = kernel.latency_percentiles();
+    /// assert_eq!(report.samples, 250);
+    /// assert!(report.p50 <= report.p99 && report.p99 <= report.p999);
+    /// ```
+    pub fn enable_self_timing(&mut self, every: u32) {
+        let every = every.max(1);
+        self.selftime = Some(SelfTimer {
+            every,
+            countdown: every,
+            histogram: LatencyHistogram::default(),
+        });
+    }
+
+    /// Stop timing cycles and drop the histogram.
+    #[inline]
+    pub fn disable_self_timing(&mut self) {
+        self.selftime = None;
+    }
+
+    /// Raw histogram of sampled cycle latencies in counter ticks, if
+    /// self-timing is on.
+    #[inline]
+    pub fn latency_histogram(&self) -> Option<&LatencyHistogram> {
+        self.selftime.as_ref().map(|t| &t.histogram)
+    }
+
+    /// p50, p99 and p999 of the sampled cycles, in nanoseconds.  All zero
+    /// when self-timing is off or nothing has been sampled yet.
+    pub fn latency_percentiles(&self) -> LatencyReport {
+        let Some(histogram) = self.latency_histogram().filter(|h| h.count() > 0) else {
+            return LatencyReport::default();
+        };
+        let rate = ticks_per_ns();
+        let ns = |q| histogram.quantile(q) as f64 / rate;
+        LatencyReport {
+            p50: ns(0.5),
+            p99: ns(0.99),
+            p999: ns(0.999),
+            samples: histogram.count(),
+        }
+    }
+
+    /// Clear the histogram, keeping the sampling rate.
+    pub(crate) fn reset_self_timing(&mut self) {
+        if let Some(timer) = &mut self.selftime {
+            timer.histogram.clear();
+        }
+    }
+
+    /// Run one cycle, timing it if it is due for a sample.
+    #[inline(always)]
+    pub(crate) fn timed<R>(&mut self, cycle: impl FnOnce(&mut Self) -> R) -> R {
+        if !self.selftime.as_mut().is_some_and(|t| t.due()) {
+            return cycle(self);
+        }
+        let start = now_ticks();
+        let out = cycle(self);
+        let ticks = now_ticks().saturating_sub(start);
+        if let Some(timer) = &mut self.selftime {
+            timer.histogram.record(ticks);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_math() {
+        assert_eq!(LatencyHistogram::bucket_of(0), 0);
+        assert_eq!(LatencyHistogram::bucket_of(1), 1);
+        assert_eq!(LatencyHistogram::bucket_of(2), 2);
+        assert_eq!(LatencyHistogram::bucket_of(3), 2);
+        assert_eq!(LatencyHistogram::bucket_of(4), 3);
+        assert_eq!(LatencyHistogram::bucket_of(u64::MAX), 64);
+        for b in 1..64 {
+            let upper = LatencyHistogram::bucket_upper(b);
+            assert_eq!(LatencyHistogram::bucket_of(upper), b);
+            assert_eq!(LatencyHistogram::bucket_of(upper + 1), b + 1);
+        }
+        assert_eq!(LatencyHistogram::bucket_upper(64), u64::MAX);
+    }
+
+    #[test]
+    fn test_percentiles_of_synthetic_samples() {
+        let mut h = LatencyHistogram::default();
+        assert_eq!(h.quantile(0.5), 0);
+        // 1000 samples: 990 at 20 ticks, 9 at 300, one at 5000.
+        for _ in 0..990 {
+            h.record(20);
+        }
+        for _ in 0..9 {
+            h.record(300);
+        }
+        h.record(5_000);
+        assert_eq!(h.count(), 1_000);
+        assert_eq!(h.quantile(0.5), 31, "bucket [16, 32)");
+        assert_eq!(h.quantile(0.99), 31, "rank 990 is the last fast one");
+        assert_eq!(h.quantile(0.995), 511, "bucket [256, 512)");
+        assert_eq!(h.quantile(0.999), 511);
+        assert_eq!(h.quantile(1.0), 8_191, "bucket [4096, 8192)");
+        assert_eq!(h.quantile(0.0), 31);
+        h.clear();
+        assert_eq!(h.count(), 0);
+    }
+
+    #[test]
+    fn test_sampling_rate_and_reset() {
+        let mut kernel = AetherLinkKernel::default();
+        assert_eq!(kernel.latency_percentiles(), LatencyReport::default());
+        kernel.enable_self_timing(10);
+        for i in 0..1_005u64 {
+            kernel.process_io_cycle(&[i, i + 2]);
+        }
+        for i in 0..500u64 {
+            kernel.push_lba(i);
+        }
+        let report = kernel.latency_percentiles();
+        assert_eq!(report.samples, 150);
+        assert!(report.p50 <= report.p999 && report.p999 > 0.0, "{report:?}");
+        kernel.reset_stats();
+        assert_eq!(kernel.latency_histogram().unwrap().count(), 0);
+        kernel.disable_self_timing();
+        assert!(kernel.latency_histogram().is_none());
+    }
+}
@@ -0,0 +1,261 @@
+//! Pulling interleaved streams apart before they reach the telemetry.
+//!
+//! Two sequential streams sharing one queue (`100, 5000, 101, 5001, …`)
+//! make a window whose span jumps back and forth, and the kernel sees
+//! noise where each stream alone is as regular as streams get.  A
+//! [`StreamSeparator`] keeps a few stream *heads* — the newest LBA of each
+//! sub-stream it has detected, with a kernel of its own cloned from a
+//! template — and sends every access to the nearest head within
+//! [`max_distance`](SeparatorOptions::max_distance) blocks, in either
+//! direction.  That head's kernel extends its private history and decides.
+//! An access near no head opens one, recycling the least recently used
+//! head (and a fresh kernel) when all are taken.
+//!
+//! A head becomes an *active* sub-stream once
+//! [`min_run`](SeparatorOptions::min_run) accesses have joined it.  Random
+//! traffic rarely lands near a head and keeps recycling them, so it does
+//! not hold an active one for long.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::{stats, AetherLinkKernel, KernelStats};
+use alloc::vec::Vec;
+
+/// Most heads a separator keeps.
+pub const MAX_HEADS: usize = 32;
+
+/// How sub-streams are told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeparatorOptions {
+    /// Heads kept, clamped to `1..=`[`MAX_HEADS`].
+    pub heads: usize,
+    /// Farthest an access may land from a head's newest LBA and still
+    /// join it, in blocks.
+    pub max_distance: u64,
+    /// Accesses a head needs before it counts as an active sub-stream.
+    pub min_run: u64,
+}
+
+impl Default for SeparatorOptions {
+    fn default() -> Self {
+        Self {
+            heads: 8,
+            max_distance: 64,
+            min_run: 8,
+        }
+    }
+}
+
+/// One detected sub-stream.
+#[derive(Debug, Clone)]
+struct Head {
+    kernel: AetherLinkKernel,
+    /// Newest LBA of the sub-stream.
+    last: u64,
+    /// Accesses since the head was opened.
+    run: u64,
+    /// Clock value of the last access.
+    used: u64,
+}
+
+/// Kernels for the sub-streams of an interleaved access stream.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::separate::{SeparatorOptions, StreamSeparator};
+/// use aether_link::AetherLinkKernel;
+///
+/// let mut separator = StreamSeparator::new(
+///     AetherLinkKernel::default(),
+///     SeparatorOptions::default(),
+/// );
+/// for i in 0..100u64 {
+///     separator.push_lba(100 + i);
+///     separator.push_lba(5_000 + i);
+/// }
+/// assert_eq!(separator.active_streams(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamSeparator {
+    template: AetherLinkKernel,
+    options: SeparatorOptions,
+    heads: Vec<Head>,
+    clock: u64,
+    opened: u64,
+}
+
+impl StreamSeparator {
+    /// A separator whose heads start as clones of `template`.
+    pub fn new(template: AetherLinkKernel, options: SeparatorOptions) -> Self {
+        let options = SeparatorOptions {
+            heads: options.heads.clamp(1, MAX_HEADS),
+            ..options
+        };
+        Self {
+            template,
+            heads: Vec::with_capacity(options.heads),
+            options,
+            clock: 0,
+            opened: 0,
+        }
+    }
+
+    /// The options in effect, heads clamped.
+    #[inline]
+    pub fn options(&self) -> SeparatorOptions {
+        self.options
+    }
+
+    /// Send `lba` to its sub-stream's kernel and return that kernel's
+    /// [`push_lba`](AetherLinkKernel::push_lba) decision: `None` for the
+    /// first access of a sub-stream.
+    pub fn push_lba(&mut self, lba: u64) -> Option<PrefetchDecision> {
+        self.clock += 1;
+        let idx = self.head_for(lba);
+        let head = &mut self.heads[idx];
+        head.last = lba;
+        head.run += 1;
+        head.used = self.clock;
+        head.kernel.push_lba(lba)
+    }
+
+    /// Index of the head `lba` joins, opening one if none is near.
+    fn head_for(&mut self, lba: u64) -> usize {
+        let nearest = self
+            .heads
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.last.abs_diff(lba), i))
+            .filter(|&(distance, _)| distance <= self.options.max_distance)
+            .min();
+        if let Some((_, idx)) = nearest {
+            return idx;
+        }
+        self.opened += 1;
+        let head = Head {
+            kernel: self.template.clone(),
+            last: lba,
+            run: 0,
+            used: self.clock,
+        };
+        if self.heads.len() < self.options.heads {
+            self.heads.push(head);
+            return self.heads.len() - 1;
+        }
+        let lru = (0..self.heads.len())
+            .min_by_key(|&i| self.heads[i].used)
+            .unwrap_or(0);
+        self.heads[lru] = head;
+        lru
+    }
+
+    /// Heads with at least [`min_run`](SeparatorOptions::min_run)
+    /// accesses: the sub-streams currently detected.
+    pub fn active_streams(&self) -> usize {
+        self.heads
+            .iter()
+            .filter(|h| h.run >= self.options.min_run)
+            .count()
+    }
+
+    /// Heads in use, active or not.
+    #[inline]
+    pub fn heads(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Heads opened so far, recycled ones included.
+    #[inline]
+    pub fn opened(&self) -> u64 {
+        self.opened
+    }
+
+    /// Counters of the current heads' kernels added up; the rolling
+    /// trigger ratio is the mean of theirs weighted by their cycles.
+    /// A recycled head's counts leave with it.
+    pub fn stats(&self) -> KernelStats {
+        stats::combined(self.heads.iter().map(|h| &h.kernel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    /// Kernels that hold back on scattered windows, so a merged stream
+    /// shows.
+    fn template() -> AetherLinkKernel {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_burst_damping(1.0);
+        kernel
+    }
+
+    fn fired(decision: Option<PrefetchDecision>) -> bool {
+        decision.is_some_and(|d| d.fire)
+    }
+
+    #[test]
+    fn test_two_interleaved_streams_fire_as_alone() {
+        const N: u64 = 10_000;
+        let (a, b) = (|i: u64| 100 + i, |i: u64| 5_000_000 + i);
+        let mut alone = [template(), template()];
+        let mut alone_fired = [0u64; 2];
+        for i in 0..N {
+            alone_fired[0] += fired(alone[0].push_lba(a(i))) as u64;
+            alone_fired[1] += fired(alone[1].push_lba(b(i))) as u64;
+        }
+
+        let mut separator = StreamSeparator::new(template(), SeparatorOptions::default());
+        let mut merged = template();
+        let (mut split_fired, mut merged_fired) = ([0u64; 2], 0u64);
+        for i in 0..N {
+            for (s, lba) in [a(i), b(i)].into_iter().enumerate() {
+                split_fired[s] += fired(separator.push_lba(lba)) as u64;
+                merged_fired += fired(merged.push_lba(lba)) as u64;
+            }
+        }
+        let rate = |n: u64| n as f64 / N as f64;
+        for s in 0..2 {
+            let (split, alone) = (rate(split_fired[s]), rate(alone_fired[s]));
+            assert!(alone > 0.9, "stream {s} alone fired {alone}");
+            assert!((split - alone).abs() < 0.01, "{split} vs {alone}");
+        }
+        assert!(
+            rate(merged_fired) / 2.0 < 0.5,
+            "merged fired {merged_fired}"
+        );
+        assert_eq!((separator.active_streams(), separator.opened()), (2, 2));
+        let s = separator.stats();
+        assert_eq!(s.prefetches, split_fired[0] + split_fired[1]);
+        assert_eq!(s.cycles, 2 * (N - 1));
+    }
+
+    #[test]
+    fn test_random_input_detects_no_streams() {
+        let mut rng = SplitMix64::new(17);
+        let mut separator = StreamSeparator::new(template(), SeparatorOptions::default());
+        for _ in 0..20_000 {
+            separator.push_lba(rng.below(1 << 30));
+            assert_eq!(separator.active_streams(), 0);
+        }
+        assert_eq!(separator.heads(), 8);
+        assert!(separator.opened() > 19_000, "{}", separator.opened());
+
+        // Streams among the noise are still found, and only they.
+        let mut separator = StreamSeparator::new(template(), SeparatorOptions::default());
+        for i in 0..2_000u64 {
+            separator.push_lba(1 << 40 | i);
+            separator.push_lba(rng.below(1 << 30));
+            separator.push_lba((2 << 40) - i);
+        }
+        assert_eq!(separator.active_streams(), 2);
+
+        let one = SeparatorOptions {
+            heads: 0,
+            ..SeparatorOptions::default()
+        };
+        assert_eq!(StreamSeparator::new(template(), one).options().heads, 1);
+    }
+}
@@ -0,0 +1,565 @@
+//! Per-core kernels with a periodic consensus.
+//!
+//! [`AtomicAetherKernel`](crate::AtomicAetherKernel) shares one learner
+//! between threads and pays a compare-exchange per step for it.  A
+//! [`ShardedKernelPool`] goes the other way: every thread takes a [`Shard`]
+//! that owns a private [`AetherLinkKernel`], so the decision path touches
+//! no shared memory at all.  Every [`merge_every`](ShardedKernelPool::merge_every)
+//! cycles a shard publishes its ε and φ, folds the latest published values
+//! of all shards into a consensus — arithmetic mean for ε, circular mean
+//! for the angle φ — under a short lock, and adopts it.  Each shard picks
+//! the consensus up at its own next merge, so a busy shard never waits for
+//! an idle one.
+//!
+//! Counters are published at every merge and when a shard is dropped;
+//! [`stats`](ShardedKernelPool::stats) is exact once every shard is gone.
+//!
+//! On multi-socket machines, [`with_topology`](ShardedKernelPool::with_topology)
+//! assigns shards round-robin to the NUMA nodes that have CPUs, backs each
+//! shard's kernel with memory on its node (see [`crate::numa`]) and
+//! lets threads find a local shard with
+//! [`shard_for_cpu`](ShardedKernelPool::shard_for_cpu).  Only the kernel,
+//! read and written on every decision, is placed; the published slots are
+//! touched once per merge.
+
+use crate::numa::{self, NumaTopology};
+use crate::AetherLinkKernel;
+use core::f32::consts::PI;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::alloc::{self, Layout};
+use std::sync::Mutex;
+
+/// Cycles between merges unless set with
+/// [`merge_every`](ShardedKernelPool::merge_every).
+pub const DEFAULT_MERGE_EVERY: u64 = 10_000;
+
+/// Published state of one shard, on its own cache line.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct Slot {
+    epsilon: AtomicU32,
+    phi: AtomicU32,
+    cycles: AtomicU64,
+    prefetches: AtomicU64,
+    /// ε and φ hold a published value.
+    published: AtomicBool,
+    /// A [`Shard`] handle is alive.
+    taken: AtomicBool,
+}
+
+/// Aggregate counters of a [`ShardedKernelPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShardStats {
+    /// Cycles published by all shards.
+    pub cycles: u64,
+    /// Prefetches published by all shards.
+    pub prefetches: u64,
+    /// Merges performed.
+    pub merges: u64,
+}
+
+/// `n` kernels cloned from one template, merged every few thousand cycles.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::{AetherLinkKernel, ShardedKernelPool};
+///
+/// let pool = ShardedKernelPool::new(4, AetherLinkKernel::default()).merge_every(1_000);
+/// std::thread::scope(|s| {
+///     for idx in 0..4 {
+///         let pool = &pool;
+///         s.spawn(move || {
+///             let mut shard = pool.shard(idx).unwrap();
+///             let base = (idx as u64) << 32;
+///             for i in 0..5_000 {
+///                 shard.push_lba(base + i);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(pool.stats().cycles, 4 * (5_000 - 1));
+/// ```
+#[derive(Debug)]
+pub struct ShardedKernelPool {
+    template: AetherLinkKernel,
+    slots: Vec<Slot>,
+    topology: Option<NumaTopology>,
+    /// Node id of each shard, with a topology.
+    nodes: Vec<usize>,
+    merge_every: u64,
+    /// Number of merges; held while computing a consensus.
+    merges: Mutex<u64>,
+}
+
+impl ShardedKernelPool {
+    /// `n_shards` (at least one) kernels cloned from `template`.
+    pub fn new(n_shards: usize, template: AetherLinkKernel) -> Self {
+        Self {
+            template,
+            slots: (0..n_shards.max(1)).map(|_| Slot::default()).collect(),
+            topology: None,
+            nodes: Vec::new(),
+            merge_every: DEFAULT_MERGE_EVERY,
+            merges: Mutex::new(0),
+        }
+    }
+
+    /// Merge after every `cycles` cycles of a shard; `0` only merges on
+    /// explicit [`Shard::merge`].
+    pub fn merge_every(mut self, cycles: u64) -> Self {
+        self.merge_every = cycles;
+        self
+    }
+
+    /// Spread the shards over the nodes of `topology` that have CPUs,
+    /// round-robin, and place each shard's kernel on its node.  A topology
+    /// without CPUs is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::numa::NumaTopology;
+    /// use aether_link::{AetherLinkKernel, ShardedKernelPool};
+    ///
+    /// let topology = NumaTopology::detect().unwrap_or_else(|| NumaTopology::single_node(4));
+    /// let pool = ShardedKernelPool::new(4, AetherLinkKernel::default()).with_topology(topology);
+    /// let mut shard = pool.shard(pool.shard_for_cpu(0)).unwrap();
+    /// shard.process_io_cycle(&[10, 11, 12]);
+    /// ```
+    pub fn with_topology(mut self, topology: NumaTopology) -> Self {
+        let with_cpus: Vec<usize> = topology
+            .nodes()
+            .iter()
+            .filter(|n| !n.cpus.is_empty())
+            .map(|n| n.id)
+            .collect();
+        if with_cpus.is_empty() {
+            return self;
+        }
+        self.nodes = (0..self.slots.len())
+            .map(|i| with_cpus[i % with_cpus.len()])
+            .collect();
+        self.topology = Some(topology);
+        self
+    }
+
+    /// Node shard `idx` is placed on, with a topology.
+    #[inline]
+    pub fn shard_node(&self, idx: usize) -> Option<usize> {
+        self.nodes.get(idx).copied()
+    }
+
+    /// The shard a thread running on `cpu` should take: one on the CPU's
+    /// own node, spreading that node's CPUs over its shards.  Without a
+    /// topology, or for a CPU it does not list, `cpu % len()`.
+    pub fn shard_for_cpu(&self, cpu: usize) -> usize {
+        let local = self.topology.as_ref().and_then(|t| {
+            let node = t.nodes().iter().find(|n| n.cpus.contains(&cpu))?;
+            let pos = node.cpus.iter().position(|&c| c == cpu)?;
+            let shards: Vec<usize> = (0..self.nodes.len())
+                .filter(|&i| self.nodes[i] == node.id)
+                .collect();
+            (!shards.is_empty()).then(|| shards[pos % shards.len()])
+        });
+        local.unwrap_or(cpu % self.slots.len())
+    }
+
+    /// The topology set with [`with_topology`](Self::with_topology).
+    #[inline]
+    pub fn topology(&self) -> Option<&NumaTopology> {
+        self.topology.as_ref()
+    }
+
+    /// Handle to shard `idx`, or `None` if `idx` is out of range or the
+    /// shard is already taken.  The kernel starts from the template with
+    /// the current consensus, if any.
+    pub fn shard(&self, idx: usize) -> Option<Shard<'_>> {
+        let slot = self.slots.get(idx)?;
+        if slot.taken.swap(true, Ordering::Acquire) {
+            return None;
+        }
+        let mut kernel = self.template.clone();
+        if let Some((epsilon, phi)) = self.consensus() {
+            kernel.epsilon = epsilon;
+            kernel.phi = phi;
+        }
+        Some(Shard {
+            pool: self,
+            idx,
+            kernel: Placed::new(kernel, self.shard_node(idx)),
+            merged_at: 0,
+            published: (0, 0),
+        })
+    }
+
+    /// Number of shards.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Always `false`: a pool has at least one shard.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// ε and φ last published by shard `idx`.
+    pub fn published(&self, idx: usize) -> Option<(f32, f32)> {
+        let slot = self.slots.get(idx)?;
+        slot.published.load(Ordering::Acquire).then(|| {
+            (
+                f32::from_bits(slot.epsilon.load(Ordering::Relaxed)),
+                f32::from_bits(slot.phi.load(Ordering::Relaxed)),
+            )
+        })
+    }
+
+    /// Consensus `(ε, φ)` of everything published so far, `None` before the
+    /// first publication.
+    pub fn consensus(&self) -> Option<(f32, f32)> {
+        let (mut eps, mut sin, mut cos, mut n) = (0.0, 0.0, 0.0, 0);
+        for (epsilon, phi) in (0..self.slots.len()).filter_map(|i| self.published(i)) {
+            eps += epsilon;
+            sin += libm::sinf(phi);
+            cos += libm::cosf(phi);
+            n += 1;
+        }
+        (n > 0).then(|| (eps / n as f32, circular_mean(sin, cos)))
+    }
+
+    /// Counters summed over all shards.
+    pub fn stats(&self) -> ShardStats {
+        ShardStats {
+            cycles: self
+                .slots
+                .iter()
+                .map(|s| s.cycles.load(Ordering::Relaxed))
+                .sum(),
+            prefetches: self
+                .slots
+                .iter()
+                .map(|s| s.prefetches.load(Ordering::Relaxed))
+                .sum(),
+            merges: *self.merges.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
+
+/// Mean direction of angles given their summed sines and cosines, in
+/// `[0, 2π)`.  Undefined directions (opposite angles) come out as 0.
+fn circular_mean(sin: f32, cos: f32) -> f32 {
+    let mean = libm::atan2f(sin, cos).rem_euclid(2.0 * PI);
+    // rem_euclid can round up to exactly 2π.
+    if mean >= 2.0 * PI {
+        0.0
+    } else {
+        mean
+    }
+}
+
+/// A value on its own pages, optionally bound to a NUMA node.
+struct Placed<T> {
+    ptr: NonNull<T>,
+}
+
+// SAFETY: `Placed` owns its `T` exclusively, like `Box`.
+unsafe impl<T: Send> Send for Placed<T> {}
+// SAFETY: shared access only hands out `&T`.
+unsafe impl<T: Sync> Sync for Placed<T> {}
+
+impl<T> Placed<T> {
+    const PAGE: usize = 4096;
+
+    fn layout() -> Layout {
+        let size = core::mem::size_of::<T>().max(1);
+        let size = (size + Self::PAGE - 1) / Self::PAGE * Self::PAGE;
+        Layout::from_size_align(size, Self::PAGE.max(core::mem::align_of::<T>()))
+            .expect("page-sized layout")
+    }
+
+    /// Move `value` into fresh pages, bound to `node` before they are
+    /// written if placement is available.
+    fn new(value: T, node: Option<usize>) -> Self {
+        let layout = Self::layout();
+        // SAFETY: the layout has a non-zero size.
+        let raw = unsafe { alloc::alloc(layout) };
+        let Some(ptr) = NonNull::new(raw.cast::<T>()) else {
+            alloc::handle_alloc_error(layout);
+        };
+        if let Some(node) = node {
+            numa::bind_to_node(raw, layout.size(), node);
+        }
+        // SAFETY: freshly allocated, suitably aligned and sized for `T`.
+        unsafe { ptr.as_ptr().write(value) };
+        Self { ptr }
+    }
+}
+
+impl<T> Deref for Placed<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        // SAFETY: initialised in `new`, alive until `drop`.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for Placed<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as for `deref`, and `&mut self` makes the access unique.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for Placed<T> {
+    fn drop(&mut self) {
+        // SAFETY: initialised in `new` with this layout and dropped once.
+        unsafe {
+            core::ptr::drop_in_place(self.ptr.as_ptr());
+            alloc::dealloc(self.ptr.as_ptr().cast(), Self::layout());
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Placed<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Exclusive handle to one shard of a [`ShardedKernelPool`].  Dereferences
+/// to the shard's kernel; decide through [`push_lba`](Self::push_lba) or
+/// [`process_io_cycle`](Self::process_io_cycle) so merges happen on
+/// schedule.
+#[derive(Debug)]
+pub struct Shard<'a> {
+    pool: &'a ShardedKernelPool,
+    idx: usize,
+    kernel: Placed<AetherLinkKernel>,
+    /// Kernel cycle count at the last merge.
+    merged_at: u64,
+    /// Cycles and prefetches already added to the slot.
+    published: (u64, u64),
+}
+
+impl Shard<'_> {
+    /// Index of this shard in the pool.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+
+    /// See [`AetherLinkKernel::push_lba`].
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<crate::PrefetchDecision> {
+        let decision = self.kernel.push_lba(lba);
+        self.maybe_merge();
+        decision
+    }
+
+    /// See [`AetherLinkKernel::process_io_cycle`].
+    #[inline]
+    pub fn process_io_cycle(&mut self, lba_stream: &[u64]) -> bool {
+        let fetch = self.kernel.process_io_cycle(lba_stream);
+        self.maybe_merge();
+        fetch
+    }
+
+    /// Mutable access to the kernel.  Cycles run through it count towards
+    /// the next merge once a decision goes through the handle again.
+    #[inline]
+    pub fn kernel_mut(&mut self) -> &mut AetherLinkKernel {
+        &mut self.kernel
+    }
+
+    #[inline(always)]
+    fn maybe_merge(&mut self) {
+        let every = self.pool.merge_every;
+        if every != 0 && self.kernel.cycles - self.merged_at >= every {
+            self.merge();
+        }
+    }
+
+    /// Publish this shard's state, fold every shard's latest state into a
+    /// consensus and adopt it.  Returns the consensus `(ε, φ)`.
+    pub fn merge(&mut self) -> (f32, f32) {
+        self.publish();
+        let mut merges = self.pool.merges.lock().unwrap_or_else(|e| e.into_inner());
+        let (epsilon, phi) = self
+            .pool
+            .consensus()
+            .unwrap_or((self.kernel.epsilon, self.kernel.phi));
+        *merges += 1;
+        drop(merges);
+        self.kernel.epsilon = epsilon;
+        self.kernel.phi = phi;
+        self.merged_at = self.kernel.cycles;
+        (epsilon, phi)
+    }
+
+    fn publish(&mut self) {
+        let slot = &self.pool.slots[self.idx];
+        let k = &self.kernel;
+        slot.epsilon.store(k.epsilon.to_bits(), Ordering::Relaxed);
+        slot.phi.store(k.phi.to_bits(), Ordering::Relaxed);
+        slot.cycles
+            .fetch_add(k.cycles - self.published.0, Ordering::Relaxed);
+        slot.prefetches
+            .fetch_add(k.prefetches - self.published.1, Ordering::Relaxed);
+        self.published = (k.cycles, k.prefetches);
+        slot.published.store(true, Ordering::Release);
+    }
+}
+
+impl Deref for Shard<'_> {
+    type Target = AetherLinkKernel;
+
+    #[inline]
+    fn deref(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+}
+
+impl Drop for Shard<'_> {
+    fn drop(&mut self) {
+        self.publish();
+        self.pool.slots[self.idx]
+            .taken
+            .store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    const N: usize = 20_000;
+
+    fn feed(shard: &mut Shard<'_>, pattern: Pattern, n: usize) {
+        for lba in pattern.workload(shard.index() as u64).take(n) {
+            shard.push_lba(lba);
+        }
+    }
+
+    #[test]
+    fn test_circular_mean_wraps() {
+        let angles = [0.1f32, 2.0 * PI - 0.1];
+        let sin: f32 = angles.iter().map(|&a| libm::sinf(a)).sum();
+        let cos: f32 = angles.iter().map(|&a| libm::cosf(a)).sum();
+        let mean = circular_mean(sin, cos);
+        assert!(!(1e-4..=2.0 * PI - 1e-4).contains(&mean), "{mean}");
+        assert!((circular_mean(libm::sinf(1.0) * 2.0, libm::cosf(1.0) * 2.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_consensus_lands_between_workloads() {
+        // Alone, a sequential scan drives ε to the lower clamp and stride 8
+        // to the upper one.
+        let alone = |pattern: Pattern| {
+            let pool = ShardedKernelPool::new(1, AetherLinkKernel::default()).merge_every(0);
+            let mut shard = pool.shard(0).unwrap();
+            feed(&mut shard, pattern, N);
+            shard.epsilon
+        };
+        let stride = Pattern::Sequential { stride: 8 };
+        let (low, high) = (alone(Pattern::SEQUENTIAL), alone(stride));
+        assert!(low < high);
+
+        let pool = ShardedKernelPool::new(4, AetherLinkKernel::default()).merge_every(500);
+        std::thread::scope(|s| {
+            for idx in 0..4 {
+                let pool = &pool;
+                s.spawn(move || {
+                    let mut shard = pool.shard(idx).unwrap();
+                    let pattern = if idx % 2 == 0 {
+                        Pattern::SEQUENTIAL
+                    } else {
+                        stride
+                    };
+                    feed(&mut shard, pattern, N);
+                });
+            }
+        });
+        let (epsilon, phi) = pool.consensus().unwrap();
+        assert!(
+            low < epsilon && epsilon < high,
+            "{low} < {epsilon} < {high}"
+        );
+        let phis: Vec<f32> = (0..4).map(|i| pool.published(i).unwrap().1).collect();
+        let (lo, hi) = phis
+            .iter()
+            .fold((PI * 2.0, 0.0f32), |(l, h), &p| (l.min(p), h.max(p)));
+        assert!(lo <= phi && phi <= hi, "{phis:?} -> {phi}");
+
+        let stats = pool.stats();
+        assert_eq!(stats.cycles, 4 * (N as u64 - 1));
+        assert!(stats.merges >= 4 * (N as u64 / 500 - 1), "{stats:?}");
+        assert!(stats.prefetches > 0);
+    }
+
+    #[test]
+    fn test_shards_are_exclusive() {
+        let pool = ShardedKernelPool::new(2, AetherLinkKernel::default());
+        let first = pool.shard(0).unwrap();
+        assert!(pool.shard(0).is_none());
+        assert!(pool.shard(2).is_none());
+        assert!(pool.consensus().is_none());
+        drop(first);
+        let mut again = pool.shard(0).unwrap();
+        again.kernel_mut().epsilon = 0.3;
+        let (epsilon, _) = again.merge();
+        assert_eq!(epsilon, 0.3);
+        drop(again);
+        // A new handle starts from the consensus.
+        assert_eq!(pool.shard(1).unwrap().epsilon, 0.3);
+        assert_eq!(pool.stats().merges, 1);
+    }
+
+    #[test]
+    fn test_shard_for_cpu_prefers_local_nodes() {
+        let node = |id, cpus: &[usize]| numa::NumaNode {
+            id,
+            cpus: cpus.to_vec(),
+        };
+        let topology = NumaTopology::new(vec![
+            node(1, &[4, 5, 6, 7]),
+            node(0, &[0, 1, 2, 3]),
+            node(2, &[]),
+        ])
+        .unwrap();
+        let pool = ShardedKernelPool::new(4, AetherLinkKernel::default()).with_topology(topology);
+        assert_eq!(
+            (0..4)
+                .map(|i| pool.shard_node(i).unwrap())
+                .collect::<Vec<_>>(),
+            [0, 1, 0, 1]
+        );
+        for cpu in 0..8 {
+            let node = pool.topology().unwrap().node_of_cpu(cpu).unwrap();
+            assert_eq!(
+                pool.shard_node(pool.shard_for_cpu(cpu)),
+                Some(node),
+                "cpu {cpu}"
+            );
+        }
+        // Both local shards are used.
+        assert_eq!(pool.shard_for_cpu(0), 0);
+        assert_eq!(pool.shard_for_cpu(1), 2);
+        assert_eq!(pool.shard_for_cpu(99), 99 % 4);
+
+        let plain = ShardedKernelPool::new(3, AetherLinkKernel::default());
+        assert_eq!(plain.shard_for_cpu(7), 1);
+        assert_eq!(plain.shard_node(0), None);
+        let mut shard = plain.shard(0).unwrap();
+        shard.process_io_cycle(&[1, 2, 3]);
+        assert_eq!(shard.cycles, 1);
+    }
+}
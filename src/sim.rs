@@ -0,0 +1,418 @@
+//! Block cache simulation for measuring prefetch effectiveness.
+//!
+//! Trigger counts only say how often the kernel fired; a cache model says
+//! whether firing helped.  [`CacheSim`] is a block-granular LRU (or FIFO)
+//! cache that distinguishes demand accesses from prefetch insertions and
+//! tracks whether each prefetched block was used before it was evicted.
+
+use std::collections::HashMap;
+
+/// Sentinel index for "no slab entry".
+const NIL: u32 = u32::MAX;
+
+/// Outcome of a demand access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Block was resident.
+    Hit,
+    /// Block had to be fetched on demand.
+    Miss,
+}
+
+/// Replacement policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Policy {
+    /// Evict the least recently used block.
+    #[default]
+    Lru,
+    /// Evict the oldest inserted block; hits do not refresh it.
+    Fifo,
+}
+
+/// Number of [`Timeliness`] buckets.
+pub const TIMELINESS_BUCKETS: usize = 8;
+
+/// Histogram of how many demand accesses passed between a block being
+/// prefetched and its first use (the using access included, so the
+/// minimum is 1).
+///
+/// Bucket 0 counts distance 1, bucket `i` counts `2^i..2^(i+1)`, and the
+/// last bucket is open-ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timeliness {
+    /// Counts per bucket.
+    pub buckets: [u64; TIMELINESS_BUCKETS],
+}
+
+impl Timeliness {
+    /// Count one prefetched block first used `distance` accesses later.
+    #[inline]
+    pub fn record(&mut self, distance: u64) {
+        let i = (63 - distance.max(1).leading_zeros()) as usize;
+        self.buckets[i.min(TIMELINESS_BUCKETS - 1)] += 1;
+    }
+
+    /// Per-bucket difference from an `earlier` snapshot of the same
+    /// histogram.
+    pub fn since(&self, earlier: &Timeliness) -> Timeliness {
+        let mut out = *self;
+        for (b, e) in out.buckets.iter_mut().zip(earlier.buckets) {
+            *b = b.saturating_sub(e);
+        }
+        out
+    }
+
+    /// Total number of recorded uses.
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Human-readable range of bucket `i`: `"1"`, `"2-3"`, …, `"128+"`.
+    pub fn label(i: usize) -> String {
+        let lo = 1u64 << i;
+        if i == 0 {
+            "1".to_owned()
+        } else if i + 1 >= TIMELINESS_BUCKETS {
+            format!("{lo}+")
+        } else {
+            format!("{lo}-{}", 2 * lo - 1)
+        }
+    }
+}
+
+/// Cumulative cache counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheStats {
+    /// Demand accesses that found the block resident.
+    pub hits: u64,
+    /// Demand accesses that missed.
+    pub misses: u64,
+    /// Blocks inserted by prefetch (excluding ones already resident).
+    pub prefetched: u64,
+    /// Demand hits on a prefetched block that had not been used yet; each
+    /// prefetched block counts at most once.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub prefetch_hits: u64,
+    /// Prefetched blocks evicted without ever being used.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pollution: u64,
+    /// When prefetched blocks were first used.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timeliness: Timeliness,
+}
+
+impl CacheStats {
+    /// Counters accumulated after the `earlier` snapshot of the same cache.
+    pub fn since(&self, earlier: &CacheStats) -> CacheStats {
+        CacheStats {
+            hits: self.hits.saturating_sub(earlier.hits),
+            misses: self.misses.saturating_sub(earlier.misses),
+            prefetched: self.prefetched.saturating_sub(earlier.prefetched),
+            prefetch_hits: self.prefetch_hits.saturating_sub(earlier.prefetch_hits),
+            pollution: self.pollution.saturating_sub(earlier.pollution),
+            timeliness: self.timeliness.since(&earlier.timeliness),
+        }
+    }
+
+    /// Demand hit rate in [0, 1]; zero before any access.
+    #[inline]
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    lba: u64,
+    prev: u32,
+    next: u32,
+    /// Inserted by a prefetch and not yet demanded.
+    unused_prefetch: bool,
+    /// Access clock when the line was inserted.
+    inserted_at: u64,
+}
+
+/// Block-granularity cache simulator.
+///
+/// Lines live in a slab indexed by `u32` and are threaded onto an intrusive
+/// list (recency order for LRU, insertion order for FIFO), so both `access`
+/// and `prefetch` are O(1) and the simulator never allocates once it has
+/// reached capacity.
+#[derive(Debug, Clone)]
+pub struct CacheSim {
+    capacity: usize,
+    policy: Policy,
+    map: HashMap<u64, u32>,
+    slab: Vec<Line>,
+    /// Most recently used.
+    head: u32,
+    /// Least recently used (next eviction victim).
+    tail: u32,
+    stats: CacheStats,
+    /// Demand accesses so far.
+    clock: u64,
+}
+
+impl CacheSim {
+    /// Create an LRU cache holding `capacity` blocks (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, Policy::Lru)
+    }
+
+    /// Create a cache holding `capacity` blocks (minimum 1) with the given
+    /// replacement policy.
+    pub fn with_policy(capacity: usize, policy: Policy) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            policy,
+            map: HashMap::with_capacity(capacity),
+            slab: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+            stats: CacheStats::default(),
+            clock: 0,
+        }
+    }
+
+    /// Capacity in blocks.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Replacement policy.
+    #[inline]
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// Number of resident blocks.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// `true` if no block is resident.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// `true` if `lba` is resident (does not touch recency).
+    #[inline]
+    pub fn contains(&self, lba: u64) -> bool {
+        self.map.contains_key(&lba)
+    }
+
+    /// Counters accumulated so far.
+    #[inline]
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Demand access to `lba`: counts a hit or miss and, under LRU, makes
+    /// it MRU.
+    pub fn access(&mut self, lba: u64) -> Access {
+        self.clock += 1;
+        if let Some(&idx) = self.map.get(&lba) {
+            self.stats.hits += 1;
+            let line = &mut self.slab[idx as usize];
+            if line.unused_prefetch {
+                line.unused_prefetch = false;
+                self.stats.prefetch_hits += 1;
+                self.stats.timeliness.record(self.clock - line.inserted_at);
+            }
+            if self.policy == Policy::Lru {
+                self.touch(idx);
+            }
+            Access::Hit
+        } else {
+            self.stats.misses += 1;
+            self.insert(lba, false);
+            Access::Miss
+        }
+    }
+
+    /// Insert `lba` on behalf of a prefetch.  Not counted as a demand access;
+    /// a block that is already resident is left where it is.
+    pub fn prefetch(&mut self, lba: u64) {
+        if self.map.contains_key(&lba) {
+            return;
+        }
+        self.stats.prefetched += 1;
+        self.insert(lba, true);
+    }
+
+    /// Drop all resident blocks and zero the counters.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.slab.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.stats = CacheStats::default();
+        self.clock = 0;
+    }
+
+    fn insert(&mut self, lba: u64, prefetched: bool) {
+        let line = Line {
+            lba,
+            prev: NIL,
+            next: NIL,
+            unused_prefetch: prefetched,
+            inserted_at: self.clock,
+        };
+        let idx = if self.slab.len() < self.capacity {
+            self.slab.push(line);
+            (self.slab.len() - 1) as u32
+        } else {
+            // Recycle the tail line in place.
+            let victim = self.tail;
+            self.unlink(victim);
+            let old = self.slab[victim as usize];
+            if old.unused_prefetch {
+                self.stats.pollution += 1;
+            }
+            self.map.remove(&old.lba);
+            self.slab[victim as usize] = line;
+            victim
+        };
+        self.map.insert(lba, idx);
+        self.push_front(idx);
+    }
+
+    fn touch(&mut self, idx: u32) {
+        if self.head != idx {
+            self.unlink(idx);
+            self.push_front(idx);
+        }
+    }
+
+    fn unlink(&mut self, idx: u32) {
+        let Line { prev, next, .. } = self.slab[idx as usize];
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.slab[prev as usize].next = next;
+        }
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.slab[next as usize].prev = prev;
+        }
+    }
+
+    fn push_front(&mut self, idx: u32) {
+        let old_head = self.head;
+        {
+            let line = &mut self.slab[idx as usize];
+            line.prev = NIL;
+            line.next = old_head;
+        }
+        if old_head != NIL {
+            self.slab[old_head as usize].prev = idx;
+        } else {
+            self.tail = idx;
+        }
+        self.head = idx;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_hit_and_miss() {
+        let mut cache = CacheSim::new(2);
+        assert_eq!(cache.access(1), Access::Miss);
+        assert_eq!(cache.access(1), Access::Hit);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn test_prefetch_serves_demand() {
+        let mut cache = CacheSim::new(4);
+        cache.prefetch(7);
+        assert_eq!(cache.access(7), Access::Hit);
+        assert_eq!(cache.stats().prefetched, 1);
+        assert!((cache.stats().hit_rate() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_lru_eviction_order() {
+        let mut cache = CacheSim::new(3);
+        for lba in [1, 2, 3, 1, 4] {
+            cache.access(lba);
+        }
+        // 2 was least recently used when 4 arrived.
+        assert!(!cache.contains(2));
+        assert!([1, 3, 4].iter().all(|&l| cache.contains(l)));
+        cache.access(5);
+        assert!(!cache.contains(3));
+    }
+
+    #[test]
+    fn test_fifo_ignores_hits() {
+        let mut cache = CacheSim::with_policy(3, Policy::Fifo);
+        for lba in [1, 2, 3, 1, 4] {
+            cache.access(lba);
+        }
+        assert!(!cache.contains(1));
+        assert!([2, 3, 4].iter().all(|&l| cache.contains(l)));
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    #[test]
+    fn test_prefetch_hit_and_pollution_accounting() {
+        let mut cache = CacheSim::new(3);
+        cache.prefetch(10);
+        cache.prefetch(11);
+        cache.prefetch(10); // already resident: not counted again
+        assert_eq!(cache.access(10), Access::Hit);
+        assert_eq!(cache.access(10), Access::Hit); // second use is a plain hit
+
+        // 11 is now LRU; two demand misses push it out unused.
+        cache.access(20);
+        cache.access(21);
+        assert!(!cache.contains(11));
+        let s = cache.stats();
+        assert_eq!((s.prefetched, s.prefetch_hits, s.pollution), (2, 1, 1));
+        assert_eq!((s.hits, s.misses), (2, 2));
+        // Evicting a used prefetch is not pollution.
+        cache.access(22);
+        cache.access(23);
+        assert!(!cache.contains(10));
+        assert_eq!(cache.stats().pollution, 1);
+    }
+
+    #[test]
+    fn test_timeliness_buckets() {
+        let mut t = Timeliness::default();
+        for d in [0, 1, 2, 3, 4, 127, 128, 1 << 40] {
+            t.record(d);
+        }
+        assert_eq!(t.buckets, [2, 2, 1, 0, 0, 0, 1, 2]);
+        assert_eq!(t.total(), 8);
+        assert_eq!(Timeliness::label(0), "1");
+        assert_eq!(Timeliness::label(2), "4-7");
+        assert_eq!(Timeliness::label(TIMELINESS_BUCKETS - 1), "128+");
+
+        let mut cache = CacheSim::new(16);
+        cache.prefetch(5);
+        cache.access(1);
+        cache.access(2);
+        cache.access(5);
+        assert_eq!(cache.stats().timeliness.buckets[1], 1);
+    }
+}
@@ -0,0 +1,101 @@
+//! Point-in-time copies of a kernel's learned state.
+//!
+//! A [`KernelSnapshot`] holds the learned operating point (ε, φ) together
+//! with the fixed parameters and the counters, as plain values.  Unlike a
+//! [`KernelConfig`] it records how far the kernel has run, so it can serve as
+//! a checkpoint; like a config it leaves the telemetry DSP out, which
+//! rebuilds itself within a few cycles.
+
+use crate::config::KernelConfig;
+use crate::AetherLinkKernel;
+
+/// Learned parameters and counters of an [`AetherLinkKernel`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelSnapshot {
+    /// Adaptive threshold ε.
+    pub epsilon: f32,
+    /// POVM basis angle φ (radians).
+    pub phi: f32,
+    /// Adaptation coefficients [λ₁, λ₂, λ₃].
+    pub lambda: [f32; 3],
+    /// Sigmoid bias.
+    pub bias: f32,
+    /// Decision cycles executed.
+    pub cycles: u64,
+    /// Prefetches triggered.
+    pub prefetches: u64,
+}
+
+impl KernelSnapshot {
+    /// The parameter part of the snapshot.
+    #[inline]
+    pub fn config(&self) -> KernelConfig {
+        KernelConfig {
+            epsilon: self.epsilon,
+            phi: self.phi,
+            lambda: self.lambda,
+            bias: self.bias,
+        }
+    }
+}
+
+impl From<KernelConfig> for KernelSnapshot {
+    /// A snapshot of a kernel that has not run yet.
+    fn from(config: KernelConfig) -> Self {
+        Self {
+            epsilon: config.epsilon,
+            phi: config.phi,
+            lambda: config.lambda,
+            bias: config.bias,
+            cycles: 0,
+            prefetches: 0,
+        }
+    }
+}
+
+impl AetherLinkKernel {
+    /// Copy out the learned state and counters.
+    #[inline]
+    pub fn snapshot(&self) -> KernelSnapshot {
+        KernelSnapshot {
+            epsilon: self.epsilon,
+            phi: self.phi,
+            lambda: self.lambda,
+            bias: self.bias,
+            cycles: self.cycles,
+            prefetches: self.prefetches,
+        }
+    }
+
+    /// Rebuild a kernel from a snapshot, with fresh telemetry.
+    pub fn from_snapshot(snapshot: &KernelSnapshot) -> Self {
+        let mut kernel = Self::new(
+            snapshot.epsilon,
+            snapshot.phi,
+            snapshot.lambda,
+            snapshot.bias,
+        );
+        kernel.cycles = snapshot.cycles;
+        kernel.prefetches = snapshot.prefetches;
+        kernel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        for i in 0..100u64 {
+            kernel.process_io_cycle(&[i * 3, i * 3 + 1, i * 7, i]);
+        }
+        let snap = kernel.snapshot();
+        assert_eq!(snap.cycles, 100);
+        assert_eq!(AetherLinkKernel::from_snapshot(&snap).snapshot(), snap);
+        assert_eq!(snap.config(), kernel.config());
+        assert_eq!(KernelSnapshot::from(snap.config()).cycles, 0);
+    }
+}
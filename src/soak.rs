@@ -0,0 +1,562 @@
+//! Long runs with the kernel's invariants checked along the way.
+//!
+//! Unit tests run thousands of cycles; a deployment runs billions.  A
+//! [`Soak`] drives one kernel through a seeded phased workload (sequential,
+//! random, stride-8 and bursty phases, blended, repeated with fresh seeds
+//! and moving offsets) for [`SoakOptions::cycles`] decision cycles and,
+//! every [`check_every`](SoakOptions::check_every) cycles, checks:
+//!
+//! - ε, φ and the rolling trigger ratio are finite, ε inside its clamp, φ
+//!   in `[0, 2π]` and the ratio in `[0, 1]`;
+//! - the kernel counted exactly the cycles the soak ran, never more
+//!   prefetches than cycles, no counter went backwards and no cycle had a
+//!   numeric fault;
+//! - ε has not sat on a clamp bound, without leaving it once, for
+//!   [`pinned_checks`](SoakOptions::pinned_checks) check intervals in a
+//!   row (the open-loop rule touches its bounds all the time; it is
+//!   staying there that is a fault);
+//! - the history holds no more than [`MAX_WINDOW`] LBAs.  The kernel's
+//!   other storage is sized at construction, and the soak keeps at most
+//!   [`samples`](SoakOptions::samples) state samples, so nothing else can
+//!   grow.
+//!
+//! The first violation ends the run with a [`Violation`] carrying the
+//! cycle and a snapshot of the kernel.  A clean run returns a
+//! [`SoakSummary`] with state samples spread evenly over the run, thinned
+//! as it goes so there are never more than `samples` of them.
+//!
+//! With a [`time_budget`](SoakOptions::time_budget) the run stops at the
+//! first check past the budget.  Its [`SoakCheckpoint`] — kernel snapshot
+//! and workload position — lets [`Soak::resume`] carry on later, so an
+//! overnight run can be split.  The resumed kernel starts with fresh
+//! telemetry and an empty history, as
+//! [`from_snapshot`](AetherLinkKernel::from_snapshot) kernels do.
+//!
+//! `aether-sim soak` runs one from the command line; `cargo test --release
+//! -- --ignored soak_smoke` runs a scaled-down one.
+
+use crate::snapshot::KernelSnapshot;
+use crate::stream::MAX_WINDOW;
+use crate::threshold::EPSILON_CLAMP;
+use crate::workload::{Pattern, Phased, PhasedWorkload};
+use crate::AetherLinkKernel;
+use core::fmt;
+use std::time::{Duration, Instant};
+
+/// Accesses in each phase of a round of the workload.
+const PHASE: u64 = 1 << 18;
+/// Accesses in one round of the workload.
+const ROUND: u64 = 4 * PHASE;
+
+/// Length, cadence and limits of a soak run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoakOptions {
+    /// Decision cycles to run in total, counting those before a resume.
+    pub cycles: u64,
+    /// Cycles between invariant checks (at least 1).
+    pub check_every: u64,
+    /// Most state samples kept in the summary.
+    pub samples: usize,
+    /// Consecutive check intervals with ε held on one clamp bound that
+    /// count as pinned (0 never does).
+    pub pinned_checks: u32,
+    /// Wall-clock time after which the run stops at the next check.
+    pub time_budget: Option<Duration>,
+    /// Workload seed; use the same one to resume.
+    pub seed: u64,
+}
+
+impl Default for SoakOptions {
+    fn default() -> Self {
+        Self {
+            cycles: 1_000_000_000,
+            check_every: 1 << 20,
+            samples: 64,
+            pinned_checks: 64,
+            time_budget: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Where a soak run stopped: enough to resume it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoakCheckpoint {
+    /// The kernel's learned state and counters.
+    pub snapshot: KernelSnapshot,
+    /// Workload accesses consumed.
+    pub accesses: u64,
+    /// Decision cycles run.
+    pub cycles: u64,
+}
+
+/// Kernel state at one check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    /// Decision cycles run when it was taken.
+    pub cycle: u64,
+    /// Learned state and counters.
+    pub snapshot: KernelSnapshot,
+    /// Trigger ratio over about the last 64 cycles.
+    pub rolling_ratio: f32,
+}
+
+/// An invariant a soak run found broken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Invariant {
+    /// ε, φ or the rolling ratio is NaN or infinite.
+    NonFinite,
+    /// ε is outside its clamp.
+    EpsilonOutOfRange,
+    /// φ is outside `[0, 2π]`.
+    PhiOutOfRange,
+    /// The kernel's cycle count differs from the cycles run.
+    CycleCount {
+        /// What the kernel should have counted.
+        expected: u64,
+    },
+    /// More prefetches than cycles.
+    PrefetchesExceedCycles,
+    /// The prefetch count fell since the last check.
+    CounterWentBack,
+    /// The rolling trigger ratio is outside `[0, 1]`.
+    RollingRatio,
+    /// Cycles whose fetch probability was not finite.
+    NumericFaults(u64),
+    /// ε sat on a clamp bound, never leaving it, for this many check
+    /// intervals in a row.
+    EpsilonPinned {
+        /// Consecutive checks.
+        checks: u32,
+    },
+    /// The history holds more than [`MAX_WINDOW`] LBAs.
+    HistoryOverflow(usize),
+}
+
+impl fmt::Display for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonFinite => f.write_str("non-finite state"),
+            Self::EpsilonOutOfRange => f.write_str("ε outside its clamp"),
+            Self::PhiOutOfRange => f.write_str("φ outside [0, 2π]"),
+            Self::CycleCount { expected } => write!(f, "cycle count off, expected {expected}"),
+            Self::PrefetchesExceedCycles => f.write_str("more prefetches than cycles"),
+            Self::CounterWentBack => f.write_str("prefetch counter went backwards"),
+            Self::RollingRatio => f.write_str("rolling ratio outside [0, 1]"),
+            Self::NumericFaults(n) => write!(f, "{n} numeric faults"),
+            Self::EpsilonPinned { checks } => {
+                write!(f, "ε pinned on a clamp bound for {checks} checks")
+            }
+            Self::HistoryOverflow(len) => write!(f, "history grew to {len} LBAs"),
+        }
+    }
+}
+
+/// The first broken invariant of a soak run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Violation {
+    /// Decision cycles run when it was found.
+    pub cycle: u64,
+    /// What was broken.
+    pub invariant: Invariant,
+    /// The kernel at that point.
+    pub snapshot: KernelSnapshot,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = &self.snapshot;
+        write!(
+            f,
+            "cycle {}: {} (ε {}, φ {}, {} cycles, {} prefetches)",
+            self.cycle, self.invariant, s.epsilon, s.phi, s.cycles, s.prefetches
+        )
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// Outcome of a soak run that broke no invariant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakSummary {
+    /// Decision cycles run in total, counting those before a resume.
+    pub cycles: u64,
+    /// Of those, cycles run by this call.
+    pub run: u64,
+    /// Invariant checks passed.
+    pub checks: u64,
+    /// Wall-clock time of this call.
+    pub elapsed: Duration,
+    /// `false` if the time budget ran out first.
+    pub complete: bool,
+    /// Lowest and highest ε seen at a check.
+    pub epsilon_range: (f32, f32),
+    /// Most consecutive check intervals ε spent held on a clamp bound.
+    pub longest_pinned: u32,
+    /// Evenly spaced state samples, oldest first.
+    pub samples: Vec<Sample>,
+    /// Where to resume.
+    pub checkpoint: SoakCheckpoint,
+}
+
+impl fmt::Display for SoakSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "{} cycles ({} this run, {:.1} M/s), {} checks passed{}",
+            self.cycles,
+            self.run,
+            self.run as f64 / secs.max(1e-9) / 1e6,
+            self.checks,
+            if self.complete {
+                ""
+            } else {
+                ", time budget hit"
+            }
+        )?;
+        writeln!(
+            f,
+            "ε in [{:.4}, {:.4}], longest on a bound {} checks",
+            self.epsilon_range.0, self.epsilon_range.1, self.longest_pinned
+        )?;
+        for s in &self.samples {
+            writeln!(
+                f,
+                "  cycle {:>13}: ε {:.4}  φ {:.4}  prefetches {:>13}  ratio {:.3}",
+                s.cycle, s.snapshot.epsilon, s.snapshot.phi, s.snapshot.prefetches, s.rolling_ratio
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A kernel under soak.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::soak::{Soak, SoakOptions};
+/// use aether_link::AetherLinkKernel;
+///
+/// let opts = SoakOptions {
+///     cycles: 100_000,
+///     check_every: 10_000,
+///     ..SoakOptions::default()
+/// };
+/// let summary = Soak::new(AetherLinkKernel::new_hft(), opts).run().unwrap();
+/// assert_eq!((summary.cycles, summary.checks), (100_000, 10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Soak {
+    opts: SoakOptions,
+    kernel: AetherLinkKernel,
+    /// Kernel cycle count before the soak's first cycle.
+    base: u64,
+    workload: PhasedWorkload,
+    accesses: u64,
+    cycles: u64,
+    /// Prefetch count at the last check.
+    prefetches: u64,
+    /// ε at the last check, and whether it has changed since.
+    epsilon: f32,
+    moved: bool,
+    /// Consecutive checks with ε held on a bound since the one before.
+    pinned: u32,
+}
+
+impl Soak {
+    /// Soak `kernel` from the start of the workload.
+    pub fn new(kernel: AetherLinkKernel, opts: SoakOptions) -> Self {
+        Self::at(kernel, opts, 0, 0)
+    }
+
+    /// Carry on a run from where `checkpoint` left it.
+    pub fn resume(checkpoint: &SoakCheckpoint, opts: SoakOptions) -> Self {
+        let kernel = AetherLinkKernel::from_snapshot(&checkpoint.snapshot);
+        Self::at(kernel, opts, checkpoint.accesses, checkpoint.cycles)
+    }
+
+    fn at(kernel: AetherLinkKernel, opts: SoakOptions, accesses: u64, cycles: u64) -> Self {
+        let opts = SoakOptions {
+            check_every: opts.check_every.max(1),
+            ..opts
+        };
+        let mut workload = round(opts.seed, accesses / ROUND);
+        if accesses % ROUND > 0 {
+            workload.nth((accesses % ROUND - 1) as usize);
+        }
+        Self {
+            opts,
+            base: kernel.cycles.wrapping_sub(cycles),
+            prefetches: kernel.prefetches,
+            epsilon: kernel.epsilon,
+            moved: false,
+            kernel,
+            workload,
+            accesses,
+            cycles,
+            pinned: 0,
+        }
+    }
+
+    /// Run until [`SoakOptions::cycles`] cycles, the time budget or the
+    /// first violation, whichever comes first.
+    pub fn run(&mut self) -> Result<SoakSummary, Violation> {
+        let started = Instant::now();
+        let first = self.cycles;
+        let every = self.opts.check_every;
+        let mut stride = every;
+        let mut samples = Vec::with_capacity(self.opts.samples + 1);
+        let mut checks = 0;
+        let mut epsilon_range = (self.kernel.epsilon, self.kernel.epsilon);
+        let mut longest_pinned = 0;
+        while self.cycles < self.opts.cycles {
+            let lba = self.next_lba();
+            if self.kernel.push_lba(lba).is_none() {
+                continue;
+            }
+            self.cycles += 1;
+            self.moved |= self.kernel.epsilon.to_bits() != self.epsilon.to_bits();
+            if self.cycles % every != 0 && self.cycles != self.opts.cycles {
+                continue;
+            }
+            self.check()?;
+            checks += 1;
+            let epsilon = self.kernel.epsilon;
+            epsilon_range = (epsilon_range.0.min(epsilon), epsilon_range.1.max(epsilon));
+            longest_pinned = longest_pinned.max(self.pinned);
+            if self.opts.samples > 0 && self.cycles % stride == 0 {
+                samples.push(Sample {
+                    cycle: self.cycles,
+                    snapshot: self.kernel.snapshot(),
+                    rolling_ratio: self.kernel.stats().rolling_ratio,
+                });
+                if samples.len() > self.opts.samples {
+                    stride = stride.saturating_mul(2);
+                    samples.retain(|s| s.cycle % stride == 0);
+                }
+            }
+            if self
+                .opts
+                .time_budget
+                .is_some_and(|budget| started.elapsed() >= budget)
+            {
+                break;
+            }
+        }
+        Ok(SoakSummary {
+            cycles: self.cycles,
+            run: self.cycles - first,
+            checks,
+            elapsed: started.elapsed(),
+            complete: self.cycles >= self.opts.cycles,
+            epsilon_range,
+            longest_pinned,
+            samples,
+            checkpoint: self.checkpoint(),
+        })
+    }
+
+    /// Where the run is now.
+    pub fn checkpoint(&self) -> SoakCheckpoint {
+        SoakCheckpoint {
+            snapshot: self.kernel.snapshot(),
+            accesses: self.accesses,
+            cycles: self.cycles,
+        }
+    }
+
+    /// The kernel under soak.
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+
+    fn next_lba(&mut self) -> u64 {
+        loop {
+            if let Some(lba) = self.workload.next() {
+                self.accesses += 1;
+                return lba;
+            }
+            self.workload = round(self.opts.seed, self.accesses / ROUND);
+        }
+    }
+
+    /// Check every invariant, failing on the first broken one.
+    fn check(&mut self) -> Result<(), Violation> {
+        const TAU: f32 = 2.0 * core::f32::consts::PI;
+        let kernel = &self.kernel;
+        let stats = kernel.stats();
+        let (epsilon, phi) = (kernel.epsilon, kernel.phi);
+        let expected = self.base.wrapping_add(self.cycles);
+        let on_bound = epsilon == EPSILON_CLAMP.0 || epsilon == EPSILON_CLAMP.1;
+        self.pinned = if on_bound && !self.moved {
+            self.pinned + 1
+        } else {
+            0
+        };
+        (self.epsilon, self.moved) = (epsilon, false);
+
+        let broken = if !(epsilon.is_finite() && phi.is_finite() && stats.rolling_ratio.is_finite())
+        {
+            Some(Invariant::NonFinite)
+        } else if !(EPSILON_CLAMP.0..=EPSILON_CLAMP.1).contains(&epsilon) {
+            Some(Invariant::EpsilonOutOfRange)
+        } else if !(0.0..=TAU).contains(&phi) {
+            Some(Invariant::PhiOutOfRange)
+        } else if kernel.cycles != expected {
+            Some(Invariant::CycleCount { expected })
+        } else if stats.prefetches > stats.cycles {
+            Some(Invariant::PrefetchesExceedCycles)
+        } else if stats.prefetches < self.prefetches {
+            Some(Invariant::CounterWentBack)
+        } else if !(0.0..=1.0).contains(&stats.rolling_ratio) {
+            Some(Invariant::RollingRatio)
+        } else if stats.numeric_faults > 0 {
+            Some(Invariant::NumericFaults(stats.numeric_faults))
+        } else if self.opts.pinned_checks > 0 && self.pinned >= self.opts.pinned_checks {
+            Some(Invariant::EpsilonPinned {
+                checks: self.pinned,
+            })
+        } else if kernel.history.len() > MAX_WINDOW {
+            Some(Invariant::HistoryOverflow(kernel.history.len()))
+        } else {
+            None
+        };
+        self.prefetches = stats.prefetches;
+        match broken {
+            Some(invariant) => Err(Violation {
+                cycle: self.cycles,
+                invariant,
+                snapshot: kernel.snapshot(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Round `n` of the workload: every phase once, seeded and placed by `n`.
+fn round(seed: u64, n: u64) -> PhasedWorkload {
+    let base = n.wrapping_mul(1 << 36);
+    Phased::new(seed ^ n.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .then_at(Pattern::SEQUENTIAL, PHASE, base)
+        .then_at(Pattern::RANDOM, PHASE, base)
+        .then_at(Pattern::Sequential { stride: 8 }, PHASE, base)
+        .then_at(Pattern::BURSTY, PHASE, base)
+        .with_ramp(1_024)
+        .iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preset;
+
+    fn opts(cycles: u64) -> SoakOptions {
+        SoakOptions {
+            cycles,
+            check_every: 10_000,
+            samples: 4,
+            ..SoakOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_split_run_resumes_where_it_stopped() {
+        // A zero budget stops at the first check.
+        let stopped = Soak::new(
+            AetherLinkKernel::new_gaming(),
+            SoakOptions {
+                time_budget: Some(Duration::ZERO),
+                ..opts(1_500_000)
+            },
+        )
+        .run()
+        .unwrap();
+        assert!(!stopped.complete);
+        assert_eq!((stopped.cycles, stopped.checks), (10_000, 1));
+
+        let half = Soak::new(AetherLinkKernel::new_gaming(), opts(1_200_000))
+            .run()
+            .unwrap();
+        assert!(half.complete);
+        assert_eq!((half.cycles, half.checks), (1_200_000, 120));
+        assert!(half.samples.len() <= 4 && half.samples.len() >= 2);
+        assert!(half.samples.windows(2).all(|w| w[0].cycle < w[1].cycle));
+        assert!(half.epsilon_range.0 <= half.epsilon_range.1);
+        // Past the end of the first round of the workload.
+        assert!(half.checkpoint.accesses > ROUND);
+
+        let mut rest = Soak::resume(&half.checkpoint, opts(1_700_000));
+        let done = rest.run().unwrap();
+        assert_eq!((done.cycles, done.run), (1_700_000, 500_000));
+        assert_eq!(done.checkpoint.snapshot.cycles, 1_700_000);
+        assert!(done.checkpoint.accesses > half.checkpoint.accesses + 500_000);
+        assert!(done.to_string().contains("1700000 cycles (500000 this run"));
+
+        // Resuming mid-round picks up the same accesses.
+        let mut straight = round(0, 0);
+        let mut resumed = Soak::resume(
+            &SoakCheckpoint {
+                accesses: 1_234,
+                ..half.checkpoint
+            },
+            opts(0),
+        );
+        assert_eq!(straight.nth(1_234), Some(resumed.next_lba()));
+    }
+
+    #[test]
+    fn test_first_violation_stops_the_run() {
+        // A NaN angle fails the first check.
+        let kernel = AetherLinkKernel::new(0.5, f32::NAN, [0.1, 0.1, 0.1], 0.0);
+        let err = Soak::new(kernel, opts(100_000)).run().unwrap_err();
+        assert_eq!((err.cycle, err.invariant), (10_000, Invariant::NonFinite));
+        assert!(err.snapshot.phi.is_nan());
+        assert!(err.to_string().starts_with("cycle 10000: non-finite state"));
+
+        // ε that never moves off its upper bound is pinned.
+        let kernel = AetherLinkKernel::new(0.9, 0.5, [0.0, 0.1, 0.1], 0.0);
+        let err = Soak::new(
+            kernel,
+            SoakOptions {
+                pinned_checks: 5,
+                ..opts(1_000_000)
+            },
+        )
+        .run()
+        .unwrap_err();
+        assert_eq!(err.cycle, 50_000);
+        assert_eq!(err.invariant, Invariant::EpsilonPinned { checks: 5 });
+
+        // A kernel counted elsewhere too is caught.
+        let mut kernel = AetherLinkKernel::new_hft();
+        kernel.cycles = 7;
+        let mut soak = Soak::new(kernel, opts(100_000));
+        soak.kernel.cycles = 0;
+        let err = soak.run().unwrap_err();
+        assert_eq!(err.invariant, Invariant::CycleCount { expected: 10_007 });
+    }
+
+    /// `cargo test --release -- --ignored soak_smoke`
+    #[test]
+    #[ignore = "long; run with --release"]
+    fn soak_smoke() {
+        for (i, preset) in Preset::ALL.into_iter().enumerate() {
+            let opts = SoakOptions {
+                cycles: 20_000_000,
+                check_every: 1 << 16,
+                time_budget: Some(Duration::from_secs(6)),
+                seed: i as u64,
+                ..SoakOptions::default()
+            };
+            match Soak::new(preset.kernel(), opts).run() {
+                Ok(summary) => println!("{preset}: {summary}"),
+                Err(violation) => panic!("{preset}: {violation}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Kernel state laid out for shared memory.
+//!
+//! A monitoring agent that maps the same segment as the process running
+//! the kernel should not have to deserialize anything to read ε.
+//! [`AetherKernelStateV1`] is a `#[repr(C)]` mirror of the learned state
+//! and the main counters with no padding and no pointers, so its bytes can
+//! be copied into a segment as they are and read back in place:
+//!
+//! - the writer calls [`AetherLinkKernel::write_state`] with the segment
+//!   (or copies [`AetherLinkKernel::state_v1`] there any other way);
+//! - the reader calls [`KernelStateView::from_bytes`] on the mapped bytes,
+//!   which checks alignment, length, magic and version and returns a
+//!   reference into them, read through accessor methods.
+//!
+//! Fields are in native byte order, so writer and reader must share an
+//! architecture.  The view is of whatever bytes are there when an accessor
+//! runs: a writer updating the segment concurrently needs its own
+//! protocol (a sequence counter beside the state, say) for the reader to
+//! get one consistent copy.
+//!
+//! Needs the `state-view` feature.
+
+use crate::AetherLinkKernel;
+use core::fmt;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// First four bytes of every state.
+pub const STATE_MAGIC: [u8; 4] = *b"AETS";
+/// Layout version of [`AetherKernelStateV1`].
+pub const STATE_VERSION: u32 = 1;
+/// Size of [`AetherKernelStateV1`], the shortest buffer a view accepts.
+pub const STATE_LEN: usize = core::mem::size_of::<AetherKernelStateV1>();
+/// Alignment the start of a view must have.
+pub const STATE_ALIGN: usize = core::mem::align_of::<AetherKernelStateV1>();
+
+/// Learned state and counters of a kernel, version 1 layout.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::state_view::KernelStateView;
+/// use aether_link::AetherLinkKernel;
+///
+/// let mut kernel = AetherLinkKernel::new_hft();
+/// for i in 0..100u64 {
+///     kernel.push_lba(i * 8);
+/// }
+/// let state = kernel.state_v1();
+/// let view = KernelStateView::from_bytes(state.as_bytes()).unwrap();
+/// assert_eq!(view.cycles(), 99);
+/// assert_eq!(view.epsilon(), kernel.epsilon);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, FromBytes, IntoBytes, Immutable, KnownLayout)]
+#[repr(C)]
+pub struct AetherKernelStateV1 {
+    magic: [u8; 4],
+    version: u32,
+    epsilon: f32,
+    phi: f32,
+    lambda: [f32; 3],
+    bias: f32,
+    cycles: u64,
+    prefetches: u64,
+    suppressed: u64,
+    degraded: u64,
+    numeric_faults: u64,
+    hits: u64,
+    wasted: u64,
+    missed: u64,
+    rolling_ratio: f32,
+    reserved: u32,
+}
+
+const _: () = assert!(STATE_LEN == 104 && STATE_ALIGN == 8);
+
+/// The name readers use for a borrowed [`AetherKernelStateV1`].
+pub type KernelStateView = AetherKernelStateV1;
+
+/// Why bytes are not a state view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The buffer does not start on a [`STATE_ALIGN`] boundary.
+    Misaligned,
+    /// The buffer is shorter than [`STATE_LEN`].
+    TooShort {
+        /// Its length.
+        len: usize,
+    },
+    /// The buffer does not start with [`STATE_MAGIC`].
+    BadMagic,
+    /// The state was written with another layout version.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misaligned => write!(f, "state not aligned to {STATE_ALIGN} bytes"),
+            Self::TooShort { len } => write!(f, "state needs {STATE_LEN} bytes, got {len}"),
+            Self::BadMagic => f.write_str("not a kernel state"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported kernel state version {v}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateError {}
+
+impl AetherKernelStateV1 {
+    /// View the state at the start of `bytes` without copying it.  Bytes
+    /// past [`STATE_LEN`] are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<&Self, StateError> {
+        let (state, _) = Self::ref_from_prefix(bytes).map_err(|e| match e {
+            zerocopy::ConvertError::Alignment(_) => StateError::Misaligned,
+            _ => StateError::TooShort { len: bytes.len() },
+        })?;
+        if state.magic != STATE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        if state.version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(state.version));
+        }
+        Ok(state)
+    }
+
+    /// The state's bytes, as [`from_bytes`](Self::from_bytes) reads them.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        IntoBytes::as_bytes(self)
+    }
+
+    /// Layout version.
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Adaptive threshold ε.
+    #[inline]
+    pub fn epsilon(&self) -> f32 {
+        self.epsilon
+    }
+
+    /// POVM basis angle φ (radians).
+    #[inline]
+    pub fn phi(&self) -> f32 {
+        self.phi
+    }
+
+    /// Adaptation coefficients [λ₁, λ₂, λ₃].
+    #[inline]
+    pub fn lambda(&self) -> [f32; 3] {
+        self.lambda
+    }
+
+    /// Sigmoid bias.
+    #[inline]
+    pub fn bias(&self) -> f32 {
+        self.bias
+    }
+
+    /// Decision cycles executed.
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Prefetches triggered.
+    #[inline]
+    pub fn prefetches(&self) -> u64 {
+        self.prefetches
+    }
+
+    /// See [`KernelStats::suppressed`](crate::KernelStats::suppressed).
+    #[inline]
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// See [`KernelStats::degraded`](crate::KernelStats::degraded).
+    #[inline]
+    pub fn degraded(&self) -> u64 {
+        self.degraded
+    }
+
+    /// See [`KernelStats::numeric_faults`](crate::KernelStats::numeric_faults).
+    #[inline]
+    pub fn numeric_faults(&self) -> u64 {
+        self.numeric_faults
+    }
+
+    /// Prefetches reported useful.
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Prefetches reported evicted unused.
+    #[inline]
+    pub fn wasted(&self) -> u64 {
+        self.wasted
+    }
+
+    /// Demand misses reported on declined prefetches.
+    #[inline]
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// Trigger ratio over about the last 64 cycles.
+    #[inline]
+    pub fn rolling_ratio(&self) -> f32 {
+        self.rolling_ratio
+    }
+}
+
+impl AetherLinkKernel {
+    /// The learned state and counters in the shared-memory layout.
+    pub fn state_v1(&self) -> AetherKernelStateV1 {
+        let stats = self.stats();
+        AetherKernelStateV1 {
+            magic: STATE_MAGIC,
+            version: STATE_VERSION,
+            epsilon: self.epsilon,
+            phi: self.phi,
+            lambda: self.lambda,
+            bias: self.bias,
+            cycles: stats.cycles,
+            prefetches: stats.prefetches,
+            suppressed: stats.suppressed,
+            degraded: stats.degraded,
+            numeric_faults: stats.numeric_faults,
+            hits: stats.hits,
+            wasted: stats.wasted,
+            missed: stats.missed,
+            rolling_ratio: stats.rolling_ratio,
+            reserved: 0,
+        }
+    }
+
+    /// Copy [`state_v1`](Self::state_v1) to the start of `segment`, which
+    /// needs no particular alignment.
+    pub fn write_state(&self, segment: &mut [u8]) -> Result<(), StateError> {
+        let len = segment.len();
+        self.state_v1()
+            .write_to_prefix(segment)
+            .map_err(|_| StateError::TooShort { len })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zeroed segment of `len` bytes and the offset of its first
+    /// [`STATE_ALIGN`]-aligned byte.
+    fn segment(len: usize) -> (Vec<u8>, usize) {
+        let buf = vec![0u8; len + STATE_ALIGN];
+        let offset = buf.as_ptr().align_offset(STATE_ALIGN);
+        (buf, offset)
+    }
+
+    #[test]
+    fn test_round_trip_through_a_shared_segment() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        let (mut shm, at) = segment(4096);
+        for i in 0..500u64 {
+            if let Some(decision) = kernel.push_lba(i * 3) {
+                if decision.fire {
+                    kernel.record_prefetch_outcome(i % 4 != 0);
+                }
+            }
+            if i % 100 == 0 {
+                kernel.write_state(&mut shm[at..]).unwrap();
+                let view = KernelStateView::from_bytes(&shm[at..]).unwrap();
+                assert_eq!(view.cycles(), kernel.cycles);
+            }
+        }
+        kernel.write_state(&mut shm[at..]).unwrap();
+        let view = KernelStateView::from_bytes(&shm[at..]).unwrap();
+        assert_eq!(*view, kernel.state_v1());
+        assert_eq!(view.as_bytes().as_ptr(), shm[at..].as_ptr(), "no copy");
+        let stats = kernel.stats();
+        assert_eq!(view.version(), STATE_VERSION);
+        assert_eq!((view.epsilon(), view.phi()), (kernel.epsilon, kernel.phi));
+        assert_eq!((view.lambda(), view.bias()), (kernel.lambda, kernel.bias));
+        assert_eq!(
+            (view.prefetches(), view.hits()),
+            (stats.prefetches, stats.hits)
+        );
+        assert_eq!((view.wasted(), view.missed()), (stats.wasted, stats.missed));
+        assert_eq!(view.rolling_ratio(), stats.rolling_ratio);
+    }
+
+    #[test]
+    fn test_rejects_misaligned_short_and_foreign_bytes() {
+        let kernel = AetherLinkKernel::default();
+        let (mut shm, at) = segment(2 * STATE_LEN);
+        kernel.write_state(&mut shm[at + 1..]).unwrap();
+        assert_eq!(
+            KernelStateView::from_bytes(&shm[at + 1..]).unwrap_err(),
+            StateError::Misaligned
+        );
+
+        kernel.write_state(&mut shm[at..]).unwrap();
+        let short = &shm[at..at + STATE_LEN - 1];
+        assert_eq!(
+            KernelStateView::from_bytes(short).unwrap_err(),
+            StateError::TooShort { len: STATE_LEN - 1 }
+        );
+        assert_eq!(
+            kernel.write_state(&mut [0; 8]),
+            Err(StateError::TooShort { len: 8 })
+        );
+
+        shm[at + 4..at + 8].copy_from_slice(&2u32.to_ne_bytes());
+        let err = KernelStateView::from_bytes(&shm[at..]).unwrap_err();
+        assert_eq!(err, StateError::UnsupportedVersion(2));
+        assert_eq!(err.to_string(), "unsupported kernel state version 2");
+        shm[at] = b'X';
+        assert_eq!(
+            KernelStateView::from_bytes(&shm[at..]).unwrap_err(),
+            StateError::BadMagic
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Everything the kernel counts, as one plain value.
+//!
+//! [`AetherLinkKernel::stats`] returns a [`KernelStats`] that can be kept
+//! between scrapes and subtracted with [`KernelStats::delta`].  Counters of
+//! features that are off stay 0, so a dashboard reads the same fields from
+//! every kernel.
+
+use crate::AetherLinkKernel;
+
+/// Weight of the newest cycle in the rolling trigger ratio (about the last
+/// 64 cycles).
+const ROLLING_ALPHA: f32 = 1.0 / 64.0;
+
+/// Counters kept only for [`KernelStats`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Counters {
+    suppressed: u64,
+    numeric_faults: u64,
+    short_windows: u64,
+    rereads: u64,
+    out_of_range: u64,
+    rolling_ratio: f32,
+}
+
+impl Counters {
+    /// Account for a decided cycle: whether it `fired`, whether it would
+    /// have fired but was `suppressed`, and its fetch probability.
+    #[inline(always)]
+    pub(crate) fn decided(&mut self, fired: bool, suppressed: bool, p_fetch: f32) {
+        self.suppressed += suppressed as u64;
+        self.numeric_faults += !p_fetch.is_finite() as u64;
+        self.rolling_ratio += ROLLING_ALPHA * (fired as u32 as f32 - self.rolling_ratio);
+    }
+
+    /// Trigger ratio over about the last 64 cycles.
+    #[inline(always)]
+    pub(crate) fn rolling_ratio(&self) -> f32 {
+        self.rolling_ratio
+    }
+
+    /// Account for a plan dropped outside the address range.
+    #[inline(always)]
+    pub(crate) fn out_of_range(&mut self, dropped: bool) {
+        self.out_of_range += dropped as u64;
+    }
+
+    /// Account for a cycle whose window ended where it began.
+    #[inline(always)]
+    pub(crate) fn reread(&mut self, rereading: bool) {
+        self.rereads += rereading as u64;
+    }
+
+    /// Account for a call that saw fewer than two LBAs.
+    #[inline(always)]
+    pub(crate) fn short_window(&mut self) {
+        self.short_windows += 1;
+    }
+}
+
+/// Snapshot of the kernel's counters since construction or the last
+/// [`reset_stats`](AetherLinkKernel::reset_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelStats {
+    /// Decision cycles.
+    pub cycles: u64,
+    /// Cycles that fired a prefetch.
+    pub prefetches: u64,
+    /// Cycles whose fetch probability cleared the threshold but that an
+    /// [anomaly alarm](crate::anomaly::AnomalyOptions::suppress_prefetch)
+    /// or an active [horizon](crate::horizon) plan kept from firing.
+    pub suppressed: u64,
+    /// Cycles the [latency budget](AetherLinkKernel::set_latency_budget)
+    /// limited to the degraded telemetry path.
+    pub degraded: u64,
+    /// Cycles whose fetch probability was not finite (a NaN or infinite
+    /// parameter); they never fire.
+    pub numeric_faults: u64,
+    /// Calls that saw fewer than two LBAs and returned without a cycle.
+    pub short_windows: u64,
+    /// Cycles whose window ended on the block it started at, such as a
+    /// block read over and over; they never fire (see
+    /// [`degenerate`](crate::degenerate)).
+    pub rereads: u64,
+    /// Plans dropped for lying entirely outside the
+    /// [address range](AetherLinkKernel::set_address_range).
+    pub out_of_range: u64,
+    /// Prefetches reported useful.
+    pub hits: u64,
+    /// Prefetches reported evicted unused.
+    pub wasted: u64,
+    /// Demand misses reported on declined prefetches.
+    pub missed: u64,
+    /// Prefetches reported cancelled before they were issued.
+    pub cancelled: u64,
+    /// Of those, cancellations because the demand read arrived first.
+    pub late_hits: u64,
+    /// Trigger ratio over about the last 64 cycles (exponentially
+    /// weighted).
+    pub rolling_ratio: f32,
+}
+
+impl KernelStats {
+    /// Counts accumulated since `earlier`.  Counters that went backwards
+    /// (the stats were reset in between) give 0; the rolling ratio is a
+    /// gauge and keeps this snapshot's value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// let before = kernel.stats();
+    /// for i in 0..100u64 {
+    ///     kernel.push_lba(i);
+    /// }
+    /// let interval = kernel.stats().delta(&before);
+    /// assert_eq!(interval.cycles, 99);
+    /// assert_eq!(interval.short_windows, 1);
+    /// ```
+    pub fn delta(&self, earlier: &KernelStats) -> KernelStats {
+        KernelStats {
+            cycles: self.cycles.saturating_sub(earlier.cycles),
+            prefetches: self.prefetches.saturating_sub(earlier.prefetches),
+            suppressed: self.suppressed.saturating_sub(earlier.suppressed),
+            degraded: self.degraded.saturating_sub(earlier.degraded),
+            numeric_faults: self.numeric_faults.saturating_sub(earlier.numeric_faults),
+            short_windows: self.short_windows.saturating_sub(earlier.short_windows),
+            rereads: self.rereads.saturating_sub(earlier.rereads),
+            out_of_range: self.out_of_range.saturating_sub(earlier.out_of_range),
+            hits: self.hits.saturating_sub(earlier.hits),
+            wasted: self.wasted.saturating_sub(earlier.wasted),
+            missed: self.missed.saturating_sub(earlier.missed),
+            cancelled: self.cancelled.saturating_sub(earlier.cancelled),
+            late_hits: self.late_hits.saturating_sub(earlier.late_hits),
+            rolling_ratio: self.rolling_ratio,
+        }
+    }
+}
+
+/// Counters of several kernels added up; the rolling trigger ratio is the
+/// mean of theirs weighted by their cycles.
+pub(crate) fn combined<'a>(kernels: impl IntoIterator<Item = &'a AetherLinkKernel>) -> KernelStats {
+    let mut total = KernelStats::default();
+    let mut weighted = 0.0;
+    for s in kernels.into_iter().map(AetherLinkKernel::stats) {
+        total.cycles += s.cycles;
+        total.prefetches += s.prefetches;
+        total.suppressed += s.suppressed;
+        total.degraded += s.degraded;
+        total.numeric_faults += s.numeric_faults;
+        total.short_windows += s.short_windows;
+        total.rereads += s.rereads;
+        total.out_of_range += s.out_of_range;
+        total.hits += s.hits;
+        total.wasted += s.wasted;
+        total.missed += s.missed;
+        total.cancelled += s.cancelled;
+        total.late_hits += s.late_hits;
+        weighted += s.rolling_ratio as f64 * s.cycles as f64;
+    }
+    if total.cycles > 0 {
+        total.rolling_ratio = (weighted / total.cycles as f64) as f32;
+    }
+    total
+}
+
+impl AetherLinkKernel {
+    /// Every counter the kernel keeps.
+    pub fn stats(&self) -> KernelStats {
+        let (hits, wasted, missed) = self.outcome_counts();
+        let (cancelled, late_hits) = self.cancel_counts();
+        KernelStats {
+            cycles: self.cycles,
+            prefetches: self.prefetches,
+            suppressed: self.counters.suppressed,
+            degraded: self.degraded_cycles(),
+            numeric_faults: self.counters.numeric_faults,
+            short_windows: self.counters.short_windows,
+            rereads: self.counters.rereads,
+            out_of_range: self.counters.out_of_range,
+            hits,
+            wasted,
+            missed,
+            cancelled,
+            late_hits,
+            rolling_ratio: self.counters.rolling_ratio,
+        }
+    }
+
+    /// Zero the [`KernelStats`]-only counters.
+    pub(crate) fn reset_counters(&mut self) {
+        self.counters = Counters::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::AnomalyOptions;
+
+    #[test]
+    fn test_delta_subtracts_counters_and_keeps_gauge() {
+        let earlier = KernelStats {
+            cycles: 10,
+            prefetches: 4,
+            suppressed: 1,
+            degraded: 2,
+            numeric_faults: 0,
+            short_windows: 3,
+            rereads: 0,
+            out_of_range: 0,
+            hits: 2,
+            wasted: 1,
+            missed: 5,
+            cancelled: 3,
+            late_hits: 1,
+            rolling_ratio: 0.4,
+        };
+        let later = KernelStats {
+            cycles: 25,
+            prefetches: 9,
+            suppressed: 4,
+            degraded: 2,
+            numeric_faults: 1,
+            short_windows: 3,
+            rereads: 6,
+            out_of_range: 2,
+            hits: 6,
+            wasted: 2,
+            missed: 7,
+            cancelled: 5,
+            late_hits: 4,
+            rolling_ratio: 0.3,
+        };
+        let d = later.delta(&earlier);
+        assert_eq!(
+            d,
+            KernelStats {
+                cycles: 15,
+                prefetches: 5,
+                suppressed: 3,
+                degraded: 0,
+                numeric_faults: 1,
+                short_windows: 0,
+                rereads: 6,
+                out_of_range: 2,
+                hits: 4,
+                wasted: 1,
+                missed: 2,
+                cancelled: 2,
+                late_hits: 3,
+                rolling_ratio: 0.3,
+            }
+        );
+        assert_eq!(earlier.delta(&later).cycles, 0, "reset in between");
+        assert_eq!(later.delta(&KernelStats::default()), later);
+    }
+
+    #[test]
+    fn test_every_counter_is_wired() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        assert_eq!(kernel.stats(), KernelStats::default());
+
+        // Short windows: neither a one-LBA slice nor the first push runs a
+        // cycle.
+        kernel.process_io_cycle(&[7]);
+        kernel.push_lba(0);
+        assert_eq!(kernel.process_micro_batch(&[]).outcome, None);
+        let s = kernel.stats();
+        assert_eq!((s.cycles, s.short_windows), (0, 3));
+
+        // Re-reads: a window that ends where it began.
+        kernel.process_io_cycle(&[7, 9, 7]);
+        assert_eq!((kernel.stats().rereads, kernel.stats().prefetches), (1, 0));
+
+        // Prefetches and the rolling ratio.
+        for lba in 1..200 {
+            kernel.push_lba(lba);
+        }
+        let s = kernel.stats();
+        assert!(s.prefetches > 150, "{s:?}");
+        assert!(s.rolling_ratio > 0.8, "{s:?}");
+
+        // Suppression: a horizon plan withholds triggers it covers.
+        kernel.set_horizon(16);
+        for lba in 200..400 {
+            kernel.push_lba(lba);
+        }
+        let s = kernel.stats().delta(&s);
+        assert!(s.suppressed > 100, "{s:?}");
+        kernel.clear_horizon();
+
+        // Anomaly suppression counts too.
+        let mut alarmed = AetherLinkKernel::new_gaming();
+        alarmed.enable_anomaly_detection(AnomalyOptions {
+            suppress_prefetch: true,
+            ..AnomalyOptions::default()
+        });
+        for i in 0..2_000u64 {
+            alarmed.process_io_cycle(&[i, i + 1]);
+        }
+        let before = alarmed.stats();
+        for i in 0..200u64 {
+            alarmed.process_io_cycle(&[i << 40, (i << 40) + (i << 30)]);
+        }
+        assert!(alarmed.stats().delta(&before).suppressed > 0);
+
+        // Degraded cycles.
+        kernel.set_latency_budget(0);
+        kernel.push_lba(400);
+        assert_eq!(kernel.stats().degraded, 1);
+
+        // Numeric faults never fire.
+        let s = kernel.stats();
+        let bias = kernel.bias;
+        kernel.bias = f32::NAN;
+        for lba in 401..411 {
+            kernel.push_lba(lba);
+        }
+        kernel.bias = bias;
+        let d = kernel.stats().delta(&s);
+        assert_eq!((d.numeric_faults, d.prefetches), (10, 0));
+
+        // Plans past the address range.
+        kernel.set_address_range(0, 420);
+        kernel.bias = -5.0;
+        kernel.push_lba(420);
+        kernel.bias = bias;
+        assert_eq!(kernel.stats().out_of_range, 1);
+
+        // Outcome reports.
+        kernel.record_prefetch_outcome(true);
+        kernel.record_prefetch_outcome(true);
+        kernel.record_prefetch_outcome(false);
+        kernel.record_missed_opportunity();
+        let s = kernel.stats();
+        assert_eq!((s.hits, s.wasted, s.missed), (2, 1, 1));
+
+        kernel.reset_stats();
+        assert_eq!(kernel.stats(), KernelStats::default());
+    }
+}
@@ -0,0 +1,337 @@
+//! Global counters over per-thread kernels.
+//!
+//! Per-thread kernels ([`ShardedKernelPool`](crate::ShardedKernelPool),
+//! [`KernelPool`](crate::KernelPool) per worker) each count their own
+//! cycles, and summing them means reaching into every thread.  A
+//! [`StatsHub`] hands every kernel a [`StatsHandle`] with its own set of
+//! counters, each on its own cache line; the owning thread bumps them with
+//! `Relaxed` adds that no other thread writes, and
+//! [`StatsHub::aggregate`] sums every set on demand without pausing
+//! anyone.  Only registration and aggregation take the hub's lock.
+//!
+//! Counters of a dropped handle stay in the hub, so totals never go back.
+//! An aggregate taken while threads are running is a consistent lower
+//! bound of each counter, not a snapshot of one instant.
+
+use crate::metrics::{MetricsSink, PrometheusSink};
+use core::fmt;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+
+/// `T` alone on a 64-byte cache line, so writes to neighbouring values do
+/// not invalidate it.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+pub struct CachePadded<T>(pub T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Counters owned by one kernel.
+#[derive(Debug, Default)]
+struct CounterSet {
+    cycles: CachePadded<AtomicU64>,
+    prefetches: CachePadded<AtomicU64>,
+    hits: CachePadded<AtomicU64>,
+    misses: CachePadded<AtomicU64>,
+}
+
+impl CounterSet {
+    fn load(&self) -> HubStats {
+        HubStats {
+            kernels: 1,
+            cycles: self.cycles.load(Relaxed),
+            prefetches: self.prefetches.load(Relaxed),
+            hits: self.hits.load(Relaxed),
+            misses: self.misses.load(Relaxed),
+        }
+    }
+}
+
+/// Registry of per-kernel counter sets.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::stats_hub::StatsHub;
+/// use aether_link::AetherLinkKernel;
+///
+/// let hub = StatsHub::new();
+/// std::thread::scope(|s| {
+///     for t in 0..4u64 {
+///         let stats = hub.register();
+///         s.spawn(move || {
+///             let mut kernel = AetherLinkKernel::default();
+///             for i in 0..1_000 {
+///                 if let Some(d) = kernel.push_lba(t << 32 | i) {
+///                     stats.record_cycle(d.fire);
+///                 }
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(hub.aggregate().cycles, 4 * 999);
+/// ```
+#[derive(Debug, Default)]
+pub struct StatsHub {
+    sets: Mutex<Vec<Arc<CounterSet>>>,
+}
+
+impl StatsHub {
+    /// Empty hub.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// New counter set for one kernel.  Move the handle to the thread that
+    /// runs the kernel.
+    pub fn register(&self) -> StatsHandle {
+        let set = Arc::new(CounterSet::default());
+        self.lock().push(Arc::clone(&set));
+        StatsHandle { set }
+    }
+
+    /// Sum of every counter set registered so far.
+    pub fn aggregate(&self) -> HubStats {
+        self.lock()
+            .iter()
+            .map(|s| s.load())
+            .fold(HubStats::default(), |a, b| a + b)
+    }
+
+    /// Per-kernel counters, in registration order.
+    pub fn per_kernel(&self) -> Vec<HubStats> {
+        self.lock().iter().map(|s| s.load()).collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Arc<CounterSet>>> {
+        self.sets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// One kernel's counters.  Meant to be written by one thread; any thread
+/// may, at the cost of contention.
+#[derive(Debug, Clone)]
+pub struct StatsHandle {
+    set: Arc<CounterSet>,
+}
+
+impl StatsHandle {
+    /// Count one decision cycle, and a prefetch if it fired.
+    #[inline]
+    pub fn record_cycle(&self, fetched: bool) {
+        self.set.cycles.fetch_add(1, Relaxed);
+        if fetched {
+            self.set.prefetches.fetch_add(1, Relaxed);
+        }
+    }
+
+    /// Count one cache access.
+    #[inline]
+    pub fn record_access(&self, hit: bool) {
+        if hit {
+            self.set.hits.fetch_add(1, Relaxed);
+        } else {
+            self.set.misses.fetch_add(1, Relaxed);
+        }
+    }
+
+    /// Add counts accumulated elsewhere, e.g. a batch of cycles.
+    pub fn add(&self, stats: &HubStats) {
+        self.set.cycles.fetch_add(stats.cycles, Relaxed);
+        self.set.prefetches.fetch_add(stats.prefetches, Relaxed);
+        self.set.hits.fetch_add(stats.hits, Relaxed);
+        self.set.misses.fetch_add(stats.misses, Relaxed);
+    }
+
+    /// This handle's counters.
+    pub fn stats(&self) -> HubStats {
+        self.set.load()
+    }
+}
+
+/// Counters of one kernel or, from [`StatsHub::aggregate`], of all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HubStats {
+    /// Counter sets summed.
+    pub kernels: u64,
+    /// Decision cycles.
+    pub cycles: u64,
+    /// Cycles that fired a prefetch.
+    pub prefetches: u64,
+    /// Cache hits.
+    pub hits: u64,
+    /// Cache misses.
+    pub misses: u64,
+}
+
+impl HubStats {
+    /// Fraction of cycles that fired.
+    #[inline]
+    pub fn trigger_ratio(&self) -> f64 {
+        if self.cycles == 0 {
+            0.0
+        } else {
+            self.prefetches as f64 / self.cycles as f64
+        }
+    }
+
+    /// Fraction of accesses that hit.
+    #[inline]
+    pub fn hit_rate(&self) -> f64 {
+        let accesses = self.hits + self.misses;
+        if accesses == 0 {
+            0.0
+        } else {
+            self.hits as f64 / accesses as f64
+        }
+    }
+
+    /// Write the counters to `sink`, every metric name starting with
+    /// `prefix` (e.g. `"aether"`).  Counters go as their
+    /// [totals](MetricsSink::counter_total).
+    pub fn flush(&self, prefix: &str, sink: &mut dyn MetricsSink) {
+        let counters = [
+            ("cycles_total", "Decision cycles.", self.cycles),
+            ("prefetches_total", "Prefetches triggered.", self.prefetches),
+            ("cache_hits_total", "Cache hits.", self.hits),
+            ("cache_misses_total", "Cache misses.", self.misses),
+        ];
+        let name = format!("{prefix}_kernels");
+        sink.describe(&name, "Kernels reporting.");
+        sink.gauge(&name, self.kernels as f64);
+        for (name, help, value) in counters {
+            let name = format!("{prefix}_{name}");
+            sink.describe(&name, help);
+            sink.counter_total(&name, value);
+        }
+        let name = format!("{prefix}_hit_rate");
+        sink.describe(&name, "Cache hit rate.");
+        sink.gauge(&name, self.hit_rate());
+    }
+
+    /// Prometheus text exposition of the counters, every metric name
+    /// starting with `prefix` (e.g. `"aether"`).
+    pub fn to_prometheus(&self, prefix: &str) -> String {
+        let mut sink = PrometheusSink::new();
+        self.flush(prefix, &mut sink);
+        sink.render()
+    }
+}
+
+impl core::ops::Add for HubStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            kernels: self.kernels + rhs.kernels,
+            cycles: self.cycles + rhs.cycles,
+            prefetches: self.prefetches + rhs.prefetches,
+            hits: self.hits + rhs.hits,
+            misses: self.misses + rhs.misses,
+        }
+    }
+}
+
+impl fmt::Display for HubStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} kernels: {} cycles, {} prefetches ({:.1}%), hit rate {:.2}%",
+            self.kernels,
+            self.cycles,
+            self.prefetches,
+            self.trigger_ratio() * 100.0,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use crate::AetherLinkKernel;
+
+    #[test]
+    fn test_aggregate_equals_sum_of_kernels() {
+        const THREADS: u64 = 8;
+        const CYCLES: u64 = 20_000;
+        let hub = StatsHub::new();
+        let kernels: Vec<(u64, u64)> = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let stats = hub.register();
+                    let hub = &hub;
+                    s.spawn(move || {
+                        let mut kernel = AetherLinkKernel::new(0.5, 0.1, [0.0, 0.2, 0.3], 0.05);
+                        let mut rng = SplitMix64::new(t);
+                        for i in 0..CYCLES {
+                            let lba = t << 40 | rng.below(1 << 12);
+                            if let Some(d) = kernel.push_lba(lba) {
+                                stats.record_cycle(d.fire);
+                            }
+                            stats.record_access(lba % 3 == 0);
+                            if i % 1_000 == 0 {
+                                // Aggregating mid-run never blocks the writers.
+                                assert!(hub.aggregate().cycles <= THREADS * CYCLES);
+                            }
+                        }
+                        (kernel.cycles, kernel.prefetches)
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        let total = hub.aggregate();
+        assert_eq!(total.kernels, THREADS);
+        assert_eq!(total.cycles, kernels.iter().map(|k| k.0).sum::<u64>());
+        assert_eq!(total.prefetches, kernels.iter().map(|k| k.1).sum::<u64>());
+        assert_eq!(total.hits + total.misses, THREADS * CYCLES);
+        assert!(total.prefetches > 0 && total.hits > 0);
+        let per_kernel = hub.per_kernel();
+        assert_eq!(per_kernel.len(), THREADS as usize);
+        assert_eq!(
+            per_kernel
+                .iter()
+                .copied()
+                .fold(HubStats::default(), |a, b| a + b),
+            total
+        );
+    }
+
+    #[test]
+    fn test_prometheus_and_report() {
+        let hub = StatsHub::new();
+        let a = hub.register();
+        a.add(&HubStats {
+            cycles: 10,
+            prefetches: 4,
+            hits: 3,
+            misses: 1,
+            ..HubStats::default()
+        });
+        drop(a);
+        hub.register().record_cycle(true);
+        let total = hub.aggregate();
+        assert_eq!((total.kernels, total.cycles, total.prefetches), (2, 11, 5));
+
+        let text = total.to_prometheus("aether");
+        assert!(text.contains("# TYPE aether_cycles_total counter\naether_cycles_total 11\n"));
+        assert!(text.contains("aether_hit_rate 0.75\n"), "{text}");
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 6);
+        assert_eq!(
+            total.to_string(),
+            "2 kernels: 11 cycles, 5 prefetches (45.5%), hit rate 75.00%"
+        );
+    }
+}
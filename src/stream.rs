@@ -0,0 +1,267 @@
+//! Streaming input: one LBA per call.
+//!
+//! [`AetherLinkKernel::push_lba`] keeps the most recent LBAs in a fixed ring
+//! inside the kernel, so per-request hot paths can feed it without
+//! buffering a window themselves and without allocating.
+
+use crate::direction::run_stride;
+use crate::prefetcher::PrefetchDecision;
+use crate::{AetherLinkKernel, CycleOutcome};
+
+/// Largest history window [`AetherLinkKernel::set_window`] accepts.
+pub const MAX_WINDOW: usize = 64;
+/// History window of a freshly built kernel.
+pub const DEFAULT_WINDOW: usize = 16;
+
+/// Fixed-capacity ring of the last `window` LBAs.
+#[derive(Debug, Clone)]
+pub(crate) struct History {
+    buf: [u64; MAX_WINDOW],
+    window: usize,
+    len: usize,
+    /// Slot the next LBA is written to.
+    pos: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            buf: [0; MAX_WINDOW],
+            window: DEFAULT_WINDOW,
+            len: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl History {
+    #[inline(always)]
+    pub(crate) fn push(&mut self, lba: u64) {
+        self.buf[self.pos % MAX_WINDOW] = lba;
+        self.pos = (self.pos + 1) % MAX_WINDOW;
+        self.len = (self.len + 1).min(self.window);
+    }
+
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Oldest and newest LBA in the window.  Only meaningful when
+    /// `len() > 0`.
+    #[inline(always)]
+    pub(crate) fn span(&self) -> (u64, u64) {
+        let first = (self.pos + MAX_WINDOW - self.len) % MAX_WINDOW;
+        let last = (self.pos + MAX_WINDOW - 1) % MAX_WINDOW;
+        (self.buf[first], self.buf[last])
+    }
+
+    /// The LBA pushed before the newest.  Only meaningful when
+    /// `len() > 1`.
+    #[inline(always)]
+    pub(crate) fn previous(&self) -> u64 {
+        self.buf[(self.pos + MAX_WINDOW - 2) % MAX_WINDOW]
+    }
+
+    /// LBAs in the window, oldest first.
+    #[inline]
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = u64> + '_ {
+        let first = self.pos + MAX_WINDOW - self.len;
+        (0..self.len).map(move |i| self.buf[(first + i) % MAX_WINDOW])
+    }
+
+    #[inline(always)]
+    pub(crate) fn window(&self) -> usize {
+        self.window
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_window(&mut self, window: usize) {
+        self.window = window.clamp(2, MAX_WINDOW);
+        self.len = self.len.min(self.window);
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl AetherLinkKernel {
+    /// Feed a single LBA and run one decision cycle over the current
+    /// history window.
+    ///
+    /// Returns `None` until the window holds two LBAs (a span needs two
+    /// ends); from then on every call runs exactly one cycle, equivalent to
+    /// [`process_io_cycle`](Self::process_io_cycle) on the last
+    /// [`window`](Self::window) LBAs.  A firing decision targets the
+    /// [`horizon_plan`](Self::horizon_plan) when a horizon is set, and
+    /// otherwise the same run-sized extent past `lba` as
+    /// [`process_io_cycle_decision`](Self::process_io_cycle_decision)
+    /// gives the window; a learned [footprint](crate::footprint) takes
+    /// precedence over both.  Every firing decision carries a target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::new_hft();
+    /// assert!(kernel.push_lba(100).is_none());
+    /// let decision = kernel.push_lba(101).unwrap();
+    /// assert_eq!(kernel.cycles, 1);
+    /// # let _ = decision.fire;
+    /// ```
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<PrefetchDecision> {
+        self.guarded(|kernel| {
+            let outcome = kernel.stream_cycle(lba, 0.0)?;
+            Some(kernel.streamed_decision(lba, &outcome))
+        })
+    }
+
+    /// Decision of the streamed cycle for `lba` that came out as `outcome`.
+    #[inline]
+    pub(crate) fn streamed_decision(
+        &mut self,
+        lba: u64,
+        outcome: &CycleOutcome,
+    ) -> PrefetchDecision {
+        let decision = self.streamed_target(lba, outcome.fetch);
+        self.heatmap_placed(decision)
+            .with_confidence(outcome.p_fetch)
+    }
+
+    /// [`streamed_decision`](Self::streamed_decision) before the confidence.
+    #[inline]
+    fn streamed_target(&mut self, lba: u64, fetch: bool) -> PrefetchDecision {
+        if let Some(decision) = fetch.then(|| self.footprint_decision()).flatten() {
+            return decision;
+        }
+        match (fetch, self.horizon.is_some()) {
+            (false, _) => PrefetchDecision::SKIP,
+            // A firing cycle with the horizon on has a plan unless it fell
+            // outside the address range.
+            (true, true) => self.horizon_plan().map_or(PrefetchDecision::SKIP, |plan| {
+                PrefetchDecision::trigger(Some(plan))
+            }),
+            (true, false) => {
+                let run = run_stride(self.history.iter().rev());
+                self.run_target(run, lba)
+            }
+        }
+    }
+
+    /// [`push_lba`](Self::push_lba) with a threshold shift, returning the
+    /// full [`CycleOutcome`] as
+    /// [`process_io_cycle_qos`](Self::process_io_cycle_qos) does.
+    #[inline]
+    pub fn push_lba_qos(&mut self, lba: u64, threshold_shift: f32) -> Option<CycleOutcome> {
+        self.guarded(|kernel| kernel.stream_cycle(lba, threshold_shift))
+    }
+
+    /// [`push_lba_qos`](Self::push_lba_qos) without the entry-point guard.
+    #[inline(always)]
+    fn stream_cycle(&mut self, lba: u64, threshold_shift: f32) -> Option<CycleOutcome> {
+        self.history.push(lba);
+        if self.history.len() < 2 {
+            self.counters.short_window();
+            return None;
+        }
+        self.timed(|kernel| {
+            let (first, last) = kernel.history.span();
+            let telemetry = kernel.span_telemetry(first, last);
+            let outcome = kernel.decide(telemetry, threshold_shift);
+            kernel.adapt_window();
+            Some(outcome)
+        })
+    }
+
+    /// Use the last `window` pushed LBAs per cycle (clamped to
+    /// `2..=`[`MAX_WINDOW`]).  Shrinking keeps the newest entries.  With
+    /// the [adaptive window](Self::enable_adaptive_window) on, this only
+    /// sets the window restored when it is turned off.
+    #[inline]
+    pub fn set_window(&mut self, window: usize) {
+        match self.fixed_window() {
+            Some(fixed) => *fixed = window.clamp(2, MAX_WINDOW),
+            None => self.history.set_window(window),
+        }
+    }
+
+    /// History window length set with [`set_window`](Self::set_window);
+    /// see [`effective_window`](Self::effective_window) for the one in use.
+    #[inline]
+    pub fn window(&self) -> usize {
+        self.restored_window().unwrap_or(self.history.window)
+    }
+
+    /// Forget pushed LBAs; learned parameters and DSP state are kept.
+    #[inline]
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::direction::LOOKAHEAD_BLOCKS;
+
+    #[test]
+    fn test_push_matches_slice_cycles() {
+        let lbas: Vec<u64> = (0..200u64).map(|i| i * 3 + (i % 7) * 40).collect();
+        let mut streamed = AetherLinkKernel::new_gaming();
+        let mut sliced = AetherLinkKernel::new_gaming();
+        streamed.set_window(8);
+        for (i, &lba) in lbas.iter().enumerate() {
+            let d = streamed.push_lba(lba);
+            if i == 0 {
+                assert!(d.is_none());
+                continue;
+            }
+            let window = &lbas[(i + 1).saturating_sub(8)..=i];
+            assert_eq!(d.unwrap().fire, sliced.process_io_cycle(window));
+            assert_eq!(streamed.epsilon, sliced.epsilon);
+        }
+        assert_eq!(streamed.cycles, 199);
+    }
+
+    #[test]
+    fn test_sequential_push_targets_the_next_blocks() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.set_window(8);
+        let mut fired = 0;
+        for lba in 1_000..1_200u64 {
+            let Some(decision) = kernel.push_lba(lba) else {
+                continue;
+            };
+            if decision.fire {
+                let target = decision.target.unwrap();
+                assert_eq!(target.start, lba + 1);
+                let steps = lba - 1_000;
+                let stride = if steps < 2 { 0 } else { 1 };
+                assert_eq!(target.len, stride + LOOKAHEAD_BLOCKS);
+                fired += 1;
+            }
+        }
+        assert!(fired > 100);
+        assert!(kernel.push_lba(1_200).unwrap().target.is_some());
+    }
+
+    #[test]
+    fn test_window_bounds_and_shrink() {
+        let mut h = History::default();
+        for lba in 0..100 {
+            h.push(lba);
+        }
+        assert_eq!(h.len(), DEFAULT_WINDOW);
+        assert_eq!(h.span(), (84, 99));
+        h.set_window(4);
+        assert_eq!((h.len(), h.span()), (4, (96, 99)));
+        h.set_window(1000);
+        assert_eq!(h.window, MAX_WINDOW);
+        // Growing refills from new pushes only.
+        h.push(100);
+        assert_eq!((h.len(), h.span()), (5, (96, 100)));
+    }
+}
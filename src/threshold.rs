@@ -0,0 +1,665 @@
+//! How the fetch threshold ε adapts.
+//!
+//! The original rule, [`ThresholdPolicy::OpenLoop`], nudges ε by λ₁ times
+//! the spatial observable every cycle, with no notion of whether the
+//! resulting prefetches helped.  [`ThresholdPolicy::Feedback`] instead
+//! closes the loop on outcomes the caller reports: a used prefetch or a
+//! demand miss the kernel declined to cover lowers ε (fire more), a
+//! prefetch evicted unused raises it (fire less).
+//!
+//! Accuracy alone hides what a useless prefetch costs: the data it pushed
+//! out.  [`AetherLinkKernel::record_pollution`] takes a count of prefetched
+//! blocks evicted unused, and with a non-zero [`Reward::pollution`] every
+//! one of them raises ε on top of the waste step, so a kernel that is
+//! accurate but floods the cache still backs off.  The kernel counts every
+//! report under either policy; see [`prefetch_accuracy`] and
+//! [`pollution_rate`].
+//!
+//! [`prefetch_accuracy`]: AetherLinkKernel::prefetch_accuracy
+//! [`pollution_rate`]: AetherLinkKernel::pollution_rate
+//!
+//! A prefetch withdrawn before it was dispatched is neither used nor
+//! wasted.  [`AetherLinkKernel::cancel_prefetch`] removes it from a
+//! [`PrefetchQueue`] and counts it apart; when the cancellation is because
+//! the demand read arrived first ([`CancelReason::DemandArrived`]) the
+//! prediction was right but late, so it also counts as a *late hit* and,
+//! under [`ThresholdPolicy::Feedback`], lowers ε by the hit step so the
+//! kernel fires sooner.  See [`prefetch_timeliness`].
+//!
+//! [`prefetch_timeliness`]: AetherLinkKernel::prefetch_timeliness
+//!
+//! [`eval::run_with_feedback`](crate::eval::run_with_feedback) wires a
+//! [`CacheSim`](crate::sim::CacheSim) up as the source of those reports;
+//! [`eval::run`](crate::eval::run) forwards pollution alone, through
+//! [`Prefetcher::record_pollution`](crate::prefetcher::Prefetcher::record_pollution).
+//!
+//! How far each report moves ε is its [`FeedbackSteps`] entry scaled by the
+//! kernel's [`Reward`] weight, so the same outcomes can be traded for
+//! latency, coverage or bandwidth.  [`OnlineTuner`](crate::tune::OnlineTuner)
+//! scores its arms with the same weights.
+
+use crate::prefetcher::PrefetchPlan;
+use crate::queue::PrefetchQueue;
+use crate::AetherLinkKernel;
+
+/// Bounds ε is kept in under every policy.
+pub(crate) const EPSILON_CLAMP: (f32, f32) = (0.1, 0.9);
+
+/// Per-signal ε step sizes for [`ThresholdPolicy::Feedback`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeedbackSteps {
+    /// Decrease after a prefetched block was used.
+    pub hit: f32,
+    /// Increase after a prefetched block was evicted unused.
+    pub waste: f32,
+    /// Decrease after a demand miss on a cycle that did not prefetch.
+    pub miss: f32,
+}
+
+impl Default for FeedbackSteps {
+    fn default() -> Self {
+        Self {
+            hit: 0.002,
+            waste: 0.004,
+            miss: 0.01,
+        }
+    }
+}
+
+/// What an outcome is worth.
+///
+/// Weights are relative: doubling all of them doubles every feedback step.
+/// The default (all ones, free bandwidth) leaves [`FeedbackSteps`] as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reward {
+    /// Value of a prefetched block that was used.
+    pub hit: f32,
+    /// Cost of a prefetched block evicted unused.
+    pub waste: f32,
+    /// Cost of a demand miss the kernel declined to prefetch for.
+    pub missed: f32,
+    /// Cost of every prefetched byte, used or not.
+    pub per_byte: f32,
+    /// Extra cost of a prefetched block evicted unused, reported through
+    /// [`AetherLinkKernel::record_pollution`].  Zero ignores pollution.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pollution: f32,
+}
+
+impl Default for Reward {
+    fn default() -> Self {
+        Self {
+            hit: 1.0,
+            waste: 1.0,
+            missed: 1.0,
+            per_byte: 0.0,
+            pollution: 0.0,
+        }
+    }
+}
+
+impl Reward {
+    /// Missed opportunities hurt most; wasted reads are cheap.
+    pub const fn latency_first() -> Self {
+        Self {
+            hit: 1.0,
+            waste: 0.25,
+            missed: 4.0,
+            per_byte: 0.0,
+            pollution: 0.0,
+        }
+    }
+
+    /// Reward every covered access, tolerate some waste.
+    pub const fn coverage_first() -> Self {
+        Self {
+            hit: 2.0,
+            waste: 0.5,
+            missed: 2.0,
+            per_byte: 0.0,
+            pollution: 0.0,
+        }
+    }
+
+    /// Wasted and issued bytes dominate; one 64 KiB prefetch costs as much
+    /// as a wasted block.
+    pub const fn bandwidth_frugal() -> Self {
+        Self {
+            hit: 1.0,
+            waste: 4.0,
+            missed: 0.25,
+            per_byte: 1.0 / 65_536.0,
+            pollution: 0.0,
+        }
+    }
+}
+
+/// Why a pending prefetch was withdrawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CancelReason {
+    /// The demand read for its blocks came before the prefetch was
+    /// issued: a late hit.
+    DemandArrived,
+    /// Anything else, such as a file closed or a device going away.
+    Withdrawn,
+}
+
+/// Outcome reports received, for accuracy and pollution rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Outcomes {
+    useful: u64,
+    wasted: u64,
+    missed: u64,
+    polluted: u64,
+    cancelled: u64,
+    late: u64,
+    /// Whether the last cycle fired, until
+    /// [`record_outcome`](AetherLinkKernel::record_outcome) scores it.
+    vote: Option<bool>,
+}
+
+/// Rule that moves ε.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThresholdPolicy {
+    /// `ε += λ₁·E₁` every cycle (the original rule).
+    #[default]
+    OpenLoop,
+    /// ε moves only on reported outcomes; λ₁ is unused.
+    Feedback(FeedbackSteps),
+}
+
+impl AetherLinkKernel {
+    /// Switch the threshold rule.  ε keeps its current value.
+    #[inline]
+    pub fn set_threshold_policy(&mut self, policy: ThresholdPolicy) {
+        self.threshold_policy = policy;
+    }
+
+    /// Current threshold rule.
+    #[inline]
+    pub fn threshold_policy(&self) -> ThresholdPolicy {
+        self.threshold_policy
+    }
+
+    /// Set the weights that scale the feedback steps.
+    #[inline]
+    pub fn set_reward(&mut self, reward: Reward) {
+        self.reward = reward;
+    }
+
+    /// Current feedback weights.
+    #[inline]
+    pub fn reward(&self) -> Reward {
+        self.reward
+    }
+
+    /// Report that a prefetched block was used (`true`) or evicted unused
+    /// (`false`).  Only moves ε under [`ThresholdPolicy::Feedback`].
+    #[inline]
+    pub fn record_prefetch_outcome(&mut self, useful: bool) {
+        if useful {
+            self.outcomes.useful += 1;
+            self.class_outcome(|s| s.hits += 1);
+        } else {
+            self.outcomes.wasted += 1;
+            self.class_outcome(|s| s.wasted += 1);
+        }
+        if let ThresholdPolicy::Feedback(steps) = self.threshold_policy {
+            let step = if useful {
+                -steps.hit * self.reward.hit
+            } else {
+                steps.waste * self.reward.waste
+            };
+            self.nudge_epsilon(step);
+        }
+    }
+
+    /// Report whether prefetching on the last cycle would have paid off,
+    /// for callers that know the next access but not what was cached.
+    ///
+    /// The kernel scores its own decision: if the cycle fired, this is
+    /// [`record_prefetch_outcome`](Self::record_prefetch_outcome); if it
+    /// declined a useful prefetch,
+    /// [`record_missed_opportunity`](Self::record_missed_opportunity); a
+    /// declined useless one changes nothing.  Only the first report after
+    /// a cycle counts.
+    ///
+    /// Outcomes move ε only under [`ThresholdPolicy::Feedback`]; under the
+    /// default [`ThresholdPolicy::OpenLoop`] this just updates the hit,
+    /// waste and miss counters behind
+    /// [`prefetch_accuracy`](Self::prefetch_accuracy) and
+    /// [`prefetch_coverage`](Self::prefetch_coverage).  φ is never
+    /// affected by either policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::{AetherLinkKernel, FeedbackSteps, ThresholdPolicy};
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+    /// let mut prev: Option<u64> = None;
+    /// for lba in [10, 11, 12, 13, 500, 9000] {
+    ///     if let Some(prev) = prev {
+    ///         // Would a prefetch after `prev` have covered `lba`?
+    ///         kernel.record_outcome(lba > prev && lba <= prev + 8);
+    ///     }
+    ///     kernel.push_lba(lba);
+    ///     prev = Some(lba);
+    /// }
+    /// // 12 and 13 followed a cycle and were covered: either hits or misses.
+    /// let stats = kernel.stats();
+    /// assert_eq!(stats.hits + stats.missed, 2);
+    /// println!("coverage {:.2}", kernel.prefetch_coverage());
+    /// ```
+    #[inline]
+    pub fn record_outcome(&mut self, useful: bool) {
+        if let Some(fired) = self.outcomes.vote.take() {
+            self.record_vote_outcome(fired, useful);
+        }
+    }
+
+    /// Report a demand miss on an access the kernel declined to prefetch
+    /// for.  Only moves ε under [`ThresholdPolicy::Feedback`].
+    #[inline]
+    pub fn record_missed_opportunity(&mut self) {
+        self.outcomes.missed += 1;
+        self.class_outcome(|s| s.missed += 1);
+        if let ThresholdPolicy::Feedback(steps) = self.threshold_policy {
+            self.nudge_epsilon(-steps.miss * self.reward.missed);
+        }
+    }
+
+    /// Report that `bytes` were just prefetched.  Raises ε by the waste step
+    /// times [`Reward::per_byte`] per byte; a no-op with free bandwidth or
+    /// outside [`ThresholdPolicy::Feedback`].
+    #[inline]
+    pub fn record_prefetch_issued(&mut self, bytes: u64) {
+        if let ThresholdPolicy::Feedback(steps) = self.threshold_policy {
+            self.nudge_epsilon(steps.waste * self.reward.per_byte * bytes as f32);
+        }
+    }
+
+    /// Report that `count` prefetched blocks were evicted without being
+    /// used.  Raises ε by the waste step times [`Reward::pollution`] per
+    /// block; a no-op without a pollution penalty or outside
+    /// [`ThresholdPolicy::Feedback`].
+    #[inline]
+    pub fn record_pollution(&mut self, count: u64) {
+        self.outcomes.polluted += count;
+        if let ThresholdPolicy::Feedback(steps) = self.threshold_policy {
+            self.nudge_epsilon(steps.waste * self.reward.pollution * count as f32);
+        }
+    }
+
+    /// Remove the pending plans overlapping `target` (an LBA or a
+    /// [`PrefetchPlan`]) from `queue` and report the cancellation with
+    /// [`record_prefetch_cancelled`](Self::record_prefetch_cancelled).
+    /// Returns the plans removed; a target with nothing pending is still
+    /// reported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::queue::PrefetchQueue;
+    /// use aether_link::{AetherLinkKernel, CancelReason, PrefetchPlan};
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// let mut queue = PrefetchQueue::new(16);
+    /// queue.push(PrefetchPlan::new(100, 8));
+    /// // The demand read of block 104 overtook the prefetch.
+    /// assert_eq!(kernel.cancel_prefetch(&mut queue, 104, CancelReason::DemandArrived), 1);
+    /// assert!(queue.is_empty());
+    /// assert_eq!((kernel.stats().cancelled, kernel.stats().late_hits), (1, 1));
+    /// ```
+    pub fn cancel_prefetch(
+        &mut self,
+        queue: &mut PrefetchQueue,
+        target: impl Into<PrefetchPlan>,
+        reason: CancelReason,
+    ) -> usize {
+        let removed = queue.cancel(target);
+        self.record_prefetch_cancelled(reason);
+        removed
+    }
+
+    /// Report a prefetch cancelled before it was issued, for dispatchers
+    /// that keep their own queue.  Counted apart from wasted prefetches
+    /// and left out of [`prefetch_accuracy`](Self::prefetch_accuracy); a
+    /// [`CancelReason::DemandArrived`] is also a late hit, which lowers ε
+    /// by the hit step under [`ThresholdPolicy::Feedback`].
+    #[inline]
+    pub fn record_prefetch_cancelled(&mut self, reason: CancelReason) {
+        self.outcomes.cancelled += 1;
+        if reason == CancelReason::DemandArrived {
+            self.outcomes.late += 1;
+            if let ThresholdPolicy::Feedback(steps) = self.threshold_policy {
+                self.nudge_epsilon(-steps.hit * self.reward.hit);
+            }
+        }
+    }
+
+    /// Share of correct predictions that arrived in time: hits over hits
+    /// plus late hits; 0 before either is reported.
+    #[inline]
+    pub fn prefetch_timeliness(&self) -> f32 {
+        let Outcomes { useful, late, .. } = self.outcomes;
+        if useful + late == 0 {
+            0.0
+        } else {
+            useful as f32 / (useful + late) as f32
+        }
+    }
+
+    /// Share of reported prefetch outcomes that were useful; 0 before any
+    /// report.
+    #[inline]
+    pub fn prefetch_accuracy(&self) -> f32 {
+        let Outcomes { useful, wasted, .. } = self.outcomes;
+        if useful + wasted == 0 {
+            0.0
+        } else {
+            useful as f32 / (useful + wasted) as f32
+        }
+    }
+
+    /// Share of reported useful prefetches the kernel made: hits over hits
+    /// plus missed opportunities; 0 before either is reported.
+    #[inline]
+    pub fn prefetch_coverage(&self) -> f32 {
+        let Outcomes { useful, missed, .. } = self.outcomes;
+        if useful + missed == 0 {
+            0.0
+        } else {
+            useful as f32 / (useful + missed) as f32
+        }
+    }
+
+    /// Prefetched blocks reported evicted unused per prefetch triggered; 0
+    /// before any trigger.
+    #[inline]
+    pub fn pollution_rate(&self) -> f32 {
+        if self.prefetches == 0 {
+            0.0
+        } else {
+            self.outcomes.polluted as f32 / self.prefetches as f32
+        }
+    }
+
+    /// Whether any prefetch outcome, any pollution, and any hit or missed
+    /// opportunity has been reported.
+    #[inline]
+    pub(crate) fn outcomes_reported(&self) -> (bool, bool, bool) {
+        let Outcomes {
+            useful,
+            wasted,
+            missed,
+            polluted,
+            ..
+        } = self.outcomes;
+        (useful + wasted > 0, polluted > 0, useful + missed > 0)
+    }
+
+    /// Cancelled prefetches and, of those, late hits.
+    #[inline]
+    pub(crate) fn cancel_counts(&self) -> (u64, u64) {
+        (self.outcomes.cancelled, self.outcomes.late)
+    }
+
+    /// Useful, wasted and missed reports.
+    #[inline]
+    pub(crate) fn outcome_counts(&self) -> (u64, u64, u64) {
+        let Outcomes {
+            useful,
+            wasted,
+            missed,
+            ..
+        } = self.outcomes;
+        (useful, wasted, missed)
+    }
+
+    /// Note the decision of the cycle just run, for
+    /// [`record_outcome`](Self::record_outcome).
+    #[inline(always)]
+    pub(crate) fn voted(&mut self, fired: bool) {
+        self.outcomes.vote = Some(fired);
+    }
+
+    /// Forget the outcome counts (see [`reset_stats`](Self::reset_stats)).
+    #[inline]
+    pub(crate) fn reset_outcomes(&mut self) {
+        self.outcomes = Outcomes::default();
+    }
+
+    /// Report the counterfactual outcome of a cycle that voted `fired`:
+    /// a prefetch outcome if it fired, a missed opportunity if it declined
+    /// a useful prefetch.
+    #[inline]
+    pub(crate) fn record_vote_outcome(&mut self, fired: bool, useful: bool) {
+        if fired {
+            self.record_prefetch_outcome(useful);
+        } else if useful {
+            self.record_missed_opportunity();
+        }
+    }
+
+    #[inline(always)]
+    fn nudge_epsilon(&mut self, step: f32) {
+        self.epsilon = (self.epsilon + step).clamp(EPSILON_CLAMP.0, EPSILON_CLAMP.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+    use crate::sim::CacheSim;
+    use crate::trace::IoEvent;
+    use crate::workload::Pattern;
+
+    #[test]
+    fn test_open_loop_ignores_feedback() {
+        let mut kernel = AetherLinkKernel::default();
+        let eps = kernel.epsilon;
+        kernel.record_prefetch_outcome(false);
+        kernel.record_missed_opportunity();
+        assert_eq!(kernel.epsilon, eps);
+
+        kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+        kernel.record_prefetch_outcome(false);
+        assert!(kernel.epsilon > eps);
+        for _ in 0..1000 {
+            kernel.record_missed_opportunity();
+        }
+        assert_eq!(kernel.epsilon, EPSILON_CLAMP.0);
+    }
+
+    #[test]
+    fn test_record_outcome_scores_the_last_cycle() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+        // No cycle yet: nothing to score.
+        kernel.record_outcome(true);
+        assert_eq!(kernel.stats().missed, 0);
+
+        let eps = kernel.epsilon;
+        kernel.process_io_cycle_qos(&[0, 1], -1.0);
+        kernel.record_outcome(false);
+        kernel.record_outcome(true);
+        assert_eq!((kernel.stats().hits, kernel.stats().wasted), (0, 1));
+        assert!(kernel.epsilon > eps);
+
+        let eps = kernel.epsilon;
+        kernel.process_io_cycle_qos(&[0, 1], 1.0);
+        kernel.record_outcome(false);
+        assert_eq!(kernel.epsilon, eps);
+        kernel.process_io_cycle_qos(&[0, 1], 1.0);
+        kernel.record_outcome(true);
+        assert!(kernel.epsilon < eps);
+        kernel.process_io_cycle_qos(&[0, 1], -1.0);
+        kernel.record_outcome(true);
+
+        let stats = kernel.stats();
+        assert_eq!((stats.hits, stats.wasted, stats.missed), (1, 1, 1));
+        assert_eq!(kernel.prefetch_coverage(), 0.5);
+        assert_eq!(kernel.report().coverage, Some(0.5));
+    }
+
+    #[test]
+    fn test_cancellations_are_not_waste() {
+        let mut kernel = AetherLinkKernel::default();
+        let mut queue = PrefetchQueue::new(8);
+        queue.push(PrefetchPlan::new(100, 8));
+        queue.push(PrefetchPlan::new(200, 8));
+        for _ in 0..3 {
+            kernel.record_prefetch_outcome(true);
+        }
+        kernel.record_prefetch_outcome(false);
+
+        let plan = PrefetchPlan::new(200, 8);
+        let demand = CancelReason::DemandArrived;
+        assert_eq!(kernel.cancel_prefetch(&mut queue, plan, demand), 1);
+        // Unknown: nothing removed, still counted.
+        assert_eq!(
+            kernel.cancel_prefetch(&mut queue, 5_000, CancelReason::Withdrawn),
+            0
+        );
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.stats().cancelled, 1);
+
+        let stats = kernel.stats();
+        assert_eq!((stats.cancelled, stats.late_hits), (2, 1));
+        assert_eq!((stats.hits, stats.wasted), (3, 1));
+        assert_eq!(kernel.prefetch_accuracy(), 0.75);
+        assert_eq!(kernel.prefetch_timeliness(), 0.75);
+        kernel.reset_stats();
+        assert_eq!(kernel.stats().cancelled, 0);
+        assert_eq!(kernel.prefetch_timeliness(), 0.0);
+    }
+
+    #[test]
+    fn test_late_hits_lower_epsilon_under_feedback() {
+        let mut kernel = AetherLinkKernel::default();
+        let eps = kernel.epsilon;
+        kernel.record_prefetch_cancelled(CancelReason::DemandArrived);
+        assert_eq!(kernel.epsilon, eps, "open loop");
+
+        kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+        kernel.record_prefetch_cancelled(CancelReason::Withdrawn);
+        assert_eq!(kernel.epsilon, eps);
+        let mut hit = kernel.clone();
+        kernel.record_prefetch_cancelled(CancelReason::DemandArrived);
+        hit.record_prefetch_outcome(true);
+        assert!(kernel.epsilon < eps);
+        assert_eq!(kernel.epsilon, hit.epsilon);
+    }
+
+    #[test]
+    fn test_bandwidth_frugal_is_more_conservative() {
+        // Same reports, two weightings: frugal must never sit below
+        // coverage-first and must end strictly above it.
+        let feedback = ThresholdPolicy::Feedback(FeedbackSteps::default());
+        let mut frugal = AetherLinkKernel::default();
+        let mut coverage = AetherLinkKernel::default();
+        frugal.set_threshold_policy(feedback);
+        coverage.set_threshold_policy(feedback);
+        frugal.set_reward(Reward::bandwidth_frugal());
+        coverage.set_reward(Reward::coverage_first());
+
+        let mut rng = crate::rng::SplitMix64::new(5);
+        for _ in 0..200 {
+            let report = rng.below(4);
+            for kernel in [&mut frugal, &mut coverage] {
+                match report {
+                    0 => kernel.record_prefetch_outcome(true),
+                    1 => kernel.record_prefetch_outcome(false),
+                    2 => kernel.record_missed_opportunity(),
+                    _ => kernel.record_prefetch_issued(8 * 4096),
+                }
+            }
+            assert!(frugal.epsilon >= coverage.epsilon);
+        }
+        assert!(
+            frugal.epsilon > coverage.epsilon,
+            "frugal {} coverage {}",
+            frugal.epsilon,
+            coverage.epsilon
+        );
+    }
+
+    #[test]
+    fn test_feedback_beats_open_loop_on_bursty() {
+        // 16-block bursts with short jumps: the open-loop rule walks ε into
+        // its upper corner here and almost stops prefetching.
+        let pattern = Pattern::Bursty {
+            burst_len: 16,
+            jump: 64,
+        };
+        let trace: Vec<IoEvent> = pattern
+            .workload(11)
+            .take(20_000)
+            .map(IoEvent::read)
+            .collect();
+        let run = |policy| {
+            let mut kernel = AetherLinkKernel::default();
+            kernel.set_threshold_policy(policy);
+            let mut cache = CacheSim::new(1024);
+            eval::run_with_feedback(
+                &mut kernel,
+                &mut cache,
+                trace.iter().copied(),
+                &Default::default(),
+            )
+        };
+        let open = run(ThresholdPolicy::OpenLoop);
+        let feedback = run(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+        assert!(
+            feedback.coverage > open.coverage,
+            "open:\n{open}\nfeedback:\n{feedback}"
+        );
+        assert!(
+            feedback.accuracy >= open.accuracy - 0.02,
+            "open:\n{open}\nfeedback:\n{feedback}"
+        );
+    }
+
+    #[test]
+    fn test_pollution_penalty_protects_hot_set() {
+        // A scattered hot set that nearly fills the cache, with one random
+        // jump in eight.  Every prefetch is useless and evicts hot blocks.
+        let mut rng = crate::rng::SplitMix64::new(7);
+        let trace: Vec<IoEvent> = (0..40_000u64)
+            .map(|i| {
+                if i % 8 == 0 {
+                    IoEvent::read(1 << 20 | rng.below(1 << 30))
+                } else {
+                    IoEvent::read(rng.below(224) * 64)
+                }
+            })
+            .collect();
+        let run = |pollution| {
+            let mut kernel = AetherLinkKernel::default();
+            kernel.set_threshold_policy(ThresholdPolicy::Feedback(FeedbackSteps::default()));
+            kernel.set_reward(Reward {
+                pollution,
+                ..Reward::default()
+            });
+            let mut cache = CacheSim::new(256);
+            let report = eval::run(&mut kernel, &mut cache, trace.iter().copied());
+            (report, kernel)
+        };
+        let (plain, plain_kernel) = run(0.0);
+        let (penalized, kernel) = run(1.0);
+        assert_eq!(plain_kernel.epsilon, 0.5, "pollution alone is free");
+        assert!(plain_kernel.pollution_rate() > 1.0);
+        assert!(kernel.epsilon > 0.5);
+        assert!(
+            penalized.triggers < plain.triggers && penalized.hit_rate > plain.hit_rate,
+            "plain:\n{plain}\npenalized:\n{penalized}"
+        );
+    }
+}
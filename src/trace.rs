@@ -0,0 +1,336 @@
+//! I/O trace parsing.
+//!
+//! Traces are decoded into a flat stream of [`IoEvent`]s in block units, so
+//! the replay harness never has to care which tool captured them.
+//!
+//! | Format | Source | Address unit |
+//! |--------|--------|--------------|
+//! | [`TraceFormat::Csv`] | `timestamp_ns,offset,length[,op]` | bytes |
+//! | [`TraceFormat::Blkparse`] | default `blkparse` text output | 512-byte sectors |
+//!
+//! Both are converted to blocks of `block_size` bytes on the way in.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+/// Size of a `blktrace` sector in bytes.
+pub const SECTOR_SIZE: u64 = 512;
+
+/// Direction of a traced request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoOp {
+    /// Read request.
+    #[default]
+    Read,
+    /// Write request.
+    Write,
+}
+
+/// A single traced I/O request, normalised to block units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoEvent {
+    /// Capture timestamp in nanoseconds (0 when the trace has none).
+    pub timestamp_ns: u64,
+    /// First block touched by the request.
+    pub lba: u64,
+    /// Number of blocks touched (always ≥ 1).
+    pub blocks: u32,
+    /// Request direction.
+    pub op: IoOp,
+}
+
+impl IoEvent {
+    /// Single-block read at `lba` with no timestamp.
+    #[inline]
+    pub fn read(lba: u64) -> Self {
+        Self {
+            timestamp_ns: 0,
+            lba,
+            blocks: 1,
+            op: IoOp::Read,
+        }
+    }
+
+    /// One past the last block touched by the request.
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.lba.saturating_add(self.blocks as u64)
+    }
+}
+
+/// On-disk trace encodings understood by [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Comma-separated `timestamp_ns,offset,length[,op]` in bytes.
+    ///
+    /// A header line and `#` comments are ignored.  `op` is `R` or `W`
+    /// (case-insensitive) and defaults to read.
+    Csv,
+    /// Default text output of `blkparse`.
+    ///
+    /// Only queue (`Q`) actions are kept — they reflect what the application
+    /// asked for, before the block layer merged or split anything.
+    Blkparse,
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "blkparse" | "blktrace" => Ok(Self::Blkparse),
+            other => Err(format!(
+                "unknown trace format '{other}' (expected csv or blkparse)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv => f.write_str("csv"),
+            Self::Blkparse => f.write_str("blkparse"),
+        }
+    }
+}
+
+/// Error raised while decoding a trace.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum TraceError {
+    /// The underlying reader failed.
+    Io(std::io::Error),
+    /// A line could not be decoded.
+    Parse {
+        /// 1-based line number.
+        line: usize,
+        /// What was wrong with it.
+        reason: String,
+    },
+    /// `block_size` was zero.
+    ZeroBlockSize,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "trace read failed: {e}"),
+            Self::Parse { line, reason } => write!(f, "line {line}: {reason}"),
+            Self::ZeroBlockSize => f.write_str("block size must be non-zero"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TraceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for TraceError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Decode every event of a trace into memory.
+///
+/// `block_size` is the prefetch granularity in bytes; byte and sector
+/// addresses are divided down to it and lengths are rounded up.
+#[cfg(feature = "std")]
+pub fn parse<R: BufRead>(
+    reader: R,
+    format: TraceFormat,
+    block_size: u64,
+) -> Result<Vec<IoEvent>, TraceError> {
+    if block_size == 0 {
+        return Err(TraceError::ZeroBlockSize);
+    }
+    let mut events = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let parsed = match format {
+            TraceFormat::Csv => parse_csv_line(&line, block_size),
+            TraceFormat::Blkparse => parse_blkparse_line(&line, block_size),
+        };
+        match parsed {
+            Ok(Some(ev)) => events.push(ev),
+            Ok(None) => {}
+            Err(reason) => {
+                return Err(TraceError::Parse {
+                    line: idx + 1,
+                    reason,
+                })
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Convert a byte extent into a block-granular event.
+#[inline]
+fn to_blocks(offset: u64, len: u64, block_size: u64) -> (u64, u32) {
+    let lba = offset / block_size;
+    let end = offset.saturating_add(len.max(1));
+    let last = (end - 1) / block_size;
+    let blocks = (last - lba + 1).min(u32::MAX as u64) as u32;
+    (lba, blocks)
+}
+
+/// Decode one CSV line.  Returns `Ok(None)` for blanks, comments and headers.
+pub fn parse_csv_line(line: &str, block_size: u64) -> Result<Option<IoEvent>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut fields = line.split(',').map(str::trim);
+    let ts = fields.next().unwrap_or_default();
+    // Header row: first field is not numeric.
+    if ts.parse::<u64>().is_err() && ts.chars().any(|c| c.is_ascii_alphabetic()) {
+        return Ok(None);
+    }
+    let timestamp_ns = ts
+        .parse::<u64>()
+        .map_err(|_| format!("bad timestamp '{ts}'"))?;
+    let offset = fields
+        .next()
+        .ok_or("missing offset column")?
+        .parse::<u64>()
+        .map_err(|e| format!("bad offset: {e}"))?;
+    let len = fields
+        .next()
+        .ok_or("missing length column")?
+        .parse::<u64>()
+        .map_err(|e| format!("bad length: {e}"))?;
+    let op = match fields.next() {
+        None | Some("") => IoOp::Read,
+        Some(s) if s.eq_ignore_ascii_case("r") || s.eq_ignore_ascii_case("read") => IoOp::Read,
+        Some(s) if s.eq_ignore_ascii_case("w") || s.eq_ignore_ascii_case("write") => IoOp::Write,
+        Some(s) => return Err(format!("bad op '{s}' (expected R or W)")),
+    };
+    let (lba, blocks) = to_blocks(offset, len, block_size);
+    Ok(Some(IoEvent {
+        timestamp_ns,
+        lba,
+        blocks,
+        op,
+    }))
+}
+
+/// Decode one `blkparse` line.  Returns `Ok(None)` for non-queue actions,
+/// summary sections and anything without a `sector + count` payload.
+///
+/// ```text
+///   8,0    3        1     0.000000000   697  Q   R 223490 + 8 [kworker/u8:0]
+/// ```
+pub fn parse_blkparse_line(line: &str, block_size: u64) -> Result<Option<IoEvent>, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // dev cpu seq time pid action rwbs sector + count ...
+    if fields.len() < 10 || !fields[0].contains(',') || fields[8] != "+" {
+        return Ok(None);
+    }
+    if fields[5] != "Q" {
+        return Ok(None);
+    }
+    let timestamp_ns =
+        parse_seconds(fields[3]).ok_or_else(|| format!("bad time '{}'", fields[3]))?;
+    let rwbs = fields[6];
+    let op = if rwbs.contains('W') {
+        IoOp::Write
+    } else if rwbs.contains('R') {
+        IoOp::Read
+    } else {
+        // Flushes, discards and barriers carry no data to prefetch.
+        return Ok(None);
+    };
+    let sector = fields[7]
+        .parse::<u64>()
+        .map_err(|e| format!("bad sector: {e}"))?;
+    let count = fields[9]
+        .parse::<u64>()
+        .map_err(|e| format!("bad sector count: {e}"))?;
+    let (lba, blocks) = to_blocks(
+        sector.saturating_mul(SECTOR_SIZE),
+        count.saturating_mul(SECTOR_SIZE),
+        block_size,
+    );
+    Ok(Some(IoEvent {
+        timestamp_ns,
+        lba,
+        blocks,
+        op,
+    }))
+}
+
+/// Parse `seconds.nanoseconds` without going through floating point.
+fn parse_seconds(s: &str) -> Option<u64> {
+    let (secs, frac) = s.split_once('.').unwrap_or((s, "0"));
+    let secs = secs.parse::<u64>().ok()?;
+    let digits = frac.len().min(9);
+    let mut nanos = frac[..digits].parse::<u64>().ok()?;
+    for _ in digits..9 {
+        nanos *= 10;
+    }
+    secs.checked_mul(1_000_000_000)?.checked_add(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_bytes_to_blocks() {
+        let ev = parse_csv_line("10,8192,4096,R", 4096).unwrap().unwrap();
+        assert_eq!((ev.timestamp_ns, ev.lba, ev.blocks), (10, 2, 1));
+        // Straddles a block boundary → two blocks.
+        let ev = parse_csv_line("0,4000,200,w", 4096).unwrap().unwrap();
+        assert_eq!((ev.lba, ev.blocks, ev.op), (0, 2, IoOp::Write));
+    }
+
+    #[test]
+    fn test_csv_header_and_errors() {
+        assert!(parse_csv_line("timestamp_ns,offset,length,op", 4096)
+            .unwrap()
+            .is_none());
+        assert!(parse_csv_line("# comment", 4096).unwrap().is_none());
+        assert!(parse_csv_line("1,abc,4096", 4096).is_err());
+        assert!(parse_csv_line("1,0", 4096).is_err());
+    }
+
+    #[test]
+    fn test_blkparse_queue_lines() {
+        let line = "  8,0    3        1     1.000000500   697  Q   R 223488 + 8 [kworker/u8:0]";
+        let ev = parse_blkparse_line(line, 4096).unwrap().unwrap();
+        assert_eq!(ev.timestamp_ns, 1_000_000_500);
+        assert_eq!(ev.lba, 223488 * 512 / 4096);
+        assert_eq!(ev.blocks, 1);
+        // Dispatch events of the same request are ignored.
+        let d = "  8,0    3        2     1.000001000   697  D   R 223490 + 8 [kworker/u8:0]";
+        assert!(parse_blkparse_line(d, 4096).unwrap().is_none());
+        assert!(parse_blkparse_line("CPU0 (8,0):", 4096).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_reports_line_number() {
+        let input = "0,0,4096\n1,4096,4096\n2,oops,1\n";
+        match parse(input.as_bytes(), TraceFormat::Csv, 4096) {
+            Err(TraceError::Parse { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected parse error, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Evolutionary search.
+//!
+//! Grid search grows exponentially with the number of axes and random
+//! search never refines what it finds.  [`evolve`] runs a small (μ + λ)
+//! evolution strategy instead: each generation, [`offspring`] children are
+//! bred from the [`parents`] by optional uniform crossover and Gaussian
+//! mutation, and the best μ of parents and children together survive, so
+//! the best configuration found is never lost.  Mutation scales are a
+//! fraction of each axis' width in the [`ParamSpace`], shrink every
+//! generation, and children are clamped to the space, so every candidate
+//! stays within the bounds the caller chose.
+//!
+//! Breeding draws from one seeded generator in a fixed order and survivors
+//! are picked with a stable sort, so a run is fully determined by the seed;
+//! with the `parallel` feature each generation is evaluated on the rayon
+//! thread pool, with identical results.
+//!
+//! [`offspring`]: EvolveOptions::offspring
+//! [`parents`]: EvolveOptions::parents
+
+use super::{evaluate, EvalSetup, ParamSpace, ScoredConfig, TraceView};
+use crate::config::KernelConfig;
+use crate::eval::EvalReport;
+use crate::rng::SplitMix64;
+
+/// Knobs for [`evolve`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvolveOptions {
+    /// Bounds of every parameter.  A value-list axis spans its smallest to
+    /// its largest value.
+    pub space: ParamSpace,
+    /// How candidates are evaluated.
+    pub setup: EvalSetup,
+    /// Survivors per generation (μ).
+    pub parents: usize,
+    /// Children per generation (λ).
+    pub offspring: usize,
+    /// Generations after the initial population.  Every generation costs
+    /// `offspring` evaluations; the initial population `parents +
+    /// offspring`.
+    pub generations: usize,
+    /// Initial mutation standard deviation, as a fraction of each axis'
+    /// width.
+    pub mutation: f32,
+    /// Factor applied to the mutation scale after every generation.
+    pub mutation_decay: f32,
+    /// Probability that a child is bred from two parents (uniform
+    /// crossover) rather than copied from one before mutation.
+    pub crossover: f32,
+    /// Seed for initialisation and breeding.
+    pub seed: u64,
+}
+
+impl Default for EvolveOptions {
+    fn default() -> Self {
+        Self {
+            space: ParamSpace::default_ranges(),
+            setup: EvalSetup::default(),
+            parents: 8,
+            offspring: 24,
+            generations: 20,
+            mutation: 0.15,
+            mutation_decay: 0.9,
+            crossover: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// Evolve kernel configs against `trace` and return the final population
+/// sorted by descending `objective`.
+///
+/// Candidates that fail validation are dropped, as in
+/// [`search`](super::search).
+pub fn evolve<'a, F>(
+    trace: impl Into<TraceView<'a>>,
+    objective: F,
+    opts: &EvolveOptions,
+) -> Vec<ScoredConfig>
+where
+    F: Fn(&EvalReport) -> f64 + Sync,
+{
+    let trace = trace.into();
+    run(
+        |config| {
+            let report = evaluate(trace, config, &opts.setup)?;
+            Some((objective(&report), report))
+        },
+        opts,
+    )
+    .into_iter()
+    .map(|(config, score, report)| ScoredConfig {
+        config,
+        score,
+        report,
+    })
+    .collect()
+}
+
+/// [`evolve`] against an arbitrary fitness function instead of a trace
+/// replay; `None` rejects a candidate.  [`EvolveOptions::setup`] is
+/// unused.
+pub fn evolve_with<F>(fitness: F, opts: &EvolveOptions) -> Vec<(KernelConfig, f64)>
+where
+    F: Fn(&KernelConfig) -> Option<f64> + Sync,
+{
+    run(|config| Some((fitness(config)?, ())), opts)
+        .into_iter()
+        .map(|(config, score, ())| (config, score))
+        .collect()
+}
+
+type Individual<T> = (KernelConfig, f64, T);
+
+fn run<T, F>(score: F, opts: &EvolveOptions) -> Vec<Individual<T>>
+where
+    T: Send,
+    F: Fn(&KernelConfig) -> Option<(f64, T)> + Sync,
+{
+    let bounds = opts.space.axes().map(|axis| axis.bounds());
+    let mut rng = SplitMix64::new(opts.seed);
+    let parents = opts.parents.max(1);
+
+    let initial: Vec<[f32; 6]> = (0..parents + opts.offspring)
+        .map(|_| bounds.map(|(lo, hi)| rng.range_f32(lo, hi)))
+        .collect();
+    let mut population = select(evaluate_all(initial, &score), parents);
+
+    let mut scale = opts.mutation;
+    for _ in 0..opts.generations {
+        if population.is_empty() {
+            break;
+        }
+        let children: Vec<[f32; 6]> = (0..opts.offspring)
+            .map(|_| {
+                let pick = |rng: &mut SplitMix64| {
+                    ParamSpace::to_point(&population[rng.below(population.len() as u64) as usize].0)
+                };
+                let mut child = pick(&mut rng);
+                if rng.next_f32() < opts.crossover {
+                    let other = pick(&mut rng);
+                    for (gene, &alt) in child.iter_mut().zip(&other) {
+                        if rng.next_f32() < 0.5 {
+                            *gene = alt;
+                        }
+                    }
+                }
+                for (gene, &(lo, hi)) in child.iter_mut().zip(&bounds) {
+                    *gene = (*gene + normal(&mut rng) * scale * (hi - lo)).clamp(lo, hi);
+                }
+                child
+            })
+            .collect();
+        population.extend(evaluate_all(children, &score));
+        population = select(population, parents);
+        scale *= opts.mutation_decay;
+    }
+    population
+}
+
+/// Score every point, dropping rejected ones, in input order.
+fn evaluate_all<T, F>(points: Vec<[f32; 6]>, score: &F) -> Vec<Individual<T>>
+where
+    T: Send,
+    F: Fn(&KernelConfig) -> Option<(f64, T)> + Sync,
+{
+    let one = |p: [f32; 6]| {
+        let config = ParamSpace::from_point(p);
+        let (s, extra) = score(&config)?;
+        Some((config, s, extra))
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        points.into_par_iter().filter_map(one).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    points.into_iter().filter_map(one).collect()
+}
+
+/// The `n` best, parents before children on ties.
+fn select<T>(mut population: Vec<Individual<T>>, n: usize) -> Vec<Individual<T>> {
+    population.sort_by(|a, b| b.1.total_cmp(&a.1));
+    population.truncate(n);
+    population
+}
+
+/// Standard normal draw (Box–Muller).
+fn normal(rng: &mut SplitMix64) -> f32 {
+    let u = 1.0 - rng.next_f64();
+    let v = rng.next_f64();
+    (libm::sqrt(-2.0 * libm::log(u)) * libm::cos(2.0 * core::f64::consts::PI * v)) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::IoEvent;
+    use crate::tune::hit_rate;
+
+    const TARGET: [f32; 6] = [0.62, 0.37, 0.08, 0.21, 0.44, -0.13];
+
+    /// Negative squared distance to `TARGET`, in units of axis width.
+    fn bowl(config: &KernelConfig) -> Option<f64> {
+        let bounds = ParamSpace::default_ranges().axes().map(|a| a.bounds());
+        let d: f32 = ParamSpace::to_point(config)
+            .iter()
+            .zip(TARGET)
+            .zip(bounds)
+            .map(|((x, t), (lo, hi))| ((x - t) / (hi - lo)).powi(2))
+            .sum();
+        Some(-d as f64)
+    }
+
+    #[test]
+    fn test_finds_known_optimum() {
+        let opts = EvolveOptions {
+            generations: 40,
+            seed: 5,
+            ..EvolveOptions::default()
+        };
+        let results = evolve_with(bowl, &opts);
+        assert_eq!(results.len(), opts.parents);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+        let best = ParamSpace::to_point(&results[0].0);
+        for ((x, t), (lo, hi)) in best
+            .iter()
+            .zip(TARGET)
+            .zip(opts.space.axes().map(|a| a.bounds()))
+        {
+            assert!((x - t).abs() <= 0.01 * (hi - lo), "{best:?} vs {TARGET:?}");
+        }
+        assert_eq!(results, evolve_with(bowl, &opts), "seeded");
+        assert_ne!(
+            results[0],
+            evolve_with(bowl, &EvolveOptions { seed: 6, ..opts })[0]
+        );
+    }
+
+    #[test]
+    fn test_evolve_on_trace_stays_in_bounds() {
+        let trace: Vec<IoEvent> = (0..300).map(|i| IoEvent::read(i * 8)).collect();
+        let opts = EvolveOptions {
+            parents: 4,
+            offspring: 8,
+            generations: 3,
+            ..EvolveOptions::default()
+        };
+        let results = evolve(&trace, hit_rate, &opts);
+        assert_eq!(results.len(), 4);
+        let bounds = opts.space.axes().map(|a| a.bounds());
+        for r in &results {
+            r.config.validate().unwrap();
+            for (x, (lo, hi)) in ParamSpace::to_point(&r.config).into_iter().zip(bounds) {
+                assert!((lo..=hi).contains(&x));
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Gradient-based calibration against labelled traces.
+//!
+//! The kernel's fetch probability is `σ(−(λ₃·E₃ + bias))`, where the
+//! spectral observable E₃ depends on the trace and on ε/φ/λ₁/λ₂ but not on
+//! λ₃ or the bias.  Replaying a trace once therefore yields a fixed feature
+//! per decision, and fitting λ₃ and the bias to per-access labels ("a
+//! prefetch here would have paid off") is plain logistic regression.
+
+use crate::config::{KernelConfig, BIAS_RANGE, LAMBDA_RANGE};
+use crate::trace::IoEvent;
+use crate::AetherLinkKernel;
+
+/// Update rule used by [`fit()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Optimizer {
+    /// Plain mini-batch gradient descent.
+    Sgd,
+    /// Adam (β₁ = 0.9, β₂ = 0.999).
+    #[default]
+    Adam,
+}
+
+/// Knobs for [`fit()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitOptions {
+    /// Maximum passes over the trace.
+    pub epochs: usize,
+    /// Step size.
+    pub learning_rate: f64,
+    /// Decisions per gradient step, taken in trace order.
+    pub batch_size: usize,
+    /// Update rule.
+    pub optimizer: Optimizer,
+    /// Stop after this many epochs without a loss improvement of at least
+    /// `min_improvement`.
+    pub patience: usize,
+    /// Smallest loss decrease that counts as progress.
+    pub min_improvement: f64,
+    /// Kernel history window used while replaying.
+    pub window: usize,
+    /// Fit λ₃ as well as the bias.  On traces where E₃ barely varies the
+    /// two are nearly interchangeable; holding λ₃ fixed makes the bias
+    /// well determined.
+    pub fit_lambda: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            epochs: 200,
+            learning_rate: 0.05,
+            batch_size: 64,
+            optimizer: Optimizer::Adam,
+            patience: 10,
+            min_improvement: 1e-6,
+            window: crate::stream::DEFAULT_WINDOW,
+            fit_lambda: true,
+        }
+    }
+}
+
+/// Outcome of [`fit()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FitResult {
+    /// `init` with the fitted λ₃ and bias.
+    pub config: KernelConfig,
+    /// Mean logistic loss of `init`.
+    pub initial_loss: f64,
+    /// Mean logistic loss of `config`.
+    pub loss: f64,
+    /// ROC AUC of `config`'s fetch probabilities against the labels (0.5
+    /// when only one class is present).
+    pub auc: f64,
+    /// Epochs run before stopping.
+    pub epochs: usize,
+    /// Decisions the loss is taken over.
+    pub samples: usize,
+}
+
+/// Fit λ₃ and the bias of `init` so that the kernel's fetch probability
+/// predicts `labels`, minimising mean logistic loss.
+///
+/// `labels[i]` says whether prefetching at `trace[i]` would have been
+/// useful, e.g. from a cache simulation.  The trace is replayed through
+/// [`AetherLinkKernel::push_lba`] as in [`replay`](crate::replay), so the
+/// first access (warm-up, no decision) does not count.  The other
+/// parameters are kept, and the fitted values are projected into their
+/// documented ranges after every step, so a valid `init` yields a valid
+/// result.  The epoch with the lowest loss wins.
+///
+/// # Panics
+///
+/// If `labels` and `trace` differ in length.
+pub fn fit(trace: &[IoEvent], labels: &[bool], init: KernelConfig, opts: FitOptions) -> FitResult {
+    assert_eq!(trace.len(), labels.len(), "one label per event");
+    let samples = features(trace, labels, &init, opts.window);
+
+    let mut w = [init.lambda[2] as f64, init.bias as f64];
+    let initial_loss = loss(&samples, w);
+    let mut best = (initial_loss, w);
+    let mut adam = Adam::default();
+    let mut stale = 0;
+    let mut epochs = 0;
+    while epochs < opts.epochs && stale < opts.patience && !samples.is_empty() {
+        epochs += 1;
+        for batch in samples.chunks(opts.batch_size.max(1)) {
+            let mut g = gradient(batch, w);
+            if !opts.fit_lambda {
+                g[0] = 0.0;
+            }
+            let step = match opts.optimizer {
+                Optimizer::Sgd => g,
+                Optimizer::Adam => adam.step(g),
+            };
+            for (wi, si) in w.iter_mut().zip(step) {
+                *wi -= opts.learning_rate * si;
+            }
+            w = project(w);
+        }
+        let l = loss(&samples, w);
+        if l < best.0 - opts.min_improvement {
+            best = (l, w);
+            stale = 0;
+        } else {
+            stale += 1;
+        }
+    }
+
+    let (final_loss, w) = best;
+    let mut config = init;
+    config.lambda[2] = w[0] as f32;
+    config.bias = w[1] as f32;
+    FitResult {
+        config,
+        initial_loss,
+        loss: final_loss,
+        auc: auc(&samples, w),
+        epochs,
+        samples: samples.len(),
+    }
+}
+
+/// `(E₃, label)` for every decision of a replay.
+fn features(
+    trace: &[IoEvent],
+    labels: &[bool],
+    init: &KernelConfig,
+    window: usize,
+) -> Vec<(f64, bool)> {
+    let mut kernel = AetherLinkKernel::new(init.epsilon, init.phi, init.lambda, init.bias);
+    kernel.set_window(window);
+    trace
+        .iter()
+        .zip(labels)
+        .filter_map(|(ev, &y)| {
+            kernel.push_lba(ev.lba)?;
+            Some((kernel.last_spectral() as f64, y))
+        })
+        .collect()
+}
+
+/// Logit of the fetch probability; `p = σ(z)`.
+#[inline]
+fn logit(x: f64, w: [f64; 2]) -> f64 {
+    -(w[0] * x + w[1])
+}
+
+#[inline]
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + libm::exp(-z))
+}
+
+/// Mean of `ln(1 + eᶻ) − y·z`, the logistic loss written to stay finite
+/// for large |z|.
+fn loss(samples: &[(f64, bool)], w: [f64; 2]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = samples
+        .iter()
+        .map(|&(x, y)| {
+            let z = logit(x, w);
+            let softplus = z.max(0.0) + libm::log1p(libm::exp(-z.abs()));
+            softplus - if y { z } else { 0.0 }
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// Mean gradient of [`loss`] over `batch` with respect to `[λ₃, bias]`.
+fn gradient(batch: &[(f64, bool)], w: [f64; 2]) -> [f64; 2] {
+    let mut g = [0.0; 2];
+    for &(x, y) in batch {
+        // ∂loss/∂z = p − y, and ∂z/∂λ₃ = −x, ∂z/∂bias = −1.
+        let r = sigmoid(logit(x, w)) - if y { 1.0 } else { 0.0 };
+        g[0] -= r * x;
+        g[1] -= r;
+    }
+    let n = batch.len() as f64;
+    [g[0] / n, g[1] / n]
+}
+
+fn project(w: [f64; 2]) -> [f64; 2] {
+    let clamp =
+        |v: f64, r: &core::ops::RangeInclusive<f32>| v.clamp(*r.start() as f64, *r.end() as f64);
+    [clamp(w[0], &LAMBDA_RANGE), clamp(w[1], &BIAS_RANGE)]
+}
+
+#[derive(Debug, Default)]
+struct Adam {
+    m: [f64; 2],
+    v: [f64; 2],
+    t: i32,
+}
+
+impl Adam {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPS: f64 = 1e-8;
+
+    fn step(&mut self, g: [f64; 2]) -> [f64; 2] {
+        self.t += 1;
+        let c1 = 1.0 - Self::BETA1.powi(self.t);
+        let c2 = 1.0 - Self::BETA2.powi(self.t);
+        let mut out = [0.0; 2];
+        for i in 0..2 {
+            self.m[i] = Self::BETA1 * self.m[i] + (1.0 - Self::BETA1) * g[i];
+            self.v[i] = Self::BETA2 * self.v[i] + (1.0 - Self::BETA2) * g[i] * g[i];
+            out[i] = (self.m[i] / c1) / (libm::sqrt(self.v[i] / c2) + Self::EPS);
+        }
+        out
+    }
+}
+
+/// ROC AUC via the rank-sum statistic, ties counted half.
+fn auc(samples: &[(f64, bool)], w: [f64; 2]) -> f64 {
+    let mut scored: Vec<(f64, bool)> = samples
+        .iter()
+        .map(|&(x, y)| (sigmoid(logit(x, w)), y))
+        .collect();
+    let pos = scored.iter().filter(|s| s.1).count();
+    let neg = scored.len() - pos;
+    if pos == 0 || neg == 0 {
+        return 0.5;
+    }
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut rank_sum = 0.0;
+    let mut i = 0;
+    while i < scored.len() {
+        let j = i + scored[i..]
+            .iter()
+            .take_while(|s| s.0 == scored[i].0)
+            .count();
+        // Ranks i+1 ..= j share their average.
+        let avg = (i + 1 + j) as f64 / 2.0;
+        rank_sum += avg * scored[i..j].iter().filter(|s| s.1).count() as f64;
+        i = j;
+    }
+    let pos = pos as f64;
+    (rank_sum - pos * (pos + 1.0) / 2.0) / (pos * neg as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use crate::workload::Pattern;
+
+    fn trace(pattern: Pattern, n: usize) -> Vec<IoEvent> {
+        pattern.workload(5).take(n).map(IoEvent::read).collect()
+    }
+
+    #[test]
+    fn test_fit_recovers_generating_parameters() {
+        // HFT ticks give E₃ the widest spread, so λ₃ and the bias are
+        // well separated.
+        let trace = trace(Pattern::HFT_TICK, 20_000);
+        let init = AetherLinkKernel::default().config();
+        // Draw labels from the kernel's own probability model with known
+        // λ₃ and bias; the fit must find them again.
+        let (lambda, bias) = (0.8, -0.7);
+        let mut rng = SplitMix64::new(9);
+        let mut labels = vec![false];
+        labels.extend(
+            features(
+                &trace,
+                &vec![false; trace.len()],
+                &init,
+                FitOptions::default().window,
+            )
+            .iter()
+            .map(|&(x, _)| rng.next_f64() < sigmoid(logit(x, [lambda, bias]))),
+        );
+        let result = fit(&trace, &labels, init, FitOptions::default());
+        assert!(result.loss < result.initial_loss);
+        assert!((result.config.bias as f64 - bias).abs() < 0.1, "{result:?}");
+        assert!(
+            (result.config.lambda[2] as f64 - lambda).abs() < 0.15,
+            "{result:?}"
+        );
+        assert!(result.auc > 0.5, "{result:?}");
+        assert_eq!(result.samples, trace.len() - 1);
+        result.config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_fit_constant_rate_sets_bias() {
+        // Labels independent of the trace and λ₃ held at 0: the optimum is
+        // p = 3/4, i.e. bias = −ln 3.
+        let trace = trace(Pattern::RANDOM, 4_000);
+        let labels: Vec<bool> = (0..trace.len()).map(|i| i % 4 != 0).collect();
+        let mut init = AetherLinkKernel::default().config();
+        init.lambda[2] = 0.0;
+        let opts = FitOptions {
+            fit_lambda: false,
+            ..FitOptions::default()
+        };
+        let result = fit(&trace, &labels, init, opts);
+        let expected = -(3.0_f64).ln();
+        assert!(
+            (result.config.bias as f64 - expected).abs() < 0.1,
+            "{result:?}"
+        );
+        assert_eq!(result.config.lambda[2], 0.0);
+        assert_eq!(result.config.epsilon, init.epsilon);
+    }
+
+    #[test]
+    fn test_auc_ranks_and_ties() {
+        let w = [1.0, 0.0];
+        // p falls as x grows, so positives at low x rank perfectly.
+        let perfect = [(-1.0, true), (-0.5, true), (0.5, false), (1.0, false)];
+        assert_eq!(auc(&perfect, w), 1.0);
+        let tied = [(0.0, true), (0.0, false)];
+        assert_eq!(auc(&tied, w), 0.5);
+        assert_eq!(auc(&[(0.0, true)], w), 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "one label per event")]
+    fn test_fit_rejects_label_mismatch() {
+        fit(
+            &trace(Pattern::RANDOM, 10),
+            &[true; 3],
+            KernelConfig::default(),
+            FitOptions::default(),
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Offline parameter search.
+//!
+//! Candidates are scored by replaying a trace through a fresh kernel with a
+//! simulated cache ([`eval::run_with`] + [`CacheSim`]) and applying a
+//! caller-supplied objective to the resulting [`EvalReport`].  Searches
+//! are deterministic for a given seed.  [`search`] enumerates a grid or
+//! samples at random; [`evolve()`] refines a population over generations,
+//! which scales better as the space grows.
+//!
+//! Every tuner fits the trace it is given; [`split()`] and [`k_fold`] hold
+//! part of it back, and [`holdout`] reports how far the results fall on
+//! the held-out part.
+//!
+//! [`sensitivity()`] shows which parameters of a config the objective
+//! actually depends on.
+//!
+//! When per-access labels are available, [`fit()`] calibrates λ₃ and the bias
+//! by gradient descent instead of searching.  [`OnlineTuner`] tunes λ₁/λ₂
+//! in production, from prefetch feedback, with a multi-armed bandit.
+
+pub mod evolve;
+pub mod fit;
+pub mod online;
+pub mod sensitivity;
+pub mod split;
+
+pub use evolve::{evolve, evolve_with, EvolveOptions};
+pub use fit::{fit, FitOptions, FitResult, Optimizer};
+pub use online::{ArmDecision, BanditPolicy, OnlineOptions, OnlineTuner};
+pub use sensitivity::{sensitivity, ParamSensitivity, Perturbation, SensitivityReport};
+pub use split::{holdout, k_fold, split, HoldoutScore, Split, TraceView};
+
+use crate::config::{KernelConfig, LAMBDA_RANGE};
+use crate::eval::{self, EvalReport};
+use crate::prefetcher::Prefetcher;
+use crate::replay::ReplayOptions;
+use crate::rng::SplitMix64;
+use crate::sim::CacheSim;
+#[cfg(doc)]
+use crate::AetherLinkKernel;
+
+/// Values a single parameter may take during a search.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamAxis {
+    /// Explicit candidate values (enumerated by grid search, sampled
+    /// uniformly by random search).
+    Values(Vec<f32>),
+    /// Inclusive range sampled uniformly by random search.
+    Range {
+        /// Lower bound.
+        min: f32,
+        /// Upper bound.
+        max: f32,
+    },
+}
+
+impl ParamAxis {
+    fn sample(&self, rng: &mut SplitMix64) -> f32 {
+        match self {
+            Self::Values(v) if v.is_empty() => 0.0,
+            Self::Values(v) => v[rng.below(v.len() as u64) as usize],
+            Self::Range { min, max } => rng.range_f32(*min, *max),
+        }
+    }
+
+    /// Smallest and largest value the axis allows.
+    fn bounds(&self) -> (f32, f32) {
+        match self {
+            Self::Values(v) if v.is_empty() => (0.0, 0.0),
+            Self::Values(v) => v
+                .iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &x| {
+                    (lo.min(x), hi.max(x))
+                }),
+            Self::Range { min, max } => (*min, *max),
+        }
+    }
+
+    fn values(&self) -> Option<&[f32]> {
+        match self {
+            Self::Values(v) => Some(v),
+            Self::Range { .. } => None,
+        }
+    }
+}
+
+/// Search space over the four kernel parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamSpace {
+    /// Initial threshold ε.
+    pub epsilon: ParamAxis,
+    /// Initial POVM basis φ.
+    pub phi: ParamAxis,
+    /// Adaptation coefficients λ₁..λ₃.
+    pub lambda: [ParamAxis; 3],
+    /// Sigmoid bias.
+    pub bias: ParamAxis,
+}
+
+impl ParamSpace {
+    /// Continuous ranges covering the recommended region of every
+    /// parameter — the space random search explores by default.
+    pub fn default_ranges() -> Self {
+        Self {
+            epsilon: ParamAxis::Range { min: 0.2, max: 0.8 },
+            phi: ParamAxis::Range { min: 0.0, max: 0.5 },
+            lambda: [
+                ParamAxis::Range {
+                    min: *LAMBDA_RANGE.start(),
+                    max: 0.3,
+                },
+                ParamAxis::Range {
+                    min: *LAMBDA_RANGE.start(),
+                    max: 0.4,
+                },
+                ParamAxis::Range {
+                    min: *LAMBDA_RANGE.start(),
+                    max: 0.6,
+                },
+            ],
+            bias: ParamAxis::Range {
+                min: -0.5,
+                max: 0.5,
+            },
+        }
+    }
+
+    /// A coarse grid over the same region (3⁶ = 729 points).
+    pub fn default_grid() -> Self {
+        Self {
+            epsilon: ParamAxis::Values(vec![0.3, 0.5, 0.7]),
+            phi: ParamAxis::Values(vec![0.0, 0.1, 0.3]),
+            lambda: [
+                ParamAxis::Values(vec![0.03, 0.1, 0.2]),
+                ParamAxis::Values(vec![0.05, 0.15, 0.3]),
+                ParamAxis::Values(vec![0.1, 0.3, 0.5]),
+            ],
+            bias: ParamAxis::Values(vec![-0.1, 0.0, 0.1]),
+        }
+    }
+
+    fn axes(&self) -> [&ParamAxis; 6] {
+        [
+            &self.epsilon,
+            &self.phi,
+            &self.lambda[0],
+            &self.lambda[1],
+            &self.lambda[2],
+            &self.bias,
+        ]
+    }
+
+    fn to_point(c: &KernelConfig) -> [f32; 6] {
+        [
+            c.epsilon,
+            c.phi,
+            c.lambda[0],
+            c.lambda[1],
+            c.lambda[2],
+            c.bias,
+        ]
+    }
+
+    fn from_point(p: [f32; 6]) -> KernelConfig {
+        KernelConfig {
+            epsilon: p[0],
+            phi: p[1],
+            lambda: [p[2], p[3], p[4]],
+            bias: p[5],
+        }
+    }
+
+    /// `true` if every axis is an explicit value list.
+    pub fn is_grid(&self) -> bool {
+        self.axes().iter().all(|a| a.values().is_some())
+    }
+
+    /// Up to `budget` candidates.
+    ///
+    /// A pure-grid space is enumerated in lexicographic order (first axis
+    /// slowest) until the budget runs out; any `Range` axis switches to
+    /// `budget` independent random draws.
+    pub fn candidates(&self, budget: usize, seed: u64) -> Vec<KernelConfig> {
+        let axes = self.axes();
+        let grid: Option<Vec<&[f32]>> = axes.iter().map(|a| a.values()).collect();
+        match grid {
+            Some(lists) if lists.iter().all(|l| !l.is_empty()) => {
+                let total = lists.iter().map(|l| l.len()).product::<usize>();
+                (0..total.min(budget))
+                    .map(|mut n| {
+                        let mut p = [0.0; 6];
+                        for (slot, list) in p.iter_mut().zip(&lists).rev() {
+                            *slot = list[n % list.len()];
+                            n /= list.len();
+                        }
+                        Self::from_point(p)
+                    })
+                    .collect()
+            }
+            _ => {
+                let mut rng = SplitMix64::new(seed);
+                (0..budget)
+                    .map(|_| {
+                        let mut p = [0.0; 6];
+                        for (slot, axis) in p.iter_mut().zip(axes) {
+                            *slot = axis.sample(&mut rng);
+                        }
+                        Self::from_point(p)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// How candidates are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalSetup {
+    /// Kernel history window (see [`AetherLinkKernel::set_window`]).
+    pub window: usize,
+    /// Replay prefetch size.
+    pub replay: ReplayOptions,
+    /// Simulated cache capacity in blocks.
+    pub cache_blocks: usize,
+}
+
+impl Default for EvalSetup {
+    fn default() -> Self {
+        Self {
+            window: crate::stream::DEFAULT_WINDOW,
+            replay: ReplayOptions::default(),
+            cache_blocks: 4096,
+        }
+    }
+}
+
+/// A candidate configuration together with its score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoredConfig {
+    /// The evaluated parameters.
+    pub config: KernelConfig,
+    /// Objective value (higher is better).
+    pub score: f64,
+    /// Evaluation the score was computed from.
+    pub report: EvalReport,
+}
+
+/// Replay `trace` through a kernel built from `config` with a fresh cache.
+///
+/// Invalid configs are reported as `None`.
+pub fn evaluate<'a>(
+    trace: impl Into<TraceView<'a>>,
+    config: &KernelConfig,
+    setup: &EvalSetup,
+) -> Option<EvalReport> {
+    let mut kernel = config.build().ok()?;
+    kernel.set_window(setup.window);
+    Some(evaluate_prefetcher(&mut kernel, trace, setup))
+}
+
+/// Replay `trace` through any [`Prefetcher`] with a fresh cache.
+///
+/// [`EvalSetup::window`] only applies to kernels built by [`evaluate`];
+/// `prefetcher` is used as given.
+pub fn evaluate_prefetcher<'a, P: Prefetcher + ?Sized>(
+    prefetcher: &mut P,
+    trace: impl Into<TraceView<'a>>,
+    setup: &EvalSetup,
+) -> EvalReport {
+    let mut cache = CacheSim::new(setup.cache_blocks);
+    eval::run_with(prefetcher, &mut cache, trace.into().iter(), &setup.replay)
+}
+
+/// Evaluate up to `budget` candidates from `space` and return them sorted by
+/// descending `objective`.
+///
+/// Candidates that fail validation are skipped.  Ties keep candidate order,
+/// so the result is fully determined by `seed`.  With the `parallel`
+/// feature candidates are evaluated on the rayon thread pool; the result is
+/// identical to a sequential run.
+pub fn search<'a, F>(
+    trace: impl Into<TraceView<'a>>,
+    objective: F,
+    space: &ParamSpace,
+    setup: &EvalSetup,
+    budget: usize,
+    seed: u64,
+) -> Vec<ScoredConfig>
+where
+    F: Fn(&EvalReport) -> f64 + Sync,
+{
+    let trace = trace.into();
+    let score = |config: KernelConfig| {
+        let report = evaluate(trace, &config, setup)?;
+        Some(ScoredConfig {
+            config,
+            score: objective(&report),
+            report,
+        })
+    };
+    let candidates = space.candidates(budget, seed);
+    #[cfg(feature = "parallel")]
+    let mut scored: Vec<ScoredConfig> = {
+        use rayon::prelude::*;
+        candidates.into_par_iter().filter_map(score).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let mut scored: Vec<ScoredConfig> = candidates.into_iter().filter_map(score).collect();
+    // Stable, so ties stay in candidate order.
+    scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+    scored
+}
+
+/// Demand hit rate of the simulated cache.
+pub fn hit_rate(report: &EvalReport) -> f64 {
+    report.hit_rate
+}
+
+/// Hit rate minus half the prefetched-blocks-per-access ratio — rewards
+/// configurations that earn their hits with less prefetch traffic.
+pub fn frugal_hit_rate(report: &EvalReport) -> f64 {
+    let c = &report.cache;
+    let accesses = (c.hits + c.misses).max(1) as f64;
+    report.hit_rate - 0.5 * c.prefetched as f64 / accesses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::IoEvent;
+
+    fn sequential_trace(n: u64) -> Vec<IoEvent> {
+        (0..n).map(IoEvent::read).collect()
+    }
+
+    #[test]
+    fn test_grid_enumeration_order() {
+        let space = ParamSpace {
+            epsilon: ParamAxis::Values(vec![0.3, 0.6]),
+            phi: ParamAxis::Values(vec![0.0]),
+            lambda: [
+                ParamAxis::Values(vec![0.1]),
+                ParamAxis::Values(vec![0.1]),
+                ParamAxis::Values(vec![0.1, 0.2]),
+            ],
+            bias: ParamAxis::Values(vec![0.0]),
+        };
+        assert!(space.is_grid());
+        let c = space.candidates(10, 0);
+        assert_eq!(c.len(), 4);
+        assert_eq!((c[0].epsilon, c[0].lambda[2]), (0.3, 0.1));
+        assert_eq!((c[1].epsilon, c[1].lambda[2]), (0.3, 0.2));
+        assert_eq!((c[3].epsilon, c[3].lambda[2]), (0.6, 0.2));
+        assert_eq!(space.candidates(3, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_random_search_is_seeded() {
+        let space = ParamSpace::default_ranges();
+        assert_eq!(space.candidates(20, 7), space.candidates(20, 7));
+        assert_ne!(space.candidates(20, 7), space.candidates(20, 8));
+        for c in space.candidates(200, 1) {
+            c.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_search_sorted_by_objective() {
+        let trace = sequential_trace(300);
+        let results = search(
+            &trace,
+            hit_rate,
+            &ParamSpace::default_ranges(),
+            &EvalSetup::default(),
+            12,
+            3,
+        );
+        assert_eq!(results.len(), 12);
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_search_beats_default_preset() {
+        // Stride-8 scan: the default preset never fires on it.
+        let trace: Vec<IoEvent> = (0..400).map(|i| IoEvent::read(i * 8)).collect();
+        let setup = EvalSetup {
+            cache_blocks: 256,
+            ..EvalSetup::default()
+        };
+        let default = evaluate(&trace, &KernelConfig::default(), &setup).unwrap();
+        let run = || search(&trace, hit_rate, &ParamSpace::default_grid(), &setup, 60, 0);
+        let results = run();
+        assert!(results[0].score > hit_rate(&default), "{:?}", results[0]);
+        assert_eq!(results, run(), "search is deterministic");
+    }
+
+    /// Candidates are scored in parallel but each replay is serial, so any
+    /// thread count gives byte-identical results.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_tuning_matches_serial() {
+        let trace: Vec<IoEvent> = crate::workload::Pattern::Bursty {
+            burst_len: 16,
+            jump: 64,
+        }
+        .workload(4)
+        .take(2_000)
+        .map(IoEvent::read)
+        .collect();
+        let setup = EvalSetup::default();
+        let opts = EvolveOptions {
+            parents: 4,
+            offspring: 8,
+            generations: 3,
+            seed: 11,
+            ..EvolveOptions::default()
+        };
+        let tune = || {
+            let searched = search(
+                &trace,
+                hit_rate,
+                &ParamSpace::default_ranges(),
+                &setup,
+                24,
+                5,
+            );
+            let evolved = evolve(&trace, hit_rate, &opts);
+            format!("{searched:?}\n{evolved:?}")
+        };
+        let on = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(tune)
+        };
+        let serial = on(1);
+        assert_eq!(on(4), serial);
+
+        // And identical to scoring the candidates one by one.
+        let mut expected: Vec<ScoredConfig> = ParamSpace::default_ranges()
+            .candidates(24, 5)
+            .into_iter()
+            .filter_map(|config| {
+                let report = evaluate(&trace, &config, &setup)?;
+                Some(ScoredConfig {
+                    config,
+                    score: hit_rate(&report),
+                    report,
+                })
+            })
+            .collect();
+        expected.sort_by(|a, b| b.score.total_cmp(&a.score));
+        assert!(serial.starts_with(&format!("{expected:?}\n")));
+    }
+}
@@ -0,0 +1,501 @@
+//! Online bandit tuning of the adaptation rates.
+//!
+//! When traces cannot be replayed offline, [`OnlineTuner`] runs `K`
+//! candidate (λ₁, λ₂) settings side by side on one kernel: every decision
+//! cycle is routed to one arm's rates (ε/φ and the DSP state stay shared),
+//! the caller reports whether each prefetch paid off, and a bandit policy
+//! shifts traffic towards the arm with the best recent [`Reward`].  Every
+//! `commit_every` cycles the current leader is committed and becomes the
+//! tuner's recommended [`config`](OnlineTuner::config).
+//!
+//! All state is fixed-size; the decision path does not allocate.
+
+use crate::config::KernelConfig;
+use crate::prefetcher::{PrefetchDecision, Prefetcher};
+use crate::rng::SplitMix64;
+use crate::threshold::Reward;
+use crate::AetherLinkKernel;
+
+/// Longest per-arm feedback window.
+pub const MAX_FEEDBACK_WINDOW: usize = 64;
+
+/// How the next arm is chosen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BanditPolicy {
+    /// Pick a uniformly random arm with probability `explore`, otherwise
+    /// the arm with the best windowed mean reward.
+    EpsilonGreedy {
+        /// Exploration probability in [0, 1].
+        explore: f32,
+    },
+    /// Upper confidence bound: windowed mean reward plus
+    /// `c·s·√(ln N / n)`, where `n` is the arm's and `N` all arms' feedback
+    /// in the window and `s` the span between the best and worst outcome.
+    Ucb {
+        /// Exploration weight.
+        c: f32,
+    },
+}
+
+impl Default for BanditPolicy {
+    fn default() -> Self {
+        Self::Ucb { c: 0.5 }
+    }
+}
+
+/// Knobs for [`OnlineTuner`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OnlineOptions {
+    /// Arm selection policy.
+    pub policy: BanditPolicy,
+    /// Outcomes per arm that count towards its score (clamped to
+    /// `1..=`[`MAX_FEEDBACK_WINDOW`]).
+    pub feedback_window: usize,
+    /// Decision cycles between commits.
+    pub commit_every: u64,
+    /// Seed for exploration.
+    pub seed: u64,
+    /// Value of each reported outcome.
+    pub reward: Reward,
+}
+
+impl Default for OnlineOptions {
+    fn default() -> Self {
+        Self {
+            policy: BanditPolicy::default(),
+            feedback_window: 32,
+            commit_every: 1024,
+            seed: 0,
+            reward: Reward::default(),
+        }
+    }
+}
+
+/// Sliding window of the last few outcome rewards.
+#[derive(Debug, Clone, Copy)]
+struct Outcomes {
+    ring: [f32; MAX_FEEDBACK_WINDOW],
+    head: u32,
+    len: u32,
+}
+
+impl Default for Outcomes {
+    fn default() -> Self {
+        Self {
+            ring: [0.0; MAX_FEEDBACK_WINDOW],
+            head: 0,
+            len: 0,
+        }
+    }
+}
+
+impl Outcomes {
+    #[inline]
+    fn push(&mut self, reward: f32, window: u32) {
+        self.ring[self.head as usize] = reward;
+        self.head = (self.head + 1) % window;
+        self.len = (self.len + 1).min(window);
+    }
+
+    /// Sum over the window.  Recomputed rather than kept running so
+    /// rounding cannot drift over a long run.
+    #[inline]
+    fn sum(&self) -> f32 {
+        self.ring[..self.len as usize].iter().sum()
+    }
+}
+
+/// One candidate setting and its recent feedback.
+#[derive(Debug, Clone, Copy)]
+pub struct Arm {
+    /// λ₁ and λ₂ used while this arm is active.
+    pub lambda: [f32; 2],
+    outcomes: Outcomes,
+    pulls: u64,
+}
+
+impl Arm {
+    fn new(lambda: [f32; 2]) -> Self {
+        Self {
+            lambda,
+            outcomes: Outcomes::default(),
+            pulls: 0,
+        }
+    }
+
+    /// Decision cycles routed to this arm.
+    #[inline]
+    pub fn pulls(&self) -> u64 {
+        self.pulls
+    }
+
+    /// Outcomes currently in the window.
+    #[inline]
+    pub fn feedback(&self) -> u32 {
+        self.outcomes.len
+    }
+
+    /// Mean reward of the windowed outcomes, `None` before any feedback.
+    pub fn mean_reward(&self) -> Option<f32> {
+        (self.outcomes.len > 0).then(|| self.outcomes.sum() / self.outcomes.len as f32)
+    }
+
+    /// Mean reward with two pseudo-outcomes worth `prior`, so arms without
+    /// feedback score `prior`.
+    #[inline]
+    fn smoothed(&self, prior: f32) -> f32 {
+        (self.outcomes.sum() + 2.0 * prior) / (self.outcomes.len as f32 + 2.0)
+    }
+}
+
+/// A decision together with the arm that made it.
+///
+/// `PartialEq` only, like the [`PrefetchDecision`] it holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArmDecision {
+    /// The kernel's decision.
+    pub decision: PrefetchDecision,
+    /// Arm to credit in [`OnlineTuner::record_prefetch_outcome`].
+    pub arm: usize,
+}
+
+/// Bandit tuner over `K` (λ₁, λ₂) arms sharing one kernel.
+///
+/// # Example
+///
+/// ```rust
+/// use aether_link::tune::online::{OnlineOptions, OnlineTuner};
+/// use aether_link::AetherLinkKernel;
+///
+/// let arms = [[0.05, 0.1], [0.1, 0.2], [0.2, 0.3]];
+/// let mut tuner = OnlineTuner::new(AetherLinkKernel::default(), arms, OnlineOptions::default());
+/// for lba in [10, 900, 20, 4000, 30] {
+///     if let Some(d) = tuner.push_lba(lba) {
+///         if d.decision.fire {
+///             // ... later, once the prefetched blocks were (not) used:
+///             tuner.record_prefetch_outcome(d.arm, true);
+///         }
+///     }
+/// }
+/// let _best = tuner.config();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OnlineTuner<const K: usize> {
+    kernel: AetherLinkKernel,
+    arms: [Arm; K],
+    opts: OnlineOptions,
+    window: u32,
+    committed: usize,
+    commits: u64,
+    since_commit: u64,
+    rng: SplitMix64,
+}
+
+impl<const K: usize> OnlineTuner<K> {
+    /// Tune `kernel` over the given (λ₁, λ₂) arms.  λ₃ and the other
+    /// parameters are kept; the first arm is committed until feedback says
+    /// otherwise.
+    ///
+    /// # Panics
+    ///
+    /// If `K` is 0.
+    pub fn new(kernel: AetherLinkKernel, lambdas: [[f32; 2]; K], opts: OnlineOptions) -> Self {
+        assert!(K > 0, "OnlineTuner needs at least one arm");
+        Self {
+            kernel,
+            arms: lambdas.map(Arm::new),
+            window: opts.feedback_window.clamp(1, MAX_FEEDBACK_WINDOW) as u32,
+            opts,
+            committed: 0,
+            commits: 0,
+            since_commit: 0,
+            rng: SplitMix64::new(opts.seed),
+        }
+    }
+
+    /// Feed one LBA, running the cycle with the selected arm's rates.
+    ///
+    /// Returns `None` during the kernel's warm-up (see
+    /// [`AetherLinkKernel::push_lba`]).
+    #[inline]
+    pub fn push_lba(&mut self, lba: u64) -> Option<ArmDecision> {
+        let arm = self.select();
+        let [l1, l2] = self.arms[arm].lambda;
+        self.kernel.lambda[0] = l1;
+        self.kernel.lambda[1] = l2;
+        let decision = self.kernel.push_lba(lba)?;
+        self.arms[arm].pulls += 1;
+        self.since_commit += 1;
+        if self.since_commit >= self.opts.commit_every {
+            self.commit();
+        }
+        Some(ArmDecision { decision, arm })
+    }
+
+    /// Report whether a prefetch issued by `arm` was used: worth
+    /// [`Reward::hit`] if so, `-`[`Reward::waste`] if not.
+    ///
+    /// # Panics
+    ///
+    /// If `arm >= K`.
+    #[inline]
+    pub fn record_prefetch_outcome(&mut self, arm: usize, useful: bool) {
+        let reward = self.opts.reward;
+        let r = if useful { reward.hit } else { -reward.waste };
+        self.arms[arm].outcomes.push(r, self.window);
+    }
+
+    /// Report a demand miss after `arm` declined to prefetch, worth
+    /// `-`[`Reward::missed`].
+    ///
+    /// # Panics
+    ///
+    /// If `arm >= K`.
+    #[inline]
+    pub fn record_missed_opportunity(&mut self, arm: usize) {
+        let r = -self.opts.reward.missed;
+        self.arms[arm].outcomes.push(r, self.window);
+    }
+
+    /// Report that `arm` prefetched `bytes`, worth
+    /// `-`[`Reward::per_byte`] per byte.  Ignored with free bandwidth, so
+    /// the window is not diluted with zeros.
+    ///
+    /// # Panics
+    ///
+    /// If `arm >= K`.
+    #[inline]
+    pub fn record_prefetch_issued(&mut self, arm: usize, bytes: u64) {
+        let per_byte = self.opts.reward.per_byte;
+        if per_byte > 0.0 {
+            self.arms[arm]
+                .outcomes
+                .push(-per_byte * bytes as f32, self.window);
+        }
+    }
+
+    /// Index of the committed arm.
+    #[inline]
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    /// Commits made so far.
+    #[inline]
+    pub fn commits(&self) -> u64 {
+        self.commits
+    }
+
+    /// Every arm with its statistics.
+    #[inline]
+    pub fn arms(&self) -> &[Arm; K] {
+        &self.arms
+    }
+
+    /// The shared kernel (its λ₁/λ₂ are those of the last active arm).
+    #[inline]
+    pub fn kernel(&self) -> &AetherLinkKernel {
+        &self.kernel
+    }
+
+    /// The kernel's current parameters with the committed arm's rates.
+    pub fn config(&self) -> KernelConfig {
+        let mut config = self.kernel.config();
+        let [l1, l2] = self.arms[self.committed].lambda;
+        config.lambda[0] = l1;
+        config.lambda[1] = l2;
+        config
+    }
+
+    /// Stop tuning and keep the kernel with the committed arm's rates.
+    pub fn into_kernel(mut self) -> AetherLinkKernel {
+        let [l1, l2] = self.arms[self.committed].lambda;
+        self.kernel.lambda[0] = l1;
+        self.kernel.lambda[1] = l2;
+        self.kernel
+    }
+
+    fn select(&mut self) -> usize {
+        if let Some(fresh) = self.arms.iter().position(|a| a.pulls == 0) {
+            return fresh;
+        }
+        match self.opts.policy {
+            BanditPolicy::EpsilonGreedy { explore } => {
+                if self.rng.next_f32() < explore {
+                    self.rng.below(K as u64) as usize
+                } else {
+                    self.leader()
+                }
+            }
+            BanditPolicy::Ucb { c } => {
+                let reward = self.opts.reward;
+                let prior = self.prior();
+                let scale = c * (reward.hit + reward.waste.max(reward.missed));
+                let total: u32 = self.arms.iter().map(|a| a.outcomes.len).sum();
+                let log_total = libm::logf(total as f32 + 1.0);
+                argmax(&self.arms, |a| {
+                    a.smoothed(prior)
+                        + scale * libm::sqrtf(log_total / (a.outcomes.len as f32 + 1.0))
+                })
+            }
+        }
+    }
+
+    /// Arm with the best smoothed mean reward; ties go to the lower index.
+    fn leader(&self) -> usize {
+        let prior = self.prior();
+        argmax(&self.arms, |a| a.smoothed(prior))
+    }
+
+    /// Midpoint between a hit and a wasted prefetch.
+    #[inline]
+    fn prior(&self) -> f32 {
+        0.5 * (self.opts.reward.hit - self.opts.reward.waste)
+    }
+
+    fn commit(&mut self) {
+        self.since_commit = 0;
+        self.commits += 1;
+        if self.arms.iter().any(|a| a.outcomes.len > 0) {
+            self.committed = self.leader();
+        }
+    }
+}
+
+fn argmax<const K: usize>(arms: &[Arm; K], score: impl Fn(&Arm) -> f32) -> usize {
+    let mut best = 0;
+    let mut best_score = f32::NEG_INFINITY;
+    for (i, arm) in arms.iter().enumerate() {
+        let s = score(arm);
+        if s > best_score {
+            best = i;
+            best_score = s;
+        }
+    }
+    best
+}
+
+impl<const K: usize> Prefetcher for OnlineTuner<K> {
+    /// Decisions only; use [`OnlineTuner::push_lba`] to learn which arm to
+    /// credit.
+    #[inline]
+    fn observe(&mut self, lba: u64) -> PrefetchDecision {
+        self.push_lba(lba).map(|d| d.decision).unwrap_or_default()
+    }
+
+    fn name(&self) -> &str {
+        "aether-online"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    /// Arm 2 is objectively best: its prefetches pay off 80% of the time.
+    const USEFUL: [f64; 4] = [0.2, 0.35, 0.8, 0.5];
+    const ARMS: [[f32; 2]; 4] = [[0.05, 0.1], [0.1, 0.2], [0.2, 0.05], [0.3, 0.3]];
+
+    fn run(policy: BanditPolicy, cycles: usize) -> OnlineTuner<4> {
+        let opts = OnlineOptions {
+            policy,
+            commit_every: 256,
+            seed: 1,
+            ..OnlineOptions::default()
+        };
+        let mut tuner = OnlineTuner::new(AetherLinkKernel::default(), ARMS, opts);
+        let mut env = SplitMix64::new(2);
+        for lba in Pattern::RANDOM.workload(3).take(cycles) {
+            if let Some(d) = tuner.push_lba(lba) {
+                if d.decision.fire {
+                    let useful = env.next_f64() < USEFUL[d.arm];
+                    tuner.record_prefetch_outcome(d.arm, useful);
+                }
+            }
+        }
+        tuner
+    }
+
+    #[test]
+    fn test_converges_to_best_arm() {
+        for policy in [
+            BanditPolicy::default(),
+            BanditPolicy::EpsilonGreedy { explore: 0.1 },
+        ] {
+            let tuner = run(policy, 4_000);
+            assert_eq!(tuner.committed(), 2, "{policy:?}: {:?}", tuner.arms());
+            let pulls: Vec<u64> = tuner.arms().iter().map(Arm::pulls).collect();
+            assert!(
+                pulls[2] > pulls.iter().sum::<u64>() / 2,
+                "{policy:?}: {pulls:?}"
+            );
+            let config = tuner.config();
+            assert_eq!([config.lambda[0], config.lambda[1]], ARMS[2]);
+        }
+    }
+
+    #[test]
+    fn test_feedback_window_slides() {
+        let mut o = Outcomes::default();
+        for _ in 0..10 {
+            o.push(1.0, 4);
+        }
+        assert_eq!((o.len, o.sum()), (4, 4.0));
+        o.push(-1.0, 4);
+        o.push(-1.0, 4);
+        assert_eq!(o.sum(), 0.0);
+        let mut wide = Outcomes::default();
+        for i in 0..100 {
+            wide.push(if i % 2 == 0 { 1.0 } else { 0.0 }, 64);
+        }
+        assert_eq!((wide.len, wide.sum()), (64, 32.0));
+    }
+
+    #[test]
+    fn test_reward_weights_rank_arms() {
+        // Arm 0 prefetches on every cycle and wastes a quarter; arm 1
+        // prefetches half as often, never wastes, and misses the rest.
+        // Frugal weights prefer arm 1, coverage weights arm 0.
+        let leader = |reward: Reward| {
+            let opts = OnlineOptions {
+                reward,
+                ..OnlineOptions::default()
+            };
+            let mut tuner = OnlineTuner::new(AetherLinkKernel::default(), [[0.1; 2]; 2], opts);
+            for i in 0..32 {
+                tuner.record_prefetch_outcome(0, i % 4 != 0);
+                tuner.record_prefetch_issued(0, 8 * 4096);
+                if i % 2 == 0 {
+                    tuner.record_prefetch_issued(1, 8 * 4096);
+                    tuner.record_prefetch_outcome(1, true);
+                } else {
+                    tuner.record_missed_opportunity(1);
+                }
+            }
+            tuner.leader()
+        };
+        assert_eq!(leader(Reward::coverage_first()), 0);
+        assert_eq!(leader(Reward::bandwidth_frugal()), 1);
+    }
+
+    #[test]
+    fn test_no_commit_without_feedback() {
+        let mut tuner = OnlineTuner::new(
+            AetherLinkKernel::default(),
+            ARMS,
+            OnlineOptions {
+                commit_every: 8,
+                ..OnlineOptions::default()
+            },
+        );
+        for lba in 0..100 {
+            tuner.push_lba(lba * 7);
+        }
+        assert!(tuner.commits() > 0);
+        assert_eq!(tuner.committed(), 0);
+        assert!(tuner.arms().iter().all(|a| a.pulls() > 0));
+        let kernel = tuner.into_kernel();
+        assert_eq!([kernel.lambda[0], kernel.lambda[1]], ARMS[0]);
+    }
+}
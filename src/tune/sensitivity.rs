@@ -0,0 +1,245 @@
+//! One-at-a-time sensitivity analysis.
+//!
+//! [`sensitivity`] perturbs each of the six kernel parameters of a base
+//! config by a list of relative deltas, holding the others fixed, replays
+//! the trace for every perturbation and reports how far the objective
+//! moves.  Parameters whose perturbations barely move it can be left at
+//! any reasonable value; the ones at the top of the report are the ones a
+//! deployment should get right.  [`SensitivityReport`] prints as a
+//! tornado table, largest swing first.
+
+use super::{evaluate, EvalSetup, ParamSpace, TraceView};
+use crate::config::{ConfigError, KernelConfig};
+use crate::eval::EvalReport;
+use core::fmt;
+
+/// Parameter names, in [`ParamSpace`] point order.
+const PARAMS: [&str; 6] = [
+    "epsilon",
+    "phi",
+    "lambda[0]",
+    "lambda[1]",
+    "lambda[2]",
+    "bias",
+];
+
+/// Width of the widest tornado bar.
+const BAR: usize = 24;
+
+/// The objective at one perturbed value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Perturbation {
+    /// Relative delta applied.
+    pub delta: f32,
+    /// Perturbed parameter value.
+    pub value: f32,
+    /// Objective minus the base objective; `None` if the perturbed config
+    /// is out of range.
+    pub change: Option<f64>,
+}
+
+/// Every perturbation of one parameter.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamSensitivity {
+    /// Parameter name, as in [`ConfigError`] (`"lambda[2]"`, …).
+    pub param: String,
+    /// Value in the base config.
+    pub base: f32,
+    /// One entry per delta, in the order given.
+    pub perturbations: Vec<Perturbation>,
+}
+
+impl ParamSensitivity {
+    /// Smallest and largest objective change over the valid perturbations.
+    pub fn range(&self) -> (f64, f64) {
+        self.perturbations
+            .iter()
+            .filter_map(|p| p.change)
+            .fold((0.0, 0.0), |(lo, hi), c| (lo.min(c), hi.max(c)))
+    }
+
+    /// Width of [`range`](Self::range): how much the parameter matters.
+    pub fn swing(&self) -> f64 {
+        let (lo, hi) = self.range();
+        hi - lo
+    }
+}
+
+/// Outcome of [`sensitivity`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensitivityReport {
+    /// The config every perturbation starts from.
+    pub base: KernelConfig,
+    /// Objective of the base config.
+    pub base_score: f64,
+    /// One entry per parameter, largest [`swing`](ParamSensitivity::swing)
+    /// first; ties keep parameter order.
+    pub params: Vec<ParamSensitivity>,
+}
+
+impl SensitivityReport {
+    /// The entry for `param` (`"bias"`, `"lambda[2]"`, …).
+    pub fn param(&self, param: &str) -> Option<&ParamSensitivity> {
+        self.params.iter().find(|p| p.param == param)
+    }
+}
+
+impl fmt::Display for SensitivityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "base objective {:.4}", self.base_score)?;
+        writeln!(
+            f,
+            "{:<10} {:>8} {:>9} {:>9}  swing",
+            "parameter", "base", "min Δ", "max Δ"
+        )?;
+        let widest = self.params.first().map_or(0.0, |p| p.swing());
+        for p in &self.params {
+            let (lo, hi) = p.range();
+            let bar = if widest > 0.0 {
+                (p.swing() / widest * BAR as f64).round() as usize
+            } else {
+                0
+            };
+            writeln!(
+                f,
+                "{:<10} {:>8.3} {:>+9.4} {:>+9.4}  {}",
+                p.param,
+                p.base,
+                lo,
+                hi,
+                "█".repeat(bar)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Perturb every parameter of `base` by each relative delta in turn
+/// (`value × (1 + delta)`, or `delta` itself for a parameter at zero) and
+/// report the change in `objective` over `trace`.
+///
+/// With the `parallel` feature the `6 × deltas.len()` replays run on the
+/// rayon thread pool; the report does not depend on it.
+pub fn sensitivity<'a, F>(
+    trace: impl Into<TraceView<'a>>,
+    base: &KernelConfig,
+    deltas: &[f32],
+    objective: F,
+    setup: &EvalSetup,
+) -> Result<SensitivityReport, ConfigError>
+where
+    F: Fn(&EvalReport) -> f64 + Sync,
+{
+    base.validate()?;
+    let trace = trace.into();
+    let score = |config: &KernelConfig| evaluate(trace, config, setup).map(|r| objective(&r));
+    let base_score = score(base).unwrap_or_default();
+
+    let origin = ParamSpace::to_point(base);
+    let jobs: Vec<(usize, f32, f32)> = (0..PARAMS.len())
+        .flat_map(|i| deltas.iter().map(move |&d| (i, d)))
+        .map(|(i, d)| {
+            let value = if origin[i] == 0.0 {
+                d
+            } else {
+                origin[i] * (1.0 + d)
+            };
+            (i, d, value)
+        })
+        .collect();
+    let run = |&(i, _, value): &(usize, f32, f32)| {
+        let mut point = origin;
+        point[i] = value;
+        score(&ParamSpace::from_point(point)).map(|s| s - base_score)
+    };
+    #[cfg(feature = "parallel")]
+    let changes: Vec<Option<f64>> = {
+        use rayon::prelude::*;
+        jobs.par_iter().map(run).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let changes: Vec<Option<f64>> = jobs.iter().map(run).collect();
+
+    let mut params: Vec<ParamSensitivity> = PARAMS
+        .iter()
+        .zip(origin)
+        .map(|(name, base)| ParamSensitivity {
+            param: (*name).to_owned(),
+            base,
+            perturbations: Vec::with_capacity(deltas.len()),
+        })
+        .collect();
+    for (&(i, delta, value), change) in jobs.iter().zip(changes) {
+        params[i].perturbations.push(Perturbation {
+            delta,
+            value,
+            change,
+        });
+    }
+    // Stable, so ties keep parameter order.
+    params.sort_by(|a, b| b.swing().total_cmp(&a.swing()));
+    Ok(SensitivityReport {
+        base: *base,
+        base_score,
+        params,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::IoEvent;
+    use crate::tune::hit_rate;
+    use crate::workload::Pattern;
+
+    const DELTAS: [f32; 4] = [-0.5, -0.2, 0.2, 0.5];
+
+    #[test]
+    fn test_fetch_shaping_parameters_dominate() {
+        let trace: Vec<IoEvent> = Pattern::Bursty {
+            burst_len: 16,
+            jump: 64,
+        }
+        .workload(2)
+        .take(4_000)
+        .map(IoEvent::read)
+        .collect();
+        let setup = EvalSetup {
+            cache_blocks: 512,
+            ..EvalSetup::default()
+        };
+        // λ₁ = 0 holds ε still, so the base sits near its decision
+        // boundary instead of drifting to the clamp.
+        let base = KernelConfig {
+            lambda: [0.0, 0.2, 0.3],
+            ..KernelConfig::default()
+        };
+        let report = sensitivity(&trace, &base, &DELTAS, hit_rate, &setup).unwrap();
+        assert_eq!(report.params.len(), 6);
+        assert!(report
+            .params
+            .windows(2)
+            .all(|w| w[0].swing() >= w[1].swing()));
+
+        let swing = |name| report.param(name).unwrap().swing();
+        for strong in ["lambda[2]", "bias"] {
+            assert!(
+                swing(strong) > 100.0 * swing("phi").max(1e-3),
+                "{strong} vs phi:\n{report}"
+            );
+        }
+        let text = report.to_string();
+        assert!(text.contains("lambda[2]") && text.contains('█'), "{text}");
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&report).unwrap();
+            assert!(json.contains(r#""param":"lambda[2]""#), "{json}");
+        }
+
+        let invalid = KernelConfig { bias: 9.0, ..base };
+        assert!(sensitivity(&trace, &invalid, &DELTAS, hit_rate, &setup).is_err());
+    }
+}
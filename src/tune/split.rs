@@ -0,0 +1,331 @@
+//! Train/test splits.
+//!
+//! Any tuner will happily fit the one trace it is given.  [`split`] divides
+//! a trace into a training and a test [`TraceView`] — by share of events,
+//! by capture time, or by holding out one phase — and [`k_fold`] rotates
+//! the test part through `k` equal folds for traces too small to give up a
+//! fixed share.  Views borrow the trace, so nothing is copied; one that
+//! wraps around a held-out part replays as a single stream with a seam.
+//!
+//! Tune on the training view, then [`holdout`] re-scores the results on
+//! the test view and flags every candidate whose objective drops by more
+//! than a chosen gap:
+//!
+//! ```rust
+//! use aether_link::trace::IoEvent;
+//! use aether_link::tune::{self, EvalSetup, ParamSpace, Split};
+//!
+//! let trace: Vec<IoEvent> = (0..2_000).map(|i| IoEvent::read(i * 8)).collect();
+//! let (train, test) = tune::split(&trace, &Split::Ratio(0.7));
+//! let setup = EvalSetup::default();
+//! let results = tune::search(train, tune::hit_rate, &ParamSpace::default_grid(), &setup, 20, 0);
+//! for r in tune::holdout(&results, test, tune::hit_rate, &setup, 0.05) {
+//!     assert!(!r.overfit, "{r:?}");
+//! }
+//! ```
+
+use super::{evaluate, EvalSetup, ScoredConfig};
+use crate::config::KernelConfig;
+use crate::eval::EvalReport;
+use crate::trace::IoEvent;
+use core::ops::Range;
+
+/// How [`split`] divides a trace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Split {
+    /// The first `ratio` of the events train, the rest test.  Clamped to
+    /// [0, 1].
+    Ratio(f64),
+    /// Events captured in the first `ratio` of the trace's time range
+    /// train, later ones test.  Timestamps must not decrease, as in any
+    /// trace read in capture order.
+    ByTime(f64),
+    /// Hold out one phase: `boundaries` are the index of every phase's
+    /// first event ([`Phased::boundaries`](crate::workload::Phased::boundaries)),
+    /// phase `test` tests and all others train.
+    ByPhase {
+        /// First event of every phase, ascending.
+        boundaries: Vec<u64>,
+        /// Index of the held-out phase.
+        test: usize,
+    },
+}
+
+/// Part of a trace: up to two ranges of it, replayed in order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceView<'a> {
+    trace: &'a [IoEvent],
+    /// `(start, end)` of both ranges; `Range` is not `Copy`.
+    head: (usize, usize),
+    tail: (usize, usize),
+}
+
+impl<'a> TraceView<'a> {
+    fn new(trace: &'a [IoEvent], head: Range<usize>, tail: Range<usize>) -> Self {
+        Self {
+            trace,
+            head: (head.start, head.end),
+            tail: (tail.start, tail.end),
+        }
+    }
+
+    /// Events in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let [head, tail] = self.ranges();
+        head.len() + tail.len()
+    }
+
+    /// `true` when the view holds no event.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index ranges of the underlying trace the view covers, in replay
+    /// order.  The second is empty unless the view wraps around a held-out
+    /// part.
+    #[inline]
+    pub fn ranges(&self) -> [Range<usize>; 2] {
+        [self.head.0..self.head.1, self.tail.0..self.tail.1]
+    }
+
+    /// The view's events in order.
+    pub fn iter(&self) -> impl Iterator<Item = IoEvent> + 'a {
+        let trace: &'a [IoEvent] = self.trace;
+        let [head, tail] = self.ranges();
+        trace[head].iter().chain(&trace[tail]).copied()
+    }
+}
+
+impl<'a> From<&'a [IoEvent]> for TraceView<'a> {
+    fn from(trace: &'a [IoEvent]) -> Self {
+        Self::new(trace, 0..trace.len(), 0..0)
+    }
+}
+
+impl<'a> From<&'a Vec<IoEvent>> for TraceView<'a> {
+    fn from(trace: &'a Vec<IoEvent>) -> Self {
+        trace.as_slice().into()
+    }
+}
+
+/// Divide `events` into non-overlapping `(train, test)` views that
+/// together cover every event once.
+pub fn split<'a>(events: &'a [IoEvent], by: &Split) -> (TraceView<'a>, TraceView<'a>) {
+    let n = events.len();
+    let at = |ratio: f64| (n as f64 * ratio.clamp(0.0, 1.0)) as usize;
+    let cut = match by {
+        Split::Ratio(ratio) => at(*ratio),
+        Split::ByTime(ratio) => match (events.first(), events.last()) {
+            (Some(first), Some(last)) => {
+                let span = last.timestamp_ns.saturating_sub(first.timestamp_ns) as f64;
+                let until = first.timestamp_ns + (span * ratio.clamp(0.0, 1.0)) as u64;
+                events.partition_point(|e| e.timestamp_ns < until)
+            }
+            _ => 0,
+        },
+        Split::ByPhase { boundaries, test } => {
+            let bound = |i: usize| boundaries.get(i).map_or(n, |&b| (b as usize).min(n));
+            let held = bound(*test)..bound(test + 1).max(bound(*test));
+            return (
+                TraceView::new(events, 0..held.start, held.end..n),
+                TraceView::new(events, held, 0..0),
+            );
+        }
+    };
+    (
+        TraceView::new(events, 0..cut, 0..0),
+        TraceView::new(events, cut..n, 0..0),
+    )
+}
+
+/// `(train, test)` views for k-fold cross-validation: fold `i` tests on the
+/// `i`-th of `k` contiguous, near-equal parts and trains on the rest.  `k`
+/// is at least 2.
+pub fn k_fold(
+    events: &[IoEvent],
+    k: usize,
+) -> impl Iterator<Item = (TraceView<'_>, TraceView<'_>)> {
+    let k = k.max(2);
+    let n = events.len();
+    (0..k).map(move |i| {
+        let test = i * n / k..(i + 1) * n / k;
+        (
+            TraceView::new(events, 0..test.start, test.end..n),
+            TraceView::new(events, test, 0..0),
+        )
+    })
+}
+
+/// A tuned config scored on both sides of a split.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HoldoutScore {
+    /// The evaluated parameters.
+    pub config: KernelConfig,
+    /// Objective on the training view, as the tuner reported it.
+    pub train: f64,
+    /// Objective on the test view.
+    pub test: f64,
+    /// `train − test` exceeded the allowed gap.
+    pub overfit: bool,
+    /// Evaluation on the test view.
+    pub report: EvalReport,
+}
+
+impl HoldoutScore {
+    /// How much better the config did on the data it was tuned on.
+    #[inline]
+    pub fn gap(&self) -> f64 {
+        self.train - self.test
+    }
+}
+
+/// Re-score tuner `results` on `test` and flag every config whose
+/// objective drops by more than `max_gap`.  Order is kept.
+pub fn holdout<'a, F>(
+    results: &[ScoredConfig],
+    test: impl Into<TraceView<'a>>,
+    objective: F,
+    setup: &EvalSetup,
+    max_gap: f64,
+) -> Vec<HoldoutScore>
+where
+    F: Fn(&EvalReport) -> f64,
+{
+    let test = test.into();
+    results
+        .iter()
+        .filter_map(|r| {
+            let report = evaluate(test, &r.config, setup)?;
+            let score = objective(&report);
+            Some(HoldoutScore {
+                config: r.config,
+                train: r.score,
+                test: score,
+                overfit: r.score - score > max_gap,
+                report,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tune::{hit_rate, search, ParamSpace};
+    use crate::workload::{Pattern, Phased};
+
+    /// Every index of a trace of `n` events is in exactly one view.
+    fn assert_partition(n: usize, (train, test): (TraceView<'_>, TraceView<'_>)) {
+        let mut seen = vec![0u8; n];
+        for range in train.ranges().into_iter().chain(test.ranges()) {
+            for i in range {
+                seen[i] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&s| s == 1), "{train:?} {test:?}");
+        assert_eq!(train.len() + test.len(), n);
+    }
+
+    #[test]
+    fn test_views_partition_the_trace() {
+        let trace: Vec<IoEvent> = (0..103).map(IoEvent::read).collect();
+        let (train, test) = split(&trace, &Split::Ratio(0.7));
+        assert_eq!((train.len(), test.len()), (72, 31));
+        assert_eq!(test.iter().next(), Some(IoEvent::read(72)));
+        for ratio in [-1.0, 0.0, 0.5, 1.0, 2.0] {
+            assert_partition(103, split(&trace, &Split::Ratio(ratio)));
+        }
+
+        let phases = Phased::new(0)
+            .then(Pattern::SEQUENTIAL, 40)
+            .then(Pattern::RANDOM, 30)
+            .then(Pattern::SEQUENTIAL, 33);
+        for held in 0..4 {
+            let by = Split::ByPhase {
+                boundaries: phases.boundaries(),
+                test: held,
+            };
+            assert_partition(103, split(&trace, &by));
+        }
+        let (train, test) = split(
+            &trace,
+            &Split::ByPhase {
+                boundaries: phases.boundaries(),
+                test: 1,
+            },
+        );
+        assert_eq!(test.ranges()[0], 40..70);
+        let lbas: Vec<u64> = train.iter().map(|e| e.lba).collect();
+        assert_eq!(lbas.len(), 73);
+        assert_eq!((lbas[39], lbas[40]), (39, 70));
+
+        let folds: Vec<_> = k_fold(&trace, 4).collect();
+        assert_eq!(folds.len(), 4);
+        let mut tested = 0;
+        for fold in folds {
+            tested += fold.1.len();
+            assert_partition(103, fold);
+        }
+        assert_eq!(tested, 103);
+    }
+
+    #[test]
+    fn test_time_split_respects_timestamps() {
+        // Bursty capture: 90 events in the first second, 10 over the next
+        // nine.
+        let trace: Vec<IoEvent> = (0..100u64)
+            .map(|i| {
+                let t = if i < 90 {
+                    i * 10_000_000
+                } else {
+                    (i - 89) * 1_000_000_000
+                };
+                IoEvent {
+                    timestamp_ns: t,
+                    ..IoEvent::read(i)
+                }
+            })
+            .collect();
+        let (train, test) = split(&trace, &Split::ByTime(0.5));
+        assert_partition(100, (train, test));
+        let cut = 5_000_000_000;
+        assert!(train.iter().all(|e| e.timestamp_ns < cut));
+        assert!(test.iter().all(|e| e.timestamp_ns >= cut));
+        assert_eq!(train.len(), 94);
+        assert_eq!(split(&trace, &Split::Ratio(0.5)).0.len(), 50);
+        assert_eq!(split(&[], &Split::ByTime(0.5)).0.len(), 0);
+    }
+
+    #[test]
+    fn test_holdout_flags_overfit() {
+        // Tuned on a sequential scan, tested on scattered reads: the winner
+        // learned nothing that carries over.
+        let trace: Vec<IoEvent> = Phased::new(3)
+            .then(Pattern::SEQUENTIAL, 600)
+            .then_at(Pattern::RANDOM, 400, 1 << 40)
+            .iter()
+            .map(IoEvent::read)
+            .collect();
+        let setup = EvalSetup {
+            cache_blocks: 256,
+            ..EvalSetup::default()
+        };
+        let space = ParamSpace::default_grid();
+        let (train, test) = split(&trace, &Split::Ratio(0.6));
+        let results = search(train, hit_rate, &space, &setup, 30, 0);
+        let scored = holdout(&results[..1], test, hit_rate, &setup, 0.1);
+        assert!(scored[0].overfit, "{:?}", scored[0]);
+        assert!(scored[0].gap() > 0.1);
+        assert_eq!(scored[0].train, results[0].score);
+
+        // The same scan on both sides generalises.
+        let scan: Vec<IoEvent> = (0..1000).map(IoEvent::read).collect();
+        let (train, test) = split(&scan, &Split::Ratio(0.6));
+        let results = search(train, hit_rate, &space, &setup, 30, 0);
+        let scored = holdout(&results[..1], test, hit_rate, &setup, 0.1);
+        assert!(!scored[0].overfit, "{:?}", scored[0]);
+    }
+}
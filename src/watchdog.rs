@@ -0,0 +1,265 @@
+//! Recovery for a kernel stuck on one answer.
+//!
+//! Open-loop ε can run into a clamp and stay there — pinned at 0.9 with
+//! no prefetch for hours, or at 0.1 prefetching everything.  With
+//! [`AetherLinkKernel::enable_watchdog`] the kernel counts how many cycles
+//! in a row gave the same decision.  Once that run reaches
+//! [`WatchdogOptions::stuck_cycles`] and the workload classifier reports
+//! the same class as halfway through the run (so the pattern itself has
+//! not changed), the kernel applies the configured [`Recovery`] and starts
+//! counting again.
+//!
+//! Per cycle this is one comparison and one increment; the classifier runs
+//! twice per `stuck_cycles`-long run at most.  Slice-driven kernels have no
+//! stream history and always classify as `Unknown`, which counts as "no
+//! change".
+
+use crate::classify::WorkloadClass;
+use crate::{threshold, AetherLinkKernel};
+
+/// What the watchdog does to a stuck kernel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Recovery {
+    /// Put ε back to its value when the watchdog was enabled.
+    ResetEpsilon,
+    /// Move ε to `margin` on the other side of the last fetch probability,
+    /// so the next cycle decides the other way and adaptation resumes from
+    /// there.
+    Recenter {
+        /// Distance from the fetch probability.
+        margin: f32,
+    },
+}
+
+/// Knobs for the watchdog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchdogOptions {
+    /// Identical decisions in a row that count as stuck.
+    pub stuck_cycles: u64,
+    /// Action taken.
+    pub recovery: Recovery,
+}
+
+impl Default for WatchdogOptions {
+    fn default() -> Self {
+        Self {
+            stuck_cycles: 100_000,
+            recovery: Recovery::ResetEpsilon,
+        }
+    }
+}
+
+/// One intervention.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecoveryEvent {
+    /// Kernel cycle at which it happened.
+    pub cycle: u64,
+    /// `true` if the kernel was stuck firing, `false` if stuck idle.
+    pub stuck_firing: bool,
+    /// Class the stuck run was observed under.
+    pub class: WorkloadClass,
+    /// ε before the recovery.
+    pub epsilon_before: f32,
+    /// ε after the recovery.
+    pub epsilon_after: f32,
+}
+
+/// Run-length state.
+#[derive(Debug, Clone)]
+pub(crate) struct Watchdog {
+    opts: WatchdogOptions,
+    initial_epsilon: f32,
+    last: bool,
+    run: u64,
+    /// Class seen halfway through the current run.
+    class: Option<WorkloadClass>,
+    recoveries: u64,
+    last_event: Option<RecoveryEvent>,
+}
+
+impl AetherLinkKernel {
+    /// Watch for runs of identical decisions.  Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::watchdog::{Recovery, WatchdogOptions};
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_watchdog(WatchdogOptions {
+    ///     stuck_cycles: 10_000,
+    ///     recovery: Recovery::Recenter { margin: 0.01 },
+    /// });
+    /// # let _ = kernel.watchdog_recoveries();
+    /// ```
+    pub fn enable_watchdog(&mut self, opts: WatchdogOptions) {
+        self.watchdog = Some(Watchdog {
+            opts,
+            initial_epsilon: self.epsilon,
+            last: false,
+            run: 0,
+            class: None,
+            recoveries: 0,
+            last_event: None,
+        });
+    }
+
+    /// Stop watching.
+    #[inline]
+    pub fn disable_watchdog(&mut self) {
+        self.watchdog = None;
+    }
+
+    /// Recoveries applied since the watchdog was enabled.
+    #[inline]
+    pub fn watchdog_recoveries(&self) -> u64 {
+        self.watchdog.as_ref().map_or(0, |w| w.recoveries)
+    }
+
+    /// The most recent recovery.  Poll it alongside
+    /// [`watchdog_recoveries`](Self::watchdog_recoveries) to log each one.
+    #[inline]
+    pub fn last_recovery(&self) -> Option<RecoveryEvent> {
+        self.watchdog.as_ref().and_then(|w| w.last_event)
+    }
+
+    /// Count the cycle's decision and recover if the run is long enough.
+    #[inline(always)]
+    pub(crate) fn watch(&mut self, fetch: bool, p_fetch: f32) {
+        let Some(w) = &mut self.watchdog else {
+            return;
+        };
+        if fetch != w.last {
+            w.last = fetch;
+            w.run = 0;
+            w.class = None;
+        }
+        w.run += 1;
+        let limit = w.opts.stuck_cycles.max(2);
+        if w.run == limit / 2 {
+            let class = self.classify();
+            if let Some(w) = &mut self.watchdog {
+                w.class = Some(class);
+            }
+        } else if w.run >= limit {
+            self.check_stuck(p_fetch);
+        }
+    }
+
+    #[cold]
+    fn check_stuck(&mut self, p_fetch: f32) {
+        let class = self.classify();
+        let cycle = self.cycles;
+        let epsilon_before = self.epsilon;
+        let Some(w) = &mut self.watchdog else {
+            return;
+        };
+        let limit = w.opts.stuck_cycles.max(2);
+        if w.class != Some(class) {
+            // The workload moved on; give the new pattern half a run.
+            w.class = Some(class);
+            w.run = limit / 2;
+            return;
+        }
+        let epsilon = match w.opts.recovery {
+            Recovery::ResetEpsilon => w.initial_epsilon,
+            Recovery::Recenter { margin } if w.last => p_fetch + margin,
+            Recovery::Recenter { margin } => p_fetch - margin,
+        };
+        self.epsilon = epsilon.clamp(threshold::EPSILON_CLAMP.0, threshold::EPSILON_CLAMP.1);
+        w.recoveries += 1;
+        w.run = 0;
+        w.class = None;
+        w.last_event = Some(RecoveryEvent {
+            cycle,
+            stuck_firing: w.last,
+            class,
+            epsilon_before,
+            epsilon_after: self.epsilon,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Pattern;
+
+    const STUCK: u64 = 500;
+
+    /// Stride 8 drives open-loop ε of the default parameters to the upper
+    /// clamp, where nothing fires.
+    fn run(kernel: &mut AetherLinkKernel, from: u64, n: u64) -> u64 {
+        Pattern::Sequential { stride: 8 }
+            .workload(0)
+            .starting_at(from * 8)
+            .take(n as usize)
+            .filter(|&lba| kernel.push_lba(lba).is_some_and(|d| d.fire))
+            .count() as u64
+    }
+
+    #[test]
+    fn test_pinned_kernel_is_recovered() {
+        let mut pinned = AetherLinkKernel::default();
+        assert_eq!(run(&mut pinned, 0, 5_000), 0, "precondition: stuck idle");
+        assert_eq!(pinned.epsilon, threshold::EPSILON_CLAMP.1);
+
+        for recovery in [Recovery::ResetEpsilon, Recovery::Recenter { margin: 0.05 }] {
+            let mut kernel = AetherLinkKernel::new(0.2, 0.1, [0.1, 0.2, 0.3], 0.05);
+            kernel.enable_watchdog(WatchdogOptions {
+                stuck_cycles: STUCK,
+                recovery,
+            });
+            let fired = run(&mut kernel, 0, 5_000);
+            assert!(kernel.watchdog_recoveries() > 0, "{recovery:?}");
+            let event = kernel.last_recovery().unwrap();
+            assert!(!event.stuck_firing);
+            assert_eq!(event.class, WorkloadClass::Strided);
+            assert!(event.epsilon_after < event.epsilon_before);
+            assert!(fired > 0, "{recovery:?}");
+
+            // Decisions resume right after each recovery.
+            let before = kernel.watchdog_recoveries();
+            let after = run(&mut kernel, 5_000, STUCK + 10);
+            assert!(kernel.watchdog_recoveries() > before);
+            assert!(after > 0, "{recovery:?}");
+        }
+    }
+
+    #[test]
+    fn test_off_by_default_and_class_change_postpones() {
+        let mut kernel = AetherLinkKernel::default();
+        run(&mut kernel, 0, 2_000);
+        assert_eq!(kernel.watchdog_recoveries(), 0);
+        assert!(kernel.last_recovery().is_none());
+
+        // Same window span, same (non-)decisions, different class.
+        let mut kernel = AetherLinkKernel {
+            epsilon: threshold::EPSILON_CLAMP.1,
+            ..AetherLinkKernel::default()
+        };
+        kernel.enable_watchdog(WatchdogOptions {
+            stuck_cycles: STUCK,
+            recovery: Recovery::Recenter { margin: 0.05 },
+        });
+        let fired = run(&mut kernel, 0, 400);
+        let mut lba = 400 * 8;
+        let mut alternate = |kernel: &mut AetherLinkKernel, n: u64| {
+            for i in 0..n {
+                lba += if i % 2 == 0 { 4 } else { 12 };
+                kernel.push_lba(lba);
+            }
+        };
+        alternate(&mut kernel, 200);
+        assert_eq!(fired, 0);
+        assert_eq!(kernel.classify(), WorkloadClass::Mixed);
+        assert_eq!(kernel.watchdog_recoveries(), 0, "postponed");
+        alternate(&mut kernel, 200);
+        assert_eq!(kernel.watchdog_recoveries(), 1);
+        assert_eq!(kernel.last_recovery().unwrap().class, WorkloadClass::Mixed);
+    }
+}
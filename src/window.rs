@@ -0,0 +1,242 @@
+//! History windows that size themselves.
+//!
+//! No fixed [`window`](AetherLinkKernel::set_window) suits every phase: a
+//! long one blurs a change of pattern for as many accesses as it holds, a
+//! short one is noisy on a pattern that has not changed in hours.  With
+//! [`AetherLinkKernel::enable_adaptive_window`] every streamed cycle
+//! compares the newest step (the last LBA minus the one before) with the
+//! mean step across the window.  The ratio of the smaller to the larger is
+//! the cycle's *fidelity*, 1 for a step like all the others; its moving
+//! average, over about the last four cycles, moves the window:
+//!
+//! - at or above [`grow_fidelity`](WindowOptions::grow_fidelity) the
+//!   window grows by one LBA per cycle, up to [`max`](WindowOptions::max);
+//! - below [`shrink_fidelity`](WindowOptions::shrink_fidelity) it halves
+//!   every cycle, down to [`min`](WindowOptions::min).
+//!
+//! A new stride or direction therefore cuts the window within a handful
+//! of cycles and the telemetry sees the new phase alone; a steady phase
+//! then grows it back one access at a time.  Scattered traffic, whose
+//! steps never resemble their mean, keeps the window at `min`.
+//!
+//! Only streamed input ([`push_lba`](AetherLinkKernel::push_lba) and the
+//! calls built on it) has a history window;
+//! [`process_io_cycle`](AetherLinkKernel::process_io_cycle) uses the slice
+//! it is given.
+
+use crate::stream::MAX_WINDOW;
+use crate::AetherLinkKernel;
+
+/// Weight of the newest cycle in the fidelity average.
+const ALPHA: f32 = 0.25;
+
+/// Bounds and thresholds of an adaptive window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowOptions {
+    /// Shortest window, clamped to `2..=`[`MAX_WINDOW`].
+    pub min: usize,
+    /// Longest window, clamped to `min..=`[`MAX_WINDOW`].
+    pub max: usize,
+    /// Average fidelity at or above which the window grows.
+    pub grow_fidelity: f32,
+    /// Average fidelity below which the window halves.
+    pub shrink_fidelity: f32,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            min: 4,
+            max: MAX_WINDOW,
+            grow_fidelity: 0.9,
+            shrink_fidelity: 0.5,
+        }
+    }
+}
+
+/// Options, the window to restore, and the fidelity average.
+#[derive(Debug, Clone)]
+pub(crate) struct AdaptiveWindow {
+    opts: WindowOptions,
+    fixed: usize,
+    fidelity: f32,
+}
+
+impl AetherLinkKernel {
+    /// Let the history window follow the stability of the pattern, within
+    /// `opts.min..=opts.max`, starting from the current window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::window::WindowOptions;
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_adaptive_window(WindowOptions::default());
+    /// for lba in 0..200u64 {
+    ///     kernel.push_lba(lba);
+    /// }
+    /// assert_eq!(kernel.effective_window(), 64);
+    /// assert_eq!(kernel.window(), 16);
+    /// ```
+    pub fn enable_adaptive_window(&mut self, opts: WindowOptions) {
+        let min = opts.min.clamp(2, MAX_WINDOW);
+        let opts = WindowOptions {
+            min,
+            max: opts.max.clamp(min, MAX_WINDOW),
+            ..opts
+        };
+        let fixed = self.window();
+        self.history.set_window(fixed.clamp(opts.min, opts.max));
+        self.adaptive_window = Some(AdaptiveWindow {
+            opts,
+            fixed,
+            fidelity: 1.0,
+        });
+    }
+
+    /// Go back to the fixed window set with
+    /// [`set_window`](Self::set_window).
+    #[inline]
+    pub fn disable_adaptive_window(&mut self) {
+        if let Some(adaptive) = self.adaptive_window.take() {
+            self.history.set_window(adaptive.fixed);
+        }
+    }
+
+    /// The adaptive window's options, bounds clamped, if it is on.
+    #[inline]
+    pub fn adaptive_window(&self) -> Option<WindowOptions> {
+        self.adaptive_window.as_ref().map(|a| a.opts)
+    }
+
+    /// LBAs the next streamed cycle may span: the adapted window when the
+    /// adaptive window is on, [`window`](Self::window) otherwise.
+    #[inline]
+    pub fn effective_window(&self) -> usize {
+        self.history.window()
+    }
+
+    /// With the adaptive window on, the fixed window to restore.
+    #[inline]
+    pub(crate) fn restored_window(&self) -> Option<usize> {
+        self.adaptive_window.as_ref().map(|a| a.fixed)
+    }
+
+    /// [`restored_window`](Self::restored_window), to change it.
+    #[inline]
+    pub(crate) fn fixed_window(&mut self) -> Option<&mut usize> {
+        self.adaptive_window.as_mut().map(|a| &mut a.fixed)
+    }
+
+    /// Resize the window after a streamed cycle.
+    #[inline(always)]
+    pub(crate) fn adapt_window(&mut self) {
+        let Some(adaptive) = &mut self.adaptive_window else {
+            return;
+        };
+        let len = self.history.len();
+        let (first, last) = self.history.span();
+        let prev = self.history.previous();
+        let mean = distance(first, last) / len.saturating_sub(1).max(1) as f32;
+        let step = distance(prev, last);
+        let (lo, hi) = (step.min(mean), step.max(mean));
+        let fidelity = if hi > 0.0 { lo / hi } else { 1.0 };
+        adaptive.fidelity += ALPHA * (fidelity - adaptive.fidelity);
+
+        let opts = adaptive.opts;
+        let window = self.history.window();
+        if adaptive.fidelity < opts.shrink_fidelity {
+            self.history.set_window((window / 2).max(opts.min));
+        } else if adaptive.fidelity >= opts.grow_fidelity {
+            self.history.set_window((window + 1).min(opts.max));
+        }
+    }
+}
+
+/// `|b − a|` in blocks.
+#[inline(always)]
+fn distance(a: u64, b: u64) -> f32 {
+    (b.wrapping_sub(a) as i64).unsigned_abs() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter::AetherIteratorExt;
+
+    /// Windows seen on `lbas`, one per push.
+    fn windows(kernel: &mut AetherLinkKernel, lbas: impl Iterator<Item = u64>) -> Vec<usize> {
+        lbas.map(|lba| {
+            kernel.push_lba(lba);
+            kernel.effective_window()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_shrinks_on_change_and_regrows() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.enable_adaptive_window(WindowOptions::default());
+        let grown = windows(&mut kernel, 0..500);
+        assert_eq!(*grown.last().unwrap(), MAX_WINDOW);
+
+        // Phase change: stride 8 from far away.
+        let start = 1 << 30;
+        let after = windows(&mut kernel, (0..500).map(|i| start + i * 8));
+        let first_small = after.iter().position(|&w| w <= MAX_WINDOW / 4).unwrap();
+        assert!(first_small <= 6, "{:?}", &after[..16]);
+        let low = after.iter().copied().min().unwrap();
+        assert!(low <= 8, "{low}");
+        // The new phase is steady, so the window grows back to the top.
+        let regrown = after.iter().rposition(|&w| w < MAX_WINDOW).unwrap();
+        assert!(regrown < 100, "{regrown}");
+        assert_eq!(*after.last().unwrap(), MAX_WINDOW);
+
+        // A reversal is a change too, and reports carry the window each
+        // cycle ran with.
+        let back: Vec<usize> = (0..20)
+            .map(|i| start - i)
+            .decisions_detailed(&mut kernel)
+            .map(|report| report.window)
+            .collect();
+        assert_eq!(back[0], MAX_WINDOW);
+        assert!(back.iter().any(|&w| w <= MAX_WINDOW / 4), "{back:?}");
+    }
+
+    #[test]
+    fn test_bounds_and_disable() {
+        let mut kernel = AetherLinkKernel::default();
+        kernel.set_window(10);
+        kernel.enable_adaptive_window(WindowOptions {
+            min: 0,
+            max: 24,
+            ..WindowOptions::default()
+        });
+        let opts = kernel.adaptive_window().unwrap();
+        assert_eq!((opts.min, opts.max), (2, 24));
+
+        // Scattered steps pin the window to its minimum.
+        let mut x = 1u64;
+        let scattered = windows(
+            &mut kernel,
+            (0..300).map(|_| {
+                x = x.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                x >> 20
+            }),
+        );
+        assert!(scattered[100..].iter().all(|&w| w <= 4), "{scattered:?}");
+        let steady = windows(&mut kernel, 0..300);
+        assert!(steady.iter().all(|&w| w <= 24));
+        assert_eq!(*steady.last().unwrap(), 24);
+
+        // `set_window` while adaptive sets the window to come back to.
+        kernel.set_window(12);
+        assert_eq!((kernel.window(), kernel.effective_window()), (12, 24));
+        kernel.disable_adaptive_window();
+        assert_eq!((kernel.window(), kernel.effective_window()), (12, 12));
+        assert_eq!(kernel.adaptive_window(), None);
+    }
+}
@@ -0,0 +1,354 @@
+//! Synthetic LBA workloads.
+//!
+//! A [`Pattern`] describes the shape of an access stream with explicit
+//! parameters; [`Pattern::workload`] turns it into a [`Workload`], an
+//! endless `Iterator<Item = u64>` that is fully determined by its seed, so
+//! traces can be streamed (`.take(n)`) without materialising them and
+//! regenerated bit-for-bit in tests.
+//!
+//! Skewed popularity distributions live in [`skewed`]; [`Phased`] chains
+//! patterns into a workload that changes character mid-trace.
+
+pub mod phased;
+pub mod skewed;
+
+pub use phased::{Phased, PhasedWorkload, Segment};
+pub use skewed::{Hotspot, Zipfian};
+
+use crate::rng::SplitMix64;
+use core::fmt;
+use core::str::FromStr;
+use skewed::ZipfSampler;
+
+/// Shape of a synthetic access stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// `start, start + stride, start + 2·stride, …`.
+    Sequential {
+        /// Distance between consecutive LBAs (1 for a plain scan).
+        stride: u64,
+    },
+    /// Uniform over `[start, start + span)`.
+    Random {
+        /// Width of the address range.
+        span: u64,
+    },
+    /// Sequential runs of `burst_len` blocks, each followed by a forward
+    /// jump of `jump` blocks.
+    Bursty {
+        /// Accesses per run.
+        burst_len: u64,
+        /// Gap between runs.
+        jump: u64,
+    },
+    /// Market-data style: short sequential runs that skip ahead by `jump`
+    /// blocks (a cache-line-sized hop) every `run_len` ticks.
+    HftTick {
+        /// Ticks per run.
+        run_len: u64,
+        /// Skip at the end of each run.
+        jump: u64,
+    },
+    /// Zipf-distributed block popularity; see [`Zipfian`].
+    Zipfian {
+        /// Number of distinct blocks.
+        n_blocks: u64,
+        /// Skew exponent (≥ 0).
+        theta: f64,
+    },
+    /// Hot/cold split; see [`Hotspot`].
+    Hotspot {
+        /// Number of distinct blocks.
+        n_blocks: u64,
+        /// Share of blocks that are hot.
+        hot_fraction: f64,
+        /// Share of accesses that go to hot blocks.
+        hot_access_fraction: f64,
+    },
+}
+
+impl Pattern {
+    /// Plain sequential scan.
+    pub const SEQUENTIAL: Pattern = Pattern::Sequential { stride: 1 };
+    /// Uniform random over 100 000 blocks.
+    pub const RANDOM: Pattern = Pattern::Random { span: 100_000 };
+    /// Runs of 5 separated by 1000-block jumps.
+    pub const BURSTY: Pattern = Pattern::Bursty {
+        burst_len: 5,
+        jump: 1000,
+    };
+    /// Runs of 10 separated by 64-block hops.
+    pub const HFT_TICK: Pattern = Pattern::HftTick {
+        run_len: 10,
+        jump: 64,
+    };
+
+    /// Human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Sequential { stride: 1 } => "Sequential",
+            Pattern::Sequential { .. } => "Strided",
+            Pattern::Random { .. } => "Random",
+            Pattern::Bursty { .. } => "Bursty",
+            Pattern::HftTick { .. } => "HFT Tick",
+            Pattern::Zipfian { .. } => "Zipfian",
+            Pattern::Hotspot { .. } => "Hotspot",
+        }
+    }
+
+    /// Endless stream of this pattern, deterministic for `seed`.
+    pub fn workload(self, seed: u64) -> Workload {
+        let zipf = match self {
+            Pattern::Zipfian { n_blocks, theta } => Some(ZipfSampler::new(n_blocks, theta)),
+            _ => None,
+        };
+        Workload {
+            pattern: self,
+            rng: SplitMix64::new(seed),
+            zipf,
+            start: 0,
+            pos: 0,
+            i: 0,
+        }
+    }
+}
+
+/// Parses `name[:arg…]` specs, each argument optional from the right:
+///
+/// | Spec | Pattern |
+/// |------|---------|
+/// | `sequential[:stride]` | [`Pattern::Sequential`] (stride 1) |
+/// | `random[:span]` | [`Pattern::Random`] (100 000) |
+/// | `bursty[:burst_len[:jump]]` | [`Pattern::Bursty`] (5, 1000) |
+/// | `hft-tick[:run_len[:jump]]` | [`Pattern::HftTick`] (10, 64) |
+/// | `zipf[:n_blocks[:theta]]` | [`Pattern::Zipfian`] (1 000 000, 0.99) |
+/// | `hotspot[:n_blocks[:hot_fraction[:hot_access_fraction]]]` | [`Pattern::Hotspot`] (1 000 000, 0.1, 0.9) |
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let args: Vec<&str> = parts.collect();
+        fn arg<T: FromStr>(args: &[&str], i: usize, default: T) -> Result<T, String> {
+            match args.get(i) {
+                None => Ok(default),
+                Some(raw) => raw
+                    .parse()
+                    .map_err(|_| format!("invalid pattern argument '{raw}'")),
+            }
+        }
+        let (pattern, max_args) = match name.as_str() {
+            "sequential" => (
+                Pattern::Sequential {
+                    stride: arg(&args, 0, 1)?,
+                },
+                1,
+            ),
+            "random" => (
+                Pattern::Random {
+                    span: arg(&args, 0, 100_000)?,
+                },
+                1,
+            ),
+            "bursty" => (
+                Pattern::Bursty {
+                    burst_len: arg(&args, 0, 5)?,
+                    jump: arg(&args, 1, 1000)?,
+                },
+                2,
+            ),
+            "hft-tick" => (
+                Pattern::HftTick {
+                    run_len: arg(&args, 0, 10)?,
+                    jump: arg(&args, 1, 64)?,
+                },
+                2,
+            ),
+            "zipf" => (
+                Pattern::Zipfian {
+                    n_blocks: arg(&args, 0, 1_000_000)?,
+                    theta: arg(&args, 1, 0.99)?,
+                },
+                2,
+            ),
+            "hotspot" => (
+                Pattern::Hotspot {
+                    n_blocks: arg(&args, 0, 1_000_000)?,
+                    hot_fraction: arg(&args, 1, 0.1)?,
+                    hot_access_fraction: arg(&args, 2, 0.9)?,
+                },
+                3,
+            ),
+            _ => {
+                return Err(format!(
+                    "unknown pattern '{s}' (expected sequential, random, bursty, \
+                     hft-tick, zipf or hotspot)"
+                ))
+            }
+        };
+        if args.len() > max_args {
+            return Err(format!("too many arguments in pattern '{s}'"));
+        }
+        Ok(pattern)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Iterator over the LBAs of a [`Pattern`].  Never ends; use `take`.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    pattern: Pattern,
+    rng: SplitMix64,
+    zipf: Option<ZipfSampler>,
+    start: u64,
+    /// Next LBA of a walking pattern, relative to `start`.
+    pos: u64,
+    /// Accesses generated so far.
+    i: u64,
+}
+
+impl Workload {
+    /// Offset every generated LBA by `start`.
+    pub fn starting_at(mut self, start: u64) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// The pattern being generated.
+    #[inline]
+    pub fn pattern(&self) -> Pattern {
+        self.pattern
+    }
+}
+
+impl Iterator for Workload {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        let i = self.i;
+        self.i += 1;
+        let offset = match self.pattern {
+            Pattern::Sequential { stride } => {
+                let lba = self.pos;
+                self.pos = self.pos.wrapping_add(stride);
+                lba
+            }
+            Pattern::Random { span } => self.rng.below(span),
+            Pattern::Bursty {
+                burst_len: run,
+                jump,
+            }
+            | Pattern::HftTick { run_len: run, jump } => {
+                if i > 0 && i % run.max(1) == 0 {
+                    self.pos = self.pos.wrapping_add(jump);
+                }
+                let lba = self.pos;
+                self.pos = self.pos.wrapping_add(1);
+                lba
+            }
+            Pattern::Zipfian { .. } => {
+                let rng = &mut self.rng;
+                match &self.zipf {
+                    Some(z) => z.sample(|| rng.next_f64()) - 1,
+                    None => 0,
+                }
+            }
+            Pattern::Hotspot {
+                n_blocks,
+                hot_fraction,
+                hot_access_fraction,
+            } => {
+                let n = n_blocks.max(1);
+                let hot = ((n as f64 * hot_fraction.clamp(0.0, 1.0)) as u64).clamp(1, n);
+                if hot == n || self.rng.next_f64() < hot_access_fraction {
+                    self.rng.below(hot)
+                } else {
+                    hot + self.rng.below(n - hot)
+                }
+            }
+        };
+        Some(self.start.wrapping_add(offset))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_follow_parameters() {
+        let take = |p: Pattern, n| p.workload(0).starting_at(100).take(n).collect::<Vec<_>>();
+        assert_eq!(take(Pattern::SEQUENTIAL, 4), [100, 101, 102, 103]);
+        assert_eq!(
+            take(Pattern::Sequential { stride: 4 }, 4),
+            [100, 104, 108, 112]
+        );
+        assert_eq!(
+            take(
+                Pattern::Bursty {
+                    burst_len: 3,
+                    jump: 10
+                },
+                7
+            ),
+            [100, 101, 102, 113, 114, 115, 126]
+        );
+        assert_eq!(take(Pattern::HFT_TICK, 12)[10], 100 + 10 + 64);
+        assert!(take(Pattern::Random { span: 50 }, 1000)
+            .iter()
+            .all(|l| (100..150).contains(l)));
+        assert_eq!(Pattern::Sequential { stride: 4 }.name(), "Strided");
+    }
+
+    #[test]
+    fn test_parse_specs() {
+        assert_eq!("sequential".parse(), Ok(Pattern::SEQUENTIAL));
+        assert_eq!(
+            "Bursty:8".parse(),
+            Ok(Pattern::Bursty {
+                burst_len: 8,
+                jump: 1000
+            })
+        );
+        assert_eq!(
+            "zipf:5000:1.1".parse(),
+            Ok(Pattern::Zipfian {
+                n_blocks: 5000,
+                theta: 1.1
+            })
+        );
+        assert!(matches!(
+            "hotspot::0.2".parse::<Pattern>(),
+            Err(e) if e.contains("invalid")
+        ));
+        assert!("random:1:2".parse::<Pattern>().is_err());
+        assert!("mystery".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn test_deterministic_for_seed() {
+        let a: Vec<u64> = Pattern::RANDOM.workload(7).take(500).collect();
+        let b: Vec<u64> = Pattern::RANDOM.workload(7).take(500).collect();
+        let c: Vec<u64> = Pattern::RANDOM.workload(8).take(500).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Streaming in chunks matches one long run.
+        let mut w = Pattern::RANDOM.workload(7);
+        let chunked: Vec<u64> = (0..5)
+            .flat_map(|_| w.by_ref().take(100).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(chunked, a);
+    }
+}
@@ -0,0 +1,227 @@
+//! Workloads that change character mid-trace.
+
+use super::{Pattern, Workload};
+use crate::rng::SplitMix64;
+
+/// One phase of a [`Phased`] workload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    /// Access pattern of the phase.
+    pub pattern: Pattern,
+    /// Accesses in the phase.
+    pub len: u64,
+    /// Offset added to the pattern's LBAs.
+    pub start: u64,
+}
+
+/// A sequence of [`Pattern`] segments, e.g. 50k sequential, then 20k
+/// random, then 30k strided.
+///
+/// With a ramp of `r` accesses, the first `r` accesses of every segment
+/// after the first blend in the previous pattern: access `j` of the ramp
+/// comes from the new pattern with probability `(j + 1) / (r + 1)`, so the
+/// switch happens gradually instead of at a hard edge.  Each segment's
+/// pattern keeps its own generator, so a blended-in previous pattern
+/// continues where it left off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phased {
+    segments: Vec<Segment>,
+    ramp: u64,
+    seed: u64,
+}
+
+impl Phased {
+    /// Empty workload; add segments with [`then`](Self::then).
+    pub fn new(seed: u64) -> Self {
+        Self {
+            segments: Vec::new(),
+            ramp: 0,
+            seed,
+        }
+    }
+
+    /// Append `len` accesses of `pattern`.
+    pub fn then(self, pattern: Pattern, len: u64) -> Self {
+        self.then_at(pattern, len, 0)
+    }
+
+    /// Append `len` accesses of `pattern`, offset by `start`.
+    pub fn then_at(mut self, pattern: Pattern, len: u64, start: u64) -> Self {
+        self.segments.push(Segment {
+            pattern,
+            len,
+            start,
+        });
+        self
+    }
+
+    /// Blend consecutive segments over `ramp` accesses (0 = hard switch).
+    pub fn with_ramp(mut self, ramp: u64) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    /// The segments in order.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Index of the first access of every segment.
+    pub fn boundaries(&self) -> Vec<u64> {
+        self.segments
+            .iter()
+            .scan(0, |at, s| {
+                let start = *at;
+                *at += s.len;
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Total number of accesses.
+    pub fn len(&self) -> u64 {
+        self.segments.iter().map(|s| s.len).sum()
+    }
+
+    /// `true` when no segment has any access.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stream the accesses, deterministic for the seed.
+    pub fn iter(&self) -> PhasedWorkload {
+        let mut seeds = SplitMix64::new(self.seed);
+        let generators = self
+            .segments
+            .iter()
+            .map(|s| s.pattern.workload(seeds.next_u64()).starting_at(s.start))
+            .collect();
+        PhasedWorkload {
+            lens: self.segments.iter().map(|s| s.len).collect(),
+            generators,
+            ramp: self.ramp,
+            blend: SplitMix64::new(seeds.next_u64()),
+            segment: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl IntoIterator for &Phased {
+    type Item = u64;
+    type IntoIter = PhasedWorkload;
+
+    fn into_iter(self) -> PhasedWorkload {
+        self.iter()
+    }
+}
+
+/// Iterator over a [`Phased`] workload.
+#[derive(Debug, Clone)]
+pub struct PhasedWorkload {
+    lens: Vec<u64>,
+    generators: Vec<Workload>,
+    ramp: u64,
+    blend: SplitMix64,
+    segment: usize,
+    /// Accesses already produced in the current segment.
+    pos: u64,
+}
+
+impl PhasedWorkload {
+    /// Index of the segment the next access belongs to.
+    #[inline]
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+}
+
+impl Iterator for PhasedWorkload {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while self.pos >= *self.lens.get(self.segment)? {
+            self.segment += 1;
+            self.pos = 0;
+        }
+        let j = self.pos;
+        self.pos += 1;
+        let source = if self.segment > 0 && j < self.ramp {
+            let p_new = (j + 1) as f64 / (self.ramp + 1) as f64;
+            if self.blend.next_f64() < p_new {
+                self.segment
+            } else {
+                self.segment - 1
+            }
+        } else {
+            self.segment
+        };
+        self.generators[source].next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self
+            .lens
+            .iter()
+            .skip(self.segment)
+            .sum::<u64>()
+            .saturating_sub(self.pos) as usize;
+        (left, Some(left))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three_phase(ramp: u64) -> Phased {
+        Phased::new(42)
+            .then(Pattern::SEQUENTIAL, 500)
+            .then_at(Pattern::Random { span: 1000 }, 200, 1_000_000)
+            .then_at(Pattern::Sequential { stride: 4 }, 300, 5_000_000)
+            .with_ramp(ramp)
+    }
+
+    #[test]
+    fn test_hard_switch_segments() {
+        let w = three_phase(0);
+        assert_eq!(w.boundaries(), [0, 500, 700]);
+        let lbas: Vec<u64> = w.iter().collect();
+        assert_eq!(lbas.len(), 1000);
+        assert_eq!(lbas[..500], (0..500).collect::<Vec<_>>()[..]);
+        assert!(lbas[500..700]
+            .iter()
+            .all(|l| (1_000_000..1_001_000).contains(l)));
+        assert_eq!(lbas[700], 5_000_000);
+        assert_eq!(lbas[999], 5_000_000 + 299 * 4);
+        assert_eq!(lbas, w.iter().collect::<Vec<_>>(), "deterministic");
+    }
+
+    #[test]
+    fn test_ramp_blends_gradually() {
+        let w = three_phase(100);
+        let lbas: Vec<u64> = w.iter().collect();
+        assert_eq!(lbas.len(), 1000);
+        let old = |r: std::ops::Range<usize>| lbas[r].iter().filter(|&&l| l < 1_000_000).count();
+        // Early in the ramp mostly old pattern, late mostly new.
+        assert!(
+            old(500..525) > old(575..600),
+            "{} vs {}",
+            old(500..525),
+            old(575..600)
+        );
+        assert!(old(500..600) > 20 && old(500..600) < 80);
+        assert_eq!(old(600..700), 0);
+        // The blended-in sequential scan continues where it stopped.
+        let seq: Vec<u64> = lbas[500..600]
+            .iter()
+            .copied()
+            .filter(|&l| l < 1_000_000)
+            .collect();
+        assert!(seq.windows(2).all(|p| p[1] == p[0] + 1) && seq[0] == 500);
+        let mut it = w.iter();
+        assert_eq!(it.size_hint(), (1000, Some(1000)));
+        it.nth(599);
+        assert_eq!((it.segment(), it.size_hint().0), (1, 400));
+    }
+}
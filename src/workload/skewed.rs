@@ -0,0 +1,247 @@
+//! Skewed-popularity workloads.
+//!
+//! Real storage traces are dominated by a small hot set.  [`Zipfian`] draws
+//! block ranks from a Zipf distribution; [`Hotspot`] splits the address
+//! range into a hot and a cold region with fixed access shares.  Rank `r`
+//! (1-based) maps to LBA `r - 1`, so the hottest blocks are the lowest
+//! addresses; offset with [`Workload::starting_at`].
+
+use super::{Pattern, Workload};
+
+/// Zipf-distributed accesses over `n_blocks` blocks.
+///
+/// `P(rank = k) ∝ k^-theta`: `theta = 0` is uniform, `0.99` is the YCSB
+/// default, larger values concentrate further.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zipfian {
+    /// Number of distinct blocks (at least 1).
+    pub n_blocks: u64,
+    /// Skew exponent (≥ 0).
+    pub theta: f64,
+    /// PRNG seed.
+    pub seed: u64,
+}
+
+impl Zipfian {
+    /// The stream of this distribution.
+    pub fn workload(self) -> Workload {
+        Pattern::Zipfian {
+            n_blocks: self.n_blocks,
+            theta: self.theta,
+        }
+        .workload(self.seed)
+    }
+}
+
+impl IntoIterator for Zipfian {
+    type Item = u64;
+    type IntoIter = Workload;
+
+    fn into_iter(self) -> Workload {
+        self.workload()
+    }
+}
+
+/// Hot/cold accesses: `hot_access_fraction` of all accesses go uniformly to
+/// the first `hot_fraction` of `n_blocks`, the rest uniformly to the
+/// remainder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hotspot {
+    /// Number of distinct blocks (at least 1).
+    pub n_blocks: u64,
+    /// Share of blocks that are hot, in (0, 1].
+    pub hot_fraction: f64,
+    /// Share of accesses that go to the hot blocks, in [0, 1].
+    pub hot_access_fraction: f64,
+    /// PRNG seed.
+    pub seed: u64,
+}
+
+impl Hotspot {
+    /// The stream of this distribution.
+    pub fn workload(self) -> Workload {
+        Pattern::Hotspot {
+            n_blocks: self.n_blocks,
+            hot_fraction: self.hot_fraction,
+            hot_access_fraction: self.hot_access_fraction,
+        }
+        .workload(self.seed)
+    }
+}
+
+impl IntoIterator for Hotspot {
+    type Item = u64;
+    type IntoIter = Workload;
+
+    fn into_iter(self) -> Workload {
+        self.workload()
+    }
+}
+
+/// Rejection-inversion Zipf sampler (Hörmann & Derflinger, 1996).
+///
+/// Constant time per draw and no tables, so `n_blocks` can be huge.  The
+/// acceptance rate is above 90 % for every exponent.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ZipfSampler {
+    n: f64,
+    exponent: f64,
+    h_integral_x1: f64,
+    h_integral_n: f64,
+    s: f64,
+}
+
+impl ZipfSampler {
+    pub(crate) fn new(n_blocks: u64, theta: f64) -> Self {
+        let n = n_blocks.max(1) as f64;
+        let exponent = if theta.is_finite() {
+            theta.max(0.0)
+        } else {
+            0.0
+        };
+        let mut z = Self {
+            n,
+            exponent,
+            h_integral_x1: 0.0,
+            h_integral_n: 0.0,
+            s: 0.0,
+        };
+        z.h_integral_x1 = z.h_integral(1.5) - 1.0;
+        z.h_integral_n = z.h_integral(n + 0.5);
+        z.s = 2.0 - z.h_integral_inverse(z.h_integral(2.5) - z.h(2.0));
+        z
+    }
+
+    /// Draw a rank in `1..=n` given a uniform `u01` source.
+    pub(crate) fn sample(&self, mut u01: impl FnMut() -> f64) -> u64 {
+        loop {
+            let u = self.h_integral_n + u01() * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = (x + 0.5).floor().clamp(1.0, self.n);
+            if k - x <= self.s || u >= self.h_integral(k + 0.5) - self.h(k) {
+                return k as u64;
+            }
+        }
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        libm::exp(-self.exponent * libm::log(x))
+    }
+
+    fn h_integral(&self, x: f64) -> f64 {
+        let log_x = libm::log(x);
+        helper2((1.0 - self.exponent) * log_x) * log_x
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = (x * (1.0 - self.exponent)).max(-1.0);
+        libm::exp(helper1(t) * x)
+    }
+}
+
+/// `ln(1 + x) / x`, accurate near zero.
+fn helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        libm::log1p(x) / x
+    } else {
+        1.0 - x * (0.5 - x * (1.0 / 3.0 - 0.25 * x))
+    }
+}
+
+/// `(e^x − 1) / x`, accurate near zero.
+fn helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        libm::expm1(x) / x
+    } else {
+        1.0 + x * 0.5 * (1.0 + x / 3.0 * (1.0 + 0.25 * x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exact Zipf probabilities for ranks `1..=n`.
+    fn zipf_pmf(n: u64, theta: f64) -> Vec<f64> {
+        let w: Vec<f64> = (1..=n).map(|k| (k as f64).powf(-theta)).collect();
+        let total: f64 = w.iter().sum();
+        w.into_iter().map(|x| x / total).collect()
+    }
+
+    #[test]
+    fn test_zipf_matches_distribution() {
+        const N: u64 = 10_000;
+        const SAMPLES: usize = 200_000;
+        for theta in [0.5, 0.99, 1.2] {
+            let pmf = zipf_pmf(N, theta);
+            let mut counts = vec![0u64; N as usize];
+            let z = Zipfian {
+                n_blocks: N,
+                theta,
+                seed: 1,
+            };
+            for lba in z.into_iter().take(SAMPLES) {
+                counts[lba as usize] += 1;
+            }
+
+            // Top-1% share within two points of the exact value.
+            let top = (N / 100) as usize;
+            let expected: f64 = pmf[..top].iter().sum();
+            let observed = counts[..top].iter().sum::<u64>() as f64 / SAMPLES as f64;
+            assert!(
+                (observed - expected).abs() < 0.02,
+                "theta {theta}: top-1% share {observed:.3}, expected {expected:.3}"
+            );
+
+            // Chi-squared over ranks 1..=10 and three tail buckets (13 dof;
+            // the 0.1% critical value is 34.5).
+            let mut buckets: Vec<(usize, usize)> = (0..10).map(|i| (i, i + 1)).collect();
+            buckets.extend([(10, 100), (100, 1000), (1000, N as usize)]);
+            let chi2: f64 = buckets
+                .iter()
+                .map(|&(lo, hi)| {
+                    let e = pmf[lo..hi].iter().sum::<f64>() * SAMPLES as f64;
+                    let o = counts[lo..hi].iter().sum::<u64>() as f64;
+                    (o - e) * (o - e) / e
+                })
+                .sum();
+            assert!(chi2 < 34.5, "theta {theta}: chi² = {chi2:.1}");
+        }
+    }
+
+    #[test]
+    fn test_zipf_bounds_and_uniform_limit() {
+        let z = ZipfSampler::new(1, 0.99);
+        let mut rng = crate::rng::SplitMix64::new(3);
+        assert!((0..100).all(|_| z.sample(|| rng.next_f64()) == 1));
+
+        let mut counts = [0u32; 4];
+        let uniform = Zipfian {
+            n_blocks: 4,
+            theta: 0.0,
+            seed: 9,
+        };
+        for lba in uniform.into_iter().take(40_000) {
+            counts[lba as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|&c| (9_000..11_000).contains(&c)),
+            "{counts:?}"
+        );
+    }
+
+    #[test]
+    fn test_hotspot_shares() {
+        let h = Hotspot {
+            n_blocks: 100_000,
+            hot_fraction: 0.1,
+            hot_access_fraction: 0.9,
+            seed: 5,
+        };
+        let lbas: Vec<u64> = h.into_iter().take(100_000).collect();
+        assert!(lbas.iter().all(|&l| l < 100_000));
+        let hot = lbas.iter().filter(|&&l| l < 10_000).count() as f64 / 1e5;
+        assert!((hot - 0.9).abs() < 0.01, "hot share {hot}");
+        assert_eq!(lbas, h.workload().take(100_000).collect::<Vec<_>>());
+    }
+}
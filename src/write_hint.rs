@@ -0,0 +1,238 @@
+//! Write coalescing: hinting at a forming dirty region.
+//!
+//! Prefetching has nothing to offer a write stream; what helps is telling
+//! the block layer that a contiguous dirty region is forming, so it can
+//! hold and merge the flushes.  [`AetherLinkKernel::push_io`] is the
+//! read/write-aware input: a read runs a decision cycle exactly like
+//! [`push_lba`](AetherLinkKernel::push_lba), a write never does.  With
+//! [`AetherLinkKernel::enable_write_coalescing`], writes feed a run
+//! tracker instead: a write starting where the previous one ended extends
+//! the run, any other write starts a new one.  Once a run holds
+//! [`COALESCE_MIN_RUN`] writes, [`AetherLinkKernel::write_hint`] returns a
+//! [`CoalesceHint`] from the run's first block, covering the blocks written
+//! so far and as many again (at most [`MAX_COALESCE_AHEAD`] more): a run
+//! tends to go on for about as long as it has lasted.
+//!
+//! Writes touch neither the read history nor any read-side state, so
+//! interleaving them through `push_io` leaves every read decision as
+//! `push_lba` on the reads alone would make it.
+
+use crate::prefetcher::PrefetchDecision;
+use crate::trace::{IoEvent, IoOp};
+use crate::AetherLinkKernel;
+
+/// Contiguous writes a run needs before it is hinted.
+pub const COALESCE_MIN_RUN: u32 = 4;
+
+/// Most blocks a hint extends past the blocks already written.
+pub const MAX_COALESCE_AHEAD: u64 = 4096;
+
+/// A contiguous dirty region worth holding flushes for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoalesceHint {
+    /// First block of the run.
+    pub start_lba: u64,
+    /// Blocks the run is expected to cover, including those written.
+    pub expected_len: u64,
+}
+
+/// Write-side counters since the coalescer was enabled or the stats were
+/// reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoalesceStats {
+    /// Writes seen.
+    pub writes: u64,
+    /// Writes that extended a run.
+    pub sequential: u64,
+    /// Runs that reached [`COALESCE_MIN_RUN`] writes.
+    pub runs: u64,
+    /// Writes after which a hint stood.
+    pub hinted: u64,
+}
+
+/// The current write run and the counters.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Coalescer {
+    start: u64,
+    /// Block after the newest write of the run.
+    end: u64,
+    writes: u32,
+    stats: CoalesceStats,
+}
+
+impl Coalescer {
+    fn observe(&mut self, lba: u64, blocks: u32) {
+        let extends = self.writes > 0 && lba == self.end;
+        if !extends {
+            self.start = lba;
+            self.writes = 0;
+        }
+        self.end = lba.saturating_add(u64::from(blocks.max(1)));
+        self.writes = self.writes.saturating_add(1);
+        self.stats.writes += 1;
+        self.stats.sequential += extends as u64;
+        self.stats.runs += (self.writes == COALESCE_MIN_RUN) as u64;
+        self.stats.hinted += (self.writes >= COALESCE_MIN_RUN) as u64;
+    }
+
+    fn hint(&self) -> Option<CoalesceHint> {
+        let written = self.end - self.start;
+        (self.writes >= COALESCE_MIN_RUN).then(|| CoalesceHint {
+            start_lba: self.start,
+            expected_len: written.saturating_add(written.min(MAX_COALESCE_AHEAD)),
+        })
+    }
+}
+
+impl AetherLinkKernel {
+    /// Feed one traced request.  A read runs a cycle on its first block,
+    /// as [`push_lba`](Self::push_lba) does; a write goes to the
+    /// [write coalescer](Self::enable_write_coalescing), if any, and
+    /// returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aether_link::trace::{IoEvent, IoOp};
+    /// use aether_link::AetherLinkKernel;
+    ///
+    /// let mut kernel = AetherLinkKernel::default();
+    /// kernel.enable_write_coalescing();
+    /// for i in 0..8u64 {
+    ///     let write = IoEvent {
+    ///         blocks: 8,
+    ///         op: IoOp::Write,
+    ///         ..IoEvent::read(1_000 + i * 8)
+    ///     };
+    ///     assert!(kernel.push_io(&write).is_none());
+    /// }
+    /// let hint = kernel.write_hint().unwrap();
+    /// assert_eq!((hint.start_lba, hint.expected_len), (1_000, 128));
+    /// assert_eq!(kernel.prefetches, 0);
+    /// ```
+    #[inline]
+    pub fn push_io(&mut self, event: &IoEvent) -> Option<PrefetchDecision> {
+        match event.op {
+            IoOp::Read => self.push_lba(event.lba),
+            IoOp::Write => {
+                if let Some(coalescer) = &mut self.write_runs {
+                    coalescer.observe(event.lba, event.blocks);
+                }
+                None
+            }
+        }
+    }
+
+    /// Track write runs from [`push_io`](Self::push_io), starting afresh.
+    pub fn enable_write_coalescing(&mut self) {
+        self.write_runs = Some(Coalescer::default());
+    }
+
+    /// Stop tracking writes and drop the current run.
+    #[inline]
+    pub fn disable_write_coalescing(&mut self) {
+        self.write_runs = None;
+    }
+
+    /// The dirty region the current write run is forming, once it has
+    /// [`COALESCE_MIN_RUN`] writes.
+    #[inline]
+    pub fn write_hint(&self) -> Option<CoalesceHint> {
+        self.write_runs.as_ref().and_then(Coalescer::hint)
+    }
+
+    /// Write-side counters; all zero while coalescing is off.
+    #[inline]
+    pub fn coalesce_stats(&self) -> CoalesceStats {
+        self.write_runs.map(|c| c.stats).unwrap_or_default()
+    }
+
+    /// Zero the write-side counters, keeping the current run.
+    pub(crate) fn reset_coalesce_stats(&mut self) {
+        if let Some(coalescer) = &mut self.write_runs {
+            coalescer.stats = CoalesceStats::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    fn write(lba: u64, blocks: u32) -> IoEvent {
+        IoEvent {
+            blocks,
+            op: IoOp::Write,
+            ..IoEvent::read(lba)
+        }
+    }
+
+    #[test]
+    fn test_sequential_writes_grow_hints_without_triggers() {
+        let mut kernel = AetherLinkKernel::new_gaming();
+        kernel.enable_write_coalescing();
+        let mut hints = Vec::new();
+        for i in 0..2_000u64 {
+            assert!(kernel.push_io(&write(50_000 + i * 4, 4)).is_none());
+            hints.push(kernel.write_hint());
+        }
+        assert!(hints[..3].iter().all(Option::is_none));
+        let hints: Vec<CoalesceHint> = hints[3..].iter().map(|h| h.unwrap()).collect();
+        assert!(hints.iter().all(|h| h.start_lba == 50_000));
+        assert!(hints
+            .windows(2)
+            .all(|w| w[1].expected_len > w[0].expected_len));
+        assert_eq!(hints[0].expected_len, 32);
+        assert_eq!(
+            hints.last().unwrap().expected_len,
+            8_000 + MAX_COALESCE_AHEAD
+        );
+        assert_eq!((kernel.cycles, kernel.prefetches), (0, 0));
+        assert_eq!(
+            kernel.coalesce_stats(),
+            CoalesceStats {
+                writes: 2_000,
+                sequential: 1_999,
+                runs: 1,
+                hinted: 1_997,
+            }
+        );
+
+        // A jump starts a new run, which must earn its own hint.
+        kernel.push_io(&write(7, 1));
+        assert_eq!(kernel.write_hint(), None);
+        kernel.reset_stats();
+        assert_eq!(kernel.coalesce_stats(), CoalesceStats::default());
+        kernel.disable_write_coalescing();
+        assert_eq!(kernel.coalesce_stats(), CoalesceStats::default());
+    }
+
+    #[test]
+    fn test_random_writes_hint_nothing_and_reads_are_untouched() {
+        let mut rng = SplitMix64::new(12);
+        let mut mixed = AetherLinkKernel::default();
+        let mut reads_only = AetherLinkKernel::default();
+        mixed.enable_write_coalescing();
+        for i in 0..3_000u64 {
+            let lba = rng.below(1 << 30);
+            let d = mixed.push_io(&write(lba, 8));
+            assert!(d.is_none() && mixed.write_hint().is_none());
+            let read = IoEvent::read(1 << 32 | i);
+            assert_eq!(mixed.push_io(&read), reads_only.push_lba(read.lba));
+        }
+        assert_eq!(mixed.prefetches, reads_only.prefetches);
+        assert_eq!(mixed.epsilon, reads_only.epsilon);
+        let s = mixed.coalesce_stats();
+        assert_eq!((s.writes, s.runs, s.hinted), (3_000, 0, 0));
+
+        // Without the coalescer writes are ignored altogether.
+        let mut off = AetherLinkKernel::default();
+        for i in 0..100u64 {
+            assert!(off.push_io(&write(i, 1)).is_none());
+        }
+        assert_eq!((off.write_hint(), off.cycles), (None, 0));
+    }
+}
@@ -0,0 +1,322 @@
+//! Integration tests for the `aether-sim` binary.
+//!
+//! Run with: cargo test --features cli --test cli
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn bin() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_aether-sim"))
+}
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join(name)
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aether-sim-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn test_replay_csv_prints_summary() {
+    let out = bin()
+        .args(["replay", "--trace"])
+        .arg(data("sample.csv"))
+        .args(["--format", "csv", "--preset", "hft", "--window", "64"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    let text = stdout(&out);
+    for label in ["Cycles", "Triggers", "Ratio", "399"] {
+        assert!(text.contains(label), "missing {label:?} in:\n{text}");
+    }
+    assert!(!text.contains("Hit rate"), "no cache was requested");
+}
+
+#[test]
+fn test_replay_synthetic_zipf() {
+    let run = || {
+        bin()
+            .args([
+                "replay",
+                "--synthetic",
+                "zipf:10000:0.99",
+                "--events",
+                "5000",
+            ])
+            .args(["--synthetic-seed", "3", "--simulate-cache", "4MiB"])
+            .output()
+            .unwrap()
+    };
+    let out = run();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    let text = stdout(&out);
+    assert!(text.contains("5000") && text.contains("Hit rate"), "{text}");
+    assert_eq!(text, stdout(&run()), "synthetic traces are seeded");
+}
+
+#[test]
+fn test_replay_blktrace_with_cache_and_exports() {
+    let report = scratch("report.json");
+    let decisions = scratch("decisions.jsonl");
+    let out = bin()
+        .args(["replay", "--trace"])
+        .arg(data("sample.blktrace"))
+        .args(["--preset", "gaming", "--block-size", "4096"])
+        .args(["--simulate-cache", "256MiB", "--out"])
+        .arg(&report)
+        .arg("--export-decisions")
+        .arg(&decisions)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    assert!(stdout(&out).contains("Hit rate"));
+
+    let json = std::fs::read_to_string(&report).unwrap();
+    assert!(json.contains("\"format\": \"blkparse\""), "{json}");
+    assert!(json.contains("\"hit_rate\""), "{json}");
+    assert!(json.contains("\"cache_blocks\": 65536"), "{json}");
+
+    // 200 queue events → 200 decisions, one JSON object per line.
+    let lines: Vec<String> = std::fs::read_to_string(&decisions)
+        .unwrap()
+        .lines()
+        .map(str::to_owned)
+        .collect();
+    assert_eq!(lines.len(), 200);
+    assert!(lines
+        .iter()
+        .all(|l| l.starts_with('{') && l.contains("\"prefetch\"")));
+}
+
+#[test]
+fn test_friendly_argument_errors() {
+    let cases: &[(&[&str], &str)] = &[
+        (&["replay"], "missing required option '--trace"),
+        (
+            &["replay", "--trace", "x.csv", "--bogus"],
+            "unknown option '--bogus'",
+        ),
+        (
+            &["replay", "--trace", "x.csv", "--preset", "turbo"],
+            "unknown preset 'turbo'",
+        ),
+        (
+            &["replay", "--trace", "x.csv", "--window"],
+            "requires a value",
+        ),
+        (
+            &["replay", "--trace", "x.csv", "--simulate-cache", "lots"],
+            "not a size",
+        ),
+        (
+            &["replay", "--trace", "x.csv", "--synthetic", "zipf"],
+            "cannot be combined",
+        ),
+        (
+            &["replay", "--synthetic", "zipf:lots"],
+            "invalid pattern argument 'lots'",
+        ),
+        (&["frobnicate"], "unknown command 'frobnicate'"),
+    ];
+    for (args, expected) in cases {
+        let out = bin().args(*args).output().unwrap();
+        assert_eq!(out.status.code(), Some(2), "args {args:?}");
+        let err = stderr(&out);
+        assert!(err.contains(expected), "args {args:?}: {err}");
+        assert!(!err.contains("panicked"), "args {args:?}: {err}");
+    }
+}
+
+#[test]
+fn test_missing_trace_is_runtime_error() {
+    let out = bin()
+        .args(["replay", "--trace", "/nonexistent/trace.csv"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).contains("cannot open"));
+}
+
+#[test]
+fn test_help() {
+    let out = bin().args(["replay", "--help"]).output().unwrap();
+    assert!(out.status.success());
+    assert!(stdout(&out).contains("--simulate-cache"));
+}
+
+#[test]
+fn test_tune_writes_loadable_config() {
+    for (name, strategy) in [("tuned.toml", "random"), ("tuned.json", "grid")] {
+        let out_path = scratch(name);
+        let out = bin()
+            .args(["tune", "--trace"])
+            .arg(data("sample.blktrace"))
+            .args(["--objective", "hit-rate", "--budget", "6", "--seed", "7"])
+            .args(["--strategy", strategy, "--simulate-cache", "1MiB", "--out"])
+            .arg(&out_path)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "stderr: {}", stderr(&out));
+        let text = stdout(&out);
+        assert!(text.contains("Rank"), "{text}");
+        // Budget 6 → a full top-5 leaderboard.
+        assert!(text.contains("│    5 │"), "{text}");
+        assert!(!text.contains("│    6 │"), "{text}");
+
+        let config = aether_link::KernelConfig::load(&out_path).unwrap();
+        let kernel = config.build().unwrap();
+        assert!((kernel.epsilon - config.epsilon).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_tune_is_reproducible_with_seed() {
+    let run = |seed: &str, name: &str| {
+        let out_path = scratch(name);
+        let out = bin()
+            .args(["tune", "--trace"])
+            .arg(data("sample.csv"))
+            .args(["--budget", "4", "--seed", seed, "--out"])
+            .arg(&out_path)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "stderr: {}", stderr(&out));
+        std::fs::read_to_string(out_path).unwrap()
+    };
+    assert_eq!(run("11", "seed-a.toml"), run("11", "seed-b.toml"));
+}
+
+#[test]
+fn test_tune_reports_holdout() {
+    let out = bin()
+        .args(["tune", "--synthetic", "sequential", "--events", "2000"])
+        .args(["--budget", "4", "--holdout", "0.25", "--out"])
+        .arg(scratch("holdout.toml"))
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    let text = stdout(&out);
+    assert!(text.contains("on 1500 events"), "{text}");
+    assert!(text.contains("Held-out 500 events"), "{text}");
+
+    let out = bin()
+        .args(["tune", "--trace", "x.csv", "--holdout", "1.5"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("'--holdout'"));
+}
+
+#[test]
+fn test_tune_rejects_bad_objective() {
+    let out = bin()
+        .args(["tune", "--trace", "x.csv", "--objective", "speed"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("unknown objective 'speed'"));
+}
+
+#[test]
+fn test_tune_saves_profile_and_replay_uses_it() {
+    let store = scratch("profiles.toml");
+    let _ = std::fs::remove_file(&store);
+    for name in ["oltp", "backup"] {
+        let out = bin()
+            .args(["tune", "--trace"])
+            .arg(data("sample.csv"))
+            .args(["--budget", "3", "--save-profile", name, "--profiles"])
+            .arg(&store)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "stderr: {}", stderr(&out));
+    }
+    let loaded = aether_link::ProfileStore::load(&store).unwrap();
+    assert_eq!(loaded.names().collect::<Vec<_>>(), ["backup", "oltp"]);
+
+    let out = bin()
+        .args(["replay", "--trace"])
+        .arg(data("sample.csv"))
+        .args(["--profile", "oltp", "--profiles"])
+        .arg(&store)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    assert!(stdout(&out).contains("Profile"), "{}", stdout(&out));
+
+    let out = bin()
+        .args(["replay", "--trace"])
+        .arg(data("sample.csv"))
+        .args(["--profile", "olap", "--profiles"])
+        .arg(&store)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(
+        stderr(&out).contains("unknown profile 'olap'"),
+        "{}",
+        stderr(&out)
+    );
+
+    let out = bin()
+        .args(["replay", "--trace", "x.csv", "--profile", "oltp"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_soak_saves_and_resumes() {
+    let checkpoint = scratch("soak.json");
+    let out = bin()
+        .args(["soak", "--preset", "gaming", "--cycles", "20000"])
+        .args(["--check-every", "5000", "--seed", "3", "--save"])
+        .arg(&checkpoint)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    assert!(
+        stdout(&out).starts_with("20000 cycles (20000 this run"),
+        "{}",
+        stdout(&out)
+    );
+
+    let out = bin()
+        .args(["soak", "--cycles", "30000", "--check-every", "5000"])
+        .args(["--seed", "3", "--resume"])
+        .arg(&checkpoint)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "stderr: {}", stderr(&out));
+    assert!(
+        stdout(&out).contains("30000 cycles (10000 this run"),
+        "{}",
+        stdout(&out)
+    );
+
+    let out = bin()
+        .args(["soak", "--preset", "hft", "--resume"])
+        .arg(&checkpoint)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(
+        stderr(&out).contains("cannot be combined"),
+        "{}",
+        stderr(&out)
+    );
+}
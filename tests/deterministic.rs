@@ -0,0 +1,57 @@
+//! Cross-platform reproducibility of the `deterministic` feature.
+//!
+//! Replays the golden traces through every preset and hashes the decision
+//! sequence together with the exact bits of the final state.  With the
+//! portable math every target must produce the hash below; a mismatch
+//! means either a platform leaked into the arithmetic or the kernel's
+//! decisions changed (then update `EXPECTED` alongside the goldens).
+//!
+//! Run with: cargo test --features deterministic --test deterministic
+#![cfg(feature = "deterministic")]
+
+use aether_link::trace::{self, TraceFormat};
+use aether_link::Preset;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+const EXPECTED: u64 = 0xb065_7cd5_c1b7_5881;
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` its output is fixed forever.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[test]
+fn test_decision_hash_is_stable() {
+    let mut hash = Fnv(0xcbf2_9ce4_8422_2325);
+    for name in ["sequential", "random", "bursty", "hft_tick"] {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/golden")
+            .join(format!("{name}.csv"));
+        let file = File::open(&path).unwrap();
+        let events = trace::parse(BufReader::new(file), TraceFormat::Csv, 4096).unwrap();
+        let lbas: Vec<u64> = events.iter().map(|e| e.lba).collect();
+        for preset in Preset::ALL {
+            let mut kernel = preset.kernel();
+            for &lba in &lbas {
+                let d = kernel.push_lba(lba).unwrap_or_default();
+                hash.write(&[d.fire as u8]);
+            }
+            for window in lbas.chunks(8) {
+                hash.write(&[kernel.process_io_cycle(window) as u8]);
+            }
+            hash.write(&kernel.epsilon.to_bits().to_le_bytes());
+            hash.write(&kernel.phi.to_bits().to_le_bytes());
+            hash.write(&kernel.cycles.to_le_bytes());
+            hash.write(&kernel.prefetches.to_le_bytes());
+        }
+    }
+    assert_eq!(hash.0, EXPECTED, "decision hash {:#018x}", hash.0);
+}
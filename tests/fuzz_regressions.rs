@@ -0,0 +1,132 @@
+//! Replays the `process_cycle` fuzz corpus without a fuzzer.
+//!
+//! Every file in `fuzz/corpus/process_cycle/` (the seeds) and
+//! `fuzz/regressions/process_cycle/` (minimised crashers, see
+//! `fuzz/README.md`) is run through the same harnesses the `process_cycle`
+//! and `decision_path` targets use.
+//! The seeds are derived from the example workloads and regenerated with:
+//!
+//! ```bash
+//! AETHER_BLESS_FUZZ_CORPUS=1 cargo test --test fuzz_regressions
+//! ```
+
+#[path = "../fuzz/src/lib.rs"]
+#[allow(dead_code)]
+mod harness;
+
+use aether_link::workload::Pattern;
+use aether_link::Preset;
+use std::path::{Path, PathBuf};
+
+const BLESS_VAR: &str = "AETHER_BLESS_FUZZ_CORPUS";
+
+fn fuzz_dir(kind: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz")
+        .join(kind)
+        .join("process_cycle")
+}
+
+/// Seed inputs: every preset on every example pattern, plus the edge
+/// shapes that reach the short-slice and wrap-around paths quickly.
+fn seeds() -> Vec<(String, Vec<u8>)> {
+    let mut out = Vec::new();
+    for preset in Preset::ALL {
+        let config = preset.kernel().config();
+        for pattern in [
+            Pattern::SEQUENTIAL,
+            Pattern::RANDOM,
+            Pattern::BURSTY,
+            Pattern::HFT_TICK,
+        ] {
+            let lbas: Vec<u64> = pattern.workload(7).take(200).collect();
+            out.push((
+                format!(
+                    "{preset}-{}",
+                    pattern.name().to_lowercase().replace(' ', "_")
+                ),
+                harness::encode(&config, 16, &lbas),
+            ));
+        }
+    }
+    let config = Preset::Default.kernel().config();
+    let descending: Vec<u64> = (0..64).rev().map(|i| i << 40).collect();
+    for (name, slice_len, lbas) in [
+        ("empty", 16, vec![]),
+        ("single", 1, vec![42]),
+        ("extremes", 2, vec![0, u64::MAX, 0, u64::MAX]),
+        ("descending", 8, descending),
+    ] {
+        out.push((name.to_owned(), harness::encode(&config, slice_len, &lbas)));
+    }
+    out
+}
+
+fn inputs(dir: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.is_file() && p.extension().map_or(true, |e| e != "md"))
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|p| {
+            let data = std::fs::read(&p).unwrap();
+            (p, data)
+        })
+        .collect()
+}
+
+#[test]
+fn test_seed_corpus() {
+    let dir = fuzz_dir("corpus");
+    if std::env::var_os(BLESS_VAR).is_some() {
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, data) in seeds() {
+            std::fs::write(dir.join(name), data).unwrap();
+        }
+    }
+    let corpus = inputs(&dir);
+    assert_eq!(
+        corpus.len(),
+        seeds().len(),
+        "seed corpus is stale; rerun with {BLESS_VAR}=1"
+    );
+    for (path, data) in corpus {
+        assert!(harness::decode(&data).is_some(), "{}", path.display());
+        harness::process_cycle(&data);
+        harness::decision_path(&data);
+    }
+}
+
+#[test]
+fn test_saved_crashers() {
+    for (path, data) in inputs(&fuzz_dir("regressions")) {
+        let run = std::panic::catch_unwind(|| {
+            harness::process_cycle(&data);
+            harness::decision_path(&data);
+        });
+        assert!(run.is_ok(), "regression {} fails again", path.display());
+    }
+}
+
+#[test]
+fn test_short_and_invalid_inputs() {
+    harness::process_cycle(&[]);
+    harness::process_cycle(&[0xff; 24]);
+    // NaN parameters are rejected by `try_new` and skipped.
+    let nan = aether_link::KernelConfig {
+        epsilon: f32::NAN,
+        ..Preset::Hft.kernel().config()
+    };
+    harness::process_cycle(&harness::encode(&nan, 4, &[1, 2, 3]));
+    // ...and taken as they are by the decision path.
+    for slice_len in [0, 1, 2, 255] {
+        let data = harness::encode(&nan, slice_len, &[0, u64::MAX, 1, u64::MAX - 1]);
+        harness::decision_path(&data);
+    }
+    harness::decision_path(&[0xff; 64]);
+}
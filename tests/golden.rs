@@ -0,0 +1,166 @@
+//! Golden-trace regression tests.
+//!
+//! Replays the traces in `tests/data/golden/` through every preset and
+//! compares the exact decision sequence, trigger count and final ε/φ with
+//! `tests/data/golden/expected.txt`.  A change that alters decisions fails
+//! here until the expectations are regenerated on purpose:
+//!
+//! ```bash
+//! AETHER_BLESS_GOLDEN=1 cargo test --test golden
+//! ```
+//!
+//! and the diff of `expected.txt` is reviewed with the change.
+
+use aether_link::replay::{self, ReplayOptions};
+use aether_link::trace::{self, TraceFormat};
+use aether_link::Preset;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+const TRACES: [&str; 4] = ["sequential", "random", "bursty", "hft_tick"];
+const BLESS_VAR: &str = "AETHER_BLESS_GOLDEN";
+/// ε/φ tolerance; loose enough for libm differences across targets.
+const TOLERANCE: f32 = 1e-4;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/golden")
+}
+
+#[derive(Debug, PartialEq)]
+struct Golden {
+    trace: String,
+    preset: String,
+    triggers: u64,
+    epsilon: f32,
+    phi: f32,
+    /// One `0`/`1` per event.
+    decisions: String,
+}
+
+impl Golden {
+    fn parse(line: &str) -> Self {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(f.len(), 6, "malformed golden line: {line}");
+        Self {
+            trace: f[0].to_owned(),
+            preset: f[1].to_owned(),
+            triggers: f[2].parse().unwrap(),
+            epsilon: f[3].parse().unwrap(),
+            phi: f[4].parse().unwrap(),
+            decisions: f[5].to_owned(),
+        }
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "{} {} {} {:.6} {:.6} {}",
+            self.trace, self.preset, self.triggers, self.epsilon, self.phi, self.decisions
+        )
+    }
+
+    /// Differences from `expected`, empty when within tolerance.
+    fn diff(&self, expected: &Golden) -> Option<String> {
+        let mut msg = String::new();
+        if let Some(i) = self
+            .decisions
+            .bytes()
+            .zip(expected.decisions.bytes())
+            .position(|(a, b)| a != b)
+        {
+            let _ = write!(msg, " first differing decision at event {i};");
+        } else if self.decisions.len() != expected.decisions.len() {
+            let _ = write!(
+                msg,
+                " {} decisions, expected {};",
+                self.decisions.len(),
+                expected.decisions.len()
+            );
+        }
+        if self.triggers != expected.triggers {
+            let _ = write!(msg, " triggers {} != {};", self.triggers, expected.triggers);
+        }
+        if (self.epsilon - expected.epsilon).abs() > TOLERANCE {
+            let _ = write!(msg, " ε {} != {};", self.epsilon, expected.epsilon);
+        }
+        if (self.phi - expected.phi).abs() > TOLERANCE {
+            let _ = write!(msg, " φ {} != {};", self.phi, expected.phi);
+        }
+        (!msg.is_empty()).then(|| format!("{}/{}:{msg}", self.trace, self.preset))
+    }
+}
+
+fn run(trace_name: &str, preset: Preset) -> Golden {
+    let path = golden_dir().join(format!("{trace_name}.csv"));
+    let file = File::open(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let events = trace::parse(BufReader::new(file), TraceFormat::Csv, 4096).unwrap();
+    let mut kernel = preset.kernel();
+    let mut decisions = String::with_capacity(events.len());
+    let summary = replay::replay(
+        &mut kernel,
+        events,
+        &ReplayOptions::default(),
+        None,
+        |d, _| decisions.push(if d.prefetch { '1' } else { '0' }),
+    );
+    Golden {
+        trace: trace_name.to_owned(),
+        preset: preset.name().to_owned(),
+        triggers: summary.triggers,
+        epsilon: kernel.epsilon,
+        phi: kernel.phi,
+        decisions,
+    }
+}
+
+#[test]
+fn test_golden_decisions() {
+    let actual: Vec<Golden> = TRACES
+        .iter()
+        .flat_map(|t| Preset::ALL.map(|p| run(t, p)))
+        .collect();
+    let expected_path = golden_dir().join("expected.txt");
+
+    if std::env::var_os(BLESS_VAR).is_some() {
+        let mut out = String::from("# trace preset triggers final_epsilon final_phi decisions\n");
+        for g in &actual {
+            out.push_str(&g.line());
+            out.push('\n');
+        }
+        std::fs::write(&expected_path, out).unwrap();
+        return;
+    }
+
+    let text = std::fs::read_to_string(&expected_path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {e} (run with {BLESS_VAR}=1 to create it)",
+            expected_path.display()
+        )
+    });
+    let expected: Vec<Golden> = text
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(Golden::parse)
+        .collect();
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "golden set changed; rerun with {BLESS_VAR}=1"
+    );
+
+    let failures: Vec<String> = actual
+        .iter()
+        .zip(&expected)
+        .filter_map(|(a, e)| {
+            assert_eq!((&a.trace, &a.preset), (&e.trace, &e.preset));
+            a.diff(e)
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "decisions drifted from the goldens:\n{}\n\
+         If this change is intended, rerun with {BLESS_VAR}=1 and commit the new expected.txt.",
+        failures.join("\n")
+    );
+}
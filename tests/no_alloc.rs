@@ -0,0 +1,193 @@
+//! The decision path never touches the heap.
+//!
+//! A counting global allocator tallies allocations per thread; after a
+//! warm-up, a million cycles through each entry point must add none.
+//! Everything documented as allocation-free in the crate docs
+//! ("Allocation") belongs here, so a feature that sneaks a `Vec` into the
+//! hot path fails this test.
+//!
+//! Run with: cargo test --test no_alloc
+
+use aether_link::anomaly::AnomalyOptions;
+use aether_link::bandwidth::BandwidthBudget;
+use aether_link::prefetcher::coalesce_in_place;
+use aether_link::trace::{IoEvent, IoOp};
+use aether_link::watchdog::WatchdogOptions;
+use aether_link::{
+    AetherIteratorExt, AetherLinkKernel, PrefetchPlan, PrefetchQueue, ThresholdPolicy,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+const CYCLES: u64 = 1_000_000;
+const WARMUP: u64 = 1_000;
+
+/// Counts allocations made by the current thread; the test harness runs
+/// other tests on other threads.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Allocations `f` makes on this thread.
+fn allocations(f: impl FnOnce()) -> u64 {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// Cheap deterministic LBA source mixing runs and jumps.
+fn lba(i: u64) -> u64 {
+    let h = i.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    if h >> 62 == 0 {
+        h >> 34
+    } else {
+        i * 8
+    }
+}
+
+fn every_option_on() -> AetherLinkKernel {
+    let mut kernel = AetherLinkKernel::new_gaming();
+    kernel.enable_anomaly_detection(AnomalyOptions::default());
+    kernel.enable_watchdog(WatchdogOptions::default());
+    kernel.set_latency_budget(16);
+    kernel.set_queue_depth(8, 32);
+    kernel.set_burst_damping(1.0);
+    kernel.enable_p_fetch_histogram();
+    kernel.enable_heatmap(0..1 << 30, 256);
+    kernel.enable_write_coalescing();
+    kernel.enable_spatial_footprints();
+    kernel.enable_delta_correlation();
+    kernel.set_threshold_policy(ThresholdPolicy::Feedback(Default::default()));
+    kernel
+}
+
+#[test]
+fn test_process_io_cycle_does_not_allocate() {
+    for mut kernel in [AetherLinkKernel::default(), every_option_on()] {
+        let mut window = [0u64; 8];
+        let mut run = |kernel: &mut AetherLinkKernel, range: std::ops::Range<u64>| {
+            for i in range {
+                window[(i % 8) as usize] = lba(i);
+                let out = kernel.process_io_cycle_qos(&window, 0.0);
+                if out.fetch {
+                    kernel.record_prefetch_outcome(i % 3 != 0);
+                }
+            }
+        };
+        run(&mut kernel, 0..WARMUP);
+        let n = allocations(|| run(&mut kernel, WARMUP..WARMUP + CYCLES));
+        assert_eq!(n, 0, "process_io_cycle allocated");
+        assert_eq!(kernel.cycles, WARMUP + CYCLES);
+    }
+}
+
+#[test]
+fn test_push_lba_does_not_allocate() {
+    for mut kernel in [AetherLinkKernel::new_hft(), every_option_on()] {
+        for i in 0..WARMUP {
+            kernel.push_lba(lba(i));
+        }
+        let n = allocations(|| {
+            for i in WARMUP..WARMUP + CYCLES {
+                std::hint::black_box(kernel.push_lba(lba(i)));
+            }
+        });
+        assert_eq!(n, 0, "push_lba allocated");
+
+        let n = allocations(|| {
+            for i in 0..CYCLES {
+                let op = [IoOp::Read, IoOp::Write][(i % 2) as usize];
+                let event = IoEvent {
+                    op,
+                    ..IoEvent::read(lba(i))
+                };
+                std::hint::black_box(kernel.push_io(&event));
+                std::hint::black_box(kernel.write_hint());
+            }
+        });
+        assert_eq!(n, 0, "push_io allocated");
+
+        let mut batch = [0u64; 16];
+        let n = allocations(|| {
+            for i in 0..CYCLES / 16 {
+                batch = std::array::from_fn(|j| lba(i * 16 + j as u64));
+                std::hint::black_box(kernel.process_micro_batch(&batch));
+            }
+        });
+        assert_eq!(n, 0, "process_micro_batch allocated");
+
+        let n = allocations(|| {
+            let fired = (0..CYCLES)
+                .map(lba)
+                .prefetch_decisions(&mut kernel)
+                .filter(|&(_, fire)| fire)
+                .count();
+            let planned = (0..CYCLES)
+                .map(lba)
+                .decisions_detailed(&mut kernel)
+                .filter(|r| r.decision.target.is_some())
+                .count();
+            std::hint::black_box((fired, planned));
+        });
+        assert_eq!(n, 0, "iterator adapters allocated");
+    }
+}
+
+#[test]
+fn test_queue_and_dispatch_do_not_allocate() {
+    let mut kernel = AetherLinkKernel::default();
+    let mut queue = PrefetchQueue::new(256);
+    let mut budget = BandwidthBudget::new(1 << 20).with_rate(64 << 10);
+    let mut batch = [PrefetchPlan::default(); 16];
+    let mut step = |kernel: &mut AetherLinkKernel, i: u64| {
+        let l = lba(i);
+        if kernel.process_io_cycle(&[l, l + 8]) {
+            queue.push(PrefetchPlan::new(l + 8, 16));
+        }
+        let mut n = 0;
+        while let Some(plan) = queue.pop_within(&mut budget) {
+            batch[n] = plan;
+            n += 1;
+            if n == batch.len() {
+                break;
+            }
+        }
+        std::hint::black_box(coalesce_in_place(&mut batch[..n]));
+        budget.tick(1);
+    };
+    for i in 0..WARMUP {
+        step(&mut kernel, i);
+    }
+    let n = allocations(|| {
+        for i in WARMUP..WARMUP + CYCLES {
+            step(&mut kernel, i);
+        }
+    });
+    assert_eq!(n, 0, "queue or dispatch allocated");
+}
+
+#[test]
+fn test_counter_sees_allocations() {
+    assert!(allocations(|| drop(std::hint::black_box(vec![1u8; 64]))) > 0);
+}
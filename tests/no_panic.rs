@@ -0,0 +1,113 @@
+//! The panic-free decision path (see "Panics" in the crate docs).
+//!
+//! Every test here drives the guarded entry points with adversarial
+//! kernels and inputs: NaN and infinite parameters, counters about to
+//! wrap, every optional stage on, LBAs at both ends of the address space
+//! and slices of every short length.  In a plain `cargo test` that catches
+//! panics at run time.  Built with
+//!
+//! ```bash
+//! cargo test --release --features no-panic --test no_panic
+//! ```
+//!
+//! the guard in each entry point also turns any panic the optimiser cannot
+//! rule out into a link error, for every input, not only these.
+
+#[path = "../fuzz/src/lib.rs"]
+#[allow(dead_code)]
+mod harness;
+
+use aether_link::AetherLinkKernel;
+use std::hint::black_box;
+
+const BAD: [f32; 7] = [
+    f32::NAN,
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+    f32::MAX,
+    f32::MIN,
+    f32::MIN_POSITIVE,
+    -0.0,
+];
+
+const EDGES: [u64; 6] = [0, 1, u64::MAX, u64::MAX - 1, 1 << 63, i64::MAX as u64];
+
+/// Kernels no validated configuration produces.
+fn hostile_kernels() -> Vec<AetherLinkKernel> {
+    let mut out = Vec::new();
+    for (i, &bad) in BAD.iter().enumerate() {
+        for stages in [0, 1, 2, 255] {
+            let mut kernel = AetherLinkKernel::new(bad, -bad, [bad, 0.1, bad], bad);
+            if stages > 0 {
+                harness::every_stage_on(&mut kernel, stages);
+            }
+            if i % 2 == 0 {
+                // Counters one step from wrapping.
+                kernel.cycles = u64::MAX - 1;
+                kernel.prefetches = u64::MAX;
+            }
+            out.push(kernel);
+        }
+    }
+    out
+}
+
+/// One call to every guarded entry point.  `black_box` keeps the
+/// optimiser from specialising the link check to these arguments.
+fn drive(kernel: &mut AetherLinkKernel, lbas: &[u64], shift: f32) {
+    let (kernel, lbas, shift) = (black_box(kernel), black_box(lbas), black_box(shift));
+    black_box(kernel.process_io_cycle(lbas));
+    black_box(kernel.process_io_cycle_qos(lbas, shift));
+    black_box(kernel.process_micro_batch(lbas));
+    let lba = lbas.first().copied().unwrap_or_default();
+    black_box(kernel.push_lba(lba));
+    black_box(kernel.push_lba_qos(lba, shift));
+    black_box(kernel.process_io_cycle_raw(lba as f32));
+    let telemetry = kernel.extract_telemetry(lbas);
+    black_box(kernel.prepare_quantum_state(black_box(telemetry)));
+    black_box(kernel.prepare_quantum_state([shift; 6]));
+}
+
+#[test]
+fn test_hostile_kernels_and_edge_lbas() {
+    let mut streams: Vec<Vec<u64>> = vec![vec![]];
+    for &a in &EDGES {
+        streams.push(vec![a]);
+        for &b in &EDGES {
+            streams.push(vec![a, b]);
+            streams.push(vec![a, b, a, b, a]);
+        }
+    }
+    streams.push((0..64).map(|i| u64::MAX - i).collect());
+    streams.push(
+        (0..64)
+            .map(|i| if i % 2 == 0 { 0 } else { u64::MAX })
+            .collect(),
+    );
+
+    for mut kernel in hostile_kernels() {
+        for lbas in &streams {
+            for &shift in BAD.iter().chain(&[0.0, 1.0]) {
+                drive(&mut kernel, lbas, shift);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_long_runs_past_every_boundary() {
+    // Long enough for the rings, windows, horizons and watchdog to wrap
+    // several times on each kernel.
+    for mut kernel in hostile_kernels().into_iter().step_by(3) {
+        kernel.enable_checkpoints(1);
+        let mut lba = u64::MAX - 300;
+        for i in 0..600u64 {
+            lba = lba.wrapping_add(i % 7);
+            kernel.push_lba(lba);
+            kernel.push_lba_qos(lba.wrapping_mul(i), f32::NAN);
+            if i % 50 == 0 {
+                kernel.process_micro_batch(&[lba, 0, u64::MAX, lba]);
+            }
+        }
+    }
+}
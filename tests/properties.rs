@@ -0,0 +1,128 @@
+//! Property-based invariants of the kernel.
+//!
+//! For any finite LBA stream and any parameters inside the documented
+//! ranges (see `aether_link::config`), every cycle must leave the kernel
+//! with ε in its clamp, φ in `[0, 2π)`, finite public state,
+//! `prefetches <= cycles` and a ratio in `[0, 1]`.
+//!
+//! Run with: cargo test --test properties
+
+use aether_link::config::{BIAS_RANGE, EPSILON_RANGE, LAMBDA_RANGE, PHI_RANGE};
+use aether_link::{wrap_angle, AetherLinkKernel};
+use proptest::prelude::*;
+use std::f32::consts::PI;
+
+/// Cycles run per generated case.
+const CYCLES: usize = 300;
+
+fn params() -> impl Strategy<Value = (f32, f32, [f32; 3], f32)> {
+    (
+        EPSILON_RANGE,
+        PHI_RANGE,
+        [LAMBDA_RANGE, LAMBDA_RANGE, LAMBDA_RANGE],
+        BIAS_RANGE,
+    )
+}
+
+/// Arbitrary streams plus the shapes most likely to break the arithmetic.
+fn lbas() -> impl Strategy<Value = Vec<u64>> {
+    prop_oneof![
+        prop::collection::vec(any::<u64>(), 0..64),
+        Just(Vec::new()),
+        any::<u64>().prop_map(|lba| vec![lba]),
+        (any::<u64>(), 1..64usize).prop_map(|(lba, n)| vec![lba; n]),
+        prop::collection::vec(prop_oneof![Just(0), Just(u64::MAX)], 1..64),
+        prop::collection::vec(any::<u64>(), 1..64).prop_map(|mut v| {
+            v.sort_unstable_by(|a, b| b.cmp(a));
+            v
+        }),
+        (0..1_000_000u64, 1..16u64, 2..64u64)
+            .prop_map(|(start, stride, n)| (0..n).map(|i| start + i * stride).collect()),
+    ]
+}
+
+fn check(kernel: &AetherLinkKernel) -> Result<(), TestCaseError> {
+    prop_assert!(
+        (0.1..=0.9).contains(&kernel.epsilon),
+        "epsilon {}",
+        kernel.epsilon
+    );
+    prop_assert!((0.0..2.0 * PI).contains(&kernel.phi), "phi {}", kernel.phi);
+    prop_assert!(kernel.lambda.iter().all(|l| l.is_finite()));
+    prop_assert!(kernel.bias.is_finite());
+    prop_assert!(kernel.prefetches <= kernel.cycles);
+    let ratio = kernel.prefetch_ratio();
+    prop_assert!((0.0..=1.0).contains(&ratio), "ratio {ratio}");
+    Ok(())
+}
+
+proptest! {
+    // Integration tests have no `lib.rs` next to them to anchor the
+    // regression file; a failing seed is printed instead.
+    #![proptest_config(ProptestConfig {
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_slice_cycles_keep_invariants(
+        (epsilon, phi, lambda, bias) in params(),
+        stream in lbas(),
+    ) {
+        let mut kernel = AetherLinkKernel::try_new(epsilon, phi, lambda, bias).unwrap();
+        for i in 0..CYCLES {
+            // Slide over the stream so each cycle sees a different window,
+            // including empty and single-element ones.
+            let start = if stream.is_empty() { 0 } else { i % stream.len() };
+            let end = (start + 1 + i % 8).min(stream.len());
+            kernel.process_io_cycle(&stream[start..end]);
+            // Short windows run no cycle, so ε is only clamped from the
+            // first real one on.
+            if kernel.cycles > 0 {
+                check(&kernel)?;
+            }
+        }
+        // Windows of fewer than two LBAs run no cycle.
+        prop_assert_eq!(kernel.cycles + kernel.stats().short_windows, CYCLES as u64);
+    }
+
+    #[test]
+    fn test_streamed_cycles_keep_invariants(
+        (epsilon, phi, lambda, bias) in params(),
+        stream in lbas().prop_filter("push needs an access", |v| !v.is_empty()),
+        window in 2..=aether_link::stream::MAX_WINDOW,
+    ) {
+        let mut kernel = AetherLinkKernel::try_new(epsilon, phi, lambda, bias).unwrap();
+        kernel.set_window(window);
+        for &lba in stream.iter().cycle().take(CYCLES) {
+            if kernel.push_lba(lba).is_some() {
+                check(&kernel)?;
+            }
+        }
+        prop_assert_eq!(kernel.cycles, CYCLES as u64 - 1);
+    }
+
+    /// 256 cases × 10 000 chained updates: the modulo-free wrap stays in
+    /// `[0, 2π)` and agrees with `rem_euclid` up to the seam at 2π.
+    #[test]
+    fn test_wrap_angle_matches_rem_euclid(
+        phi in 0.0f32..2.0 * PI,
+        lambda in LAMBDA_RANGE,
+        seed in any::<u64>(),
+    ) {
+        let (mut phi, mut state) = (phi, seed | 1);
+        for _ in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // E₂ uniform in [-1, 1].
+            let e2 = (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0;
+            let step = lambda * e2;
+            let want = (phi + step).rem_euclid(2.0 * PI);
+            phi = wrap_angle(phi, step);
+            prop_assert!((0.0..2.0 * PI).contains(&phi), "phi {}", phi);
+            let d = (phi - want).abs();
+            prop_assert!(d.min(2.0 * PI - d) < 1e-5, "{} vs {}", phi, want);
+        }
+    }
+}